@@ -0,0 +1,84 @@
+//! 代码预分词预设基准
+//!
+//! 在合成的源代码语料上比较GPT-4模式与代码预设的预分词耗时，以及各自训练后的编码耗时。
+//! 每个预设训练后的平均每行标记数输出到标准错误，用于比较压缩率。
+//! 运行：`cargo bench --bench code_presets`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+use zero_tokenizer::base::tokenizer_base::{compile_pattern, split_with};
+use zero_tokenizer::bbpe::BBPETokenizer;
+use zero_tokenizer::prelude::*;
+
+const PRESETS: [&str; 3] = ["gpt4", "code", "code_subwords"];
+
+/// 构造缩进层级和命名风格混合的Python与Rust代码
+fn code_corpus() -> Vec<String> {
+    (0..400)
+        .map(|i| {
+            format!(
+                "class HttpHandler{i}(BaseHandler):\n    def get_response_code(self, request_id):\n        \
+                 if self.cache_enabled and request_id in self.cache:\n            return self.cache[request_id]\n        \
+                 return {i}\n\nfn parse_header_{i}(input: &str) -> Result<HeaderMap, ParseError> {{\n    \
+                 let mut headerMap = HeaderMap::new();\n    for line in input.lines() {{\n        \
+                 headerMap.insert(line.trim(), {i});\n    }}\n    Ok(headerMap)\n}}\n"
+            )
+        })
+        .collect()
+}
+
+fn bench_split(c: &mut Criterion) {
+    let corpus = code_corpus();
+    let mut group = c.benchmark_group("code_presets_split");
+    for preset in PRESETS {
+        let pattern = compile_pattern(preset).unwrap();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(preset),
+            &pattern,
+            |b, pattern| {
+                b.iter(|| {
+                    for text in &corpus {
+                        black_box(split_with(pattern, black_box(text)).unwrap());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let corpus = code_corpus();
+    let lines: usize = corpus.iter().map(|text| text.lines().count()).sum();
+    let mut group = c.benchmark_group("code_presets_encode");
+    group.sample_size(10);
+    for preset in PRESETS {
+        let mut tokenizer = BBPETokenizer::with_pattern_internal(preset.to_string()).unwrap();
+        tokenizer.train(corpus.clone(), 1024).unwrap();
+        let tokens: usize = corpus
+            .iter()
+            .map(|text| tokenizer.encode(text).unwrap().len())
+            .sum();
+        eprintln!(
+            "{}: 平均每行 {:.2} 个标记",
+            preset,
+            tokens as f64 / lines as f64
+        );
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(preset),
+            &tokenizer,
+            |b, tokenizer| {
+                b.iter(|| {
+                    for text in &corpus {
+                        black_box(tokenizer.encode(black_box(text)).unwrap());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_split, bench_encode);
+criterion_main!(benches);
@@ -0,0 +1,123 @@
+//! 编码热路径的内存分配基准
+//!
+//! 用计数分配器统计四种分词器编码每条文本的平均分配次数（输出到标准错误），再测量编码耗时。
+//! 统计前先编码一遍语料，使线程内复用的缓冲区已经分配好。
+//! 运行：`cargo bench --bench encode_allocations`，只检查分配次数时加 `-- --test`
+//!
+//! 平均每条文本的分配次数，超过 [`ALLOCATION_CEILINGS`] 时基准失败：
+//!
+//! | 分词器    | 复用缓冲区之前 | 复用缓冲区之后 | 当前  |
+//! |-----------|----------------|----------------|-------|
+//! | bpe       | 132.6          | 100.0          | 100.0 |
+//! | bbpe      | 291.2          | 100.0          | 100.0 |
+//! | unigram   | 364.0          | 337.0          | 362.0 |
+//! | wordpiece | 412.0          | 369.2          | 208.2 |
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use zero_tokenizer::prelude::*;
+
+/// 统计分配次数的全局分配器，重新分配也计为一次
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// 各分词器平均每条文本分配次数的上限，在当前测量值之上留出约一成余量
+const ALLOCATION_CEILINGS: [(&str, f64); 4] = [
+    ("bpe", 110.0),
+    ("bbpe", 110.0),
+    ("unigram", 400.0),
+    ("wordpiece", 230.0),
+];
+
+/// 中英文混合的短句，包含词汇表之外的字符
+fn corpus() -> Vec<String> {
+    (0..500)
+        .map(|i| {
+            format!(
+                "The tokenizer processed request {i} in {} ms, 处理了第{i}个请求。 \
+                 Internationalization and localization matter ☃ {i}!",
+                i % 97
+            )
+        })
+        .collect()
+}
+
+/// 编码整个语料时平均每条文本的分配次数
+fn allocations_per_text(tokenizer: &dyn Tokenizer<TokenId = u32>, corpus: &[String]) -> f64 {
+    for text in corpus {
+        black_box(tokenizer.encode(text).unwrap());
+    }
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for text in corpus {
+        black_box(tokenizer.encode(black_box(text)).unwrap());
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    allocations as f64 / corpus.len() as f64
+}
+
+fn trained(name: &str, corpus: &[String]) -> Box<dyn Tokenizer<TokenId = u32>> {
+    let mut tokenizer: Box<dyn Tokenizer<TokenId = u32>> = match name {
+        "bpe" => Box::new(bpe().unwrap()),
+        "bbpe" => Box::new(bbpe().unwrap()),
+        "unigram" => Box::new(unigram().unwrap()),
+        _ => Box::new(wordpiece().unwrap()),
+    };
+    tokenizer.train(corpus[..100].to_vec(), 600).unwrap();
+    tokenizer
+}
+
+fn bench_encode_allocations(c: &mut Criterion) {
+    let corpus = corpus();
+    let mut group = c.benchmark_group("encode_allocations");
+    group.sample_size(10);
+    for (name, ceiling) in ALLOCATION_CEILINGS {
+        let tokenizer = trained(name, &corpus);
+        let allocations = allocations_per_text(tokenizer.as_ref(), &corpus);
+        eprintln!("{}: 平均每条文本分配 {:.1} 次", name, allocations);
+        assert!(
+            allocations <= ceiling,
+            "{} 平均每条文本分配 {:.1} 次，超过上限 {}",
+            name,
+            allocations,
+            ceiling
+        );
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &tokenizer,
+            |b, tokenizer| {
+                b.iter(|| {
+                    for text in &corpus {
+                        black_box(tokenizer.encode(black_box(text)).unwrap());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode_allocations);
+criterion_main!(benches);
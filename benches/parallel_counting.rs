@@ -0,0 +1,47 @@
+//! 并行词对计数的任务划分粒度基准
+//!
+//! 语料由大量短片段和少量超长片段组成，比较rayon自适应划分与固定粒度划分的耗时。
+//! 运行：`cargo bench --bench parallel_counting`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+use zero_tokenizer::base::tokenizer_base::{count_pairs_parallel_with, ParallelChunking};
+use zero_tokenizer::base::word::Word;
+
+/// 构造片段长度高度偏斜的语料：超长片段集中在末尾
+fn skewed_words() -> (Vec<Word<u32>>, Vec<i32>) {
+    let mut words: Vec<Word<u32>> = (0..200_000u32)
+        .map(|i| Word::new(vec![i % 251, (i / 3) % 251, (i / 7) % 251]))
+        .collect();
+    words.extend((0..64u32).map(|i| Word::new((0..20_000).map(|j| (i + j * 31) % 4096).collect())));
+    let counts = vec![1; words.len()];
+    (words, counts)
+}
+
+fn bench_parallel_counting(c: &mut Criterion) {
+    let (words, counts) = skewed_words();
+    let configs = [
+        ("auto", ParallelChunking::default()),
+        ("1024x2", ParallelChunking::new(1024, 2).unwrap()),
+        ("1024x8", ParallelChunking::new(1024, 8).unwrap()),
+        ("8192x8", ParallelChunking::new(8192, 8).unwrap()),
+    ];
+
+    let mut group = c.benchmark_group("count_pairs_parallel");
+    group.sample_size(10);
+    for (name, chunking) in configs {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &chunking,
+            |b, &chunking| {
+                b.iter(|| {
+                    count_pairs_parallel_with(black_box(&words), black_box(&counts), chunking)
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parallel_counting);
+criterion_main!(benches);
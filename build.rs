@@ -0,0 +1,29 @@
+//! 构建脚本
+//!
+//! 启用 `ffi` 特性时使用 cbindgen 在 `OUT_DIR` 中生成 C 头文件 `zero_tokenizer.h`。
+//! 构建不会改写源码树，仓库中的 `include/zero_tokenizer.h` 由 `ffi_test` 校验，
+//! 设置 `ZERO_TOKENIZER_BLESS_HEADER=1` 运行该测试时才会更新。
+
+fn main() {
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR 未设置");
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR 未设置");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+        .expect("无法读取 cbindgen.toml");
+
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    // 只解析FFI模块，其余代码对C接口不可见
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{}/src/ffi.rs", crate_dir))
+        .generate()
+        .expect("生成C头文件失败")
+        .write_to_file(format!("{}/zero_tokenizer.h", out_dir));
+}
@@ -0,0 +1,102 @@
+#ifndef ZERO_TOKENIZER_H
+#define ZERO_TOKENIZER_H
+
+/* 此文件由 cbindgen 自动生成，请勿手动修改 */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// 分词器类型
+//
+// C 接口以 `uint32_t` 传入类型值，越界的值返回错误而不是被解释为枚举
+typedef enum ZeroTokenizerKind {
+  // 字节级BPE
+  ZERO_TOKENIZER_KIND_BBPE = 0,
+  // Unigram
+  ZERO_TOKENIZER_KIND_UNIGRAM = 1,
+  // WordPiece
+  ZERO_TOKENIZER_KIND_WORD_PIECE = 2,
+} ZeroTokenizerKind;
+
+// 不透明的分词器句柄
+typedef struct ZeroTokenizer ZeroTokenizer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// 从文件加载分词器
+//
+// `kind` 取 [`ZeroTokenizerKind`] 中的值，其他值视为错误。
+// 失败时返回空指针。返回的句柄需要用 [`zero_tokenizer_free`] 释放。
+//
+// # Safety
+//
+// `path` 必须是以空字节结尾的有效字符串
+struct ZeroTokenizer *zero_tokenizer_new_from_file(const char *path, uint32_t kind);
+
+// 释放分词器句柄，传入空指针时不做任何操作
+//
+// # Safety
+//
+// `tokenizer` 必须是 [`zero_tokenizer_new_from_file`] 返回且尚未释放的句柄
+void zero_tokenizer_free(struct ZeroTokenizer *tokenizer);
+
+// 编码文本，成功返回0并通过 `out_ids`/`out_len` 输出标记ID数组
+//
+// 输出的数组需要用 [`zero_tokenizer_free_ids`] 释放。失败时返回-1。
+//
+// # Safety
+//
+// `tokenizer` 必须是有效句柄，`text` 必须是以空字节结尾的有效字符串，
+// `out_ids` 和 `out_len` 必须指向可写的内存
+int32_t zero_tokenizer_encode(const struct ZeroTokenizer *tokenizer,
+                              const char *text,
+                              uint32_t **out_ids,
+                              size_t *out_len);
+
+// 释放 [`zero_tokenizer_encode`] 输出的标记ID数组
+//
+// # Safety
+//
+// `ids` 和 `len` 必须是同一次 [`zero_tokenizer_encode`] 调用的输出，且尚未释放
+void zero_tokenizer_free_ids(uint32_t *ids, size_t len);
+
+// 解码标记ID数组，返回以空字节结尾的字符串
+//
+// 返回的字符串需要用 [`zero_tokenizer_free_string`] 释放。失败时返回空指针。
+//
+// # Safety
+//
+// `tokenizer` 必须是有效句柄，`ids` 必须指向至少 `len` 个元素（`len` 为0时可以为空指针）
+char *zero_tokenizer_decode(const struct ZeroTokenizer *tokenizer,
+                            const uint32_t *ids,
+                            size_t len);
+
+// 释放 [`zero_tokenizer_decode`] 返回的字符串
+//
+// # Safety
+//
+// `text` 必须是 [`zero_tokenizer_decode`] 返回且尚未释放的指针
+void zero_tokenizer_free_string(char *text);
+
+// 获取词汇表大小，句柄为空时返回0
+//
+// # Safety
+//
+// `tokenizer` 必须是有效句柄或空指针
+size_t zero_tokenizer_vocab_size(const struct ZeroTokenizer *tokenizer);
+
+// 获取当前线程最近一次错误的信息，没有错误时返回空指针
+//
+// 返回的指针在同一线程下一次调用失败前有效，调用方不需要释放
+const char *zero_tokenizer_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ZERO_TOKENIZER_H */
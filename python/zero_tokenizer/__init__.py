@@ -8,10 +8,16 @@ This package provides various tokenization algorithms including:
 - WordPiece
 """
 
-from ._zero_tokenizer import Tokenizer, BBPETokenizer, UnigramTokenizer, WordPieceTokenizer
+from ._zero_tokenizer import (
+    Tokenizer,
+    BBPETokenizer,
+    UnigramTokenizer,
+    WordPieceTokenizer,
+    list_models,
+)
 
 __version__ = "0.1.0"
-__all__ = ["Tokenizer", "BBPETokenizer", "UnigramTokenizer", "WordPieceTokenizer"]
+__all__ = ["Tokenizer", "BBPETokenizer", "UnigramTokenizer", "WordPieceTokenizer", "list_models"]
 
 # 为了向后兼容，创建别名
 BPETokenizer = Tokenizer
\ No newline at end of file
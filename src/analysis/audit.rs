@@ -0,0 +1,126 @@
+//! 词汇表UTF-8安全性审计
+
+use serde::Serialize;
+
+use crate::base::traits::VocabBytes;
+
+/// 无法单独解码为UTF-8的标记类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Utf8Fragment {
+    /// 以不完整的多字节字符结尾，需要等待后续标记
+    Truncated,
+    /// 以延续字节开头，是前一个标记中字符的剩余部分
+    Continuation,
+    /// 以延续字节开头，同时以不完整的多字节字符结尾
+    Both,
+    /// 包含任何位置都不合法的字节（如 `0xFF`）
+    Invalid,
+}
+
+impl Utf8Fragment {
+    /// 判断字节序列能否单独解码，可以时返回 `None`
+    #[must_use]
+    pub fn classify(bytes: &[u8]) -> Option<Self> {
+        if std::str::from_utf8(bytes).is_ok() {
+            return None;
+        }
+
+        // 一个字符最多有3个延续字节
+        let leading = bytes
+            .iter()
+            .take(3)
+            .take_while(|&&b| is_continuation(b))
+            .count();
+        let rest = &bytes[leading..];
+        let truncated = match std::str::from_utf8(rest) {
+            Ok(_) => false,
+            Err(e) if e.error_len().is_none() => true,
+            Err(_) => return Some(Self::Invalid),
+        };
+
+        Some(match (leading > 0, truncated) {
+            (true, true) => Self::Both,
+            (true, false) => Self::Continuation,
+            (false, true) => Self::Truncated,
+            (false, false) => Self::Invalid,
+        })
+    }
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+/// 无法单独解码为UTF-8的标记
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnsafeToken {
+    /// 标记ID
+    pub id: u32,
+    /// 标记的原始字节
+    pub bytes: Vec<u8>,
+    /// 不完整的类型
+    pub kind: Utf8Fragment,
+}
+
+/// 词汇表UTF-8安全性审计报告
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VocabAudit {
+    /// 词汇表大小
+    pub vocab_size: usize,
+    /// 无法单独解码的标记，按ID升序排列
+    pub unsafe_tokens: Vec<UnsafeToken>,
+}
+
+impl VocabAudit {
+    /// 无法单独解码的标记数
+    #[must_use]
+    pub fn unsafe_count(&self) -> usize {
+        self.unsafe_tokens.len()
+    }
+
+    /// 标记能否单独解码为UTF-8
+    ///
+    /// 流式解码时，遇到不安全的标记应先缓存字节，等字符完整后再输出
+    #[must_use]
+    pub fn is_utf8_safe(&self, id: u32) -> bool {
+        self.unsafe_tokens
+            .binary_search_by_key(&id, |token| token.id)
+            .is_err()
+    }
+
+    /// 标记的不完整类型，标记可以单独解码时返回 `None`
+    #[must_use]
+    pub fn fragment(&self, id: u32) -> Option<Utf8Fragment> {
+        self.unsafe_tokens
+            .binary_search_by_key(&id, |token| token.id)
+            .ok()
+            .map(|index| self.unsafe_tokens[index].kind)
+    }
+}
+
+/// 找出词汇表中无法单独解码为UTF-8的标记
+///
+/// 字节级分词器的词汇表中，多字节字符常被拆到不同标记里，这些标记单独解码会失败
+pub fn audit_vocab<T>(tokenizer: &T) -> VocabAudit
+where
+    T: VocabBytes<TokenId = u32> + ?Sized,
+{
+    let entries = tokenizer.vocab_bytes();
+    let vocab_size = entries.len();
+    let unsafe_tokens = entries
+        .into_iter()
+        .filter_map(|(id, bytes)| {
+            Utf8Fragment::classify(&bytes).map(|kind| UnsafeToken {
+                id,
+                bytes: bytes.into_owned(),
+                kind,
+            })
+        })
+        .collect();
+
+    VocabAudit {
+        vocab_size,
+        unsafe_tokens,
+    }
+}
@@ -0,0 +1,135 @@
+//! 两个分词器的对比
+
+use serde::Serialize;
+
+use crate::analysis::render_token;
+use crate::base::traits::Tokenizer;
+use crate::error::TokenizerError;
+
+/// 两个分词器切分结果不同的样本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Divergence {
+    /// 样本序号
+    pub index: usize,
+    /// 原始输入
+    pub input: String,
+    /// 第一个分词器切分出的标记
+    pub left: Vec<String>,
+    /// 第二个分词器切分出的标记
+    pub right: Vec<String>,
+}
+
+/// 两个分词器在同一份语料上的对比报告
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompareReport {
+    /// 样本条数
+    pub samples: usize,
+    /// 第一个分词器的标记总数
+    pub left_tokens: usize,
+    /// 第二个分词器的标记总数
+    pub right_tokens: usize,
+    /// 第一个分词器标记更少的样本数
+    pub left_fewer: usize,
+    /// 第二个分词器标记更少的样本数
+    pub right_fewer: usize,
+    /// 两者切分完全相同的样本数
+    pub identical: usize,
+    /// 切分不同的样本示例
+    pub divergences: Vec<Divergence>,
+}
+
+impl CompareReport {
+    /// 标记数之比：第二个分词器的标记总数除以第一个
+    ///
+    /// 小于1表示第二个分词器压缩率更高
+    #[must_use]
+    pub fn token_ratio(&self) -> f64 {
+        if self.left_tokens == 0 {
+            0.0
+        } else {
+            self.right_tokens as f64 / self.left_tokens as f64
+        }
+    }
+
+    /// 切分完全相同的样本比例，没有样本时为1
+    #[must_use]
+    pub fn agreement_rate(&self) -> f64 {
+        if self.samples == 0 {
+            1.0
+        } else {
+            self.identical as f64 / self.samples as f64
+        }
+    }
+}
+
+/// 在同一份语料上对比两个分词器
+///
+/// 两个分词器的词汇表不同，因此按标记的文本形式判断切分是否相同。
+///
+/// # 参数
+/// - `left`, `right`: 参与对比的两个分词器
+/// - `corpus`: 样本语料，每项为一条文本
+/// - `max_examples`: 最多保留的切分不同的样本数
+///
+/// # Errors
+///
+/// 当任意样本编码失败时返回错误，错误信息包含样本序号
+pub fn compare<L, R, I, S>(
+    left: &L,
+    right: &R,
+    corpus: I,
+    max_examples: usize,
+) -> Result<CompareReport, TokenizerError>
+where
+    L: Tokenizer<TokenId = u32> + ?Sized,
+    R: Tokenizer<TokenId = u32> + ?Sized,
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut report = CompareReport {
+        samples: 0,
+        left_tokens: 0,
+        right_tokens: 0,
+        left_fewer: 0,
+        right_fewer: 0,
+        identical: 0,
+        divergences: Vec::new(),
+    };
+
+    for (index, text) in corpus.into_iter().enumerate() {
+        let text = text.as_ref();
+        let left_ids = left
+            .encode(text)
+            .map_err(|e| e.context(format!("第 {} 条样本编码失败（第一个分词器）", index)))?;
+        let right_ids = right
+            .encode(text)
+            .map_err(|e| e.context(format!("第 {} 条样本编码失败（第二个分词器）", index)))?;
+
+        report.samples += 1;
+        report.left_tokens += left_ids.len();
+        report.right_tokens += right_ids.len();
+        match left_ids.len().cmp(&right_ids.len()) {
+            std::cmp::Ordering::Less => report.left_fewer += 1,
+            std::cmp::Ordering::Greater => report.right_fewer += 1,
+            std::cmp::Ordering::Equal => {}
+        }
+
+        let left_pieces: Vec<String> = left_ids.iter().map(|&id| render_token(left, id)).collect();
+        let right_pieces: Vec<String> = right_ids
+            .iter()
+            .map(|&id| render_token(right, id))
+            .collect();
+        if left_pieces == right_pieces {
+            report.identical += 1;
+        } else if report.divergences.len() < max_examples {
+            report.divergences.push(Divergence {
+                index,
+                input: text.to_string(),
+                left: left_pieces,
+                right: right_pieces,
+            });
+        }
+    }
+
+    Ok(report)
+}
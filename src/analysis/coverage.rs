@@ -0,0 +1,135 @@
+//! 语料字符的词汇表覆盖检查
+//!
+//! 训练前在语料上运行，找出基础词汇表中没有对应标记的字符（如生僻汉字、emoji），
+//! 避免它们在训练和编码时被悄悄拆成字节或变成未知标记。
+
+use ahash::AHashMap;
+use serde::Serialize;
+
+use crate::base::traits::Tokenizer;
+use crate::error::TokenizerError;
+
+/// 单个字符及其在语料中的出现次数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CharCount {
+    /// 字符
+    pub ch: char,
+    /// 出现次数
+    pub count: usize,
+}
+
+/// 语料字符的覆盖报告
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CoverageReport {
+    /// 样本条数
+    pub samples: usize,
+    /// 语料中的字符总数
+    pub total_chars: usize,
+    /// 语料中的不同字符数
+    pub distinct_chars: usize,
+    /// 编码时不需要回退标记的字符出现次数
+    pub covered_chars: usize,
+    /// 没有对应标记、只能拆成字节标记的字符，按出现次数降序
+    pub byte_fallback: Vec<CharCount>,
+    /// 无法表示、编码为未知标记的字符，按出现次数降序
+    pub unknown: Vec<CharCount>,
+}
+
+impl CoverageReport {
+    /// 不需要回退标记的字符占全部字符的比例，语料为空时为1
+    #[must_use]
+    pub fn coverage_rate(&self) -> f64 {
+        if self.total_chars == 0 {
+            1.0
+        } else {
+            self.covered_chars as f64 / self.total_chars as f64
+        }
+    }
+
+    /// 语料中的所有字符都能无损编码（允许拆成字节标记）
+    #[must_use]
+    pub fn is_lossless(&self) -> bool {
+        self.unknown.is_empty()
+    }
+}
+
+#[cfg(feature = "python")]
+impl CoverageReport {
+    /// 转换为Python字典，`byte_fallback` 和 `unknown` 为 [(字符, 次数)]
+    pub(crate) fn to_py_dict<'py>(
+        &self,
+        py: pyo3::Python<'py>,
+    ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyDict>> {
+        use pyo3::types::{PyDict, PyDictMethods};
+
+        let pairs = |chars: &[CharCount]| -> Vec<(char, usize)> {
+            chars.iter().map(|c| (c.ch, c.count)).collect()
+        };
+        let dict = PyDict::new(py);
+        dict.set_item("samples", self.samples)?;
+        dict.set_item("total_chars", self.total_chars)?;
+        dict.set_item("distinct_chars", self.distinct_chars)?;
+        dict.set_item("covered_chars", self.covered_chars)?;
+        dict.set_item("coverage_rate", self.coverage_rate())?;
+        dict.set_item("byte_fallback", pairs(&self.byte_fallback))?;
+        dict.set_item("unknown", pairs(&self.unknown))?;
+        Ok(dict)
+    }
+}
+
+/// 检查语料中的字符能否由分词器的基础词汇表表示
+///
+/// 每个不同的字符单独编码一次，与相邻字符之间的合并无关：不含回退标记的字符视为已覆盖，
+/// 含回退标记但能解码还原的字符归入 `byte_fallback`，无法还原的归入 `unknown`
+///
+/// # Errors
+///
+/// 当任意字符编码或解码失败时返回错误
+pub fn coverage<T, I, S>(tokenizer: &T, corpus: I) -> Result<CoverageReport, TokenizerError>
+where
+    T: Tokenizer + ?Sized,
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut samples = 0;
+    let mut counts: AHashMap<char, usize> = AHashMap::new();
+    for text in corpus {
+        samples += 1;
+        for ch in text.as_ref().chars() {
+            *counts.entry(ch).or_insert(0) += 1;
+        }
+    }
+
+    let mut report = CoverageReport {
+        samples,
+        total_chars: counts.values().sum(),
+        distinct_chars: counts.len(),
+        ..CoverageReport::default()
+    };
+    let mut buf = [0u8; 4];
+    for (&ch, &count) in &counts {
+        let text = ch.encode_utf8(&mut buf);
+        let ids = tokenizer
+            .encode(text)
+            .map_err(|e| e.context(format!("字符 {:?} 编码失败", ch)))?;
+        if !ids.iter().any(|id| tokenizer.is_fallback_token(id)) {
+            report.covered_chars += count;
+            continue;
+        }
+
+        let decoded = tokenizer
+            .decode_cow(&ids)
+            .map_err(|e| e.context(format!("字符 {:?} 解码失败", ch)))?;
+        let entry = CharCount { ch, count };
+        if decoded == *text {
+            report.byte_fallback.push(entry);
+        } else {
+            report.unknown.push(entry);
+        }
+    }
+
+    for chars in [&mut report.byte_fallback, &mut report.unknown] {
+        chars.sort_unstable_by(|a, b| b.count.cmp(&a.count).then(a.ch.cmp(&b.ch)));
+    }
+    Ok(report)
+}
@@ -0,0 +1,116 @@
+//! 多分词器评测
+
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::analysis::{analyze, ratio, verify_lossless};
+use crate::base::traits::Tokenizer;
+use crate::error::TokenizerError;
+
+/// 单个分词器在单份语料上的评测结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalRow {
+    /// 分词器名称
+    pub tokenizer: String,
+    /// 语料名称（通常是语言）
+    pub corpus: String,
+    /// 样本条数
+    pub samples: usize,
+    /// 压缩率：平均每个标记覆盖的字节数
+    pub bytes_per_token: f64,
+    /// 平均每个词切分出的标记数
+    pub fertility: f64,
+    /// 字节回退或未知标记的比例
+    pub fallback_rate: f64,
+    /// 能无损还原的样本比例
+    pub lossless_rate: f64,
+    /// 编码吞吐量（MB/s，单线程）
+    pub throughput_mb_s: f64,
+}
+
+/// 评测结果表
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EvalReport {
+    /// 按分词器、语料顺序排列的结果
+    pub rows: Vec<EvalRow>,
+}
+
+impl EvalReport {
+    /// 渲染为Markdown表格
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut table = String::from(
+            "| 分词器 | 语料 | 样本 | 字节/标记 | 标记/词 | 回退率 | 无损率 | 吞吐 (MB/s) |\n\
+             |--------|------|------|-----------|---------|--------|--------|-------------|\n",
+        );
+        for row in &self.rows {
+            table.push_str(&format!(
+                "| {} | {} | {} | {:.3} | {:.3} | {:.2}% | {:.2}% | {:.2} |\n",
+                row.tokenizer,
+                row.corpus,
+                row.samples,
+                row.bytes_per_token,
+                row.fertility,
+                row.fallback_rate * 100.0,
+                row.lossless_rate * 100.0,
+                row.throughput_mb_s
+            ));
+        }
+        table
+    }
+}
+
+/// 在每份语料上评测每个分词器
+///
+/// # 参数
+/// - `tokenizers`: (名称, 分词器) 列表
+/// - `corpora`: (名称, 样本) 列表，通常每种语言一份
+///
+/// # Errors
+///
+/// 当任意样本编码失败时返回错误，错误信息包含分词器和语料名称
+pub fn evaluate<S>(
+    tokenizers: &[(&str, &dyn Tokenizer<TokenId = u32>)],
+    corpora: &[(&str, &[S])],
+) -> Result<EvalReport, TokenizerError>
+where
+    S: AsRef<str>,
+{
+    let mut rows = Vec::with_capacity(tokenizers.len() * corpora.len());
+
+    for &(name, tokenizer) in tokenizers {
+        for &(corpus_name, samples) in corpora {
+            let context = |e: TokenizerError| {
+                e.context(format!("{} 在语料 {} 上评测失败", name, corpus_name))
+            };
+
+            let analysis = analyze(tokenizer, samples).map_err(context)?;
+            let lossless = verify_lossless(tokenizer, samples).map_err(context)?;
+
+            let started = Instant::now();
+            for text in samples {
+                tokenizer.encode(text.as_ref()).map_err(context)?;
+            }
+            let seconds = started.elapsed().as_secs_f64();
+            let throughput_mb_s = if seconds > 0.0 {
+                analysis.overall.bytes as f64 / seconds / 1_000_000.0
+            } else {
+                0.0
+            };
+
+            rows.push(EvalRow {
+                tokenizer: name.to_string(),
+                corpus: corpus_name.to_string(),
+                samples: analysis.samples,
+                bytes_per_token: ratio(analysis.overall.bytes, analysis.overall.tokens),
+                fertility: analysis.overall.fertility(),
+                fallback_rate: analysis.overall.fallback_rate(),
+                lossless_rate: lossless.lossless_rate(),
+                throughput_mb_s,
+            });
+        }
+    }
+
+    Ok(EvalReport { rows })
+}
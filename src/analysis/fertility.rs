@@ -0,0 +1,153 @@
+//! 语料的切分粒度和未登录率分析
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use unicode_script::{Script, UnicodeScript};
+
+use crate::analysis::ratio;
+use crate::base::traits::Tokenizer;
+use crate::error::TokenizerError;
+
+/// 一组文本上的切分统计
+///
+/// 序列化为JSON时会附带 `fertility`、`tokens_per_byte` 和 `fallback_rate`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SegmentStats {
+    /// 词数（按空白分隔）
+    pub words: usize,
+    /// 字节数
+    pub bytes: usize,
+    /// 字符数
+    pub chars: usize,
+    /// 标记数
+    pub tokens: usize,
+    /// 字节回退或未知标记数
+    pub fallback_tokens: usize,
+}
+
+impl SegmentStats {
+    /// 平均每个词切分出的标记数
+    #[must_use]
+    pub fn fertility(&self) -> f64 {
+        ratio(self.tokens, self.words)
+    }
+
+    /// 平均每个字节对应的标记数
+    #[must_use]
+    pub fn tokens_per_byte(&self) -> f64 {
+        ratio(self.tokens, self.bytes)
+    }
+
+    /// 回退标记占全部标记的比例
+    #[must_use]
+    pub fn fallback_rate(&self) -> f64 {
+        ratio(self.fallback_tokens, self.tokens)
+    }
+
+    fn add(&mut self, text: &str, tokens: usize, fallback_tokens: usize) {
+        self.bytes += text.len();
+        self.chars += text.chars().count();
+        self.tokens += tokens;
+        self.fallback_tokens += fallback_tokens;
+    }
+}
+
+impl Serialize for SegmentStats {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("SegmentStats", 8)?;
+        state.serialize_field("words", &self.words)?;
+        state.serialize_field("bytes", &self.bytes)?;
+        state.serialize_field("chars", &self.chars)?;
+        state.serialize_field("tokens", &self.tokens)?;
+        state.serialize_field("fallback_tokens", &self.fallback_tokens)?;
+        state.serialize_field("fertility", &self.fertility())?;
+        state.serialize_field("tokens_per_byte", &self.tokens_per_byte())?;
+        state.serialize_field("fallback_rate", &self.fallback_rate())?;
+        state.end()
+    }
+}
+
+/// 语料分析结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CorpusAnalysis {
+    /// 样本条数
+    pub samples: usize,
+    /// 整条样本编码的统计
+    pub overall: SegmentStats,
+    /// 按文字体系分组的统计，键为Unicode文字名称（如 `Latin`、`Han`）
+    ///
+    /// 每个词单独编码，并按其中占多数的文字归类；只含数字、标点等通用字符的词归为 `Common`
+    pub scripts: BTreeMap<String, SegmentStats>,
+}
+
+/// 词中占多数的文字体系，数量相同时取先出现的
+fn dominant_script(word: &str) -> Script {
+    let mut counts: Vec<(Script, usize)> = Vec::new();
+    for script in word.chars().map(|c| c.script()) {
+        if matches!(script, Script::Common | Script::Inherited | Script::Unknown) {
+            continue;
+        }
+        match counts.iter_mut().find(|(s, _)| *s == script) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((script, 1)),
+        }
+    }
+    counts
+        .iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map_or(Script::Common, |(script, _)| *script)
+}
+
+/// 分析分词器在语料上的切分粒度（fertility）、压缩率和回退率，并按文字体系分组
+///
+/// # Errors
+///
+/// 当任意样本编码失败时返回错误，错误信息包含样本序号
+pub fn analyze<T, I, S>(tokenizer: &T, corpus: I) -> Result<CorpusAnalysis, TokenizerError>
+where
+    T: Tokenizer + ?Sized,
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut samples = 0;
+    let mut overall = SegmentStats::default();
+    let mut scripts: BTreeMap<String, SegmentStats> = BTreeMap::new();
+
+    let count_fallback = |ids: &[T::TokenId]| {
+        ids.iter()
+            .filter(|id| tokenizer.is_fallback_token(id))
+            .count()
+    };
+
+    for (index, text) in corpus.into_iter().enumerate() {
+        let text = text.as_ref();
+        let ids = tokenizer
+            .encode(text)
+            .map_err(|e| e.context(format!("第 {} 条样本编码失败", index)))?;
+
+        samples += 1;
+        overall.add(text, ids.len(), count_fallback(&ids));
+
+        for word in text.split_whitespace() {
+            overall.words += 1;
+            let ids = tokenizer
+                .encode(word)
+                .map_err(|e| e.context(format!("第 {} 条样本编码失败", index)))?;
+            let stats = scripts
+                .entry(dominant_script(word).full_name().to_string())
+                .or_default();
+            stats.words += 1;
+            stats.add(word, ids.len(), count_fallback(&ids));
+        }
+    }
+
+    Ok(CorpusAnalysis {
+        samples,
+        overall,
+        scripts,
+    })
+}
@@ -0,0 +1,138 @@
+//! 词汇表使用情况统计
+
+use ahash::AHashMap;
+use serde::Serialize;
+
+use crate::analysis::{ratio, render_token};
+use crate::base::traits::Tokenizer;
+use crate::error::TokenizerError;
+
+/// 单个标记及其在样本中的出现次数
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TokenCount {
+    /// 标记ID
+    pub id: u32,
+    /// 标记的文本形式
+    pub token: String,
+    /// 出现次数
+    pub count: u64,
+}
+
+/// 词汇表在样本语料上的统计报告
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InspectReport {
+    /// 词汇表大小
+    pub vocab_size: usize,
+    /// 样本条数
+    pub samples: usize,
+    /// 样本总字节数
+    pub total_bytes: usize,
+    /// 样本总字符数
+    pub total_chars: usize,
+    /// 编码后的标记总数
+    pub total_tokens: usize,
+    /// 样本中出现过的不同标记数
+    pub used_tokens: usize,
+    /// 字节回退或未知标记的数量
+    pub fallback_tokens: usize,
+    /// 出现次数最多的标记
+    pub most_used: Vec<TokenCount>,
+    /// 出现过的标记中次数最少的标记
+    pub least_used: Vec<TokenCount>,
+}
+
+impl InspectReport {
+    /// 样本中从未出现的词汇表条目数
+    #[must_use]
+    pub fn unused_tokens(&self) -> usize {
+        self.vocab_size.saturating_sub(self.used_tokens)
+    }
+
+    /// 回退标记占全部标记的比例
+    #[must_use]
+    pub fn fallback_rate(&self) -> f64 {
+        ratio(self.fallback_tokens, self.total_tokens)
+    }
+
+    /// 压缩率：平均每个标记覆盖的字节数
+    #[must_use]
+    pub fn bytes_per_token(&self) -> f64 {
+        ratio(self.total_bytes, self.total_tokens)
+    }
+
+    /// 平均每个标记覆盖的字符数
+    #[must_use]
+    pub fn chars_per_token(&self) -> f64 {
+        ratio(self.total_chars, self.total_tokens)
+    }
+}
+
+/// 在样本语料上统计词汇表的使用情况
+///
+/// # 参数
+/// - `tokenizer`: 已训练的分词器
+/// - `corpus`: 样本语料，每项为一条文本
+/// - `top`: 最常用和最少用标记列表的长度
+///
+/// # Errors
+///
+/// 当任意样本编码失败时返回错误，错误信息包含样本序号
+pub fn inspect<T, I, S>(
+    tokenizer: &T,
+    corpus: I,
+    top: usize,
+) -> Result<InspectReport, TokenizerError>
+where
+    T: Tokenizer<TokenId = u32> + ?Sized,
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut counts: AHashMap<u32, u64> = AHashMap::new();
+    let mut samples = 0;
+    let mut total_bytes = 0;
+    let mut total_chars = 0;
+    let mut total_tokens = 0;
+    let mut fallback_tokens = 0;
+
+    for (index, text) in corpus.into_iter().enumerate() {
+        let text = text.as_ref();
+        let ids = tokenizer
+            .encode(text)
+            .map_err(|e| e.context(format!("第 {} 条样本编码失败", index)))?;
+
+        samples += 1;
+        total_bytes += text.len();
+        total_chars += text.chars().count();
+        total_tokens += ids.len();
+        for id in ids {
+            if tokenizer.is_fallback_token(&id) {
+                fallback_tokens += 1;
+            }
+            *counts.entry(id).or_default() += 1;
+        }
+    }
+
+    // 次数相同时按ID排序，保证输出稳定
+    let mut sorted: Vec<(u32, u64)> = counts.into_iter().collect();
+    sorted.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let to_entry = |&(id, count): &(u32, u64)| TokenCount {
+        id,
+        token: render_token(tokenizer, id),
+        count,
+    };
+    let most_used = sorted.iter().take(top).map(to_entry).collect();
+    let least_used = sorted.iter().rev().take(top).map(to_entry).collect();
+
+    Ok(InspectReport {
+        vocab_size: tokenizer.vocab_size(),
+        samples,
+        total_bytes,
+        total_chars,
+        total_tokens,
+        used_tokens: sorted.len(),
+        fallback_tokens,
+        most_used,
+        least_used,
+    })
+}
@@ -0,0 +1,161 @@
+//! 编码长度分布
+//!
+//! 选择最大序列长度和拼接策略前，统计语料中每条样本编码后的标记数分布。
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::analysis::ratio;
+use crate::base::traits::Tokenizer;
+use crate::error::{input_validation_error, TokenizerError};
+
+/// 长度区间 `[start, end)` 内的样本数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LengthBucket {
+    /// 区间起点（包含）
+    pub start: usize,
+    /// 区间终点（不包含）
+    pub end: usize,
+    /// 编码长度落在区间内的样本数
+    pub count: usize,
+}
+
+/// 编码长度分布
+///
+/// 序列化为JSON时会附带 `mean`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LengthHistogram {
+    /// 区间宽度
+    pub bucket_size: usize,
+    /// 样本条数
+    pub samples: usize,
+    /// 标记总数
+    pub total_tokens: usize,
+    /// 最短的编码长度，没有样本时为0
+    pub min: usize,
+    /// 最长的编码长度，没有样本时为0
+    pub max: usize,
+    /// 中位数
+    pub p50: usize,
+    /// 90分位数
+    pub p90: usize,
+    /// 99分位数
+    pub p99: usize,
+    /// 从0到最长长度的全部区间，依次排列，没有样本的区间计数为0
+    pub buckets: Vec<LengthBucket>,
+}
+
+impl LengthHistogram {
+    /// 平均编码长度
+    #[must_use]
+    pub fn mean(&self) -> f64 {
+        ratio(self.total_tokens, self.samples)
+    }
+
+    /// 按区间统计，编码长度不超过 `max_len` 的样本比例，没有样本时为1
+    ///
+    /// `max_len + 1` 不是区间边界时，所在区间按全部超出计算，结果偏低
+    #[must_use]
+    pub fn fraction_within(&self, max_len: usize) -> f64 {
+        if self.samples == 0 {
+            return 1.0;
+        }
+        let within: usize = self
+            .buckets
+            .iter()
+            .take_while(|bucket| bucket.end <= max_len + 1)
+            .map(|bucket| bucket.count)
+            .sum();
+        ratio(within, self.samples)
+    }
+}
+
+impl Serialize for LengthHistogram {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("LengthHistogram", 10)?;
+        state.serialize_field("bucket_size", &self.bucket_size)?;
+        state.serialize_field("samples", &self.samples)?;
+        state.serialize_field("total_tokens", &self.total_tokens)?;
+        state.serialize_field("min", &self.min)?;
+        state.serialize_field("max", &self.max)?;
+        state.serialize_field("mean", &self.mean())?;
+        state.serialize_field("p50", &self.p50)?;
+        state.serialize_field("p90", &self.p90)?;
+        state.serialize_field("p99", &self.p99)?;
+        state.serialize_field("buckets", &self.buckets)?;
+        state.end()
+    }
+}
+
+/// 最近秩法的分位数，`sorted` 须已升序排列且非空
+fn percentile(sorted: &[usize], p: usize) -> usize {
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// 并行编码语料，统计每条样本编码长度的分布
+///
+/// # 参数
+/// - `corpus`: 样本语料，每项为一条文本
+/// - `bucket_size`: 区间宽度，第 `i` 个区间为 `[i * bucket_size, (i + 1) * bucket_size)`
+///
+/// # Errors
+///
+/// 当 `bucket_size` 为0或任意样本编码失败时返回错误，错误信息包含样本序号
+pub fn length_histogram<T, S>(
+    tokenizer: &T,
+    corpus: &[S],
+    bucket_size: usize,
+) -> Result<LengthHistogram, TokenizerError>
+where
+    T: Tokenizer + Sync + ?Sized,
+    S: AsRef<str> + Sync,
+{
+    if bucket_size == 0 {
+        return Err(input_validation_error("区间宽度必须大于0"));
+    }
+
+    let mut lengths = corpus
+        .par_iter()
+        .enumerate()
+        .map(|(index, text)| {
+            tokenizer
+                .encode(text.as_ref())
+                .map(|ids| ids.len())
+                .map_err(|e| e.context(format!("第 {} 条样本编码失败", index)))
+        })
+        .collect::<Result<Vec<usize>, TokenizerError>>()?;
+    if lengths.is_empty() {
+        return Ok(LengthHistogram {
+            bucket_size,
+            ..LengthHistogram::default()
+        });
+    }
+    lengths.par_sort_unstable();
+
+    let max = lengths[lengths.len() - 1];
+    let mut buckets: Vec<LengthBucket> = (0..=max / bucket_size)
+        .map(|i| LengthBucket {
+            start: i * bucket_size,
+            end: (i + 1) * bucket_size,
+            count: 0,
+        })
+        .collect();
+    for &len in &lengths {
+        buckets[len / bucket_size].count += 1;
+    }
+
+    Ok(LengthHistogram {
+        bucket_size,
+        samples: lengths.len(),
+        total_tokens: lengths.iter().sum(),
+        min: lengths[0],
+        max,
+        p50: percentile(&lengths, 50),
+        p90: percentile(&lengths, 90),
+        p99: percentile(&lengths, 99),
+        buckets,
+    })
+}
@@ -0,0 +1,105 @@
+//! 无损往返校验
+
+use serde::Serialize;
+
+use crate::base::traits::Tokenizer;
+use crate::error::TokenizerError;
+
+/// 编码再解码后与原文不一致的样本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LosslessMismatch {
+    /// 样本序号
+    pub index: usize,
+    /// 原始输入
+    pub input: String,
+    /// 解码结果，解码失败时为 `None`
+    pub decoded: Option<String>,
+    /// 第一个不一致的字节偏移（按原始输入计），解码失败时为0
+    pub first_divergent_byte: usize,
+    /// 解码失败时的错误信息
+    pub error: Option<String>,
+}
+
+/// 无损往返校验报告
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LosslessReport {
+    /// 样本条数
+    pub samples: usize,
+    /// 不一致的样本
+    pub mismatches: Vec<LosslessMismatch>,
+}
+
+impl LosslessReport {
+    /// 全部样本是否都能无损还原
+    #[must_use]
+    pub fn is_lossless(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// 能无损还原的样本比例，没有样本时为1
+    #[must_use]
+    pub fn lossless_rate(&self) -> f64 {
+        if self.samples == 0 {
+            1.0
+        } else {
+            (self.samples - self.mismatches.len()) as f64 / self.samples as f64
+        }
+    }
+}
+
+/// 两个字节序列第一个不同的位置，一方是另一方的前缀时为较短一方的长度
+fn first_divergence(a: &[u8], b: &[u8]) -> usize {
+    a.iter()
+        .zip(b)
+        .position(|(x, y)| x != y)
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+/// 对每条样本编码再解码，收集无法还原原文的样本
+///
+/// 用于在训练模型之前确认分词器不会丢失信息，例如字符级BPE或WordPiece中的未知标记。
+///
+/// # Errors
+///
+/// 当任意样本编码失败时返回错误，错误信息包含样本序号
+pub fn verify_lossless<T, I, S>(tokenizer: &T, corpus: I) -> Result<LosslessReport, TokenizerError>
+where
+    T: Tokenizer + ?Sized,
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut samples = 0;
+    let mut mismatches = Vec::new();
+
+    for (index, text) in corpus.into_iter().enumerate() {
+        let text = text.as_ref();
+        let ids = tokenizer
+            .encode(text)
+            .map_err(|e| e.context(format!("第 {} 条样本编码失败", index)))?;
+        samples += 1;
+
+        let mismatch = match tokenizer.decode_cow(&ids) {
+            Ok(decoded) if decoded == text => continue,
+            Ok(decoded) => LosslessMismatch {
+                index,
+                input: text.to_string(),
+                first_divergent_byte: first_divergence(text.as_bytes(), decoded.as_bytes()),
+                decoded: Some(decoded.into_owned()),
+                error: None,
+            },
+            Err(e) => LosslessMismatch {
+                index,
+                input: text.to_string(),
+                decoded: None,
+                first_divergent_byte: 0,
+                error: Some(e.to_string()),
+            },
+        };
+        mismatches.push(mismatch);
+    }
+
+    Ok(LosslessReport {
+        samples,
+        mismatches,
+    })
+}
@@ -0,0 +1,46 @@
+//! 分词器分析工具
+//!
+//! 在样本语料上评估已训练的分词器，结果可序列化为JSON，供命令行工具和脚本使用。
+
+pub mod audit;
+pub mod compare;
+pub mod coverage;
+pub mod eval;
+pub mod fertility;
+pub mod inspect;
+pub mod lengths;
+pub mod lossless;
+pub mod usage;
+
+pub use audit::{audit_vocab, UnsafeToken, Utf8Fragment, VocabAudit};
+pub use compare::{compare, CompareReport, Divergence};
+pub use coverage::{coverage, CharCount, CoverageReport};
+pub use eval::{evaluate, EvalReport, EvalRow};
+pub use fertility::{analyze, CorpusAnalysis, SegmentStats};
+pub use inspect::{inspect, InspectReport, TokenCount};
+pub use lengths::{length_histogram, LengthBucket, LengthHistogram};
+pub use lossless::{verify_lossless, LosslessMismatch, LosslessReport};
+pub use usage::token_usage;
+
+use crate::base::traits::Tokenizer;
+
+/// 将单个标记渲染为可读字符串
+///
+/// 无法单独解码的标记（如BBPE中不完整的多字节序列）显示为替换字符
+pub(crate) fn render_token<T>(tokenizer: &T, id: u32) -> String
+where
+    T: Tokenizer<TokenId = u32> + ?Sized,
+{
+    tokenizer
+        .decode_cow(&[id])
+        .map_or_else(|_| '\u{FFFD}'.to_string(), |text| text.into_owned())
+}
+
+/// 计算比值，分母为0时返回0
+pub(crate) fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
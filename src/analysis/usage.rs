@@ -0,0 +1,35 @@
+//! 标记使用频次
+
+use ahash::AHashMap;
+
+use crate::base::traits::Tokenizer;
+use crate::error::TokenizerError;
+
+/// 统计编码语料时每个标记出现的次数
+///
+/// 结果按标记ID升序排列，未出现的标记不包含在内。可用于决定词汇表裁剪，
+/// 或为新增标记初始化嵌入向量。
+///
+/// # Errors
+///
+/// 当任意样本编码失败时返回错误，错误信息包含样本序号
+pub fn token_usage<T, I, S>(tokenizer: &T, corpus: I) -> Result<Vec<(u32, u64)>, TokenizerError>
+where
+    T: Tokenizer<TokenId = u32> + ?Sized,
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut counts: AHashMap<u32, u64> = AHashMap::new();
+    for (index, text) in corpus.into_iter().enumerate() {
+        let ids = tokenizer
+            .encode(text.as_ref())
+            .map_err(|e| e.context(format!("第 {} 条样本编码失败", index)))?;
+        for id in ids {
+            *counts.entry(id).or_default() += 1;
+        }
+    }
+
+    let mut usage: Vec<(u32, u64)> = counts.into_iter().collect();
+    usage.sort_unstable_by_key(|&(id, _)| id);
+    Ok(usage)
+}
@@ -0,0 +1,78 @@
+//! 异步接口
+//!
+//! 供异步数据加载管线使用：从异步流读取训练语料，以及在分块之间让出执行器的批量编码。
+//! 计算密集的部分在阻塞线程池或rayon中执行，避免长时间占用异步执行器。
+
+use futures_util::{Stream, StreamExt};
+use rayon::prelude::*;
+
+use crate::base::traits::Tokenizer;
+use crate::error::{training_error, TokenizerError};
+
+/// 从异步流读取全部语料后训练分词器
+///
+/// 训练在tokio的阻塞线程池中执行，因此分词器按值传入，训练完成后返回。
+///
+/// # Errors
+///
+/// 当训练失败或训练任务异常终止时返回错误
+pub async fn train_from_stream<T, S>(
+    mut tokenizer: T,
+    texts: S,
+    vocab_size: u32,
+) -> Result<T, TokenizerError>
+where
+    T: Tokenizer + Send + 'static,
+    S: Stream<Item = String>,
+{
+    let texts: Vec<String> = texts.collect().await;
+
+    tokio::task::spawn_blocking(move || {
+        tokenizer.train(texts, vocab_size)?;
+        Ok(tokenizer)
+    })
+    .await
+    .map_err(|e| training_error(format!("训练任务执行失败: {}", e)))?
+}
+
+/// 分块并行编码一批文本，每块编码完成后让出执行器
+///
+/// `chunk_size` 控制每次占用执行器的时长，为0时按1处理。
+///
+/// # Errors
+///
+/// 当任意文本编码失败时返回错误，错误信息包含文本序号
+pub async fn encode_batch<T, S>(
+    tokenizer: &T,
+    texts: &[S],
+    chunk_size: usize,
+) -> Result<Vec<Vec<T::TokenId>>, TokenizerError>
+where
+    T: Tokenizer + Sync + ?Sized,
+    T::TokenId: Send,
+    S: AsRef<str> + Sync,
+{
+    let chunk_size = chunk_size.max(1);
+    let mut result = Vec::with_capacity(texts.len());
+
+    for (chunk_index, chunk) in texts.chunks(chunk_size).enumerate() {
+        let offset = chunk_index * chunk_size;
+        let encoded: Vec<Vec<T::TokenId>> = chunk
+            .par_iter()
+            .enumerate()
+            .map(|(index, text)| {
+                tokenizer
+                    .encode(text.as_ref())
+                    .map_err(|e| TokenizerError::BatchItem {
+                        index: offset + index,
+                        source: Box::new(e),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        result.extend(encoded);
+
+        tokio::task::yield_now().await;
+    }
+
+    Ok(result)
+}
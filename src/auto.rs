@@ -0,0 +1,267 @@
+//! 运行时选择分词器类型
+//!
+//! [`Tokenizer`] 的标记ID是关联类型，不同类型的分词器无法直接放进同一个容器。本库的四种分词器都使用
+//! `u32` 作为标记ID，[`DynTokenizer`] 固定了这一点，可以以 `Box<dyn DynTokenizer>` 的形式持有任意分词器。
+//! [`AutoTokenizer::from_file`] 根据模型文件的内容识别类型并加载，应用不必事先知道保存的是哪种分词器：
+//!
+//! - 二进制模型：读取头部记录的模型类型；
+//! - HuggingFace `tokenizer.json`：按模型类型和是否使用 `ByteLevel` 预分词器区分BPE和BBPE；
+//! - 文本模型：按词汇表之后各分词器追加的数据识别。
+
+use std::any::Any;
+
+use rayon::prelude::*;
+
+use crate::base::binary::{self, ModelKind};
+use crate::base::hf_json::{HfModel, HfTokenizerJson};
+use crate::base::padding::BatchEncoding;
+use crate::base::registry::{ModelRegistry, NamedModel};
+use crate::base::resources::ResourceConfig;
+use crate::base::tokenizer_base::lines_after_vocab;
+use crate::base::traits::{SpecialTokenizer, Tokenizer, VocabBytes};
+use crate::bbpe::BBPETokenizer;
+use crate::bpe::Tokenizer as BPETokenizer;
+use crate::error::{model_load_error, TokenizerError};
+use crate::unigram::UnigramTokenizer;
+use crate::wordpiece::WordPieceTokenizer;
+
+/// 标记ID固定为 `u32`、可以作为trait对象使用的分词器
+///
+/// 要求 `Self: Sized` 的方法（如 [`SpecialTokenizer::encode_batch_plus`]）不能通过trait对象调用，
+/// 这里提供对应的对象安全版本；需要具体类型的接口时用 `downcast_ref` 取回原类型
+pub trait DynTokenizer: SpecialTokenizer + VocabBytes<TokenId = u32> + Send + Sync + Any {
+    /// 分词器类型
+    fn kind(&self) -> ModelKind;
+
+    /// 并行编码一批文本
+    ///
+    /// # Errors
+    ///
+    /// 当任意文本编码失败时返回错误
+    fn batch_encode(&self, texts: &[&str]) -> Result<Vec<Vec<u32>>, TokenizerError>;
+
+    /// 按分词器的截断与补齐配置并行编码一批文本，同 [`SpecialTokenizer::encode_batch_plus`]
+    ///
+    /// # Errors
+    ///
+    /// 当 `pairs` 与 `texts` 数量不同、句子对所需的特殊标记未注册、截断失败或编码失败时返回错误
+    fn batch_encode_plus(
+        &self,
+        texts: &[&str],
+        pairs: Option<&[&str]>,
+    ) -> Result<BatchEncoding, TokenizerError>;
+
+    /// 转换为 `&dyn Any`，用于取回具体类型
+    fn as_any(&self) -> &dyn Any;
+
+    /// 转换为 `&mut dyn Any`，用于取回具体类型
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T> DynTokenizer for T
+where
+    T: NamedModel + SpecialTokenizer + VocabBytes<TokenId = u32> + Send + Sync + 'static,
+{
+    fn kind(&self) -> ModelKind {
+        T::KIND
+    }
+
+    fn batch_encode(&self, texts: &[&str]) -> Result<Vec<Vec<u32>>, TokenizerError> {
+        texts.par_iter().map(|text| self.encode(text)).collect()
+    }
+
+    fn batch_encode_plus(
+        &self,
+        texts: &[&str],
+        pairs: Option<&[&str]>,
+    ) -> Result<BatchEncoding, TokenizerError> {
+        self.encode_batch_plus(texts, pairs)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl dyn DynTokenizer {
+    /// 具体类型为 `T` 时返回其引用
+    #[must_use]
+    pub fn downcast_ref<T: DynTokenizer>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    /// 具体类型为 `T` 时返回其可变引用
+    pub fn downcast_mut<T: DynTokenizer>(&mut self) -> Option<&mut T> {
+        self.as_any_mut().downcast_mut()
+    }
+}
+
+/// 模型文件的格式
+#[derive(Debug)]
+enum ModelFormat {
+    /// `save` 写出的文本格式
+    Text,
+    /// `save_binary` 写出的二进制格式
+    Binary,
+    /// HuggingFace `tokenizer.json`，已解析
+    HfJson(HfTokenizerJson),
+}
+
+/// 按模型文件内容创建对应类型的分词器
+#[derive(Debug, Clone, Copy)]
+pub struct AutoTokenizer;
+
+impl AutoTokenizer {
+    /// 创建指定类型的空分词器，用于随后加载模型
+    ///
+    /// 词汇表来自模型文件，Unigram和WordPiece不读取常用汉字字表，与当前工作目录无关
+    ///
+    /// # Errors
+    ///
+    /// 当分词器初始化失败时返回错误
+    pub fn for_kind(kind: ModelKind) -> Result<Box<dyn DynTokenizer>, TokenizerError> {
+        let resources = ResourceConfig::without_char_dict();
+        Ok(match kind {
+            ModelKind::Bpe => Box::new(BPETokenizer::_new_internal()?),
+            ModelKind::Bbpe => Box::new(BBPETokenizer::new_internal()?),
+            ModelKind::Unigram => Box::new(UnigramTokenizer::with_resources(&resources)?),
+            ModelKind::WordPiece => Box::new(WordPieceTokenizer::with_resources(&resources)?),
+        })
+    }
+
+    /// 识别模型文件的类型并加载
+    ///
+    /// # Errors
+    ///
+    /// 当文件无法读取、无法识别模型类型或加载失败时返回错误
+    pub fn from_file(path: &str) -> Result<Box<dyn DynTokenizer>, TokenizerError> {
+        let (kind, format) = sniff(path)?;
+        let resources = ResourceConfig::without_char_dict();
+        match kind {
+            ModelKind::Bpe => finish(BPETokenizer::_new_internal()?, path, format),
+            ModelKind::Bbpe => finish(BBPETokenizer::new_internal()?, path, format),
+            ModelKind::Unigram => {
+                finish(UnigramTokenizer::with_resources(&resources)?, path, format)
+            }
+            ModelKind::WordPiece => finish(
+                WordPieceTokenizer::with_resources(&resources)?,
+                path,
+                format,
+            ),
+        }
+    }
+
+    /// 从默认的本地模型注册表按名称加载，类型取注册时的类型
+    ///
+    /// # Errors
+    ///
+    /// 当模型不存在或加载失败时返回错误
+    pub fn from_named(name: &str) -> Result<Box<dyn DynTokenizer>, TokenizerError> {
+        let entry = ModelRegistry::open_default()?.get(name)?;
+        let mut tokenizer = Self::for_kind(entry.kind)?;
+        tokenizer.load(&entry.path.to_string_lossy())?;
+        Ok(tokenizer)
+    }
+
+    /// 识别模型文件的类型，不加载模型
+    ///
+    /// # Errors
+    ///
+    /// 当文件无法读取或无法识别模型类型时返回错误
+    pub fn detect(path: &str) -> Result<ModelKind, TokenizerError> {
+        sniff(path).map(|(kind, _)| kind)
+    }
+}
+
+/// 各分词器加载二进制模型和 `tokenizer.json` 的方法
+trait AutoLoad: DynTokenizer + Sized {
+    fn load_binary_model(&mut self, path: &str) -> Result<(), TokenizerError>;
+
+    fn load_json_model(&mut self, json: HfTokenizerJson) -> Result<(), TokenizerError>;
+}
+
+macro_rules! impl_auto_load {
+    ($($tokenizer:ty),*) => {
+        $(
+            impl AutoLoad for $tokenizer {
+                fn load_binary_model(&mut self, path: &str) -> Result<(), TokenizerError> {
+                    self.load_binary(path)
+                }
+
+                fn load_json_model(&mut self, json: HfTokenizerJson) -> Result<(), TokenizerError> {
+                    self.load_hf_json(json)
+                }
+            }
+        )*
+    };
+}
+
+impl_auto_load!(
+    BPETokenizer,
+    BBPETokenizer,
+    UnigramTokenizer,
+    WordPieceTokenizer
+);
+
+fn finish<T: AutoLoad>(
+    mut tokenizer: T,
+    path: &str,
+    format: ModelFormat,
+) -> Result<Box<dyn DynTokenizer>, TokenizerError> {
+    match format {
+        ModelFormat::Text => Tokenizer::load(&mut tokenizer, path)?,
+        ModelFormat::Binary => tokenizer.load_binary_model(path)?,
+        ModelFormat::HfJson(json) => tokenizer.load_json_model(json)?,
+    }
+    Ok(Box::new(tokenizer))
+}
+
+/// 识别模型文件的类型和格式
+fn sniff(path: &str) -> Result<(ModelKind, ModelFormat), TokenizerError> {
+    let data = std::fs::read(path)
+        .map_err(|e| model_load_error(format!("打开文件 {} 失败: {}", path, e)))?;
+    if binary::is_binary(&data) {
+        return Ok((binary::peek_kind(&data)?, ModelFormat::Binary));
+    }
+    let text = String::from_utf8_lossy(&data);
+    if text.trim_start().starts_with('{') {
+        let json = HfTokenizerJson::parse(&text)?;
+        let kind = match json.model {
+            HfModel::Bpe { .. } if json.byte_level => ModelKind::Bbpe,
+            HfModel::Bpe { .. } => ModelKind::Bpe,
+            HfModel::Unigram { .. } => ModelKind::Unigram,
+            HfModel::WordPiece { .. } => ModelKind::WordPiece,
+        };
+        return Ok((kind, ModelFormat::HfJson(json)));
+    }
+    let unknown = || {
+        model_load_error(format!(
+            "无法识别模型文件 {} 的类型，请指定分词器类型",
+            path
+        ))
+    };
+    // BPE和BBPE的词汇表写在各自的数据中，Unigram和WordPiece的专用数据在词汇表之后
+    let kind = lines_after_vocab(&text)
+        .map_err(|_| unknown())?
+        .find_map(|line| {
+            if line.starts_with("sections: ") || line.starts_with("base_chars: ") {
+                Some(ModelKind::Bbpe)
+            } else if line.starts_with("vocab: ") {
+                Some(ModelKind::Bpe)
+            } else if line.starts_with("scores: ") {
+                Some(ModelKind::Unigram)
+            } else if line.starts_with("continuing_subword_prefix: ")
+                || line.starts_with("unk_token: ")
+            {
+                Some(ModelKind::WordPiece)
+            } else {
+                None
+            }
+        })
+        .ok_or_else(unknown)?;
+    Ok((kind, ModelFormat::Text))
+}
@@ -0,0 +1,545 @@
+//! 带版本头和校验和的二进制模型格式
+//!
+//! 文本格式按行追加各分词器的数据，词汇表很大时解析慢，文件被截断或改动也难以发现。
+//! 二进制格式的布局为：
+//!
+//! ```text
+//! 魔数 "ZTOK" | 格式版本 u16 | 模型类型 u8 | 保留 u8 | 段数 u32
+//! 段 × N: 标签 u8 | 长度 u64 | 内容
+//! 校验和 u64（之前全部字节的64位FNV-1a）
+//! ```
+//!
+//! 整数均为小端序。加载时跳过不认识的段，新版本增加的段不影响旧版本读取；
+//! 格式版本高于当前支持的版本时拒绝加载。
+
+use std::ops::Range;
+
+use crate::base::content_hash::hash_bytes;
+use crate::base::score::check_score;
+use crate::base::special_tokens::SpecialTokens;
+use crate::base::tokenizer_base::{ranked_merges, restore_ranked_merges, MergeEntry, MergeMap};
+use crate::error::{input_validation_error, model_load_error, TokenizerError};
+
+/// 二进制模型文件开头的魔数
+pub const BINARY_MAGIC: &[u8; 4] = b"ZTOK";
+
+/// 当前写出的格式版本，加载时接受不高于它的版本
+pub const BINARY_FORMAT_VERSION: u16 = 1;
+
+/// 魔数、版本、模型类型、保留字节和段数占用的字节数
+const HEADER_LEN: usize = 12;
+
+/// 段标签
+pub mod tag {
+    /// 预分词正则表达式
+    pub const PATTERN: u8 = 1;
+    /// 特殊标记和预留ID区间
+    pub const SPECIAL_TOKENS: u8 = 2;
+    /// 词汇表条目 (ID, 字节)
+    pub const VOCAB: u8 = 3;
+    /// 按等级排列的合并规则
+    pub const MERGES: u8 = 4;
+    /// 标记分数
+    pub const SCORES: u8 = 5;
+    /// 各分词器专用的键值设置
+    pub const SETTINGS: u8 = 6;
+    /// BBPE的基础字符
+    pub const BASE_CHARS: u8 = 7;
+    /// 规范化流水线，为空时不写出
+    pub const NORMALIZER: u8 = 8;
+    /// 预分词流水线，为默认流水线时不写出
+    pub const PRE_TOKENIZER: u8 = 9;
+    /// 解码器，为默认的直接拼接时不写出
+    pub const DECODER: u8 = 10;
+}
+
+/// 二进制模型对应的分词器类型，加载到其他类型的分词器时报错
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelKind {
+    /// 字符级BPE
+    Bpe = 1,
+    /// 字节级BPE
+    Bbpe = 2,
+    /// Unigram
+    Unigram = 3,
+    /// WordPiece
+    WordPiece = 4,
+}
+
+impl ModelKind {
+    /// 全部模型类型
+    pub const ALL: [Self; 4] = [Self::Bpe, Self::Bbpe, Self::Unigram, Self::WordPiece];
+
+    /// 类型名称，用于模型注册表和命令行
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bpe => "bpe",
+            Self::Bbpe => "bbpe",
+            Self::Unigram => "unigram",
+            Self::WordPiece => "wordpiece",
+        }
+    }
+
+    /// 按名称查找模型类型
+    ///
+    /// # Errors
+    ///
+    /// 当名称不是已知的模型类型时返回错误
+    pub fn from_name(name: &str) -> Result<Self, TokenizerError> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| input_validation_error(format!("未知的模型类型: {}", name)))
+    }
+
+    fn from_u8(value: u8) -> Result<Self, TokenizerError> {
+        match value {
+            1 => Ok(Self::Bpe),
+            2 => Ok(Self::Bbpe),
+            3 => Ok(Self::Unigram),
+            4 => Ok(Self::WordPiece),
+            other => Err(model_load_error(format!("未知的模型类型: {}", other))),
+        }
+    }
+}
+
+/// 数据是否以二进制模型的魔数开头，否则按文本格式加载
+#[must_use]
+pub fn is_binary(data: &[u8]) -> bool {
+    data.starts_with(BINARY_MAGIC)
+}
+
+/// 读取二进制模型头部记录的模型类型，不检查校验和
+///
+/// # Errors
+///
+/// 当数据不是二进制模型、头部被截断或模型类型未知时返回错误
+pub fn peek_kind(data: &[u8]) -> Result<ModelKind, TokenizerError> {
+    if !is_binary(data) {
+        return Err(model_load_error("不是二进制模型文件"));
+    }
+    let kind = data
+        .get(BINARY_MAGIC.len() + 2)
+        .ok_or_else(|| model_load_error("二进制模型数据被截断"))?;
+    ModelKind::from_u8(*kind)
+}
+
+/// 内存中的二进制模型：模型类型和按写入顺序排列的段
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryModel {
+    /// 模型类型
+    pub kind: ModelKind,
+    /// (标签, 内容)
+    pub sections: Vec<(u8, Vec<u8>)>,
+}
+
+impl BinaryModel {
+    /// 创建没有任何段的模型
+    #[must_use]
+    pub fn new(kind: ModelKind) -> Self {
+        Self {
+            kind,
+            sections: Vec::new(),
+        }
+    }
+
+    /// 追加一段
+    pub fn push(&mut self, tag: u8, writer: SectionWriter) {
+        self.sections.push((tag, writer.0));
+    }
+
+    /// 标签对应的第一段，不存在时返回 `None`
+    #[must_use]
+    pub fn section(&self, tag: u8) -> Option<SectionReader<'_>> {
+        self.sections
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, data)| SectionReader::new(data))
+    }
+
+    /// 必需的段，缺少时返回错误
+    ///
+    /// # Errors
+    ///
+    /// 当模型中没有该标签的段时返回错误
+    pub fn required(&self, tag: u8) -> Result<SectionReader<'_>, TokenizerError> {
+        self.section(tag)
+            .ok_or_else(|| model_load_error(format!("二进制模型缺少段: {}", tag)))
+    }
+
+    /// 序列化为字节，末尾附加校验和
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let body_len: usize = self.sections.iter().map(|(_, data)| 9 + data.len()).sum();
+        let mut out = Vec::with_capacity(HEADER_LEN + body_len + 8);
+        out.extend_from_slice(BINARY_MAGIC);
+        out.extend_from_slice(&BINARY_FORMAT_VERSION.to_le_bytes());
+        out.push(self.kind as u8);
+        out.push(0);
+        out.extend_from_slice(&(self.sections.len() as u32).to_le_bytes());
+        for (tag, data) in &self.sections {
+            out.push(*tag);
+            out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            out.extend_from_slice(data);
+        }
+        let checksum = hash_bytes(&out);
+        out.extend_from_slice(&checksum.to_le_bytes());
+        out
+    }
+
+    /// 解析二进制模型，并检查魔数、版本、校验和和模型类型
+    ///
+    /// # Errors
+    ///
+    /// 当数据不是二进制模型、版本过高、校验和不符、数据被截断或模型类型与 `expected` 不同时返回错误
+    pub fn from_bytes(data: &[u8], expected: ModelKind) -> Result<Self, TokenizerError> {
+        if !is_binary(data) {
+            return Err(model_load_error("不是二进制模型文件"));
+        }
+        if data.len() < HEADER_LEN + 8 {
+            return Err(model_load_error("二进制模型数据被截断"));
+        }
+        let (body, checksum) = data.split_at(data.len() - 8);
+        let checksum = u64::from_le_bytes(
+            checksum
+                .try_into()
+                .map_err(|_| model_load_error("校验和长度无效"))?,
+        );
+        if hash_bytes(body) != checksum {
+            return Err(model_load_error("二进制模型校验和不符，文件可能已损坏"));
+        }
+
+        let mut reader = SectionReader::new(&body[BINARY_MAGIC.len()..]);
+        let version = u16::from_le_bytes([reader.u8()?, reader.u8()?]);
+        if version > BINARY_FORMAT_VERSION {
+            return Err(model_load_error(format!(
+                "模型格式版本 {} 高于支持的版本 {}",
+                version, BINARY_FORMAT_VERSION
+            )));
+        }
+        let kind = ModelKind::from_u8(reader.u8()?)?;
+        if kind != expected {
+            return Err(model_load_error(format!(
+                "模型类型不匹配: 文件为 {:?}，分词器为 {:?}",
+                kind, expected
+            )));
+        }
+        reader.u8()?;
+        let count = reader.u32()?;
+        let mut sections = Vec::with_capacity(count.min(64) as usize);
+        for _ in 0..count {
+            let tag = reader.u8()?;
+            let len =
+                usize::try_from(reader.u64()?).map_err(|_| model_load_error("段长度超出范围"))?;
+            sections.push((tag, reader.take(len)?.to_vec()));
+        }
+        reader.finish()?;
+        Ok(Self { kind, sections })
+    }
+}
+
+/// 段内容的写入器
+#[derive(Debug, Default)]
+pub struct SectionWriter(Vec<u8>);
+
+impl SectionWriter {
+    /// 创建空的写入器
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 写入小端序u32
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    /// 写入小端序f64，按位保存，可以精确还原
+    pub fn f64(&mut self, value: f64) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    /// 写入u32长度前缀和字节
+    pub fn bytes(&mut self, value: &[u8]) -> &mut Self {
+        self.u32(value.len() as u32);
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    /// 写入UTF-8字符串
+    pub fn str(&mut self, value: &str) -> &mut Self {
+        self.bytes(value.as_bytes())
+    }
+}
+
+/// 段内容的读取器，越界时返回错误而不是panic
+#[derive(Debug)]
+pub struct SectionReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SectionReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], TokenizerError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| model_load_error("二进制模型数据被截断"))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, TokenizerError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// 读取小端序u32
+    ///
+    /// # Errors
+    ///
+    /// 剩余数据不足时返回错误
+    pub fn u32(&mut self) -> Result<u32, TokenizerError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, TokenizerError> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    /// 读取小端序f64
+    ///
+    /// # Errors
+    ///
+    /// 剩余数据不足时返回错误
+    pub fn f64(&mut self) -> Result<f64, TokenizerError> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(f64::from_le_bytes(bytes))
+    }
+
+    /// 读取带长度前缀的字节
+    ///
+    /// # Errors
+    ///
+    /// 剩余数据不足时返回错误
+    pub fn bytes(&mut self) -> Result<&'a [u8], TokenizerError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    /// 读取UTF-8字符串
+    ///
+    /// # Errors
+    ///
+    /// 剩余数据不足或不是有效的UTF-8时返回错误
+    pub fn str(&mut self) -> Result<&'a str, TokenizerError> {
+        std::str::from_utf8(self.bytes()?)
+            .map_err(|e| model_load_error(format!("二进制模型中的字符串无效: {}", e)))
+    }
+
+    /// 读取元素数量，数量超过剩余字节数时视为数据损坏，避免按错误的数量预分配
+    fn count(&mut self) -> Result<usize, TokenizerError> {
+        let count = self.u32()? as usize;
+        if count > self.data.len() - self.pos {
+            return Err(model_load_error("二进制模型数据被截断"));
+        }
+        Ok(count)
+    }
+
+    /// 检查段内容已全部读完
+    ///
+    /// # Errors
+    ///
+    /// 有多余数据时返回错误
+    pub fn finish(&self) -> Result<(), TokenizerError> {
+        if self.pos == self.data.len() {
+            Ok(())
+        } else {
+            Err(model_load_error("二进制模型段中有多余数据"))
+        }
+    }
+}
+
+/// 写出词汇表段
+pub fn write_vocab<'a>(entries: impl Iterator<Item = (u32, &'a [u8])>) -> SectionWriter {
+    let entries: Vec<_> = entries.collect();
+    let mut writer = SectionWriter::new();
+    writer.u32(entries.len() as u32);
+    for (id, bytes) in entries {
+        writer.u32(id).bytes(bytes);
+    }
+    writer
+}
+
+/// 读取词汇表段
+///
+/// # Errors
+///
+/// 当段内容被截断或有多余数据时返回错误
+pub fn read_vocab(mut reader: SectionReader<'_>) -> Result<Vec<(u32, Vec<u8>)>, TokenizerError> {
+    let count = reader.count()?;
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        let id = reader.u32()?;
+        entries.push((id, reader.bytes()?.to_vec()));
+    }
+    reader.finish()?;
+    Ok(entries)
+}
+
+/// 读取文本词汇表段，标记必须是有效的UTF-8
+///
+/// # Errors
+///
+/// 当段内容无效或标记不是有效的UTF-8时返回错误
+pub fn read_text_vocab(reader: SectionReader<'_>) -> Result<Vec<(u32, String)>, TokenizerError> {
+    read_vocab(reader)?
+        .into_iter()
+        .map(|(id, bytes)| {
+            String::from_utf8(bytes)
+                .map(|text| (id, text))
+                .map_err(|e| {
+                    model_load_error(format!("词汇表中ID {} 的标记不是有效的UTF-8: {}", id, e))
+                })
+        })
+        .collect()
+}
+
+/// 按等级顺序写出合并规则段
+#[must_use]
+pub fn write_merges(merges: &MergeMap) -> SectionWriter {
+    let ranked = ranked_merges(merges);
+    let mut writer = SectionWriter::new();
+    writer.u32(ranked.len() as u32);
+    for ((left, right), new_id) in ranked {
+        writer.u32(left).u32(right).u32(new_id);
+    }
+    writer
+}
+
+/// 读取合并规则段，第 `i` 条的等级为 `i`
+///
+/// # Errors
+///
+/// 当段内容被截断或有多余数据时返回错误
+pub fn read_merges(mut reader: SectionReader<'_>) -> Result<MergeMap, TokenizerError> {
+    let count = reader.count()?;
+    let mut entries = Vec::with_capacity(count);
+    for rank in 0..count as u32 {
+        entries.push(MergeEntry {
+            pair: (reader.u32()?, reader.u32()?),
+            new_id: reader.u32()?,
+            rank: Some(rank),
+        });
+    }
+    reader.finish()?;
+    let mut merges = MergeMap::with_capacity(count);
+    merges.extend(
+        restore_ranked_merges(entries)?
+            .into_iter()
+            .map(|entry| (entry.pair, entry.new_id)),
+    );
+    Ok(merges)
+}
+
+/// 写出特殊标记段：特殊标记 (ID, 文本) 和预留区间 (起始, 结束)
+#[must_use]
+pub fn write_special_tokens(special_tokens: &SpecialTokens) -> SectionWriter {
+    let mut writer = SectionWriter::new();
+    writer.u32(special_tokens.len() as u32);
+    for (id, token) in special_tokens.iter() {
+        writer.u32(id).str(token);
+    }
+    writer.u32(special_tokens.reserved().len() as u32);
+    for range in special_tokens.reserved() {
+        writer.u32(range.start).u32(range.end);
+    }
+    writer
+}
+
+/// 读取特殊标记段
+///
+/// # Errors
+///
+/// 当段内容无效或标记与预留区间冲突时返回错误
+pub fn read_special_tokens(mut reader: SectionReader<'_>) -> Result<SpecialTokens, TokenizerError> {
+    let mut special_tokens = SpecialTokens::new();
+    for _ in 0..reader.count()? {
+        let id = reader.u32()?;
+        special_tokens.insert(reader.str()?, id)?;
+    }
+    for _ in 0..reader.count()? {
+        let range: Range<u32> = reader.u32()?..reader.u32()?;
+        special_tokens.reserve(range)?;
+    }
+    reader.finish()?;
+    Ok(special_tokens)
+}
+
+/// 写出分数段
+#[must_use]
+pub fn write_scores(scores: &[f64]) -> SectionWriter {
+    let mut writer = SectionWriter::new();
+    writer.u32(scores.len() as u32);
+    for &score in scores {
+        writer.f64(score);
+    }
+    writer
+}
+
+/// 读取分数段
+///
+/// # Errors
+///
+/// 当段内容被截断、有多余数据或分数不是有限数时返回错误
+pub fn read_scores(mut reader: SectionReader<'_>) -> Result<Vec<f64>, TokenizerError> {
+    let count = reader.count()?;
+    let mut scores = Vec::with_capacity(count);
+    for index in 0..count {
+        let score = check_score(reader.f64()?)
+            .map_err(|e| model_load_error(format!("第 {} 个分数无效: {}", index, e)))?;
+        scores.push(score);
+    }
+    reader.finish()?;
+    Ok(scores)
+}
+
+/// 写出键值设置段，值使用与文本格式相同的写法
+#[must_use]
+pub fn write_settings(settings: &[(&str, String)]) -> SectionWriter {
+    let mut writer = SectionWriter::new();
+    writer.u32(settings.len() as u32);
+    for (key, value) in settings {
+        writer.str(key).str(value);
+    }
+    writer
+}
+
+/// 读取键值设置段，不认识的键由调用方忽略
+///
+/// # Errors
+///
+/// 当段内容无效时返回错误
+pub fn read_settings(
+    mut reader: SectionReader<'_>,
+) -> Result<Vec<(String, String)>, TokenizerError> {
+    let count = reader.count()?;
+    let mut settings = Vec::with_capacity(count);
+    for _ in 0..count {
+        settings.push((reader.str()?.to_string(), reader.str()?.to_string()));
+    }
+    reader.finish()?;
+    Ok(settings)
+}
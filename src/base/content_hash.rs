@@ -0,0 +1,25 @@
+//! 与平台无关的标记ID哈希
+//!
+//! 预处理流水线缓存编码结果时，用标记ID序列的哈希判断缓存是否因分词器变化而过期。
+//! 每个ID按小端序写成4个字节，用64位FNV-1a计算，结果不随平台、进程和Rust版本变化。
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 计算标记ID序列的64位FNV-1a哈希，空序列的哈希为FNV偏移基数
+#[must_use]
+pub fn hash_ids(ids: &[u32]) -> u64 {
+    fnv1a(ids.iter().flat_map(|id| id.to_le_bytes()))
+}
+
+/// 计算字节序列的64位FNV-1a哈希，用作二进制模型文件的校验和
+#[must_use]
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    fnv1a(bytes.iter().copied())
+}
+
+fn fnv1a(bytes: impl Iterator<Item = u8>) -> u64 {
+    bytes.fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
@@ -0,0 +1,235 @@
+//! 解码器
+//!
+//! 解码时依次取出每个标记的字节，由 [`Decoder`] 拼接成文本。默认的 [`DecoderKind::Fuse`] 直接拼接，
+//! 与本库的编码互逆；其余解码器按各模型家族的约定还原文本：GPT-2风格的词汇表把字节写成 `Ġ` 等字符，
+//! SentencePiece风格的词汇表用 `▁` 表示空格，BERT风格的WordPiece在词之间补空格。解码器随模型保存。
+//! 流式解码（[`DecodeStream`](crate::generation::DecodeStream)）按同样的规则逐个还原标记；WordPiece的标点清理
+//! 可能跨越标记，因此最后一个词要等后续标记到达后才输出。
+
+use std::borrow::Cow;
+
+use crate::base::tokenizer_base::piece_bytes;
+use crate::bbpe::byte_level::char_to_byte;
+use crate::error::{input_validation_error, invalid_utf8_error, TokenizerError};
+
+/// 模型文件中解码器行的前缀，完整形式为 `decoder: metaspace`
+pub const DECODER_HEADER: &str = "decoder: ";
+
+/// SentencePiece表示空格的字符
+const METASPACE: char = '▁';
+
+/// 交给解码器的单个标记
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedPiece<'a> {
+    /// 标记解码后的字节
+    pub bytes: Cow<'a, [u8]>,
+    /// 字节已是原始字节（BBPE的标记、`<0xNN>` 字节标记），不再按文本还原
+    pub raw: bool,
+    /// 是否为WordPiece的词内续接片段，词汇表中带续接前缀
+    pub continuation: bool,
+}
+
+impl<'a> DecodedPiece<'a> {
+    /// 原始字节组成的标记
+    pub fn raw(bytes: impl Into<Cow<'a, [u8]>>) -> Self {
+        Self {
+            bytes: bytes.into(),
+            raw: true,
+            continuation: false,
+        }
+    }
+
+    /// 字符串词汇表中的标记，`<0xNN>` 形式的字节标记还原为原始字节
+    #[must_use]
+    pub fn from_vocab(piece: &'a str) -> Self {
+        let bytes = piece_bytes(piece);
+        Self {
+            raw: matches!(bytes, Cow::Owned(_)),
+            bytes,
+            continuation: false,
+        }
+    }
+
+    /// 设置是否为词内续接片段
+    #[must_use]
+    pub fn with_continuation(mut self, continuation: bool) -> Self {
+        self.continuation = continuation;
+        self
+    }
+}
+
+/// 把标记序列还原为文本的解码器
+pub trait Decoder {
+    /// 按顺序拼接 `pieces`
+    ///
+    /// # Errors
+    ///
+    /// 当结果不是有效的UTF-8时返回 [`TokenizerError::InvalidUtf8`]
+    fn decode_pieces(&self, pieces: &[DecodedPiece<'_>]) -> Result<String, TokenizerError>;
+}
+
+/// 内置解码器
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DecoderKind {
+    /// 直接拼接各标记的字节
+    #[default]
+    Fuse,
+    /// GPT-2风格：把 `Ġ` 等字符按字节↔Unicode映射还原为字节
+    ByteLevel,
+    /// SentencePiece风格：`▁` 还原为空格，去掉开头的一个空格
+    Metaspace,
+    /// BERT风格：非续接片段之前补空格，并去掉标点和缩写之前多余的空格
+    WordPiece,
+}
+
+impl DecoderKind {
+    /// 全部内置解码器
+    pub const ALL: [Self; 4] = [
+        Self::Fuse,
+        Self::ByteLevel,
+        Self::Metaspace,
+        Self::WordPiece,
+    ];
+
+    /// 解码器名称，用于模型文件和Python接口
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fuse => "fuse",
+            Self::ByteLevel => "byte_level",
+            Self::Metaspace => "metaspace",
+            Self::WordPiece => "wordpiece",
+        }
+    }
+
+    /// 按名称查找解码器
+    ///
+    /// # Errors
+    ///
+    /// 当名称不是内置解码器时返回错误
+    pub fn from_name(name: &str) -> Result<Self, TokenizerError> {
+        let name = name.trim();
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|kind| kind.name()).collect();
+                input_validation_error(format!(
+                    "未知的解码器: {}，可选: {}",
+                    name,
+                    names.join(", ")
+                ))
+            })
+    }
+
+    /// 单个标记按解码器约定还原后的字节，`first` 表示是否为第一个标记
+    pub(crate) fn piece<'p>(self, piece: &'p DecodedPiece<'_>, first: bool) -> Cow<'p, [u8]> {
+        let bytes = piece.bytes.as_ref();
+        let text = match std::str::from_utf8(bytes) {
+            Ok(text) if !piece.raw => text,
+            _ => return Cow::Borrowed(bytes),
+        };
+        match self {
+            Self::Fuse => Cow::Borrowed(bytes),
+            Self::ByteLevel => {
+                let mut unmapped = Vec::with_capacity(text.len());
+                let mut buf = [0u8; 4];
+                for c in text.chars() {
+                    match char_to_byte(c) {
+                        Some(byte) => unmapped.push(byte),
+                        None => unmapped.extend_from_slice(c.encode_utf8(&mut buf).as_bytes()),
+                    }
+                }
+                Cow::Owned(unmapped)
+            }
+            Self::Metaspace => {
+                let replaced = text.replace(METASPACE, " ");
+                let replaced = match replaced.strip_prefix(' ') {
+                    Some(rest) if first => rest.to_string(),
+                    _ => replaced,
+                };
+                Cow::Owned(replaced.into_bytes())
+            }
+            Self::WordPiece if first || piece.continuation => Cow::Borrowed(bytes),
+            Self::WordPiece => Cow::Owned([b" ", bytes].concat()),
+        }
+    }
+}
+
+impl Decoder for DecoderKind {
+    fn decode_pieces(&self, pieces: &[DecodedPiece<'_>]) -> Result<String, TokenizerError> {
+        let parts: Vec<Cow<'_, [u8]>> = pieces
+            .iter()
+            .enumerate()
+            .map(|(index, piece)| self.piece(piece, index == 0))
+            .collect();
+        let text = String::from_utf8(parts.concat())
+            .map_err(|e| invalid_utf8_error(&e.utf8_error(), parts.iter().map(|p| p.len())))?;
+        Ok(self.finish_text(text))
+    }
+}
+
+impl DecoderKind {
+    /// 拼接后的文本按解码器约定做最后的整理，目前只有WordPiece需要清理标点前的空格
+    pub(crate) fn finish_text(self, text: String) -> String {
+        match self {
+            Self::WordPiece => cleanup(&text),
+            _ => text,
+        }
+    }
+
+    /// 流式解码时 `text` 中可以先行输出的前缀长度，之后追加任何文本都不会改变这部分整理后的结果
+    pub(crate) fn stable_prefix_len(self, text: &str) -> usize {
+        if self != Self::WordPiece {
+            return text.len();
+        }
+        // 清理规则都以空格开头，只有 " do not" 在字母之后还含有空格。在字母或数字之后的空格处切分，
+        // 且切分点之前不是 " do" 时，两部分分别清理与整体清理的结果相同
+        text.char_indices()
+            .rev()
+            .filter(|&(index, c)| c == ' ' && index > 0)
+            .map(|(index, _)| index)
+            .find(|&index| {
+                let head = &text[..index];
+                head.chars().next_back().is_some_and(char::is_alphanumeric)
+                    && !head.ends_with(" do")
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// 依次取出 `tokens` 中每个标记交给 `decoder`，`piece` 在ID无效时返回 `None`
+///
+/// # Errors
+///
+/// 当标记ID无效或结果不是有效的UTF-8时返回错误
+pub fn decode_ids<'a, D>(
+    decoder: &D,
+    tokens: &[u32],
+    piece: impl Fn(u32) -> Option<DecodedPiece<'a>>,
+) -> Result<String, TokenizerError>
+where
+    D: Decoder + ?Sized,
+{
+    let pieces = tokens
+        .iter()
+        .enumerate()
+        .map(|(index, &id)| piece(id).ok_or(TokenizerError::UnknownTokenId { id, index }))
+        .collect::<Result<Vec<_>, _>>()?;
+    decoder.decode_pieces(&pieces)
+}
+
+/// 去掉标点和英文缩写之前多余的空格，规则与HuggingFace `tokenizers` 的WordPiece解码器相同
+fn cleanup(text: &str) -> String {
+    text.replace(" .", ".")
+        .replace(" ?", "?")
+        .replace(" !", "!")
+        .replace(" ,", ",")
+        .replace(" ' ", "'")
+        .replace(" n't", "n't")
+        .replace(" 'm", "'m")
+        .replace(" do not", " don't")
+        .replace(" 's", "'s")
+        .replace(" 've", "'ve")
+        .replace(" 're", "'re")
+}
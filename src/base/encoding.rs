@@ -0,0 +1,162 @@
+//! 带偏移的编码结果
+//!
+//! 命名实体识别、片段标注等任务需要把模型在标记上的输出对齐回原文，
+//! [`Encoding`] 同时保存标记ID、标记文本和每个标记在原文中的区间。
+
+use std::ops::Range;
+
+use crate::base::traits::VocabBytes;
+
+/// 编码结果：标记ID、标记文本和每个标记在原文中的 `(start, end)` 区间
+///
+/// 三者长度相同、按标记顺序排列。区间规则同 [`VocabBytes::spans`]：区间按规范化前的原文给出，
+/// 预分词丢弃的空白被跳过，未知标记覆盖它所代替的文本；规范化把一个字符变成多个字符时，
+/// 由它产生的各标记共用该字符的区间。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Encoding {
+    /// 标记ID
+    pub ids: Vec<u32>,
+    /// 标记解码后的文本，为规范化后的形式，不一定与原文区间中的文本相同；
+    /// 不构成有效UTF-8的字节（如单独的字节回退标记）显示为替换字符
+    pub tokens: Vec<String>,
+    /// 每个标记在原文中的区间
+    pub offsets: Vec<(usize, usize)>,
+}
+
+impl Encoding {
+    /// 由 [`VocabBytes::spans`] 或 [`VocabBytes::spans_in`] 的结果构造
+    pub fn from_spans<T>(tokenizer: &T, spans: Vec<(usize, usize, u32)>) -> Self
+    where
+        T: VocabBytes<TokenId = u32> + ?Sized,
+    {
+        let mut encoding = Self {
+            ids: Vec::with_capacity(spans.len()),
+            tokens: Vec::with_capacity(spans.len()),
+            offsets: Vec::with_capacity(spans.len()),
+        };
+        for (start, end, id) in spans {
+            let token = tokenizer
+                .token_bytes(&id)
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                .unwrap_or_default();
+            encoding.ids.push(id);
+            encoding.tokens.push(token);
+            encoding.offsets.push((start, end));
+        }
+        encoding
+    }
+
+    /// 标记数
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// 是否没有标记
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// 覆盖原文位置 `offset` 的标记下标，该位置被跳过（如空白）时返回 `None`
+    #[must_use]
+    pub fn token_at(&self, offset: usize) -> Option<usize> {
+        let index = self.offsets.partition_point(|&(_, end)| end <= offset);
+        self.offsets
+            .get(index)
+            .filter(|&&(start, end)| start <= offset && offset < end)
+            .map(|_| index)
+    }
+
+    /// 与原文区间 `start..end` 重叠的标记下标范围，用于把字符级标注对齐到标记
+    #[must_use]
+    pub fn token_range(&self, start: usize, end: usize) -> Range<usize> {
+        let first = self
+            .offsets
+            .partition_point(|&(_, token_end)| token_end <= start);
+        let last = self
+            .offsets
+            .partition_point(|&(token_start, _)| token_start < end);
+        first..last.max(first)
+    }
+}
+
+/// 句子对的编码结果：`[CLS] A [SEP] B [SEP]` 的标记ID和每个标记所属的句子
+///
+/// `token_type_ids` 与 `ids` 等长，第一句及其前后的 `[CLS]`、`[SEP]` 为0，第二句及末尾的 `[SEP]` 为1，
+/// 对应BERT类模型的segment嵌入。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PairEncoding {
+    /// 标记ID
+    pub ids: Vec<u32>,
+    /// 每个标记所属的句子，0或1
+    pub token_type_ids: Vec<u32>,
+}
+
+impl PairEncoding {
+    /// 用分类标记 `cls`、分隔标记 `sep` 拼接两句已编码的标记ID
+    #[must_use]
+    pub fn from_pair(cls: u32, sep: u32, first: &[u32], second: &[u32]) -> Self {
+        let first_len = first.len() + 2;
+        let second_len = second.len() + 1;
+
+        let mut ids = Vec::with_capacity(first_len + second_len);
+        ids.push(cls);
+        ids.extend_from_slice(first);
+        ids.push(sep);
+        ids.extend_from_slice(second);
+        ids.push(sep);
+
+        let mut token_type_ids = vec![0; first_len];
+        token_type_ids.resize(first_len + second_len, 1);
+        Self {
+            ids,
+            token_type_ids,
+        }
+    }
+
+    /// 标记数
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// 是否没有标记
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+#[cfg(feature = "python")]
+impl PairEncoding {
+    /// 转换为Python字典，包含 `input_ids` 和 `token_type_ids`
+    pub(crate) fn to_py_dict<'py>(
+        &self,
+        py: pyo3::Python<'py>,
+    ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyDict>> {
+        use pyo3::types::{PyDict, PyDictMethods};
+
+        let dict = PyDict::new(py);
+        dict.set_item("input_ids", self.ids.clone())?;
+        dict.set_item("token_type_ids", self.token_type_ids.clone())?;
+        Ok(dict)
+    }
+}
+
+#[cfg(feature = "python")]
+impl Encoding {
+    /// 转换为Python字典，包含 `ids`、`tokens` 和 `offsets`
+    pub(crate) fn to_py_dict<'py>(
+        &self,
+        py: pyo3::Python<'py>,
+    ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyDict>> {
+        use pyo3::types::{PyDict, PyDictMethods};
+
+        let dict = PyDict::new(py);
+        dict.set_item("ids", self.ids.clone())?;
+        dict.set_item("tokens", self.tokens.clone())?;
+        dict.set_item("offsets", self.offsets.clone())?;
+        Ok(dict)
+    }
+}
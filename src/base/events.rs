@@ -0,0 +1,421 @@
+//! 训练事件流
+//!
+//! 训练过程以类型化事件的形式通知已注册的观察者，便于构建监控面板或记录可复现的训练日志。
+//! 默认注册的 [`LogObserver`] 会把事件转换为 `log` 输出。
+//!
+//! 观察者还可以通过 [`TrainObserver::snapshot_request`] 定期获取当前计数最高的候选配对，
+//! 查看训练器接下来要学习的内容；发现语料配置有误时由 [`TrainObserver::should_stop`] 提前停止训练。
+
+use std::fmt;
+#[cfg(feature = "python")]
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+
+#[cfg(feature = "python")]
+use crate::error::{input_validation_error, TokenizerError};
+
+/// 训练结束时的统计信息
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrainStats {
+    /// 实际完成的合并次数
+    pub merges: u32,
+    /// 训练后的词汇表大小
+    pub vocab_size: usize,
+    /// 训练耗时
+    pub elapsed: Duration,
+    /// 是否因观察者要求而提前停止
+    pub stopped: bool,
+}
+
+/// 候选配对快照中的一个配对
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PairCandidate {
+    /// 配对的两个token ID
+    pub pair: (u32, u32),
+    /// 该配对当前的出现次数
+    pub count: u64,
+    /// 合并后的片段，字节级片段按UTF-8有损转换
+    pub piece: String,
+}
+
+/// 观察者对候选配对快照的请求
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotRequest {
+    /// 每完成多少次合并生成一次快照，训练开始时（第0次合并之前）总会生成一次；为0时不生成
+    pub every: u32,
+    /// 每次快照包含的配对数
+    pub top_k: usize,
+}
+
+/// 训练事件
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum TrainEvent {
+    /// 训练开始
+    TrainStarted {
+        /// 参与训练的唯一序列数
+        unique_sequences: usize,
+        /// 目标词汇表大小
+        target_vocab_size: u32,
+        /// 计划执行的合并次数
+        planned_merges: u32,
+    },
+    /// 初始配对计数完成
+    PairCountsDone {
+        /// 唯一配对数量
+        unique_pairs: usize,
+    },
+    /// 应用了一次合并
+    MergeApplied {
+        /// 合并等级（从0开始，即第几次合并）
+        rank: u32,
+        /// 被合并的配对
+        pair: (u32, u32),
+        /// 合并生成的token ID
+        new_id: u32,
+        /// 合并时该配对的出现次数
+        count: u64,
+    },
+    /// 候选配对快照，只发给请求了快照的观察者
+    PairSnapshot {
+        /// 已完成的合并次数
+        merges_done: u32,
+        /// 计数最高的候选配对，按计数降序排列，计数相同时按配对升序
+        ///
+        /// 计数为当前的精确值，但超过最大长度的配对仍会列出，训练时会被跳过
+        candidates: Vec<PairCandidate>,
+    },
+    /// 训练结束
+    Finished {
+        /// 统计信息
+        stats: TrainStats,
+    },
+}
+
+/// 训练事件观察者
+pub trait TrainObserver: Send + Sync {
+    /// 处理一个训练事件
+    fn on_event(&self, event: &TrainEvent);
+
+    /// 需要候选配对快照时返回请求，默认不需要
+    fn snapshot_request(&self) -> Option<SnapshotRequest> {
+        None
+    }
+
+    /// 返回 `true` 时训练在下一次合并之前停止，已学到的合并保留，默认从不停止
+    fn should_stop(&self) -> bool {
+        false
+    }
+}
+
+impl<F> TrainObserver for F
+where
+    F: Fn(&TrainEvent) + Send + Sync,
+{
+    fn on_event(&self, event: &TrainEvent) {
+        self(event)
+    }
+}
+
+/// 将训练事件写入 `log` 的观察者，每完成1%的合并记录一次进度
+#[derive(Debug, Default)]
+pub struct LogObserver {
+    planned_merges: AtomicU32,
+    last_percent: AtomicU32,
+}
+
+impl TrainObserver for LogObserver {
+    fn on_event(&self, event: &TrainEvent) {
+        match event {
+            TrainEvent::TrainStarted {
+                unique_sequences,
+                planned_merges,
+                ..
+            } => {
+                self.planned_merges
+                    .store(*planned_merges, Ordering::Relaxed);
+                self.last_percent.store(0, Ordering::Relaxed);
+                log::info!(
+                    "开始增量训练: 需要计算 {} 次合并，共 {} 个唯一序列",
+                    planned_merges,
+                    unique_sequences
+                );
+            }
+            TrainEvent::PairCountsDone { unique_pairs } => {
+                log::info!("使用 {} 个唯一配对构建堆", unique_pairs);
+            }
+            TrainEvent::MergeApplied { rank, .. } => {
+                let merges_done = rank + 1;
+                let planned = self.planned_merges.load(Ordering::Relaxed).max(1);
+                let percent = merges_done * 100 / planned;
+                if percent > self.last_percent.load(Ordering::Relaxed) {
+                    log::info!("训练进度: {}% ({} 次合并)", percent, merges_done);
+                    self.last_percent.store(percent, Ordering::Relaxed);
+                }
+            }
+            TrainEvent::PairSnapshot { .. } => {}
+            TrainEvent::Finished { stats } => {
+                if stats.stopped {
+                    log::info!("观察者要求停止训练");
+                }
+                log::info!(
+                    "训练完成: {} 次合并，词汇表大小: {}，耗时 {:.2?}",
+                    stats.merges,
+                    stats.vocab_size,
+                    stats.elapsed
+                );
+            }
+        }
+    }
+}
+
+/// 已注册的训练观察者集合
+#[derive(Clone)]
+pub struct TrainObservers {
+    observers: Vec<Arc<dyn TrainObserver>>,
+}
+
+impl TrainObservers {
+    /// 创建不含任何观察者的集合
+    #[must_use]
+    pub fn empty() -> Self {
+        Self {
+            observers: Vec::new(),
+        }
+    }
+
+    /// 注册观察者
+    pub fn add(&mut self, observer: Arc<dyn TrainObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// 移除所有观察者（包括默认的日志观察者）
+    pub fn clear(&mut self) {
+        self.observers.clear();
+    }
+
+    /// 已注册的观察者数量
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    /// 是否没有注册任何观察者
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    /// 向所有观察者分发事件
+    pub fn emit(&self, event: TrainEvent) {
+        for observer in &self.observers {
+            observer.on_event(&event);
+        }
+    }
+
+    /// 完成 `merges_done` 次合并后，向到期的观察者分发候选配对快照
+    ///
+    /// `candidates(k)` 返回计数最高的 `k` 个配对，只在有观察者到期时调用一次，
+    /// 每个观察者收到其中前 `top_k` 个
+    pub fn snapshot(&self, merges_done: u32, candidates: impl FnOnce(usize) -> Vec<PairCandidate>) {
+        let due: Vec<(&Arc<dyn TrainObserver>, usize)> = self
+            .observers
+            .iter()
+            .filter_map(|observer| {
+                let request = observer.snapshot_request()?;
+                (request.every > 0 && merges_done.is_multiple_of(request.every))
+                    .then_some((observer, request.top_k))
+            })
+            .collect();
+        let Some(k) = due.iter().map(|&(_, top_k)| top_k).max() else {
+            return;
+        };
+        let candidates = candidates(k);
+        for (observer, top_k) in due {
+            observer.on_event(&TrainEvent::PairSnapshot {
+                merges_done,
+                candidates: candidates.iter().take(top_k).cloned().collect(),
+            });
+        }
+    }
+
+    /// 是否有观察者要求停止训练
+    #[must_use]
+    pub fn should_stop(&self) -> bool {
+        self.observers.iter().any(|observer| observer.should_stop())
+    }
+}
+
+/// 计数最高的 `k` 个配对，按计数降序排列，计数相同时按配对升序
+pub fn top_pairs<'a>(
+    pair_counts: impl IntoIterator<Item = (&'a (u32, u32), &'a i32)>,
+    k: usize,
+) -> Vec<((u32, u32), u64)> {
+    let mut pairs: Vec<((u32, u32), u64)> = pair_counts
+        .into_iter()
+        .filter(|&(_, &count)| count > 0)
+        .map(|(&pair, &count)| (pair, count as u64))
+        .collect();
+    let order = |a: &((u32, u32), u64), b: &((u32, u32), u64)| b.1.cmp(&a.1).then(a.0.cmp(&b.0));
+    if pairs.len() > k && k > 0 {
+        pairs.select_nth_unstable_by(k - 1, order);
+    }
+    pairs.truncate(k);
+    pairs.sort_unstable_by(order);
+    pairs
+}
+
+impl Default for TrainObservers {
+    /// 默认只包含 [`LogObserver`]
+    fn default() -> Self {
+        let mut observers = Self::empty();
+        observers.add(Arc::new(LogObserver::default()));
+        observers
+    }
+}
+
+impl fmt::Debug for TrainObservers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrainObservers")
+            .field("len", &self.observers.len())
+            .finish()
+    }
+}
+
+/// 将训练事件转发给Python可调用对象的观察者，事件以dict形式传入
+///
+/// 回调返回 `False` 时训练在下一次合并之前停止，返回其他值（包括 `None`）时继续
+#[cfg(feature = "python")]
+pub struct PyTrainObserver {
+    callback: pyo3::Py<pyo3::PyAny>,
+    snapshot: Option<SnapshotRequest>,
+    stop: AtomicBool,
+}
+
+#[cfg(feature = "python")]
+impl PyTrainObserver {
+    /// 包装一个Python可调用对象
+    pub fn new(callback: pyo3::Py<pyo3::PyAny>) -> Self {
+        Self {
+            callback,
+            snapshot: None,
+            stop: AtomicBool::new(false),
+        }
+    }
+
+    /// 请求候选配对快照
+    #[must_use]
+    pub fn with_snapshot(mut self, request: SnapshotRequest) -> Self {
+        self.snapshot = Some(request);
+        self
+    }
+
+    /// 由Python的 `add_train_observer` 参数构造观察者，`snapshot_every` 为 `None` 时不请求快照
+    ///
+    /// # Errors
+    ///
+    /// 当 `snapshot_every` 或 `top_k` 为0时返回错误
+    pub fn from_py(
+        callback: pyo3::Py<pyo3::PyAny>,
+        snapshot_every: Option<u32>,
+        top_k: usize,
+    ) -> Result<Self, TokenizerError> {
+        let observer = Self::new(callback);
+        let Some(every) = snapshot_every else {
+            return Ok(observer);
+        };
+        if every == 0 {
+            return Err(input_validation_error("snapshot_every必须大于0"));
+        }
+        if top_k == 0 {
+            return Err(input_validation_error("top_k必须大于0"));
+        }
+        Ok(observer.with_snapshot(SnapshotRequest { every, top_k }))
+    }
+}
+
+#[cfg(feature = "python")]
+impl TrainObserver for PyTrainObserver {
+    fn on_event(&self, event: &TrainEvent) {
+        use pyo3::types::{PyDict, PyDictMethods};
+
+        pyo3::Python::with_gil(|py| {
+            let dict = PyDict::new(py);
+            let result = (|| -> pyo3::PyResult<()> {
+                match event {
+                    TrainEvent::TrainStarted {
+                        unique_sequences,
+                        target_vocab_size,
+                        planned_merges,
+                    } => {
+                        dict.set_item("event", "train_started")?;
+                        dict.set_item("unique_sequences", unique_sequences)?;
+                        dict.set_item("target_vocab_size", target_vocab_size)?;
+                        dict.set_item("planned_merges", planned_merges)?;
+                    }
+                    TrainEvent::PairCountsDone { unique_pairs } => {
+                        dict.set_item("event", "pair_counts_done")?;
+                        dict.set_item("unique_pairs", unique_pairs)?;
+                    }
+                    TrainEvent::MergeApplied {
+                        rank,
+                        pair,
+                        new_id,
+                        count,
+                    } => {
+                        dict.set_item("event", "merge_applied")?;
+                        dict.set_item("rank", rank)?;
+                        dict.set_item("pair", pair)?;
+                        dict.set_item("new_id", new_id)?;
+                        dict.set_item("count", count)?;
+                    }
+                    TrainEvent::PairSnapshot {
+                        merges_done,
+                        candidates,
+                    } => {
+                        dict.set_item("event", "pair_snapshot")?;
+                        dict.set_item("merges_done", merges_done)?;
+                        let candidates = candidates
+                            .iter()
+                            .map(|candidate| {
+                                let item = PyDict::new(py);
+                                item.set_item("pair", candidate.pair)?;
+                                item.set_item("count", candidate.count)?;
+                                item.set_item("piece", &candidate.piece)?;
+                                Ok(item)
+                            })
+                            .collect::<pyo3::PyResult<Vec<_>>>()?;
+                        dict.set_item("candidates", candidates)?;
+                    }
+                    TrainEvent::Finished { stats } => {
+                        dict.set_item("event", "finished")?;
+                        dict.set_item("merges", stats.merges)?;
+                        dict.set_item("vocab_size", stats.vocab_size)?;
+                        dict.set_item("elapsed_secs", stats.elapsed.as_secs_f64())?;
+                        dict.set_item("stopped", stats.stopped)?;
+                    }
+                }
+                let returned = self.callback.call1(py, (dict,))?;
+                if let Ok(false) = returned.extract::<bool>(py) {
+                    self.stop.store(true, Ordering::Relaxed);
+                }
+                Ok(())
+            })();
+
+            if let Err(e) = result {
+                log::warn!("训练观察者回调失败: {}", e);
+            }
+        });
+    }
+
+    fn snapshot_request(&self) -> Option<SnapshotRequest> {
+        self.snapshot
+    }
+
+    fn should_stop(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+}
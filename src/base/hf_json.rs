@@ -0,0 +1,688 @@
+//! HuggingFace `tokenizers` 的 tokenizer.json 格式
+//!
+//! 导出的文件可以由 `tokenizers.Tokenizer.from_file` 或 `PreTrainedTokenizerFast(tokenizer_file=...)`
+//! 直接加载。导入时只接受本库能够等价实现的组件：按正则表达式切分的 `Split`、`ByteLevel`、
+//! `Whitespace`、逐个切分的 `Digits` 和 `Punctuation` 预分词器，以及NFC、NFKC、NFKD、小写规范化，
+//! 遇到其他组件返回错误，避免加载后编码结果悄悄不同。导出时同样只接受这几种组件。
+//! 解码器按 `ByteLevel`、`Metaspace`（或Llama风格把 `▁` 替换为空格的 `Replace`）、`WordPiece`
+//! 对应到 [`DecoderKind`]，其余解码器组件导入时忽略。
+//! 后处理器（如自动添加BOS的 `TemplateProcessing`）不参与编码，导入时忽略。
+
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::base::decoder::DecoderKind;
+use crate::base::normalizer::{Normalizer, NormalizerStep};
+use crate::base::pre_tokenizer::{PreTokenizerPipeline, PreTokenizerStep};
+use crate::base::special_tokens::SpecialTokens;
+use crate::base::tokenizer_base::{MergeMap, GPT2_PATTERN};
+use crate::error::{model_load_error, model_save_error, TokenizerError};
+
+/// 没有预分词器时把整段文本作为一个片段
+const WHOLE_TEXT_PATTERN: &str = r"[\s\S]+";
+
+/// tokenizer.json 中的模型部分
+#[derive(Debug, Clone, PartialEq)]
+pub enum HfModel {
+    /// BPE模型，合并规则按等级排列
+    Bpe {
+        /// 标记文本及其ID
+        vocab: Vec<(String, u32)>,
+        /// 合并规则两侧的标记文本，第 `i` 项的等级为 `i`
+        merges: Vec<(String, String)>,
+        /// 未知标记的文本
+        unk_token: Option<String>,
+        /// 词汇表之外的字符是否拆成 `<0xNN>` 字节标记
+        byte_fallback: bool,
+    },
+    /// WordPiece模型
+    WordPiece {
+        /// 标记文本及其ID
+        vocab: Vec<(String, u32)>,
+        /// 未知标记的文本
+        unk_token: String,
+        /// 词内后续片段的前缀
+        continuing_subword_prefix: String,
+    },
+    /// Unigram模型，第 `i` 个片段的ID为 `i`
+    Unigram {
+        /// 片段文本及其分数
+        vocab: Vec<(String, f64)>,
+        /// 未知标记的ID
+        unk_id: Option<u32>,
+        /// 无法覆盖的字符是否拆成 `<0xNN>` 字节标记
+        byte_fallback: bool,
+    },
+}
+
+/// tokenizer.json 中的附加标记
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HfAddedToken {
+    /// 标记ID
+    pub id: u32,
+    /// 标记文本
+    pub content: String,
+    /// 是否为特殊标记
+    pub special: bool,
+}
+
+/// 本库能够表示的 tokenizer.json 内容
+#[derive(Debug, Clone, PartialEq)]
+pub struct HfTokenizerJson {
+    /// 模型
+    pub model: HfModel,
+    /// 附加标记，编码时整体匹配
+    pub added_tokens: Vec<HfAddedToken>,
+    /// 预分词正则表达式，由流水线中的 [`PreTokenizerStep::Regex`] 步骤使用
+    pub pattern: String,
+    /// 预分词流水线
+    pub pre_tokenizer: PreTokenizerPipeline,
+    /// 切分后是否经过 `ByteLevel` 字节到字符的映射
+    pub byte_level: bool,
+    /// 预分词之前的规范化流水线
+    pub normalizer: Normalizer,
+    /// 解码器，使用 `ByteLevel` 映射时总是按字节级解码
+    pub decoder: DecoderKind,
+}
+
+impl HfTokenizerJson {
+    /// 由特殊标记注册表生成附加标记，按ID升序
+    #[must_use]
+    pub fn added_tokens_from(special_tokens: &SpecialTokens) -> Vec<HfAddedToken> {
+        let mut added: Vec<HfAddedToken> = special_tokens
+            .iter()
+            .map(|(id, token)| HfAddedToken {
+                id,
+                content: token.to_string(),
+                special: true,
+            })
+            .collect();
+        added.sort_unstable_by_key(|token| token.id);
+        added
+    }
+
+    /// 附加标记组成的特殊标记注册表
+    ///
+    /// 本库不区分普通附加标记和特殊标记，两者都整体匹配
+    ///
+    /// # Errors
+    ///
+    /// 当附加标记为空、包含换行符或互相冲突时返回错误
+    pub fn special_tokens(&self) -> Result<SpecialTokens, TokenizerError> {
+        let mut special_tokens = SpecialTokens::new();
+        for token in &self.added_tokens {
+            special_tokens.insert(&token.content, token.id)?;
+        }
+        Ok(special_tokens)
+    }
+
+    /// 写出 tokenizer.json 文本
+    ///
+    /// # Errors
+    ///
+    /// 当序列化失败时返回错误
+    pub fn to_json_string(&self) -> Result<String, TokenizerError> {
+        let split = |pattern: &str| {
+            json!({
+                "type": "Split",
+                "pattern": { "Regex": pattern },
+                "behavior": "Isolated",
+                "invert": false,
+            })
+        };
+        let mut stages = self
+            .pre_tokenizer
+            .steps()
+            .iter()
+            .map(|step| match step {
+                PreTokenizerStep::Regex => Ok(split(&self.pattern)),
+                PreTokenizerStep::ByteLevel => Ok(split(GPT2_PATTERN)),
+                PreTokenizerStep::Whitespace => Ok(json!({ "type": "Whitespace" })),
+                PreTokenizerStep::Digits => {
+                    Ok(json!({ "type": "Digits", "individual_digits": true }))
+                }
+                PreTokenizerStep::Punctuation => {
+                    Ok(json!({ "type": "Punctuation", "behavior": "Isolated" }))
+                }
+                PreTokenizerStep::Metaspace => Err(model_save_error(
+                    "tokenizer.json不支持导出metaspace预分词步骤",
+                )),
+            })
+            .collect::<Result<Vec<_>, TokenizerError>>()?;
+        if self.byte_level {
+            stages.push(json!({
+                "type": "ByteLevel",
+                "add_prefix_space": false,
+                "trim_offsets": true,
+                "use_regex": false,
+            }));
+        }
+        let pre_tokenizer = match stages.len() {
+            0 => Value::Null,
+            1 => stages.remove(0),
+            _ => json!({ "type": "Sequence", "pretokenizers": stages }),
+        };
+
+        let byte_fallback = match &self.model {
+            HfModel::Bpe { byte_fallback, .. } | HfModel::Unigram { byte_fallback, .. } => {
+                *byte_fallback
+            }
+            HfModel::WordPiece { .. } => false,
+        };
+        let decoder = if self.byte_level || self.decoder == DecoderKind::ByteLevel {
+            json!({
+                "type": "ByteLevel",
+                "add_prefix_space": true,
+                "trim_offsets": true,
+                "use_regex": true,
+            })
+        } else {
+            let decoder = match self.decoder {
+                DecoderKind::Metaspace => json!({
+                    "type": "Metaspace",
+                    "replacement": "▁",
+                    "prepend_scheme": "always",
+                    "split": true,
+                }),
+                DecoderKind::WordPiece => {
+                    let prefix = match &self.model {
+                        HfModel::WordPiece {
+                            continuing_subword_prefix,
+                            ..
+                        } => continuing_subword_prefix.as_str(),
+                        _ => "##",
+                    };
+                    json!({ "type": "WordPiece", "prefix": prefix, "cleanup": true })
+                }
+                DecoderKind::Fuse | DecoderKind::ByteLevel => json!({ "type": "Fuse" }),
+            };
+            if byte_fallback {
+                json!({
+                    "type": "Sequence",
+                    "decoders": [{ "type": "ByteFallback" }, decoder],
+                })
+            } else {
+                decoder
+            }
+        };
+
+        let added_tokens: Vec<Value> = self
+            .added_tokens
+            .iter()
+            .map(|token| {
+                json!({
+                    "id": token.id,
+                    "content": token.content,
+                    "single_word": false,
+                    "lstrip": false,
+                    "rstrip": false,
+                    "normalized": false,
+                    "special": token.special,
+                })
+            })
+            .collect();
+
+        let root = json!({
+            "version": "1.0",
+            "truncation": null,
+            "padding": null,
+            "added_tokens": added_tokens,
+            "normalizer": normalizer_json(&self.normalizer)?,
+            "pre_tokenizer": pre_tokenizer,
+            "post_processor": null,
+            "decoder": decoder,
+            "model": self.model_json(),
+        });
+        serde_json::to_string_pretty(&root)
+            .map_err(|e| model_save_error(format!("序列化tokenizer.json失败: {}", e)))
+    }
+
+    fn model_json(&self) -> Value {
+        let vocab_map = |vocab: &[(String, u32)]| -> Map<String, Value> {
+            vocab
+                .iter()
+                .map(|(token, id)| (token.clone(), json!(id)))
+                .collect()
+        };
+        match &self.model {
+            HfModel::Bpe {
+                vocab,
+                merges,
+                unk_token,
+                byte_fallback,
+            } => json!({
+                "type": "BPE",
+                "dropout": null,
+                "unk_token": unk_token,
+                "continuing_subword_prefix": null,
+                "end_of_word_suffix": null,
+                "fuse_unk": false,
+                "byte_fallback": byte_fallback,
+                "ignore_merges": false,
+                "vocab": vocab_map(vocab),
+                "merges": merges
+                    .iter()
+                    .map(|(a, b)| json!([a, b]))
+                    .collect::<Vec<_>>(),
+            }),
+            HfModel::WordPiece {
+                vocab,
+                unk_token,
+                continuing_subword_prefix,
+            } => json!({
+                "type": "WordPiece",
+                "unk_token": unk_token,
+                "continuing_subword_prefix": continuing_subword_prefix,
+                "max_input_chars_per_word": u32::MAX,
+                "vocab": vocab_map(vocab),
+            }),
+            HfModel::Unigram {
+                vocab,
+                unk_id,
+                byte_fallback,
+            } => json!({
+                "type": "Unigram",
+                "unk_id": unk_id,
+                "vocab": vocab
+                    .iter()
+                    .map(|(piece, score)| json!([piece, score]))
+                    .collect::<Vec<_>>(),
+                "byte_fallback": byte_fallback,
+            }),
+        }
+    }
+
+    /// 解析 tokenizer.json 文本
+    ///
+    /// # Errors
+    ///
+    /// 当JSON无效、缺少必需字段，或包含本库无法等价实现的组件时返回错误
+    pub fn parse(text: &str) -> Result<Self, TokenizerError> {
+        let root: Value = serde_json::from_str(text)
+            .map_err(|e| model_load_error(format!("解析tokenizer.json失败: {}", e)))?;
+
+        let normalizer = match non_null(&root, "normalizer") {
+            None => Normalizer::default(),
+            Some(normalizer) => match component_type(normalizer)? {
+                "Sequence" => sequence(normalizer, "normalizers")?
+                    .iter()
+                    .map(parse_normalizer_step)
+                    .collect::<Result<Vec<_>, TokenizerError>>()?
+                    .into(),
+                _ => Normalizer::default().with(parse_normalizer_step(normalizer)?),
+            },
+        };
+
+        let (pattern, byte_level, pre_tokenizer) = match non_null(&root, "pre_tokenizer") {
+            None => (None, false, PreTokenizerPipeline::default()),
+            Some(pre_tokenizer) => parse_pre_tokenizer(pre_tokenizer)?,
+        };
+
+        let decoder = match non_null(&root, "decoder") {
+            None => DecoderKind::default(),
+            // 字节级模型的标记本身就是原始字节，不需要再按ByteLevel还原
+            Some(_) if byte_level => DecoderKind::default(),
+            Some(decoder) => parse_decoder(decoder)?,
+        };
+
+        let added_tokens = match non_null(&root, "added_tokens") {
+            None => Vec::new(),
+            Some(tokens) => tokens
+                .as_array()
+                .ok_or_else(|| model_load_error("added_tokens应为数组"))?
+                .iter()
+                .map(|token| {
+                    Ok(HfAddedToken {
+                        id: as_u32(token.get("id"), "附加标记的id")?,
+                        content: as_str(token.get("content"), "附加标记的content")?.to_string(),
+                        special: token
+                            .get("special")
+                            .and_then(Value::as_bool)
+                            .unwrap_or(false),
+                    })
+                })
+                .collect::<Result<Vec<_>, TokenizerError>>()?,
+        };
+
+        let model = parse_model(
+            root.get("model")
+                .ok_or_else(|| model_load_error("tokenizer.json缺少model"))?,
+        )?;
+
+        Ok(Self {
+            model,
+            added_tokens,
+            pattern: pattern.unwrap_or_else(|| WHOLE_TEXT_PATTERN.to_string()),
+            byte_level,
+            pre_tokenizer,
+            normalizer,
+            decoder,
+        })
+    }
+
+    /// 写入 tokenizer.json 文件
+    ///
+    /// # Errors
+    ///
+    /// 当序列化或写入失败时返回错误
+    pub fn save(&self, path: &str) -> Result<(), TokenizerError> {
+        std::fs::write(path, self.to_json_string()?)
+            .map_err(|e| model_save_error(format!("写入文件 {} 失败: {}", path, e)))
+    }
+
+    /// 读取 tokenizer.json 文件
+    ///
+    /// # Errors
+    ///
+    /// 当读取或解析失败时返回错误
+    pub fn load(path: &str) -> Result<Self, TokenizerError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| model_load_error(format!("读取文件 {} 失败: {}", path, e)))?;
+        Self::parse(&text)
+    }
+}
+
+/// 把按等级排列的合并规则转换为ID形式，结果保持原有的等级顺序
+///
+/// # Errors
+///
+/// 当合并规则引用的标记或合并结果不在词汇表中时返回错误
+pub fn merge_ids(
+    vocab: &[(String, u32)],
+    merges: &[(String, String)],
+) -> Result<MergeMap, TokenizerError> {
+    let ids: HashMap<&str, u32> = vocab
+        .iter()
+        .map(|(token, id)| (token.as_str(), *id))
+        .collect();
+    let lookup = |token: &str| {
+        ids.get(token)
+            .copied()
+            .ok_or_else(|| model_load_error(format!("合并规则引用的标记 {:?} 不在词汇表中", token)))
+    };
+
+    let mut merge_map = MergeMap::with_capacity(merges.len());
+    for (a, b) in merges {
+        let new_id = lookup(&format!("{}{}", a, b))?;
+        merge_map.insert((lookup(a)?, lookup(b)?), new_id);
+    }
+    Ok(merge_map)
+}
+
+/// 规范化流水线对应的 `normalizer` 组件，没有步骤时为 `null`
+fn normalizer_json(normalizer: &Normalizer) -> Result<Value, TokenizerError> {
+    let steps = normalizer
+        .steps()
+        .iter()
+        .map(|&step| {
+            let name = match step {
+                NormalizerStep::Nfc => "NFC",
+                NormalizerStep::Nfkc => "NFKC",
+                NormalizerStep::Nfkd => "NFKD",
+                NormalizerStep::Lowercase => "Lowercase",
+                other => {
+                    return Err(model_save_error(format!(
+                        "规范化步骤 {} 无法等价导出为tokenizer.json",
+                        other.name()
+                    )))
+                }
+            };
+            Ok(json!({ "type": name }))
+        })
+        .collect::<Result<Vec<_>, TokenizerError>>()?;
+    Ok(match <[Value; 1]>::try_from(steps) {
+        Ok([single]) => single,
+        Err(steps) if steps.is_empty() => Value::Null,
+        Err(steps) => json!({ "type": "Sequence", "normalizers": steps }),
+    })
+}
+
+/// 解析单个规范化器，`Sequence` 不能嵌套
+fn parse_normalizer_step(value: &Value) -> Result<NormalizerStep, TokenizerError> {
+    match component_type(value)? {
+        "NFC" => Ok(NormalizerStep::Nfc),
+        "NFKC" => Ok(NormalizerStep::Nfkc),
+        "NFKD" => Ok(NormalizerStep::Nfkd),
+        "Lowercase" => Ok(NormalizerStep::Lowercase),
+        other => Err(model_load_error(format!("不支持的规范化器: {}", other))),
+    }
+}
+
+fn non_null<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    value.get(key).filter(|value| !value.is_null())
+}
+
+fn component_type(value: &Value) -> Result<&str, TokenizerError> {
+    value
+        .get("type")
+        .and_then(Value::as_str)
+        .ok_or_else(|| model_load_error("组件缺少type字段"))
+}
+
+fn sequence<'a>(value: &'a Value, key: &str) -> Result<&'a [Value], TokenizerError> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .ok_or_else(|| model_load_error(format!("Sequence缺少{}数组", key)))
+}
+
+fn as_str<'a>(value: Option<&'a Value>, what: &str) -> Result<&'a str, TokenizerError> {
+    value
+        .and_then(Value::as_str)
+        .ok_or_else(|| model_load_error(format!("{}应为字符串", what)))
+}
+
+fn as_u32(value: Option<&Value>, what: &str) -> Result<u32, TokenizerError> {
+    value
+        .and_then(Value::as_u64)
+        .and_then(|id| u32::try_from(id).ok())
+        .ok_or_else(|| model_load_error(format!("{}应为u32整数", what)))
+}
+
+/// 解析预分词器，返回切分用的正则表达式、是否使用 `ByteLevel` 映射和预分词流水线
+///
+/// 按正则表达式切分的阶段对应流水线中的 [`PreTokenizerStep::Regex`] 步骤
+fn parse_pre_tokenizer(
+    value: &Value,
+) -> Result<(Option<String>, bool, PreTokenizerPipeline), TokenizerError> {
+    let stages = match component_type(value)? {
+        "Sequence" => sequence(value, "pretokenizers")?,
+        _ => std::slice::from_ref(value),
+    };
+
+    let mut pattern = None;
+    let mut byte_level = false;
+    let mut steps = Vec::new();
+    for stage in stages {
+        let stage_pattern = match component_type(stage)? {
+            "Split" => {
+                if stage.get("behavior").and_then(Value::as_str) != Some("Isolated")
+                    || stage.get("invert").and_then(Value::as_bool) == Some(true)
+                {
+                    return Err(model_load_error(
+                        "只支持behavior为Isolated且不反转的Split预分词器",
+                    ));
+                }
+                let regex = stage
+                    .get("pattern")
+                    .and_then(|pattern| pattern.get("Regex"))
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| model_load_error("只支持正则表达式形式的Split预分词器"))?;
+                Some(regex.to_string())
+            }
+            "ByteLevel" => {
+                if stage.get("add_prefix_space").and_then(Value::as_bool) == Some(true) {
+                    return Err(model_load_error(
+                        "不支持add_prefix_space为true的ByteLevel预分词器",
+                    ));
+                }
+                byte_level = true;
+                // use_regex 默认为true，此时按GPT-2的规则切分
+                (stage.get("use_regex").and_then(Value::as_bool) != Some(false))
+                    .then(|| GPT2_PATTERN.to_string())
+            }
+            "Whitespace" => {
+                steps.push(PreTokenizerStep::Whitespace);
+                None
+            }
+            "Digits" => {
+                if stage.get("individual_digits").and_then(Value::as_bool) != Some(true) {
+                    return Err(model_load_error(
+                        "只支持individual_digits为true的Digits预分词器",
+                    ));
+                }
+                steps.push(PreTokenizerStep::Digits);
+                None
+            }
+            "Punctuation" => {
+                if stage
+                    .get("behavior")
+                    .and_then(Value::as_str)
+                    .is_some_and(|behavior| behavior != "Isolated")
+                {
+                    return Err(model_load_error(
+                        "只支持behavior为Isolated的Punctuation预分词器",
+                    ));
+                }
+                steps.push(PreTokenizerStep::Punctuation);
+                None
+            }
+            other => return Err(model_load_error(format!("不支持的预分词器: {}", other))),
+        };
+        if let Some(stage_pattern) = stage_pattern {
+            if pattern.replace(stage_pattern).is_some() {
+                return Err(model_load_error("预分词器中有多个切分规则"));
+            }
+            steps.push(PreTokenizerStep::Regex);
+        }
+    }
+    Ok((pattern, byte_level, steps.into()))
+}
+
+/// 解析解码器，识别不出的组件（如 `ByteFallback`、`Fuse`、`Strip`）按直接拼接处理
+fn parse_decoder(value: &Value) -> Result<DecoderKind, TokenizerError> {
+    let stages = match component_type(value)? {
+        "Sequence" => sequence(value, "decoders")?,
+        _ => std::slice::from_ref(value),
+    };
+
+    let mut decoder = DecoderKind::default();
+    for stage in stages {
+        let kind = match component_type(stage)? {
+            "ByteLevel" => DecoderKind::ByteLevel,
+            "Metaspace" => DecoderKind::Metaspace,
+            "WordPiece" => DecoderKind::WordPiece,
+            "Replace"
+                if stage
+                    .get("pattern")
+                    .and_then(|pattern| pattern.get("String"))
+                    .and_then(Value::as_str)
+                    == Some("▁")
+                    && stage.get("content").and_then(Value::as_str) == Some(" ") =>
+            {
+                DecoderKind::Metaspace
+            }
+            _ => continue,
+        };
+        if decoder != DecoderKind::default() && decoder != kind {
+            return Err(model_load_error("解码器中有多个互相冲突的组件"));
+        }
+        decoder = kind;
+    }
+    Ok(decoder)
+}
+
+fn parse_vocab_map(value: Option<&Value>) -> Result<Vec<(String, u32)>, TokenizerError> {
+    let map = value
+        .and_then(Value::as_object)
+        .ok_or_else(|| model_load_error("model.vocab应为对象"))?;
+    let mut vocab = map
+        .iter()
+        .map(|(token, id)| Ok((token.clone(), as_u32(Some(id), "词汇表ID")?)))
+        .collect::<Result<Vec<_>, TokenizerError>>()?;
+    vocab.sort_unstable_by_key(|&(_, id)| id);
+    Ok(vocab)
+}
+
+fn parse_model(model: &Value) -> Result<HfModel, TokenizerError> {
+    match component_type(model)? {
+        "BPE" => {
+            for key in ["continuing_subword_prefix", "end_of_word_suffix"] {
+                if non_null(model, key)
+                    .and_then(Value::as_str)
+                    .is_some_and(|affix| !affix.is_empty())
+                {
+                    return Err(model_load_error(format!("不支持设置了{}的BPE模型", key)));
+                }
+            }
+            let merges = model
+                .get("merges")
+                .and_then(Value::as_array)
+                .ok_or_else(|| model_load_error("model.merges应为数组"))?
+                .iter()
+                .map(|merge| match merge {
+                    // 旧版格式 "a b"
+                    Value::String(pair) => pair
+                        .split_once(' ')
+                        .map(|(a, b)| (a.to_string(), b.to_string()))
+                        .ok_or_else(|| model_load_error(format!("无效的合并规则: {:?}", pair))),
+                    Value::Array(pair) => match &pair[..] {
+                        [Value::String(a), Value::String(b)] => Ok((a.clone(), b.clone())),
+                        _ => Err(model_load_error(format!("无效的合并规则: {}", merge))),
+                    },
+                    _ => Err(model_load_error(format!("无效的合并规则: {}", merge))),
+                })
+                .collect::<Result<Vec<_>, TokenizerError>>()?;
+            Ok(HfModel::Bpe {
+                vocab: parse_vocab_map(model.get("vocab"))?,
+                merges,
+                unk_token: non_null(model, "unk_token")
+                    .map(|token| as_str(Some(token), "model.unk_token").map(str::to_string))
+                    .transpose()?,
+                byte_fallback: model
+                    .get("byte_fallback")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            })
+        }
+        "WordPiece" => Ok(HfModel::WordPiece {
+            vocab: parse_vocab_map(model.get("vocab"))?,
+            unk_token: as_str(model.get("unk_token"), "model.unk_token")?.to_string(),
+            continuing_subword_prefix: non_null(model, "continuing_subword_prefix")
+                .map_or(Ok("##"), |prefix| {
+                    as_str(Some(prefix), "model.continuing_subword_prefix")
+                })?
+                .to_string(),
+        }),
+        "Unigram" => {
+            let vocab = model
+                .get("vocab")
+                .and_then(Value::as_array)
+                .ok_or_else(|| model_load_error("model.vocab应为数组"))?
+                .iter()
+                .map(|entry| match entry.as_array().map(Vec::as_slice) {
+                    Some([Value::String(piece), score]) => score
+                        .as_f64()
+                        .map(|score| (piece.clone(), score))
+                        .ok_or_else(|| {
+                            model_load_error(format!("片段 {:?} 的分数不是数字", piece))
+                        }),
+                    _ => Err(model_load_error(format!("无效的Unigram片段: {}", entry))),
+                })
+                .collect::<Result<Vec<_>, TokenizerError>>()?;
+            Ok(HfModel::Unigram {
+                vocab,
+                unk_id: non_null(model, "unk_id")
+                    .map(|id| as_u32(Some(id), "model.unk_id"))
+                    .transpose()?,
+                byte_fallback: model
+                    .get("byte_fallback")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            })
+        }
+        other => Err(model_load_error(format!("不支持的模型类型: {}", other))),
+    }
+}
@@ -0,0 +1,395 @@
+//! 模型完整性检查
+//!
+//! 手工编辑或截断的模型文件可能缺少合并规则引用的标记，或让两个ID对应同一个标记。
+//! 这些问题在加载时不会立即暴露，而是在编码时表现为难以定位的 "ID不存在" 错误。
+//! [`IntegrityReport`] 在加载后交叉检查词汇表、合并规则和特殊标记注册表，一次列出全部问题。
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::Hash;
+
+use serde::Serialize;
+
+use crate::base::special_tokens::SpecialTokens;
+use crate::base::tokenizer_base::MergeMap;
+use crate::base::vocab_manager::VocabManager;
+use crate::error::{vocab_error, TokenizerError};
+
+/// 报错时最多列出的问题数
+const MAX_LISTED_ISSUES: usize = 10;
+
+/// 完整性问题，标记文本按UTF-8解码显示，无效字节显示为替换字符
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IntegrityIssue {
+    /// 模型文件中同一个ID出现多次，只有最后一个生效
+    DuplicateId {
+        /// 标记ID
+        id: u32,
+        /// 依次出现的标记
+        tokens: Vec<String>,
+    },
+    /// 同一个标记对应多个ID，只有一个能被编码出来
+    DuplicateToken {
+        /// 标记
+        token: String,
+        /// 对应的ID，升序
+        ids: Vec<u32>,
+    },
+    /// 正向映射（ID到标记）与反向映射（标记到ID）不一致
+    ReverseMismatch {
+        /// 标记
+        token: String,
+        /// 正向映射中对应该标记的ID
+        id: Option<u32>,
+        /// 反向映射中该标记的ID
+        reverse_id: Option<u32>,
+    },
+    /// 合并规则引用的标记不在词汇表中
+    MissingMergeToken {
+        /// 合并的左侧标记ID
+        left: u32,
+        /// 合并的右侧标记ID
+        right: u32,
+        /// 合并结果的ID
+        new_id: u32,
+        /// 不在词汇表中的ID
+        missing: u32,
+    },
+    /// 合并结果的标记不等于两侧标记拼接
+    MergeMismatch {
+        /// 合并的左侧标记ID
+        left: u32,
+        /// 合并的右侧标记ID
+        right: u32,
+        /// 合并结果的ID
+        new_id: u32,
+        /// 两侧标记拼接的结果
+        expected: String,
+        /// 词汇表中合并结果的标记
+        actual: String,
+    },
+    /// 特殊标记注册表与词汇表不一致
+    SpecialTokenMismatch {
+        /// 特殊标记ID
+        id: u32,
+        /// 注册表中的标记
+        token: String,
+        /// 词汇表中该ID的标记，不存在时为 `None`
+        actual: Option<String>,
+    },
+}
+
+impl fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateId { id, tokens } => write!(f, "ID {} 重复出现: {:?}", id, tokens),
+            Self::DuplicateToken { token, ids } => {
+                write!(f, "标记 {:?} 对应多个ID: {:?}", token, ids)
+            }
+            Self::ReverseMismatch {
+                token,
+                id,
+                reverse_id,
+            } => write!(
+                f,
+                "标记 {:?} 的正向映射ID为 {:?}，反向映射ID为 {:?}",
+                token, id, reverse_id
+            ),
+            Self::MissingMergeToken {
+                left,
+                right,
+                new_id,
+                missing,
+            } => write!(
+                f,
+                "合并 ({}, {}) -> {} 引用的ID {} 不在词汇表中",
+                left, right, new_id, missing
+            ),
+            Self::MergeMismatch {
+                left,
+                right,
+                new_id,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "合并 ({}, {}) -> {} 的结果应为 {:?}，词汇表中为 {:?}",
+                left, right, new_id, expected, actual
+            ),
+            Self::SpecialTokenMismatch { id, token, actual } => write!(
+                f,
+                "特殊标记 {:?} 的ID {} 在词汇表中为 {:?}",
+                token, id, actual
+            ),
+        }
+    }
+}
+
+/// 完整性检查报告
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IntegrityReport {
+    /// 发现的问题，按检查项分组，组内按ID升序
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(f, "模型完整性检查通过");
+        }
+        write!(f, "模型完整性检查发现 {} 个问题", self.issues.len())?;
+        for issue in self.issues.iter().take(MAX_LISTED_ISSUES) {
+            write!(f, "\n  - {}", issue)?;
+        }
+        if self.issues.len() > MAX_LISTED_ISSUES {
+            write!(
+                f,
+                "\n  ……另有 {} 个问题",
+                self.issues.len() - MAX_LISTED_ISSUES
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl IntegrityReport {
+    /// 是否没有发现问题
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// 没有问题时返回 `Ok`，否则返回列出问题的错误信息
+    ///
+    /// # Errors
+    ///
+    /// 当发现问题时返回错误
+    pub fn into_result(self) -> Result<(), TokenizerError> {
+        if self.is_ok() {
+            Ok(())
+        } else {
+            Err(vocab_error(self.to_string()))
+        }
+    }
+
+    /// 追加 `other` 中尚未记录的问题
+    pub fn extend(&mut self, other: Self) {
+        for issue in other.issues {
+            if !self.issues.contains(&issue) {
+                self.issues.push(issue);
+            }
+        }
+    }
+
+    /// 检查模型文件中的词汇表条目是否有重复的ID
+    ///
+    /// 加载时同一ID的后一个条目会覆盖前一个，重复的标记会被词汇表去重，因此需要在插入前检查
+    pub fn check_entries<V: AsRef<[u8]>>(&mut self, entries: &[(u32, V)]) {
+        let mut by_id: BTreeMap<u32, Vec<&V>> = BTreeMap::new();
+        for (id, token) in entries {
+            by_id.entry(*id).or_default().push(token);
+        }
+        self.issues.extend(
+            by_id
+                .into_iter()
+                .filter(|(_, tokens)| tokens.len() > 1)
+                .map(|(id, tokens)| IntegrityIssue::DuplicateId {
+                    id,
+                    tokens: tokens.into_iter().map(|t| render(t.as_ref())).collect(),
+                }),
+        );
+
+        let mut by_token: HashMap<&[u8], Vec<u32>> = HashMap::new();
+        for (id, token) in entries {
+            by_token.entry(token.as_ref()).or_default().push(*id);
+        }
+        self.push_duplicate_tokens(by_token);
+    }
+
+    /// 检查词汇表的正向和反向映射是否一致
+    pub fn check_vocab<V>(&mut self, vocab: &VocabManager<u32, V>)
+    where
+        V: AsRef<[u8]> + Eq + Hash + Clone + fmt::Debug,
+    {
+        let mut by_token: HashMap<&[u8], Vec<u32>> = HashMap::new();
+        for (&id, token) in vocab.iter() {
+            by_token.entry(token.as_ref()).or_default().push(id);
+        }
+        self.push_duplicate_tokens(by_token);
+
+        let mut mismatches = Vec::new();
+        for (&id, token) in vocab.iter() {
+            let reverse_id = vocab.get_by_value(token).copied();
+            if reverse_id.is_none() {
+                mismatches.push((id, token.as_ref(), Some(id), None));
+            }
+        }
+        for (token, &reverse_id) in vocab.value_map() {
+            if vocab.get_by_id(&reverse_id) != Some(token) {
+                let id = vocab.iter().find(|(_, t)| *t == token).map(|(&id, _)| id);
+                mismatches.push((reverse_id, token.as_ref(), id, Some(reverse_id)));
+            }
+        }
+        mismatches.sort_unstable();
+        self.issues
+            .extend(mismatches.into_iter().map(|(_, token, id, reverse_id)| {
+                IntegrityIssue::ReverseMismatch {
+                    token: render(token),
+                    id,
+                    reverse_id,
+                }
+            }));
+    }
+
+    /// 按等级顺序检查每条合并规则的两侧和结果都在词汇表中，且结果等于两侧拼接
+    ///
+    /// 不同的合并路径可能得到同一个标记，多条规则产生同一个ID是合法的
+    pub fn check_merges<V>(&mut self, vocab: &VocabManager<u32, V>, merges: &MergeMap)
+    where
+        V: AsRef<[u8]> + Eq + Hash + Clone + fmt::Debug,
+    {
+        for (&(left, right), &new_id) in merges {
+            let lookup = |id: u32| vocab.get_by_id(&id).map(AsRef::as_ref);
+            let (Some(left_bytes), Some(right_bytes), Some(actual)) =
+                (lookup(left), lookup(right), lookup(new_id))
+            else {
+                for missing in [left, right, new_id] {
+                    if lookup(missing).is_none() {
+                        self.issues.push(IntegrityIssue::MissingMergeToken {
+                            left,
+                            right,
+                            new_id,
+                            missing,
+                        });
+                    }
+                }
+                continue;
+            };
+            let expected = [left_bytes, right_bytes].concat();
+            if expected != actual {
+                self.issues.push(IntegrityIssue::MergeMismatch {
+                    left,
+                    right,
+                    new_id,
+                    expected: render(&expected),
+                    actual: render(actual),
+                });
+            }
+        }
+    }
+
+    /// 检查每个特殊标记都以注册的ID存在于词汇表中
+    pub fn check_special_tokens<V>(&mut self, special: &SpecialTokens, vocab: &VocabManager<u32, V>)
+    where
+        V: AsRef<[u8]> + Eq + Hash + Clone + fmt::Debug,
+    {
+        for (id, token) in special.iter() {
+            let actual = vocab.get_by_id(&id).map(AsRef::as_ref);
+            if actual != Some(token.as_bytes()) {
+                self.issues.push(IntegrityIssue::SpecialTokenMismatch {
+                    id,
+                    token: token.to_string(),
+                    actual: actual.map(render),
+                });
+            }
+        }
+    }
+
+    fn push_duplicate_tokens(&mut self, by_token: HashMap<&[u8], Vec<u32>>) {
+        let mut duplicates: Vec<(Vec<u32>, &[u8])> = by_token
+            .into_iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|(token, mut ids)| {
+                ids.sort_unstable();
+                (ids, token)
+            })
+            .collect();
+        duplicates.sort_unstable();
+        self.issues
+            .extend(
+                duplicates
+                    .into_iter()
+                    .map(|(ids, token)| IntegrityIssue::DuplicateToken {
+                        token: render(token),
+                        ids,
+                    }),
+            );
+    }
+}
+
+fn render(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+#[cfg(feature = "python")]
+impl IntegrityReport {
+    /// 转换为Python字典列表，每项包含 `kind`、`message` 和该类问题的字段
+    pub(crate) fn to_py_list<'py>(
+        &self,
+        py: pyo3::Python<'py>,
+    ) -> pyo3::PyResult<Vec<pyo3::Bound<'py, pyo3::types::PyDict>>> {
+        use pyo3::types::{PyDict, PyDictMethods};
+
+        self.issues
+            .iter()
+            .map(|issue| {
+                let dict = PyDict::new(py);
+                dict.set_item("message", issue.to_string())?;
+                match issue {
+                    IntegrityIssue::DuplicateId { id, tokens } => {
+                        dict.set_item("kind", "duplicate_id")?;
+                        dict.set_item("id", id)?;
+                        dict.set_item("tokens", tokens.clone())?;
+                    }
+                    IntegrityIssue::DuplicateToken { token, ids } => {
+                        dict.set_item("kind", "duplicate_token")?;
+                        dict.set_item("token", token)?;
+                        dict.set_item("ids", ids.clone())?;
+                    }
+                    IntegrityIssue::ReverseMismatch {
+                        token,
+                        id,
+                        reverse_id,
+                    } => {
+                        dict.set_item("kind", "reverse_mismatch")?;
+                        dict.set_item("token", token)?;
+                        dict.set_item("id", id)?;
+                        dict.set_item("reverse_id", reverse_id)?;
+                    }
+                    IntegrityIssue::MissingMergeToken {
+                        left,
+                        right,
+                        new_id,
+                        missing,
+                    } => {
+                        dict.set_item("kind", "missing_merge_token")?;
+                        dict.set_item("pair", (left, right))?;
+                        dict.set_item("new_id", new_id)?;
+                        dict.set_item("missing", missing)?;
+                    }
+                    IntegrityIssue::MergeMismatch {
+                        left,
+                        right,
+                        new_id,
+                        expected,
+                        actual,
+                    } => {
+                        dict.set_item("kind", "merge_mismatch")?;
+                        dict.set_item("pair", (left, right))?;
+                        dict.set_item("new_id", new_id)?;
+                        dict.set_item("expected", expected)?;
+                        dict.set_item("actual", actual)?;
+                    }
+                    IntegrityIssue::SpecialTokenMismatch { id, token, actual } => {
+                        dict.set_item("kind", "special_token_mismatch")?;
+                        dict.set_item("id", id)?;
+                        dict.set_item("token", token)?;
+                        dict.set_item("actual", actual)?;
+                    }
+                }
+                Ok(dict)
+            })
+            .collect()
+    }
+}
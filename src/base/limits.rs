@@ -0,0 +1,117 @@
+//! 编码输入的长度限制
+//!
+//! 服务端直接编码外部输入时，超长的文本会长时间占用正则引擎和内存。[`InputLimits`] 保存在分词器的
+//! [`EncodeOptions`](crate::base::padding::EncodeOptions) 中，每次编码前检查输入字节数，
+//! 编码后检查标记数，超出时按 [`LimitPolicy`] 报错或截断。
+
+use crate::error::{input_validation_error, TokenizerError};
+
+/// 超出长度限制时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LimitPolicy {
+    /// 返回 [`TokenizerError::InputTooLarge`] 或 [`TokenizerError::TooManyTokens`]
+    #[default]
+    Error,
+    /// 输入截断到不超过限制的最后一个字符边界，标记序列只保留开头的部分
+    Truncate,
+}
+
+/// 编码输入的长度限制，默认不限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InputLimits {
+    /// 输入文本的最大字节数，在预分词之前检查
+    pub max_input_bytes: Option<usize>,
+    /// 编码结果的最大标记数
+    pub max_tokens: Option<usize>,
+    /// 超出限制时的处理方式
+    pub policy: LimitPolicy,
+}
+
+impl InputLimits {
+    /// 检查配置是否有效
+    ///
+    /// # Errors
+    ///
+    /// 当任一限制为0时返回错误
+    pub fn validate(&self) -> Result<(), TokenizerError> {
+        if self.max_input_bytes == Some(0) {
+            return Err(input_validation_error("max_input_bytes必须大于0"));
+        }
+        if self.max_tokens == Some(0) {
+            return Err(input_validation_error("max_tokens必须大于0"));
+        }
+        Ok(())
+    }
+
+    /// 是否没有任何限制
+    #[must_use]
+    pub fn is_unlimited(&self) -> bool {
+        self.max_input_bytes.is_none() && self.max_tokens.is_none()
+    }
+
+    /// 按字节数限制检查输入，截断时返回原文开头不超过限制的部分
+    ///
+    /// # Errors
+    ///
+    /// 当输入超出限制且处理方式为 [`LimitPolicy::Error`] 时返回错误
+    pub fn check_input<'a>(&self, text: &'a str) -> Result<&'a str, TokenizerError> {
+        let Some(limit) = self.max_input_bytes else {
+            return Ok(text);
+        };
+        if text.len() <= limit {
+            return Ok(text);
+        }
+        match self.policy {
+            LimitPolicy::Error => Err(TokenizerError::InputTooLarge {
+                bytes: text.len(),
+                limit,
+            }),
+            LimitPolicy::Truncate => {
+                let end = (0..=limit)
+                    .rev()
+                    .find(|&end| text.is_char_boundary(end))
+                    .unwrap_or(0);
+                Ok(&text[..end])
+            }
+        }
+    }
+
+    /// 按标记数限制检查编码结果，截断时只保留开头的部分
+    ///
+    /// # Errors
+    ///
+    /// 当标记数超出限制且处理方式为 [`LimitPolicy::Error`] 时返回错误
+    pub fn check_tokens(&self, ids: &mut Vec<u32>) -> Result<(), TokenizerError> {
+        let Some(limit) = self.max_tokens else {
+            return Ok(());
+        };
+        if ids.len() <= limit {
+            return Ok(());
+        }
+        match self.policy {
+            LimitPolicy::Error => Err(TokenizerError::TooManyTokens {
+                tokens: ids.len(),
+                limit,
+            }),
+            LimitPolicy::Truncate => {
+                ids.truncate(limit);
+                Ok(())
+            }
+        }
+    }
+
+    /// 依次检查输入和编码结果，用于包装一次编码
+    ///
+    /// # Errors
+    ///
+    /// 当超出限制且处理方式为 [`LimitPolicy::Error`] 时返回错误，或返回 `encode` 的错误
+    pub fn apply<F>(&self, text: &str, encode: F) -> Result<Vec<u32>, TokenizerError>
+    where
+        F: FnOnce(&str) -> Result<Vec<u32>, TokenizerError>,
+    {
+        let text = self.check_input(text)?;
+        let mut ids = encode(text)?;
+        self.check_tokens(&mut ids)?;
+        Ok(ids)
+    }
+}
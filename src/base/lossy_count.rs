@@ -0,0 +1,118 @@
+//! 有内存上限的近似计数
+//!
+//! 流式训练要在读完语料之后才能开始合并，期间唯一片段的计数全部留在内存中。
+//! 网页等噪声数据里的唯一片段数随语料长度近似线性增长，没有上限时可能耗尽内存。
+//! [`LossyCounts`] 在唯一片段数超过上限时按自适应阈值丢弃低频片段（lossy counting），
+//! 误差有明确的上界：
+//!
+//! - 设最终阈值为 `θ`（[`LossyCounts::threshold`]），每个保留片段的计数比真实次数少，但至多少 `θ`；
+//! - 真实次数大于 `θ` 的片段一定被保留。
+//!
+//! 每个片段额外记录插入时的阈值 `delta`，表示在此之前可能已被丢弃的次数上界。
+//! 丢弃时比较的是 `计数 + delta`，因此被丢弃的片段真实次数不超过当时的阈值。
+
+use std::hash::Hash;
+
+use ahash::AHashMap;
+
+use crate::error::{input_validation_error, TokenizerError};
+
+/// 有唯一键数量上限的近似计数表
+#[derive(Debug, Clone)]
+pub struct LossyCounts<K> {
+    /// 键 -> (计数, 插入时的阈值)
+    counts: AHashMap<K, (i32, i32)>,
+    /// 唯一键数量上限，为 `None` 时精确计数
+    max_keys: Option<usize>,
+    /// 当前阈值，即目前为止丢弃的最大 `计数 + delta`
+    threshold: i32,
+    /// 累计丢弃的键数
+    pruned: u64,
+}
+
+impl<K: Eq + Hash> LossyCounts<K> {
+    /// 创建计数表，`max_keys` 为 `None` 时不丢弃任何键
+    ///
+    /// # Errors
+    ///
+    /// 当 `max_keys` 为0时返回错误
+    pub fn new(max_keys: Option<usize>) -> Result<Self, TokenizerError> {
+        if max_keys == Some(0) {
+            return Err(input_validation_error("唯一片段数上限必须大于0"));
+        }
+        Ok(Self {
+            counts: AHashMap::new(),
+            max_keys,
+            threshold: 0,
+            pruned: 0,
+        })
+    }
+
+    /// 累加一批计数，超过上限时丢弃低频键
+    pub fn extend<I>(&mut self, counts: I)
+    where
+        I: IntoIterator<Item = (K, i32)>,
+    {
+        let threshold = self.threshold;
+        for (key, count) in counts {
+            self.counts.entry(key).or_insert((0, threshold)).0 += count;
+        }
+        self.prune();
+    }
+
+    /// 唯一键数超过上限时提高阈值，丢弃 `计数 + delta` 不超过阈值的键
+    ///
+    /// 丢弃后只保留上限的一半，避免之后每一批都重新丢弃
+    fn prune(&mut self) {
+        let Some(max_keys) = self.max_keys else {
+            return;
+        };
+        if self.counts.len() <= max_keys {
+            return;
+        }
+        let keep = (max_keys / 2).max(1);
+        let mut bounds: Vec<i32> = self
+            .counts
+            .values()
+            .map(|&(count, delta)| count + delta)
+            .collect();
+        let (_, &mut kth, _) = bounds.select_nth_unstable_by(keep, |a, b| b.cmp(a));
+        self.threshold = self.threshold.max(kth);
+        let threshold = self.threshold;
+        let before = self.counts.len();
+        self.counts
+            .retain(|_, &mut (count, delta)| count + delta > threshold);
+        self.pruned += (before - self.counts.len()) as u64;
+    }
+
+    /// 当前阈值：保留的计数至多比真实次数少这么多，真实次数更大的键一定被保留
+    #[must_use]
+    pub fn threshold(&self) -> i32 {
+        self.threshold
+    }
+
+    /// 累计丢弃的键数，同一个键多次被丢弃时重复计数
+    #[must_use]
+    pub fn pruned(&self) -> u64 {
+        self.pruned
+    }
+
+    /// 当前保留的唯一键数
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// 是否没有任何键
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// 保留的键及其计数，计数为真实次数的下界
+    pub fn into_counts(self) -> impl Iterator<Item = (K, i32)> {
+        self.counts
+            .into_iter()
+            .map(|(key, (count, _))| (key, count))
+    }
+}
@@ -2,6 +2,38 @@ use std::cmp::Ordering;
 use std::collections::HashSet;
 use std::hash::Hash;
 
+use ahash::AHashMap;
+
+use crate::base::word::Word;
+
+/// 训练时计数相同的配对之间的合并顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    /// 配对ID较小的优先
+    #[default]
+    PairId,
+    /// 两侧标记的内容按字典序较小的优先，与HuggingFace训练器的顺序一致，不受ID分配方式影响
+    Lexicographic,
+    /// 先出现的配对优先：初始配对按在语料中首次出现的位置，合并产生的配对按产生它的合并步骤
+    ///
+    /// 先按片段去重计数的流式训练按片段排序后训练，此时的顺序是片段的字典序而不是语料顺序
+    InsertionOrder,
+}
+
+/// 计数相同时比较的次级排序键，较小的优先
+///
+/// 同一个堆中的任务使用同一种键；键也相同时再按配对ID比较
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TieKey {
+    /// 不使用次级排序键
+    #[default]
+    None,
+    /// 配对两侧标记的内容
+    Bytes(Vec<u8>, Vec<u8>),
+    /// 配对首次出现的顺序
+    Order(u64),
+}
+
 /// 表示一个合并任务
 #[derive(Debug, Clone)]
 pub struct MergeJob<Id: Ord> {
@@ -11,11 +43,13 @@ pub struct MergeJob<Id: Ord> {
     pub count: u64,
     /// 需要处理此配对的词索引集合
     pub pos: HashSet<usize>,
+    /// 计数相同时的次级排序键
+    pub tie: TieKey,
 }
 
 impl<Id: PartialEq + Ord> PartialEq for MergeJob<Id> {
     fn eq(&self, other: &Self) -> bool {
-        self.count == other.count && self.pair == other.pair
+        self.count == other.count && self.tie == other.tie && self.pair == other.pair
     }
 }
 
@@ -29,13 +63,11 @@ impl<Id: PartialOrd + Ord> PartialOrd for MergeJob<Id> {
 
 impl<Id: Ord> Ord for MergeJob<Id> {
     fn cmp(&self, other: &Self) -> Ordering {
-        // 按计数最大堆；计数相同时按配对升序（确定性）
-        if self.count != other.count {
-            self.count.cmp(&other.count)
-        } else {
-            // 计数相同时按配对升序
-            other.pair.cmp(&self.pair)
-        }
+        // 按计数最大堆；计数相同时次级排序键较小的优先，再按配对升序（确定性）
+        self.count
+            .cmp(&other.count)
+            .then_with(|| other.tie.cmp(&self.tie))
+            .then_with(|| other.pair.cmp(&self.pair))
     }
 }
 
@@ -46,9 +78,17 @@ impl<Id: Clone + Hash + Eq + Ord> MergeJob<Id> {
             pair,
             count,
             pos: HashSet::new(),
+            tie: TieKey::None,
         }
     }
 
+    /// 设置计数相同时的次级排序键
+    #[must_use]
+    pub fn with_tie(mut self, tie: TieKey) -> Self {
+        self.tie = tie;
+        self
+    }
+
     /// 添加词索引
     pub fn add_position(&mut self, pos: usize) {
         self.pos.insert(pos);
@@ -61,3 +101,65 @@ impl<Id: Clone + Hash + Eq + Ord> MergeJob<Id> {
         }
     }
 }
+
+/// 按 [`TieBreak`] 为训练中的配对生成次级排序键
+#[derive(Debug)]
+pub struct TieKeys<Id> {
+    mode: TieBreak,
+    /// 配对首次出现的顺序，只在 [`TieBreak::InsertionOrder`] 下记录
+    first_seen: AHashMap<(Id, Id), u64>,
+    /// 当前合并步骤中新出现的配对使用的顺序
+    step: u64,
+}
+
+impl<Id: Copy + Hash + Eq> TieKeys<Id> {
+    /// 创建生成器，[`TieBreak::InsertionOrder`] 下按语料中的位置记录初始配对的顺序
+    pub fn new(mode: TieBreak, words: &[Word<Id>]) -> Self {
+        let mut first_seen = AHashMap::new();
+        if mode == TieBreak::InsertionOrder {
+            for word in words {
+                for pair in word.ids().windows(2) {
+                    let order = first_seen.len() as u64;
+                    first_seen.entry((pair[0], pair[1])).or_insert(order);
+                }
+            }
+        }
+        let step = first_seen.len() as u64;
+        Self {
+            mode,
+            first_seen,
+            step,
+        }
+    }
+
+    /// 进入下一个合并步骤，之后新出现的配对排在之前出现的配对之后
+    pub fn next_step(&mut self) {
+        self.step += 1;
+    }
+
+    /// 配对的次级排序键，`content` 给出标记的内容，只在 [`TieBreak::Lexicographic`] 下调用
+    pub fn key<'a, F>(&mut self, pair: (Id, Id), content: F) -> TieKey
+    where
+        F: Fn(Id) -> Option<&'a [u8]>,
+    {
+        match self.mode {
+            TieBreak::PairId => TieKey::None,
+            TieBreak::Lexicographic => TieKey::Bytes(
+                content(pair.0).unwrap_or_default().to_vec(),
+                content(pair.1).unwrap_or_default().to_vec(),
+            ),
+            TieBreak::InsertionOrder => {
+                TieKey::Order(*self.first_seen.entry(pair).or_insert(self.step))
+            }
+        }
+    }
+}
+
+/// 按配对排序哈希表中的条目，训练时按此顺序入堆和更新，结果与哈希种子和线程数无关
+pub fn sorted_by_pair<Id: Ord, V>(
+    entries: impl IntoIterator<Item = ((Id, Id), V)>,
+) -> Vec<((Id, Id), V)> {
+    let mut entries: Vec<_> = entries.into_iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
@@ -1,5 +1,33 @@
+pub mod binary;
+pub mod content_hash;
+pub mod decoder;
+pub mod encoding;
+pub mod events;
+pub mod hf_json;
+pub mod integrity;
+pub mod limits;
+pub mod lossy_count;
 pub mod merge_job;
+pub mod normalizer;
+pub mod packed;
+pub mod padding;
+pub mod pre_tokenizer;
+pub mod profile;
+#[cfg(feature = "python")]
+pub(crate) mod py_arrow;
+#[cfg(feature = "python")]
+pub(crate) mod py_future;
+#[cfg(feature = "python")]
+pub(crate) mod py_numpy;
+pub mod registry;
+pub mod remap;
+pub mod resources;
+pub mod score;
+pub mod snapshot;
+pub mod special_tokens;
 pub mod tokenizer_base;
+pub mod trainer_config;
 pub mod traits;
+pub mod unk_stats;
 pub mod vocab_manager;
 pub mod word;
@@ -0,0 +1,381 @@
+//! 预分词之前的文本规范化
+//!
+//! [`Normalizer`] 由若干步骤按顺序组成，训练和编码时都在按正则表达式切分之前执行，
+//! 特殊标记不参与规范化。规范化流水线随模型保存，加载后得到相同的切分。
+//! 规范化会改变文本长度，[`Normalizer::apply_aligned`] 同时记录规范化文本的每个字节来自原文的哪个区间，
+//! 编码得到的偏移区间据此换算回规范化前的原文。
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::{
+    is_nfc_quick, is_nfkc_quick, is_nfkd_quick, IsNormalized, UnicodeNormalization,
+};
+
+use crate::error::{input_validation_error, TokenizerError};
+
+/// 模型文件中规范化流水线行的前缀，完整形式为 `normalizer: nfkc,lowercase`
+pub const NORMALIZER_HEADER: &str = "normalizer: ";
+
+/// 规范化流水线中的一个步骤
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NormalizerStep {
+    /// Unicode规范组合形式（NFC）
+    Nfc,
+    /// Unicode兼容组合形式（NFKC），全角字母数字、连字等转换为常规形式
+    Nfkc,
+    /// Unicode兼容分解形式（NFKD）
+    Nfkd,
+    /// 转换为小写
+    Lowercase,
+    /// 去除重音等组合附加符号：先做NFD分解，去除规范组合类不为0的字符，再重新组合为NFC
+    StripAccents,
+    /// 把换行、制表符等空白统一为空格，合并连续的空白并去除首尾空白
+    CleanWhitespace,
+}
+
+impl NormalizerStep {
+    /// 全部内置步骤
+    pub const ALL: [Self; 6] = [
+        Self::Nfc,
+        Self::Nfkc,
+        Self::Nfkd,
+        Self::Lowercase,
+        Self::StripAccents,
+        Self::CleanWhitespace,
+    ];
+
+    /// 步骤名称，用于模型文件和Python接口
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Nfc => "nfc",
+            Self::Nfkc => "nfkc",
+            Self::Nfkd => "nfkd",
+            Self::Lowercase => "lowercase",
+            Self::StripAccents => "strip_accents",
+            Self::CleanWhitespace => "clean_whitespace",
+        }
+    }
+
+    /// 按名称查找步骤
+    ///
+    /// # Errors
+    ///
+    /// 当名称不是内置步骤时返回错误
+    pub fn from_name(name: &str) -> Result<Self, TokenizerError> {
+        Self::ALL
+            .into_iter()
+            .find(|step| step.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|step| step.name()).collect();
+                input_validation_error(format!(
+                    "未知的规范化步骤: {}，可选: {}",
+                    name,
+                    names.join(", ")
+                ))
+            })
+    }
+
+    /// 执行这一步，文本不变时直接借用
+    #[must_use]
+    pub fn apply(self, text: &str) -> Cow<'_, str> {
+        let unchanged = match self {
+            Self::Nfc => is_nfc_quick(text.chars()) == IsNormalized::Yes,
+            Self::Nfkc => is_nfkc_quick(text.chars()) == IsNormalized::Yes,
+            Self::Nfkd => is_nfkd_quick(text.chars()) == IsNormalized::Yes,
+            Self::Lowercase => text.chars().all(|ch| {
+                let mut lower = ch.to_lowercase();
+                lower.next() == Some(ch) && lower.next().is_none()
+            }),
+            Self::StripAccents => text.is_ascii(),
+            Self::CleanWhitespace => {
+                text.is_empty()
+                    || (!text.starts_with(char::is_whitespace)
+                        && !text.ends_with(char::is_whitespace)
+                        && !text
+                            .split(' ')
+                            .any(|word| word.is_empty() || word.contains(char::is_whitespace)))
+            }
+        };
+        if unchanged {
+            return Cow::Borrowed(text);
+        }
+        Cow::Owned(match self {
+            Self::Nfc => text.nfc().collect(),
+            Self::Nfkc => text.nfkc().collect(),
+            Self::Nfkd => text.nfkd().collect(),
+            Self::Lowercase => text.to_lowercase(),
+            Self::StripAccents => text
+                .nfd()
+                .filter(|&ch| canonical_combining_class(ch) == 0)
+                .nfc()
+                .collect(),
+            Self::CleanWhitespace => text.split_whitespace().collect::<Vec<_>>().join(" "),
+        })
+    }
+
+    /// 把 `text` 切成若干段，各段分别执行这一步后依次拼接的结果与整段执行相同，
+    /// 返回每段的区间和结果，用于对齐规范化前后的文本
+    ///
+    /// Unicode规范化在不会与前面的字符组合的起始字符之前切开，小写按字符切开，
+    /// 空白整理把每段连续空白单独作为一段。上下文相关的情况（如希腊文词尾的 `Σ`）
+    /// 逐段执行的结果与整段不同，此时整段作为一段返回
+    fn segments(self, text: &str) -> Vec<(Range<usize>, Cow<'_, str>)> {
+        let whole = self.apply(text);
+        if let Cow::Borrowed(_) = whole {
+            return vec![(0..text.len(), whole)];
+        }
+
+        let mut segments: Vec<(Range<usize>, Cow<'_, str>)> = Vec::new();
+        match self {
+            Self::CleanWhitespace => {
+                let mut last = 0;
+                let mut words = text
+                    .split(char::is_whitespace)
+                    .filter(|word| !word.is_empty())
+                    .peekable();
+                while let Some(word) = words.next() {
+                    let start = word.as_ptr() as usize - text.as_ptr() as usize;
+                    // 开头的空白丢弃，词之间的空白变为一个空格
+                    if start > last {
+                        let space = if last == 0 { "" } else { " " };
+                        segments.push((last..start, Cow::Borrowed(space)));
+                    }
+                    segments.push((start..start + word.len(), Cow::Borrowed(word)));
+                    last = start + word.len();
+                    if words.peek().is_none() && last < text.len() {
+                        segments.push((last..text.len(), Cow::Borrowed("")));
+                    }
+                }
+                if segments.is_empty() {
+                    segments.push((0..text.len(), Cow::Borrowed("")));
+                }
+            }
+            _ => {
+                let mut start = 0;
+                for (index, ch) in text.char_indices().skip(1) {
+                    if self.is_boundary_before(ch) {
+                        segments.push((start..index, self.apply(&text[start..index])));
+                        start = index;
+                    }
+                }
+                segments.push((start..text.len(), self.apply(&text[start..])));
+            }
+        }
+
+        let joined: String = segments.iter().map(|(_, output)| output.as_ref()).collect();
+        if joined != whole {
+            return vec![(0..text.len(), whole)];
+        }
+        segments
+    }
+
+    /// 在 `ch` 之前切开时，两侧分别执行这一步不会改变结果：`ch` 分解后以组合类为0的字符开头，
+    /// 且组合形式下该字符不会与前面的字符组合
+    fn is_boundary_before(self, ch: char) -> bool {
+        let once = std::iter::once(ch);
+        let starter = |first: Option<char>, quick: fn(char) -> IsNormalized| {
+            first.is_some_and(|first| {
+                canonical_combining_class(first) == 0 && quick(first) != IsNormalized::Maybe
+            })
+        };
+        match self {
+            Self::Nfc | Self::StripAccents => {
+                starter(once.nfd().next(), |c| is_nfc_quick(std::iter::once(c)))
+            }
+            Self::Nfkc => starter(once.nfkd().next(), |c| is_nfkc_quick(std::iter::once(c))),
+            Self::Nfkd => starter(once.nfkd().next(), |_| IsNormalized::Yes),
+            Self::Lowercase => true,
+            Self::CleanWhitespace => false,
+        }
+    }
+}
+
+/// 由若干步骤按顺序组成的规范化流水线，默认为空，不改变文本
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Normalizer {
+    steps: Vec<NormalizerStep>,
+}
+
+impl Normalizer {
+    /// 创建空的流水线
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 在末尾追加一个步骤
+    #[must_use]
+    pub fn with(mut self, step: NormalizerStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// 解析以逗号分隔的步骤名称，如 `"nfkc,lowercase"`；空字符串和 `"none"` 表示空流水线
+    ///
+    /// # Errors
+    ///
+    /// 当任意名称不是内置步骤时返回错误
+    pub fn parse(spec: &str) -> Result<Self, TokenizerError> {
+        let spec = spec.trim();
+        if spec.is_empty() || spec == "none" {
+            return Ok(Self::default());
+        }
+        let steps = spec
+            .split(',')
+            .map(|name| NormalizerStep::from_name(name.trim()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { steps })
+    }
+
+    /// 以逗号分隔的步骤名称，空流水线为 `"none"`，可由 [`Normalizer::parse`] 还原
+    #[must_use]
+    pub fn spec(&self) -> String {
+        if self.steps.is_empty() {
+            return "none".to_string();
+        }
+        let names: Vec<&str> = self.steps.iter().map(|step| step.name()).collect();
+        names.join(",")
+    }
+
+    /// 按顺序排列的步骤
+    #[must_use]
+    pub fn steps(&self) -> &[NormalizerStep] {
+        &self.steps
+    }
+
+    /// 是否没有任何步骤
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// 依次执行各步骤，同时记录结果的每个字节来自原文的哪个区间
+    ///
+    /// 得到的文本与 [`Normalizer::apply`] 相同，用于把规范化文本中的偏移换算回原文
+    #[must_use]
+    pub fn apply_aligned(&self, text: &str) -> NormalizedText {
+        let mut result = NormalizedText::new(text);
+        for step in &self.steps {
+            let segments = step.segments(result.text());
+            if let [(_, Cow::Borrowed(_))] = segments.as_slice() {
+                continue;
+            }
+            let mut next = NormalizedText::default();
+            for (range, output) in segments {
+                next.push_segment(&result.text[range.clone()], range.start, &output);
+            }
+            // 各段的来源是上一步结果中的位置，换算为原文中的区间
+            for source in &mut next.sources {
+                *source = result.source_range(source.0, source.1);
+            }
+            result = next;
+        }
+        result
+    }
+
+    /// 依次执行各步骤，文本不变时直接借用
+    #[must_use]
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut result = Cow::Borrowed(text);
+        for step in &self.steps {
+            let changed = match step.apply(&result) {
+                Cow::Owned(changed) => changed,
+                Cow::Borrowed(_) => continue,
+            };
+            result = Cow::Owned(changed);
+        }
+        result
+    }
+}
+
+impl From<Vec<NormalizerStep>> for Normalizer {
+    fn from(steps: Vec<NormalizerStep>) -> Self {
+        Self { steps }
+    }
+}
+
+/// 规范化后的文本，以及它的每个字节来自原文的哪个区间
+///
+/// 一个原文字符规范化为长度相同的字符时逐字节对应；长度不同时，结果的每个字节都对应整个原字符；
+/// 字符数改变的一段（如 `ﬁ` 变为 `fi`）中，结果的每个字节都对应整段原文
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NormalizedText {
+    /// 规范化后的文本
+    text: String,
+    /// 第 `i` 项为规范化文本第 `i` 个字节在原文中的区间 `(start, end)`
+    sources: Vec<(usize, usize)>,
+}
+
+impl NormalizedText {
+    /// 未经规范化的文本，每个字节对应原文中的自身
+    #[must_use]
+    pub fn new(text: &str) -> Self {
+        let mut result = Self::default();
+        result.push_unchanged(text, 0);
+        result
+    }
+
+    /// 规范化后的文本
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// 追加原文 `offset` 处未经规范化的 `text`
+    pub fn push_unchanged(&mut self, text: &str, offset: usize) {
+        self.text.push_str(text);
+        self.sources
+            .extend((offset..offset + text.len()).map(|start| (start, start + 1)));
+    }
+
+    /// 追加另一段规范化文本，其原文位于整个原文的 `offset` 处
+    pub fn push_normalized(&mut self, other: &Self, offset: usize) {
+        self.text.push_str(&other.text);
+        self.sources.extend(
+            other
+                .sources
+                .iter()
+                .map(|&(start, end)| (start + offset, end + offset)),
+        );
+    }
+
+    /// 追加 `input`（位于 `offset` 处）规范化得到的 `output`
+    fn push_segment(&mut self, input: &str, offset: usize, output: &str) {
+        if input.chars().count() == output.chars().count() {
+            for ((index, from), to) in input.char_indices().zip(output.chars()) {
+                let start = offset + index;
+                if from.len_utf8() == to.len_utf8() {
+                    self.sources
+                        .extend((start..start + to.len_utf8()).map(|start| (start, start + 1)));
+                } else {
+                    let source = (start, start + from.len_utf8());
+                    self.sources
+                        .extend(std::iter::repeat_n(source, to.len_utf8()));
+                }
+            }
+        } else {
+            let source = (offset, offset + input.len());
+            self.sources
+                .extend(std::iter::repeat_n(source, output.len()));
+        }
+        self.text.push_str(output);
+    }
+
+    /// 规范化文本中的区间 `start..end` 在原文中的区间
+    ///
+    /// 空区间落在原文中对应位置，`start` 位于文本末尾时落在最后一个字节的来源之后
+    #[must_use]
+    pub fn source_range(&self, start: usize, end: usize) -> (usize, usize) {
+        if start < end {
+            return (self.sources[start].0, self.sources[end - 1].1);
+        }
+        let position = match self.sources.get(start) {
+            Some(&(source, _)) => source,
+            None => self.sources.last().map_or(0, |&(_, end)| end),
+        };
+        (position, position)
+    }
+}
@@ -0,0 +1,272 @@
+//! 连续存储的批量编码结果
+//!
+//! `Vec<Vec<u32>>` 中每行单独分配，复制到张量前还要再拼接一次。[`PackedBatch`]
+//! 把整批标记ID放在一块连续缓冲区中，另附每行的长度和起始位置，可以直接整体复制。
+
+use std::collections::BTreeSet;
+
+use rayon::prelude::*;
+
+use crate::base::traits::Tokenizer;
+use crate::error::{input_validation_error, TokenizerError};
+
+/// 连续存储的批量编码结果
+///
+/// 未补齐时各行首尾相接；补齐时每行恰好占 `width` 个位置，`ids` 即形状为 `(len, width)`
+/// 的行优先矩阵。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackedBatch {
+    /// 整批标记ID
+    pub ids: Vec<u32>,
+    /// 每行截断后的标记数，不含补齐
+    pub lengths: Vec<usize>,
+    /// 每行在 `ids` 中的起始位置，末尾追加 `ids` 的总长度，共 `len() + 1` 项
+    pub offsets: Vec<usize>,
+    /// 补齐后的行宽，未补齐时为 `None`
+    pub width: Option<usize>,
+}
+
+impl PackedBatch {
+    /// 并行编码 `texts` 并连续存储
+    ///
+    /// `max_len` 为每行的最大标记数，超出部分被截断。`pad_id` 不为空时每行用它补齐到
+    /// `max_len`，未指定 `max_len` 时补齐到最长的行。
+    ///
+    /// # Errors
+    ///
+    /// 当任意文本编码失败时返回 [`TokenizerError::BatchItem`]，其中包含文本序号
+    pub fn encode<T, S>(
+        tokenizer: &T,
+        texts: &[S],
+        max_len: Option<usize>,
+        pad_id: Option<u32>,
+    ) -> Result<Self, TokenizerError>
+    where
+        T: Tokenizer<TokenId = u32> + Sync + ?Sized,
+        S: AsRef<str> + Sync,
+    {
+        let rows = texts
+            .par_iter()
+            .enumerate()
+            .map(|(index, text)| {
+                let mut ids =
+                    tokenizer
+                        .encode(text.as_ref())
+                        .map_err(|e| TokenizerError::BatchItem {
+                            index,
+                            source: Box::new(e),
+                        })?;
+                if let Some(max_len) = max_len {
+                    ids.truncate(max_len);
+                }
+                Ok(ids)
+            })
+            .collect::<Result<Vec<_>, TokenizerError>>()?;
+        Ok(Self::pack(&rows, max_len, pad_id))
+    }
+
+    /// 把已编码的各行拼接为连续存储，截断和补齐规则同 [`PackedBatch::encode`]
+    #[must_use]
+    pub fn pack(rows: &[Vec<u32>], max_len: Option<usize>, pad_id: Option<u32>) -> Self {
+        let lengths: Vec<usize> = rows
+            .iter()
+            .map(|row| max_len.map_or(row.len(), |max_len| row.len().min(max_len)))
+            .collect();
+        let width =
+            pad_id.map(|_| max_len.unwrap_or_else(|| lengths.iter().copied().max().unwrap_or(0)));
+        let total = match width {
+            Some(width) => width * rows.len(),
+            None => lengths.iter().sum(),
+        };
+
+        let mut ids = Vec::with_capacity(total);
+        let mut offsets = Vec::with_capacity(rows.len() + 1);
+        for (row, &len) in rows.iter().zip(&lengths) {
+            offsets.push(ids.len());
+            ids.extend_from_slice(&row[..len]);
+            if let (Some(width), Some(pad_id)) = (width, pad_id) {
+                ids.resize(ids.len() + width - len, pad_id);
+            }
+        }
+        offsets.push(ids.len());
+
+        Self {
+            ids,
+            lengths,
+            offsets,
+            width,
+        }
+    }
+
+    /// 行数
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lengths.len()
+    }
+
+    /// 是否没有任何行
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lengths.is_empty()
+    }
+
+    /// 第 `index` 行的标记ID，不含补齐
+    #[must_use]
+    pub fn row(&self, index: usize) -> Option<&[u32]> {
+        let start = *self.offsets.get(index)?;
+        let len = *self.lengths.get(index)?;
+        Some(&self.ids[start..start + len])
+    }
+
+    /// 与 `ids` 等长的注意力掩码，真实标记为1，补齐位置为0
+    #[must_use]
+    pub fn attention_mask(&self) -> Vec<u8> {
+        let mut mask = vec![0; self.ids.len()];
+        for (&start, &len) in self.offsets.iter().zip(&self.lengths) {
+            mask[start..start + len].fill(1);
+        }
+        mask
+    }
+}
+
+/// 多条样本拼接成的定长行，见 [`pack_sequences`]
+///
+/// `ids`、`segment_ids` 和 `position_ids` 都是形状为 `(len, width)` 的行优先矩阵
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackedSequences {
+    /// 所有行的标记ID，空余位置用分隔符填充
+    pub ids: Vec<u32>,
+    /// 行宽
+    pub width: usize,
+    /// 每个位置所属样本在本行中的序号，从1开始，空余位置为0；
+    /// 训练时据此构造块对角注意力掩码，避免同一行的样本互相可见
+    pub segment_ids: Vec<u32>,
+    /// 每个位置在所属样本中的位置，每个样本（超长样本的每一段）从0开始，空余位置为0
+    pub position_ids: Vec<u32>,
+    /// 每行依次放入的样本在输入中的序号；超长样本被切成多段时会出现在多行中
+    pub sources: Vec<Vec<usize>>,
+}
+
+impl PackedSequences {
+    /// 行数
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// 是否没有任何行
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// 第 `index` 行的标记ID，含空余位置
+    #[must_use]
+    pub fn row(&self, index: usize) -> Option<&[u32]> {
+        self.ids.get(index * self.width..(index + 1) * self.width)
+    }
+
+    /// 与 `ids` 等长的注意力掩码，样本内容为1，空余位置为0
+    #[must_use]
+    pub fn attention_mask(&self) -> Vec<u8> {
+        self.segment_ids.iter().map(|&s| u8::from(s != 0)).collect()
+    }
+
+    /// 样本内容占全部位置的比例，没有任何行时为1
+    #[must_use]
+    pub fn efficiency(&self) -> f64 {
+        if self.ids.is_empty() {
+            return 1.0;
+        }
+        let used = self.segment_ids.iter().filter(|&&s| s != 0).count();
+        used as f64 / self.ids.len() as f64
+    }
+}
+
+/// 用最佳适配递减法把已编码的样本拼接成长度为 `max_len` 的行
+///
+/// 每条样本末尾追加 `separator_id`，按长度从长到短依次放入剩余空间最小且放得下的行，
+/// 没有这样的行时新开一行；长度相同的样本按输入顺序处理，结果是确定的。
+/// 加上分隔符后超过 `max_len` 的样本先切成若干整行单独成行，剩余部分再参与拼接。
+/// 行内空余位置用 `separator_id` 填充，`segment_ids` 为0。
+///
+/// # Errors
+///
+/// 当 `max_len` 为0或超出 `u32` 范围时返回错误
+pub fn pack_sequences(
+    encodings: &[Vec<u32>],
+    max_len: usize,
+    separator_id: u32,
+) -> Result<PackedSequences, TokenizerError> {
+    if max_len == 0 || u32::try_from(max_len).is_err() {
+        return Err(input_validation_error(format!(
+            "行宽 {} 必须大于0且不超过u32范围",
+            max_len
+        )));
+    }
+
+    // (样本序号, 片段)，片段含末尾的分隔符
+    let mut full_rows: Vec<(usize, Vec<u32>)> = Vec::new();
+    let mut pieces: Vec<(usize, Vec<u32>)> = Vec::with_capacity(encodings.len());
+    for (index, ids) in encodings.iter().enumerate() {
+        let mut sample = Vec::with_capacity(ids.len() + 1);
+        sample.extend_from_slice(ids);
+        sample.push(separator_id);
+        let mut chunks = sample.chunks(max_len).peekable();
+        while let Some(chunk) = chunks.next() {
+            if chunks.peek().is_some() || chunk.len() == max_len {
+                full_rows.push((index, chunk.to_vec()));
+            } else {
+                pieces.push((index, chunk.to_vec()));
+            }
+        }
+    }
+    pieces.sort_by_key(|piece| std::cmp::Reverse(piece.1.len()));
+
+    let mut rows: Vec<Vec<(usize, Vec<u32>)>> =
+        full_rows.into_iter().map(|piece| vec![piece]).collect();
+    // (剩余空间, 行号)，放得下的第一项即为剩余空间最小的行，空间相同时取先开的行
+    let mut free: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for piece in pieces {
+        let len = piece.1.len();
+        let (row, remaining) = match free.range((len, 0)..).next().copied() {
+            Some((space, row)) => {
+                free.remove(&(space, row));
+                rows[row].push(piece);
+                (row, space - len)
+            }
+            None => {
+                rows.push(vec![piece]);
+                (rows.len() - 1, max_len - len)
+            }
+        };
+        if remaining > 0 {
+            free.insert((remaining, row));
+        }
+    }
+
+    let mut packed = PackedSequences {
+        ids: Vec::with_capacity(rows.len() * max_len),
+        width: max_len,
+        segment_ids: Vec::with_capacity(rows.len() * max_len),
+        position_ids: Vec::with_capacity(rows.len() * max_len),
+        sources: Vec::with_capacity(rows.len()),
+    };
+    for row in rows {
+        let mut sources = Vec::with_capacity(row.len());
+        for (segment, (index, piece)) in (1u32..).zip(row) {
+            packed.ids.extend_from_slice(&piece);
+            packed
+                .segment_ids
+                .extend(std::iter::repeat_n(segment, piece.len()));
+            packed.position_ids.extend(0..piece.len() as u32);
+            sources.push(index);
+        }
+        let padded = packed.sources.len() * max_len + max_len;
+        packed.ids.resize(padded, separator_id);
+        packed.segment_ids.resize(padded, 0);
+        packed.position_ids.resize(padded, 0);
+        packed.sources.push(sources);
+    }
+    Ok(packed)
+}
@@ -0,0 +1,453 @@
+//! 截断与补齐
+//!
+//! 模型输入有最大长度，批量输入还要补齐成矩形并附带注意力掩码。[`TruncationConfig`] 和
+//! [`PaddingConfig`] 保存在分词器的 [`EncodeOptions`] 中，由 [`SpecialTokenizer::encode_plus`]
+//! 和 [`SpecialTokenizer::encode_batch_plus`] 应用，结果为 [`BatchEncoding`]。
+//!
+//! [`Tokenizer::encode`](crate::base::traits::Tokenizer::encode) 和各分词器的 `encode_batch`
+//! 不应用截断与补齐：它们只返回 `Vec<u32>`，补齐的位置无法与真实标记区分，截断也会让依赖完整结果的
+//! 接口出错，如解码往返、`encode_pair`、标记计数和训练统计。只需限制 `encode` 结果长度时使用
+//! [`InputLimits`] 的 `max_tokens`。
+
+use rayon::prelude::*;
+
+use crate::base::encoding::PairEncoding;
+use crate::base::limits::InputLimits;
+use crate::base::traits::SpecialTokenizer;
+use crate::error::{input_validation_error, TokenizerError};
+
+/// 句子对超长时截断哪一句
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncationStrategy {
+    /// 每次从较长的一句末尾去掉一个标记，两句等长时先截第二句
+    #[default]
+    LongestFirst,
+    /// 只截断第一句
+    OnlyFirst,
+    /// 只截断第二句
+    OnlySecond,
+}
+
+/// 句子对截断后的一行：`(第一句, 第二句)`
+pub type PairSlices<'a> = (&'a [u32], &'a [u32]);
+
+/// 截断配置
+///
+/// 单条文本总是截断自身，`strategy` 只对句子对有效。`stride` 不为空时超出的部分不丢弃，
+/// 而是切成若干溢出块，相邻块重叠 `stride` 个标记，每块单独成行；句子对只能在
+/// `only_first`、`only_second` 策略下产生溢出块。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TruncationConfig {
+    /// 每行的最大标记数，句子对包含 `[CLS]` 和两个 `[SEP]`
+    pub max_length: usize,
+    /// 句子对的截断策略
+    pub strategy: TruncationStrategy,
+    /// 溢出块之间重叠的标记数，`None` 时丢弃超出的部分
+    pub stride: Option<usize>,
+}
+
+impl TruncationConfig {
+    /// 截断到 `max_length` 个标记，丢弃超出的部分
+    #[must_use]
+    pub fn new(max_length: usize) -> Self {
+        Self {
+            max_length,
+            strategy: TruncationStrategy::default(),
+            stride: None,
+        }
+    }
+
+    /// 设置句子对的截断策略
+    #[must_use]
+    pub fn with_strategy(mut self, strategy: TruncationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// 保留超出的部分，切成相邻重叠 `stride` 个标记的溢出块
+    #[must_use]
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.stride = Some(stride);
+        self
+    }
+
+    /// 检查配置是否有效
+    ///
+    /// # Errors
+    ///
+    /// 当 `max_length` 为0或 `stride` 不小于 `max_length` 时返回错误
+    pub fn validate(&self) -> Result<(), TokenizerError> {
+        if self.max_length == 0 {
+            return Err(input_validation_error("max_length 必须大于0"));
+        }
+        if let Some(stride) = self.stride {
+            if stride >= self.max_length {
+                return Err(input_validation_error(format!(
+                    "stride {} 必须小于 max_length {}",
+                    stride, self.max_length
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 截断单条序列，返回各行；没有超长或不保留溢出时只有一行
+    ///
+    /// # Errors
+    ///
+    /// 当序列超长需要产生溢出块，而 `stride` 不小于 `max_length` 时返回错误
+    pub fn truncate<'a>(&self, ids: &'a [u32]) -> Result<Vec<&'a [u32]>, TokenizerError> {
+        chunks(ids, self.max_length, self.stride)
+    }
+
+    /// 截断句子对，`num_special` 为拼接时加入的特殊标记数，返回各行的 `(第一句, 第二句)`
+    ///
+    /// # Errors
+    ///
+    /// 当 `max_length` 容纳不下特殊标记、不被截断的一句已占满长度，或在 `longest_first`
+    /// 策略下需要产生溢出块时返回错误
+    pub fn truncate_pair<'a>(
+        &self,
+        first: &'a [u32],
+        second: &'a [u32],
+        num_special: usize,
+    ) -> Result<Vec<PairSlices<'a>>, TokenizerError> {
+        let budget = self.max_length.checked_sub(num_special).ok_or_else(|| {
+            input_validation_error(format!(
+                "max_length {} 容纳不下句子对的 {} 个特殊标记",
+                self.max_length, num_special
+            ))
+        })?;
+        if first.len() + second.len() <= budget {
+            return Ok(vec![(first, second)]);
+        }
+
+        match self.strategy {
+            TruncationStrategy::LongestFirst => {
+                if self.stride.is_some() {
+                    return Err(input_validation_error(
+                        "longest_first 策略截断句子对时不能产生溢出块",
+                    ));
+                }
+                let (len_a, len_b) = (first.len(), second.len());
+                let (keep_a, keep_b) = if len_b <= budget / 2 {
+                    (budget - len_b, len_b)
+                } else if len_a <= budget - budget / 2 {
+                    (len_a, budget - len_a)
+                } else {
+                    (budget - budget / 2, budget / 2)
+                };
+                Ok(vec![(&first[..keep_a], &second[..keep_b])])
+            }
+            TruncationStrategy::OnlyFirst => {
+                let size = budget.checked_sub(second.len()).filter(|&size| size > 0);
+                let size = size.ok_or_else(|| {
+                    input_validation_error("第二句已占满 max_length，无法只截断第一句")
+                })?;
+                Ok(chunks(first, size, self.stride)?
+                    .into_iter()
+                    .map(|chunk| (chunk, second))
+                    .collect())
+            }
+            TruncationStrategy::OnlySecond => {
+                let size = budget.checked_sub(first.len()).filter(|&size| size > 0);
+                let size = size.ok_or_else(|| {
+                    input_validation_error("第一句已占满 max_length，无法只截断第二句")
+                })?;
+                Ok(chunks(second, size, self.stride)?
+                    .into_iter()
+                    .map(|chunk| (first, chunk))
+                    .collect())
+            }
+        }
+    }
+}
+
+/// 把 `ids` 切成长度不超过 `size` 的块；`stride` 为空时只保留第一块，否则相邻块重叠 `stride` 个标记
+fn chunks(ids: &[u32], size: usize, stride: Option<usize>) -> Result<Vec<&[u32]>, TokenizerError> {
+    if ids.len() <= size {
+        return Ok(vec![ids]);
+    }
+    let Some(stride) = stride else {
+        return Ok(vec![&ids[..size]]);
+    };
+    if stride >= size {
+        return Err(input_validation_error(format!(
+            "stride {} 必须小于可截断的长度 {}",
+            stride, size
+        )));
+    }
+
+    let step = size - stride;
+    let mut result = Vec::with_capacity((ids.len() - stride).div_ceil(step));
+    let mut start = 0;
+    loop {
+        let end = (start + size).min(ids.len());
+        result.push(&ids[start..end]);
+        if end == ids.len() {
+            return Ok(result);
+        }
+        start += step;
+    }
+}
+
+/// 补齐位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaddingDirection {
+    /// 补在末尾
+    #[default]
+    Right,
+    /// 补在开头，自回归生成时常用
+    Left,
+}
+
+/// 补齐配置
+///
+/// 每行补齐到 `length`（未指定时为本批最长的行），再向上取整到 `pad_to_multiple_of` 的倍数；
+/// 比 `length` 更长的行（未启用截断时）不会被截断，其余行补齐到最长的行。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PaddingConfig {
+    /// 补齐位置
+    pub direction: PaddingDirection,
+    /// 补齐使用的标记ID
+    pub pad_id: u32,
+    /// 固定的行宽，`None` 时补齐到本批最长的行
+    pub length: Option<usize>,
+    /// 行宽向上取整到该值的倍数，便于张量核心等硬件对齐
+    pub pad_to_multiple_of: Option<usize>,
+}
+
+impl PaddingConfig {
+    /// 用 `pad_id` 在末尾补齐到本批最长的行
+    #[must_use]
+    pub fn new(pad_id: u32) -> Self {
+        Self {
+            pad_id,
+            ..Self::default()
+        }
+    }
+
+    /// 设置补齐位置
+    #[must_use]
+    pub fn with_direction(mut self, direction: PaddingDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// 补齐到固定的行宽
+    #[must_use]
+    pub fn with_length(mut self, length: usize) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// 行宽向上取整到 `multiple` 的倍数
+    #[must_use]
+    pub fn with_pad_to_multiple_of(mut self, multiple: usize) -> Self {
+        self.pad_to_multiple_of = Some(multiple);
+        self
+    }
+
+    /// 检查配置是否有效
+    ///
+    /// # Errors
+    ///
+    /// 当 `pad_to_multiple_of` 为0时返回错误
+    pub fn validate(&self) -> Result<(), TokenizerError> {
+        if self.pad_to_multiple_of == Some(0) {
+            return Err(input_validation_error("pad_to_multiple_of 必须大于0"));
+        }
+        Ok(())
+    }
+
+    /// 最长的行有 `longest` 个标记时的行宽
+    #[must_use]
+    pub fn width(&self, longest: usize) -> usize {
+        let width = self.length.map_or(longest, |length| length.max(longest));
+        match self.pad_to_multiple_of {
+            Some(multiple) if multiple > 0 => width.div_ceil(multiple) * multiple,
+            _ => width,
+        }
+    }
+}
+
+/// 分词器保存的截断与补齐配置，都为空时 `encode_plus` 只附加掩码和句子编号
+///
+/// 截断与补齐只对 `encode_plus` 和 `encode_batch_plus` 生效，原因见 [`crate::base::padding`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EncodeOptions {
+    /// 截断配置
+    pub truncation: Option<TruncationConfig>,
+    /// 补齐配置
+    pub padding: Option<PaddingConfig>,
+    /// 每次编码的输入长度限制，对 `encode` 及所有基于它的接口生效
+    pub limits: InputLimits,
+}
+
+impl EncodeOptions {
+    /// 用 `tokenizer` 编码 `texts`（及对应的第二句 `pairs`），再截断、补齐
+    ///
+    /// 句子对拼接为 `[CLS] A [SEP] B [SEP]`，格式同 [`SpecialTokenizer::encode_pair`]
+    ///
+    /// # Errors
+    ///
+    /// 当 `pairs` 与 `texts` 数量不同或句子对所需的特殊标记未注册时返回错误；
+    /// 任意文本截断或编码失败时返回 [`TokenizerError::BatchItem`]，其中包含文本序号
+    pub fn encode_batch<T, S>(
+        &self,
+        tokenizer: &T,
+        texts: &[S],
+        pairs: Option<&[S]>,
+    ) -> Result<BatchEncoding, TokenizerError>
+    where
+        T: SpecialTokenizer + Sync + ?Sized,
+        S: AsRef<str> + Sync,
+    {
+        if let Some(pairs) = pairs {
+            if pairs.len() != texts.len() {
+                return Err(input_validation_error(format!(
+                    "第二句有 {} 条，与第一句的 {} 条不一致",
+                    pairs.len(),
+                    texts.len()
+                )));
+            }
+        }
+        let separators = match pairs {
+            Some(_) => {
+                let special = tokenizer.special_tokens();
+                let lookup = |token: &str| {
+                    special
+                        .id(token)
+                        .ok_or_else(|| TokenizerError::UnregisteredSpecialToken {
+                            token: token.to_string(),
+                        })
+                };
+                Some((lookup("[CLS]")?, lookup("[SEP]")?))
+            }
+            None => None,
+        };
+
+        let rows = texts
+            .par_iter()
+            .enumerate()
+            .map(|(index, text)| {
+                let encode = || -> Result<_, TokenizerError> {
+                    let first = tokenizer.encode(text.as_ref())?;
+                    match (pairs, separators) {
+                        (Some(pairs), Some((cls, sep))) => {
+                            let second = tokenizer.encode(pairs[index].as_ref())?;
+                            self.pair_rows(&first, &second, cls, sep)
+                        }
+                        _ => self.single_rows(&first),
+                    }
+                };
+                encode().map_err(|e| TokenizerError::BatchItem {
+                    index,
+                    source: Box::new(e),
+                })
+            })
+            .collect::<Result<Vec<_>, TokenizerError>>()?;
+
+        let mut batch = BatchEncoding::default();
+        for (index, sample_rows) in rows.into_iter().enumerate() {
+            for encoding in sample_rows {
+                batch.ids.push(encoding.ids);
+                batch.token_type_ids.push(encoding.token_type_ids);
+                batch.overflow_to_sample.push(index);
+            }
+        }
+        batch.pad(self.padding.as_ref());
+        Ok(batch)
+    }
+
+    /// 单条序列截断后的各行，句子编号全为0
+    fn single_rows(&self, ids: &[u32]) -> Result<Vec<PairEncoding>, TokenizerError> {
+        let chunks = match &self.truncation {
+            Some(truncation) => truncation.truncate(ids)?,
+            None => vec![ids],
+        };
+        Ok(chunks
+            .into_iter()
+            .map(|chunk| PairEncoding {
+                ids: chunk.to_vec(),
+                token_type_ids: vec![0; chunk.len()],
+            })
+            .collect())
+    }
+
+    /// 句子对截断后拼接的各行
+    fn pair_rows(
+        &self,
+        first: &[u32],
+        second: &[u32],
+        cls: u32,
+        sep: u32,
+    ) -> Result<Vec<PairEncoding>, TokenizerError> {
+        let chunks = match &self.truncation {
+            Some(truncation) => truncation.truncate_pair(first, second, 3)?,
+            None => vec![(first, second)],
+        };
+        Ok(chunks
+            .into_iter()
+            .map(|(a, b)| PairEncoding::from_pair(cls, sep, a, b))
+            .collect())
+    }
+}
+
+/// 截断、补齐后的批量编码结果
+///
+/// `ids`、`attention_mask` 和 `token_type_ids` 行数相同，同一行长度相同；启用补齐时各行等长。
+/// 溢出块各占一行，`overflow_to_sample` 记录每行来自第几条输入。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchEncoding {
+    /// 标记ID，补齐位置为 `pad_id`
+    pub ids: Vec<Vec<u32>>,
+    /// 注意力掩码，真实标记为1，补齐位置为0
+    pub attention_mask: Vec<Vec<u8>>,
+    /// 每个标记所属的句子，单条文本和补齐位置为0
+    pub token_type_ids: Vec<Vec<u32>>,
+    /// 每行对应的输入序号
+    pub overflow_to_sample: Vec<usize>,
+}
+
+impl BatchEncoding {
+    /// 行数
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// 是否没有任何行
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// 按 `padding` 补齐各行并生成注意力掩码，`padding` 为空时只生成掩码
+    fn pad(&mut self, padding: Option<&PaddingConfig>) {
+        let longest = self.ids.iter().map(Vec::len).max().unwrap_or(0);
+        let width = padding.map(|padding| padding.width(longest));
+
+        self.attention_mask = Vec::with_capacity(self.ids.len());
+        for (ids, type_ids) in self.ids.iter_mut().zip(&mut self.token_type_ids) {
+            let len = ids.len();
+            let mut mask = vec![1u8; len];
+            if let (Some(padding), Some(width)) = (padding, width) {
+                let pad = width - len;
+                match padding.direction {
+                    PaddingDirection::Right => {
+                        ids.resize(width, padding.pad_id);
+                        type_ids.resize(width, 0);
+                        mask.resize(width, 0);
+                    }
+                    PaddingDirection::Left => {
+                        ids.splice(0..0, std::iter::repeat_n(padding.pad_id, pad));
+                        type_ids.splice(0..0, std::iter::repeat_n(0, pad));
+                        mask.splice(0..0, std::iter::repeat_n(0, pad));
+                    }
+                }
+            }
+            self.attention_mask.push(mask);
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! 预分词流水线
+//!
+//! 预分词把规范化后的文本切成片段，训练时在片段内统计、编码时逐片段查找和合并，标记不会跨越片段边界。
+//! [`PreTokenizerPipeline`] 由若干步骤按顺序组成，每一步把上一步得到的每个片段进一步切分。
+//! 片段总是借用原文，不改写文本，因此编码得到的偏移区间仍与原文对齐；`ByteLevel` 的 `Ġ`
+//! 和 `Metaspace` 的 `▁` 只是导出词汇表时的表示方式。流水线随模型保存。
+
+use std::sync::LazyLock;
+
+use fancy_regex::Regex;
+
+use crate::base::tokenizer_base::GPT2_PATTERN;
+use crate::error::{input_validation_error, TokenizerError};
+
+/// 模型文件中预分词流水线行的前缀，完整形式为 `pre_tokenizer: regex,digits`
+pub const PRE_TOKENIZER_HEADER: &str = "pre_tokenizer: ";
+
+/// 片段回调，返回错误时中止预分词
+pub type PieceFn<'f> = dyn FnMut(&str) -> Result<(), TokenizerError> + 'f;
+
+/// 把文本切成片段的预分词器
+pub trait PreTokenizer {
+    /// 把 `text` 切成非空片段，按在原文中的顺序依次交给 `f`
+    ///
+    /// # Errors
+    ///
+    /// 当匹配失败或 `f` 返回错误时返回错误
+    fn pre_tokenize(&self, text: &str, f: &mut PieceFn<'_>) -> Result<(), TokenizerError>;
+}
+
+/// 按正则表达式切分：每个匹配成为一个片段，匹配之间的文本被丢弃；
+/// 没有任何匹配时按空白分割作为后备
+impl PreTokenizer for Regex {
+    fn pre_tokenize(&self, text: &str, f: &mut PieceFn<'_>) -> Result<(), TokenizerError> {
+        let mut matched = false;
+        find_pieces(self, text, &mut |piece| {
+            matched = true;
+            f(piece)
+        })?;
+        if !matched {
+            for piece in text.split_whitespace() {
+                f(piece)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 依次把每个非空匹配交给 `f`，匹配失败时错误信息包含失败位置的字节偏移
+fn for_each_match<'t>(
+    pattern: &Regex,
+    text: &'t str,
+    mut f: impl FnMut(fancy_regex::Match<'t>) -> Result<(), TokenizerError>,
+) -> Result<(), TokenizerError> {
+    let mut offset = 0;
+    for mat in pattern.find_iter(text) {
+        let m = mat.map_err(|e| TokenizerError::RegexMatchError {
+            offset,
+            message: e.to_string(),
+        })?;
+        offset = m.end();
+        if !m.as_str().is_empty() {
+            f(m)?;
+        }
+    }
+    Ok(())
+}
+
+/// 每个匹配成为一个片段，匹配之间的文本被丢弃
+fn find_pieces(pattern: &Regex, text: &str, f: &mut PieceFn<'_>) -> Result<(), TokenizerError> {
+    for_each_match(pattern, text, |m| f(m.as_str()))
+}
+
+/// 每个匹配单独成为一个片段，匹配之间的文本也各自成为片段
+fn isolate(pattern: &Regex, text: &str, f: &mut PieceFn<'_>) -> Result<(), TokenizerError> {
+    let mut last = 0;
+    for_each_match(pattern, text, |m| {
+        if m.start() > last {
+            f(&text[last..m.start()])?;
+        }
+        last = m.end();
+        f(m.as_str())
+    })?;
+    if last < text.len() {
+        f(&text[last..])?;
+    }
+    Ok(())
+}
+
+/// BERT风格的 `Whitespace`：连续的单词字符或连续的标点各成一片，空白被丢弃
+static WHITESPACE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\w+|[^\w\s]+").expect("valid whitespace pattern"));
+
+/// 单个数字字符
+static DIGIT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\p{N}").expect("valid digit pattern"));
+
+/// 单个标点字符，包括ASCII中不属于Unicode标点类别的符号
+static PUNCTUATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\p{P}$+<=>^`|~]").expect("valid punctuation pattern"));
+
+/// GPT-2的 `ByteLevel` 预分词器使用的正则表达式
+static BYTE_LEVEL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(GPT2_PATTERN).expect("valid GPT-2 pattern"));
+
+/// 预分词流水线中的一个步骤
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PreTokenizerStep {
+    /// 按分词器的预分词正则表达式切分，见 [`TokenizerBase::set_pattern`](crate::base::tokenizer_base::TokenizerBase::set_pattern)
+    Regex,
+    /// BERT风格：连续的单词字符或连续的标点各成一片，丢弃空白
+    Whitespace,
+    /// GPT-2风格：按GPT-2正则表达式切分，空格附在后面的单词上，导出时字节映射为 `Ġ` 等字符
+    ByteLevel,
+    /// SentencePiece风格：在每个空格之前断开，空格附在后面的单词上，导出时空格表示为 `▁`
+    Metaspace,
+    /// 每个数字单独成片，如Llama对数字的处理
+    Digits,
+    /// 每个标点单独成片
+    Punctuation,
+}
+
+impl PreTokenizerStep {
+    /// 全部内置步骤
+    pub const ALL: [Self; 6] = [
+        Self::Regex,
+        Self::Whitespace,
+        Self::ByteLevel,
+        Self::Metaspace,
+        Self::Digits,
+        Self::Punctuation,
+    ];
+
+    /// 步骤名称，用于模型文件和Python接口
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Regex => "regex",
+            Self::Whitespace => "whitespace",
+            Self::ByteLevel => "byte_level",
+            Self::Metaspace => "metaspace",
+            Self::Digits => "digits",
+            Self::Punctuation => "punctuation",
+        }
+    }
+
+    /// 按名称查找步骤
+    ///
+    /// # Errors
+    ///
+    /// 当名称不是内置步骤时返回错误
+    pub fn from_name(name: &str) -> Result<Self, TokenizerError> {
+        Self::ALL
+            .into_iter()
+            .find(|step| step.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|step| step.name()).collect();
+                input_validation_error(format!(
+                    "未知的预分词步骤: {}，可选: {}",
+                    name,
+                    names.join(", ")
+                ))
+            })
+    }
+
+    /// 切分一个片段，`pattern` 为 [`PreTokenizerStep::Regex`] 使用的正则表达式
+    ///
+    /// # Errors
+    ///
+    /// 当匹配失败或 `f` 返回错误时返回错误
+    pub fn split(
+        self,
+        pattern: &Regex,
+        text: &str,
+        f: &mut PieceFn<'_>,
+    ) -> Result<(), TokenizerError> {
+        match self {
+            Self::Regex => pattern.pre_tokenize(text, f),
+            Self::Whitespace => find_pieces(&WHITESPACE, text, f),
+            Self::ByteLevel => find_pieces(&BYTE_LEVEL, text, f),
+            Self::Metaspace => {
+                let mut start = 0;
+                for (index, _) in text.match_indices(' ') {
+                    if index > start {
+                        f(&text[start..index])?;
+                    }
+                    start = index;
+                }
+                if start < text.len() {
+                    f(&text[start..])?;
+                }
+                Ok(())
+            }
+            Self::Digits => isolate(&DIGIT, text, f),
+            Self::Punctuation => isolate(&PUNCTUATION, text, f),
+        }
+    }
+}
+
+/// 由若干步骤按顺序组成的预分词流水线，默认只有 [`PreTokenizerStep::Regex`] 一步
+///
+/// 没有任何步骤时整段文本作为一个片段
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PreTokenizerPipeline {
+    steps: Vec<PreTokenizerStep>,
+}
+
+impl Default for PreTokenizerPipeline {
+    fn default() -> Self {
+        Self {
+            steps: vec![PreTokenizerStep::Regex],
+        }
+    }
+}
+
+impl PreTokenizerPipeline {
+    /// 解析以逗号分隔的步骤名称，如 `"regex,digits"`；`"none"` 表示没有任何步骤
+    ///
+    /// # Errors
+    ///
+    /// 当任意名称不是内置步骤或 `spec` 为空时返回错误
+    pub fn parse(spec: &str) -> Result<Self, TokenizerError> {
+        let spec = spec.trim();
+        if spec == "none" {
+            return Ok(Self { steps: Vec::new() });
+        }
+        if spec.is_empty() {
+            return Err(input_validation_error(
+                "预分词流水线不能为空，不切分请使用 \"none\"",
+            ));
+        }
+        let steps = spec
+            .split(',')
+            .map(|name| PreTokenizerStep::from_name(name.trim()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { steps })
+    }
+
+    /// 以逗号分隔的步骤名称，没有步骤时为 `"none"`，可由 [`PreTokenizerPipeline::parse`] 还原
+    #[must_use]
+    pub fn spec(&self) -> String {
+        if self.steps.is_empty() {
+            return "none".to_string();
+        }
+        let names: Vec<&str> = self.steps.iter().map(|step| step.name()).collect();
+        names.join(",")
+    }
+
+    /// 按顺序排列的步骤
+    #[must_use]
+    pub fn steps(&self) -> &[PreTokenizerStep] {
+        &self.steps
+    }
+
+    /// 是否为只按正则表达式切分的默认流水线
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        self.steps == [PreTokenizerStep::Regex]
+    }
+
+    /// 绑定 [`PreTokenizerStep::Regex`] 使用的正则表达式，得到可以直接切分文本的预分词器
+    #[must_use]
+    pub fn with_pattern<'a>(&'a self, pattern: &'a Regex) -> BoundPipeline<'a> {
+        BoundPipeline {
+            steps: &self.steps,
+            pattern,
+        }
+    }
+}
+
+impl From<Vec<PreTokenizerStep>> for PreTokenizerPipeline {
+    fn from(steps: Vec<PreTokenizerStep>) -> Self {
+        Self { steps }
+    }
+}
+
+/// 绑定了正则表达式的预分词流水线，见 [`PreTokenizerPipeline::with_pattern`]
+#[derive(Debug, Clone, Copy)]
+pub struct BoundPipeline<'a> {
+    steps: &'a [PreTokenizerStep],
+    pattern: &'a Regex,
+}
+
+impl PreTokenizer for BoundPipeline<'_> {
+    fn pre_tokenize(&self, text: &str, f: &mut PieceFn<'_>) -> Result<(), TokenizerError> {
+        run_steps(self.steps, self.pattern, text, f)
+    }
+}
+
+/// 用第一步切分 `text`，再把每个片段交给其余步骤
+fn run_steps(
+    steps: &[PreTokenizerStep],
+    pattern: &Regex,
+    text: &str,
+    f: &mut PieceFn<'_>,
+) -> Result<(), TokenizerError> {
+    match steps.split_first() {
+        None if text.is_empty() => Ok(()),
+        None => f(text),
+        Some((step, rest)) => step.split(pattern, text, &mut |piece| {
+            run_steps(rest, pattern, piece, f)
+        }),
+    }
+}
@@ -0,0 +1,217 @@
+//! 编码性能分析
+//!
+//! 开启后按阶段累计编码耗时，用于在不借助外部profiler的情况下定位性能回退。
+//! 关闭时每个阶段只多一次原子读取。
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::base::pre_tokenizer::{PieceFn, PreTokenizer};
+use crate::error::TokenizerError;
+
+/// 编码阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// 预分词
+    PreTokenize,
+    /// 词汇表查找
+    VocabLookup,
+    /// 应用合并规则
+    Merge,
+    /// Python对象与Rust数据之间的转换
+    PythonConversion,
+}
+
+const STAGES: usize = 4;
+
+/// 一次性能分析的结果
+///
+/// 各阶段耗时为所有工作线程的累计值，并行编码时可能大于 `total_ns`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct EncodeProfile {
+    /// 编码的文本条数
+    pub texts: u64,
+    /// 预分词耗时（纳秒）
+    pub pre_tokenize_ns: u64,
+    /// 词汇表查找耗时（纳秒）
+    pub vocab_lookup_ns: u64,
+    /// 应用合并规则耗时（纳秒）
+    pub merge_ns: u64,
+    /// Python转换耗时（纳秒）
+    pub python_conversion_ns: u64,
+    /// 批量编码的总耗时（纳秒）
+    pub total_ns: u64,
+}
+
+/// 分阶段的编码耗时统计
+///
+/// 默认关闭。批量编码开始时会清空之前的统计，因此读取到的是最近一次批量编码的结果；
+/// 逐条调用 `encode` 时统计持续累加，需要手动 [`EncodeProfiler::reset`]。
+#[derive(Debug, Default)]
+pub struct EncodeProfiler {
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    enabled: AtomicBool,
+    texts: AtomicU64,
+    stages: [AtomicU64; STAGES],
+    total: AtomicU64,
+}
+
+impl EncodeProfiler {
+    /// 与 `self` 共用同一份统计的句柄
+    ///
+    /// 在后台线程中对分词器的快照编码时，用它把耗时仍计入原分词器
+    #[must_use]
+    pub fn share(&self) -> Self {
+        Self {
+            counters: Arc::clone(&self.counters),
+        }
+    }
+
+    /// 开启或关闭统计
+    pub fn set_enabled(&self, enabled: bool) {
+        self.counters.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 是否开启了统计
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.counters.enabled.load(Ordering::Relaxed)
+    }
+
+    /// 清空已有的统计
+    pub fn reset(&self) {
+        self.counters.texts.store(0, Ordering::Relaxed);
+        self.counters.total.store(0, Ordering::Relaxed);
+        for stage in &self.counters.stages {
+            stage.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// 执行 `f` 并将耗时计入 `stage`，未开启统计时直接执行
+    #[inline]
+    pub fn time<R>(&self, stage: Stage, f: impl FnOnce() -> R) -> R {
+        if !self.is_enabled() {
+            return f();
+        }
+        let start = Instant::now();
+        let result = f();
+        self.add(stage, start.elapsed());
+        result
+    }
+
+    /// 用 `pre_tokenizer` 切分 `text` 并把每个片段交给 `f`
+    ///
+    /// 切分的耗时计入 [`Stage::PreTokenize`]，不含 `f` 本身的耗时
+    ///
+    /// # Errors
+    ///
+    /// 当预分词失败或 `f` 返回错误时返回错误
+    pub fn pre_tokenize<P>(
+        &self,
+        pre_tokenizer: &P,
+        text: &str,
+        f: &mut PieceFn<'_>,
+    ) -> Result<(), TokenizerError>
+    where
+        P: PreTokenizer + ?Sized,
+    {
+        if !self.is_enabled() {
+            return pre_tokenizer.pre_tokenize(text, f);
+        }
+        let start = Instant::now();
+        let mut pieces = Duration::ZERO;
+        let result = pre_tokenizer.pre_tokenize(text, &mut |piece| {
+            let piece_start = Instant::now();
+            let result = f(piece);
+            pieces += piece_start.elapsed();
+            result
+        });
+        self.add(Stage::PreTokenize, start.elapsed().saturating_sub(pieces));
+        result
+    }
+
+    /// 将耗时计入 `stage`
+    pub fn add(&self, stage: Stage, elapsed: Duration) {
+        self.counters.stages[stage as usize].fetch_add(nanos(elapsed), Ordering::Relaxed);
+    }
+
+    /// 记录完成编码的文本条数
+    pub fn add_texts(&self, texts: usize) {
+        if self.is_enabled() {
+            self.counters
+                .texts
+                .fetch_add(texts as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// 在批量编码开始时清空统计，返回计时起点；未开启统计时返回 `None`
+    pub fn start_batch(&self) -> Option<Instant> {
+        self.is_enabled().then(|| {
+            self.reset();
+            Instant::now()
+        })
+    }
+
+    /// 记录批量编码的总耗时
+    pub fn finish_batch(&self, start: Option<Instant>) {
+        if let Some(start) = start {
+            self.counters
+                .total
+                .store(nanos(start.elapsed()), Ordering::Relaxed);
+        }
+    }
+
+    /// 当前的统计结果
+    #[must_use]
+    pub fn snapshot(&self) -> EncodeProfile {
+        let stage = |stage: Stage| self.counters.stages[stage as usize].load(Ordering::Relaxed);
+        EncodeProfile {
+            texts: self.counters.texts.load(Ordering::Relaxed),
+            pre_tokenize_ns: stage(Stage::PreTokenize),
+            vocab_lookup_ns: stage(Stage::VocabLookup),
+            merge_ns: stage(Stage::Merge),
+            python_conversion_ns: stage(Stage::PythonConversion),
+            total_ns: self.counters.total.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Clone for EncodeProfiler {
+    /// 克隆只保留开关状态，统计从零开始
+    fn clone(&self) -> Self {
+        let profiler = Self::default();
+        profiler.set_enabled(self.is_enabled());
+        profiler
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+#[cfg(feature = "python")]
+impl EncodeProfile {
+    /// 转换为Python字典
+    pub(crate) fn to_py_dict<'py>(
+        self,
+        py: pyo3::Python<'py>,
+    ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyDict>> {
+        use pyo3::types::{PyDict, PyDictMethods};
+
+        let dict = PyDict::new(py);
+        dict.set_item("texts", self.texts)?;
+        dict.set_item("pre_tokenize_ns", self.pre_tokenize_ns)?;
+        dict.set_item("vocab_lookup_ns", self.vocab_lookup_ns)?;
+        dict.set_item("merge_ns", self.merge_ns)?;
+        dict.set_item("python_conversion_ns", self.python_conversion_ns)?;
+        dict.set_item("total_ns", self.total_ns)?;
+        Ok(dict)
+    }
+}
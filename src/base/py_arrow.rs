@@ -0,0 +1,197 @@
+//! Python批量输入：字符串列表或Arrow字符串列
+//!
+//! Arrow数据通过 [Arrow PyCapsule 接口](https://arrow.apache.org/docs/format/CDataInterface/PyCapsuleInterface.html)
+//! 读取，直接借用Arrow缓冲区中的字符串，不会为每个元素创建Python `str` 对象。
+
+use std::ffi::{c_char, c_void, CStr};
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyCapsule, PyTuple};
+
+/// Arrow C数据接口中的 `ArrowSchema`
+#[repr(C)]
+struct ArrowSchema {
+    format: *const c_char,
+    name: *const c_char,
+    metadata: *const c_char,
+    flags: i64,
+    n_children: i64,
+    children: *mut *mut ArrowSchema,
+    dictionary: *mut ArrowSchema,
+    release: Option<unsafe extern "C" fn(*mut ArrowSchema)>,
+    private_data: *mut c_void,
+}
+
+/// Arrow C数据接口中的 `ArrowArray`
+#[repr(C)]
+struct ArrowArray {
+    length: i64,
+    null_count: i64,
+    offset: i64,
+    n_buffers: i64,
+    n_children: i64,
+    buffers: *mut *const c_void,
+    children: *mut *mut ArrowArray,
+    dictionary: *mut ArrowArray,
+    release: Option<unsafe extern "C" fn(*mut ArrowArray)>,
+    private_data: *mut c_void,
+}
+
+/// 批量编码的输入
+///
+/// Arrow输入中的字符串借用自仍由本结构持有的PyCapsule，因此只能通过 [`BatchInput::texts`] 访问
+pub(crate) struct BatchInput<'py> {
+    /// 保持Arrow数据存活的 (schema, array) 胶囊
+    _capsules: Vec<Bound<'py, PyTuple>>,
+    owned: Vec<String>,
+    /// Arrow字符串的指针和长度，`None` 表示空值
+    borrowed: Vec<Option<(*const u8, usize)>>,
+}
+
+impl<'py> BatchInput<'py> {
+    /// 从Python对象提取批量输入
+    ///
+    /// 支持字符串列表、pyarrow的 `StringArray`/`LargeStringArray`、`ChunkedArray`，
+    /// 以及 `RecordBatch`/`Table`（通过 `column` 指定列名，只有一列时可以省略）
+    pub(crate) fn extract(texts: &Bound<'py, PyAny>, column: Option<&str>) -> PyResult<Self> {
+        let mut input = Self {
+            _capsules: Vec::new(),
+            owned: Vec::new(),
+            borrowed: Vec::new(),
+        };
+
+        if texts.hasattr("num_columns")? {
+            let column = match column {
+                Some(name) => texts.call_method1("column", (name,))?,
+                None if texts.getattr("num_columns")?.extract::<usize>()? == 1 => {
+                    texts.call_method1("column", (0,))?
+                }
+                None => {
+                    return Err(PyValueError::new_err(
+                        "输入包含多列，需要通过column参数指定文本列",
+                    ))
+                }
+            };
+            input.push_arrow(&column)?;
+        } else if texts.hasattr("__arrow_c_array__")? || texts.hasattr("chunks")? {
+            input.push_arrow(texts)?;
+        } else {
+            input.owned = texts.extract()?;
+        }
+
+        Ok(input)
+    }
+
+    fn push_arrow(&mut self, array: &Bound<'py, PyAny>) -> PyResult<()> {
+        // ChunkedArray 按块依次读取
+        if !array.hasattr("__arrow_c_array__")? {
+            for chunk in array.getattr("chunks")?.try_iter()? {
+                self.push_arrow(&chunk?)?;
+            }
+            return Ok(());
+        }
+
+        let capsules = array
+            .call_method0("__arrow_c_array__")?
+            .downcast_into::<PyTuple>()?;
+        let schema = capsules.get_item(0)?.downcast_into::<PyCapsule>()?;
+        let data = capsules.get_item(1)?.downcast_into::<PyCapsule>()?;
+
+        // SAFETY: 胶囊由Arrow生产者创建，名称分别为 arrow_schema 和 arrow_array，
+        // 在 `capsules` 存活期间指针有效
+        unsafe {
+            let schema = &*schema.pointer().cast::<ArrowSchema>();
+            let data = &*data.pointer().cast::<ArrowArray>();
+            read_strings(schema, data, &mut self.borrowed)?;
+        }
+
+        self._capsules.push(capsules);
+        Ok(())
+    }
+
+    /// 输入中的全部文本，空值为 `None`
+    pub(crate) fn texts(&self) -> Vec<Option<&str>> {
+        if self.borrowed.is_empty() {
+            return self.owned.iter().map(|s| Some(s.as_str())).collect();
+        }
+
+        self.borrowed
+            .iter()
+            .map(|value| {
+                value.map(|(ptr, len)| {
+                    // SAFETY: 指针指向 `_capsules` 持有的Arrow缓冲区，读取时已校验UTF-8
+                    unsafe { std::str::from_utf8_unchecked(std::slice::from_raw_parts(ptr, len)) }
+                })
+            })
+            .collect()
+    }
+}
+
+/// 读取Arrow字符串数组（`utf8` 或 `large_utf8`）中的每个元素
+unsafe fn read_strings(
+    schema: &ArrowSchema,
+    array: &ArrowArray,
+    out: &mut Vec<Option<(*const u8, usize)>>,
+) -> PyResult<()> {
+    let format = CStr::from_ptr(schema.format).to_string_lossy();
+    let large = match format.as_ref() {
+        "u" => false,
+        "U" => true,
+        other => {
+            return Err(PyTypeError::new_err(format!(
+                "只支持Arrow字符串类型（utf8/large_utf8），实际格式为 {:?}",
+                other
+            )))
+        }
+    };
+    if array.n_buffers != 3 {
+        return Err(PyValueError::new_err(
+            "无效的Arrow字符串数组: 缓冲区数量不是3",
+        ));
+    }
+
+    let buffers = std::slice::from_raw_parts(array.buffers, 3);
+    let validity = buffers[0].cast::<u8>();
+    let data = buffers[2].cast::<u8>();
+    let length = usize::try_from(array.length).unwrap_or(0);
+    let offset = usize::try_from(array.offset).unwrap_or(0);
+
+    let value_offset = |i: usize| -> usize {
+        if large {
+            *buffers[1].cast::<i64>().add(i) as usize
+        } else {
+            *buffers[1].cast::<i32>().add(i) as usize
+        }
+    };
+
+    out.reserve(length);
+    for i in offset..offset + length {
+        let is_null = array.null_count != 0
+            && !validity.is_null()
+            && (*validity.add(i / 8) >> (i % 8)) & 1 == 0;
+        if is_null {
+            out.push(None);
+            continue;
+        }
+
+        let start = value_offset(i);
+        let end = value_offset(i + 1);
+        // 全部为空字符串时数据缓冲区可能为空指针
+        let bytes: &[u8] = if end == start {
+            &[]
+        } else {
+            std::slice::from_raw_parts(data.add(start), end - start)
+        };
+        std::str::from_utf8(bytes).map_err(|e| {
+            PyValueError::new_err(format!(
+                "Arrow字符串第 {} 个元素不是有效的UTF-8: {}",
+                i - offset,
+                e
+            ))
+        })?;
+        out.push(Some((bytes.as_ptr(), bytes.len())));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! 在后台线程池中执行计算，并通过 asyncio future 返回结果
+//!
+//! 计算期间释放GIL，事件循环可以继续处理其他协程；结果通过
+//! `loop.call_soon_threadsafe` 交回事件循环线程。
+
+use std::sync::OnceLock;
+
+use pyo3::prelude::*;
+use pyo3::{wrap_pyfunction, IntoPyObjectExt, PyClass};
+
+/// 在事件循环线程中设置future的结果，future已被取消时忽略
+#[pyfunction]
+fn resolve_future(future: &Bound<'_, PyAny>, ok: bool, value: PyObject) -> PyResult<()> {
+    if future.call_method0("done")?.is_truthy()? {
+        return Ok(());
+    }
+    let method = if ok { "set_result" } else { "set_exception" };
+    future.call_method1(method, (value,))?;
+    Ok(())
+}
+
+/// 异步任务共用的线程池，线程数与CPU核数相同
+///
+/// 任务再多也不会额外创建线程，任务内部的并行迭代同样在这里执行。交付结果时需要等待GIL，
+/// 使用单独的线程池可以避免占住全局rayon线程池中同步编码所需的线程。
+fn worker_pool() -> &'static rayon::ThreadPool {
+    static POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .thread_name(|i| format!("zero-tokenizer-async-{}", i))
+            .build()
+            .expect("无法启动异步编码线程池")
+    })
+}
+
+/// 在当前线程借用 `owner`，用 `prepare` 取出计算所需的状态后在后台线程池中执行 `work`，
+/// 返回在当前事件循环上完成的 asyncio future
+///
+/// 借用只在本函数返回前持有，后台线程不再访问 `owner`，因此之后对其的可变调用（如训练）
+/// 既不会失败，也不会影响已经开始的计算。
+///
+/// # Errors
+///
+/// 当没有正在运行的事件循环，或 `owner` 正被可变借用时返回错误
+pub(crate) fn spawn_with_future<'py, T, S, R, P, F>(
+    owner: &Bound<'py, T>,
+    prepare: P,
+    work: F,
+) -> PyResult<Bound<'py, PyAny>>
+where
+    T: PyClass,
+    S: Send + 'static,
+    R: for<'a> IntoPyObject<'a> + Send + 'static,
+    P: FnOnce(&T) -> S,
+    F: FnOnce(S) -> PyResult<R> + Send + 'static,
+{
+    let py = owner.py();
+    let state = prepare(&*owner.try_borrow()?);
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    let future = event_loop.call_method0("create_future")?;
+
+    let event_loop_ref = event_loop.unbind();
+    let future_ref = future.clone().unbind();
+
+    worker_pool().spawn(move || {
+        // 计算期间不持有GIL
+        let result = work(state);
+
+        Python::with_gil(|py| {
+            let (ok, value) = match result.and_then(|value| value.into_py_any(py)) {
+                Ok(value) => (true, value),
+                Err(e) => (false, e.into_value(py).into_any()),
+            };
+
+            let delivered = wrap_pyfunction!(resolve_future, py).and_then(|resolve| {
+                event_loop_ref.call_method1(
+                    py,
+                    "call_soon_threadsafe",
+                    (resolve, future_ref, ok, value),
+                )
+            });
+            if let Err(e) = delivered {
+                // 事件循环已关闭时无法交付结果
+                log::warn!("无法将异步结果交回事件循环: {}", e);
+            }
+        });
+    });
+
+    Ok(future)
+}
@@ -0,0 +1,658 @@
+//! Python批量接口与NumPy数组的互转
+//!
+//! 批量解码的输入可以是嵌套列表或二维整数数组，批量编码的偏移和掩码可以直接构造成NumPy数组。
+//! 输入数组通过 [`__array_interface__`](https://numpy.org/doc/stable/reference/arrays.interface.html)
+//! 直接读取底层缓冲区，不会为每个元素创建Python整数对象。PyTorch等库的CPU张量通过 `__array__`
+//! 先转换为NumPy数组（共享内存，不复制）。
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyByteArray, PyDict, PyTuple};
+use rayon::prelude::*;
+
+use crate::base::encoding::Encoding;
+use crate::base::limits::{InputLimits, LimitPolicy};
+use crate::base::packed::PackedBatch;
+use crate::base::padding::{
+    BatchEncoding, PaddingConfig, PaddingDirection, TruncationConfig, TruncationStrategy,
+};
+use crate::base::traits::{OffsetUnit, SpecialTokenizer, Tokenizer, VocabBytes};
+use crate::error::{py_value_error, TokenizerError};
+
+/// 二维整数数组的只读视图，借用自仍由本结构持有的数组对象
+struct IntMatrix<'py> {
+    /// 保持缓冲区存活的数组对象
+    _array: Bound<'py, PyAny>,
+    data: *const u8,
+    rows: usize,
+    cols: usize,
+    /// 行、列方向的字节步长
+    strides: (isize, isize),
+    signed: bool,
+    itemsize: usize,
+}
+
+impl<'py> IntMatrix<'py> {
+    /// 从支持数组接口的对象构造视图，对象不是数组时返回 `None`
+    fn extract(obj: &Bound<'py, PyAny>) -> PyResult<Option<Self>> {
+        let array = if obj.hasattr("__array_interface__")? {
+            obj.clone()
+        } else if obj.hasattr("__array__")? {
+            obj.call_method0("__array__")?
+        } else {
+            return Ok(None);
+        };
+
+        let interface = array.getattr("__array_interface__")?;
+        let interface = interface.downcast::<PyDict>()?;
+        let get = |key: &str| -> PyResult<Bound<'py, PyAny>> {
+            interface
+                .get_item(key)?
+                .ok_or_else(|| PyValueError::new_err(format!("数组接口缺少 {}", key)))
+        };
+
+        let shape: Vec<usize> = get("shape")?.extract()?;
+        let [rows, cols] = shape[..] else {
+            return Err(PyValueError::new_err(format!(
+                "需要二维数组，实际为 {} 维",
+                shape.len()
+            )));
+        };
+
+        let typestr: String = get("typestr")?.extract()?;
+        let (signed, itemsize) = parse_typestr(&typestr)?;
+
+        let data = get("data")?;
+        let data = data.downcast::<PyTuple>()?;
+        let address: usize = data.get_item(0)?.extract()?;
+
+        // 没有步长时为C连续布局
+        let strides = match interface.get_item("strides")? {
+            Some(strides) if !strides.is_none() => {
+                let strides: Vec<isize> = strides.extract()?;
+                (strides[0], strides[1])
+            }
+            _ => ((cols * itemsize) as isize, itemsize as isize),
+        };
+
+        Ok(Some(Self {
+            _array: array,
+            data: address as *const u8,
+            rows,
+            cols,
+            strides,
+            signed,
+            itemsize,
+        }))
+    }
+
+    fn shape(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    /// 读取第 `row` 行第 `col` 列的元素
+    fn get(&self, row: usize, col: usize) -> i128 {
+        let offset = row as isize * self.strides.0 + col as isize * self.strides.1;
+        // SAFETY: 下标在形状范围内，偏移由数组接口给出的步长计算，
+        // 数组对象由 `_array` 持有，读取期间缓冲区保持有效
+        unsafe {
+            let ptr = self.data.offset(offset);
+            match (self.signed, self.itemsize) {
+                (true, 1) => i128::from(ptr.cast::<i8>().read_unaligned()),
+                (true, 2) => i128::from(ptr.cast::<i16>().read_unaligned()),
+                (true, 4) => i128::from(ptr.cast::<i32>().read_unaligned()),
+                (true, _) => i128::from(ptr.cast::<i64>().read_unaligned()),
+                (false, 1) => i128::from(ptr.read()),
+                (false, 2) => i128::from(ptr.cast::<u16>().read_unaligned()),
+                (false, 4) => i128::from(ptr.cast::<u32>().read_unaligned()),
+                (false, _) => i128::from(ptr.cast::<u64>().read_unaligned()),
+            }
+        }
+    }
+}
+
+/// 解析数组接口的类型字符串，只接受本机字节序的整数类型，返回 (是否有符号, 字节数)
+fn parse_typestr(typestr: &str) -> PyResult<(bool, usize)> {
+    let bytes = typestr.as_bytes();
+    let native = if cfg!(target_endian = "little") {
+        b'<'
+    } else {
+        b'>'
+    };
+    let invalid =
+        || PyTypeError::new_err(format!("需要本机字节序的整数数组，实际类型为 {}", typestr));
+
+    let [order, kind, size @ ..] = bytes else {
+        return Err(invalid());
+    };
+    if *order != native && *order != b'|' && *order != b'=' {
+        return Err(invalid());
+    }
+    let signed = match kind {
+        b'i' => true,
+        b'u' => false,
+        _ => return Err(invalid()),
+    };
+    match size {
+        b"1" => Ok((signed, 1)),
+        b"2" => Ok((signed, 2)),
+        b"4" => Ok((signed, 4)),
+        b"8" => Ok((signed, 8)),
+        _ => Err(invalid()),
+    }
+}
+
+/// 提取批量解码的标记ID
+///
+/// `ids` 可以是嵌套列表，也可以是二维整数数组（NumPy数组或PyTorch CPU张量）。
+/// 给出 `attention_mask` 时必须与 `ids` 形状相同，只保留掩码非0位置的标记，用于去掉填充。
+pub(crate) fn extract_id_batch(
+    ids: &Bound<'_, PyAny>,
+    attention_mask: Option<&Bound<'_, PyAny>>,
+) -> PyResult<Vec<Vec<u32>>> {
+    let Some(matrix) = IntMatrix::extract(ids)? else {
+        let lists: Vec<Vec<u32>> = ids.extract()?;
+        return match attention_mask {
+            Some(mask) => apply_list_mask(lists, mask.extract()?),
+            None => Ok(lists),
+        };
+    };
+
+    let mask = match attention_mask {
+        Some(mask) => {
+            let mask = IntMatrix::extract(mask)?
+                .ok_or_else(|| PyTypeError::new_err("ids为数组时attention_mask也需要是数组"))?;
+            if mask.shape() != matrix.shape() {
+                return Err(PyValueError::new_err(format!(
+                    "attention_mask的形状 {:?} 与ids的形状 {:?} 不一致",
+                    mask.shape(),
+                    matrix.shape()
+                )));
+            }
+            Some(mask)
+        }
+        None => None,
+    };
+
+    (0..matrix.rows)
+        .map(|row| {
+            let mut tokens = Vec::with_capacity(matrix.cols);
+            for col in 0..matrix.cols {
+                if mask.as_ref().is_some_and(|mask| mask.get(row, col) == 0) {
+                    continue;
+                }
+                let value = matrix.get(row, col);
+                let id = u32::try_from(value).map_err(|_| {
+                    PyValueError::new_err(format!(
+                        "第 {} 行第 {} 列的标记ID {} 超出u32范围",
+                        row, col, value
+                    ))
+                })?;
+                tokens.push(id);
+            }
+            Ok(tokens)
+        })
+        .collect()
+}
+
+/// 对嵌套列表输入应用注意力掩码
+fn apply_list_mask(lists: Vec<Vec<u32>>, mask: Vec<Vec<i64>>) -> PyResult<Vec<Vec<u32>>> {
+    if mask.len() != lists.len() {
+        return Err(PyValueError::new_err(format!(
+            "attention_mask有 {} 行，ids有 {} 行",
+            mask.len(),
+            lists.len()
+        )));
+    }
+    lists
+        .into_iter()
+        .zip(mask)
+        .enumerate()
+        .map(|(row, (tokens, mask))| {
+            if mask.len() != tokens.len() {
+                return Err(PyValueError::new_err(format!(
+                    "第 {} 行的attention_mask长度与ids不一致",
+                    row
+                )));
+            }
+            Ok(tokens
+                .into_iter()
+                .zip(mask)
+                .filter(|&(_, keep)| keep != 0)
+                .map(|(id, _)| id)
+                .collect())
+        })
+        .collect()
+}
+
+/// 解析偏移单位：`"byte"` 或 `"char"`
+fn parse_offset_unit(name: &str) -> PyResult<OffsetUnit> {
+    match name {
+        "byte" => Ok(OffsetUnit::Byte),
+        "char" => Ok(OffsetUnit::Char),
+        other => Err(PyValueError::new_err(format!(
+            "不支持的偏移单位: {}，可选 \"byte\" 或 \"char\"",
+            other
+        ))),
+    }
+}
+
+/// 并行批量编码字符串列表或Arrow字符串列，编码期间释放GIL，空值编码为空列表
+pub(crate) fn encode_batch<T>(
+    tokenizer: &T,
+    texts: &Bound<'_, PyAny>,
+    column: Option<&str>,
+) -> PyResult<Vec<Vec<u32>>>
+where
+    T: Tokenizer<TokenId = u32> + Sync,
+{
+    let py = texts.py();
+    let input = crate::base::py_arrow::BatchInput::extract(texts, column)?;
+    let texts = input.texts();
+    py.allow_threads(|| {
+        texts
+            .par_iter()
+            .map(|text| text.map_or_else(|| Ok(Vec::new()), |text| tokenizer.encode(text)))
+            .collect::<Result<Vec<_>, TokenizerError>>()
+    })
+    .map_err(PyErr::from)
+}
+
+/// 并行批量解码，输入格式见 [`extract_id_batch`]，解码期间释放GIL
+pub(crate) fn decode_batch<T>(
+    py: Python<'_>,
+    tokenizer: &T,
+    token_lists: &Bound<'_, PyAny>,
+    attention_mask: Option<&Bound<'_, PyAny>>,
+) -> PyResult<Vec<String>>
+where
+    T: Tokenizer<TokenId = u32> + Sync,
+{
+    let token_lists = extract_id_batch(token_lists, attention_mask)?;
+    py.allow_threads(|| {
+        token_lists
+            .par_iter()
+            .map(|tokens| tokenizer.decode(tokens))
+            .collect::<Result<Vec<_>, TokenizerError>>()
+    })
+    .map_err(PyErr::from)
+}
+
+/// 编码文本并按 `offset_unit` 返回每个标记的区间 `(start, end, id)`
+pub(crate) fn spans<T>(
+    tokenizer: &T,
+    text: &str,
+    offset_unit: &str,
+) -> PyResult<Vec<(usize, usize, u32)>>
+where
+    T: VocabBytes<TokenId = u32>,
+{
+    tokenizer
+        .spans_in(text, parse_offset_unit(offset_unit)?)
+        .map_err(PyErr::from)
+}
+
+/// 编码文本并返回 `ids`、`tokens`、`offsets` 组成的字典，偏移按 `offset_unit` 给出
+pub(crate) fn encode_with_offsets<'py, T>(
+    py: Python<'py>,
+    tokenizer: &T,
+    text: &str,
+    offset_unit: &str,
+) -> PyResult<Bound<'py, PyDict>>
+where
+    T: VocabBytes<TokenId = u32>,
+{
+    let spans = tokenizer
+        .spans_in(text, parse_offset_unit(offset_unit)?)
+        .map_err(PyErr::from)?;
+    Encoding::from_spans(tokenizer, spans).to_py_dict(py)
+}
+
+/// 编码句子对并返回 `input_ids`、`token_type_ids` 组成的字典
+pub(crate) fn encode_pair<'py, T>(
+    py: Python<'py>,
+    tokenizer: &T,
+    text_a: &str,
+    text_b: &str,
+    cls_token: &str,
+    sep_token: &str,
+) -> PyResult<Bound<'py, PyDict>>
+where
+    T: SpecialTokenizer,
+{
+    tokenizer
+        .encode_pair_with(text_a, text_b, cls_token, sep_token)
+        .map_err(PyErr::from)?
+        .to_py_dict(py)
+}
+
+/// 由Python参数构造截断配置，`strategy` 为 `"longest_first"`、`"only_first"` 或 `"only_second"`
+pub(crate) fn truncation_config(
+    max_length: usize,
+    strategy: &str,
+    stride: Option<usize>,
+) -> PyResult<TruncationConfig> {
+    let strategy = match strategy {
+        "longest_first" => TruncationStrategy::LongestFirst,
+        "only_first" => TruncationStrategy::OnlyFirst,
+        "only_second" => TruncationStrategy::OnlySecond,
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "未知的截断策略: {}，可选 \"longest_first\"、\"only_first\" 或 \"only_second\"",
+                other
+            )))
+        }
+    };
+    let config = TruncationConfig {
+        max_length,
+        strategy,
+        stride,
+    };
+    config.validate().map_err(py_value_error)?;
+    Ok(config)
+}
+
+/// 由Python参数构造输入长度限制，`policy` 为 `"error"` 或 `"truncate"`
+pub(crate) fn input_limits(
+    max_input_bytes: Option<usize>,
+    max_tokens: Option<usize>,
+    policy: &str,
+) -> PyResult<InputLimits> {
+    let policy = match policy {
+        "error" => LimitPolicy::Error,
+        "truncate" => LimitPolicy::Truncate,
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "未知的超限处理方式: {}，可选 \"error\" 或 \"truncate\"",
+                other
+            )))
+        }
+    };
+    let limits = InputLimits {
+        max_input_bytes,
+        max_tokens,
+        policy,
+    };
+    limits.validate().map_err(py_value_error)?;
+    Ok(limits)
+}
+
+/// 由Python参数构造补齐配置，`direction` 为 `"right"` 或 `"left"`
+pub(crate) fn padding_config(
+    direction: &str,
+    pad_id: u32,
+    length: Option<usize>,
+    pad_to_multiple_of: Option<usize>,
+) -> PyResult<PaddingConfig> {
+    let direction = match direction {
+        "right" => PaddingDirection::Right,
+        "left" => PaddingDirection::Left,
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "未知的补齐位置: {}，可选 \"right\" 或 \"left\"",
+                other
+            )))
+        }
+    };
+    let config = PaddingConfig {
+        direction,
+        pad_id,
+        length,
+        pad_to_multiple_of,
+    };
+    config.validate().map_err(py_value_error)?;
+    Ok(config)
+}
+
+/// 按分词器的截断与补齐配置编码一批文本（及第二句 `pairs`），编码期间释放GIL
+///
+/// 返回 `input_ids`、`attention_mask`、`token_type_ids` 和 `overflow_to_sample_mapping` 组成的字典。
+/// `return_tensors="np"` 时前三者为形状 `(N, L)` 的int64 NumPy数组，要求各行等长（通常需要启用补齐）；
+/// 否则返回嵌套列表。
+pub(crate) fn encode_batch_plus<'py, T>(
+    py: Python<'py>,
+    tokenizer: &T,
+    texts: Vec<String>,
+    pairs: Option<Vec<String>>,
+    return_tensors: Option<&str>,
+) -> PyResult<Bound<'py, PyDict>>
+where
+    T: SpecialTokenizer + Sync,
+{
+    let as_numpy = parse_return_tensors(return_tensors)?;
+    let batch = py
+        .allow_threads(|| tokenizer.encode_batch_plus(&texts, pairs.as_deref()))
+        .map_err(PyErr::from)?;
+    batch_encoding_to_dict(py, &batch, as_numpy)
+}
+
+/// 把 [`BatchEncoding`] 转换为Python字典，规则见 [`encode_batch_plus`]
+fn batch_encoding_to_dict<'py>(
+    py: Python<'py>,
+    batch: &BatchEncoding,
+    as_numpy: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let output = PyDict::new(py);
+    if as_numpy {
+        let width = batch.ids.first().map_or(0, Vec::len);
+        if batch.ids.iter().any(|row| row.len() != width) {
+            return Err(PyValueError::new_err(
+                "各行长度不同，无法返回NumPy数组，请先调用 enable_padding",
+            ));
+        }
+        let shape = [batch.len(), width];
+        let flatten = |rows: Vec<i64>| int64_array(py, &rows, &shape);
+        output.set_item(
+            "input_ids",
+            flatten(
+                batch
+                    .ids
+                    .iter()
+                    .flatten()
+                    .map(|&id| i64::from(id))
+                    .collect(),
+            )?,
+        )?;
+        output.set_item(
+            "attention_mask",
+            flatten(
+                batch
+                    .attention_mask
+                    .iter()
+                    .flatten()
+                    .map(|&m| i64::from(m))
+                    .collect(),
+            )?,
+        )?;
+        output.set_item(
+            "token_type_ids",
+            flatten(
+                batch
+                    .token_type_ids
+                    .iter()
+                    .flatten()
+                    .map(|&t| i64::from(t))
+                    .collect(),
+            )?,
+        )?;
+    } else {
+        output.set_item("input_ids", batch.ids.clone())?;
+        output.set_item("attention_mask", batch.attention_mask.clone())?;
+        output.set_item("token_type_ids", batch.token_type_ids.clone())?;
+    }
+    output.set_item(
+        "overflow_to_sample_mapping",
+        batch.overflow_to_sample.clone(),
+    )?;
+    Ok(output)
+}
+
+/// 批量编码文本并返回 `input_ids`、`offsets`、`attention_mask` 组成的字典
+///
+/// 各序列按最长序列补齐，补齐位置的ID为0、偏移为 `(0, 0)`、掩码为0。
+/// 偏移默认为UTF-8字节区间，`offset_unit="char"` 时为字符区间，与Python字符串的下标一致。
+/// `return_tensors="np"` 时三者分别为形状 `(N, L)`、`(N, L, 2)`、`(N, L)` 的int64 NumPy数组，
+/// 数据在Rust中填好后一次性交给NumPy，不会为每个偏移创建Python元组；否则返回嵌套列表。
+pub(crate) fn encode_batch_with_offsets<'py, T>(
+    py: Python<'py>,
+    tokenizer: &T,
+    texts: Vec<String>,
+    return_tensors: Option<&str>,
+    offset_unit: &str,
+) -> PyResult<Bound<'py, PyDict>>
+where
+    T: VocabBytes<TokenId = u32> + Sync,
+{
+    let unit = parse_offset_unit(offset_unit)?;
+    let as_numpy = parse_return_tensors(return_tensors)?;
+
+    let spans = py
+        .allow_threads(|| {
+            texts
+                .par_iter()
+                .map(|text| tokenizer.spans_in(text, unit))
+                .collect::<Result<Vec<_>, TokenizerError>>()
+        })
+        .map_err(PyErr::from)?;
+    let width = spans.iter().map(Vec::len).max().unwrap_or(0);
+
+    let output = PyDict::new(py);
+    if as_numpy {
+        let rows = spans.len();
+        let mut ids = vec![0i64; rows * width];
+        let mut offsets = vec![0i64; rows * width * 2];
+        let mut mask = vec![0i64; rows * width];
+        for (row, tokens) in spans.iter().enumerate() {
+            for (col, &(start, end, id)) in tokens.iter().enumerate() {
+                let index = row * width + col;
+                ids[index] = i64::from(id);
+                offsets[index * 2] = start as i64;
+                offsets[index * 2 + 1] = end as i64;
+                mask[index] = 1;
+            }
+        }
+        output.set_item("input_ids", int64_array(py, &ids, &[rows, width])?)?;
+        output.set_item("offsets", int64_array(py, &offsets, &[rows, width, 2])?)?;
+        output.set_item("attention_mask", int64_array(py, &mask, &[rows, width])?)?;
+    } else {
+        let ids: Vec<Vec<u32>> = spans
+            .iter()
+            .map(|tokens| {
+                let mut ids: Vec<u32> = tokens.iter().map(|&(_, _, id)| id).collect();
+                ids.resize(width, 0);
+                ids
+            })
+            .collect();
+        let offsets: Vec<Vec<(usize, usize)>> = spans
+            .iter()
+            .map(|tokens| {
+                let mut offsets: Vec<_> =
+                    tokens.iter().map(|&(start, end, _)| (start, end)).collect();
+                offsets.resize(width, (0, 0));
+                offsets
+            })
+            .collect();
+        let mask: Vec<Vec<u8>> = spans
+            .iter()
+            .map(|tokens| {
+                let mut mask = vec![1u8; tokens.len()];
+                mask.resize(width, 0);
+                mask
+            })
+            .collect();
+        output.set_item("input_ids", ids)?;
+        output.set_item("offsets", offsets)?;
+        output.set_item("attention_mask", mask)?;
+    }
+    Ok(output)
+}
+
+/// 批量编码文本，返回连续存储的 `input_ids` 以及 `lengths`、`offsets`
+///
+/// 未指定 `pad_id` 时 `input_ids` 为各行首尾相接的一维序列，`offsets` 为每行的起始位置
+/// （末尾追加总长度）；指定 `pad_id` 时每行补齐到 `max_len`（未指定时为最长行），
+/// `input_ids` 为二维，并额外返回 `attention_mask`。`return_tensors="np"` 时返回int64 NumPy数组，
+/// 否则返回列表。
+pub(crate) fn encode_batch_packed<'py, T>(
+    py: Python<'py>,
+    tokenizer: &T,
+    texts: Vec<String>,
+    max_len: Option<usize>,
+    pad_id: Option<u32>,
+    return_tensors: Option<&str>,
+) -> PyResult<Bound<'py, PyDict>>
+where
+    T: Tokenizer<TokenId = u32> + Sync,
+{
+    let as_numpy = parse_return_tensors(return_tensors)?;
+    let packed = py
+        .allow_threads(|| PackedBatch::encode(tokenizer, &texts, max_len, pad_id))
+        .map_err(PyErr::from)?;
+
+    let output = PyDict::new(py);
+    let mask = packed.width.map(|_| packed.attention_mask());
+    if as_numpy {
+        let to_i64 = |values: &[usize]| values.iter().map(|&v| v as i64).collect::<Vec<_>>();
+        let ids: Vec<i64> = packed.ids.iter().map(|&id| i64::from(id)).collect();
+        let shape = match packed.width {
+            Some(width) => vec![packed.len(), width],
+            None => vec![ids.len()],
+        };
+        output.set_item("input_ids", int64_array(py, &ids, &shape)?)?;
+        let lengths = to_i64(&packed.lengths);
+        output.set_item("lengths", int64_array(py, &lengths, &[lengths.len()])?)?;
+        let offsets = to_i64(&packed.offsets);
+        output.set_item("offsets", int64_array(py, &offsets, &[offsets.len()])?)?;
+        if let Some(mask) = mask {
+            let mask: Vec<i64> = mask.into_iter().map(i64::from).collect();
+            output.set_item("attention_mask", int64_array(py, &mask, &shape)?)?;
+        }
+    } else {
+        match packed.width {
+            Some(width) => {
+                let rows: Vec<&[u32]> = packed.offsets[..packed.len()]
+                    .iter()
+                    .map(|&start| &packed.ids[start..start + width])
+                    .collect();
+                output.set_item("input_ids", rows)?;
+                let mask = mask.unwrap_or_default();
+                let rows: Vec<Vec<u32>> = packed.offsets[..packed.len()]
+                    .iter()
+                    .map(|&start| {
+                        mask[start..start + width]
+                            .iter()
+                            .map(|&m| u32::from(m))
+                            .collect()
+                    })
+                    .collect();
+                output.set_item("attention_mask", rows)?;
+            }
+            None => output.set_item("input_ids", &packed.ids)?,
+        }
+        output.set_item("lengths", &packed.lengths)?;
+        output.set_item("offsets", &packed.offsets)?;
+    }
+    Ok(output)
+}
+
+/// 解析 `return_tensors`：为空时返回列表，`"np"` 时返回NumPy数组
+fn parse_return_tensors(return_tensors: Option<&str>) -> PyResult<bool> {
+    match return_tensors {
+        None => Ok(false),
+        Some("np") => Ok(true),
+        Some(other) => Err(PyValueError::new_err(format!(
+            "不支持的return_tensors: {}，可选值为 \"np\"",
+            other
+        ))),
+    }
+}
+
+/// 把本机字节序的int64数据复制到可写的NumPy数组中
+fn int64_array<'py>(py: Python<'py>, data: &[i64], shape: &[usize]) -> PyResult<Bound<'py, PyAny>> {
+    let numpy = py.import("numpy")?;
+    let bytes: Vec<u8> = data.iter().flat_map(|value| value.to_ne_bytes()).collect();
+    let buffer = PyByteArray::new(py, &bytes);
+    numpy
+        .call_method1("frombuffer", (buffer, "int64"))?
+        .call_method1("reshape", (PyTuple::new(py, shape)?,))
+}
@@ -0,0 +1,356 @@
+//! 本地模型注册表
+//!
+//! 配置文件中直接写模型路径时，换一台机器或调整目录结构就要逐个修改。注册表把模型按名称保存在
+//! `~/.cache/zero-tokenizer/models/<名称>/` 下，之后只需按名称引用：
+//!
+//! ```text
+//! <根目录>/<名称>/tokenizer.model   save 写出的模型文件
+//! <根目录>/<名称>/meta.json         模型类型、词汇表大小和保存时间
+//! ```
+//!
+//! 根目录依次取环境变量 `ZERO_TOKENIZER_MODELS`、`$XDG_CACHE_HOME/zero-tokenizer/models`
+//! 和 `~/.cache/zero-tokenizer/models`。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::base::binary::ModelKind;
+use crate::base::traits::Tokenizer;
+use crate::bbpe::BBPETokenizer;
+use crate::bpe::Tokenizer as BPETokenizer;
+use crate::error::{input_validation_error, model_load_error, model_save_error, TokenizerError};
+use crate::unigram::UnigramTokenizer;
+use crate::wordpiece::WordPieceTokenizer;
+
+/// 覆盖注册表根目录的环境变量
+pub const MODELS_ENV: &str = "ZERO_TOKENIZER_MODELS";
+
+/// 模型目录中的模型文件名
+const MODEL_FILE: &str = "tokenizer.model";
+
+/// 模型目录中的元数据文件名
+const META_FILE: &str = "meta.json";
+
+/// `meta.json` 的内容
+#[derive(Debug, Serialize, Deserialize)]
+struct ModelMeta {
+    kind: String,
+    vocab_size: usize,
+    saved_at: u64,
+}
+
+/// 注册表中的一个模型
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegistryEntry {
+    /// 模型名称
+    pub name: String,
+    /// 模型类型
+    #[serde(serialize_with = "serialize_kind")]
+    pub kind: ModelKind,
+    /// 保存时的词汇表大小
+    pub vocab_size: usize,
+    /// 保存时间，Unix时间戳（秒）
+    pub saved_at: u64,
+    /// 模型文件路径
+    pub path: PathBuf,
+}
+
+fn serialize_kind<S: serde::Serializer>(
+    kind: &ModelKind,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(kind.name())
+}
+
+/// 可以按名称保存到注册表的分词器
+pub trait NamedModel: Tokenizer {
+    /// 分词器对应的模型类型
+    const KIND: ModelKind;
+
+    /// 保存到默认注册表，返回模型文件路径；同名模型会被覆盖
+    ///
+    /// # Errors
+    ///
+    /// 当名称无效、找不到注册表目录或保存失败时返回错误
+    fn save_as(&self, name: &str) -> Result<PathBuf, TokenizerError> {
+        ModelRegistry::open_default()?.save(self, name)
+    }
+
+    /// 从默认注册表加载
+    ///
+    /// # Errors
+    ///
+    /// 当模型不存在、类型不匹配或加载失败时返回错误
+    fn load_named(&mut self, name: &str) -> Result<(), TokenizerError> {
+        ModelRegistry::open_default()?.load(self, name)
+    }
+}
+
+impl NamedModel for BPETokenizer {
+    const KIND: ModelKind = ModelKind::Bpe;
+}
+
+impl NamedModel for BBPETokenizer {
+    const KIND: ModelKind = ModelKind::Bbpe;
+}
+
+impl NamedModel for UnigramTokenizer {
+    const KIND: ModelKind = ModelKind::Unigram;
+}
+
+impl NamedModel for WordPieceTokenizer {
+    const KIND: ModelKind = ModelKind::WordPiece;
+}
+
+/// 以目录为根的模型注册表
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelRegistry {
+    root: PathBuf,
+}
+
+impl ModelRegistry {
+    /// 以 `root` 为根目录，目录在第一次保存时创建
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 打开默认根目录下的注册表，见模块文档
+    ///
+    /// # Errors
+    ///
+    /// 当环境变量都未设置、无法确定用户目录时返回错误
+    pub fn open_default() -> Result<Self, TokenizerError> {
+        default_root().map(Self::new)
+    }
+
+    /// 根目录
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 名称对应的模型文件路径，不检查文件是否存在
+    ///
+    /// # Errors
+    ///
+    /// 当名称无效时返回错误
+    pub fn model_path(&self, name: &str) -> Result<PathBuf, TokenizerError> {
+        Ok(self.model_dir(name)?.join(MODEL_FILE))
+    }
+
+    /// 是否已注册名为 `name` 的模型
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.model_dir(name)
+            .is_ok_and(|dir| dir.join(META_FILE).is_file() && dir.join(MODEL_FILE).is_file())
+    }
+
+    /// 把分词器保存为 `name`，返回模型文件路径；同名模型会被覆盖
+    ///
+    /// 模型先写入临时文件，完整写出后才替换原文件，保存中途失败不会破坏已有的同名模型
+    ///
+    /// # Errors
+    ///
+    /// 当名称无效或写入失败时返回错误
+    pub fn save<T: NamedModel + ?Sized>(
+        &self,
+        tokenizer: &T,
+        name: &str,
+    ) -> Result<PathBuf, TokenizerError> {
+        let dir = self.model_dir(name)?;
+        fs::create_dir_all(&dir)
+            .map_err(|e| model_save_error(format!("创建目录 {} 失败: {}", dir.display(), e)))?;
+
+        let path = dir.join(MODEL_FILE);
+        let tmp = dir.join(format!("{}.tmp", MODEL_FILE));
+        tokenizer.save(&tmp.to_string_lossy())?;
+        fs::rename(&tmp, &path)
+            .map_err(|e| model_save_error(format!("替换模型文件失败: {}", e)))?;
+
+        let meta = ModelMeta {
+            kind: T::KIND.name().to_string(),
+            vocab_size: tokenizer.vocab_size(),
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+        };
+        let json = serde_json::to_string_pretty(&meta)?;
+        fs::write(dir.join(META_FILE), json)
+            .map_err(|e| model_save_error(format!("写入元数据失败: {}", e)))?;
+        Ok(path)
+    }
+
+    /// 把名为 `name` 的模型加载到分词器中
+    ///
+    /// # Errors
+    ///
+    /// 当模型不存在、类型与分词器不匹配或加载失败时返回错误
+    pub fn load<T: NamedModel + ?Sized>(
+        &self,
+        tokenizer: &mut T,
+        name: &str,
+    ) -> Result<(), TokenizerError> {
+        let entry = self.get(name)?;
+        if entry.kind != T::KIND {
+            return Err(model_load_error(format!(
+                "模型 {} 的类型为 {}，不能加载到 {} 分词器",
+                name,
+                entry.kind.name(),
+                T::KIND.name()
+            )));
+        }
+        tokenizer.load(&entry.path.to_string_lossy())
+    }
+
+    /// 名为 `name` 的模型的信息
+    ///
+    /// # Errors
+    ///
+    /// 当名称无效、模型不存在或元数据损坏时返回错误
+    pub fn get(&self, name: &str) -> Result<RegistryEntry, TokenizerError> {
+        let dir = self.model_dir(name)?;
+        let path = dir.join(MODEL_FILE);
+        if !path.is_file() {
+            return Err(model_load_error(format!(
+                "注册表 {} 中没有模型 {}",
+                self.root.display(),
+                name
+            )));
+        }
+        let json = fs::read_to_string(dir.join(META_FILE))
+            .map_err(|e| model_load_error(format!("读取模型 {} 的元数据失败: {}", name, e)))?;
+        let meta: ModelMeta = serde_json::from_str(&json)
+            .map_err(|e| model_load_error(format!("模型 {} 的元数据损坏: {}", name, e)))?;
+        Ok(RegistryEntry {
+            name: name.to_string(),
+            kind: ModelKind::from_name(&meta.kind)?,
+            vocab_size: meta.vocab_size,
+            saved_at: meta.saved_at,
+            path,
+        })
+    }
+
+    /// 按名称排序的全部模型，根目录不存在时返回空列表
+    ///
+    /// 元数据缺失或损坏的目录被跳过
+    ///
+    /// # Errors
+    ///
+    /// 当根目录无法读取时返回错误
+    pub fn list(&self) -> Result<Vec<RegistryEntry>, TokenizerError> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(model_load_error(format!(
+                    "读取目录 {} 失败: {}",
+                    self.root.display(),
+                    e
+                )))
+            }
+        };
+        let mut models = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| model_load_error(format!("读取目录项失败: {}", e)))?;
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            if validate_name(&name).is_err() || !entry.path().is_dir() {
+                continue;
+            }
+            match self.get(&name) {
+                Ok(model) => models.push(model),
+                Err(e) => log::warn!("跳过注册表中的 {}: {}", name, e),
+            }
+        }
+        models.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(models)
+    }
+
+    /// 删除名为 `name` 的模型
+    ///
+    /// # Errors
+    ///
+    /// 当模型不存在或删除失败时返回错误
+    pub fn remove(&self, name: &str) -> Result<(), TokenizerError> {
+        let dir = self.model_dir(name)?;
+        if !dir.is_dir() {
+            return Err(model_save_error(format!(
+                "注册表 {} 中没有模型 {}",
+                self.root.display(),
+                name
+            )));
+        }
+        fs::remove_dir_all(&dir)
+            .map_err(|e| model_save_error(format!("删除模型 {} 失败: {}", name, e)))
+    }
+
+    fn model_dir(&self, name: &str) -> Result<PathBuf, TokenizerError> {
+        validate_name(name)?;
+        Ok(self.root.join(name))
+    }
+}
+
+/// 名称只能由字母、数字、`.`、`_` 和 `-` 组成，且不能以 `.` 开头，避免指向注册表之外的路径
+fn validate_name(name: &str) -> Result<(), TokenizerError> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(input_validation_error(format!(
+            "无效的模型名称: {:?}，只能包含字母、数字、'.'、'_' 和 '-'，且不能以 '.' 开头",
+            name
+        )))
+    }
+}
+
+fn default_root() -> Result<PathBuf, TokenizerError> {
+    let non_empty = |key: &str| std::env::var_os(key).filter(|value| !value.is_empty());
+    if let Some(root) = non_empty(MODELS_ENV) {
+        return Ok(PathBuf::from(root));
+    }
+    let cache = non_empty("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            non_empty("HOME")
+                .or_else(|| non_empty("USERPROFILE"))
+                .map(|home| Path::new(&home).join(".cache"))
+        })
+        .ok_or_else(|| {
+            input_validation_error(format!("无法确定用户目录，请设置环境变量 {}", MODELS_ENV))
+        })?;
+    Ok(cache.join("zero-tokenizer").join("models"))
+}
+
+/// 列出默认注册表中的模型，每个模型为一个dict：`name`、`kind`、`vocab_size`、`saved_at` 和 `path`
+#[cfg(feature = "python")]
+#[pyo3::pyfunction]
+#[pyo3(name = "list_models")]
+pub fn py_list_models(
+    py: pyo3::Python<'_>,
+) -> pyo3::PyResult<Vec<pyo3::Bound<'_, pyo3::types::PyDict>>> {
+    use pyo3::types::{PyDict, PyDictMethods};
+
+    let models = ModelRegistry::open_default()
+        .and_then(|registry| registry.list())
+        .map_err(crate::error::py_value_error)?;
+    models
+        .into_iter()
+        .map(|model| {
+            let dict = PyDict::new(py);
+            dict.set_item("name", model.name)?;
+            dict.set_item("kind", model.kind.name())?;
+            dict.set_item("vocab_size", model.vocab_size)?;
+            dict.set_item("saved_at", model.saved_at)?;
+            dict.set_item("path", model.path.to_string_lossy())?;
+            Ok(dict)
+        })
+        .collect()
+}
@@ -0,0 +1,124 @@
+//! 标记ID重映射清单
+//!
+//! 裁剪、蒸馏、追加词汇等会改变标记ID的操作都返回一份清单，
+//! 模型改造脚本可以据此自动调整嵌入矩阵的行。
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{model_load_error, model_save_error, TokenizerError};
+
+/// 标记ID重映射清单
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemapManifest {
+    /// 操作前的词汇表大小
+    pub old_vocab_size: usize,
+    /// 操作后的词汇表大小
+    pub new_vocab_size: usize,
+    /// 保留的标记：旧ID -> 新ID
+    pub mapping: BTreeMap<u32, u32>,
+    /// 新增的标记ID（新ID空间），需要初始化嵌入
+    pub added: Vec<u32>,
+    /// 被移除的标记ID（旧ID空间）
+    pub removed: Vec<u32>,
+}
+
+impl RemapManifest {
+    /// 根据操作前后的全部ID和保留标记的映射生成清单
+    pub fn new<O, N>(old_ids: O, new_ids: N, mapping: BTreeMap<u32, u32>) -> Self
+    where
+        O: IntoIterator<Item = u32>,
+        N: IntoIterator<Item = u32>,
+    {
+        let old_ids: Vec<u32> = old_ids.into_iter().collect();
+        let new_ids: Vec<u32> = new_ids.into_iter().collect();
+        let mapped: std::collections::BTreeSet<u32> = mapping.values().copied().collect();
+
+        let mut removed: Vec<u32> = old_ids
+            .iter()
+            .copied()
+            .filter(|id| !mapping.contains_key(id))
+            .collect();
+        removed.sort_unstable();
+        let mut added: Vec<u32> = new_ids
+            .iter()
+            .copied()
+            .filter(|id| !mapped.contains(id))
+            .collect();
+        added.sort_unstable();
+
+        Self {
+            old_vocab_size: old_ids.len(),
+            new_vocab_size: new_ids.len(),
+            mapping,
+            added,
+            removed,
+        }
+    }
+
+    /// 旧ID对应的新ID，标记被移除时返回 `None`
+    #[must_use]
+    pub fn get(&self, old_id: u32) -> Option<u32> {
+        self.mapping.get(&old_id).copied()
+    }
+
+    /// 所有标记ID都未改变
+    #[must_use]
+    pub fn is_identity(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.mapping.iter().all(|(old, new)| old == new)
+    }
+
+    /// 序列化为JSON
+    ///
+    /// # Errors
+    ///
+    /// 当序列化失败时返回错误
+    pub fn to_json(&self) -> Result<String, TokenizerError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| model_save_error(format!("序列化重映射清单失败: {}", e)))
+    }
+
+    /// 保存为JSON文件
+    ///
+    /// # Errors
+    ///
+    /// 当序列化或写入文件失败时返回错误
+    pub fn save(&self, path: &str) -> Result<(), TokenizerError> {
+        std::fs::write(path, self.to_json()?)
+            .map_err(|e| model_save_error(format!("保存重映射清单失败: {}", e)))
+    }
+
+    /// 从JSON文件加载
+    ///
+    /// # Errors
+    ///
+    /// 当文件不存在或格式无效时返回错误
+    pub fn load(path: &str) -> Result<Self, TokenizerError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| model_load_error(format!("打开文件失败: {}", e)))?;
+        serde_json::from_str(&content)
+            .map_err(|e| model_load_error(format!("解析重映射清单失败: {}", e)))
+    }
+}
+
+#[cfg(feature = "python")]
+impl RemapManifest {
+    /// 转换为Python字典，`mapping` 为 {旧ID: 新ID}
+    pub(crate) fn to_py_dict<'py>(
+        &self,
+        py: pyo3::Python<'py>,
+    ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyDict>> {
+        use pyo3::types::{PyDict, PyDictMethods};
+
+        let dict = PyDict::new(py);
+        dict.set_item("old_vocab_size", self.old_vocab_size)?;
+        dict.set_item("new_vocab_size", self.new_vocab_size)?;
+        dict.set_item("mapping", self.mapping.clone())?;
+        dict.set_item("added", self.added.clone())?;
+        dict.set_item("removed", self.removed.clone())?;
+        Ok(dict)
+    }
+}
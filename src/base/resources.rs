@@ -0,0 +1,132 @@
+//! 创建分词器时加载的资源
+//!
+//! Unigram和WordPiece创建时把常用汉字字表加入初始词汇表。字表默认从当前工作目录下的
+//! [`DEFAULT_CHAR_DICT_PATH`] 读取，在仓库之外运行时通常找不到该文件。[`ResourceConfig`]
+//! 指定字表的来源：自定义路径、编译进库的字表（`embedded-dict` 特性）或不预置任何字符。
+
+use std::path::{Path, PathBuf};
+
+use crate::error::TokenizerError;
+
+/// 默认的常用汉字字表路径，相对于当前工作目录
+pub const DEFAULT_CHAR_DICT_PATH: &str = "dict/常用汉字字表.txt";
+
+/// 编译进库的常用汉字字表，内容与 [`DEFAULT_CHAR_DICT_PATH`] 相同
+#[cfg(feature = "embedded-dict")]
+pub const EMBEDDED_CHAR_DICT: &str = include_str!("../../dict/常用汉字字表.txt");
+
+/// 初始字符集的来源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CharDict {
+    /// 从文件读取，每行一个字符
+    Path(PathBuf),
+    /// 使用编译进库的字表
+    #[cfg(feature = "embedded-dict")]
+    Embedded,
+    /// 不预置任何字符，初始词汇表只有字节标记
+    None,
+}
+
+impl Default for CharDict {
+    /// 启用 `embedded-dict` 特性时使用内嵌字表，否则读取 [`DEFAULT_CHAR_DICT_PATH`]
+    fn default() -> Self {
+        #[cfg(feature = "embedded-dict")]
+        {
+            Self::Embedded
+        }
+        #[cfg(not(feature = "embedded-dict"))]
+        {
+            Self::Path(PathBuf::from(DEFAULT_CHAR_DICT_PATH))
+        }
+    }
+}
+
+/// 创建分词器时加载的资源
+///
+/// ```no_run
+/// use zero_tokenizer::base::resources::{CharDict, ResourceConfig};
+/// use zero_tokenizer::unigram::UnigramTokenizer;
+///
+/// let resources = ResourceConfig::default().with_char_dict(CharDict::None);
+/// let tokenizer = UnigramTokenizer::with_resources(&resources)?;
+/// # Ok::<(), zero_tokenizer::error::TokenizerError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceConfig {
+    /// 初始字符集的来源
+    pub char_dict: CharDict,
+}
+
+impl ResourceConfig {
+    /// 不预置任何字符，加载已保存的模型时使用：词汇表来自模型文件，不需要读取字表
+    #[must_use]
+    pub fn without_char_dict() -> Self {
+        Self::default().with_char_dict(CharDict::None)
+    }
+
+    /// 设置初始字符集的来源
+    #[must_use]
+    pub fn with_char_dict(mut self, char_dict: CharDict) -> Self {
+        self.char_dict = char_dict;
+        self
+    }
+
+    /// 从 `path` 读取初始字符集
+    #[must_use]
+    pub fn with_char_dict_path(self, path: impl Into<PathBuf>) -> Self {
+        self.with_char_dict(CharDict::Path(path.into()))
+    }
+
+    /// 按字表中的顺序返回初始字符集，每行去掉首尾空白，跳过空行
+    ///
+    /// # Errors
+    ///
+    /// 当字表文件无法读取时返回错误
+    pub fn char_dict_entries(&self) -> Result<Vec<String>, TokenizerError> {
+        let content = match &self.char_dict {
+            CharDict::Path(path) => read_char_dict(path)?,
+            #[cfg(feature = "embedded-dict")]
+            CharDict::Embedded => EMBEDDED_CHAR_DICT.to_string(),
+            CharDict::None => return Ok(Vec::new()),
+        };
+        Ok(content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+/// 词表文件的路径：`file` 存在时直接使用，否则按旧的用法在 `dict` 目录下查找
+#[must_use]
+pub fn resolve_dict_file(file: &str) -> PathBuf {
+    let path = Path::new(file);
+    if path.exists() {
+        return path.to_path_buf();
+    }
+    Path::new("dict").join(file)
+}
+
+/// 由Python构造函数的参数得到资源配置：`preload_chars` 为假时不预置字符，
+/// 否则 `char_dict` 给出时从该路径读取
+#[cfg(feature = "python")]
+pub(crate) fn from_py_args(char_dict: Option<String>, preload_chars: bool) -> ResourceConfig {
+    let resources = ResourceConfig::default();
+    match char_dict {
+        _ if !preload_chars => ResourceConfig::without_char_dict(),
+        Some(path) => resources.with_char_dict_path(path),
+        None => resources,
+    }
+}
+
+fn read_char_dict(path: &Path) -> Result<String, TokenizerError> {
+    std::fs::read_to_string(path).map_err(|e| TokenizerError::InitializationError {
+        message: format!(
+            "无法打开常用汉字文件 {}: {}；可以通过 ResourceConfig 指定字表路径、\
+             启用 embedded-dict 特性或不预置字符",
+            path.display(),
+            e
+        ),
+    })
+}
@@ -0,0 +1,46 @@
+//! 分数的文本表示
+//!
+//! Unigram和WordPiece的分数以文本写入模型文件。[`format_score`] 固定使用Rust的科学计数法（`{:e}`），
+//! 输出能精确还原该 `f64` 的最短数字，小数点总是 `.`，与系统区域设置无关，例如 `-3.25e0`、`1e-7`。
+//! [`parse_score`] 同时接受旧模型中的普通小数写法；NaN和无穷大不是有效分数，加载时报错。
+
+use crate::error::{model_load_error, TokenizerError};
+
+/// 把分数写成可以精确还原的固定格式
+#[must_use]
+pub fn format_score(score: f64) -> String {
+    format!("{:e}", score)
+}
+
+/// 解析 [`format_score`] 或旧模型写出的分数
+///
+/// # Errors
+///
+/// 当文本不是数字（如使用逗号作为小数点）或分数不是有限数时返回错误
+pub fn parse_score(text: &str) -> Result<f64, TokenizerError> {
+    let text = text.trim();
+    let score: f64 = text.parse().map_err(|e| {
+        if text.contains(',') {
+            model_load_error(format!("无法解析分数 {:?}: 小数点必须为 '.'", text))
+        } else {
+            model_load_error(format!("无法解析分数 {:?}: {}", text, e))
+        }
+    })?;
+    check_score(score)
+}
+
+/// 检查分数是否为有限数
+///
+/// # Errors
+///
+/// 当分数为NaN或无穷大时返回错误
+pub fn check_score(score: f64) -> Result<f64, TokenizerError> {
+    if score.is_finite() {
+        Ok(score)
+    } else {
+        Err(model_load_error(format!(
+            "分数必须是有限数，实际为 {}",
+            score
+        )))
+    }
+}
@@ -0,0 +1,49 @@
+//! 供后台任务读取的分词器只读快照
+//!
+//! 异步编码在后台线程中执行，期间分词器可能被训练或重新加载。快照在第一次使用时复制一份，
+//! 之后的任务共享同一个 `Arc`，分词器被修改时作废，下次使用时重建。
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// 延迟创建、修改时作废的只读快照
+#[derive(Debug)]
+pub struct FrozenSnapshot<T> {
+    cell: Mutex<Option<Arc<T>>>,
+}
+
+impl<T> Default for FrozenSnapshot<T> {
+    fn default() -> Self {
+        Self {
+            cell: Mutex::new(None),
+        }
+    }
+}
+
+/// 复制出的分词器之后可能被单独修改，因此不共用原来的快照
+impl<T> Clone for FrozenSnapshot<T> {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl<T> FrozenSnapshot<T> {
+    /// 返回当前快照，没有快照时用 `freeze` 创建
+    pub fn get_or_freeze(&self, freeze: impl FnOnce() -> T) -> Arc<T> {
+        let mut cell = self.cell.lock().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(cell.get_or_insert_with(|| Arc::new(freeze())))
+    }
+
+    /// 作废当前快照，已经取得快照的任务不受影响
+    pub fn invalidate(&mut self) {
+        *self.cell.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// 是否已有可用的快照
+    #[must_use]
+    pub fn is_frozen(&self) -> bool {
+        self.cell
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+}
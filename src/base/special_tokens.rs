@@ -0,0 +1,417 @@
+//! 特殊标记注册表
+//!
+//! 特殊标记（如 `<|endoftext|>`、`[MASK]`）在编码时整体匹配，不会被预分词或合并拆开；
+//! 训练时从语料中去除，解码时可以跳过。注册表只记录哪些ID是特殊标记，标记文本和普通标记一样
+//! 保存在各分词器的词汇表中，因此解码、按字节枚举词汇表等功能无需特殊处理。
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use ahash::AHashMap;
+
+use crate::base::vocab_manager::VocabManager;
+use crate::error::{model_load_error, vocab_error, TokenizerError};
+
+/// 模型文件中特殊标记行的前缀，格式为 `special_token: <ID> <标记>`
+pub const SPECIAL_TOKEN_HEADER: &str = "special_token: ";
+
+/// 模型文件中预留ID区间行的前缀，格式为 `reserved_ids: <起始ID> <结束ID>`
+pub const RESERVED_IDS_HEADER: &str = "reserved_ids: ";
+
+/// 文本按特殊标记切分后的一段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment<'a> {
+    /// 普通文本，不为空
+    Text(&'a str),
+    /// 特殊标记的ID
+    Special(u32),
+}
+
+/// 特殊标记注册表
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpecialTokens {
+    /// ID -> 标记，按ID升序
+    by_id: BTreeMap<u32, String>,
+    /// 标记 -> ID
+    by_token: AHashMap<String, u32>,
+    /// 预留给特殊标记的ID区间，按起始ID升序且互不重叠
+    reserved: Vec<Range<u32>>,
+}
+
+impl SpecialTokens {
+    /// 创建空的注册表
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 是否没有注册任何特殊标记
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+
+    /// 已注册的特殊标记数量
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    /// 特殊标记的ID
+    #[must_use]
+    pub fn id(&self, token: &str) -> Option<u32> {
+        self.by_token.get(token).copied()
+    }
+
+    /// ID对应的特殊标记
+    #[must_use]
+    pub fn token(&self, id: u32) -> Option<&str> {
+        self.by_id.get(&id).map(String::as_str)
+    }
+
+    /// ID是否为特殊标记
+    #[must_use]
+    pub fn contains_id(&self, id: u32) -> bool {
+        self.by_id.contains_key(&id)
+    }
+
+    /// 按ID升序遍历 `(ID, 标记)`
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> + '_ {
+        self.by_id.iter().map(|(&id, token)| (id, token.as_str()))
+    }
+
+    /// 预留的ID区间，按起始ID升序
+    #[must_use]
+    pub fn reserved(&self) -> &[Range<u32>] {
+        &self.reserved
+    }
+
+    /// ID是否位于预留区间内
+    #[must_use]
+    pub fn is_reserved(&self, id: u32) -> bool {
+        self.reserved.iter().any(|range| range.contains(&id))
+    }
+
+    /// 注册表涉及的最大ID之后的第一个ID，普通标记应从这里之后分配；注册表为空时为0
+    #[must_use]
+    pub fn end_id(&self) -> u32 {
+        let tokens_end = self.by_id.keys().next_back().map_or(0, |&id| id + 1);
+        let reserved_end = self.reserved.last().map_or(0, |range| range.end);
+        tokens_end.max(reserved_end)
+    }
+
+    /// 注册特殊标记，标记文本需要已经以 `id` 存在于词汇表中
+    ///
+    /// # Errors
+    ///
+    /// 当标记为空或包含换行符，或标记与ID已分别注册为其他ID和标记时返回错误
+    pub fn insert(&mut self, token: &str, id: u32) -> Result<(), TokenizerError> {
+        if token.is_empty() {
+            return Err(vocab_error("特殊标记不能为空"));
+        }
+        if token.contains(['\n', '\r']) {
+            return Err(vocab_error(format!("特殊标记不能包含换行符: {:?}", token)));
+        }
+        if let Some(&existing) = self.by_token.get(token) {
+            if existing != id {
+                return Err(vocab_error(format!(
+                    "特殊标记 {:?} 已注册为ID {}，不能再注册为 {}",
+                    token, existing, id
+                )));
+            }
+        }
+        if let Some(existing) = self.by_id.get(&id) {
+            if existing != token {
+                return Err(vocab_error(format!(
+                    "ID {} 已注册为特殊标记 {:?}",
+                    id, existing
+                )));
+            }
+        }
+        self.by_id.insert(id, token.to_string());
+        self.by_token.insert(token.to_string(), id);
+        Ok(())
+    }
+
+    /// 预留ID区间
+    ///
+    /// # Errors
+    ///
+    /// 当区间为空或与已预留的区间重叠时返回错误
+    pub fn reserve(&mut self, ids: Range<u32>) -> Result<(), TokenizerError> {
+        if ids.is_empty() {
+            return Err(vocab_error(format!("预留的ID区间为空: {:?}", ids)));
+        }
+        if let Some(overlap) = self
+            .reserved
+            .iter()
+            .find(|range| range.start < ids.end && ids.start < range.end)
+        {
+            return Err(vocab_error(format!(
+                "预留的ID区间 {:?} 与已预留的 {:?} 重叠",
+                ids, overlap
+            )));
+        }
+        self.reserved.push(ids);
+        self.reserved.sort_unstable_by_key(|range| range.start);
+        Ok(())
+    }
+
+    /// 从 `*next_id` 开始预留 `count` 个ID，并把 `*next_id` 移到区间之后
+    ///
+    /// # Errors
+    ///
+    /// 当 `count` 为0或ID溢出时返回错误
+    pub fn reserve_after(
+        &mut self,
+        next_id: &mut u32,
+        count: u32,
+    ) -> Result<Range<u32>, TokenizerError> {
+        let end = next_id
+            .checked_add(count)
+            .ok_or_else(|| vocab_error(format!("预留 {} 个ID后超出ID范围", count)))?;
+        let ids = *next_id..end;
+        self.reserve(ids.clone())?;
+        *next_id = end;
+        Ok(ids)
+    }
+
+    /// 清空注册表和预留区间
+    pub fn clear(&mut self) {
+        self.by_id.clear();
+        self.by_token.clear();
+        self.reserved.clear();
+    }
+
+    /// 词汇表重新编号后同步特殊标记的ID，`map` 返回 `None` 的标记被移除
+    ///
+    /// 预留区间中的ID尚未使用，重新编号后不再有意义，一并清空
+    pub fn remap(&mut self, map: impl Fn(u32) -> Option<u32>) {
+        let tokens = std::mem::take(&mut self.by_id);
+        self.by_token.clear();
+        self.reserved.clear();
+        for (id, token) in tokens {
+            if let Some(new_id) = map(id) {
+                self.by_token.insert(token.clone(), new_id);
+                self.by_id.insert(new_id, token);
+            }
+        }
+    }
+
+    /// 按特殊标记切分文本，每个位置优先匹配最长的特殊标记
+    #[must_use]
+    pub fn split<'a>(&self, text: &'a str) -> Vec<Segment<'a>> {
+        let mut segments = Vec::new();
+        let mut start = 0;
+        let mut pos = 0;
+        while pos < text.len() {
+            let rest = &text[pos..];
+            let matched = self
+                .by_token
+                .iter()
+                .filter(|(token, _)| rest.starts_with(token.as_str()))
+                .max_by_key(|(token, &id)| (token.len(), std::cmp::Reverse(id)));
+            match matched {
+                Some((token, &id)) => {
+                    if start < pos {
+                        segments.push(Segment::Text(&text[start..pos]));
+                    }
+                    segments.push(Segment::Special(id));
+                    pos += token.len();
+                    start = pos;
+                }
+                None => {
+                    pos += rest.chars().next().map_or(1, char::len_utf8);
+                }
+            }
+        }
+        if start < text.len() {
+            segments.push(Segment::Text(&text[start..]));
+        }
+        segments
+    }
+
+    /// 编码文本：特殊标记直接输出其ID，其余各段交给 `encode`
+    ///
+    /// 没有注册特殊标记时整段文本直接交给 `encode`
+    ///
+    /// # Errors
+    ///
+    /// 返回 `encode` 产生的错误
+    pub fn encode_with<E>(
+        &self,
+        text: &str,
+        mut encode: impl FnMut(&str) -> Result<Vec<u32>, E>,
+    ) -> Result<Vec<u32>, E> {
+        if self.is_empty() {
+            return encode(text);
+        }
+        let mut ids = Vec::new();
+        for segment in self.split(text) {
+            match segment {
+                Segment::Text(text) => ids.extend(encode(text)?),
+                Segment::Special(id) => ids.push(id),
+            }
+        }
+        Ok(ids)
+    }
+
+    /// 去除训练语料中的特殊标记，标记两侧的文本作为独立的样本
+    #[must_use]
+    pub fn strip_texts(&self, texts: Vec<String>) -> Vec<String> {
+        if self.is_empty() {
+            return texts;
+        }
+        texts
+            .iter()
+            .flat_map(|text| {
+                self.split(text)
+                    .into_iter()
+                    .filter_map(|segment| match segment {
+                        Segment::Text(text) => Some(text.to_string()),
+                        Segment::Special(_) => None,
+                    })
+            })
+            .collect()
+    }
+
+    /// 把特殊标记加入词汇表并注册，返回每个标记的ID
+    ///
+    /// 已在词汇表中的标记沿用原ID；否则使用预留区间中最小的空闲ID，没有时使用 `*next_id`
+    /// 并将其后移
+    ///
+    /// # Errors
+    ///
+    /// 当任意标记为空、包含换行符或与已注册的标记冲突时返回错误，之前的标记已经注册
+    pub(crate) fn add<V: SpecialVocab>(
+        &mut self,
+        vocab: &mut V,
+        next_id: &mut u32,
+        tokens: &[&str],
+    ) -> Result<Vec<u32>, TokenizerError> {
+        let mut ids = Vec::with_capacity(tokens.len());
+        for &token in tokens {
+            if let Some(id) = vocab.find(token) {
+                self.insert(token, id)?;
+                ids.push(id);
+                continue;
+            }
+
+            let reserved = self
+                .reserved
+                .iter()
+                .flat_map(Clone::clone)
+                .find(|&id| !self.contains_id(id) && !vocab.contains(id));
+            let id = reserved.unwrap_or(*next_id);
+            self.insert(token, id)?;
+            vocab.add(id, token);
+            if id >= *next_id {
+                *next_id = id + 1;
+            }
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// 模型文件中的特殊标记行和预留区间行
+    #[must_use]
+    pub fn to_lines(&self) -> Vec<String> {
+        self.iter()
+            .map(|(id, token)| format!("{}{} {}", SPECIAL_TOKEN_HEADER, id, token))
+            .chain(
+                self.reserved
+                    .iter()
+                    .map(|range| format!("{}{} {}", RESERVED_IDS_HEADER, range.start, range.end)),
+            )
+            .collect()
+    }
+
+    /// 解析模型文件中的一行，是特殊标记行或预留区间行时返回 `true`
+    ///
+    /// # Errors
+    ///
+    /// 当行的格式无效或内容与注册表冲突时返回错误
+    pub fn parse_line(&mut self, line: &str) -> Result<bool, TokenizerError> {
+        if let Some(entry) = line.strip_prefix(SPECIAL_TOKEN_HEADER) {
+            let (id, token) = parse_special_token(entry)?;
+            self.insert(token, id)?;
+            Ok(true)
+        } else if let Some(entry) = line.strip_prefix(RESERVED_IDS_HEADER) {
+            self.reserve(parse_reserved_ids(entry)?)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+/// 解析 `<ID> <标记>` 形式的特殊标记条目（不含 `special_token: ` 前缀），标记两侧的空格原样保留
+///
+/// # Errors
+///
+/// 当条目缺少标记或ID无法解析时返回错误
+pub fn parse_special_token(entry: &str) -> Result<(u32, &str), TokenizerError> {
+    let entry = entry.strip_suffix('\r').unwrap_or(entry);
+    let (id, token) = entry
+        .split_once(' ')
+        .ok_or_else(|| model_load_error("无效的特殊标记行"))?;
+    let id = id
+        .parse()
+        .map_err(|e| model_load_error(format!("解析特殊标记ID失败: {}", e)))?;
+    Ok((id, token))
+}
+
+/// 解析 `<起始ID> <结束ID>` 形式的预留区间条目（不含 `reserved_ids: ` 前缀）
+///
+/// # Errors
+///
+/// 当条目格式无效或ID无法解析时返回错误
+pub fn parse_reserved_ids(entry: &str) -> Result<Range<u32>, TokenizerError> {
+    let (start, end) = entry
+        .trim()
+        .split_once(' ')
+        .ok_or_else(|| model_load_error("无效的预留区间行"))?;
+    let parse = |n: &str| {
+        n.parse::<u32>()
+            .map_err(|e| model_load_error(format!("解析预留区间失败: {}", e)))
+    };
+    Ok(parse(start)?..parse(end)?)
+}
+
+/// 可以加入特殊标记的词汇表
+pub(crate) trait SpecialVocab {
+    /// 按文本查找已有标记的ID
+    fn find(&self, token: &str) -> Option<u32>;
+    /// ID是否已被占用
+    fn contains(&self, id: u32) -> bool;
+    /// 以 `id` 加入标记
+    fn add(&mut self, id: u32, token: &str);
+}
+
+impl SpecialVocab for VocabManager<u32, String> {
+    fn find(&self, token: &str) -> Option<u32> {
+        self.get_by_value(&token.to_string()).copied()
+    }
+
+    fn contains(&self, id: u32) -> bool {
+        self.contains_id(&id)
+    }
+
+    fn add(&mut self, id: u32, token: &str) {
+        self.insert(id, token.to_string());
+    }
+}
+
+impl SpecialVocab for VocabManager<u32, Vec<u8>> {
+    fn find(&self, token: &str) -> Option<u32> {
+        self.get_by_value(&token.as_bytes().to_vec()).copied()
+    }
+
+    fn contains(&self, id: u32) -> bool {
+        self.contains_id(&id)
+    }
+
+    fn add(&mut self, id: u32, token: &str) {
+        self.insert(id, token.as_bytes().to_vec());
+    }
+}
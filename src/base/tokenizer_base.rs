@@ -1,17 +1,140 @@
 use fancy_regex::Regex;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs::File;
-use std::hash::Hash;
+use std::hash::{BuildHasher, Hash};
 use std::io::{self, BufRead, BufReader, Write};
 use std::path::Path;
 
+use crate::base::binary::{self, BinaryModel, SectionWriter};
+use crate::base::decoder::{DecoderKind, DECODER_HEADER};
+use crate::base::merge_job::TieBreak;
+use crate::base::normalizer::{NormalizedText, Normalizer, NORMALIZER_HEADER};
+use crate::base::padding::EncodeOptions;
+use crate::base::pre_tokenizer::{
+    BoundPipeline, PreTokenizer, PreTokenizerPipeline, PRE_TOKENIZER_HEADER,
+};
+use crate::base::special_tokens::{
+    Segment, SpecialTokens, RESERVED_IDS_HEADER, SPECIAL_TOKEN_HEADER,
+};
+use crate::base::trainer_config::TrainerConfig;
 use crate::base::vocab_manager::VocabManager;
 use crate::base::word::Word;
+use crate::error::{
+    input_validation_error, model_load_error, model_save_error, vocab_error, TokenizerError,
+};
 
 /// 默认的GPT-4风格正则表达式模式，用于分割文本
 pub const GPT4_PATTERN: &str = r"'(?i:[sdmt]|ll|ve|re)|[^\r\n\p{L}\p{N}]?+\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]++[\r\n]*|\s*[\r\n]|\s+(?!\S)|\s+";
 
+/// GPT-2风格正则表达式模式，数字不限长度，不区分缩写的大小写
+pub const GPT2_PATTERN: &str =
+    r"'(?:[sdmt]|ll|ve|re)| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+";
+
+/// 面向源代码的正则表达式模式
+///
+/// 与GPT-4模式相比：换行符单独成片，不与前面的标点或空白合并；行首的缩进整体成片，
+/// 便于学到常见的缩进层级；下划线属于标识符，`snake_case` 名称保持完整；数字最多三位一组。
+pub const CODE_PATTERN: &str = r"(?m)\r?\n|\r|^[^\S\r\n]+| ?[\p{L}\p{M}_][\p{L}\p{M}\p{N}_]*|\p{N}{1,3}| ?[^\s\p{L}\p{M}\p{N}_]+|[^\S\r\n]+(?!\S)|[^\S\r\n]+";
+
+/// 面向源代码、并在 `camelCase`、`snake_case` 边界拆分标识符的正则表达式模式
+///
+/// 其余规则同 [`CODE_PATTERN`]。`getHTTPResponse_code` 切分为 `get`、`HTTP`、`Response`、`_code`，
+/// 不同命名风格中的同一个词可以共享标记。
+pub const CODE_SUBWORDS_PATTERN: &str = r"(?m)\r?\n|\r|^[^\S\r\n]+| ?_*(?:\p{Lu}+(?=\p{Lu}[\p{Ll}\p{Lt}\p{Lm}\p{Lo}\p{M}])|\p{Lu}?[\p{Ll}\p{Lt}\p{Lm}\p{Lo}\p{M}]+|\p{Lu}+)|_+|\p{N}{1,3}| ?[^\s\p{L}\p{M}\p{N}_]+|[^\S\r\n]+(?!\S)|[^\S\r\n]+";
+
+/// tiktoken `cl100k_base` 使用的正则表达式模式
+///
+/// 与 [`GPT4_PATTERN`] 相比，文本末尾的空白整体成片，最后一个分支每次只取一个空白字符。
+pub const CL100K_PATTERN: &str = r"'(?i:[sdmt]|ll|ve|re)|[^\r\n\p{L}\p{N}]?+\p{L}++|\p{N}{1,3}+| ?[^\s\p{L}\p{N}]++[\r\n]*+|\s++$|\s*[\r\n]|\s+(?!\S)|\s";
+
+/// tiktoken `o200k_base` 使用的正则表达式模式，按大小写边界拆分单词，缩写跟随前面的单词
+pub const O200K_PATTERN: &str = r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]*[\p{Ll}\p{Lm}\p{Lo}\p{M}]+(?i:'s|'t|'re|'ve|'m|'ll|'d)?|[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]+[\p{Ll}\p{Lm}\p{Lo}\p{M}]*(?i:'s|'t|'re|'ve|'m|'ll|'d)?|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n/]*|\s*[\r\n]+|\s+(?!\S)|\s+";
+
+/// 可以按名称引用的预分词正则表达式
+pub const PATTERN_PRESETS: &[(&str, &str)] = &[
+    ("gpt4", GPT4_PATTERN),
+    ("gpt2", GPT2_PATTERN),
+    ("code", CODE_PATTERN),
+    ("code_subwords", CODE_SUBWORDS_PATTERN),
+    ("cl100k", CL100K_PATTERN),
+    ("o200k", O200K_PATTERN),
+];
+
+/// `preset_or_regex` 为 [`PATTERN_PRESETS`] 中的名称时返回对应的正则表达式，否则原样返回
+#[must_use]
+pub fn resolve_pattern(preset_or_regex: &str) -> &str {
+    PATTERN_PRESETS
+        .iter()
+        .find(|(name, _)| *name == preset_or_regex)
+        .map_or(preset_or_regex, |&(_, pattern)| pattern)
+}
+
+/// 编译预分词正则表达式，`preset_or_regex` 为 [`PATTERN_PRESETS`] 中的名称时使用对应的模式
+///
+/// # Errors
+///
+/// 当正则表达式无效时返回错误
+pub fn compile_pattern(preset_or_regex: &str) -> Result<Regex, TokenizerError> {
+    Regex::new(resolve_pattern(preset_or_regex)).map_err(|e| TokenizerError::InvalidRegex {
+        message: e.to_string(),
+    })
+}
+
+/// 使用给定的预分词器（如正则表达式）分割文本，规则同 [`TokenizerBase::split_text`]
+///
+/// # Errors
+///
+/// 当正则表达式匹配失败（如回溯超限）时返回错误，错误信息包含失败位置的字节偏移
+pub fn split_with<P>(pre_tokenizer: &P, text: &str) -> Result<Vec<String>, TokenizerError>
+where
+    P: PreTokenizer + ?Sized,
+{
+    let mut parts = Vec::new();
+    for_each_piece(pre_tokenizer, text, |piece| {
+        parts.push(piece.to_string());
+        Ok(())
+    })?;
+    Ok(parts)
+}
+
+/// 与 [`split_with`] 规则相同，但直接把借用原文的片段依次交给 `f`，不为片段分配字符串
+///
+/// # Errors
+///
+/// 当正则表达式匹配失败或 `f` 返回错误时返回错误
+pub fn for_each_piece<P, F>(pre_tokenizer: &P, text: &str, mut f: F) -> Result<(), TokenizerError>
+where
+    P: PreTokenizer + ?Sized,
+    F: FnMut(&str) -> Result<(), TokenizerError>,
+{
+    pre_tokenizer.pre_tokenize(text, &mut f)
+}
+
+thread_local! {
+    /// 编码热路径复用的标记ID缓冲区，见 [`with_id_scratch`]
+    static ID_SCRATCH: std::cell::Cell<Vec<u32>> = const { std::cell::Cell::new(Vec::new()) };
+}
+
+/// 缓冲区容量超过此值时用完即释放，避免个别超长片段让线程长期占用内存
+const ID_SCRATCH_RETAIN_LIMIT: usize = 1 << 16;
+
+/// 借出当前线程的标记ID缓冲区，借出时为空
+///
+/// 逐片段编码时用它代替每个片段新建的 `Vec`，同一线程上的后续片段复用已分配的容量。
+/// 嵌套调用时内层得到一个新的空缓冲区，不会与外层冲突。
+pub fn with_id_scratch<R>(f: impl FnOnce(&mut Vec<u32>) -> R) -> R {
+    let mut ids = ID_SCRATCH.with(std::cell::Cell::take);
+    ids.clear();
+    let result = f(&mut ids);
+    if ids.capacity() <= ID_SCRATCH_RETAIN_LIMIT {
+        ID_SCRATCH.with(|scratch| scratch.set(ids));
+    }
+    result
+}
+
 /// 分词器基础实现，提供通用功能
 #[derive(Clone)]
 pub struct TokenizerBase<Id>
@@ -24,6 +147,22 @@ where
     pub pattern: String,
     /// 编译后的正则表达式
     pub compiled_pattern: Regex,
+    /// 预分词之前的规范化流水线，随模型保存
+    pub normalizer: Normalizer,
+    /// 预分词流水线，其中的正则表达式步骤使用 `compiled_pattern`，随模型保存
+    pub pre_tokenizer: PreTokenizerPipeline,
+    /// 把标记序列还原为文本的解码器，随模型保存
+    pub decoder: DecoderKind,
+    /// 训练时并行计数的任务划分粒度
+    pub parallel: ParallelChunking,
+    /// 训练时计数相同的配对之间的合并顺序
+    pub tie_break: TieBreak,
+    /// 训练配置，见 [`TrainerConfig`]
+    pub trainer: TrainerConfig,
+    /// 特殊标记注册表，编码时整体匹配、训练时从语料中去除
+    pub special_tokens: SpecialTokens,
+    /// `encode_plus` 使用的截断与补齐配置，不随模型文件保存
+    pub encode_options: EncodeOptions,
 }
 
 impl<Id: Clone + Serialize + for<'de> Deserialize<'de> + Eq + Hash + std::fmt::Debug + Default>
@@ -34,31 +173,52 @@ impl<Id: Clone + Serialize + for<'de> Deserialize<'de> + Eq + Hash + std::fmt::D
     /// # Errors
     ///
     /// 当默认正则表达式模式编译失败时返回错误（这种情况极少发生）
-    pub fn new() -> Result<Self, String> {
+    pub fn new() -> Result<Self, TokenizerError> {
         let pattern = GPT4_PATTERN.to_string();
-        let compiled_pattern =
-            Regex::new(&pattern).map_err(|e| format!("无效的正则表达式: {}", e))?;
+        let compiled_pattern = Regex::new(&pattern).map_err(|e| TokenizerError::InvalidRegex {
+            message: e.to_string(),
+        })?;
 
         Ok(Self {
             vocab: VocabManager::new(),
             pattern,
             compiled_pattern,
+            normalizer: Normalizer::default(),
+            pre_tokenizer: PreTokenizerPipeline::default(),
+            decoder: DecoderKind::default(),
+            parallel: ParallelChunking::default(),
+            tie_break: TieBreak::default(),
+            trainer: TrainerConfig::default(),
+            special_tokens: SpecialTokens::default(),
+            encode_options: EncodeOptions::default(),
         })
     }
 
     /// 使用自定义正则表达式模式创建分词器基础结构
     ///
+    /// `pattern` 也可以是 [`PATTERN_PRESETS`] 中的名称，保存时写出对应的完整正则表达式
+    ///
     /// # Errors
     ///
     /// 当提供的正则表达式模式无效或编译失败时返回错误
-    pub fn with_pattern(pattern: String) -> Result<Self, String> {
-        let compiled_pattern =
-            Regex::new(&pattern).map_err(|e| format!("无效的正则表达式: {}", e))?;
+    pub fn with_pattern(pattern: String) -> Result<Self, TokenizerError> {
+        let pattern = resolve_pattern(&pattern).to_string();
+        let compiled_pattern = Regex::new(&pattern).map_err(|e| TokenizerError::InvalidRegex {
+            message: e.to_string(),
+        })?;
 
         Ok(Self {
             vocab: VocabManager::new(),
             pattern,
             compiled_pattern,
+            normalizer: Normalizer::default(),
+            pre_tokenizer: PreTokenizerPipeline::default(),
+            decoder: DecoderKind::default(),
+            parallel: ParallelChunking::default(),
+            tie_break: TieBreak::default(),
+            trainer: TrainerConfig::default(),
+            special_tokens: SpecialTokens::default(),
+            encode_options: EncodeOptions::default(),
         })
     }
 
@@ -67,13 +227,13 @@ impl<Id: Clone + Serialize + for<'de> Deserialize<'de> + Eq + Hash + std::fmt::D
     /// # Errors
     ///
     /// 当标记已存在于词汇表中或ID已被使用时返回错误
-    pub fn add_token(&mut self, token: &str, id: Id) -> Result<(), String> {
+    pub fn add_token(&mut self, token: &str, id: Id) -> Result<(), TokenizerError> {
         if self.vocab.contains_value(&token.to_string()) {
-            return Err(format!("标记 '{}' 已存在于词汇表中", token));
+            return Err(vocab_error(format!("标记 '{}' 已存在于词汇表中", token)));
         }
 
         if self.vocab.contains_id(&id) {
-            return Err(format!("ID '{:?}' 已存在于词汇表中", id));
+            return Err(vocab_error(format!("ID '{:?}' 已存在于词汇表中", id)));
         }
 
         self.vocab.insert(id, token.to_string());
@@ -98,26 +258,57 @@ impl<Id: Clone + Serialize + for<'de> Deserialize<'de> + Eq + Hash + std::fmt::D
         self.vocab.len()
     }
 
-    /// 使用正则表达式分割文本
+    /// 按 [`TokenizerBase::normalizer`] 规范化后使用预分词流水线分割文本
     ///
     /// # Errors
     ///
-    /// 当正则表达式匹配失败时返回错误。如果正则表达式无法匹配任何内容，
-    /// 将使用空格分割作为后备方案
-    pub fn split_text(&self, text: &str) -> Result<Vec<String>, String> {
-        let parts: Vec<String> = self
-            .compiled_pattern
-            .find_iter(text)
-            .filter_map(|m| m.ok())
-            .map(|m| m.as_str().to_string())
-            .collect();
-
-        if parts.is_empty() && !text.is_empty() {
-            // 如果正则表达式没有匹配任何内容，使用空格分割作为后备
-            Ok(text.split_whitespace().map(|s| s.to_string()).collect())
-        } else {
-            Ok(parts)
+    /// 当正则表达式匹配失败（如回溯超限）时返回错误，错误信息包含失败位置的字节偏移。
+    /// 如果正则表达式无法匹配任何内容，将使用空格分割作为后备方案
+    pub fn split_text(&self, text: &str) -> Result<Vec<String>, TokenizerError> {
+        split_with(&self.pre_tokenizer(), &self.normalizer.apply(text))
+    }
+
+    /// 按编码时的方式规范化 `text`：特殊标记保持原样，其余各段由 [`TokenizerBase::normalizer`]
+    /// 分别规范化，并记录与原文的对齐
+    #[must_use]
+    pub fn normalize_aligned(&self, text: &str) -> NormalizedText {
+        if self.normalizer.is_empty() {
+            return NormalizedText::new(text);
         }
+        let mut result = NormalizedText::default();
+        let mut offset = 0;
+        for segment in self.special_tokens.split(text) {
+            let len = match segment {
+                Segment::Text(segment) => {
+                    result.push_normalized(&self.normalizer.apply_aligned(segment), offset);
+                    segment.len()
+                }
+                Segment::Special(id) => {
+                    let token = self.special_tokens.token(id).unwrap_or_default();
+                    result.push_unchanged(token, offset);
+                    token.len()
+                }
+            };
+            offset += len;
+        }
+        result
+    }
+
+    /// 绑定了 `compiled_pattern` 的预分词流水线
+    #[must_use]
+    pub fn pre_tokenizer(&self) -> BoundPipeline<'_> {
+        self.pre_tokenizer.with_pattern(&self.compiled_pattern)
+    }
+
+    /// 更换预分词正则表达式，`pattern` 也可以是 [`PATTERN_PRESETS`] 中的名称
+    ///
+    /// # Errors
+    ///
+    /// 当正则表达式无效时返回错误，原有模式保持不变
+    pub fn set_pattern(&mut self, pattern: &str) -> Result<(), TokenizerError> {
+        self.compiled_pattern = compile_pattern(pattern)?;
+        self.pattern = resolve_pattern(pattern).to_string();
+        Ok(())
     }
 
     /// 保存分词器到文件
@@ -125,27 +316,52 @@ impl<Id: Clone + Serialize + for<'de> Deserialize<'de> + Eq + Hash + std::fmt::D
     /// # Errors
     ///
     /// 当无法创建目录、文件创建失败或写入操作失败时返回错误
-    pub fn save(&self, path: &str) -> Result<(), String> {
+    pub fn save(&self, path: &str) -> Result<(), TokenizerError> {
         let path = Path::new(path);
         if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+            std::fs::create_dir_all(parent)
+                .map_err(|e| model_save_error(format!("创建目录失败: {}", e)))?;
         }
-
-        let file = File::create(path).map_err(|e| format!("创建文件失败: {}", e))?;
+        let file =
+            File::create(path).map_err(|e| model_save_error(format!("创建文件失败: {}", e)))?;
         let mut writer = io::BufWriter::new(file);
 
         // 写入正则表达式模式
         writeln!(writer, "pattern: {}", self.pattern)
-            .map_err(|e| format!("写入正则表达式失败: {}", e))?;
+            .map_err(|e| model_save_error(format!("写入正则表达式失败: {}", e)))?;
 
         // 写入词汇表
         writeln!(writer, "vocab_size: {}", self.vocab.len())
-            .map_err(|e| format!("写入词汇表大小失败: {}", e))?;
+            .map_err(|e| model_save_error(format!("写入词汇表大小失败: {}", e)))?;
 
         for (id, token) in self.vocab.iter() {
-            let id_str = serde_json::to_string(id).map_err(|e| format!("序列化ID失败: {}", e))?;
+            let id_str = serde_json::to_string(id)
+                .map_err(|e| model_save_error(format!("序列化ID失败: {}", e)))?;
             writeln!(writer, "{} {}", token, id_str)
-                .map_err(|e| format!("写入词汇表项失败: {}", e))?;
+                .map_err(|e| model_save_error(format!("写入词汇表项失败: {}", e)))?;
+        }
+
+        // 特殊标记、规范化和预分词流水线以及解码器紧跟在词汇表之后，位于各分词器追加的数据之前
+        for line in self.special_tokens.to_lines() {
+            writeln!(writer, "{}", line)
+                .map_err(|e| model_save_error(format!("写入特殊标记失败: {}", e)))?;
+        }
+        if !self.normalizer.is_empty() {
+            writeln!(writer, "{}{}", NORMALIZER_HEADER, self.normalizer.spec())
+                .map_err(|e| model_save_error(format!("写入规范化流水线失败: {}", e)))?;
+        }
+        if !self.pre_tokenizer.is_default() {
+            writeln!(
+                writer,
+                "{}{}",
+                PRE_TOKENIZER_HEADER,
+                self.pre_tokenizer.spec()
+            )
+            .map_err(|e| model_save_error(format!("写入预分词流水线失败: {}", e)))?;
+        }
+        if self.decoder != DecoderKind::Fuse {
+            writeln!(writer, "{}{}", DECODER_HEADER, self.decoder.name())
+                .map_err(|e| model_save_error(format!("写入解码器失败: {}", e)))?;
         }
 
         Ok(())
@@ -155,48 +371,169 @@ impl<Id: Clone + Serialize + for<'de> Deserialize<'de> + Eq + Hash + std::fmt::D
     ///
     /// # Errors
     ///
-    /// 当文件不存在、文件格式无效、正则表达式编译失败或ID反序列化失败时返回错误
-    pub fn load(&mut self, path: &str) -> Result<(), String> {
-        let file = File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
-        let reader = BufReader::new(file);
+    /// 当文件不存在、文件格式无效、正则表达式编译失败或ID反序列化失败时返回错误，
+    /// 无效的词汇表行返回 [`TokenizerError::InvalidVocabEntry`]
+    pub fn load(&mut self, path: &str) -> Result<(), TokenizerError> {
+        let file =
+            File::open(path).map_err(|e| model_load_error(format!("打开文件失败: {}", e)))?;
+        self.load_from_reader(BufReader::new(file))
+    }
 
-        // 清空当前词汇表
+    /// 从任意输入源加载分词器，格式与 [`TokenizerBase::save`] 写出的文件相同
+    ///
+    /// # Errors
+    ///
+    /// 当读取失败、格式无效、正则表达式编译失败或ID反序列化失败时返回错误，
+    /// 无效的词汇表行返回 [`TokenizerError::InvalidVocabEntry`]
+    pub fn load_from_reader<R: BufRead>(&mut self, reader: R) -> Result<(), TokenizerError> {
+        // 清空当前词汇表、特殊标记、规范化和预分词流水线以及解码器
         self.vocab.clear();
+        self.special_tokens.clear();
+        self.normalizer = Normalizer::default();
+        self.pre_tokenizer = PreTokenizerPipeline::default();
+        self.decoder = DecoderKind::default();
 
-        let mut lines = reader.lines();
+        let read_error = |e: io::Error| model_load_error(format!("读取行失败: {}", e));
+        let mut lines = reader.lines().peekable();
 
         // 读取正则表达式模式
-        if let Some(Ok(line)) = lines.next() {
-            if let Some(pattern_str) = line.strip_prefix("pattern: ") {
-                self.pattern = pattern_str.to_string();
-                self.compiled_pattern =
-                    Regex::new(&self.pattern).map_err(|e| format!("无效的正则表达式: {}", e))?;
-            }
-        }
-
-        // 跳过词汇表大小行
-        let _ = lines.next();
-
-        // 读取词汇表
-        for line in lines {
-            let line = line.map_err(|e| format!("读取行失败: {}", e))?;
+        let line = lines
+            .next()
+            .ok_or_else(|| model_load_error("模型文件为空"))?
+            .map_err(read_error)?;
+        let pattern_str = line
+            .strip_prefix("pattern: ")
+            .ok_or_else(|| model_load_error("无效的模型文件格式: 缺少pattern行"))?;
+        self.pattern = pattern_str.to_string();
+        self.compiled_pattern =
+            Regex::new(&self.pattern).map_err(|e| TokenizerError::InvalidRegex {
+                message: e.to_string(),
+            })?;
+
+        // 读取词汇表大小行，子类追加的数据位于词汇表条目之后，不能一并解析
+        let line = lines
+            .next()
+            .ok_or_else(|| model_load_error("无效的模型文件格式: 缺少vocab_size行"))?
+            .map_err(read_error)?;
+        let vocab_size = line
+            .strip_prefix("vocab_size: ")
+            .and_then(|n| n.trim().parse::<usize>().ok())
+            .ok_or_else(|| model_load_error("无效的模型文件格式: vocab_size行无效"))?;
+
+        // 读取词汇表，条目从第3行开始
+        let mut entries = Vec::with_capacity(vocab_size);
+        for (index, line) in lines.by_ref().take(vocab_size).enumerate() {
+            let line = line.map_err(read_error)?;
             if line.trim().is_empty() {
                 continue;
             }
+            let invalid = |message: String| TokenizerError::InvalidVocabEntry {
+                line: index + 3,
+                message,
+            };
 
-            let mut parts = line.splitn(2, ' ');
-            let token = parts.next().ok_or("无效的词汇表行")?;
-            let id_str = parts.next().ok_or("无效的词汇表行")?;
+            // ID位于行尾，标记本身可能包含空格；ID之后的空白是编辑器留下的，可以去掉
+            let (token, id_str) = line
+                .trim_end()
+                .rsplit_once(' ')
+                .ok_or_else(|| invalid("缺少ID".to_string()))?;
 
-            let id: Id =
-                serde_json::from_str(id_str).map_err(|e| format!("反序列化ID失败: {}", e))?;
+            let id: Id = serde_json::from_str(id_str)
+                .map_err(|e| invalid(format!("反序列化ID失败: {}", e)))?;
+
+            entries.push((id, token.to_string()));
+        }
+        check_entries(&entries)?;
+        for (id, token) in entries {
+            self.vocab.insert(id, token);
+        }
 
-            self.vocab.insert(id, token.to_string());
+        // 读取紧跟在词汇表之后的特殊标记、规范化和预分词流水线以及解码器
+        while let Some(Ok(line)) = lines.peek() {
+            if let Some(spec) = line.strip_prefix(NORMALIZER_HEADER) {
+                self.normalizer = Normalizer::parse(spec)?;
+            } else if let Some(spec) = line.strip_prefix(PRE_TOKENIZER_HEADER) {
+                self.pre_tokenizer = PreTokenizerPipeline::parse(spec)?;
+            } else if let Some(name) = line.strip_prefix(DECODER_HEADER) {
+                self.decoder = DecoderKind::from_name(name)?;
+            } else if !self.special_tokens.parse_line(line)? {
+                break;
+            }
+            lines.next();
         }
 
         Ok(())
     }
 
+    /// 把正则表达式模式、特殊标记、规范化和预分词流水线以及解码器写入二进制模型，词汇表由各分词器写出
+    pub fn write_binary(&self, model: &mut BinaryModel) {
+        let mut pattern = SectionWriter::new();
+        pattern.str(&self.pattern);
+        model.push(binary::tag::PATTERN, pattern);
+        model.push(
+            binary::tag::SPECIAL_TOKENS,
+            binary::write_special_tokens(&self.special_tokens),
+        );
+        if !self.normalizer.is_empty() {
+            let mut normalizer = SectionWriter::new();
+            normalizer.str(&self.normalizer.spec());
+            model.push(binary::tag::NORMALIZER, normalizer);
+        }
+        if !self.pre_tokenizer.is_default() {
+            let mut pre_tokenizer = SectionWriter::new();
+            pre_tokenizer.str(&self.pre_tokenizer.spec());
+            model.push(binary::tag::PRE_TOKENIZER, pre_tokenizer);
+        }
+        if self.decoder != DecoderKind::Fuse {
+            let mut decoder = SectionWriter::new();
+            decoder.str(self.decoder.name());
+            model.push(binary::tag::DECODER, decoder);
+        }
+    }
+
+    /// 从二进制模型读取正则表达式模式、特殊标记、规范化和预分词流水线以及解码器，并清空词汇表
+    ///
+    /// # Errors
+    ///
+    /// 当缺少必需的段、段内容无效或正则表达式编译失败时返回错误
+    pub fn read_binary(&mut self, model: &BinaryModel) -> Result<(), TokenizerError> {
+        let mut reader = model.required(binary::tag::PATTERN)?;
+        let pattern = reader.str()?.to_string();
+        reader.finish()?;
+        self.compiled_pattern = Regex::new(&pattern).map_err(|e| TokenizerError::InvalidRegex {
+            message: e.to_string(),
+        })?;
+        self.pattern = pattern;
+        self.special_tokens =
+            binary::read_special_tokens(model.required(binary::tag::SPECIAL_TOKENS)?)?;
+        self.normalizer = match model.section(binary::tag::NORMALIZER) {
+            Some(mut reader) => {
+                let normalizer = Normalizer::parse(reader.str()?)?;
+                reader.finish()?;
+                normalizer
+            }
+            None => Normalizer::default(),
+        };
+        self.pre_tokenizer = match model.section(binary::tag::PRE_TOKENIZER) {
+            Some(mut reader) => {
+                let pre_tokenizer = PreTokenizerPipeline::parse(reader.str()?)?;
+                reader.finish()?;
+                pre_tokenizer
+            }
+            None => PreTokenizerPipeline::default(),
+        };
+        self.decoder = match model.section(binary::tag::DECODER) {
+            Some(mut reader) => {
+                let decoder = DecoderKind::from_name(reader.str()?)?;
+                reader.finish()?;
+                decoder
+            }
+            None => DecoderKind::default(),
+        };
+        self.vocab.clear();
+        Ok(())
+    }
+
     // 注意：load_vocab_from_dict() 方法已被移除
     // 每个分词器都有自己的实现，因为ID生成策略因分词器而异
     // 参见各分词器模块中的 _load_vocab_from_dict() 方法
@@ -213,35 +550,384 @@ impl<Id: Clone + Serialize + for<'de> Deserialize<'de> + Eq + Hash + std::fmt::D
     }
 }
 
+/// 检查词汇表条目中重复的ID和标记，插入词汇表后这些重复会被静默覆盖
+fn check_entries<Id: Hash + Eq + std::fmt::Debug>(
+    entries: &[(Id, String)],
+) -> Result<(), TokenizerError> {
+    let mut ids = HashMap::with_capacity(entries.len());
+    let mut tokens = HashMap::with_capacity(entries.len());
+    for (id, token) in entries {
+        if let Some(previous) = ids.insert(id, token) {
+            return Err(model_load_error(format!(
+                "模型完整性检查失败: ID {:?} 重复出现: {:?} 和 {:?}",
+                id, previous, token
+            )));
+        }
+        if let Some(previous) = tokens.insert(token, id) {
+            return Err(model_load_error(format!(
+                "模型完整性检查失败: 标记 {:?} 对应多个ID: {:?} 和 {:?}",
+                token, previous, id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 字符串词汇表中单个标记解码后的字节
+///
+/// `<0xNN>` 形式的字节标记解码为单个字节，其余标记按原文输出
+pub fn piece_bytes(piece: &str) -> Cow<'_, [u8]> {
+    let byte = piece
+        .strip_prefix("<0x")
+        .and_then(|s| s.strip_suffix('>'))
+        .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+    match byte {
+        Some(byte) => Cow::Owned(vec![byte]),
+        None => Cow::Borrowed(piece.as_bytes()),
+    }
+}
+
+/// 按ID升序列出字符串词汇表中每个标记解码后的字节，规则同 [`piece_bytes`]
+pub fn piece_vocab_bytes(vocab: &VocabManager<u32, String>) -> Vec<(u32, Cow<'_, [u8]>)> {
+    let mut entries: Vec<(u32, Cow<'_, [u8]>)> = vocab
+        .iter()
+        .map(|(&id, piece)| (id, piece_bytes(piece)))
+        .collect();
+    entries.sort_unstable_by_key(|&(id, _)| id);
+    entries
+}
+
+/// 合并规则映射类型：(左标记, 右标记) -> 新标记ID
+///
+/// 按插入顺序保存，第 `i` 条规则的等级为 `i`。训练时可能复用已有ID，预置的基础字符也占用了部分ID，
+/// 新标记ID不一定随等级递增，因此等级只以插入顺序为准
+pub type MergeMap = IndexMap<(u32, u32), u32>;
+
+/// 按等级（训练顺序）排列合并规则，第 `i` 项的等级为 `i`
+pub fn ranked_merges(merges: &MergeMap) -> Vec<((u32, u32), u32)> {
+    merges.iter().map(|(&pair, &id)| (pair, id)).collect()
+}
+
+/// 片段长度低于此值时逐轮扫描，否则使用优先队列
+const RANKED_MERGE_SCAN_LIMIT: usize = 32;
+
+/// 按训练顺序在 `ids` 上应用合并规则，结果与训练时对同一片段的切分一致
+///
+/// 每一步合并等级最高（在 [`MergeMap`] 中最靠前）的词对，等级相同时先合并最左边的一处，
+/// 没有可合并的词对时停止，与GPT-2、tiktoken的编码规则相同。短片段逐轮扫描；
+/// 长片段使用优先队列和双向链表，复杂度为 O(n log n)。
+pub fn apply_ranked_merges(ids: &mut Vec<u32>, merges: &MergeMap) {
+    if ids.len() < 2 {
+        return;
+    }
+    if ids.len() < RANKED_MERGE_SCAN_LIMIT {
+        while let Some((_, i, new_id)) = ids
+            .windows(2)
+            .enumerate()
+            .filter_map(|(i, pair)| {
+                let (rank, _, &new_id) = merges.get_full(&(pair[0], pair[1]))?;
+                Some((rank, i, new_id))
+            })
+            .min()
+        {
+            ids[i] = new_id;
+            ids.remove(i + 1);
+        }
+        return;
+    }
+
+    // 节点按原始位置编号，合并后保留左节点，因此编号顺序始终与从左到右的顺序一致
+    let len = ids.len();
+    let mut next: Vec<usize> = (1..=len).collect();
+    let mut prev: Vec<usize> = (0..len).map(|i| i.wrapping_sub(1)).collect();
+    let mut alive = vec![true; len];
+    // 队列中的条目：(等级, 左节点, 左标记, 右标记)，取出时核对标记以跳过过期条目
+    let mut heap = dary_heap::OctonaryHeap::new();
+    for (i, pair) in ids.windows(2).enumerate() {
+        if let Some(rank) = merges.get_index_of(&(pair[0], pair[1])) {
+            heap.push(std::cmp::Reverse((rank, i, pair[0], pair[1])));
+        }
+    }
+
+    while let Some(std::cmp::Reverse((rank, left, left_id, right_id))) = heap.pop() {
+        let right = next[left];
+        if !alive[left] || right >= len || ids[left] != left_id || ids[right] != right_id {
+            continue;
+        }
+        let new_id = merges[rank];
+        ids[left] = new_id;
+        alive[right] = false;
+        next[left] = next[right];
+        if next[left] < len {
+            prev[next[left]] = left;
+        }
+
+        let before = prev[left];
+        if before < len {
+            if let Some(merged) = merges.get_index_of(&(ids[before], new_id)) {
+                heap.push(std::cmp::Reverse((merged, before, ids[before], new_id)));
+            }
+        }
+        let after = next[left];
+        if after < len {
+            if let Some(merged) = merges.get_index_of(&(new_id, ids[after])) {
+                heap.push(std::cmp::Reverse((merged, left, new_id, ids[after])));
+            }
+        }
+    }
+
+    let mut write = 0;
+    for read in 0..len {
+        if alive[read] {
+            ids[write] = ids[read];
+            write += 1;
+        }
+    }
+    ids.truncate(write);
+}
+
+/// 模型文件中的一条合并规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeEntry {
+    /// 合并的词对
+    pub pair: (u32, u32),
+    /// 合并产生的新标记ID
+    pub new_id: u32,
+    /// 显式记录的等级，旧格式的文件没有这一列
+    pub rank: Option<u32>,
+}
+
+impl MergeEntry {
+    /// 解析 `a b new_id [rank]` 形式的合并规则行（不含 `merge: ` 前缀）
+    ///
+    /// # Errors
+    ///
+    /// 当列数不是3或4，或任意一列不是整数时返回错误
+    pub fn parse(data: &str) -> Result<Self, TokenizerError> {
+        let fields = data
+            .split_whitespace()
+            .map(|field| {
+                field
+                    .parse::<u32>()
+                    .map_err(|e| model_load_error(format!("解析合并规则失败: {}: {}", data, e)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match fields[..] {
+            [a, b, new_id] => Ok(Self {
+                pair: (a, b),
+                new_id,
+                rank: None,
+            }),
+            [a, b, new_id, rank] => Ok(Self {
+                pair: (a, b),
+                new_id,
+                rank: Some(rank),
+            }),
+            _ => Err(model_load_error(format!("合并规则应为3或4列: {}", data))),
+        }
+    }
+
+    /// 写入模型文件的形式（不含 `merge: ` 前缀），与 [`MergeEntry::parse`] 互逆
+    pub fn to_line(&self) -> String {
+        match self.rank {
+            Some(rank) => format!("{} {} {} {}", self.pair.0, self.pair.1, self.new_id, rank),
+            None => format!("{} {} {}", self.pair.0, self.pair.1, self.new_id),
+        }
+    }
+}
+
+/// 按等级顺序恢复合并规则
+///
+/// 所有条目都带有等级时按等级排序，并要求等级恰好为 `0..n`；
+/// 旧格式的文件没有等级，按新标记ID排序
+///
+/// # Errors
+///
+/// 当只有部分条目带有等级，或等级重复、不连续时返回错误
+pub fn restore_ranked_merges(
+    mut entries: Vec<MergeEntry>,
+) -> Result<Vec<MergeEntry>, TokenizerError> {
+    let ranked = entries.iter().filter(|entry| entry.rank.is_some()).count();
+    if ranked == 0 {
+        entries.sort_unstable_by_key(|entry| (entry.new_id, entry.pair));
+        return Ok(entries);
+    }
+    if ranked != entries.len() {
+        return Err(model_load_error("部分合并规则缺少等级"));
+    }
+
+    entries.sort_unstable_by_key(|entry| entry.rank);
+    for (expected, entry) in (0u32..).zip(&entries) {
+        if entry.rank != Some(expected) {
+            return Err(model_load_error(format!(
+                "合并规则的等级不连续：期望 {}，实际 {:?}",
+                expected, entry.rank
+            )));
+        }
+    }
+    Ok(entries)
+}
+
+/// 跳过 [`TokenizerBase::save`] 写出的正则表达式、词汇表大小、词汇表、特殊标记、规范化和预分词流水线
+/// 以及解码器各行，返回之后由各分词器追加的行
+///
+/// 按词汇表大小跳过而不是按前缀查找，避免把内容恰好像追加数据的标记误认为追加数据
+///
+/// # Errors
+///
+/// 当缺少有效的vocab_size行时返回错误
+pub fn lines_after_vocab(model: &str) -> Result<impl Iterator<Item = &str>, TokenizerError> {
+    let mut lines = model.lines();
+    lines.next();
+    let vocab_size = lines
+        .next()
+        .and_then(|line| line.strip_prefix("vocab_size: "))
+        .and_then(|n| n.trim().parse::<usize>().ok())
+        .ok_or_else(|| model_load_error("无效的模型文件格式: vocab_size行无效"))?;
+    Ok(lines.skip(vocab_size).skip_while(|line| {
+        line.starts_with(SPECIAL_TOKEN_HEADER)
+            || line.starts_with(RESERVED_IDS_HEADER)
+            || line.starts_with(NORMALIZER_HEADER)
+            || line.starts_with(PRE_TOKENIZER_HEADER)
+            || line.starts_with(DECODER_HEADER)
+    }))
+}
+
+/// 把模型数据中的 `\r\n` 换行统一为 `\n`，没有 `\r\n` 时不复制
+///
+/// 在Windows上编辑过的模型文件可能混用两种换行，BBPE的段索引按字节记录长度，必须先统一换行才能对齐。
+/// 保存时总是写出 `\n`，单独的 `\r` 可能是标记内容，保持不变
+#[must_use]
+pub fn normalize_line_endings(data: &[u8]) -> Cow<'_, [u8]> {
+    if !data.windows(2).any(|pair| pair == b"\r\n") {
+        return Cow::Borrowed(data);
+    }
+    let mut normalized = Vec::with_capacity(data.len());
+    let mut bytes = data.iter().peekable();
+    while let Some(&byte) = bytes.next() {
+        if byte != b'\r' || bytes.peek() != Some(&&b'\n') {
+            normalized.push(byte);
+        }
+    }
+    Cow::Owned(normalized)
+}
+
 /// 词对计数映射类型：(Id, Id) -> 计数
 pub type PairCounts<Id> = HashMap<(Id, Id), i32>;
 
 /// 词对位置映射类型：(Id, Id) -> 词位置列表
 pub type PairPositions<Id> = HashMap<(Id, Id), Vec<usize>>;
 
-/// 并行计算词对频率的通用函数
+/// 并行计数的任务划分粒度
+///
+/// 默认交给rayon自适应划分并两两归并。语料中片段长度差异很大时，自适应划分可能把少数超长片段
+/// 分到同一个任务里，此时可以固定每个任务的片段数，并增大归并树的宽度以减少中间映射的合并次数。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelChunking {
+    /// 每个rayon任务处理的片段数，0表示由rayon自适应划分
+    pub pieces_per_task: usize,
+    /// 归并树每个节点合并的局部计数个数，至少为2
+    pub reduce_width: usize,
+}
+
+impl Default for ParallelChunking {
+    fn default() -> Self {
+        Self {
+            pieces_per_task: 0,
+            reduce_width: 2,
+        }
+    }
+}
+
+impl ParallelChunking {
+    /// 创建指定粒度的划分配置
+    ///
+    /// # Errors
+    ///
+    /// 当 `reduce_width` 小于2时返回错误
+    pub fn new(pieces_per_task: usize, reduce_width: usize) -> Result<Self, TokenizerError> {
+        if reduce_width < 2 {
+            return Err(input_validation_error(format!(
+                "归并树宽度至少为2，实际为 {}",
+                reduce_width
+            )));
+        }
+        Ok(Self {
+            pieces_per_task,
+            reduce_width,
+        })
+    }
+
+    /// 并行统计 `items` 中的键频率
+    ///
+    /// `count` 把单个元素的计数累加到局部映射中，键可以借用元素本身；
+    /// 各任务的局部映射按 `reduce_width` 逐层并行归并
+    pub fn count<'a, T, K, S, F>(&self, items: &'a [T], count: F) -> HashMap<K, i32, S>
+    where
+        T: Sync,
+        K: Eq + Hash + Send,
+        S: BuildHasher + Default + Send,
+        F: Fn(&'a T, &mut HashMap<K, i32, S>) + Sync,
+    {
+        use rayon::prelude::*;
+
+        let count_into = |mut local: HashMap<K, i32, S>, item: &'a T| {
+            count(item, &mut local);
+            local
+        };
+        let mut locals: Vec<HashMap<K, i32, S>> = if self.pieces_per_task == 0 {
+            items
+                .par_iter()
+                .fold(HashMap::default, count_into)
+                .collect()
+        } else {
+            items
+                .par_chunks(self.pieces_per_task)
+                .map(|chunk| chunk.iter().fold(HashMap::default(), count_into))
+                .collect()
+        };
+
+        let width = self.reduce_width.max(2);
+        while locals.len() > 1 {
+            locals = locals
+                .into_par_iter()
+                .chunks(width)
+                .map(|group| {
+                    let mut group = group.into_iter();
+                    let mut merged = group.next().unwrap_or_default();
+                    for local in group {
+                        for (key, value) in local {
+                            *merged.entry(key).or_insert(0) += value;
+                        }
+                    }
+                    merged
+                })
+                .collect();
+        }
+        locals.pop().unwrap_or_default()
+    }
+}
+
+/// 并行计算词对频率的通用函数，使用默认的任务划分粒度
 pub fn count_pairs_parallel<Id: Clone + Eq + Hash + Send + Sync>(
     words: &[Word<Id>],
     counts: &[i32],
 ) -> (PairCounts<Id>, PairPositions<Id>) {
-    use rayon::prelude::*;
-
-    let pair_counts: PairCounts<Id> = words
-        .par_iter()
-        .enumerate()
-        .map(|(i, word)| {
-            let mut local_counts = HashMap::new();
-            for pair in word.pairs() {
-                *local_counts.entry(pair).or_insert(0) += counts[i];
-            }
-            local_counts
-        })
-        .reduce(HashMap::new, |mut acc, local_counts| {
-            for (pair, count) in local_counts {
-                *acc.entry(pair).or_insert(0) += count;
-            }
-            acc
-        });
+    count_pairs_parallel_with(words, counts, ParallelChunking::default())
+}
+
+/// 按给定的任务划分粒度并行计算词对频率
+pub fn count_pairs_parallel_with<Id: Clone + Eq + Hash + Send + Sync>(
+    words: &[Word<Id>],
+    counts: &[i32],
+    chunking: ParallelChunking,
+) -> (PairCounts<Id>, PairPositions<Id>) {
+    let indexed: Vec<(&Word<Id>, i32)> = words.iter().zip(counts.iter().copied()).collect();
+    let pair_counts: PairCounts<Id> = chunking.count(&indexed, |&(word, count), local| {
+        for pair in word.pairs() {
+            *local.entry(pair).or_insert(0) += count;
+        }
+    });
 
     let mut where_to_update: PairPositions<Id> = HashMap::new();
     for (i, word) in words.iter().enumerate() {
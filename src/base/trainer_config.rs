@@ -0,0 +1,173 @@
+//! 训练配置
+//!
+//! `train(texts, vocab_size)` 只能指定词汇表大小。[`TrainerConfig`] 汇集各训练器共用的选项：
+//! 过滤低频合并、限制标记长度、在训练前注册特殊标记、控制字母表以及打乱训练文本。
+
+use std::collections::HashMap;
+
+use ahash::AHashSet;
+
+use crate::base::traits::SpecialTokenizer;
+use crate::corpus::shuffle::shuffle_seeded;
+use crate::error::TokenizerError;
+
+/// 训练配置，通过 `with_*` 方法逐项设置
+///
+/// 各训练器按自身的基本单位解释这些选项：BPE以字符为单位，BBPE、Unigram和WordPiece以字节为单位。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrainerConfig {
+    /// 合并或候选片段的最低出现次数，0和1都表示不过滤
+    pub min_frequency: u64,
+    /// 新标记的最大长度，BPE按字符计，其余按字节计（WordPiece不含续接前缀）；
+    /// `None` 时BPE、BBPE和WordPiece不限制，Unigram的候选片段最长16个字节
+    pub max_piece_length: Option<usize>,
+    /// 训练前注册的特殊标记，先于合并结果分配ID，并从语料中去除
+    pub special_tokens: Vec<String>,
+    /// 无论是否出现在语料中都加入字母表的字符
+    pub initial_alphabet: Vec<char>,
+    /// 字母表最多保留的字符数，对BBPE无效，不计入 `initial_alphabet`
+    /// 和词汇表中已有的字符
+    pub limit_alphabet: Option<usize>,
+    /// 设置时在统计片段之前按此种子打乱训练文本，见 [`shuffle_seeded`]
+    pub shuffle_seed: Option<u64>,
+}
+
+impl TrainerConfig {
+    /// 创建默认配置，与不带配置的 `train` 行为相同
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置最低出现次数
+    #[must_use]
+    pub fn with_min_frequency(mut self, min_frequency: u64) -> Self {
+        self.min_frequency = min_frequency;
+        self
+    }
+
+    /// 设置新标记的最大长度
+    #[must_use]
+    pub fn with_max_piece_length(mut self, max_piece_length: usize) -> Self {
+        self.max_piece_length = Some(max_piece_length);
+        self
+    }
+
+    /// 设置训练前注册的特殊标记
+    #[must_use]
+    pub fn with_special_tokens<I, S>(mut self, tokens: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.special_tokens = tokens.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 设置初始字母表，重复的字符只保留一个
+    #[must_use]
+    pub fn with_initial_alphabet<I: IntoIterator<Item = char>>(mut self, alphabet: I) -> Self {
+        let mut seen = AHashSet::new();
+        self.initial_alphabet = alphabet.into_iter().filter(|&ch| seen.insert(ch)).collect();
+        self
+    }
+
+    /// 设置字母表最多保留的字符数
+    #[must_use]
+    pub fn with_limit_alphabet(mut self, limit_alphabet: usize) -> Self {
+        self.limit_alphabet = Some(limit_alphabet);
+        self
+    }
+
+    /// 设置打乱训练文本的种子
+    #[must_use]
+    pub fn with_shuffle_seed(mut self, seed: u64) -> Self {
+        self.shuffle_seed = Some(seed);
+        self
+    }
+
+    /// 设置了 `shuffle_seed` 时按种子打乱训练文本，否则原样返回
+    #[must_use]
+    pub fn shuffle(&self, texts: Vec<String>) -> Vec<String> {
+        match self.shuffle_seed {
+            Some(seed) => shuffle_seeded(texts, seed),
+            None => texts,
+        }
+    }
+
+    /// 出现 `count` 次的合并或片段是否达到最低出现次数
+    #[must_use]
+    pub fn keeps_frequency(&self, count: u64) -> bool {
+        count >= self.min_frequency
+    }
+
+    /// 长度为 `len` 的新标记是否不超过最大长度
+    #[must_use]
+    pub fn allows_length(&self, len: usize) -> bool {
+        self.max_piece_length.is_none_or(|max| len <= max)
+    }
+
+    /// 按 `limit_alphabet` 选出的字母表，未限制时返回 `None`
+    ///
+    /// 保留出现次数最多的字符，次数相同时码点较小的优先，`initial_alphabet` 中的字符总是保留
+    #[must_use]
+    pub fn alphabet<S: std::hash::BuildHasher>(
+        &self,
+        char_counts: &HashMap<char, u64, S>,
+    ) -> Option<AHashSet<char>> {
+        let limit = self.limit_alphabet?;
+        let mut alphabet: AHashSet<char> = self.initial_alphabet.iter().copied().collect();
+        let mut chars: Vec<(char, u64)> = char_counts
+            .iter()
+            .filter(|(ch, _)| !alphabet.contains(ch))
+            .map(|(&ch, &count)| (ch, count))
+            .collect();
+        chars.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        alphabet.extend(chars.into_iter().take(limit).map(|(ch, _)| ch));
+        Some(alphabet)
+    }
+
+    /// 注册尚未注册的 `special_tokens`
+    ///
+    /// # Errors
+    ///
+    /// 当特殊标记为空、包含换行符或与已注册的标记冲突时返回错误
+    pub fn register_special_tokens<T: SpecialTokenizer + ?Sized>(
+        &self,
+        tokenizer: &mut T,
+    ) -> Result<(), TokenizerError> {
+        let missing: Vec<&str> = self
+            .special_tokens
+            .iter()
+            .map(String::as_str)
+            .filter(|token| tokenizer.special_tokens().id(token).is_none())
+            .collect();
+        if !missing.is_empty() {
+            tokenizer.add_special_tokens(&missing)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "python")]
+impl TrainerConfig {
+    /// 由Python的 `set_trainer_config` 关键字参数构造配置
+    pub(crate) fn from_py(
+        min_frequency: u64,
+        max_piece_length: Option<usize>,
+        special_tokens: Option<Vec<String>>,
+        initial_alphabet: Option<Vec<char>>,
+        limit_alphabet: Option<usize>,
+        shuffle_seed: Option<u64>,
+    ) -> Self {
+        Self {
+            max_piece_length,
+            limit_alphabet,
+            shuffle_seed,
+            ..Self::new()
+                .with_min_frequency(min_frequency)
+                .with_special_tokens(special_tokens.unwrap_or_default())
+                .with_initial_alphabet(initial_alphabet.unwrap_or_default())
+        }
+    }
+}
@@ -1,4 +1,19 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::ops::Range;
+
+use indexmap::IndexMap;
+
+use crate::base::content_hash::hash_ids;
+use crate::base::decoder::{DecodedPiece, DecoderKind};
+use crate::base::encoding::{Encoding, PairEncoding};
+use crate::base::limits::InputLimits;
+use crate::base::normalizer::NormalizedText;
+use crate::base::packed::PackedBatch;
+use crate::base::padding::{BatchEncoding, EncodeOptions, PaddingConfig, TruncationConfig};
+use crate::base::special_tokens::SpecialTokens;
+use crate::base::trainer_config::TrainerConfig;
+use crate::error::{training_error, Result, TokenizerError};
+use crate::generation::DecodeStream;
 
 /// 分词器基础接口，定义所有分词器必须实现的方法
 pub trait Tokenizer {
@@ -10,38 +25,111 @@ pub trait Tokenizer {
     /// # Errors
     ///
     /// 当正则表达式分割失败或文本包含无效字符时返回错误
-    fn encode(&self, text: &str) -> Result<Vec<Self::TokenId>, String>;
+    fn encode(&self, text: &str) -> Result<Vec<Self::TokenId>>;
 
     /// 解码标记ID序列为文本
     ///
     /// # Errors
     ///
     /// 当标记ID不在词汇表中时返回错误
-    fn decode(&self, tokens: &[Self::TokenId]) -> Result<String, String>;
+    fn decode(&self, tokens: &[Self::TokenId]) -> Result<String>;
+
+    /// 解码标记ID序列为文本，尽量避免分配
+    ///
+    /// 流式生成时通常每步只解码一个标记，此时实现可以直接借用词汇表中的文本，
+    /// 默认实现退化为 [`Tokenizer::decode`]。
+    ///
+    /// # Errors
+    ///
+    /// 当标记ID不在词汇表中时返回错误
+    fn decode_cow<'a>(&'a self, tokens: &[Self::TokenId]) -> Result<Cow<'a, str>> {
+        self.decode(tokens).map(Cow::Owned)
+    }
+
+    /// 并行编码一批文本，结果存放在一块连续缓冲区中，可以直接复制到张量
+    ///
+    /// 每行最多保留 `max_len` 个标记；`pad_id` 不为空时每行补齐到相同长度，
+    /// 规则见 [`PackedBatch::encode`]
+    ///
+    /// # Errors
+    ///
+    /// 当任意文本编码失败时返回 [`TokenizerError::BatchItem`]
+    fn encode_batch_packed<S>(
+        &self,
+        texts: &[S],
+        max_len: Option<usize>,
+        pad_id: Option<u32>,
+    ) -> Result<PackedBatch>
+    where
+        Self: Tokenizer<TokenId = u32> + Sync + Sized,
+        S: AsRef<str> + Sync,
+    {
+        PackedBatch::encode(self, texts, max_len, pad_id)
+    }
+
+    /// 编码文本并返回标记ID序列的哈希，计算方式见 [`hash_ids`]
+    ///
+    /// 哈希与平台无关，可以随缓存的编码结果一起保存；分词器更新后重新计算，不一致说明缓存已过期
+    ///
+    /// # Errors
+    ///
+    /// 当编码失败时返回错误
+    fn content_hash(&self, text: &str) -> Result<u64>
+    where
+        Self: Tokenizer<TokenId = u32> + Sized,
+    {
+        self.encode(text).map(|ids| hash_ids(&ids))
+    }
 
     /// 训练分词器
     ///
     /// # Errors
     ///
     /// 当训练文本为空、词汇表大小无效或训练过程中出现错误时返回错误
-    fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> Result<(), String>;
+    fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> Result<()>;
+
+    /// 按训练配置训练分词器，配置只对本次训练有效
+    ///
+    /// 默认实现只接受默认配置，内置的分词器都支持全部选项
+    ///
+    /// # Errors
+    ///
+    /// 当分词器不支持给定的配置或训练失败时返回错误
+    fn train_with_config(
+        &mut self,
+        texts: Vec<String>,
+        vocab_size: u32,
+        config: TrainerConfig,
+    ) -> Result<()> {
+        if config != TrainerConfig::default() {
+            return Err(training_error("此分词器不支持训练配置"));
+        }
+        self.train(texts, vocab_size)
+    }
 
     /// 获取词汇表大小
     fn vocab_size(&self) -> usize;
 
+    /// 判断标记是否为字节回退或未知标记
+    ///
+    /// 用于统计语料的OOV率，默认实现认为所有标记都是正常标记
+    fn is_fallback_token(&self, _id: &Self::TokenId) -> bool {
+        false
+    }
+
     /// 保存分词器到文件
     ///
     /// # Errors
     ///
     /// 当文件无法创建、写入失败或路径无效时返回错误
-    fn save(&self, path: &str) -> Result<(), String>;
+    fn save(&self, path: &str) -> Result<()>;
 
     /// 从文件加载分词器
     ///
     /// # Errors
     ///
     /// 当文件不存在、格式无效或解析失败时返回错误
-    fn load(&mut self, path: &str) -> Result<(), String>;
+    fn load(&mut self, path: &str) -> Result<()>;
 }
 
 /// 基于合并的分词器接口（BPE和BBPE）
@@ -51,13 +139,13 @@ pub trait MergeBasedTokenizer: Tokenizer {
     /// # Errors
     ///
     /// 当标记序列无效或合并规则应用失败时返回错误
-    fn apply_merges(&mut self, tokens: &mut Vec<Self::TokenId>) -> Result<(), String>;
+    fn apply_merges(&mut self, tokens: &mut Vec<Self::TokenId>) -> Result<()>;
 
-    /// 获取合并规则
-    fn get_merges(&self) -> &HashMap<(Self::TokenId, Self::TokenId), Self::TokenId>;
+    /// 获取合并规则，按等级（训练顺序）排列
+    fn get_merges(&self) -> &IndexMap<(Self::TokenId, Self::TokenId), Self::TokenId>;
 
-    /// 设置合并规则
-    fn set_merges(&mut self, merges: HashMap<(Self::TokenId, Self::TokenId), Self::TokenId>);
+    /// 设置合并规则，插入顺序即等级
+    fn set_merges(&mut self, merges: IndexMap<(Self::TokenId, Self::TokenId), Self::TokenId>);
 }
 
 /// 基于子词的分词器接口（WordPiece和Unigram）
@@ -68,3 +156,488 @@ pub trait SubwordTokenizer: Tokenizer {
     /// 设置标记分数（用于Unigram）
     fn set_scores(&mut self, scores: Vec<f64>);
 }
+
+/// 支持特殊标记的分词器
+///
+/// 特殊标记在编码时整体匹配，不会被预分词或合并拆开，训练时从语料中去除
+pub trait SpecialTokenizer: Tokenizer<TokenId = u32> {
+    /// 特殊标记注册表
+    fn special_tokens(&self) -> &SpecialTokens;
+
+    /// 注册特殊标记，返回每个标记的ID
+    ///
+    /// 已在词汇表中的标记沿用原ID，否则优先使用预留的ID，没有空闲的预留ID时追加到词汇表末尾
+    ///
+    /// # Errors
+    ///
+    /// 当标记为空、包含换行符或与已注册的标记冲突时返回错误
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Result<Vec<u32>>;
+
+    /// 在词汇表末尾预留 `count` 个ID供之后注册的特殊标记使用，返回预留的区间
+    ///
+    /// # Errors
+    ///
+    /// 当 `count` 为0、ID溢出或分词器当前不能预留（如BBPE已经训练出合并规则）时返回错误
+    fn reserve_special_ids(&mut self, count: u32) -> Result<Range<u32>>;
+
+    /// `encode_plus` 使用的截断与补齐配置，以及编码输入的长度限制
+    ///
+    /// `encode` 和 `encode_batch` 只检查长度限制，不截断也不补齐，见 [`crate::base::padding`]
+    fn encode_options(&self) -> &EncodeOptions;
+
+    /// 可修改的截断与补齐配置，修改时不做检查，通常使用 `enable_truncation`、`enable_padding`、
+    /// `set_input_limits`
+    fn encode_options_mut(&mut self) -> &mut EncodeOptions;
+
+    /// 启用截断
+    ///
+    /// # Errors
+    ///
+    /// 当配置无效时返回错误，见 [`TruncationConfig::validate`]
+    fn enable_truncation(&mut self, config: TruncationConfig) -> Result<()> {
+        config.validate()?;
+        self.encode_options_mut().truncation = Some(config);
+        Ok(())
+    }
+
+    /// 关闭截断
+    fn no_truncation(&mut self) {
+        self.encode_options_mut().truncation = None;
+    }
+
+    /// 启用补齐
+    ///
+    /// # Errors
+    ///
+    /// 当配置无效时返回错误，见 [`PaddingConfig::validate`]
+    fn enable_padding(&mut self, config: PaddingConfig) -> Result<()> {
+        config.validate()?;
+        self.encode_options_mut().padding = Some(config);
+        Ok(())
+    }
+
+    /// 关闭补齐
+    fn no_padding(&mut self) {
+        self.encode_options_mut().padding = None;
+    }
+
+    /// 设置编码输入的长度限制，[`InputLimits::default`] 表示不限制
+    ///
+    /// # Errors
+    ///
+    /// 当配置无效时返回错误，见 [`InputLimits::validate`]
+    fn set_input_limits(&mut self, limits: InputLimits) -> Result<()> {
+        limits.validate()?;
+        self.encode_options_mut().limits = limits;
+        Ok(())
+    }
+
+    /// 解码标记ID序列，`skip_special_tokens` 为真时跳过特殊标记
+    ///
+    /// # Errors
+    ///
+    /// 当标记ID不在词汇表中时返回错误
+    fn decode_with(&self, tokens: &[u32], skip_special_tokens: bool) -> Result<String> {
+        if !skip_special_tokens {
+            return self.decode(tokens);
+        }
+        let special = self.special_tokens();
+        let kept: Vec<u32> = tokens
+            .iter()
+            .copied()
+            .filter(|&id| !special.contains_id(id))
+            .collect();
+        self.decode(&kept)
+    }
+
+    /// 编码句子对为 `[CLS] A [SEP] B [SEP]`，同时给出区分两句的 `token_type_ids`
+    ///
+    /// `[CLS]` 和 `[SEP]` 需要已注册为特殊标记；使用其他名称（如 `<s>`、`</s>`）时调用
+    /// [`SpecialTokenizer::encode_pair_with`]
+    ///
+    /// # Errors
+    ///
+    /// 当 `[CLS]` 或 `[SEP]` 未注册为特殊标记或编码失败时返回错误
+    fn encode_pair(&self, text_a: &str, text_b: &str) -> Result<PairEncoding> {
+        self.encode_pair_with(text_a, text_b, "[CLS]", "[SEP]")
+    }
+
+    /// 以 `cls_token`、`sep_token` 为分类和分隔标记编码句子对，格式同 [`SpecialTokenizer::encode_pair`]
+    ///
+    /// # Errors
+    ///
+    /// 当 `cls_token` 或 `sep_token` 未注册为特殊标记或编码失败时返回错误
+    fn encode_pair_with(
+        &self,
+        text_a: &str,
+        text_b: &str,
+        cls_token: &str,
+        sep_token: &str,
+    ) -> Result<PairEncoding> {
+        let special = self.special_tokens();
+        let lookup = |token: &str| {
+            special
+                .id(token)
+                .ok_or_else(|| TokenizerError::UnregisteredSpecialToken {
+                    token: token.to_string(),
+                })
+        };
+        let cls = lookup(cls_token)?;
+        let sep = lookup(sep_token)?;
+
+        let first = self.encode(text_a)?;
+        let second = self.encode(text_b)?;
+        Ok(PairEncoding::from_pair(cls, sep, &first, &second))
+    }
+
+    /// 编码单条文本或句子对，按分词器的截断与补齐配置处理，并附带注意力掩码
+    ///
+    /// 句子对拼接为 `[CLS] A [SEP] B [SEP]`。启用溢出块时结果可能有多行
+    ///
+    /// # Errors
+    ///
+    /// 当句子对所需的特殊标记未注册、截断失败或编码失败时返回错误
+    fn encode_plus(&self, text: &str, pair: Option<&str>) -> Result<BatchEncoding>
+    where
+        Self: Sync + Sized,
+    {
+        let pairs = pair.map(|pair| [pair]);
+        self.encode_options()
+            .encode_batch(self, &[text], pairs.as_ref().map(|p| p.as_slice()))
+    }
+
+    /// 并行编码一批文本（及对应的第二句 `pairs`），按分词器的截断与补齐配置处理，
+    /// 规则见 [`EncodeOptions::encode_batch`]
+    ///
+    /// # Errors
+    ///
+    /// 当 `pairs` 与 `texts` 数量不同、句子对所需的特殊标记未注册、截断失败或编码失败时返回错误，
+    /// 单条文本失败时为 [`TokenizerError::BatchItem`]
+    fn encode_batch_plus<S>(&self, texts: &[S], pairs: Option<&[S]>) -> Result<BatchEncoding>
+    where
+        Self: Sync + Sized,
+        S: AsRef<str> + Sync,
+    {
+        self.encode_options().encode_batch(self, texts, pairs)
+    }
+}
+
+/// 可以按解码后的字节枚举词汇表的分词器
+///
+/// 用于在采样阶段实现logit偏置和禁用词列表
+pub trait VocabBytes: Tokenizer {
+    /// 单个标记解码后的字节序列，ID无效时返回 `None`
+    ///
+    /// 与 [`Tokenizer::decode`] 不同，不完整的多字节字符也会原样返回
+    fn token_bytes(&self, id: &Self::TokenId) -> Option<Cow<'_, [u8]>>;
+
+    /// 词汇表中每个标记的ID及其解码后的字节序列，按ID升序排列
+    fn vocab_bytes(&self) -> Vec<(Self::TokenId, Cow<'_, [u8]>)>;
+
+    /// 解码时使用的解码器，默认直接拼接各标记的字节
+    fn decoder(&self) -> DecoderKind {
+        DecoderKind::Fuse
+    }
+
+    /// 交给解码器的单个标记，ID无效时返回 `None`
+    ///
+    /// 默认把 [`VocabBytes::token_bytes`] 作为原始字节交给解码器
+    fn decoded_piece(&self, id: &Self::TokenId) -> Option<DecodedPiece<'_>> {
+        self.token_bytes(id).map(DecodedPiece::raw)
+    }
+
+    /// 解码结果以 `prefix` 开头的全部标记ID，按ID升序排列
+    fn ids_with_prefix(&self, prefix: &str) -> Vec<Self::TokenId> {
+        self.vocab_bytes()
+            .into_iter()
+            .filter(|(_, bytes)| bytes.starts_with(prefix.as_bytes()))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// 忽略开头的空白后解码结果恰好为 `text` 的全部标记ID，按ID升序排列
+    ///
+    /// 结果同时包含 `"word"` 和 `" word"` 这类带前导空格的变体，适合构造禁用词列表
+    fn token_ids_covering(&self, text: &str) -> Vec<Self::TokenId> {
+        self.vocab_bytes()
+            .into_iter()
+            .filter(|(_, bytes)| bytes.trim_ascii_start() == text.as_bytes())
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// 编码时实际参与分词的文本及其与原文的对齐，用于计算偏移区间
+    ///
+    /// 默认认为编码前不改写文本；有规范化流水线的分词器返回规范化后的文本
+    fn normalize_aligned(&self, text: &str) -> NormalizedText {
+        NormalizedText::new(text)
+    }
+
+    /// 编码 `text`，并给出每个标记在原文中的字节区间 `(start, end, id)`
+    ///
+    /// 区间按标记顺序排列，可直接用于在界面中高亮标记边界。标记先与规范化后的文本对齐，
+    /// 再经 [`VocabBytes::normalize_aligned`] 换算回原文，因此规范化改写过的字符（如NFC组合、
+    /// 全角转半角）也能得到正确的区间；规范化把一个字符变成多个字符时，由它产生的各标记
+    /// 都得到该字符的完整区间。预分词丢弃的文本被跳过；解码结果与文本对不上的标记（如未知标记）
+    /// 覆盖它与下一个对上的标记之间的文本（去掉首尾空白），连续多个时由第一个覆盖，其余为空区间。
+    ///
+    /// # Errors
+    ///
+    /// 当编码失败时返回错误
+    fn spans(&self, text: &str) -> Result<Vec<(usize, usize, Self::TokenId)>> {
+        let ids = self.encode(text)?;
+        let normalized = self.normalize_aligned(text);
+        let bytes = normalized.text().as_bytes();
+        let mut spans = Vec::with_capacity(ids.len());
+        // 还没对上的标记在 `spans` 中的下标，由下一个对上的标记确定它们覆盖的范围
+        let mut pending = Vec::new();
+        let mut cursor = 0;
+
+        for id in ids {
+            let token = self.token_bytes(&id).unwrap_or_default();
+            let start = if token.is_empty() {
+                None
+            } else {
+                find_bytes(&bytes[cursor..], &token).map(|index| cursor + index)
+            };
+            match start {
+                Some(start) => {
+                    assign_pending(&mut spans, &mut pending, bytes, cursor, start);
+                    cursor = start + token.len();
+                    spans.push((start, cursor, id));
+                }
+                None => {
+                    pending.push(spans.len());
+                    spans.push((cursor, cursor, id));
+                }
+            }
+        }
+        assign_pending(&mut spans, &mut pending, bytes, cursor, bytes.len());
+
+        Ok(spans
+            .into_iter()
+            .map(|(start, end, id)| {
+                let (start, end) = normalized.source_range(start, end);
+                (start, end, id)
+            })
+            .collect())
+    }
+
+    /// 创建流式解码器，逐个接收生成的标记并输出新增的文本片段
+    fn decode_stream(&self) -> DecodeStream<'_, Self>
+    where
+        Self: Sized + Tokenizer<TokenId = u32>,
+    {
+        DecodeStream::new(self)
+    }
+
+    /// 编码 `text`，返回标记ID、标记文本和每个标记在原文中的字节区间
+    ///
+    /// 区间规则同 [`VocabBytes::spans`]，可以用 [`Encoding::token_range`] 把原文中的标注区间
+    /// 对齐到标记
+    ///
+    /// # Errors
+    ///
+    /// 当编码失败时返回错误
+    fn encode_with_offsets(&self, text: &str) -> Result<Encoding>
+    where
+        Self: Sized + Tokenizer<TokenId = u32>,
+    {
+        Ok(Encoding::from_spans(self, self.spans(text)?))
+    }
+
+    /// 与 [`VocabBytes::spans`] 相同，但区间按 `unit` 给出
+    ///
+    /// 字节偏移用于切片原始UTF-8缓冲区，字符偏移用于按字符计数的界面和Python字符串。
+    /// 字节标记只覆盖字符的一部分时，字符区间的起点向下、终点向上取到字符边界，
+    /// 因此同一字符的多个字节标记得到相同的字符区间。
+    ///
+    /// # Errors
+    ///
+    /// 当编码失败时返回错误
+    fn spans_in(&self, text: &str, unit: OffsetUnit) -> Result<Vec<(usize, usize, Self::TokenId)>> {
+        let spans = self.spans(text)?;
+        Ok(match unit {
+            OffsetUnit::Byte => spans,
+            OffsetUnit::Char => {
+                let to_chars = CharOffsets::new(text);
+                spans
+                    .into_iter()
+                    .map(|(start, end, id)| (to_chars.floor(start), to_chars.ceil(end), id))
+                    .collect()
+            }
+        })
+    }
+
+    /// 编码 `text`，并标出每个标记是否开始一个新词，用于MLM的整词掩码
+    ///
+    /// 词由连续的字母和数字组成，空白和标点处断开；每个汉字单独成词。
+    /// 被拆成多个字节标记的字符只有第一个标记开始新词。
+    ///
+    /// # Errors
+    ///
+    /// 当编码失败时返回错误
+    fn word_starts(&self, text: &str) -> Result<Vec<bool>> {
+        Ok(self
+            .spans(text)?
+            .into_iter()
+            .map(|(start, _, _)| !continues_word(text, start))
+            .collect())
+    }
+
+    /// 将单个标记渲染为适合日志和界面显示的字符串
+    ///
+    /// 控制字符按 `escape_debug` 转义，首尾的空格显示为 `␣`，不构成有效UTF-8的字节
+    /// （如单独的字节回退标记）显示为 `<0xNN>`；ID无效时返回 `<?>`
+    fn render_token(&self, id: &Self::TokenId) -> String {
+        self.token_bytes(id)
+            .map_or_else(|| "<?>".to_string(), |bytes| render_bytes(&bytes))
+    }
+}
+
+/// 渲染标记字节，规则见 [`VocabBytes::render_token`]
+fn render_bytes(bytes: &[u8]) -> String {
+    let leading = bytes.iter().take_while(|&&b| b == b' ').count();
+    let trailing = bytes[leading..]
+        .iter()
+        .rev()
+        .take_while(|&&b| b == b' ')
+        .count();
+
+    let mut rendered = "␣".repeat(leading);
+    for chunk in bytes[leading..bytes.len() - trailing].utf8_chunks() {
+        for ch in chunk.valid().chars() {
+            if ch.is_control() {
+                rendered.extend(ch.escape_debug());
+            } else {
+                rendered.push(ch);
+            }
+        }
+        for byte in chunk.invalid() {
+            rendered.push_str(&format!("<0x{:02X}>", byte));
+        }
+    }
+    rendered.push_str(&"␣".repeat(trailing));
+    rendered
+}
+
+/// `needle` 在 `haystack` 中第一次出现的位置
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// 让还没对上的标记覆盖 `start..end` 中去掉首尾空白的部分：字符数与标记数相同时（如每个未知字符
+/// 一个未知标记）逐个对应，否则第一个标记覆盖整段，其余为空区间
+fn assign_pending<Id>(
+    spans: &mut [(usize, usize, Id)],
+    pending: &mut Vec<usize>,
+    bytes: &[u8],
+    start: usize,
+    end: usize,
+) {
+    let gap = &bytes[start..end];
+    let leading = gap.iter().take_while(|b| b.is_ascii_whitespace()).count();
+    let trailing = gap[leading..]
+        .iter()
+        .rev()
+        .take_while(|b| b.is_ascii_whitespace())
+        .count();
+    let (start, end) = (start + leading, end - trailing);
+    let chars: Vec<(usize, char)> = std::str::from_utf8(&bytes[start..end])
+        .map(|gap| gap.char_indices().collect())
+        .unwrap_or_default();
+    let count = pending.len();
+    for (n, index) in pending.drain(..).enumerate() {
+        let span = if chars.len() == count {
+            let (offset, ch) = chars[n];
+            (start + offset, start + offset + ch.len_utf8())
+        } else if n == 0 {
+            (start, end)
+        } else {
+            (end, end)
+        };
+        spans[index].0 = span.0;
+        spans[index].1 = span.1;
+    }
+}
+
+/// 标记区间的偏移单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OffsetUnit {
+    /// UTF-8字节偏移
+    #[default]
+    Byte,
+    /// Unicode字符（码点）偏移
+    Char,
+}
+
+/// 字节偏移到字符偏移的换算表
+struct CharOffsets<'a> {
+    text: &'a str,
+    /// 第 `i` 项为字节位置 `i` 之前开始的字符数
+    starts_before: Vec<usize>,
+}
+
+impl<'a> CharOffsets<'a> {
+    fn new(text: &'a str) -> Self {
+        let mut starts_before = Vec::with_capacity(text.len() + 1);
+        let mut count = 0;
+        for byte in 0..=text.len() {
+            starts_before.push(count);
+            if text.is_char_boundary(byte) {
+                count += 1;
+            }
+        }
+        Self {
+            text,
+            starts_before,
+        }
+    }
+
+    /// 字节位置所在字符的字符偏移，落在字符中间时取该字符的起点
+    fn floor(&self, byte: usize) -> usize {
+        let count = self.starts_before[byte];
+        if self.text.is_char_boundary(byte) {
+            count
+        } else {
+            count - 1
+        }
+    }
+
+    /// 字节位置对应的字符偏移，落在字符中间时取下一个字符的起点
+    fn ceil(&self, byte: usize) -> usize {
+        self.starts_before[byte]
+    }
+}
+
+/// 从 `start` 开始的标记是否延续前面的词
+fn continues_word(text: &str, start: usize) -> bool {
+    if start == 0 {
+        return false;
+    }
+    // 落在字符中间说明是同一字符的后续字节标记
+    if !text.is_char_boundary(start) {
+        return true;
+    }
+    let is_word_char = |c: char| c.is_alphanumeric() && !is_cjk_char(c);
+    let previous = text[..start].chars().next_back();
+    let next = text[start..].chars().next();
+    previous.is_some_and(is_word_char) && next.is_some_and(is_word_char)
+}
+
+/// CJK统一表意文字，范围与BERT的中文分字规则一致
+fn is_cjk_char(c: char) -> bool {
+    matches!(
+        u32::from(c),
+        0x4E00..=0x9FFF
+            | 0x3400..=0x4DBF
+            | 0x20000..=0x2A6DF
+            | 0x2A700..=0x2B73F
+            | 0x2B740..=0x2B81F
+            | 0x2B820..=0x2CEAF
+            | 0xF900..=0xFAFF
+            | 0x2F800..=0x2FA1F
+    )
+}
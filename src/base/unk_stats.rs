@@ -0,0 +1,102 @@
+//! 未知标记统计
+//!
+//! 记录自加载以来编码输出未知标记的次数，线上服务可以据此观察流量相对词汇表的漂移，
+//! 不必离线重新分析语料。统计始终开启，每条文本只多几次原子加法。
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+
+/// 一次读取的未知标记统计
+///
+/// 只统计由文本切分得到的标记，文本中直接写出的特殊标记不计入
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct UnkStats {
+    /// 编码的文本条数
+    pub texts: u64,
+    /// 输出的标记总数
+    pub tokens: u64,
+    /// 输出的未知标记数
+    pub unk_tokens: u64,
+    /// 至少包含一个未知标记的文本条数
+    pub texts_with_unk: u64,
+}
+
+impl UnkStats {
+    /// 未知标记占输出标记的比例，没有输出标记时为0
+    #[must_use]
+    pub fn unk_rate(&self) -> f64 {
+        if self.tokens == 0 {
+            0.0
+        } else {
+            self.unk_tokens as f64 / self.tokens as f64
+        }
+    }
+}
+
+/// 自加载以来的未知标记计数，可在多个线程中同时编码时累加
+#[derive(Debug, Default)]
+pub struct UnkCounter {
+    texts: AtomicU64,
+    tokens: AtomicU64,
+    unk_tokens: AtomicU64,
+    texts_with_unk: AtomicU64,
+}
+
+impl UnkCounter {
+    /// 记录一条文本的编码结果：输出的标记数和其中的未知标记数
+    pub fn record(&self, tokens: usize, unk_tokens: usize) {
+        self.texts.fetch_add(1, Ordering::Relaxed);
+        self.tokens.fetch_add(tokens as u64, Ordering::Relaxed);
+        if unk_tokens > 0 {
+            self.unk_tokens
+                .fetch_add(unk_tokens as u64, Ordering::Relaxed);
+            self.texts_with_unk.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 清空已有的统计，加载模型时会自动调用
+    pub fn reset(&self) {
+        self.texts.store(0, Ordering::Relaxed);
+        self.tokens.store(0, Ordering::Relaxed);
+        self.unk_tokens.store(0, Ordering::Relaxed);
+        self.texts_with_unk.store(0, Ordering::Relaxed);
+    }
+
+    /// 当前的统计结果
+    #[must_use]
+    pub fn snapshot(&self) -> UnkStats {
+        UnkStats {
+            texts: self.texts.load(Ordering::Relaxed),
+            tokens: self.tokens.load(Ordering::Relaxed),
+            unk_tokens: self.unk_tokens.load(Ordering::Relaxed),
+            texts_with_unk: self.texts_with_unk.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Clone for UnkCounter {
+    /// 克隆得到的分词器从零开始统计
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "python")]
+impl UnkStats {
+    /// 转换为Python字典，额外包含 `unk_rate`
+    pub(crate) fn to_py_dict<'py>(
+        self,
+        py: pyo3::Python<'py>,
+    ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyDict>> {
+        use pyo3::types::{PyDict, PyDictMethods};
+
+        let dict = PyDict::new(py);
+        dict.set_item("texts", self.texts)?;
+        dict.set_item("tokens", self.tokens)?;
+        dict.set_item("unk_tokens", self.unk_tokens)?;
+        dict.set_item("texts_with_unk", self.texts_with_unk)?;
+        dict.set_item("unk_rate", self.unk_rate())?;
+        Ok(dict)
+    }
+}
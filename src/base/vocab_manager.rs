@@ -1,7 +1,10 @@
+use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 
+use crate::error::{vocab_error, TokenizerError};
+
 /// 通用词汇表管理器，封装双向映射的同步管理
 ///
 /// 解决问题：
@@ -92,9 +95,13 @@ where
         self.id_to_value.get(id)
     }
 
-    /// 根据值获取ID
+    /// 根据值获取ID，可以用借用形式查找（如 `&str`、`&[u8]`），不必先分配一个值
     #[inline]
-    pub fn get_by_value(&self, value: &V) -> Option<&K> {
+    pub fn get_by_value<Q>(&self, value: &Q) -> Option<&K>
+    where
+        V: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
         self.value_to_id.get(value)
     }
 
@@ -106,7 +113,11 @@ where
 
     /// 检查值是否存在
     #[inline]
-    pub fn contains_value(&self, value: &V) -> bool {
+    pub fn contains_value<Q>(&self, value: &Q) -> bool
+    where
+        V: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
         self.value_to_id.contains_key(value)
     }
 
@@ -191,18 +202,18 @@ where
     ///
     /// # 返回值
     /// - `Ok(())`: 映射一致
-    /// - `Err(String)`: 发现不一致，返回错误信息
+    /// - `Err(TokenizerError::VocabError)`: 发现不一致，返回错误信息
     ///
     /// # 用途
     /// 用于调试和测试，确保数据完整性
-    pub fn validate(&self) -> Result<(), String> {
+    pub fn validate(&self) -> Result<(), TokenizerError> {
         // 检查大小一致性
         if self.id_to_value.len() != self.value_to_id.len() {
-            return Err(format!(
+            return Err(vocab_error(format!(
                 "Size mismatch: id_to_value={}, value_to_id={}",
                 self.id_to_value.len(),
                 self.value_to_id.len()
-            ));
+            )));
         }
 
         // 检查每个正向映射都有对应的反向映射
@@ -210,17 +221,17 @@ where
             match self.value_to_id.get(value) {
                 Some(reverse_id) if reverse_id == id => {}
                 Some(reverse_id) => {
-                    return Err(format!(
+                    return Err(vocab_error(format!(
                         "Reverse mapping mismatch: id={:?} maps to value={:?}, \
                          but value maps back to different id={:?}",
                         id, value, reverse_id
-                    ));
+                    )));
                 }
                 None => {
-                    return Err(format!(
+                    return Err(vocab_error(format!(
                         "Missing reverse mapping: id={:?} -> value={:?}",
                         id, value
-                    ));
+                    )));
                 }
             }
         }
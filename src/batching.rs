@@ -0,0 +1,199 @@
+//! 动态批处理队列
+//!
+//! 服务端的并发请求通常各自只带一两条文本，逐条编码无法利用rayon的并行度。
+//! [`BatchQueue`] 在后台线程中收集并发提交的文本，凑满批次大小或等待超时后统一并行编码，
+//! 再通过通道把结果分别送回每个请求。
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+
+use crate::base::traits::Tokenizer;
+use crate::error::{encoding_error, TokenizerError};
+use crate::telemetry;
+
+/// 批处理队列共享的分词器
+pub type SharedTokenizer = Arc<dyn Tokenizer<TokenId = u32> + Send + Sync>;
+
+/// 批次的大小和延迟预算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchConfig {
+    /// 每批最多包含的文本数，为0时按1处理
+    pub max_batch_size: usize,
+    /// 收到批内第一条文本后最多等待的时长
+    pub max_wait: Duration,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 64,
+            max_wait: Duration::from_millis(2),
+        }
+    }
+}
+
+/// 等待编码的文本及结果的回传通道
+struct Job {
+    text: String,
+    reply: Sender<Result<Vec<u32>, TokenizerError>>,
+}
+
+/// 已提交、尚未取回结果的编码请求
+#[derive(Debug)]
+pub struct PendingEncode {
+    receiver: Receiver<Result<Vec<u32>, TokenizerError>>,
+}
+
+impl PendingEncode {
+    /// 阻塞等待编码结果
+    ///
+    /// # Errors
+    ///
+    /// 当文本编码失败，或批处理线程在返回结果前退出时返回错误
+    pub fn wait(self) -> Result<Vec<u32>, TokenizerError> {
+        self.receiver
+            .recv()
+            .map_err(|_| encoding_error("批处理线程已退出"))?
+    }
+}
+
+/// 动态批处理队列
+///
+/// 可以在多个线程间共享（如放在 `Arc` 中），提交操作只需 `&self`。
+/// 队列被丢弃时会处理完已提交的文本，再等待后台线程退出。
+#[derive(Debug)]
+pub struct BatchQueue {
+    sender: Option<Sender<Job>>,
+    worker: Option<JoinHandle<()>>,
+    config: BatchConfig,
+}
+
+impl BatchQueue {
+    /// 创建队列并启动后台批处理线程
+    #[must_use]
+    pub fn new(tokenizer: SharedTokenizer, config: BatchConfig) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let worker = std::thread::Builder::new()
+            .name("zero-tokenizer-batch".to_string())
+            .spawn(move || run_worker(tokenizer.as_ref(), &receiver, config))
+            .expect("无法启动批处理线程");
+
+        Self {
+            sender: Some(sender),
+            worker: Some(worker),
+            config,
+        }
+    }
+
+    /// 队列的批次配置
+    #[must_use]
+    pub fn config(&self) -> BatchConfig {
+        self.config
+    }
+
+    /// 提交一条文本，立即返回，结果通过 [`PendingEncode::wait`] 取回
+    ///
+    /// # Errors
+    ///
+    /// 当批处理线程已经退出时返回错误
+    pub fn submit(&self, text: impl Into<String>) -> Result<PendingEncode, TokenizerError> {
+        let (reply, receiver) = mpsc::channel();
+        let job = Job {
+            text: text.into(),
+            reply,
+        };
+        self.sender
+            .as_ref()
+            .and_then(|sender| sender.send(job).ok())
+            .ok_or_else(|| encoding_error("批处理线程已退出"))?;
+        Ok(PendingEncode { receiver })
+    }
+
+    /// 提交一条文本并阻塞等待结果
+    ///
+    /// # Errors
+    ///
+    /// 当文本编码失败或批处理线程已经退出时返回错误
+    pub fn encode(&self, text: impl Into<String>) -> Result<Vec<u32>, TokenizerError> {
+        self.submit(text)?.wait()
+    }
+
+    /// 提交一组文本并按顺序等待全部结果
+    ///
+    /// 这些文本可能与其他调用方的文本合并到同一批次中
+    ///
+    /// # Errors
+    ///
+    /// 当任意文本编码失败时返回错误，错误信息包含文本序号
+    pub fn encode_many<I, S>(&self, texts: I) -> Result<Vec<Vec<u32>>, TokenizerError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let pending = texts
+            .into_iter()
+            .map(|text| self.submit(text))
+            .collect::<Result<Vec<_>, _>>()?;
+        pending
+            .into_iter()
+            .enumerate()
+            .map(|(index, pending)| {
+                pending.wait().map_err(|e| TokenizerError::BatchItem {
+                    index,
+                    source: Box::new(e),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Drop for BatchQueue {
+    fn drop(&mut self) {
+        // 关闭发送端后，后台线程处理完剩余文本即退出
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// 后台线程主循环：阻塞等待批内第一条文本，再在延迟预算内尽量凑满批次
+fn run_worker(
+    tokenizer: &(dyn Tokenizer<TokenId = u32> + Send + Sync),
+    receiver: &Receiver<Job>,
+    config: BatchConfig,
+) {
+    let max_batch_size = config.max_batch_size.max(1);
+    let mut batch = Vec::with_capacity(max_batch_size);
+
+    while let Ok(first) = receiver.recv() {
+        batch.push(first);
+        let deadline = Instant::now() + config.max_wait;
+        while batch.len() < max_batch_size {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match receiver.recv_timeout(remaining) {
+                Ok(job) => batch.push(job),
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        let started = Instant::now();
+        let size = batch.len();
+        let tokens: usize = batch
+            .par_drain(..)
+            .map(|job| {
+                let encoded = tokenizer.encode(&job.text);
+                let tokens = encoded.as_ref().map_or(0, Vec::len);
+                // 请求方可能已经放弃等待，发送失败时直接忽略
+                let _ = job.reply.send(encoded);
+                tokens
+            })
+            .sum();
+        telemetry::record_batch(size, started.elapsed());
+        telemetry::record_encoded(size, tokens);
+    }
+}
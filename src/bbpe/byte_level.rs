@@ -0,0 +1,99 @@
+//! 字节级标记的可读表示
+//!
+//! BBPE的标记是任意字节序列，可能是不完整的UTF-8，无法直接显示或写入JSON。
+//! 这里提供两种表示：GPT-2的字节↔Unicode映射（可逆，与HuggingFace `ByteLevel` 预分词器一致），
+//! 以及把无效字节写成 `\xNN` 的转义表示。
+
+/// GPT-2字节到Unicode字符的映射表
+///
+/// 可打印的Latin-1字节映射为自身，其余字节按顺序映射到U+0100起的字符，例如空格为 `Ġ`
+const BYTE_TO_CHAR: [char; 256] = build_byte_to_char();
+
+const fn is_printable(byte: u8) -> bool {
+    matches!(byte, b'!'..=b'~' | 0xA1..=0xAC | 0xAE..=0xFF)
+}
+
+const fn build_byte_to_char() -> [char; 256] {
+    let mut table = ['\0'; 256];
+    let mut shifted = 0;
+    let mut byte = 0;
+    while byte < 256 {
+        let code = if is_printable(byte as u8) {
+            byte
+        } else {
+            shifted += 1;
+            255 + shifted
+        };
+        table[byte as usize] = match char::from_u32(code) {
+            Some(c) => c,
+            None => panic!("映射结果不是有效字符"),
+        };
+        byte += 1;
+    }
+    table
+}
+
+/// 标记字节序列的显示方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VocabStringStyle {
+    /// GPT-2字节↔Unicode映射，可逆，适合导出为HuggingFace等格式
+    #[default]
+    ByteLevel,
+    /// 有效的UTF-8原样保留（控制字符转义），无效字节写成 `\xNN`，适合人工查看
+    Escaped,
+}
+
+impl VocabStringStyle {
+    /// 按当前方式渲染字节序列
+    #[must_use]
+    pub fn render(self, bytes: &[u8]) -> String {
+        match self {
+            Self::ByteLevel => bytes_to_byte_level(bytes),
+            Self::Escaped => escape_bytes(bytes),
+        }
+    }
+}
+
+/// 单个字节对应的GPT-2字符
+#[must_use]
+pub fn byte_to_char(byte: u8) -> char {
+    BYTE_TO_CHAR[usize::from(byte)]
+}
+
+/// GPT-2字符对应的字节，不在映射表中时返回 `None`
+#[must_use]
+pub fn char_to_byte(c: char) -> Option<u8> {
+    match u32::from(c) {
+        code @ 0..=255 if is_printable(code as u8) => Some(code as u8),
+        code @ 256..=511 => BYTE_TO_CHAR
+            .iter()
+            .position(|&mapped| u32::from(mapped) == code)
+            .map(|byte| byte as u8),
+        _ => None,
+    }
+}
+
+/// 用GPT-2映射把字节序列写成字符串
+#[must_use]
+pub fn bytes_to_byte_level(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| byte_to_char(byte)).collect()
+}
+
+/// [`bytes_to_byte_level`] 的逆运算，含有映射表以外的字符时返回 `None`
+#[must_use]
+pub fn byte_level_to_bytes(text: &str) -> Option<Vec<u8>> {
+    text.chars().map(char_to_byte).collect()
+}
+
+/// 有效的UTF-8片段按 `escape_debug` 转义，无效字节写成 `\xNN`
+#[must_use]
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    let mut rendered = String::new();
+    for chunk in bytes.utf8_chunks() {
+        rendered.extend(chunk.valid().escape_debug());
+        for byte in chunk.invalid() {
+            rendered.push_str(&format!("\\x{:02X}", byte));
+        }
+    }
+    rendered
+}
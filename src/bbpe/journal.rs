@@ -0,0 +1,215 @@
+//! BBPE词汇表的追加式日志
+//!
+//! 长期运行的自适应部署中，每次追加少量标记都重写完整的模型文件代价很高。
+//! 日志文件 `<模型路径>.journal` 只追加记录加载模型之后新增的标记、合并规则和特殊标记，
+//! 加载模型时自动重放。每行格式与模型文件中的对应行相同。
+
+use std::io::Write;
+use std::ops::Range;
+
+use crate::base::special_tokens::{
+    parse_reserved_ids, parse_special_token, RESERVED_IDS_HEADER, SPECIAL_TOKEN_HEADER,
+};
+use crate::base::tokenizer_base::MergeEntry;
+use crate::bbpe::tokenizer::{parse_vocab_entry, BBPETokenizer, ModelParts};
+use crate::error::{model_load_error, model_save_error, TokenizerError};
+
+/// 日志中的一条记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalEntry {
+    /// 设置词汇表条目（新增标记，或把预留占位符换成实际的特殊标记）
+    Token {
+        /// 标记ID
+        id: u32,
+        /// 标记的字节序列
+        bytes: Vec<u8>,
+    },
+    /// 追加一条合并规则
+    Merge {
+        /// 合并的词对
+        pair: (u32, u32),
+        /// 合并产生的新标记ID
+        new_id: u32,
+    },
+    /// 注册特殊标记
+    Special {
+        /// 标记ID
+        id: u32,
+        /// 特殊标记文本
+        token: String,
+    },
+    /// 预留特殊标记ID区间
+    Reserved {
+        /// 预留的ID区间
+        ids: Range<u32>,
+    },
+}
+
+impl JournalEntry {
+    /// 转换为日志中的一行（不含换行符）
+    #[must_use]
+    pub fn to_line(&self) -> String {
+        match self {
+            Self::Token { id, bytes } => {
+                let bytes: Vec<String> = bytes.iter().map(u8::to_string).collect();
+                format!("vocab_entry: {} {}", id, bytes.join(" "))
+            }
+            Self::Merge { pair, new_id } => {
+                let entry = MergeEntry {
+                    pair: *pair,
+                    new_id: *new_id,
+                    rank: None,
+                };
+                format!("merge: {}", entry.to_line())
+            }
+            Self::Special { id, token } => format!("{}{} {}", SPECIAL_TOKEN_HEADER, id, token),
+            Self::Reserved { ids } => format!("{}{} {}", RESERVED_IDS_HEADER, ids.start, ids.end),
+        }
+    }
+
+    /// 解析日志中的一行
+    ///
+    /// # Errors
+    ///
+    /// 当行前缀未知或内容无法解析时返回错误
+    pub fn parse(line: &str) -> Result<Self, TokenizerError> {
+        // 特殊标记两侧的空格属于标记本身，不能随行一起去除
+        if let Some(data) = line.trim_start().strip_prefix(SPECIAL_TOKEN_HEADER) {
+            let (id, token) = parse_special_token(data)?;
+            return Ok(Self::Special {
+                id,
+                token: token.to_string(),
+            });
+        }
+
+        let line = line.trim();
+        if let Some(data) = line.strip_prefix("vocab_entry: ") {
+            let (id, bytes) = parse_vocab_entry(data)?;
+            Ok(Self::Token { id, bytes })
+        } else if let Some(data) = line.strip_prefix("merge: ") {
+            let entry = MergeEntry::parse(data)?;
+            Ok(Self::Merge {
+                pair: entry.pair,
+                new_id: entry.new_id,
+            })
+        } else if let Some(data) = line.strip_prefix(RESERVED_IDS_HEADER) {
+            Ok(Self::Reserved {
+                ids: parse_reserved_ids(data)?,
+            })
+        } else {
+            Err(model_load_error(format!("无效的日志行: {}", line)))
+        }
+    }
+}
+
+/// 模型文件对应的日志文件路径
+#[must_use]
+pub fn journal_path(model_path: &str) -> String {
+    format!("{}.journal", model_path)
+}
+
+impl BBPETokenizer {
+    /// 记录一条尚未写入日志的变更；词汇表没有对应的已保存模型时忽略
+    pub(crate) fn record_journal(&mut self, entry: JournalEntry) {
+        if let Some(pending) = &mut self.journal {
+            pending.push(entry);
+        }
+    }
+
+    /// 把加载模型之后新增的标记、合并规则和特殊标记追加到 `model_path` 对应的日志文件，返回写入的条数
+    ///
+    /// 只追加写入，不会重写模型文件；之后用 [`Tokenizer::load`](crate::base::traits::Tokenizer::load)
+    /// 加载同一路径时会自动重放日志。
+    ///
+    /// # Errors
+    ///
+    /// 当分词器不是从模型文件加载的（新建或重新训练后需要先完整保存），或写入失败时返回错误
+    pub fn append_journal(&mut self, model_path: &str) -> Result<usize, TokenizerError> {
+        let pending = self.journal.as_ref().ok_or_else(|| {
+            model_save_error("词汇表没有对应的已保存模型，请先完整保存并重新加载")
+        })?;
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let mut data = String::new();
+        for entry in pending {
+            data.push_str(&entry.to_line());
+            data.push('\n');
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(journal_path(model_path))
+            .map_err(|e| model_save_error(format!("打开日志文件失败: {}", e)))?;
+        file.write_all(data.as_bytes())
+            .map_err(|e| model_save_error(format!("写入日志失败: {}", e)))?;
+
+        let written = pending.len();
+        self.journal = Some(Vec::new());
+        Ok(written)
+    }
+
+    /// 在已加载的模型上重放日志数据，返回重放的条数
+    ///
+    /// 重放是幂等的，重复的记录不会改变结果。追加过程中被中断时最后一行可能不完整，
+    /// 这样的行会被忽略。部分加载的模型只保留 [`ModelParts`] 对应的部分。
+    ///
+    /// # Errors
+    ///
+    /// 当日志不是有效的UTF-8或任意完整的行无法解析时返回错误
+    pub fn replay_journal(&mut self, data: &[u8]) -> Result<usize, TokenizerError> {
+        let text = std::str::from_utf8(data)
+            .map_err(|e| model_load_error(format!("日志不是有效的UTF-8: {}", e)))?;
+        let complete = match text.rfind('\n') {
+            Some(end) => &text[..=end],
+            None => "",
+        };
+        if complete.len() < text.len() {
+            log::warn!("忽略日志末尾不完整的行");
+        }
+
+        let mut replayed = 0;
+        for line in complete.lines().filter(|line| !line.trim().is_empty()) {
+            match JournalEntry::parse(line)? {
+                JournalEntry::Token { id, bytes } => {
+                    if self
+                        .parts
+                        .keeps_entry(id, &bytes, &self.base.special_tokens)
+                    {
+                        self.vocab.insert(id, bytes);
+                    }
+                    self.next_token_id = self.next_token_id.max(id + 1);
+                }
+                JournalEntry::Merge { pair, new_id } => {
+                    if self.parts != ModelParts::DecodeOnly {
+                        self.merges.insert(pair, new_id);
+                    }
+                }
+                JournalEntry::Special { id, token } => {
+                    self.base.special_tokens.insert(&token, id)?;
+                }
+                JournalEntry::Reserved { ids } => {
+                    if !self.base.special_tokens.reserved().contains(&ids) {
+                        self.base.special_tokens.reserve(ids)?;
+                    }
+                }
+            }
+            replayed += 1;
+        }
+        Ok(replayed)
+    }
+
+    /// 存在与模型文件对应的日志文件时重放其中的变更并重新检查完整性
+    pub(super) fn replay_journal_file(&mut self, model_path: &str) -> Result<(), TokenizerError> {
+        let journal = journal_path(model_path);
+        if std::path::Path::new(&journal).exists() {
+            let data = std::fs::read(&journal)
+                .map_err(|e| model_load_error(format!("打开日志文件失败: {}", e)))?;
+            let replayed = self.replay_journal(&data)?;
+            log::info!("已重放 {} 条日志记录", replayed);
+            self.check_integrity().into_result()?;
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,8 @@
+pub mod byte_level;
+mod journal;
+pub mod tiktoken;
 mod tokenizer;
 
-pub use tokenizer::BBPETokenizer;
+pub use byte_level::VocabStringStyle;
+pub use journal::{journal_path, JournalEntry};
+pub use tokenizer::{BBPETokenizer, ModelParts, RESERVED_SPECIAL_TOKEN_PREFIX};
@@ -0,0 +1,177 @@
+//! tiktoken 的 `.tiktoken` 等级表格式
+//!
+//! 每行是base64编码的标记字节和它的等级，等级即标记ID，文件中没有合并规则和特殊标记。
+//! tiktoken编码时反复合并拼接结果在等级表中且等级最低的相邻配对，等级相同时先合并最左边的一处。
+//! 加载时为每个多字节标记的每一种两段拆分生成一条合并规则，新标记ID即等级，
+//! 按本库“新标记ID越小越先合并”的规则编码，结果与tiktoken相同。
+
+use std::collections::HashMap as StdHashMap;
+
+use ahash::{AHashMap, AHashSet};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::base::tokenizer_base::{MergeMap, CL100K_PATTERN};
+use crate::base::vocab_manager::VocabManager;
+use crate::bbpe::tokenizer::BBPETokenizer;
+use crate::error::{model_load_error, model_save_error, TokenizerError};
+
+/// 解析 `.tiktoken` 文件内容，返回 (标记字节, 等级)，保持文件中的顺序
+///
+/// # Errors
+///
+/// 当某行不是 `base64 等级` 的形式，或标记、等级重复时返回错误，错误信息包含行号
+pub fn parse_tiktoken_ranks(data: &str) -> Result<Vec<(Vec<u8>, u32)>, TokenizerError> {
+    let mut ranks = Vec::new();
+    let mut seen_tokens = AHashSet::new();
+    let mut seen_ranks = AHashSet::new();
+    for (line_no, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let context = |e: String| model_load_error(format!("第 {} 行: {}", line_no + 1, e));
+        let (token, rank) = line
+            .split_once(' ')
+            .ok_or_else(|| context("缺少等级".to_string()))?;
+        let token = STANDARD
+            .decode(token)
+            .map_err(|e| context(format!("base64解码失败: {}", e)))?;
+        let rank = rank
+            .trim()
+            .parse::<u32>()
+            .map_err(|e| context(format!("解析等级失败: {}", e)))?;
+        if token.is_empty() {
+            return Err(context("标记为空".to_string()));
+        }
+        if !seen_ranks.insert(rank) {
+            return Err(context(format!("等级 {} 重复", rank)));
+        }
+        if !seen_tokens.insert(token.clone()) {
+            return Err(context(format!(
+                "标记 {:?} 重复",
+                String::from_utf8_lossy(&token)
+            )));
+        }
+        ranks.push((token, rank));
+    }
+    Ok(ranks)
+}
+
+/// 写出 `.tiktoken` 文件内容，每行一个标记
+#[must_use]
+pub fn write_tiktoken_ranks(ranks: &[(Vec<u8>, u32)]) -> String {
+    let mut text = String::new();
+    for (token, rank) in ranks {
+        text.push_str(&STANDARD.encode(token));
+        text.push(' ');
+        text.push_str(&rank.to_string());
+        text.push('\n');
+    }
+    text
+}
+
+impl BBPETokenizer {
+    /// 导出tiktoken的等级表：词汇表中除特殊标记和预留位置以外的全部标记，按等级升序排列
+    ///
+    /// 等级表中没有合并规则，tiktoken会合并任意拼接结果在表中的相邻配对，而本分词器只按训练得到的合并规则合并。
+    /// 同一个标记可以由多种方式拼成时（如 `ab`+`c` 与 `a`+`bc`），两者对少数片段的切分可能不同
+    #[must_use]
+    pub fn to_tiktoken_ranks(&self) -> Vec<(Vec<u8>, u32)> {
+        let special_tokens = &self.base.special_tokens;
+        let mut ranks: Vec<(Vec<u8>, u32)> = self
+            .vocab
+            .iter()
+            .filter(|&(&id, _)| !special_tokens.contains_id(id) && !special_tokens.is_reserved(id))
+            .map(|(&id, bytes)| (bytes.clone(), id))
+            .collect();
+        ranks.sort_unstable_by_key(|&(_, rank)| rank);
+        ranks
+    }
+
+    /// 导出tiktoken `Encoding` 构造函数的 `mergeable_ranks` 参数：标记字节 -> 等级，
+    /// 内容与 [`BBPETokenizer::to_tiktoken_ranks`] 相同
+    #[must_use]
+    pub fn mergeable_ranks_bytes(&self) -> StdHashMap<Vec<u8>, u32> {
+        self.to_tiktoken_ranks().into_iter().collect()
+    }
+
+    /// 写出 `.tiktoken` 等级表文件，见 [`BBPETokenizer::to_tiktoken_ranks`]
+    ///
+    /// # Errors
+    ///
+    /// 当写入失败时返回错误
+    pub fn save_tiktoken(&self, path: &str) -> Result<(), TokenizerError> {
+        std::fs::write(path, write_tiktoken_ranks(&self.to_tiktoken_ranks()))
+            .map_err(|e| model_save_error(format!("写入文件 {} 失败: {}", path, e)))
+    }
+
+    /// 由tiktoken的等级表创建分词器，标记ID即等级，预分词模式为 [`CL100K_PATTERN`]
+    ///
+    /// 其他编码需要换用对应的模式，如o200k_base使用 `base.set_pattern("o200k")`；
+    /// 特殊标记不在等级表中，需要用 [`BBPETokenizer::insert_special_token`] 按原ID注册
+    ///
+    /// # Errors
+    ///
+    /// 当等级表缺少某个单字节标记，或标记、等级重复时返回错误
+    pub fn from_tiktoken_ranks(ranks: Vec<(Vec<u8>, u32)>) -> Result<Self, TokenizerError> {
+        let mut id_map = StdHashMap::with_capacity(ranks.len());
+        for (token, rank) in ranks {
+            if let Some(previous) = id_map.insert(rank, token) {
+                return Err(model_load_error(format!(
+                    "等级 {} 重复: {:?}",
+                    rank,
+                    String::from_utf8_lossy(&previous)
+                )));
+            }
+        }
+        let vocab = VocabManager::from_id_map(id_map);
+        if vocab.value_map().len() != vocab.len() {
+            return Err(model_load_error("等级表中有重复的标记"));
+        }
+        if let Some(byte) = (0..=255u8).find(|&b| !vocab.contains_value(&vec![b])) {
+            return Err(model_load_error(format!(
+                "等级表缺少单字节标记 0x{:02X}",
+                byte
+            )));
+        }
+
+        let ids: AHashMap<&[u8], u32> = vocab
+            .iter()
+            .map(|(&id, bytes)| (bytes.as_slice(), id))
+            .collect();
+        // 等级表中标记的等级就是合并的先后，按ID升序插入；同一标记的多种拆分共享等级
+        let mut ranked: Vec<(u32, &Vec<u8>)> =
+            vocab.iter().map(|(&id, bytes)| (id, bytes)).collect();
+        ranked.sort_unstable_by_key(|&(id, _)| id);
+        let mut merges = MergeMap::new();
+        for (id, bytes) in ranked {
+            for split in 1..bytes.len() {
+                let (left, right) = bytes.split_at(split);
+                if let (Some(&left), Some(&right)) = (ids.get(left), ids.get(right)) {
+                    merges.insert((left, right), id);
+                }
+            }
+        }
+
+        let mut tokenizer = Self::new_internal()?;
+        tokenizer.base.set_pattern(CL100K_PATTERN)?;
+        tokenizer.next_token_id = vocab.ids().max().map_or(0, |&id| id + 1);
+        tokenizer.vocab = vocab;
+        tokenizer.merges = merges;
+        tokenizer.base_chars.clear();
+        tokenizer.journal = None;
+        Ok(tokenizer)
+    }
+
+    /// 读取 `.tiktoken` 等级表文件（如 `cl100k_base.tiktoken`）创建分词器，见 [`BBPETokenizer::from_tiktoken_ranks`]
+    ///
+    /// # Errors
+    ///
+    /// 当读取或解析失败时返回错误
+    pub fn from_tiktoken_file(path: &str) -> Result<Self, TokenizerError> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| model_load_error(format!("读取文件 {} 失败: {}", path, e)))?;
+        Self::from_tiktoken_ranks(parse_tiktoken_ranks(&data)?)
+    }
+}
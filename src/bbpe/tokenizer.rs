@@ -1,4 +1,7 @@
+use std::borrow::Cow;
 use std::collections::HashMap as StdHashMap;
+use std::sync::Arc;
+use std::time::Instant;
 
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
@@ -8,20 +11,102 @@ use pyo3::exceptions::PyValueError;
 
 use ahash::{AHashMap, AHashSet};
 use dary_heap::OctonaryHeap;
+use fancy_regex::Regex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::*;
 
-use crate::base::merge_job::MergeJob;
-use crate::base::tokenizer_base::{count_pairs_parallel, TokenizerBase};
-use crate::base::traits::{MergeBasedTokenizer, Tokenizer};
+use crate::analysis::audit::{audit_vocab, VocabAudit};
+use crate::base::binary::{self, BinaryModel, ModelKind, SectionWriter};
+use crate::base::decoder::{decode_ids, DecoderKind};
+use crate::base::events::{
+    top_pairs, PairCandidate, TrainEvent, TrainObserver, TrainObservers, TrainStats,
+};
+use crate::base::hf_json::{merge_ids, HfModel, HfTokenizerJson};
+use crate::base::integrity::IntegrityReport;
+#[cfg(feature = "python")]
+use crate::base::lossy_count::LossyCounts;
+#[cfg(feature = "python")]
+use crate::base::merge_job::TieBreak;
+use crate::base::merge_job::{sorted_by_pair, MergeJob, TieKeys};
+use crate::base::normalizer::NormalizedText;
+#[cfg(feature = "python")]
+use crate::base::normalizer::Normalizer;
+use crate::base::padding::EncodeOptions;
+#[cfg(feature = "python")]
+use crate::base::pre_tokenizer::{PreTokenizer, PreTokenizerPipeline};
+use crate::base::profile::{EncodeProfiler, Stage};
+#[cfg(feature = "python")]
+use crate::base::registry::NamedModel;
+use crate::base::remap::RemapManifest;
+use crate::base::resources::resolve_dict_file;
+use crate::base::snapshot::FrozenSnapshot;
+use crate::base::special_tokens::SpecialTokens;
+use crate::base::tokenizer_base::{
+    apply_ranked_merges, compile_pattern, count_pairs_parallel_with, normalize_line_endings,
+    ranked_merges, restore_ranked_merges, with_id_scratch, MergeEntry, MergeMap, PairCounts,
+    TokenizerBase,
+};
+use crate::base::trainer_config::TrainerConfig;
+use crate::base::traits::{MergeBasedTokenizer, SpecialTokenizer, Tokenizer, VocabBytes};
 use crate::base::vocab_manager::VocabManager;
 use crate::base::word::Word;
+use crate::bbpe::byte_level::{byte_level_to_bytes, VocabStringStyle};
+use crate::bbpe::journal::{journal_path, JournalEntry};
+#[cfg(feature = "python")]
+use crate::error::py_value_error;
+use crate::error::{
+    encoding_error, input_validation_error, invalid_utf8_error, load_error, model_load_error,
+    model_save_error, training_error, vocab_error, TokenizerError,
+};
+#[cfg(feature = "python")]
+use crate::generation::TextSampler;
+
+/// 预留特殊标记占位符的前缀，完整形式为 `<|reserved_special_token_N|>`
+pub const RESERVED_SPECIAL_TOKEN_PREFIX: &str = "<|reserved_special_token_";
+
+/// 加载模型时保留的部分，嵌入式或WASM目标上只加载需要的结构以节省内存
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ModelParts {
+    /// 完整加载词汇表和合并规则
+    #[default]
+    Full,
+    /// 只加载词汇表，跳过合并规则，只能解码
+    DecodeOnly,
+    /// 只加载合并规则、单字节标记和特殊标记，只能编码
+    EncodeOnly,
+}
+
+impl ModelParts {
+    /// 解析 `"full"`、`"decode_only"` 或 `"encode_only"`
+    ///
+    /// # Errors
+    ///
+    /// 名称无效时返回错误
+    pub fn parse(name: &str) -> Result<Self, TokenizerError> {
+        match name {
+            "full" => Ok(Self::Full),
+            "decode_only" => Ok(Self::DecodeOnly),
+            "encode_only" => Ok(Self::EncodeOnly),
+            _ => Err(input_validation_error(format!(
+                "无效的加载模式: {}（可选 full、decode_only、encode_only）",
+                name
+            ))),
+        }
+    }
+
+    /// 是否保留词汇表条目，只编码时只需要单字节标记和特殊标记
+    pub(super) fn keeps_entry(self, id: u32, bytes: &[u8], special_tokens: &SpecialTokens) -> bool {
+        self != Self::EncodeOnly || bytes.len() == 1 || special_tokens.contains_id(id)
+    }
+}
 
 /// BBPE (字节级BPE) 分词器
 #[cfg_attr(feature = "python", pyclass)]
 #[derive(Clone)]
 pub struct BBPETokenizer {
     /// 合并规则
-    pub merges: StdHashMap<(u32, u32), u32>,
+    pub merges: MergeMap,
     /// 词汇表管理器（管理 ID <-> Vec<u8> 的双向映射）
     pub vocab: VocabManager<u32, Vec<u8>>,
     /// 基础分词器
@@ -30,19 +115,34 @@ pub struct BBPETokenizer {
     pub base_chars: AHashSet<Vec<u8>>,
     /// 下一个可用的token ID
     pub next_token_id: u32,
+    /// 训练事件观察者
+    pub observers: TrainObservers,
+    /// 编码性能分析
+    pub profiler: EncodeProfiler,
+    /// 异步编码使用的只读快照，通过Python接口修改分词器时作废
+    pub async_snapshot: FrozenSnapshot<Self>,
+    /// 加载模型之后尚未写入日志的变更，`None` 表示词汇表没有对应的已保存模型（新建或重新训练后）
+    pub journal: Option<Vec<JournalEntry>>,
+    /// 加载时保留的部分，部分加载的模型不能保存
+    pub parts: ModelParts,
 }
 
 impl BBPETokenizer {
     /// 创建新的BBPE分词器
-    pub fn new_internal() -> Result<Self, String> {
+    pub fn new_internal() -> Result<Self, TokenizerError> {
         let base = TokenizerBase::new()?;
 
         let mut tokenizer = Self {
-            merges: StdHashMap::new(),
+            merges: MergeMap::new(),
             vocab: VocabManager::new(),
             base,
             base_chars: AHashSet::new(),
             next_token_id: 0,
+            observers: TrainObservers::default(),
+            profiler: EncodeProfiler::default(),
+            async_snapshot: FrozenSnapshot::default(),
+            journal: None,
+            parts: ModelParts::Full,
         };
 
         // 初始化词汇表，添加所有字节值
@@ -51,16 +151,21 @@ impl BBPETokenizer {
         Ok(tokenizer)
     }
 
-    /// 使用自定义正则表达式模式创建新的BBPE分词器
-    pub fn with_pattern_internal(pattern: String) -> Result<Self, String> {
+    /// 使用自定义正则表达式模式（或预设名称，如 `"code"`）创建新的BBPE分词器
+    pub fn with_pattern_internal(pattern: String) -> Result<Self, TokenizerError> {
         let base = TokenizerBase::with_pattern(pattern)?;
 
         let mut tokenizer = Self {
-            merges: StdHashMap::new(),
+            merges: MergeMap::new(),
             vocab: VocabManager::new(),
             base,
             base_chars: AHashSet::new(),
             next_token_id: 0,
+            observers: TrainObservers::default(),
+            profiler: EncodeProfiler::default(),
+            async_snapshot: FrozenSnapshot::default(),
+            journal: None,
+            parts: ModelParts::Full,
         };
 
         // 初始化词汇表，添加所有字节值
@@ -69,6 +174,11 @@ impl BBPETokenizer {
         Ok(tokenizer)
     }
 
+    /// 注册训练事件观察者
+    pub fn add_train_observer(&mut self, observer: Arc<dyn TrainObserver>) {
+        self.observers.add(observer);
+    }
+
     /// 从常用汉字字表文件加载基础字符
     pub fn load_base_chars(&mut self, file_path: &str) -> Result<(), std::io::Error> {
         use std::fs::File;
@@ -90,21 +200,22 @@ impl BBPETokenizer {
         Ok(())
     }
 
-    /// 从dict目录加载初始化词表
-    pub fn _load_vocab_from_dict(&mut self, dict_file: &str) -> Result<(), String> {
+    /// 加载初始化词表，`dict_file` 为词表文件路径，文件不存在时在 `dict` 目录下查找
+    pub fn _load_vocab_from_dict(&mut self, dict_file: &str) -> Result<(), TokenizerError> {
         use std::fs::File;
         use std::io::{self, BufRead};
 
-        let dict_path = format!("dict/{}", dict_file);
+        let dict_path = resolve_dict_file(dict_file);
         let file = File::open(&dict_path)
-            .map_err(|e| format!("打开词表文件 {} 失败: {}", dict_path, e))?;
+            .map_err(|e| load_error(format!("打开词表文件 {} 失败: {}", dict_path.display(), e)))?;
         let reader = io::BufReader::new(file);
 
         // 保留基础字符和字节值，添加新词汇
         let base_vocab_size = self.next_token_id;
+        self.journal = None;
 
         for line in reader.lines() {
-            let line = line.map_err(|e| format!("读取行失败: {}", e))?;
+            let line = line.map_err(|e| load_error(format!("读取行失败: {}", e)))?;
             let token = line.trim();
             if token.is_empty() {
                 continue;
@@ -124,47 +235,191 @@ impl BBPETokenizer {
         Ok(())
     }
 
-    /// 应用合并规则到ID序列（优化版：贪心合并）
+    /// 按训练顺序应用合并规则到ID序列，每一步合并等级最高的词对，见 [`apply_ranked_merges`]
     pub fn apply_merges(&self, ids: &mut Vec<u32>) {
-        // 持续应用合并规则，直到没有更多可能的合并
-        while ids.len() >= 2 {
-            // 在一次扫描中找到所有可以合并的位置
-            let mut merges_to_apply = Vec::new();
-            let mut i = 0;
-
-            while i < ids.len() - 1 {
-                if let Some(&new_id) = self.merges.get(&(ids[i], ids[i + 1])) {
-                    merges_to_apply.push((i, new_id));
-                    i += 2; // 跳过已合并的pair
-                } else {
-                    i += 1;
-                }
-            }
+        apply_ranked_merges(ids, &self.merges);
+    }
 
-            if merges_to_apply.is_empty() {
+    /// 按BPE-dropout应用合并规则
+    ///
+    /// 每一步先以概率 `dropout` 独立丢弃每个可合并的位置，再在剩余位置中合并等级最高
+    /// （在合并规则中最靠前）的一对，没有可合并的位置时停止
+    fn apply_merges_with_dropout<R: Rng>(&self, ids: &mut Vec<u32>, dropout: f64, rng: &mut R) {
+        while ids.len() >= 2 {
+            let best = ids
+                .windows(2)
+                .enumerate()
+                .filter_map(|(i, pair)| {
+                    let (rank, _, &new_id) = self.merges.get_full(&(pair[0], pair[1]))?;
+                    (!rng.gen_bool(dropout)).then_some((rank, i, new_id))
+                })
+                .min();
+            let Some((_, i, new_id)) = best else {
                 break;
-            }
+            };
+            ids[i] = new_id;
+            ids.remove(i + 1);
+        }
+    }
 
-            // 应用所有合并，从后往前以避免索引偏移问题
-            let mut new_ids = Vec::with_capacity(ids.len());
-            let mut next_merge_idx = 0;
-            let mut i = 0;
-
-            while i < ids.len() {
-                if next_merge_idx < merges_to_apply.len() && merges_to_apply[next_merge_idx].0 == i
-                {
-                    // 这个位置需要合并
-                    new_ids.push(merges_to_apply[next_merge_idx].1);
-                    i += 2; // 跳过被合并的两个token
-                    next_merge_idx += 1;
-                } else {
-                    new_ids.push(ids[i]);
-                    i += 1;
-                }
-            }
+    /// 本次编码临时改用另一种预分词方式，不修改分词器，用于在同一组合并规则上对比切分策略
+    ///
+    /// `pattern` 为 [`PATTERN_PRESETS`](crate::base::tokenizer_base::PATTERN_PRESETS) 中的名称或正则表达式。
+    /// 需要反复编码时先用 [`compile_pattern`] 编译，再调用 [`BBPETokenizer::encode_with_regex`]。
+    ///
+    /// # Errors
+    ///
+    /// 当正则表达式无效或编码失败时返回错误
+    pub fn encode_with_pattern(
+        &self,
+        text: &str,
+        pattern: &str,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        self.encode_with_regex(text, &compile_pattern(pattern)?)
+    }
+
+    /// 以已编译的正则表达式代替预分词流水线中的正则表达式步骤并编码，不修改分词器
+    ///
+    /// # Errors
+    ///
+    /// 当正则表达式匹配失败或编码失败时返回错误
+    pub fn encode_with_regex(
+        &self,
+        text: &str,
+        pattern: &Regex,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        trace_span!(debug: "bbpe.encode_with_pattern", text_len = text.len());
+        self.encode_with(text, pattern, |ids| self.apply_merges(ids))
+    }
 
-            *ids = new_ids;
+    /// 使用BPE-dropout编码文本，用于训练数据增强
+    ///
+    /// 每次合并以概率 `dropout` 被跳过，同一文本可以得到不同的切分，解码结果始终与原文一致。
+    /// 传入 `seed` 时结果可复现；`dropout` 为0时与 [`Tokenizer::encode`] 相同。
+    ///
+    /// # Errors
+    ///
+    /// 当 `dropout` 不在 `[0, 1]` 范围内或编码失败时返回错误
+    pub fn encode_with_dropout(
+        &self,
+        text: &str,
+        dropout: f64,
+        seed: Option<u64>,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        self.encode_with_dropout_rng(text, dropout, &mut rng)
+    }
+
+    /// 使用BPE-dropout并行编码一批文本
+    ///
+    /// 传入 `seed` 时第 `i` 条文本使用种子 `seed + i`，结果与线程调度无关
+    ///
+    /// # Errors
+    ///
+    /// 当 `dropout` 不在 `[0, 1]` 范围内或任意文本编码失败时返回错误
+    pub fn encode_batch_with_dropout(
+        &self,
+        texts: &[&str],
+        dropout: f64,
+        seed: Option<u64>,
+    ) -> Result<Vec<Vec<u32>>, TokenizerError> {
+        texts
+            .par_iter()
+            .enumerate()
+            .map(|(index, text)| {
+                let seed = seed.map(|seed| seed.wrapping_add(index as u64));
+                self.encode_with_dropout(text, dropout, seed)
+            })
+            .collect()
+    }
+
+    fn encode_with_dropout_rng<R: Rng>(
+        &self,
+        text: &str,
+        dropout: f64,
+        rng: &mut R,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        if !(0.0..=1.0).contains(&dropout) {
+            return Err(input_validation_error(format!(
+                "dropout必须在0到1之间，实际为 {}",
+                dropout
+            )));
+        }
+        if dropout == 0.0 {
+            return self.encode(text);
+        }
+        trace_span!(debug: "bbpe.encode_with_dropout", text_len = text.len());
+        self.encode_with(text, &self.base.compiled_pattern, |ids| {
+            self.apply_merges_with_dropout(ids, dropout, rng)
+        })
+    }
+
+    /// 按特殊标记切分文本，以 `pattern` 作为预分词流水线中的正则表达式步骤，
+    /// 预分词后对其余各段每个片段的字节ID调用 `merge`
+    fn encode_with<F: FnMut(&mut Vec<u32>)>(
+        &self,
+        text: &str,
+        pattern: &Regex,
+        mut merge: F,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        if self.parts == ModelParts::DecodeOnly {
+            return Err(encoding_error("模型只加载了词汇表，不能编码"));
+        }
+        let limits = &self.base.encode_options.limits;
+        let text = limits.check_input(text)?;
+        let mut result = self.base.special_tokens.encode_with(text, |segment| {
+            self.encode_segment(segment, pattern, &mut merge)
+        })?;
+        limits.check_tokens(&mut result)?;
+        self.profiler.add_texts(1);
+        Ok(result)
+    }
+
+    /// 预分词后对每个片段的字节ID调用 `merge`
+    fn encode_segment<F: FnMut(&mut Vec<u32>)>(
+        &self,
+        text: &str,
+        pattern: &Regex,
+        merge: &mut F,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        let profiler = &self.profiler;
+        let text = self.base.normalizer.apply(text);
+        let text = text.as_ref();
+        let mut result = Vec::new();
+        // 片段直接借用原文，字节ID写入线程内复用的缓冲区
+        let mut encode_piece = |piece: &str, result: &mut Vec<u32>| {
+            with_id_scratch(|ids| {
+                profiler.time(Stage::VocabLookup, || {
+                    self.push_byte_ids(piece.as_bytes(), ids)
+                })?;
+                profiler.time(Stage::Merge, || merge(ids));
+                result.extend_from_slice(ids);
+                Ok::<_, TokenizerError>(())
+            })
+        };
+
+        let pre_tokenizer = self.base.pre_tokenizer.with_pattern(pattern);
+        profiler.pre_tokenize(&pre_tokenizer, text, &mut |piece| {
+            encode_piece(piece, &mut result)
+        })?;
+
+        Ok(result)
+    }
+
+    /// 将字节序列对应的字节token ID追加到 `ids`
+    fn push_byte_ids(&self, bytes: &[u8], ids: &mut Vec<u32>) -> Result<(), TokenizerError> {
+        for &byte in bytes {
+            // 词汇表初始化时已经添加了所有字节，找不到说明词汇表被破坏
+            let id = self
+                .vocab
+                .get_by_value([byte].as_slice())
+                .ok_or_else(|| encoding_error(format!("未找到字节 {} 对应的ID", byte)))?;
+            ids.push(*id);
         }
+        Ok(())
     }
 
     /// 给定唯一词的核心增量BPE训练
@@ -173,23 +428,32 @@ impl BBPETokenizer {
         mut words: Vec<Word<u32>>,
         counts: Vec<i32>,
         vocab_size: u32,
-    ) -> Result<(), String> {
-        let num_merges = vocab_size - self.vocab.len() as u32;
-        log::info!("开始增量BBPE训练: 需要计算 {} 次合并", num_merges);
-        self.merges.clear();
+    ) -> Result<(), TokenizerError> {
+        let started = Instant::now();
+        let num_merges = vocab_size.saturating_sub(self.vocab.len() as u32);
+        self.observers.emit(TrainEvent::TrainStarted {
+            unique_sequences: words.len(),
+            target_vocab_size: vocab_size,
+            planned_merges: num_merges,
+        });
 
         // ---- 初始配对计数和更新位置（并行） ----
-        log::info!("从 {} 个唯一序列计算初始配对计数", words.len());
-        let (pair_counts, where_to_update) = count_pairs_parallel(&words, &counts);
+        let (pair_counts, where_to_update) =
+            count_pairs_parallel_with(&words, &counts, self.base.parallel);
+        self.observers.emit(TrainEvent::PairCountsDone {
+            unique_pairs: pair_counts.len(),
+        });
 
         // ---- 构建堆 ----
-        log::info!("使用 {} 个唯一配对构建堆", pair_counts.len());
+        // 按配对顺序入堆，计数和排序键都相同的任务出堆顺序与哈希表的迭代顺序无关
+        let mut tie_keys = TieKeys::new(self.base.tie_break, &words);
         let heap = {
             let mut heap = OctonaryHeap::with_capacity(pair_counts.len());
-            for (pair, pos) in where_to_update {
+            for (pair, pos) in sorted_by_pair(where_to_update) {
                 let c = *pair_counts.get(&pair).unwrap_or(&0);
                 if c > 0 {
-                    let mut merge_job = MergeJob::new(pair, c as u64);
+                    let tie = tie_keys.key(pair, |id| self.vocab.get_by_id(&id).map(Vec::as_slice));
+                    let mut merge_job = MergeJob::new(pair, c as u64).with_tie(tie);
                     merge_job.add_positions(&pos);
                     heap.push(merge_job);
                 }
@@ -199,14 +463,19 @@ impl BBPETokenizer {
         let mut heap = heap;
 
         // ---- 合并循环 ----
-        log::info!("开始合并循环");
-        let _merges_done = {
+        let mut stopped = false;
+        let merges_done = {
             let mut merges_done = 0u32;
-            let mut last_log_percent = 0u32;
-            let initial_vocab_size = self.vocab.len() as u32;
+            let mut next_id = self.next_token_id.max(self.vocab.len() as u32);
             let mut pair_counts = pair_counts;
+            self.observers
+                .snapshot(merges_done, |k| self.pair_candidates(&pair_counts, k));
 
-            while merges_done < num_merges {
+            while (self.vocab.len() as u32) < vocab_size {
+                if self.observers.should_stop() {
+                    stopped = true;
+                    break;
+                }
                 let Some(top) = heap.pop() else {
                     break;
                 };
@@ -219,25 +488,45 @@ impl BBPETokenizer {
                 } else {
                     continue;
                 }
-
-                let new_id = initial_vocab_size + merges_done;
-                self.merges.insert(top.pair, new_id);
+                // 堆顶计数最大，之后的配对都不会达到最低出现次数
+                if !self.base.trainer.keeps_frequency(top.count) {
+                    break;
+                }
 
                 // 创建新标记
                 let new_token_bytes = {
-                    let first = self
-                        .vocab
-                        .get_by_id(&top.pair.0)
-                        .ok_or_else(|| format!("词汇表中缺少token ID: {}", top.pair.0))?;
-                    let second = self
-                        .vocab
-                        .get_by_id(&top.pair.1)
-                        .ok_or_else(|| format!("词汇表中缺少token ID: {}", top.pair.1))?;
+                    let first = self.vocab.get_by_id(&top.pair.0).ok_or_else(|| {
+                        training_error(format!("词汇表中缺少token ID: {}", top.pair.0))
+                    })?;
+                    let second = self.vocab.get_by_id(&top.pair.1).ok_or_else(|| {
+                        training_error(format!("词汇表中缺少token ID: {}", top.pair.1))
+                    })?;
                     let mut new_token_bytes = first.clone();
                     new_token_bytes.extend(second);
                     new_token_bytes
                 };
-                self.vocab.insert(new_id, new_token_bytes);
+                if !self.base.trainer.allows_length(new_token_bytes.len()) {
+                    continue;
+                }
+
+                // 不同的合并路径可能得到相同的字节序列（如 "a"+"ab" 与 "aa"+"b"），
+                // 此时复用已有ID，保持词汇表双向映射一致
+                let new_id = match self.vocab.get_by_value(&new_token_bytes) {
+                    Some(&id) => id,
+                    None => {
+                        let id = next_id;
+                        next_id += 1;
+                        self.vocab.insert(id, new_token_bytes);
+                        id
+                    }
+                };
+                self.merges.insert(top.pair, new_id);
+                self.observers.emit(TrainEvent::MergeApplied {
+                    rank: merges_done,
+                    pair: top.pair,
+                    new_id,
+                    count: top.count,
+                });
 
                 // 更新受影响的词
                 let (updated_pairs, updated_where) = {
@@ -255,37 +544,166 @@ impl BBPETokenizer {
                 };
 
                 // 更新全局计数
-                for (pair, delta) in updated_pairs {
+                for (pair, delta) in sorted_by_pair(updated_pairs) {
                     let entry = pair_counts.entry(pair).or_insert(0);
                     *entry += delta;
 
                     if *entry <= 0 {
                         pair_counts.remove(&pair);
                     } else if let Some(pos_set) = updated_where.get(&pair) {
-                        let mut merge_job = MergeJob::new(pair, *entry as u64);
+                        let tie =
+                            tie_keys.key(pair, |id| self.vocab.get_by_id(&id).map(Vec::as_slice));
+                        let mut merge_job = MergeJob::new(pair, *entry as u64).with_tie(tie);
                         merge_job.add_positions(&pos_set.iter().cloned().collect::<Vec<_>>());
                         heap.push(merge_job);
                     }
                 }
+                tie_keys.next_step();
 
                 merges_done += 1;
-
-                // 每10%记录一次进度
-                let percent = merges_done * 100 / num_merges;
-                if percent > last_log_percent {
-                    log::info!("训练进度: {}% ({} 次合并)", percent, merges_done);
-                    last_log_percent = percent;
-                }
+                self.observers
+                    .snapshot(merges_done, |k| self.pair_candidates(&pair_counts, k));
             }
+            self.next_token_id = next_id;
             merges_done
         };
+
+        self.observers.emit(TrainEvent::Finished {
+            stats: TrainStats {
+                merges: merges_done,
+                vocab_size: self.vocab.len(),
+                elapsed: started.elapsed(),
+                stopped,
+            },
+        });
+        Ok(())
+    }
+
+    /// 计数最高的 `k` 个候选配对及其合并后的字节
+    fn pair_candidates(&self, pair_counts: &PairCounts<u32>, k: usize) -> Vec<PairCandidate> {
+        top_pairs(pair_counts, k)
+            .into_iter()
+            .map(|(pair, count)| {
+                let bytes = |id| self.vocab.get_by_id(&id).map_or(&[][..], Vec::as_slice);
+                PairCandidate {
+                    pair,
+                    count,
+                    piece: String::from_utf8_lossy(&[bytes(pair.0), bytes(pair.1)].concat())
+                        .into_owned(),
+                }
+            })
+            .collect()
+    }
+
+    /// 把 [`TrainerConfig::initial_alphabet`] 中的每个多字节字符从左到右逐字节合并为单个标记
+    ///
+    /// 字节级词汇表已经包含全部256个字节，初始字母表用于保证这些字符各自对应一个完整的标记。
+    /// 生成的合并规则排在训练学到的规则之前
+    fn seed_initial_alphabet(&mut self) {
+        let mut next_id = self.next_token_id.max(self.vocab.len() as u32);
+        for ch in self.base.trainer.initial_alphabet.clone() {
+            let mut buf = [0u8; 4];
+            let bytes = ch.encode_utf8(&mut buf).as_bytes();
+            let mut left = *self
+                .vocab
+                .get_by_value(&bytes[..1])
+                .expect("all byte values are in the vocabulary");
+            for end in 2..=bytes.len() {
+                let right = *self
+                    .vocab
+                    .get_by_value(&bytes[end - 1..end])
+                    .expect("all byte values are in the vocabulary");
+                let merged = match self.vocab.get_by_value(&bytes[..end]) {
+                    Some(&id) => id,
+                    None => {
+                        let id = next_id;
+                        next_id += 1;
+                        self.vocab.insert(id, bytes[..end].to_vec());
+                        id
+                    }
+                };
+                self.merges.entry((left, right)).or_insert(merged);
+                left = merged;
+            }
+        }
+        self.next_token_id = next_id;
+    }
+
+    /// 从头训练前检查目标大小、准备词汇表并注册配置中的特殊标记，清空合并规则后写入初始字母表
+    fn prepare_training(&mut self, vocab_size: u32) -> Result<(), TokenizerError> {
+        if vocab_size < 256 {
+            return Err(training_error("词汇表大小必须至少为256"));
+        }
+
+        // 只有在词汇表为空时才初始化
+        if self.vocab.is_empty() {
+            self.init_vocab();
+        }
+        // 重新训练会改变整个合并表，只能完整保存
+        self.journal = None;
+        // 配置中的特殊标记先于合并结果分配ID
+        self.base.trainer.clone().register_special_tokens(self)?;
+        self.merges.clear();
+        self.seed_initial_alphabet();
         Ok(())
     }
 
+    /// 把训练片段转换为字节ID序列，初始字母表中的字符已经合并为单个标记
+    fn training_word(&self, piece: &[u8]) -> Word<u32> {
+        let mut ids: Vec<u32> = piece
+            .iter()
+            .map(|&b| {
+                *self
+                    .vocab
+                    .get_by_value([b].as_slice())
+                    .unwrap_or_else(|| panic!("字节 {} 在vocab中不存在", b))
+            })
+            .collect();
+        apply_ranked_merges(&mut ids, &self.merges);
+        Word::new(ids)
+    }
+
+    /// 直接从文本文件训练，支持目录、glob模式以及gzip和zstd压缩文件
+    ///
+    /// 文件按行流式读取，内存中只保留唯一片段的计数，不会把语料读成 `Vec<String>`。
+    /// 片段按文本排序后训练，结果与文件中行的顺序无关，`shuffle_seed` 不起作用
+    ///
+    /// # Errors
+    ///
+    /// 当词汇表大小小于256、文件无法读取或预分词失败时返回错误
+    #[cfg(feature = "files")]
+    pub fn train_from_files<P: AsRef<std::path::Path>>(
+        &mut self,
+        paths: &[P],
+        vocab_size: u32,
+        config: TrainerConfig,
+    ) -> Result<(), TokenizerError> {
+        trace_span!("bbpe.train_from_files", files = paths.len(), vocab_size);
+        let previous = std::mem::replace(&mut self.base.trainer, config);
+        let result = self.prepare_training(vocab_size);
+        let result = result.and_then(|()| {
+            let base = &self.base;
+            let pieces = crate::corpus::files::count_chunks(paths, &base.special_tokens, |text| {
+                base.split_text(text)
+            })?;
+            let (words, counts) = pieces
+                .iter()
+                .map(|(piece, count)| (self.training_word(piece.as_bytes()), *count))
+                .unzip();
+            self.train_core_incremental(words, counts, vocab_size)?;
+            log::info!("BBPE训练完成，最终词汇表大小: {}", self.vocab.len());
+            Ok(())
+        });
+        self.base.trainer = previous;
+        result
+    }
+
     /// 初始化词汇表
     fn init_vocab(&mut self) {
         log::info!("初始化词汇表");
         self.vocab.clear();
+        self.journal = None;
+        self.parts = ModelParts::Full;
 
         // 首先添加基础字符（如果有）
         for (i, char_bytes) in self.base_chars.iter().enumerate() {
@@ -310,6 +728,35 @@ impl BBPETokenizer {
     }
 }
 
+/// 按特殊标记切分原始字节串，其余文本规范化后按预分词流水线切分，特殊标记被去除，
+/// 无效UTF-8的字节段原样作为单独的片段
+#[cfg(feature = "python")]
+fn split_training_bytes(bytes: &[u8], base: &TokenizerBase<u32>) -> Vec<Vec<u8>> {
+    let mut pieces = Vec::new();
+    for chunk in bytes.utf8_chunks() {
+        for segment in base.special_tokens.split(chunk.valid()) {
+            let crate::base::special_tokens::Segment::Text(text) = segment else {
+                continue;
+            };
+            let text = base.normalizer.apply(text);
+            // 匹配失败时跳过该段的剩余部分
+            let _ = base.pre_tokenizer().pre_tokenize(&text, &mut |piece| {
+                pieces.push(piece.as_bytes().to_vec());
+                Ok(())
+            });
+        }
+        if !chunk.invalid().is_empty() {
+            pieces.push(chunk.invalid().to_vec());
+        }
+    }
+    pieces
+}
+
+/// 是否为尚未分配的预留特殊标记占位符
+fn is_reserved_placeholder(bytes: &[u8]) -> bool {
+    bytes.starts_with(RESERVED_SPECIAL_TOKEN_PREFIX.as_bytes()) && bytes.ends_with(b"|>")
+}
+
 impl Default for BBPETokenizer {
     fn default() -> Self {
         Self::new_internal().expect(
@@ -326,21 +773,20 @@ impl BBPETokenizer {
     /// 创建一个新的BBPE分词器，使用默认的GPT-4风格正则表达式模式
     #[new]
     pub fn new() -> PyResult<Self> {
-        Self::new_internal()
-            .map_err(|e| crate::error::TokenizerError::InitializationError { message: e }.into())
+        Self::new_internal().map_err(Into::into)
     }
 
-    /// 使用自定义正则表达式模式创建新的BBPE分词器
+    /// 使用自定义正则表达式模式（或预设名称，如 `"code"`）创建新的BBPE分词器
     #[staticmethod]
     pub fn with_pattern(pattern: String) -> PyResult<Self> {
-        Self::with_pattern_internal(pattern)
-            .map_err(|e| crate::error::TokenizerError::InitializationError { message: e }.into())
+        Self::with_pattern_internal(pattern).map_err(Into::into)
     }
 
     /// 从常用汉字字表文件加载基础字符
     #[cfg(feature = "python")]
     #[pyo3(name = "load_base_chars")]
     pub fn py_load_base_chars(&mut self, file_path: String) -> PyResult<()> {
+        self.async_snapshot.invalidate();
         self.load_base_chars(&file_path)?;
 
         // 重新初始化词汇表以包含新加载的基础字符
@@ -353,8 +799,8 @@ impl BBPETokenizer {
     #[cfg(feature = "python")]
     #[pyo3(name = "load_vocab_from_dict")]
     pub fn py_load_vocab_from_dict(&mut self, dict_file: String) -> PyResult<()> {
-        self._load_vocab_from_dict(&dict_file)
-            .map_err(|e| crate::error::TokenizerError::LoadError { message: e }.into())
+        self.async_snapshot.invalidate();
+        self._load_vocab_from_dict(&dict_file).map_err(Into::into)
     }
 
     /// 从Python迭代器训练分词器
@@ -366,29 +812,276 @@ impl BBPETokenizer {
         vocab_size: usize,
         _show_progress: bool,
     ) -> PyResult<()> {
-        self.train(texts, vocab_size as u32)
-            .map_err(|e| crate::error::TokenizerError::TrainingError { message: e }.into())
+        self.async_snapshot.invalidate();
+        self.train(texts, vocab_size as u32).map_err(PyErr::from)
     }
 
     /// 从迭代器训练分词器
     #[cfg(feature = "python")]
     #[pyo3(name = "train")]
     pub fn py_train(&mut self, texts: Vec<String>, vocab_size: usize) -> PyResult<()> {
-        self.train(texts, vocab_size as u32)
-            .map_err(|e| crate::error::TokenizerError::TrainingError { message: e }.into())
+        self.async_snapshot.invalidate();
+        self.train(texts, vocab_size as u32).map_err(PyErr::from)
     }
 
-    /// 从迭代器训练分词器
+    /// 从流式迭代器训练，每次读取 `buffer_size` 项，内存中只保留唯一片段的计数
+    ///
+    /// 迭代器的项可以是 `str`，也可以是 `bytes`/`bytearray`：字节串不经解码直接参与训练，
+    /// 其中的有效UTF-8部分按特殊标记和预分词流水线切分，无效字节各自成为一个片段。
+    ///
+    /// 给出 `max_unique_pieces` 时，唯一片段数超过上限后按自适应阈值丢弃低频片段，
+    /// 规则和误差上界见 [`LossyCounts`]。
+    ///
+    /// 返回摘要字典：`sequences` 为参与训练的项数，`bytes` 为其中的字节串项数，
+    /// `unique_pieces` 为参与训练的唯一片段数，`pruned` 为累计丢弃的片段数，
+    /// `count_error` 为片段计数的误差上界（未丢弃时为0）
     #[cfg(feature = "python")]
-    #[pyo3(name = "train_from_iterator_stream")]
-    pub fn train_from_iterator(
+    #[pyo3(
+        name = "train_from_iterator_stream",
+        signature = (
+            iterator,
+            vocab_size,
+            _show_progress = false,
+            buffer_size = 8192,
+            max_unique_pieces = None
+        )
+    )]
+    pub fn train_from_iterator<'py>(
         &mut self,
-        texts: Vec<String>,
+        py: Python<'py>,
+        iterator: &Bound<'py, PyAny>,
         vocab_size: usize,
         _show_progress: bool,
+        buffer_size: usize,
+        max_unique_pieces: Option<usize>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        self.async_snapshot.invalidate();
+        trace_span!("bbpe.train_from_iterator", vocab_size, buffer_size);
+        let vocab_size = vocab_size as u32;
+        let mut counts = LossyCounts::new(max_unique_pieces).map_err(py_value_error)?;
+        self.prepare_training(vocab_size)?;
+        let mut iter =
+            iterator
+                .try_iter()
+                .map_err(|e| crate::error::TokenizerError::InvalidIterator {
+                    message: e.to_string(),
+                })?;
+
+        let mut buf: Vec<Vec<u8>> = Vec::with_capacity(buffer_size);
+        let (mut sequences, mut raw) = (0u64, 0u64);
+        let mut exhausted = false;
+        while !exhausted {
+            // 在GIL下填充缓冲区，在GIL外并行切分和计数
+            buf.clear();
+            while buf.len() < buffer_size.max(1) {
+                let Some(item) = iter.next() else {
+                    exhausted = true;
+                    break;
+                };
+                let item = item?;
+                if let Ok(text) = item.downcast::<pyo3::types::PyString>() {
+                    buf.push(text.to_str()?.as_bytes().to_vec());
+                } else if let Ok(bytes) = item.downcast::<pyo3::types::PyBytes>() {
+                    buf.push(bytes.as_bytes().to_vec());
+                    raw += 1;
+                } else if let Ok(bytes) = item.downcast::<pyo3::types::PyByteArray>() {
+                    buf.push(bytes.to_vec());
+                    raw += 1;
+                } else {
+                    return Err(crate::error::TokenizerError::InvalidInput {
+                        message: format!(
+                            "迭代器中的项不是字符串或字节串: {}",
+                            item.get_type().name()?
+                        ),
+                    }
+                    .into());
+                }
+            }
+            sequences += buf.len() as u64;
+
+            let base = &self.base;
+            let chunking = self.base.parallel;
+            let local: StdHashMap<Vec<u8>, i32, ahash::RandomState> = py.allow_threads(|| {
+                chunking.count(&buf, |bytes, m| {
+                    for piece in split_training_bytes(bytes, base) {
+                        *m.entry(piece).or_default() += 1;
+                    }
+                })
+            });
+            counts.extend(local);
+        }
+        log::info!(
+            "从迭代器读取 {} 项，得到 {} 个唯一片段，丢弃 {} 个低频片段",
+            sequences,
+            counts.len(),
+            counts.pruned()
+        );
+        let (unique_pieces, pruned, count_error) =
+            (counts.len(), counts.pruned(), counts.threshold());
+
+        // 按片段排序，结果与迭代顺序无关
+        let mut pieces: Vec<(Vec<u8>, i32)> = counts.into_counts().collect();
+        pieces.sort_unstable();
+        let (words, counts) = pieces
+            .iter()
+            .map(|(piece, count)| (self.training_word(piece), *count))
+            .unzip();
+        self.train_core_incremental(words, counts, vocab_size)?;
+
+        let dict = pyo3::types::PyDict::new(py);
+        dict.set_item("sequences", sequences)?;
+        dict.set_item("bytes", raw)?;
+        dict.set_item("unique_pieces", unique_pieces)?;
+        dict.set_item("pruned", pruned)?;
+        dict.set_item("count_error", count_error)?;
+        Ok(dict)
+    }
+
+    /// 设置训练时并行计数的粒度：每个任务处理的片段数（0为自适应）和归并树宽度
+    #[cfg(feature = "python")]
+    #[pyo3(name = "set_parallel_chunking", signature = (pieces_per_task = 0, reduce_width = 2))]
+    pub fn py_set_parallel_chunking(
+        &mut self,
+        pieces_per_task: usize,
+        reduce_width: usize,
+    ) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.base.parallel =
+            crate::base::tokenizer_base::ParallelChunking::new(pieces_per_task, reduce_width)
+                .map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 设置之后训练使用的配置：最低合并次数、新标记的最大字节数、训练前注册的特殊标记和初始字母表
+    ///
+    /// 字节级词汇表总是包含全部256个字节，`limit_alphabet` 不起作用
+    #[cfg(feature = "python")]
+    #[pyo3(
+        name = "set_trainer_config",
+        signature = (
+            min_frequency = 0,
+            max_piece_length = None,
+            special_tokens = None,
+            initial_alphabet = None,
+            limit_alphabet = None,
+            shuffle_seed = None
+        )
+    )]
+    pub fn py_set_trainer_config(
+        &mut self,
+        min_frequency: u64,
+        max_piece_length: Option<usize>,
+        special_tokens: Option<Vec<String>>,
+        initial_alphabet: Option<Vec<char>>,
+        limit_alphabet: Option<usize>,
+        shuffle_seed: Option<u64>,
+    ) {
+        self.async_snapshot.invalidate();
+        self.base.trainer = TrainerConfig::from_py(
+            min_frequency,
+            max_piece_length,
+            special_tokens,
+            initial_alphabet,
+            limit_alphabet,
+            shuffle_seed,
+        );
+    }
+
+    /// 设置训练时计数相同的配对之间的合并顺序："pair_id"（默认）、"lexicographic" 或 "insertion_order"
+    #[cfg(feature = "python")]
+    #[pyo3(name = "set_tie_break")]
+    pub fn py_set_tie_break(&mut self, mode: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.base.tie_break = match mode {
+            "pair_id" => TieBreak::PairId,
+            "lexicographic" => TieBreak::Lexicographic,
+            "insertion_order" => TieBreak::InsertionOrder,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "未知的平局处理方式: {}，可选 \"pair_id\"、\"lexicographic\" 或 \"insertion_order\"",
+                    other
+                )))
+            }
+        };
+        Ok(())
+    }
+
+    /// 设置预分词之前的规范化流水线，步骤以逗号分隔，如 `"nfkc,lowercase"`，`"none"` 表示不做规范化
+    ///
+    /// 可选步骤：nfc、nfkc、nfkd、lowercase、strip_accents、clean_whitespace
+    #[cfg(feature = "python")]
+    #[pyo3(name = "set_normalizer")]
+    pub fn py_set_normalizer(&mut self, spec: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.base.normalizer = Normalizer::parse(spec).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的规范化流水线，格式同 `set_normalizer`
+    #[cfg(feature = "python")]
+    #[pyo3(name = "get_normalizer")]
+    pub fn py_get_normalizer(&self) -> String {
+        self.base.normalizer.spec()
+    }
+
+    /// 设置预分词流水线，步骤以逗号分隔，如 `"whitespace,punctuation"`，按顺序逐步细分片段，
+    /// `"none"` 表示不切分
+    ///
+    /// 可选步骤：regex（按 `set_pattern` 设置的正则表达式，默认流水线只有这一步）、whitespace、
+    /// byte_level、metaspace、digits、punctuation
+    #[cfg(feature = "python")]
+    #[pyo3(name = "set_pre_tokenizer")]
+    pub fn py_set_pre_tokenizer(&mut self, spec: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.base.pre_tokenizer = PreTokenizerPipeline::parse(spec).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的预分词流水线，格式同 `set_pre_tokenizer`
+    #[cfg(feature = "python")]
+    #[pyo3(name = "get_pre_tokenizer")]
+    pub fn py_get_pre_tokenizer(&self) -> String {
+        self.base.pre_tokenizer.spec()
+    }
+
+    /// 设置解码器："fuse"（默认，直接拼接）、"byte_level"（还原 `Ġ` 等字节映射字符）、
+    /// "metaspace"（`▁` 还原为空格并去掉开头的空格）或 "wordpiece"（词之间补空格）
+    #[cfg(feature = "python")]
+    #[pyo3(name = "set_decoder")]
+    pub fn py_set_decoder(&mut self, name: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.base.decoder = DecoderKind::from_name(name).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的解码器名称
+    #[cfg(feature = "python")]
+    #[pyo3(name = "get_decoder")]
+    pub fn py_get_decoder(&self) -> &'static str {
+        self.base.decoder.name()
+    }
+
+    /// 注册训练事件回调，回调接收描述事件的dict
+    ///
+    /// 给出 `snapshot_every` 时，训练开始时和每完成这么多次合并后收到一个 `pair_snapshot` 事件，
+    /// 其中 `candidates` 为当前计数最高的 `top_k` 个配对。回调返回 `False` 时训练在下一次合并之前停止
+    #[cfg(feature = "python")]
+    #[pyo3(
+        name = "add_train_observer",
+        signature = (callback, snapshot_every = None, top_k = 10)
+    )]
+    pub fn py_add_train_observer(
+        &mut self,
+        callback: Py<PyAny>,
+        snapshot_every: Option<u32>,
+        top_k: usize,
     ) -> PyResult<()> {
-        self.train(texts, vocab_size as u32)
-            .map_err(|e| crate::error::TokenizerError::TrainingError { message: e }.into())
+        self.async_snapshot.invalidate();
+        let observer =
+            crate::base::events::PyTrainObserver::from_py(callback, snapshot_every, top_k)
+                .map_err(py_value_error)?;
+        self.add_train_observer(Arc::new(observer));
+        Ok(())
     }
 
     /// 返回正则表达式模式
@@ -401,7 +1094,7 @@ impl BBPETokenizer {
     /// 获取合并等级映射
     #[cfg(feature = "python")]
     #[pyo3(name = "get_mergeable_ranks")]
-    pub fn py_get_mergeable_ranks(&self) -> StdHashMap<(u32, u32), u32> {
+    pub fn py_get_mergeable_ranks(&self) -> MergeMap {
         self.get_mergeable_ranks()
     }
 
@@ -409,302 +1102,1346 @@ impl BBPETokenizer {
     #[cfg(feature = "python")]
     #[pyo3(name = "encode")]
     pub fn py_encode(&self, text: &str) -> PyResult<Vec<u32>> {
-        self.encode(text)
-            .map_err(|e| crate::error::TokenizerError::EncodingError { message: e }.into())
+        self.encode(text).map_err(PyErr::from)
     }
 
-    /// 将token IDs解码为文本
+    /// 使用BPE-dropout编码文本，传入 `seed` 时结果可复现
     #[cfg(feature = "python")]
-    #[pyo3(name = "decode")]
-    pub fn py_decode(&self, tokens: Vec<u32>) -> PyResult<String> {
-        self.decode(&tokens)
-            .map_err(|e| crate::error::TokenizerError::DecodingError { message: e }.into())
+    #[pyo3(name = "encode_with_dropout", signature = (text, dropout, seed = None))]
+    pub fn py_encode_with_dropout(
+        &self,
+        text: &str,
+        dropout: f64,
+        seed: Option<u64>,
+    ) -> PyResult<Vec<u32>> {
+        self.encode_with_dropout(text, dropout, seed)
+            .map_err(py_value_error)
     }
 
-    /// 批量编码文本为token IDs（并行处理）
+    /// 使用BPE-dropout并行编码一批文本，第 `i` 条文本使用种子 `seed + i`
     #[cfg(feature = "python")]
-    #[pyo3(name = "encode_batch")]
-    pub fn py_encode_batch(&self, texts: Vec<String>) -> PyResult<Vec<Vec<u32>>> {
-        // 使用rayon并行处理所有文本
-        let results: Result<Vec<Vec<u32>>, String> =
-            texts.par_iter().map(|text| self.encode(text)).collect();
-
-        results.map_err(|e| crate::error::TokenizerError::EncodingError { message: e }.into())
+    #[pyo3(name = "encode_batch_with_dropout", signature = (texts, dropout, seed = None))]
+    pub fn py_encode_batch_with_dropout(
+        &self,
+        py: Python<'_>,
+        texts: Vec<String>,
+        dropout: f64,
+        seed: Option<u64>,
+    ) -> PyResult<Vec<Vec<u32>>> {
+        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+        py.allow_threads(|| self.encode_batch_with_dropout(&texts, dropout, seed))
+            .map_err(py_value_error)
     }
 
-    /// 批量解码token IDs为文本（并行处理）
+    /// 将token IDs解码为文本，`skip_special_tokens` 为真时跳过特殊标记
     #[cfg(feature = "python")]
-    #[pyo3(name = "decode_batch")]
-    pub fn py_decode_batch(&self, token_lists: Vec<Vec<u32>>) -> PyResult<Vec<String>> {
-        // 使用rayon并行处理所有token列表
-        let results: Result<Vec<String>, String> = token_lists
-            .par_iter()
-            .map(|tokens| self.decode(tokens))
-            .collect();
-
-        results.map_err(|e| PyValueError::new_err(e.to_string()))
+    #[pyo3(name = "decode", signature = (tokens, skip_special_tokens = false))]
+    pub fn py_decode(&self, tokens: Vec<u32>, skip_special_tokens: bool) -> PyResult<String> {
+        self.decode_with(&tokens, skip_special_tokens)
+            .map_err(PyErr::from)
     }
 
-    /// 获取词汇表大小
+    /// 本次编码临时改用另一种预分词方式（预设名称或正则表达式），不修改分词器
     #[cfg(feature = "python")]
-    #[pyo3(name = "vocab_size")]
-    pub fn py_vocab_size(&self) -> usize {
-        self.vocab_size()
+    #[pyo3(name = "encode_with_pattern")]
+    pub fn py_encode_with_pattern(&self, text: &str, pattern: &str) -> PyResult<Vec<u32>> {
+        self.encode_with_pattern(text, pattern).map_err(Into::into)
     }
 
-    /// 获取词汇表
+    /// 批量编码文本为token IDs（并行处理）
+    ///
+    /// `texts` 可以是字符串列表，也可以是pyarrow的字符串数组、ChunkedArray、RecordBatch或Table
+    /// （多列时通过 `column` 指定列名）。Arrow输入直接从缓冲区读取字符串，空值编码为空列表
     #[cfg(feature = "python")]
-    #[pyo3(name = "get_vocab")]
-    pub fn py_get_vocab(&self) -> StdHashMap<u32, Vec<u8>> {
-        self.vocab.id_map().clone()
+    #[pyo3(name = "encode_batch", signature = (texts, column = None))]
+    pub fn py_encode_batch<'py>(
+        &self,
+        texts: &Bound<'py, PyAny>,
+        column: Option<&str>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let py = texts.py();
+        let profiler = &self.profiler;
+        let start = profiler.start_batch();
+
+        let input = profiler.time(Stage::PythonConversion, || {
+            crate::base::py_arrow::BatchInput::extract(texts, column)
+        })?;
+        let texts = input.texts();
+
+        let ids = py.allow_threads(|| self.encode_batch_internal(&texts))?;
+        let result = profiler.time(Stage::PythonConversion, || ids.into_pyobject(py))?;
+
+        profiler.finish_batch(start);
+        Ok(result)
     }
 
-    /// 获取反向词汇表
+    /// 异步批量编码，返回可在asyncio中await的future
+    ///
+    /// 编码在共享的后台线程池中并行执行并释放GIL，不会阻塞事件循环。编码使用调用时的分词器快照，
+    /// 之后的训练或加载不影响结果；快照在分词器被修改后的第一次调用时复制，其余调用共用同一份
     #[cfg(feature = "python")]
-    #[pyo3(name = "get_vocab_rev")]
-    pub fn py_get_vocab_rev(&self) -> StdHashMap<Vec<u8>, u32> {
-        self.vocab.value_map().clone()
+    #[pyo3(name = "encode_batch_async")]
+    pub fn py_encode_batch_async<'py>(
+        slf: &Bound<'py, Self>,
+        texts: Vec<String>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        crate::base::py_future::spawn_with_future(
+            slf,
+            |this| {
+                // 快照在调用线程上复制，耗时统计仍计入原分词器
+                this.async_snapshot.get_or_freeze(|| {
+                    let mut snapshot = this.clone();
+                    snapshot.profiler = this.profiler.share();
+                    snapshot
+                })
+            },
+            move |this| {
+                let start = this.profiler.start_batch();
+                let texts: Vec<Option<&str>> =
+                    texts.iter().map(|text| Some(text.as_str())).collect();
+                let result = this.encode_batch_internal(&texts).map_err(PyErr::from);
+                this.profiler.finish_batch(start);
+                result
+            },
+        )
     }
 
-    /// 获取合并规则
+    /// 开启或关闭分阶段的编码耗时统计
     #[cfg(feature = "python")]
-    #[pyo3(name = "get_merges")]
-    pub fn py_get_merges(&self) -> StdHashMap<(u32, u32), u32> {
-        self.merges.clone()
+    #[pyo3(name = "enable_profiling", signature = (enabled = true))]
+    pub fn py_enable_profiling(&self, enabled: bool) {
+        self.profiler.set_enabled(enabled);
     }
 
-    /// 保存分词器到文件
+    /// 获取最近一次批量编码的分阶段耗时（纳秒）
+    ///
+    /// 返回的字典包含 texts、pre_tokenize_ns、vocab_lookup_ns、merge_ns、python_conversion_ns 和 total_ns
     #[cfg(feature = "python")]
-    #[pyo3(name = "save")]
-    pub fn py_save(&self, path: String) -> PyResult<()> {
-        self.save(&path)
-            .map_err(|e| crate::error::TokenizerError::ModelSaveError { message: e }.into())
+    #[pyo3(name = "get_profile")]
+    pub fn py_get_profile<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        self.profiler.snapshot().to_py_dict(py)
     }
 
-    /// 从文件加载分词器
+    /// 解码结果以 `prefix` 开头的全部标记ID，用于logit偏置
     #[cfg(feature = "python")]
-    #[pyo3(name = "load")]
-    pub fn py_load(&mut self, path: String) -> PyResult<()> {
-        self.load(&path)
-            .map_err(|e| crate::error::TokenizerError::ModelLoadError { message: e }.into())
+    #[pyo3(name = "ids_with_prefix")]
+    pub fn py_ids_with_prefix(&self, prefix: &str) -> Vec<u32> {
+        VocabBytes::ids_with_prefix(self, prefix)
     }
-}
 
-impl BBPETokenizer {
-    /// 获取合并等级映射
-    pub fn get_mergeable_ranks(&self) -> StdHashMap<(u32, u32), u32> {
-        self.merges.clone()
+    /// 忽略开头的空白后解码结果恰好为 `text` 的全部标记ID，用于禁用词列表
+    #[cfg(feature = "python")]
+    #[pyo3(name = "token_ids_covering")]
+    pub fn py_token_ids_covering(&self, text: &str) -> Vec<u32> {
+        VocabBytes::token_ids_covering(self, text)
     }
-}
 
-impl Tokenizer for BBPETokenizer {
-    type TokenId = u32;
+    /// 将单个标记渲染为适合日志和界面显示的字符串，控制字符转义、首尾空格显示为 ␣
+    #[cfg(feature = "python")]
+    #[pyo3(name = "render_token")]
+    pub fn py_render_token(&self, id: u32) -> String {
+        VocabBytes::render_token(self, &id)
+    }
 
-    fn encode(&self, text: &str) -> Result<Vec<Self::TokenId>, String> {
-        // 使用正则表达式分割文本
-        let parts = self.base.split_text(text)?;
+    /// 从词汇表中随机抽取 `n_tokens` 个标记并解码为文本，用于压测和往返测试
+    ///
+    /// `counts` 为 `[(ID, 次数)]` 时按次数加权抽取，否则等概率抽取；传入 `seed` 时结果可复现
+    #[cfg(feature = "python")]
+    #[pyo3(name = "sample_text", signature = (n_tokens, seed = None, counts = None))]
+    pub fn py_sample_text(
+        &self,
+        n_tokens: usize,
+        seed: Option<u64>,
+        counts: Option<Vec<(u32, u64)>>,
+    ) -> PyResult<String> {
+        let sampler = match counts {
+            Some(counts) => TextSampler::with_counts(self, &counts, seed),
+            None => TextSampler::new(self, seed),
+        };
+        sampler
+            .and_then(|mut sampler| sampler.sample_text(self, n_tokens))
+            .map_err(py_value_error)
+    }
 
-        let mut result = Vec::new();
+    /// 编码文本并返回每个标记的区间 `(start, end, id)`，用于可视化标记边界
+    ///
+    /// 区间默认为UTF-8字节偏移，`offset_unit="char"` 时为字符偏移
+    #[cfg(feature = "python")]
+    #[pyo3(name = "spans", signature = (text, offset_unit = "byte"))]
+    pub fn py_spans(&self, text: &str, offset_unit: &str) -> PyResult<Vec<(usize, usize, u32)>> {
+        crate::base::py_numpy::spans(self, text, offset_unit)
+    }
 
-        for part in parts {
-            if part.is_empty() {
-                continue;
-            }
+    /// 编码文本并返回 `ids`、`tokens`、`offsets` 组成的字典，用于把标注对齐回原文
+    ///
+    /// 区间默认为UTF-8字节偏移，`offset_unit="char"` 时为字符偏移
+    #[cfg(feature = "python")]
+    #[pyo3(name = "encode_with_offsets", signature = (text, offset_unit = "byte"))]
+    pub fn py_encode_with_offsets<'py>(
+        &self,
+        py: Python<'py>,
+        text: &str,
+        offset_unit: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_with_offsets(py, self, text, offset_unit)
+    }
 
-            // 将每个部分转换为字节ID
-            let mut ids: Vec<u32> = Vec::new();
-            for &byte in part.as_bytes() {
-                let byte_vec = vec![byte];
-                if let Some(&id) = self.vocab.get_by_value(&byte_vec) {
-                    ids.push(id);
-                } else {
-                    // 这种情况不应该发生，因为我们已经初始化了所有可能的字节
-                    return Err(format!("未找到字节 {} 对应的ID", byte));
-                }
-            }
+    /// 编码句子对为 `[CLS] A [SEP] B [SEP]`，返回 `input_ids`、`token_type_ids` 组成的字典
+    ///
+    /// 分类和分隔标记需要已注册为特殊标记，可用 `cls_token`、`sep_token` 改用其他名称
+    #[cfg(feature = "python")]
+    #[pyo3(name = "encode_pair", signature = (text_a, text_b, cls_token = "[CLS]", sep_token = "[SEP]"))]
+    pub fn py_encode_pair<'py>(
+        &self,
+        py: Python<'py>,
+        text_a: &str,
+        text_b: &str,
+        cls_token: &str,
+        sep_token: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_pair(py, self, text_a, text_b, cls_token, sep_token)
+    }
 
-            // 应用合并规则
-            self.apply_merges(&mut ids);
-            result.extend(ids);
-        }
-
-        // 如果没有匹配到任何内容，退回到简单分割
-        if result.is_empty() {
-            for word in text.split_whitespace() {
-                let mut ids: Vec<u32> = Vec::new();
-                for &byte in word.as_bytes() {
-                    let byte_vec = vec![byte];
-                    if let Some(&id) = self.vocab.get_by_value(&byte_vec) {
-                        ids.push(id);
-                    } else {
-                        // 这种情况不应该发生，因为我们已经初始化了所有可能的字节
-                        return Err(format!("未找到字节 {} 对应的ID", byte));
-                    }
-                }
+    /// 启用截断，`encode_plus`、`encode_batch_plus` 每行最多保留 `max_length` 个标记
+    ///
+    /// `strategy` 为 `"longest_first"`（默认）、`"only_first"` 或 `"only_second"`，只对句子对有效；
+    /// 指定 `stride` 时超出的部分切成相邻重叠 `stride` 个标记的溢出块，每块单独成行
+    #[cfg(feature = "python")]
+    #[pyo3(name = "enable_truncation", signature = (max_length, strategy = "longest_first", stride = None))]
+    pub fn py_enable_truncation(
+        &mut self,
+        max_length: usize,
+        strategy: &str,
+        stride: Option<usize>,
+    ) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        let config = crate::base::py_numpy::truncation_config(max_length, strategy, stride)?;
+        self.encode_options_mut().truncation = Some(config);
+        Ok(())
+    }
 
-                // 应用合并规则
-                self.apply_merges(&mut ids);
-                result.extend(ids);
-            }
-        }
+    /// 关闭截断
+    #[cfg(feature = "python")]
+    #[pyo3(name = "no_truncation")]
+    pub fn py_no_truncation(&mut self) {
+        self.async_snapshot.invalidate();
+        self.no_truncation();
+    }
 
-        Ok(result)
+    /// 启用补齐，`encode_plus`、`encode_batch_plus` 用 `pad_id` 把各行补齐到 `length`（默认为最长的行）
+    ///
+    /// `direction` 为 `"right"`（默认）或 `"left"`；指定 `pad_to_multiple_of` 时行宽向上取整到其倍数
+    #[cfg(feature = "python")]
+    #[pyo3(name = "enable_padding", signature = (pad_id = 0, direction = "right", length = None, pad_to_multiple_of = None))]
+    pub fn py_enable_padding(
+        &mut self,
+        pad_id: u32,
+        direction: &str,
+        length: Option<usize>,
+        pad_to_multiple_of: Option<usize>,
+    ) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        let config =
+            crate::base::py_numpy::padding_config(direction, pad_id, length, pad_to_multiple_of)?;
+        self.encode_options_mut().padding = Some(config);
+        Ok(())
     }
 
-    fn decode(&self, tokens: &[Self::TokenId]) -> Result<String, String> {
-        let mut bytes = Vec::new();
+    /// 关闭补齐
+    #[cfg(feature = "python")]
+    #[pyo3(name = "no_padding")]
+    pub fn py_no_padding(&mut self) {
+        self.async_snapshot.invalidate();
+        self.no_padding();
+    }
 
-        for &id in tokens {
-            if let Some(token_bytes) = self.vocab.get_by_id(&id) {
-                bytes.extend_from_slice(token_bytes);
-            } else {
-                return Err(format!("未找到ID {} 对应的词汇", id));
-            }
-        }
+    /// 设置编码输入的长度限制，参数都为 `None` 时不限制
+    ///
+    /// 输入超过 `max_input_bytes` 字节或结果超过 `max_tokens` 个标记时，`policy="error"`（默认）报错，
+    /// `"truncate"` 截断输入或结果；对 `encode` 及所有基于它的接口生效
+    #[cfg(feature = "python")]
+    #[pyo3(name = "set_input_limits", signature = (max_input_bytes = None, max_tokens = None, policy = "error"))]
+    pub fn py_set_input_limits(
+        &mut self,
+        max_input_bytes: Option<usize>,
+        max_tokens: Option<usize>,
+        policy: &str,
+    ) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.encode_options_mut().limits =
+            crate::base::py_numpy::input_limits(max_input_bytes, max_tokens, policy)?;
+        Ok(())
+    }
 
-        match String::from_utf8(bytes) {
-            Ok(s) => Ok(s),
-            Err(e) => Err(format!("UTF-8解码失败: {}", e)),
-        }
+    /// 按截断与补齐配置编码单条文本或句子对，返回 `input_ids`、`attention_mask`、`token_type_ids`
+    /// 和 `overflow_to_sample_mapping` 组成的字典，每项按行给出（溢出块各占一行）
+    ///
+    /// 句子对拼接为 `[CLS] A [SEP] B [SEP]`；`return_tensors="np"` 时返回NumPy数组
+    #[cfg(feature = "python")]
+    #[pyo3(name = "encode_plus", signature = (text, pair = None, return_tensors = None))]
+    pub fn py_encode_plus<'py>(
+        &self,
+        py: Python<'py>,
+        text: String,
+        pair: Option<String>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_plus(
+            py,
+            self,
+            vec![text],
+            pair.map(|pair| vec![pair]),
+            return_tensors,
+        )
     }
 
-    fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> Result<(), String> {
-        log::info!("开始BBPE训练，目标词汇表大小: {}", vocab_size);
+    /// 按截断与补齐配置并行编码一批文本（及对应的第二句 `pairs`），返回格式同 `encode_plus`
+    #[cfg(feature = "python")]
+    #[pyo3(name = "encode_batch_plus", signature = (texts, pairs = None, return_tensors = None))]
+    pub fn py_encode_batch_plus<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        pairs: Option<Vec<String>>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_plus(py, self, texts, pairs, return_tensors)
+    }
 
-        // 验证词汇表大小
-        if vocab_size < 256 {
-            return Err("词汇表大小必须至少为256".to_string());
+    /// 交叉检查词汇表、合并规则和特殊标记注册表，返回发现的问题列表，每项为包含 `kind`、`message` 的字典
+    #[cfg(feature = "python")]
+    #[pyo3(name = "check_integrity")]
+    pub fn py_check_integrity<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Vec<Bound<'py, pyo3::types::PyDict>>> {
+        self.check_integrity().to_py_list(py)
+    }
+
+    /// 批量编码并返回连续存储的 `input_ids` 和每行的 `lengths`、`offsets`，可直接复制到张量
+    ///
+    /// 每行最多保留 `max_len` 个标记；指定 `pad_id` 时补齐为二维并返回 `attention_mask`，
+    /// `return_tensors="np"` 时返回NumPy数组
+    #[cfg(feature = "python")]
+    #[pyo3(name = "encode_batch_packed", signature = (texts, max_len = None, pad_id = None, return_tensors = None))]
+    pub fn py_encode_batch_packed<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        max_len: Option<usize>,
+        pad_id: Option<u32>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_packed(py, self, texts, max_len, pad_id, return_tensors)
+    }
+
+    /// 批量编码并返回补齐后的 `input_ids`、`offsets`、`attention_mask`
+    ///
+    /// `return_tensors="np"` 时返回NumPy数组，避免为大批量创建大量小元组；
+    /// `offset_unit="char"` 时偏移为字符区间
+    #[cfg(feature = "python")]
+    #[pyo3(name = "encode_batch_with_offsets", signature = (texts, return_tensors = None, offset_unit = "byte"))]
+    pub fn py_encode_batch_with_offsets<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        return_tensors: Option<&str>,
+        offset_unit: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_with_offsets(
+            py,
+            self,
+            texts,
+            return_tensors,
+            offset_unit,
+        )
+    }
+
+    /// 编码文本并返回标记ID序列与平台无关的64位哈希，用于判断缓存的编码结果是否过期
+    #[cfg(feature = "python")]
+    #[pyo3(name = "content_hash")]
+    pub fn py_content_hash(&self, text: &str) -> PyResult<u64> {
+        Tokenizer::content_hash(self, text).map_err(PyErr::from)
+    }
+
+    /// 编码文本并标出每个标记是否开始一个新词，用于整词掩码
+    #[cfg(feature = "python")]
+    #[pyo3(name = "word_starts")]
+    pub fn py_word_starts(&self, text: &str) -> PyResult<Vec<bool>> {
+        VocabBytes::word_starts(self, text).map_err(PyErr::from)
+    }
+
+    /// 在字节标记之后预留 `count` 个特殊标记ID，返回 `(start, end)` 区间
+    #[cfg(feature = "python")]
+    #[pyo3(name = "reserve_special_tokens")]
+    pub fn py_reserve_special_tokens(&mut self, count: u32) -> PyResult<(u32, u32)> {
+        self.async_snapshot.invalidate();
+        self.reserve_special_tokens(count)
+            .map(|range| (range.start, range.end))
+            .map_err(py_value_error)
+    }
+
+    /// 注册特殊标记，优先放入空闲的预留位置，返回每个标记的ID
+    #[cfg(feature = "python")]
+    #[pyo3(name = "add_special_tokens")]
+    pub fn py_add_special_tokens(&mut self, tokens: Vec<String>) -> PyResult<Vec<u32>> {
+        self.async_snapshot.invalidate();
+        let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+        SpecialTokenizer::add_special_tokens(self, &tokens).map_err(PyErr::from)
+    }
+
+    /// 预留 `count` 个特殊标记ID，返回 `(start, end)` 区间
+    #[cfg(feature = "python")]
+    #[pyo3(name = "reserve_special_ids")]
+    pub fn py_reserve_special_ids(&mut self, count: u32) -> PyResult<(u32, u32)> {
+        self.async_snapshot.invalidate();
+        self.reserve_special_ids(count)
+            .map(|range| (range.start, range.end))
+            .map_err(PyErr::from)
+    }
+
+    /// 已注册的特殊标记，`{标记: ID}`
+    #[cfg(feature = "python")]
+    #[getter(special_tokens)]
+    pub fn py_special_tokens(&self) -> StdHashMap<String, u32> {
+        self.base
+            .special_tokens
+            .iter()
+            .map(|(id, token)| (token.to_string(), id))
+            .collect()
+    }
+
+    /// 尚未分配的预留特殊标记ID
+    #[cfg(feature = "python")]
+    #[pyo3(name = "reserved_special_token_ids")]
+    pub fn py_reserved_special_token_ids(&self) -> Vec<u32> {
+        self.reserved_special_token_ids()
+    }
+
+    /// 把特殊标记放入空闲的预留位置，返回其ID
+    #[cfg(feature = "python")]
+    #[pyo3(name = "assign_special_token")]
+    pub fn py_assign_special_token(&mut self, token: &str) -> PyResult<u32> {
+        self.async_snapshot.invalidate();
+        self.assign_special_token(token).map_err(py_value_error)
+    }
+
+    /// 检查语料中的字符能否由基础词汇表表示，返回覆盖统计以及只能拆成字节或编码为未知标记的字符
+    #[cfg(feature = "python")]
+    #[pyo3(name = "coverage")]
+    pub fn py_coverage<'py>(
+        &self,
+        py: Python<'py>,
+        corpus: Vec<String>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        py.allow_threads(|| crate::analysis::coverage(self, &corpus))
+            .map_err(py_value_error)?
+            .to_py_dict(py)
+    }
+
+    /// 无法单独解码为UTF-8的标记ID，按ID升序排列
+    #[cfg(feature = "python")]
+    #[pyo3(name = "audit_vocab")]
+    pub fn py_audit_vocab(&self) -> Vec<u32> {
+        self.audit_vocab()
+            .unsafe_tokens
+            .iter()
+            .map(|token| token.id)
+            .collect()
+    }
+
+    /// 批量解码token IDs为文本（并行处理）
+    ///
+    /// `token_lists` 可以是嵌套列表，也可以是二维整数NumPy数组或PyTorch CPU张量，数组直接从缓冲区读取。
+    /// 给出 `attention_mask` 时只解码掩码非0位置的标记，用于去掉填充
+    #[cfg(feature = "python")]
+    #[pyo3(name = "decode_batch", signature = (token_lists, attention_mask = None))]
+    pub fn py_decode_batch(
+        &self,
+        py: Python<'_>,
+        token_lists: &Bound<'_, PyAny>,
+        attention_mask: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Vec<String>> {
+        let token_lists = crate::base::py_numpy::extract_id_batch(token_lists, attention_mask)?;
+        // 使用rayon并行处理所有token列表，解码期间释放GIL
+        py.allow_threads(|| self.decode_token_lists(&token_lists))
+            .map_err(PyErr::from)
+    }
+
+    /// 获取词汇表大小
+    #[cfg(feature = "python")]
+    #[pyo3(name = "vocab_size")]
+    pub fn py_vocab_size(&self) -> usize {
+        self.vocab_size()
+    }
+
+    /// 获取词汇表
+    #[cfg(feature = "python")]
+    #[pyo3(name = "get_vocab")]
+    pub fn py_get_vocab(&self) -> StdHashMap<u32, Vec<u8>> {
+        self.vocab.id_map().clone()
+    }
+
+    /// 获取可读的词汇表，标记ID到字符串
+    ///
+    /// 默认使用GPT-2字节↔Unicode映射（如空格显示为 `Ġ`），`escape=True` 时改为 `\xNN` 转义
+    #[cfg(feature = "python")]
+    #[pyo3(name = "get_vocab_strings", signature = (escape=false))]
+    pub fn py_get_vocab_strings(&self, escape: bool) -> StdHashMap<u32, String> {
+        let style = if escape {
+            VocabStringStyle::Escaped
+        } else {
+            VocabStringStyle::ByteLevel
+        };
+        self.vocab_strings(style).into_iter().collect()
+    }
+
+    /// 获取反向词汇表
+    #[cfg(feature = "python")]
+    #[pyo3(name = "get_vocab_rev")]
+    pub fn py_get_vocab_rev(&self) -> StdHashMap<Vec<u8>, u32> {
+        self.vocab.value_map().clone()
+    }
+
+    /// 在新领域语料上追加学习合并规则，已有标记的ID保持不变
+    ///
+    /// 返回重映射清单字典，`added` 为新增的标记ID
+    #[cfg(feature = "python")]
+    #[pyo3(name = "adapt")]
+    pub fn py_adapt<'py>(
+        &mut self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        extra_tokens: u32,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        self.async_snapshot.invalidate();
+        self.adapt(&texts, extra_tokens)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?
+            .to_py_dict(py)
+    }
+
+    /// 根据语料上的使用频次蒸馏出词汇表更小的分词器
+    ///
+    /// 返回 (新分词器, 重映射清单字典)
+    #[cfg(feature = "python")]
+    #[pyo3(name = "distill")]
+    pub fn py_distill<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        vocab_size: usize,
+    ) -> PyResult<(Self, Bound<'py, pyo3::types::PyDict>)> {
+        let (distilled, manifest) = self
+            .distill(&texts, vocab_size)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok((distilled, manifest.to_py_dict(py)?))
+    }
+
+    /// 获取合并规则
+    #[cfg(feature = "python")]
+    #[pyo3(name = "get_merges")]
+    pub fn py_get_merges(&self) -> MergeMap {
+        self.merges.clone()
+    }
+
+    /// 保存分词器到文件
+    #[cfg(feature = "python")]
+    #[pyo3(name = "save")]
+    pub fn py_save(&self, path: String) -> PyResult<()> {
+        self.save(&path).map_err(PyErr::from)
+    }
+
+    /// 以带版本头和校验和的二进制格式保存
+    #[cfg(feature = "python")]
+    #[pyo3(name = "save_binary")]
+    pub fn py_save_binary(&self, path: &str) -> PyResult<()> {
+        self.save_binary(path).map_err(Into::into)
+    }
+
+    /// 加载二进制模型，文件不是二进制格式时按文本格式加载
+    #[cfg(feature = "python")]
+    #[pyo3(name = "load_binary")]
+    pub fn py_load_binary(&mut self, path: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.load_binary(path).map_err(Into::into)
+    }
+
+    /// 保存为HuggingFace `tokenizers` 的 tokenizer.json
+    #[cfg(feature = "python")]
+    #[pyo3(name = "save_tokenizer_json")]
+    pub fn py_save_tokenizer_json(&self, path: &str) -> PyResult<()> {
+        self.save_tokenizer_json(path).map_err(Into::into)
+    }
+
+    /// 从HuggingFace `tokenizers` 的 tokenizer.json 加载
+    #[cfg(feature = "python")]
+    #[pyo3(name = "load_tokenizer_json")]
+    pub fn py_load_tokenizer_json(&mut self, path: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.load_tokenizer_json(path).map_err(Into::into)
+    }
+
+    /// 保存为tiktoken的 `.tiktoken` 等级表文件
+    #[cfg(feature = "python")]
+    #[pyo3(name = "save_tiktoken")]
+    pub fn py_save_tiktoken(&self, path: &str) -> PyResult<()> {
+        self.save_tiktoken(path).map_err(Into::into)
+    }
+
+    /// 导出tiktoken `Encoding` 构造函数的 `mergeable_ranks` 参数，`{标记字节: 等级}`
+    #[cfg(feature = "python")]
+    #[pyo3(name = "mergeable_ranks_bytes")]
+    pub fn py_mergeable_ranks_bytes<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        let ranks = pyo3::types::PyDict::new(py);
+        for (token, rank) in self.to_tiktoken_ranks() {
+            ranks.set_item(pyo3::types::PyBytes::new(py, &token), rank)?;
         }
+        Ok(ranks)
+    }
 
-        // 只有在词汇表为空时才初始化
-        if self.vocab.is_empty() {
-            self.init_vocab();
+    /// 读取tiktoken的 `.tiktoken` 等级表文件（如 `cl100k_base.tiktoken`）创建分词器，
+    /// `pattern` 为预设名称或正则表达式，默认为 `"cl100k"`
+    #[cfg(feature = "python")]
+    #[staticmethod]
+    #[pyo3(name = "from_tiktoken_file", signature = (path, pattern = None))]
+    pub fn py_from_tiktoken_file(path: &str, pattern: Option<&str>) -> PyResult<Self> {
+        let mut tokenizer = Self::from_tiktoken_file(path)?;
+        if let Some(pattern) = pattern {
+            tokenizer.base.set_pattern(pattern)?;
         }
+        Ok(tokenizer)
+    }
 
-        // 将文本转换为词序列
-        log::info!("处理 {} 个文本样本", texts.len());
-        let (words, counts) = {
-            let mut words = Vec::new();
-            let mut counts = Vec::new();
+    /// 把特殊标记放到指定的ID，用于还原外部词汇表中特殊标记的固定ID
+    #[cfg(feature = "python")]
+    #[pyo3(name = "insert_special_token")]
+    pub fn py_insert_special_token(&mut self, token: &str, id: u32) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.insert_special_token(token, id).map_err(py_value_error)
+    }
 
-            for text in &texts {
-                // 使用正则表达式分割文本
-                let parts = self.base.split_text(text)?;
+    /// 把加载模型之后新增的标记和合并规则追加到日志文件，返回写入的条数
+    #[cfg(feature = "python")]
+    #[pyo3(name = "append_journal")]
+    pub fn py_append_journal(&mut self, model_path: &str) -> PyResult<usize> {
+        self.async_snapshot.invalidate();
+        self.append_journal(model_path).map_err(py_value_error)
+    }
 
-                for part in parts {
-                    if part.is_empty() {
-                        continue;
-                    }
+    /// 从文件加载分词器
+    ///
+    /// `parts` 为 `"decode_only"` 时只加载词汇表，为 `"encode_only"` 时只加载合并规则和单字节标记
+    #[cfg(feature = "python")]
+    #[pyo3(name = "load", signature = (path, parts = "full"))]
+    pub fn py_load(&mut self, path: String, parts: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        let parts = ModelParts::parse(parts).map_err(py_value_error)?;
+        self.load_parts(&path, parts).map_err(Into::into)
+    }
 
-                    // 将词转换为字节ID - 通过vocab查找每个字节对应的ID
-                    let ids: Vec<u32> = part
-                        .bytes()
-                        .map(|b| {
-                            let byte_vec = vec![b];
-                            *self
-                                .vocab
-                                .get_by_value(&byte_vec)
-                                .unwrap_or_else(|| panic!("字节 {} 在vocab中不存在", b))
-                        })
-                        .collect();
-                    words.push(Word::new(ids));
-                    counts.push(1);
-                }
+    /// 保存到本地模型注册表，返回模型文件路径；同名模型会被覆盖
+    #[cfg(feature = "python")]
+    #[pyo3(name = "save_as")]
+    pub fn py_save_as(&self, name: &str) -> PyResult<String> {
+        let path = NamedModel::save_as(self, name)?;
+        Ok(path.to_string_lossy().into_owned())
+    }
 
-                // 如果没有匹配到任何内容，退回到简单分割
-                if words.is_empty() {
-                    log::warn!("正则表达式未匹配，使用简单分割");
-                    for word in text.split_whitespace() {
-                        let ids: Vec<u32> = word
-                            .bytes()
-                            .map(|b| {
-                                let byte_vec = vec![b];
-                                *self
-                                    .vocab
-                                    .get_by_value(&byte_vec)
-                                    .unwrap_or_else(|| panic!("字节 {} 在vocab中不存在", b))
-                            })
-                            .collect();
-                        words.push(Word::new(ids));
-                        counts.push(1);
-                    }
-                }
-            }
-            (words, counts)
+    /// 按名称从本地模型注册表加载
+    #[cfg(feature = "python")]
+    #[pyo3(name = "load_named")]
+    pub fn py_load_named(&mut self, name: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        NamedModel::load_named(self, name).map_err(Into::into)
+    }
+}
+
+impl BBPETokenizer {
+    /// 获取合并等级映射
+    pub fn get_mergeable_ranks(&self) -> MergeMap {
+        self.merges.clone()
+    }
+
+    /// 把每个标记渲染为可读字符串，按ID升序排列
+    ///
+    /// 字节级标记可能是不完整的UTF-8，无法直接显示或写入JSON，
+    /// [`VocabStringStyle::ByteLevel`] 的结果可以用 [`byte_level_to_bytes`](crate::bbpe::byte_level::byte_level_to_bytes) 还原
+    #[must_use]
+    pub fn vocab_strings(&self, style: VocabStringStyle) -> Vec<(u32, String)> {
+        let mut strings: Vec<(u32, String)> = self
+            .vocab
+            .iter()
+            .map(|(&id, bytes)| (id, style.render(bytes)))
+            .collect();
+        strings.sort_unstable_by_key(|&(id, _)| id);
+        strings
+    }
+
+    /// 转换为HuggingFace `tokenizers` 的BPE模型，标记使用GPT-2字节级表示
+    ///
+    /// # Errors
+    ///
+    /// 当合并规则引用的标记不在词汇表中时返回错误
+    pub fn to_hf_json(&self) -> Result<HfTokenizerJson, TokenizerError> {
+        let render = |id: u32| {
+            self.vocab
+                .get_by_id(&id)
+                .map(|bytes| VocabStringStyle::ByteLevel.render(bytes))
+                .ok_or_else(|| {
+                    model_save_error(format!("合并规则引用的标记ID {} 不在词汇表中", id))
+                })
         };
+        let merges = ranked_merges(&self.merges)
+            .into_iter()
+            .map(|((a, b), _)| Ok((render(a)?, render(b)?)))
+            .collect::<Result<Vec<_>, TokenizerError>>()?;
+        let vocab = self
+            .vocab_strings(VocabStringStyle::ByteLevel)
+            .into_iter()
+            .map(|(id, token)| (token, id))
+            .collect();
 
-        // 使用增量训练核心
-        self.train_core_incremental(words, counts, vocab_size)?;
-        log::info!("BBPE训练完成，最终词汇表大小: {}", self.vocab.len());
+        Ok(HfTokenizerJson {
+            model: HfModel::Bpe {
+                vocab,
+                merges,
+                unk_token: None,
+                byte_fallback: false,
+            },
+            added_tokens: HfTokenizerJson::added_tokens_from(&self.base.special_tokens),
+            pattern: self.base.pattern.clone(),
+            pre_tokenizer: self.base.pre_tokenizer.clone(),
+            decoder: self.base.decoder,
+            byte_level: true,
+            normalizer: self.base.normalizer.clone(),
+        })
+    }
 
+    /// 加载HuggingFace `tokenizers` 的字节级BPE模型，替换当前的词汇表、合并规则、预分词模式和特殊标记
+    ///
+    /// # Errors
+    ///
+    /// 当模型不是字节级BPE或缺少单字节标记时返回错误
+    pub fn load_hf_json(&mut self, json: HfTokenizerJson) -> Result<(), TokenizerError> {
+        let HfModel::Bpe { vocab, merges, .. } = &json.model else {
+            return Err(model_load_error("BBPE分词器只能加载BPE模型"));
+        };
+        if !json.byte_level {
+            return Err(model_load_error(
+                "BBPE分词器只能加载使用ByteLevel预分词器的模型",
+            ));
+        }
+        let special_tokens = json.special_tokens()?;
+
+        let mut id_map = StdHashMap::with_capacity(vocab.len() + json.added_tokens.len());
+        for (token, id) in vocab {
+            // 附加标记在词汇表中保留原文，不经过字节级映射
+            let bytes = match special_tokens.id(token) {
+                Some(_) => token.as_bytes().to_vec(),
+                None => byte_level_to_bytes(token).ok_or_else(|| {
+                    model_load_error(format!("标记 {:?} 不是有效的字节级表示", token))
+                })?,
+            };
+            id_map.insert(*id, bytes);
+        }
+        for token in &json.added_tokens {
+            id_map
+                .entry(token.id)
+                .or_insert_with(|| token.content.as_bytes().to_vec());
+        }
+        let new_vocab = VocabManager::from_id_map(id_map);
+        if let Some(byte) = (0..=255u8).find(|&b| !new_vocab.contains_value(&vec![b])) {
+            return Err(model_load_error(format!(
+                "词汇表缺少单字节标记 0x{:02X}",
+                byte
+            )));
+        }
+        let new_merges = merge_ids(vocab, merges)?;
+
+        self.base.set_pattern(&json.pattern)?;
+        self.vocab = new_vocab;
+        self.merges = new_merges;
+        self.base.special_tokens = special_tokens;
+        self.base.normalizer = json.normalizer.clone();
+        self.base.decoder = json.decoder;
+        self.base.pre_tokenizer = json.pre_tokenizer.clone();
+        self.base_chars.clear();
+        self.journal = None;
+        self.parts = ModelParts::Full;
+        self.next_token_id = self
+            .vocab
+            .ids()
+            .max()
+            .map_or(0, |&id| id + 1)
+            .max(self.base.special_tokens.end_id());
         Ok(())
     }
 
-    fn vocab_size(&self) -> usize {
-        self.vocab.len()
+    /// 保存为HuggingFace `tokenizers` 的 tokenizer.json，见 [`BBPETokenizer::to_hf_json`]
+    ///
+    /// # Errors
+    ///
+    /// 当转换或写入失败时返回错误
+    pub fn save_tokenizer_json(&self, path: &str) -> Result<(), TokenizerError> {
+        self.to_hf_json()?.save(path)
     }
 
-    fn save(&self, path: &str) -> Result<(), String> {
-        // 使用基础分词器的保存方法
-        self.base.save(path)?;
+    /// 从HuggingFace `tokenizers` 的 tokenizer.json 加载，见 [`BBPETokenizer::load_hf_json`]
+    ///
+    /// # Errors
+    ///
+    /// 当读取、解析或转换失败时返回错误
+    pub fn load_tokenizer_json(&mut self, path: &str) -> Result<(), TokenizerError> {
+        self.load_hf_json(HfTokenizerJson::load(path)?)
+    }
 
-        // 保存BBPE特定的数据
-        use std::fs::OpenOptions;
-        use std::io::Write;
+    /// 以带版本头和校验和的二进制格式保存，见 [`crate::base::binary`]；与 [`Tokenizer::save`]
+    /// 相同，完整的模型已包含日志中的变更，旧日志会被删除
+    ///
+    /// # Errors
+    ///
+    /// 当模型只加载了一部分或写入失败时返回错误
+    pub fn save_binary(&self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("bbpe.save_binary", path);
+        if self.parts != ModelParts::Full {
+            return Err(model_save_error("部分加载的模型不能保存"));
+        }
+        let mut model = BinaryModel::new(ModelKind::Bbpe);
+        self.base.write_binary(&mut model);
+        let mut base_chars: Vec<&Vec<u8>> = self.base_chars.iter().collect();
+        base_chars.sort_unstable();
+        let mut writer = SectionWriter::new();
+        writer.u32(base_chars.len() as u32);
+        for bytes in base_chars {
+            writer.bytes(bytes);
+        }
+        model.push(binary::tag::BASE_CHARS, writer);
+        model.push(
+            binary::tag::VOCAB,
+            binary::write_vocab(self.vocab.iter().map(|(&id, bytes)| (id, bytes.as_slice()))),
+        );
+        model.push(binary::tag::MERGES, binary::write_merges(&self.merges));
+        std::fs::write(path, model.to_bytes())
+            .map_err(|e| model_save_error(format!("写入模型文件失败: {}", e)))?;
+
+        let journal = journal_path(path);
+        if std::path::Path::new(&journal).exists() {
+            std::fs::remove_file(&journal)
+                .map_err(|e| model_save_error(format!("删除旧日志失败: {}", e)))?;
+        }
+        Ok(())
+    }
 
-        let mut file = OpenOptions::new()
-            .append(true)
-            .open(path)
-            .map_err(|e| format!("打开文件失败: {}", e))?;
+    /// 加载 [`BBPETokenizer::save_binary`] 写出的二进制模型并重放日志，
+    /// 文件不是二进制格式时按文本格式加载
+    ///
+    /// # Errors
+    ///
+    /// 当读取失败、校验和不符、格式版本过高、模型类型不匹配或完整性检查发现问题时返回错误
+    pub fn load_binary(&mut self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("bbpe.load_binary", path);
+        let data =
+            std::fs::read(path).map_err(|e| model_load_error(format!("打开文件失败: {}", e)))?;
+        if !binary::is_binary(&data) {
+            return Tokenizer::load(self, path);
+        }
+        let model = BinaryModel::from_bytes(&data, ModelKind::Bbpe)?;
 
-        // 保存基础字符
-        writeln!(file, "base_chars: {}", self.base_chars.len())
-            .map_err(|e| format!("写入基础字符数量失败: {}", e))?;
+        self.base.read_binary(&model)?;
+        let mut base_chars = AHashSet::new();
+        if let Some(mut reader) = model.section(binary::tag::BASE_CHARS) {
+            for _ in 0..reader.u32()? {
+                base_chars.insert(reader.bytes()?.to_vec());
+            }
+            reader.finish()?;
+        }
+        let entries = binary::read_vocab(model.required(binary::tag::VOCAB)?)?;
+        let merges = binary::read_merges(model.required(binary::tag::MERGES)?)?;
 
-        for char_bytes in &self.base_chars {
-            let char_str = String::from_utf8_lossy(char_bytes);
-            writeln!(file, "base_char: {}", char_str)
-                .map_err(|e| format!("写入基础字符失败: {}", e))?;
+        let mut report = IntegrityReport::default();
+        report.check_entries(&entries);
+        self.base_chars = base_chars;
+        self.vocab.clear();
+        for (id, bytes) in entries {
+            self.vocab.insert(id, bytes);
         }
+        self.merges = merges;
+        self.journal = Some(Vec::new());
+        self.parts = ModelParts::Full;
+        self.next_token_id = self
+            .vocab
+            .ids()
+            .max()
+            .map_or(0, |&id| id + 1)
+            .max(self.base.special_tokens.end_id());
+
+        report.extend(self.check_integrity());
+        report.into_result()?;
+        self.replay_journal_file(path)
+    }
 
-        // 保存词汇表
-        writeln!(file, "vocab: {}", self.vocab.len())
-            .map_err(|e| format!("写入词汇表数量失败: {}", e))?;
+    /// 在字节标记之后预留 `count` 个特殊标记ID，返回预留的ID区间
+    ///
+    /// 每个ID先用 `<|reserved_special_token_N|>` 占位，之后通过
+    /// [`BBPETokenizer::assign_special_token`] 换成实际的特殊标记（如对话控制标记），
+    /// 不会与合并规则产生的ID冲突。预留的ID计入训练的目标词汇表大小，占位符随模型一起保存。
+    ///
+    /// # Errors
+    ///
+    /// 已经训练出合并规则时返回错误，预留必须在训练之前进行
+    pub fn reserve_special_tokens(
+        &mut self,
+        count: u32,
+    ) -> Result<std::ops::Range<u32>, TokenizerError> {
+        if !self.merges.is_empty() {
+            return Err(vocab_error("已经存在合并规则，特殊标记必须在训练之前预留"));
+        }
 
-        for (id, bytes) in self.vocab.iter() {
-            let byte_str = bytes
-                .iter()
-                .map(|b| b.to_string())
-                .collect::<Vec<_>>()
-                .join(" ");
-            writeln!(file, "vocab_entry: {} {}", id, byte_str)
-                .map_err(|e| format!("写入词汇表条目失败: {}", e))?;
+        let first_index: usize = self
+            .base
+            .special_tokens
+            .reserved()
+            .iter()
+            .map(|range| range.len())
+            .sum();
+        let start = self.next_token_id;
+        for index in 0..count as usize {
+            let placeholder = format!("{}{}|>", RESERVED_SPECIAL_TOKEN_PREFIX, first_index + index);
+            self.vocab
+                .insert(self.next_token_id, placeholder.clone().into_bytes());
+            self.record_journal(JournalEntry::Token {
+                id: self.next_token_id,
+                bytes: placeholder.into_bytes(),
+            });
+            self.next_token_id += 1;
+        }
+        if count > 0 {
+            self.base
+                .special_tokens
+                .reserve(start..self.next_token_id)?;
+            self.record_journal(JournalEntry::Reserved {
+                ids: start..self.next_token_id,
+            });
         }
+        Ok(start..self.next_token_id)
+    }
 
-        // 保存合并规则
-        writeln!(file, "merges: {}", self.merges.len())
-            .map_err(|e| format!("写入合并规则数量失败: {}", e))?;
+    /// 尚未分配的预留特殊标记ID，按ID升序排列
+    ///
+    /// 只包含 [`BBPETokenizer::reserve_special_tokens`] 预留、仍由占位符占据的ID，
+    /// 普通标记即使形如占位符也不算在内
+    #[must_use]
+    pub fn reserved_special_token_ids(&self) -> Vec<u32> {
+        let special = &self.base.special_tokens;
+        special
+            .reserved()
+            .iter()
+            .flat_map(|range| range.clone())
+            .filter(|&id| {
+                !special.contains_id(id)
+                    && self
+                        .vocab
+                        .get_by_id(&id)
+                        .is_some_and(|bytes| is_reserved_placeholder(bytes))
+            })
+            .collect()
+    }
 
-        for ((a, b), &rank) in &self.merges {
-            writeln!(file, "merge: {} {} {}", a, b, rank)
-                .map_err(|e| format!("写入合并规则失败: {}", e))?;
+    /// 把 `token` 放入ID最小的空闲预留位置并注册为特殊标记，返回其ID；
+    /// `token` 已注册为特殊标记时直接返回其ID
+    ///
+    /// # Errors
+    ///
+    /// 当 `token` 已是词汇表中的普通标记、没有空闲的预留位置，或标记无效、与已注册的特殊标记冲突时返回错误
+    pub fn assign_special_token(&mut self, token: &str) -> Result<u32, TokenizerError> {
+        if let Some(id) = self.base.special_tokens.id(token) {
+            return Ok(id);
+        }
+        let bytes = token.as_bytes().to_vec();
+        if let Some(&id) = self.vocab.get_by_value(&bytes) {
+            return Err(vocab_error(format!(
+                "{} 已是词汇表中的普通标记，ID为 {}，只能分配到预留位置",
+                token, id
+            )));
         }
+        let id = *self.reserved_special_token_ids().first().ok_or_else(|| {
+            vocab_error(format!("没有空闲的预留特殊标记位置，无法添加 {}", token))
+        })?;
+        self.register_special_token(token, id)?;
+        self.vocab.insert(id, bytes.clone());
+        self.record_journal(JournalEntry::Token { id, bytes });
+        Ok(id)
+    }
 
+    /// 把 `token` 放到指定的 `id` 并注册为特殊标记，用于还原外部词汇表中特殊标记的固定ID，
+    /// 如cl100k_base的 `<|endoftext|>` 为100257
+    ///
+    /// # Errors
+    ///
+    /// 当 `id` 已被其他普通标记占用、`token` 已在其他ID上，或标记无效、与已注册的特殊标记冲突时返回错误
+    pub fn insert_special_token(&mut self, token: &str, id: u32) -> Result<(), TokenizerError> {
+        let bytes = token.as_bytes().to_vec();
+        if let Some(existing) = self.vocab.get_by_id(&id) {
+            if *existing != bytes && !is_reserved_placeholder(existing) {
+                return Err(vocab_error(format!(
+                    "ID {} 已被标记 {:?} 占用",
+                    id,
+                    String::from_utf8_lossy(existing)
+                )));
+            }
+        }
+        if let Some(&other) = self.vocab.get_by_value(&bytes) {
+            if other != id {
+                return Err(vocab_error(format!(
+                    "{} 已在词汇表中，ID为 {}",
+                    token, other
+                )));
+            }
+        }
+        self.register_special_token(token, id)?;
+        if self.vocab.get_by_id(&id) != Some(&bytes) {
+            self.vocab.insert(id, bytes.clone());
+            self.record_journal(JournalEntry::Token { id, bytes });
+        }
+        self.next_token_id = self.next_token_id.max(id + 1);
+        Ok(())
+    }
+
+    /// 在注册表中登记特殊标记并记入日志
+    fn register_special_token(&mut self, token: &str, id: u32) -> Result<(), TokenizerError> {
+        if self.base.special_tokens.id(token) == Some(id) {
+            return Ok(());
+        }
+        self.base.special_tokens.insert(token, id)?;
+        self.record_journal(JournalEntry::Special {
+            id,
+            token: token.to_string(),
+        });
         Ok(())
     }
 
-    fn load(&mut self, path: &str) -> Result<(), String> {
+    /// 审计词汇表，找出无法单独解码为UTF-8的标记（被拆开的多字节字符片段）
+    ///
+    /// 流式解码时可以用 [`VocabAudit::is_utf8_safe`] 判断是否需要先缓存字节
+    #[must_use]
+    pub fn audit_vocab(&self) -> VocabAudit {
+        audit_vocab(self)
+    }
+
+    /// 在新领域语料上追加学习合并规则，已有标记的ID保持不变
+    ///
+    /// 新语料先用现有合并规则切分，只在此基础上学习新的合并，新标记排在现有ID之后。
+    /// 已训练模型的嵌入仍然有效，只需为新增的行初始化。
+    ///
+    /// 返回的重映射清单中 `added` 为新增的标记，语料中可合并的配对不足时可能少于 `extra_tokens`
+    ///
+    /// # Errors
+    ///
+    /// 当语料预分词失败时返回错误
+    pub fn adapt<I, S>(
+        &mut self,
+        corpus: I,
+        extra_tokens: u32,
+    ) -> Result<RemapManifest, TokenizerError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        trace_span!("bbpe.adapt", extra_tokens);
+        let before = self.vocab.len();
+        let old_ids: Vec<u32> = self.vocab.ids().copied().collect();
+
+        let mut words = Vec::new();
+        for text in corpus {
+            for part in self.base.split_text(text.as_ref())? {
+                if part.is_empty() {
+                    continue;
+                }
+                let mut ids = Vec::with_capacity(part.len());
+                self.push_byte_ids(part.as_bytes(), &mut ids)?;
+                // 显式调用固有方法，避免解析到 MergeBasedTokenizer::apply_merges
+                Self::apply_merges(self, &mut ids);
+                if ids.len() >= 2 {
+                    words.push(Word::new(ids));
+                }
+            }
+        }
+        let counts = vec![1; words.len()];
+
+        let target = u32::try_from(before)
+            .unwrap_or(u32::MAX)
+            .saturating_add(extra_tokens);
+        self.train_core_incremental(words, counts, target)?;
+
+        log::info!("领域适配完成，新增 {} 个标记", self.vocab.len() - before);
+        let mapping = old_ids.iter().map(|&id| (id, id)).collect();
+        let manifest = RemapManifest::new(old_ids, self.vocab.ids().copied(), mapping);
+
+        // 新增的标记和产生它们的合并规则按ID顺序记入日志
+        let mut added_merges: Vec<((u32, u32), u32)> = self
+            .merges
+            .iter()
+            .filter(|(_, id)| manifest.added.binary_search(id).is_ok())
+            .map(|(&pair, &id)| (pair, id))
+            .collect();
+        added_merges.sort_unstable_by_key(|&(pair, id)| (id, pair));
+        for &id in &manifest.added {
+            if let Some(bytes) = self.vocab.get_by_id(&id) {
+                let bytes = bytes.clone();
+                self.record_journal(JournalEntry::Token { id, bytes });
+            }
+        }
+        for (pair, new_id) in added_merges {
+            self.record_journal(JournalEntry::Merge { pair, new_id });
+        }
+        Ok(manifest)
+    }
+
+    /// 根据语料上的使用频次，从当前分词器蒸馏出词汇表更小的分词器
+    ///
+    /// 保留全部字节标记，再按使用次数从高到低保留合并生成的标记；保留某个标记时，
+    /// 生成它所需的中间标记也一并保留，因此新的合并表仍然完整。保留的标记按原ID顺序
+    /// 重新编号。使用次数按当前分词器的切分统计，蒸馏后的切分可能与之不同。
+    ///
+    /// 返回新的分词器和重映射清单
+    ///
+    /// # Errors
+    ///
+    /// 当目标大小小于字节标记数，或语料编码失败时返回错误
+    pub fn distill<I, S>(
+        &self,
+        corpus: I,
+        target_vocab_size: usize,
+    ) -> Result<(Self, RemapManifest), TokenizerError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut keep: AHashSet<u32> = self
+            .vocab
+            .iter()
+            .filter(|(_, bytes)| bytes.len() == 1)
+            .map(|(&id, _)| id)
+            .chain(self.base.special_tokens.iter().map(|(id, _)| id))
+            .collect();
+        if target_vocab_size < keep.len() {
+            return Err(input_validation_error(format!(
+                "目标词汇表大小必须至少为 {}",
+                keep.len()
+            )));
+        }
+
+        let usage: AHashMap<u32, u64> = crate::analysis::token_usage(self, corpus)?
+            .into_iter()
+            .collect();
+        let components: AHashMap<u32, (u32, u32)> =
+            self.merges.iter().map(|(&pair, &id)| (id, pair)).collect();
+
+        // 次数相同时按ID排序，保证结果稳定
+        let mut candidates: Vec<(u32, u64)> = self
+            .vocab
+            .ids()
+            .filter(|id| !keep.contains(id))
+            .map(|&id| (id, usage.get(&id).copied().unwrap_or(0)))
+            .collect();
+        candidates.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        for (id, _) in candidates {
+            if keep.len() >= target_vocab_size {
+                break;
+            }
+            // 收集生成该标记所需、尚未保留的全部标记
+            let mut missing = Vec::new();
+            let mut stack = vec![id];
+            while let Some(token) = stack.pop() {
+                if keep.contains(&token) || missing.contains(&token) {
+                    continue;
+                }
+                missing.push(token);
+                if let Some(&(left, right)) = components.get(&token) {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+            if keep.len() + missing.len() <= target_vocab_size {
+                keep.extend(missing);
+            }
+        }
+
+        // 合并生成的标记ID总是大于其组成部分，按原ID顺序编号即可保持合并表一致
+        let mut kept: Vec<u32> = keep.into_iter().collect();
+        kept.sort_unstable();
+        let id_map: std::collections::BTreeMap<u32, u32> = kept
+            .iter()
+            .enumerate()
+            .map(|(new_id, &old_id)| (old_id, new_id as u32))
+            .collect();
+
+        let mut distilled = self.clone();
+        distilled.vocab = VocabManager::with_capacity(kept.len());
+        for &old_id in &kept {
+            if let Some(bytes) = self.vocab.get_by_id(&old_id) {
+                distilled.vocab.insert(id_map[&old_id], bytes.clone());
+            }
+        }
+        distilled.merges = self
+            .merges
+            .iter()
+            .filter_map(|(&(left, right), id)| {
+                Some((
+                    (*id_map.get(&left)?, *id_map.get(&right)?),
+                    *id_map.get(id)?,
+                ))
+            })
+            .collect();
+        distilled
+            .base
+            .special_tokens
+            .remap(|id| id_map.get(&id).copied());
+        distilled.next_token_id = kept.len() as u32;
+        distilled.journal = None;
+
+        log::info!(
+            "蒸馏完成: 词汇表大小 {} -> {}",
+            self.vocab.len(),
+            distilled.vocab.len()
+        );
+        let manifest = RemapManifest::new(
+            self.vocab.ids().copied(),
+            distilled.vocab.ids().copied(),
+            id_map,
+        );
+        Ok((distilled, manifest))
+    }
+
+    /// 使用rayon并行解码一批标记ID
+    #[cfg(feature = "python")]
+    fn decode_token_lists(&self, token_lists: &[Vec<u32>]) -> Result<Vec<String>, TokenizerError> {
+        token_lists
+            .par_iter()
+            .map(|tokens| self.decode(tokens))
+            .collect()
+    }
+
+    /// 使用rayon并行编码一批文本，空值编码为空列表
+    #[cfg(feature = "python")]
+    fn encode_batch_internal(
+        &self,
+        texts: &[Option<&str>],
+    ) -> Result<Vec<Vec<u32>>, TokenizerError> {
+        texts
+            .par_iter()
+            .map(|text| text.map_or_else(|| Ok(Vec::new()), |text| self.encode(text)))
+            .collect()
+    }
+
+    /// 直接从内存中的模型数据创建分词器，适用于浏览器等没有文件系统的环境
+    ///
+    /// # Errors
+    ///
+    /// 当模型数据格式无效或解析失败时返回错误
+    pub fn from_bytes(data: &[u8]) -> Result<Self, TokenizerError> {
+        let mut tokenizer = Self::new_internal()?;
+        tokenizer.load_from_bytes(data)?;
+        Ok(tokenizer)
+    }
+
+    /// 交叉检查词汇表、合并规则和特殊标记注册表，见 [`IntegrityReport`]
+    ///
+    /// 加载时会自动检查，直接修改 `vocab`、`merges` 等字段后可以再次调用。
+    /// 部分加载的模型缺少合并结果对应的词汇表条目，不检查合并规则
+    #[must_use]
+    pub fn check_integrity(&self) -> IntegrityReport {
+        let mut report = IntegrityReport::default();
+        report.check_vocab(&self.vocab);
+        if self.parts == ModelParts::Full {
+            report.check_merges(&self.vocab, &self.merges);
+        }
+        report.check_special_tokens(&self.base.special_tokens, &self.vocab);
+        report
+    }
+
+    /// 从内存中的模型数据加载分词器，格式与 [`Tokenizer::save`] 写出的文件相同
+    ///
+    /// # Errors
+    ///
+    /// 当模型数据格式无效、解析失败或完整性检查发现问题时返回错误
+    pub fn load_from_bytes(&mut self, data: &[u8]) -> Result<(), TokenizerError> {
+        self.load_parts_from_bytes(data, ModelParts::Full)
+    }
+
+    /// 从文件只加载模型的一部分，见 [`ModelParts`]；存在日志文件时同样重放
+    ///
+    /// # Errors
+    ///
+    /// 当文件读取失败、模型数据无效或完整性检查发现问题时返回错误
+    pub fn load_parts(&mut self, path: &str, parts: ModelParts) -> Result<(), TokenizerError> {
+        trace_span!("bbpe.load_parts", path);
+        let data =
+            std::fs::read(path).map_err(|e| model_load_error(format!("打开文件失败: {}", e)))?;
+        self.load_parts_from_bytes(&data, parts)?;
+        self.replay_journal_file(path)
+    }
+
+    /// 从内存中的模型数据只加载一部分，见 [`ModelParts`]
+    ///
+    /// # Errors
+    ///
+    /// 当模型数据格式无效、解析失败或完整性检查发现问题时返回错误
+    pub fn load_parts_from_bytes(
+        &mut self,
+        data: &[u8],
+        parts: ModelParts,
+    ) -> Result<(), TokenizerError> {
+        let data = &*normalize_line_endings(data);
         // 使用基础分词器的加载方法
-        self.base.load(path)?;
+        self.base.load_from_reader(data)?;
+        self.journal = Some(Vec::new());
+        self.parts = parts;
 
-        // 加载BBPE特定的数据
-        use std::fs::File;
-        use std::io::{self, BufRead};
+        let mut report = match find_sections(data)? {
+            Some((start, lengths)) if sections_aligned(data, start, lengths) => {
+                self.load_sections(data, start, lengths)?
+            }
+            Some(_) => {
+                // 段索引按字节记录长度，手工编辑过的文件（如增删了空白）不再对齐，逐行加载不依赖索引
+                log::warn!("模型文件的段索引与内容不一致，改为逐行加载");
+                self.load_lines(data, true)?
+            }
+            None => self.load_lines(data, false)?,
+        };
 
-        let file = File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
-        let reader = io::BufReader::new(file);
+        // 新标记排在已有标记、合并结果和预留的特殊标记ID之后
+        self.next_token_id = self
+            .vocab
+            .ids()
+            .chain(self.merges.values())
+            .max()
+            .map_or(0, |&id| id + 1)
+            .max(self.base.special_tokens.end_id());
+
+        report.extend(self.check_integrity());
+        report.into_result()
+    }
+
+    /// 逐行加载没有段索引的旧模型中的基础字符、词汇表和合并规则，返回词汇表条目的重复检查结果
+    ///
+    /// 段索引存在但与内容不对齐时也逐行加载，此时 `check_counts` 为true，要求各段的行数与段首行记录的一致
+    fn load_lines(
+        &mut self,
+        data: &[u8],
+        check_counts: bool,
+    ) -> Result<IntegrityReport, TokenizerError> {
+        use std::io::BufRead;
 
-        let lines = reader.lines();
+        // 加载BBPE特定的数据
+        let lines = data.lines();
         let mut in_base_chars = false;
         let mut in_vocab = false;
         let mut in_merges = false;
@@ -713,9 +2450,15 @@ impl Tokenizer for BBPETokenizer {
         self.base_chars.clear();
         self.vocab.clear();
         self.merges.clear();
+        let mut entries = Vec::new();
+        let mut merges = Vec::new();
+        // 段首行记录的条目数，用于发现被截断或改坏的行
+        let mut vocab_count = None;
+        let mut merges_count = None;
+        let parse_count = |count: &str| count.trim().parse::<usize>().ok();
 
         for line in lines {
-            let line = line.map_err(|e| format!("读取行失败: {}", e))?;
+            let line = line.map_err(|e| model_load_error(format!("读取行失败: {}", e)))?;
             let line = line.trim();
 
             if line.starts_with("base_chars: ") {
@@ -723,12 +2466,14 @@ impl Tokenizer for BBPETokenizer {
                 in_vocab = false;
                 in_merges = false;
                 continue;
-            } else if line.starts_with("vocab: ") {
+            } else if let Some(count) = line.strip_prefix("vocab: ") {
+                vocab_count = parse_count(count);
                 in_base_chars = false;
                 in_vocab = true;
                 in_merges = false;
                 continue;
-            } else if line.starts_with("merges: ") {
+            } else if let Some(count) = line.strip_prefix("merges: ") {
+                merges_count = parse_count(count);
                 in_base_chars = false;
                 in_vocab = false;
                 in_merges = true;
@@ -744,77 +2489,526 @@ impl Tokenizer for BBPETokenizer {
                     if let Some(entry_data) = line.strip_prefix("vocab_entry: ") {
                         let parts: Vec<&str> = entry_data.split_whitespace().collect();
                         if parts.len() >= 2 {
-                            let id = parts[0]
-                                .parse::<u32>()
-                                .map_err(|e| format!("解析词汇表ID失败: {}", e))?;
+                            let id = parts[0].parse::<u32>().map_err(|e| {
+                                model_load_error(format!("解析词汇表ID失败: {}", e))
+                            })?;
                             let bytes: Result<Vec<u8>, _> =
                                 parts[1..].iter().map(|s| s.parse::<u8>()).collect();
-                            let bytes = bytes.map_err(|e| format!("解析字节失败: {}", e))?;
+                            let bytes = bytes
+                                .map_err(|e| model_load_error(format!("解析字节失败: {}", e)))?;
 
-                            self.vocab.insert(id, bytes);
+                            entries.push((id, bytes));
                         }
                     }
                 }
             } else if line.starts_with("merge: ") && in_merges {
                 if let Some(merge_data) = line.strip_prefix("merge: ") {
-                    let parts: Vec<&str> = merge_data.split_whitespace().collect();
-                    if parts.len() == 3 {
-                        let a = parts[0]
-                            .parse::<u32>()
-                            .map_err(|e| format!("解析合并规则失败: {}", e))?;
-                        let b = parts[1]
-                            .parse::<u32>()
-                            .map_err(|e| format!("解析合并规则失败: {}", e))?;
-                        let rank = parts[2]
-                            .parse::<u32>()
-                            .map_err(|e| format!("解析合并规则失败: {}", e))?;
-                        self.merges.insert((a, b), rank);
-                    }
+                    merges.push(MergeEntry::parse(merge_data)?);
                 }
             }
         }
 
-        Ok(())
+        for (header, expected, actual) in [
+            ("vocab", vocab_count, entries.len()),
+            ("merges", merges_count, merges.len()),
+        ] {
+            if let Some(expected) = expected.filter(|&expected| check_counts && expected != actual)
+            {
+                return Err(model_load_error(format!(
+                    "{} 段应有 {} 行，实际为 {} 行",
+                    header, expected, actual
+                )));
+            }
+        }
+
+        let mut report = IntegrityReport::default();
+        report.check_entries(&entries);
+        let special_tokens = &self.base.special_tokens;
+        for (id, bytes) in entries {
+            if self.parts.keeps_entry(id, &bytes, special_tokens) {
+                self.vocab.insert(id, bytes);
+            }
+        }
+        if self.parts != ModelParts::DecodeOnly {
+            for entry in restore_ranked_merges(merges)? {
+                self.merges.insert(entry.pair, entry.new_id);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// 按段索引加载基础字符、词汇表和合并规则，词汇表和合并规则并行解析，
+    /// 返回词汇表条目的重复检查结果
+    fn load_sections(
+        &mut self,
+        data: &[u8],
+        start: usize,
+        [base_chars_len, vocab_len, merges_len]: [usize; 3],
+    ) -> Result<IntegrityReport, TokenizerError> {
+        let end = start + base_chars_len + vocab_len + merges_len;
+        let sections = data
+            .get(start..end)
+            .ok_or_else(|| model_load_error("段索引超出模型数据长度"))?;
+        let (base_chars, rest) = sections.split_at(base_chars_len);
+        let (vocab, merges) = rest.split_at(vocab_len);
+
+        let parts = self.parts;
+        let special_tokens = &self.base.special_tokens;
+        let (vocab, merges) = rayon::join(
+            || -> Result<_, TokenizerError> {
+                let entries = parse_section(vocab, "vocab: ", "vocab_entry: ", parse_vocab_entry)?;
+                let mut report = IntegrityReport::default();
+                report.check_entries(&entries);
+                let mut id_map = StdHashMap::with_capacity(entries.len());
+                id_map.extend(
+                    entries
+                        .into_iter()
+                        .filter(|(id, bytes)| parts.keeps_entry(*id, bytes, special_tokens)),
+                );
+                Ok((VocabManager::from_id_map(id_map), report))
+            },
+            || -> Result<_, TokenizerError> {
+                // 只解码时跳过合并规则段，不解析也不分配内存
+                if parts == ModelParts::DecodeOnly {
+                    return Ok(MergeMap::new());
+                }
+                let entries = parse_section(merges, "merges: ", "merge: ", MergeEntry::parse)?;
+                let entries = restore_ranked_merges(entries)?;
+                let mut merges = MergeMap::with_capacity(entries.len());
+                merges.extend(entries.into_iter().map(|entry| (entry.pair, entry.new_id)));
+                Ok(merges)
+            },
+        );
+
+        let base_chars = std::str::from_utf8(base_chars)
+            .map_err(|e| model_load_error(format!("基础字符段不是有效的UTF-8: {}", e)))?;
+        self.base_chars = base_chars
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("base_char: "))
+            .map(|char_str| char_str.as_bytes().to_vec())
+            .collect();
+        let (vocab, report) = vocab?;
+        self.vocab = vocab;
+        self.merges = merges?;
+        Ok(report)
+    }
+
+    /// 生成模型文件中的基础字符、词汇表和合并规则三段文本
+    fn model_sections(&self) -> Result<(String, String, String), TokenizerError> {
+        use std::fmt::Write;
+
+        // 保存基础字符
+        let mut base_chars = String::new();
+        writeln!(base_chars, "base_chars: {}", self.base_chars.len())
+            .map_err(|e| model_save_error(format!("写入基础字符数量失败: {}", e)))?;
+        for char_bytes in &self.base_chars {
+            let char_str = String::from_utf8_lossy(char_bytes);
+            writeln!(base_chars, "base_char: {}", char_str)
+                .map_err(|e| model_save_error(format!("写入基础字符失败: {}", e)))?;
+        }
+
+        // 保存词汇表
+        let mut vocab = String::new();
+        writeln!(vocab, "vocab: {}", self.vocab.len())
+            .map_err(|e| model_save_error(format!("写入词汇表数量失败: {}", e)))?;
+        for (id, bytes) in self.vocab.iter() {
+            let byte_str = bytes
+                .iter()
+                .map(|b| b.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(vocab, "vocab_entry: {} {}", id, byte_str)
+                .map_err(|e| model_save_error(format!("写入词汇表条目失败: {}", e)))?;
+        }
+
+        // 保存合并规则，按等级顺序写出，并显式记录等级
+        let mut merges = String::new();
+        writeln!(merges, "merges: {}", self.merges.len())
+            .map_err(|e| model_save_error(format!("写入合并规则数量失败: {}", e)))?;
+        for (rank, (pair, new_id)) in (0u32..).zip(ranked_merges(&self.merges)) {
+            let entry = MergeEntry {
+                pair,
+                new_id,
+                rank: Some(rank),
+            };
+            writeln!(merges, "merge: {}", entry.to_line())
+                .map_err(|e| model_save_error(format!("写入合并规则失败: {}", e)))?;
+        }
+
+        Ok((base_chars, vocab, merges))
     }
 }
 
-impl MergeBasedTokenizer for BBPETokenizer {
-    fn apply_merges(&mut self, tokens: &mut Vec<Self::TokenId>) -> Result<(), String> {
-        // 应用合并规则，直到没有更多合并可以应用
-        let mut changed = true;
-        while changed {
-            changed = false;
-            let mut new_tokens = Vec::new();
-            let mut i = 0;
-
-            while i < tokens.len() {
-                if i + 1 < tokens.len() {
-                    // 检查当前token对是否可以合并
-                    if let Some(&new_id) = self.merges.get(&(tokens[i], tokens[i + 1])) {
-                        new_tokens.push(new_id);
-                        i += 2;
-                        changed = true;
-                    } else {
-                        new_tokens.push(tokens[i]);
-                        i += 1;
+/// 解析 `id b1 b2 ...` 形式的词汇表条目（不含 `vocab_entry: ` 前缀）
+pub(super) fn parse_vocab_entry(entry: &str) -> Result<(u32, Vec<u8>), TokenizerError> {
+    let (id, bytes) = entry.split_once(' ').unwrap_or((entry, ""));
+    let id = id
+        .parse::<u32>()
+        .map_err(|e| model_load_error(format!("解析词汇表ID失败: {}", e)))?;
+    let bytes = bytes
+        .split_whitespace()
+        .map(str::parse::<u8>)
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|e| model_load_error(format!("解析字节失败: {}", e)))?;
+    Ok((id, bytes))
+}
+
+/// 模型文件中段索引行的前缀，记录基础字符、词汇表和合并规则三段的字节长度
+const SECTIONS_PREFIX: &str = "sections: ";
+
+/// 查找段索引行，返回第一段的起始偏移和各段长度；旧格式没有索引时返回 `None`
+fn find_sections(data: &[u8]) -> Result<Option<(usize, [usize; 3])>, TokenizerError> {
+    let mut offset = 0;
+    for line in data.split(|&b| b == b'\n') {
+        let next = offset + line.len() + 1;
+        if let Some(lengths) = line.strip_prefix(SECTIONS_PREFIX.as_bytes()) {
+            let lengths = std::str::from_utf8(lengths)
+                .map_err(|e| model_load_error(format!("段索引不是有效的UTF-8: {}", e)))?
+                .split_whitespace()
+                .map(str::parse::<usize>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| model_load_error(format!("解析段索引失败: {}", e)))?;
+            let [base_chars, vocab, merges] = lengths[..] else {
+                return Err(model_load_error(format!(
+                    "段索引需要3个长度，实际为 {} 个",
+                    lengths.len()
+                )));
+            };
+            return Ok(Some((next, [base_chars, vocab, merges])));
+        }
+        if line.starts_with(b"base_chars: ") {
+            return Ok(None);
+        }
+        offset = next;
+    }
+    Ok(None)
+}
+
+/// 段索引给出的三段是否恰好落在 `base_chars: `、`vocab: `、`merges: ` 各行的起止位置
+fn sections_aligned(data: &[u8], start: usize, lengths: [usize; 3]) -> bool {
+    let mut offset = start;
+    for (length, header) in lengths
+        .into_iter()
+        .zip(["base_chars: ", "vocab: ", "merges: "])
+    {
+        let Some(section) = data.get(offset..offset + length) else {
+            return false;
+        };
+        if !section.starts_with(header.as_bytes()) || !section.ends_with(b"\n") {
+            return false;
+        }
+        offset += length;
+    }
+    true
+}
+
+/// 解析一段：首行为 `header` 加条目数，其余每行以 `prefix` 开头，各行并行解析
+fn parse_section<T, F>(
+    section: &[u8],
+    header: &str,
+    prefix: &str,
+    parse: F,
+) -> Result<Vec<T>, TokenizerError>
+where
+    T: Send,
+    F: Fn(&str) -> Result<T, TokenizerError> + Sync,
+{
+    let text = std::str::from_utf8(section)
+        .map_err(|e| model_load_error(format!("{}段不是有效的UTF-8: {}", header.trim(), e)))?;
+    let mut lines = text.lines();
+    let count = lines
+        .next()
+        .and_then(|line| line.strip_prefix(header))
+        .and_then(|count| count.trim().parse::<usize>().ok())
+        .ok_or_else(|| model_load_error(format!("段缺少有效的 {} 行", header.trim())))?;
+
+    let mut entries = Vec::with_capacity(count);
+    entries.extend(lines.filter(|line| !line.trim().is_empty()));
+    if entries.len() != count {
+        return Err(model_load_error(format!(
+            "{} 段应有 {} 行，实际为 {} 行",
+            header.trim(),
+            count,
+            entries.len()
+        )));
+    }
+
+    entries
+        .par_iter()
+        .map(|line| {
+            let data = line
+                .trim()
+                .strip_prefix(prefix)
+                .ok_or_else(|| model_load_error(format!("无效的模型行: {}", line)))?;
+            parse(data)
+        })
+        .collect()
+}
+
+impl Tokenizer for BBPETokenizer {
+    type TokenId = u32;
+
+    fn encode(&self, text: &str) -> Result<Vec<Self::TokenId>, TokenizerError> {
+        trace_span!(debug: "bbpe.encode", text_len = text.len());
+        self.encode_with(text, &self.base.compiled_pattern, |ids| {
+            self.apply_merges(ids)
+        })
+    }
+
+    fn decode(&self, tokens: &[Self::TokenId]) -> Result<String, TokenizerError> {
+        if self.base.decoder != DecoderKind::Fuse {
+            return decode_ids(&self.base.decoder, tokens, |id| self.decoded_piece(&id));
+        }
+        // 按标记字节长度之和预分配，避免逐个追加时反复扩容
+        let total_len = tokens
+            .iter()
+            .map(|id| self.vocab.get_by_id(id).map_or(0, Vec::len))
+            .sum();
+        let mut bytes = Vec::with_capacity(total_len);
+
+        for (index, &id) in tokens.iter().enumerate() {
+            if let Some(token_bytes) = self.vocab.get_by_id(&id) {
+                bytes.extend_from_slice(token_bytes);
+            } else {
+                return Err(TokenizerError::UnknownTokenId { id, index });
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|e| {
+            let token_lens = tokens
+                .iter()
+                .map(|id| self.vocab.get_by_id(id).map_or(0, Vec::len));
+            invalid_utf8_error(&e.utf8_error(), token_lens)
+        })
+    }
+
+    fn decode_cow<'a>(&'a self, tokens: &[Self::TokenId]) -> Result<Cow<'a, str>, TokenizerError> {
+        if let ([id], DecoderKind::Fuse) = (tokens, self.base.decoder) {
+            let token_bytes = self
+                .vocab
+                .get_by_id(id)
+                .ok_or(TokenizerError::UnknownTokenId { id: *id, index: 0 })?;
+            return std::str::from_utf8(token_bytes)
+                .map(Cow::Borrowed)
+                .map_err(|e| invalid_utf8_error(&e, [token_bytes.len()]));
+        }
+
+        Tokenizer::decode(self, tokens).map(Cow::Owned)
+    }
+
+    fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> Result<(), TokenizerError> {
+        trace_span!("bbpe.train", texts = texts.len(), vocab_size);
+        log::info!("开始BBPE训练，目标词汇表大小: {}", vocab_size);
+
+        self.prepare_training(vocab_size)?;
+        // 特殊标记不参与合并，从语料中去除
+        let texts = self.base.special_tokens.strip_texts(texts);
+        let texts = self.base.trainer.shuffle(texts);
+
+        // 将文本转换为词序列
+        log::info!("处理 {} 个文本样本", texts.len());
+        let (words, counts) = {
+            let mut words = Vec::new();
+            let mut counts = Vec::new();
+
+            for text in &texts {
+                // 使用正则表达式分割文本
+                let parts = self.base.split_text(text)?;
+
+                for part in parts {
+                    if part.is_empty() {
+                        continue;
+                    }
+
+                    words.push(self.training_word(part.as_bytes()));
+                    counts.push(1);
+                }
+
+                // 如果没有匹配到任何内容，退回到简单分割
+                if words.is_empty() {
+                    log::warn!("正则表达式未匹配，使用简单分割");
+                    for word in text.split_whitespace() {
+                        words.push(self.training_word(word.as_bytes()));
+                        counts.push(1);
                     }
-                } else {
-                    new_tokens.push(tokens[i]);
-                    i += 1;
                 }
             }
+            (words, counts)
+        };
+
+        // 使用增量训练核心，从头学习合并规则
+        self.train_core_incremental(words, counts, vocab_size)?;
+        log::info!("BBPE训练完成，最终词汇表大小: {}", self.vocab.len());
+
+        Ok(())
+    }
+
+    fn train_with_config(
+        &mut self,
+        texts: Vec<String>,
+        vocab_size: u32,
+        config: TrainerConfig,
+    ) -> Result<(), TokenizerError> {
+        let previous = std::mem::replace(&mut self.base.trainer, config);
+        let result = Tokenizer::train(self, texts, vocab_size);
+        self.base.trainer = previous;
+        result
+    }
+
+    fn vocab_size(&self) -> usize {
+        self.vocab.len()
+    }
 
-            *tokens = new_tokens;
+    /// 单独出现的非ASCII字节说明字符没有被合并为完整的token
+    fn is_fallback_token(&self, id: &Self::TokenId) -> bool {
+        *id < 256
+            && self
+                .vocab
+                .get_by_id(id)
+                .is_some_and(|bytes| matches!(bytes.as_slice(), [b] if *b >= 0x80))
+    }
+
+    fn save(&self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("bbpe.save", path);
+        if self.parts != ModelParts::Full {
+            return Err(model_save_error("部分加载的模型不能保存"));
         }
+        // 使用基础分词器的保存方法
+        self.base.save(path)?;
+
+        // 保存BBPE特定的数据
+        use std::fs::OpenOptions;
+        use std::io::Write;
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| model_save_error(format!("打开文件失败: {}", e)))?;
+
+        // 各段先写入内存，再在段前写出各段的字节长度，加载时据此定位各段并行解析
+        let (base_chars, vocab, merges) = self.model_sections()?;
+        writeln!(
+            file,
+            "{}{} {} {}",
+            SECTIONS_PREFIX,
+            base_chars.len(),
+            vocab.len(),
+            merges.len()
+        )
+        .map_err(|e| model_save_error(format!("写入段索引失败: {}", e)))?;
+        for section in [base_chars, vocab, merges] {
+            file.write_all(section.as_bytes())
+                .map_err(|e| model_save_error(format!("写入模型数据失败: {}", e)))?;
+        }
+
+        // 完整的模型已包含日志中的全部变更，旧日志不能再重放
+        let journal = journal_path(path);
+        if std::path::Path::new(&journal).exists() {
+            std::fs::remove_file(&journal)
+                .map_err(|e| model_save_error(format!("删除旧日志失败: {}", e)))?;
+        }
+
+        Ok(())
+    }
+
+    fn load(&mut self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("bbpe.load", path);
+        let data =
+            std::fs::read(path).map_err(|e| model_load_error(format!("打开文件失败: {}", e)))?;
+        self.load_from_bytes(&data)?;
+        self.replay_journal_file(path)
+    }
+}
 
+impl MergeBasedTokenizer for BBPETokenizer {
+    fn apply_merges(&mut self, tokens: &mut Vec<Self::TokenId>) -> Result<(), TokenizerError> {
+        apply_ranked_merges(tokens, &self.merges);
         Ok(())
     }
 
-    fn get_merges(&self) -> &StdHashMap<(Self::TokenId, Self::TokenId), Self::TokenId> {
+    fn get_merges(&self) -> &MergeMap {
         &self.merges
     }
 
-    fn set_merges(&mut self, merges: StdHashMap<(Self::TokenId, Self::TokenId), Self::TokenId>) {
+    fn set_merges(&mut self, merges: MergeMap) {
         self.merges = merges;
+        self.journal = None;
+    }
+}
+
+impl SpecialTokenizer for BBPETokenizer {
+    fn special_tokens(&self) -> &SpecialTokens {
+        &self.base.special_tokens
+    }
+
+    fn encode_options(&self) -> &EncodeOptions {
+        &self.base.encode_options
+    }
+
+    fn encode_options_mut(&mut self) -> &mut EncodeOptions {
+        &mut self.base.encode_options
+    }
+
+    /// 优先放入空闲的预留占位符位置，没有时追加到词汇表末尾
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Result<Vec<u32>, TokenizerError> {
+        let mut ids = Vec::with_capacity(tokens.len());
+        for &token in tokens {
+            let bytes = token.as_bytes().to_vec();
+            if let Some(&id) = self.vocab.get_by_value(&bytes) {
+                self.register_special_token(token, id)?;
+                ids.push(id);
+                continue;
+            }
+            if !self.reserved_special_token_ids().is_empty() {
+                ids.push(self.assign_special_token(token)?);
+                continue;
+            }
+            let id =
+                self.base
+                    .special_tokens
+                    .add(&mut self.vocab, &mut self.next_token_id, &[token])?[0];
+            self.record_journal(JournalEntry::Token { id, bytes });
+            self.record_journal(JournalEntry::Special {
+                id,
+                token: token.to_string(),
+            });
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// 同 [`BBPETokenizer::reserve_special_tokens`]，预留的位置先用占位符填充
+    fn reserve_special_ids(&mut self, count: u32) -> Result<std::ops::Range<u32>, TokenizerError> {
+        if count == 0 {
+            return Err(vocab_error("预留的特殊标记数量必须大于0"));
+        }
+        self.reserve_special_tokens(count)
+    }
+}
+
+impl VocabBytes for BBPETokenizer {
+    fn normalize_aligned(&self, text: &str) -> NormalizedText {
+        self.base.normalize_aligned(text)
+    }
+
+    fn token_bytes(&self, id: &u32) -> Option<Cow<'_, [u8]>> {
+        self.vocab
+            .get_by_id(id)
+            .map(|bytes| Cow::Borrowed(bytes.as_slice()))
+    }
+
+    fn vocab_bytes(&self) -> Vec<(u32, Cow<'_, [u8]>)> {
+        let mut entries: Vec<(u32, Cow<'_, [u8]>)> = self
+            .vocab
+            .iter()
+            .map(|(&id, bytes)| (id, Cow::Borrowed(bytes.as_slice())))
+            .collect();
+        entries.sort_unstable_by_key(|&(id, _)| id);
+        entries
+    }
+
+    fn decoder(&self) -> DecoderKind {
+        self.base.decoder
     }
 }
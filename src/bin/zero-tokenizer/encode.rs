@@ -0,0 +1,147 @@
+//! `encode` 子命令
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use clap::{Args, ValueEnum};
+
+use zero_tokenizer::error::{encoding_error, input_validation_error, load_error, TokenizerError};
+use zero_tokenizer::pipeline::{
+    encode_jsonl_stream, encode_stream, JsonlFields, LengthPrefixedWriter, NpyShardWriter,
+    StreamConfig, StreamStats,
+};
+
+use crate::model::ModelArgs;
+
+/// 编码结果的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// 单个文件，每行先写标记数，再写标记ID，均为小端u32
+    Binary,
+    /// 输出目录中按固定标记数切分的 .npy 分片
+    Npy,
+    /// 输入为JSONL，把各字段的标记ID写回每条记录
+    Jsonl,
+}
+
+#[derive(Debug, Args)]
+pub struct EncodeArgs {
+    #[command(flatten)]
+    model: ModelArgs,
+
+    /// 输入语料文件，每行一条文本；指定 --field 时每行一条JSON记录
+    #[arg(long)]
+    input: String,
+
+    /// 输出文件（binary、jsonl）或输出目录（npy）
+    #[arg(long)]
+    output: String,
+
+    /// 输出格式，默认为 binary，指定 --field 时为 jsonl
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// 需要编码的JSONL字段，可重复指定；字段 f 的标记ID写回到 f_ids
+    #[arg(long)]
+    field: Vec<String>,
+
+    /// 同时把每个字段的标记数写回到 f_num_tokens
+    #[arg(long)]
+    counts: bool,
+
+    /// 每个npy分片包含的标记数
+    #[arg(long, default_value_t = 100_000_000)]
+    shard_tokens: usize,
+
+    /// 在npy分片中每条文本之后追加的分隔标记ID
+    #[arg(long)]
+    separator: Option<u32>,
+
+    /// 编码线程数，默认使用CPU核数
+    #[arg(long, default_value_t = 0)]
+    workers: usize,
+
+    /// 每块包含的行数
+    #[arg(long, default_value_t = 1024)]
+    chunk_lines: usize,
+
+    /// 同时在途的最大块数，决定内存占用上限
+    #[arg(long, default_value_t = 64)]
+    max_in_flight: usize,
+}
+
+pub fn run(args: &EncodeArgs) -> Result<(), TokenizerError> {
+    let tokenizer = args.model.load()?;
+    let input = File::open(&args.input)
+        .map_err(|e| load_error(format!("无法打开语料 {}: {}", args.input, e)))?;
+    let reader = BufReader::new(input);
+    let config = StreamConfig {
+        workers: args.workers,
+        chunk_lines: args.chunk_lines,
+        max_in_flight: args.max_in_flight,
+    };
+
+    let format = args.format.unwrap_or(if args.field.is_empty() {
+        OutputFormat::Binary
+    } else {
+        OutputFormat::Jsonl
+    });
+    match (format, args.field.is_empty()) {
+        (OutputFormat::Jsonl, true) => {
+            return Err(input_validation_error(
+                "jsonl 格式需要通过 --field 指定字段",
+            ))
+        }
+        (OutputFormat::Binary | OutputFormat::Npy, false) => {
+            return Err(input_validation_error("--field 只能用于 jsonl 格式"))
+        }
+        _ => {}
+    }
+
+    let stats = match format {
+        OutputFormat::Binary => {
+            let output = File::create(&args.output)
+                .map_err(|e| encoding_error(format!("无法创建输出文件 {}: {}", args.output, e)))?;
+            let mut sink = LengthPrefixedWriter::new(BufWriter::new(output));
+            encode_stream(tokenizer.as_ref(), reader, &mut sink, config)?
+        }
+        OutputFormat::Npy => {
+            let stem = std::path::Path::new(&args.input)
+                .file_stem()
+                .map_or_else(|| "shard".to_string(), |s| s.to_string_lossy().into_owned());
+            let mut sink = NpyShardWriter::new(&args.output, &stem, args.shard_tokens)
+                .map_err(|e| encoding_error(format!("无法创建输出目录 {}: {}", args.output, e)))?;
+            if let Some(separator) = args.separator {
+                sink = sink.with_separator(separator);
+            }
+            let stats = encode_stream(tokenizer.as_ref(), reader, &mut sink, config)?;
+            println!("写出 {} 个分片", sink.shards().len());
+            stats
+        }
+        OutputFormat::Jsonl => {
+            let output = File::create(&args.output)
+                .map_err(|e| encoding_error(format!("无法创建输出文件 {}: {}", args.output, e)))?;
+            let mut fields = JsonlFields::new(args.field.iter().cloned());
+            if args.counts {
+                fields = fields.with_counts();
+            }
+            encode_jsonl_stream(
+                tokenizer.as_ref(),
+                reader,
+                &mut BufWriter::new(output),
+                &fields,
+                config,
+            )?
+        }
+    };
+
+    print_stats(&stats);
+    Ok(())
+}
+
+fn print_stats(stats: &StreamStats) {
+    println!(
+        "编码 {} 行，{} 字节，{} 个标记",
+        stats.texts, stats.bytes, stats.tokens
+    );
+}
@@ -0,0 +1,92 @@
+//! `eval` 子命令
+
+use std::path::Path;
+
+use clap::{Args, ValueEnum};
+
+use zero_tokenizer::analysis;
+use zero_tokenizer::error::{load_error, TokenizerError};
+
+use crate::model::{ModelArgs, ModelKind};
+
+#[derive(Debug, Args)]
+pub struct EvalArgs {
+    /// 参与评测的模型，格式为 `类型:路径`，如 `bbpe:en.model`，或省略类型按文件内容识别，可重复指定
+    #[arg(long = "tokenizer", required = true)]
+    tokenizers: Vec<String>,
+
+    /// 评测语料，格式为 `名称=路径` 或 `路径`（以文件名为名称），每行一条文本，可重复指定
+    #[arg(long = "corpus", required = true)]
+    corpora: Vec<String>,
+
+    /// 以JSON格式输出结果
+    #[arg(long)]
+    json: bool,
+}
+
+/// 解析 `类型:路径` 形式的模型参数，省略类型时按模型文件内容自动识别
+fn parse_model(spec: &str) -> Result<ModelArgs, TokenizerError> {
+    let parsed = spec
+        .split_once(':')
+        .and_then(|(kind, path)| Some((ModelKind::from_str(kind, true).ok()?, path)));
+    Ok(match parsed {
+        Some((kind, path)) => ModelArgs {
+            model: path.to_string(),
+            kind: Some(kind),
+        },
+        None => ModelArgs {
+            model: spec.to_string(),
+            kind: None,
+        },
+    })
+}
+
+/// 解析 `名称=路径` 形式的语料参数并读取语料
+fn read_corpus(spec: &str) -> Result<(String, Vec<String>), TokenizerError> {
+    let (name, path) = match spec.split_once('=') {
+        Some((name, path)) => (name.to_string(), path),
+        None => {
+            let stem = Path::new(spec)
+                .file_stem()
+                .map_or_else(|| spec.to_string(), |s| s.to_string_lossy().into_owned());
+            (stem, spec)
+        }
+    };
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| load_error(format!("无法读取语料 {}: {}", path, e)))?;
+    Ok((name, content.lines().map(str::to_string).collect()))
+}
+
+pub fn run(args: &EvalArgs) -> Result<(), TokenizerError> {
+    let models = args
+        .tokenizers
+        .iter()
+        .map(|spec| {
+            let model = parse_model(spec)?;
+            Ok((model.model.clone(), model.load()?))
+        })
+        .collect::<Result<Vec<_>, TokenizerError>>()?;
+    let corpora = args
+        .corpora
+        .iter()
+        .map(|spec| read_corpus(spec))
+        .collect::<Result<Vec<_>, TokenizerError>>()?;
+
+    let tokenizers: Vec<_> = models
+        .iter()
+        .map(|(name, tokenizer)| (name.as_str(), tokenizer.as_ref() as _))
+        .collect();
+    let corpora: Vec<_> = corpora
+        .iter()
+        .map(|(name, samples)| (name.as_str(), samples.as_slice()))
+        .collect();
+
+    let report = analysis::evaluate(&tokenizers, &corpora)?;
+    if args.json {
+        let json = serde_json::to_string_pretty(&report)?;
+        println!("{}", json);
+    } else {
+        print!("{}", report.to_markdown());
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! `inspect` 子命令
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use clap::Args;
+
+use zero_tokenizer::analysis::{self, InspectReport, TokenCount};
+use zero_tokenizer::base::traits::VocabBytes;
+use zero_tokenizer::error::{load_error, TokenizerError};
+
+use crate::model::ModelArgs;
+
+#[derive(Debug, Args)]
+pub struct InspectArgs {
+    #[command(flatten)]
+    model: ModelArgs,
+
+    /// 样本语料文件，每行一条文本
+    #[arg(long)]
+    corpus: Option<String>,
+
+    /// 最常用和最少用标记列表的长度
+    #[arg(long, default_value_t = 20)]
+    top: usize,
+
+    /// 以JSON格式输出报告
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: &InspectArgs) -> Result<(), TokenizerError> {
+    let tokenizer = args.model.load()?;
+
+    let report = match &args.corpus {
+        Some(path) => {
+            let file = File::open(path)
+                .map_err(|e| load_error(format!("无法打开语料 {}: {}", path, e)))?;
+            let lines = BufReader::new(file)
+                .lines()
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| load_error(format!("读取语料 {} 失败: {}", path, e)))?;
+            analysis::inspect(tokenizer.as_ref(), lines, args.top)?
+        }
+        None => analysis::inspect(tokenizer.as_ref(), std::iter::empty::<&str>(), args.top)?,
+    };
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&report)?;
+        println!("{}", json);
+    } else {
+        print_report(tokenizer.as_ref(), &report, args.corpus.is_some());
+    }
+    Ok(())
+}
+
+fn print_report<T>(tokenizer: &T, report: &InspectReport, with_corpus: bool)
+where
+    T: VocabBytes<TokenId = u32> + ?Sized,
+{
+    println!("词汇表大小: {}", report.vocab_size);
+    if !with_corpus {
+        return;
+    }
+
+    println!("样本条数: {}", report.samples);
+    println!(
+        "样本大小: {} 字节, {} 字符",
+        report.total_bytes, report.total_chars
+    );
+    println!("标记总数: {}", report.total_tokens);
+    println!(
+        "已使用标记: {} ({:.2}%), 未使用: {}",
+        report.used_tokens,
+        percent(report.used_tokens, report.vocab_size),
+        report.unused_tokens()
+    );
+    println!(
+        "OOV/字节回退: {} ({:.2}%)",
+        report.fallback_tokens,
+        report.fallback_rate() * 100.0
+    );
+    println!(
+        "压缩率: {:.3} 字节/标记, {:.3} 字符/标记",
+        report.bytes_per_token(),
+        report.chars_per_token()
+    );
+
+    println!();
+    println!("最常用的 {} 个标记:", report.most_used.len());
+    print_counts(tokenizer, &report.most_used);
+    println!();
+    println!("最少用的 {} 个标记:", report.least_used.len());
+    print_counts(tokenizer, &report.least_used);
+}
+
+fn print_counts<T>(tokenizer: &T, counts: &[TokenCount])
+where
+    T: VocabBytes<TokenId = u32> + ?Sized,
+{
+    for entry in counts {
+        println!(
+            "{:>8}  {:>10}  {}",
+            entry.id,
+            entry.count,
+            tokenizer.render_token(&entry.id)
+        );
+    }
+}
+
+fn percent(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
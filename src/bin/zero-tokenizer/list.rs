@@ -0,0 +1,77 @@
+//! `list` 子命令
+
+use clap::Args;
+
+use zero_tokenizer::prelude::*;
+
+#[derive(Debug, Args)]
+pub struct ListArgs {
+    /// 注册表根目录，默认取环境变量 ZERO_TOKENIZER_MODELS 或 ~/.cache/zero-tokenizer/models
+    #[arg(long)]
+    registry: Option<String>,
+
+    /// 以JSON格式输出
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: &ListArgs) -> Result<(), TokenizerError> {
+    let registry = match &args.registry {
+        Some(root) => ModelRegistry::new(root),
+        None => ModelRegistry::open_default()?,
+    };
+    let models = registry.list()?;
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&models)?;
+        println!("{}", json);
+        return Ok(());
+    }
+    if models.is_empty() {
+        println!("注册表 {} 中没有模型", registry.root().display());
+        return Ok(());
+    }
+
+    let width = models.iter().map(|m| m.name.len()).max().unwrap_or(0);
+    for model in &models {
+        println!(
+            "{:<width$}  {:<9}  词汇表 {:>7}  {} UTC  {}",
+            model.name,
+            model.kind.name(),
+            model.vocab_size,
+            format_time(model.saved_at),
+            model.path.display(),
+            width = width
+        );
+    }
+    Ok(())
+}
+
+/// 把Unix时间戳格式化为 `YYYY-MM-DD HH:MM:SS`
+fn format_time(secs: u64) -> String {
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
+
+/// 自1970-01-01起的天数对应的公历日期
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
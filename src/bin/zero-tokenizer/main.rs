@@ -0,0 +1,56 @@
+//! zero-tokenizer 命令行工具
+
+mod encode;
+mod eval;
+mod inspect;
+mod list;
+mod model;
+mod repl;
+#[cfg(feature = "server")]
+mod serve;
+
+use clap::{Parser, Subcommand};
+
+/// Zero Tokenizer 命令行工具
+#[derive(Debug, Parser)]
+#[command(name = "zero-tokenizer", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// 查看词汇表统计，并在样本语料上分析标记使用情况
+    Inspect(inspect::InspectArgs),
+    /// 并行编码整个语料文件或JSONL数据集，写出二进制、npy分片或带标记ID的JSONL
+    Encode(encode::EncodeArgs),
+    /// 在多份语料上对比多个分词器的压缩率、切分粒度、无损率和吞吐量
+    Eval(eval::EvalArgs),
+    /// 交互式查看文本的切分结果
+    Repl(repl::ReplArgs),
+    /// 列出本地模型注册表中的模型，其他子命令可以直接使用这些名称
+    List(list::ListArgs),
+    /// 启动HTTP分词服务
+    #[cfg(feature = "server")]
+    Serve(serve::ServeArgs),
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Inspect(args) => inspect::run(&args),
+        Command::Encode(args) => encode::run(&args),
+        Command::Eval(args) => eval::run(&args),
+        Command::Repl(args) => repl::run(&args),
+        Command::List(args) => list::run(&args),
+        #[cfg(feature = "server")]
+        Command::Serve(args) => serve::run(&args),
+    };
+
+    if let Err(e) = result {
+        eprintln!("错误: {}", e);
+        std::process::exit(1);
+    }
+}
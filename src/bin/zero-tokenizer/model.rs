@@ -0,0 +1,78 @@
+//! 模型加载
+
+use std::path::Path;
+
+use clap::{Args, ValueEnum};
+
+use zero_tokenizer::base::binary;
+use zero_tokenizer::prelude::*;
+
+/// 分词器类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ModelKind {
+    /// 字节级BPE
+    Bbpe,
+    /// 字符级BPE
+    Bpe,
+    /// Unigram
+    Unigram,
+    /// WordPiece
+    Wordpiece,
+}
+
+impl From<ModelKind> for binary::ModelKind {
+    fn from(kind: ModelKind) -> Self {
+        match kind {
+            ModelKind::Bpe => Self::Bpe,
+            ModelKind::Bbpe => Self::Bbpe,
+            ModelKind::Unigram => Self::Unigram,
+            ModelKind::Wordpiece => Self::WordPiece,
+        }
+    }
+}
+
+/// 各子命令共用的模型参数
+#[derive(Debug, Args)]
+pub struct ModelArgs {
+    /// 模型文件路径，或本地模型注册表中的模型名称（见 `list` 子命令）
+    pub model: String,
+
+    /// 模型对应的分词器类型，省略时按模型文件内容自动识别；按名称加载注册表中的模型时取注册时的类型
+    #[arg(long, value_enum)]
+    pub kind: Option<ModelKind>,
+}
+
+impl ModelArgs {
+    /// 加载模型
+    pub fn load(&self) -> Result<Box<dyn VocabBytes<TokenId = u32> + Send + Sync>, TokenizerError> {
+        self.load_with_limits(InputLimits::default())
+    }
+
+    /// 加载模型，并设置编码输入的长度限制
+    pub fn load_with_limits(
+        &self,
+        limits: InputLimits,
+    ) -> Result<Box<dyn VocabBytes<TokenId = u32> + Send + Sync>, TokenizerError> {
+        let mut tokenizer = self
+            .load_dyn()
+            .map_err(|e| e.context(format!("加载模型 {} 失败", self.model)))?;
+        tokenizer.set_input_limits(limits)?;
+        Ok(tokenizer)
+    }
+
+    /// 路径不存在且注册表中有同名模型时加载注册表中的模型，否则按 `kind` 或文件内容确定类型
+    fn load_dyn(&self) -> Result<Box<dyn DynTokenizer>, TokenizerError> {
+        if !Path::new(&self.model).exists() && ModelRegistry::open_default()?.contains(&self.model)
+        {
+            return AutoTokenizer::from_named(&self.model);
+        }
+        match self.kind {
+            Some(kind) => {
+                let mut tokenizer = AutoTokenizer::for_kind(kind.into())?;
+                tokenizer.load(&self.model)?;
+                Ok(tokenizer)
+            }
+            None => AutoTokenizer::from_file(&self.model),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! `repl` 子命令
+
+use std::io::{self, BufRead, Write};
+
+use clap::Args;
+
+use zero_tokenizer::base::traits::VocabBytes;
+use zero_tokenizer::error::TokenizerError;
+
+use crate::model::ModelArgs;
+
+#[derive(Debug, Args)]
+pub struct ReplArgs {
+    #[command(flatten)]
+    model: ModelArgs,
+}
+
+/// 每行输入的显示方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Display {
+    /// 只显示标记ID
+    Ids,
+    /// 只显示带边界的标记文本
+    Tokens,
+    /// 同时显示ID和标记文本
+    Both,
+}
+
+const HELP: &str = "\
+输入任意文本查看切分结果，以 : 开头的行是命令:
+  :ids     只显示标记ID
+  :tokens  只显示标记文本
+  :both    同时显示ID和标记文本（默认）
+  :help    显示本帮助
+  :quit    退出";
+
+pub fn run(args: &ReplArgs) -> Result<(), TokenizerError> {
+    let tokenizer = args.model.load()?;
+    println!(
+        "已加载 {}，词汇表大小 {}，输入 :help 查看命令",
+        args.model.model,
+        tokenizer.vocab_size()
+    );
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut display = Display::Both;
+    let mut line = String::new();
+
+    loop {
+        print!("> ");
+        stdout.flush()?;
+
+        line.clear();
+        let read = stdin.lock().read_line(&mut line)?;
+        if read == 0 {
+            println!();
+            return Ok(());
+        }
+        let text = line.strip_suffix('\n').unwrap_or(&line);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+
+        match text.trim() {
+            ":ids" => display = Display::Ids,
+            ":tokens" => display = Display::Tokens,
+            ":both" => display = Display::Both,
+            ":help" => println!("{}", HELP),
+            ":quit" | ":q" => return Ok(()),
+            command if command.starts_with(':') => {
+                println!("未知命令 {}，输入 :help 查看命令", command);
+            }
+            _ => match print_line(tokenizer.as_ref(), text, display) {
+                Ok(()) => {}
+                Err(e) => println!("编码失败: {}", e),
+            },
+        }
+    }
+}
+
+fn print_line<T>(tokenizer: &T, text: &str, display: Display) -> Result<(), TokenizerError>
+where
+    T: VocabBytes<TokenId = u32> + ?Sized,
+{
+    let ids = tokenizer.encode(text)?;
+    println!(
+        "标记: {}  字节: {}  字符: {}",
+        ids.len(),
+        text.len(),
+        text.chars().count()
+    );
+
+    if display != Display::Tokens {
+        println!("{:?}", ids);
+    }
+    if display != Display::Ids {
+        let tokens: Vec<String> = ids.iter().map(|id| tokenizer.render_token(id)).collect();
+        println!("|{}|", tokens.join("|"));
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! `serve` 子命令
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::Args;
+
+use zero_tokenizer::base::limits::{InputLimits, LimitPolicy};
+use zero_tokenizer::base::traits::VocabBytes;
+use zero_tokenizer::batching::BatchConfig;
+use zero_tokenizer::error::TokenizerError;
+use zero_tokenizer::server;
+
+use crate::model::ModelArgs;
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    #[command(flatten)]
+    model: ModelArgs,
+
+    /// 监听地址
+    #[arg(long, default_value = "127.0.0.1:8000")]
+    addr: SocketAddr,
+
+    /// 启用动态批处理，合并并发请求中的文本，每批最多包含的文本数
+    #[arg(long)]
+    batch_size: Option<usize>,
+
+    /// 动态批处理时凑批最多等待的毫秒数
+    #[arg(long, default_value_t = 2)]
+    batch_wait_ms: u64,
+
+    /// 每条文本的最大字节数，超出时请求返回错误
+    #[arg(long)]
+    max_input_bytes: Option<usize>,
+
+    /// 每条文本编码结果的最大标记数，超出时请求返回错误
+    #[arg(long)]
+    max_tokens: Option<usize>,
+}
+
+pub fn run(args: &ServeArgs) -> Result<(), TokenizerError> {
+    let tokenizer: server::SharedTokenizer =
+        Arc::<dyn VocabBytes<TokenId = u32> + Send + Sync>::from(args.model.load_with_limits(
+            InputLimits {
+                max_input_bytes: args.max_input_bytes,
+                max_tokens: args.max_tokens,
+                policy: LimitPolicy::Error,
+            },
+        )?);
+
+    let runtime =
+        tokio::runtime::Runtime::new().map_err(|e| TokenizerError::InitializationError {
+            message: format!("创建运行时失败: {}", e),
+        })?;
+    println!("分词服务监听于 http://{}", args.addr);
+    let served = match args.batch_size {
+        Some(max_batch_size) => {
+            let config = BatchConfig {
+                max_batch_size,
+                max_wait: Duration::from_millis(args.batch_wait_ms),
+            };
+            runtime.block_on(server::serve_batched(args.addr, tokenizer, config))
+        }
+        None => runtime.block_on(server::serve(args.addr, tokenizer)),
+    };
+    served.map_err(|e| TokenizerError::InitializationError {
+        message: format!("分词服务运行失败: {}", e),
+    })
+}
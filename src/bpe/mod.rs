@@ -1,3 +1,3 @@
 mod tokenizer;
 
-pub use tokenizer::Tokenizer;
+pub use tokenizer::{DecodeMode, Tokenizer, UnknownCharFallback, UNK_TOKEN};
@@ -1,81 +1,801 @@
-#[cfg(feature = "python")]
+use std::borrow::Cow;
 use std::collections::HashMap as StdHashMap;
+use std::sync::Arc;
+use std::time::Instant;
 
-#[cfg(feature = "python")]
 use dary_heap::OctonaryHeap;
 #[cfg(feature = "python")]
 use pyo3::exceptions::PyValueError;
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
 
-#[cfg(feature = "python")]
 use ahash::{AHashMap, AHashSet};
 #[cfg(feature = "python")]
 use compact_str::CompactString;
-#[cfg(feature = "python")]
+use fancy_regex::Regex;
 use rayon::prelude::*;
 
+use crate::base::binary::{self, BinaryModel, ModelKind};
+use crate::base::decoder::{decode_ids, DecodedPiece, DecoderKind};
+use crate::base::events::{
+    top_pairs, PairCandidate, TrainEvent, TrainObserver, TrainObservers, TrainStats,
+};
+use crate::base::hf_json::{merge_ids, HfModel, HfTokenizerJson};
+use crate::base::integrity::IntegrityReport;
 #[cfg(feature = "python")]
-use crate::base::merge_job::MergeJob;
+use crate::base::merge_job::TieBreak;
+use crate::base::merge_job::{sorted_by_pair, MergeJob, TieKeys};
+use crate::base::normalizer::{NormalizedText, Normalizer, NormalizerStep};
+use crate::base::padding::EncodeOptions;
 #[cfg(feature = "python")]
-use crate::base::tokenizer_base::{count_pairs_parallel, TokenizerBase, GPT4_PATTERN};
+use crate::base::pre_tokenizer::{PreTokenizer, PreTokenizerPipeline};
+use crate::base::profile::{EncodeProfiler, Stage};
 #[cfg(feature = "python")]
-use crate::base::traits::{MergeBasedTokenizer, Tokenizer as TokenizerTrait};
+use crate::base::registry::NamedModel;
+use crate::base::remap::RemapManifest;
+use crate::base::resources::resolve_dict_file;
+use crate::base::snapshot::FrozenSnapshot;
+use crate::base::special_tokens::SpecialTokens;
+use crate::base::tokenizer_base::{
+    apply_ranked_merges, compile_pattern, count_pairs_parallel_with, piece_bytes,
+    piece_vocab_bytes, ranked_merges, restore_ranked_merges, with_id_scratch, MergeEntry, MergeMap,
+    PairCounts, TokenizerBase,
+};
 #[cfg(feature = "python")]
+use crate::base::tokenizer_base::{resolve_pattern, ParallelChunking, GPT4_PATTERN};
+use crate::base::trainer_config::TrainerConfig;
+use crate::base::traits::{
+    MergeBasedTokenizer, SpecialTokenizer, Tokenizer as TokenizerTrait, VocabBytes,
+};
 use crate::base::vocab_manager::VocabManager;
-#[cfg(feature = "python")]
 use crate::base::word::Word;
+#[cfg(feature = "python")]
+use crate::error::{input_validation_error, py_value_error};
+use crate::error::{load_error, model_load_error, model_save_error, TokenizerError};
+#[cfg(feature = "python")]
+use crate::generation::TextSampler;
 
 /// 词ID类型
 pub type WordId = u32;
 
-/// BPE分词器实现，参考template.rs并结合src/base基础组件
+/// 未知标记的文本
+pub const UNK_TOKEN: &str = "<unk>";
+
+/// 流式训练时Python迭代器中不是字符串的项的处理方式
+#[cfg(feature = "python")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InvalidItemPolicy {
+    /// 报错并停止训练
+    Fail,
+    /// 跳过并计数
+    Skip,
+    /// 调用 `str()` 转换
+    Coerce,
+}
+
+/// 流式训练时 `bytes` 项按UTF-8解码遇到无效字节的处理方式，与Python `bytes.decode` 的 `errors` 相同
+#[cfg(feature = "python")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Utf8Errors {
+    /// 报错并停止训练
+    Strict,
+    /// 替换为U+FFFD
+    Replace,
+    /// 丢弃无效字节
+    Ignore,
+}
+
 #[cfg(feature = "python")]
-#[pyclass]
+impl Utf8Errors {
+    fn parse(name: &str) -> Result<Self, TokenizerError> {
+        match name {
+            "strict" => Ok(Self::Strict),
+            "replace" => Ok(Self::Replace),
+            "ignore" => Ok(Self::Ignore),
+            other => Err(input_validation_error(format!(
+                "未知的解码错误处理方式: {}，可选 \"strict\"、\"replace\" 或 \"ignore\"",
+                other
+            ))),
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> Result<String, TokenizerError> {
+        match self {
+            Self::Strict => std::str::from_utf8(bytes).map(str::to_string).map_err(|e| {
+                TokenizerError::InvalidInput {
+                    message: format!(
+                        "bytes项不是有效的UTF-8，偏移 {} 处的字节无效",
+                        e.valid_up_to()
+                    ),
+                }
+            }),
+            Self::Replace => Ok(String::from_utf8_lossy(bytes).into_owned()),
+            Self::Ignore => Ok(bytes.utf8_chunks().map(|chunk| chunk.valid()).collect()),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl InvalidItemPolicy {
+    fn parse(name: &str) -> Result<Self, TokenizerError> {
+        match name {
+            "fail" => Ok(Self::Fail),
+            "skip" => Ok(Self::Skip),
+            "str" => Ok(Self::Coerce),
+            other => Err(input_validation_error(format!(
+                "未知的无效项处理方式: {}，可选 \"fail\"、\"skip\" 或 \"str\"",
+                other
+            ))),
+        }
+    }
+}
+
+/// 流式训练中被跳过和转换的项的统计
+#[cfg(feature = "python")]
+#[derive(Debug, Default)]
+struct InvalidItemSummary {
+    decoded: u64,
+    skipped: u64,
+    coerced: u64,
+    skipped_types: std::collections::BTreeMap<String, u64>,
+}
+
+#[cfg(feature = "python")]
+impl InvalidItemSummary {
+    /// 按处理方式把迭代器中的一项转换为文本，跳过时返回 `None`
+    fn extract(
+        &mut self,
+        obj: &Bound<'_, PyAny>,
+        policy: InvalidItemPolicy,
+        errors: Utf8Errors,
+    ) -> PyResult<Option<String>> {
+        if let Ok(s) = obj.extract::<String>() {
+            return Ok(Some(s));
+        }
+        let bytes = if let Ok(bytes) = obj.downcast::<pyo3::types::PyBytes>() {
+            Some(bytes.as_bytes().to_vec())
+        } else if let Ok(bytes) = obj.downcast::<pyo3::types::PyByteArray>() {
+            Some(bytes.to_vec())
+        } else {
+            None
+        };
+        if let Some(bytes) = bytes {
+            self.decoded += 1;
+            return errors.decode(&bytes).map(Some).map_err(Into::into);
+        }
+        let type_name = obj.get_type().name()?.to_string();
+        match policy {
+            InvalidItemPolicy::Fail => Err(crate::error::TokenizerError::InvalidInput {
+                message: format!("迭代器中的项不是字符串: {}", type_name),
+            }
+            .into()),
+            InvalidItemPolicy::Coerce if !obj.is_none() => {
+                self.coerced += 1;
+                Ok(Some(obj.str()?.to_string()))
+            }
+            _ => {
+                self.skipped += 1;
+                *self.skipped_types.entry(type_name).or_default() += 1;
+                Ok(None)
+            }
+        }
+    }
+
+    fn to_py_dict<'py>(
+        &self,
+        py: Python<'py>,
+        sequences: u64,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        let dict = pyo3::types::PyDict::new(py);
+        dict.set_item("sequences", sequences)?;
+        dict.set_item("decoded", self.decoded)?;
+        dict.set_item("skipped", self.skipped)?;
+        dict.set_item("coerced", self.coerced)?;
+        dict.set_item("skipped_types", &self.skipped_types)?;
+        Ok(dict)
+    }
+}
+
+/// 编码时遇到训练语料中没有出现过的字符的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownCharFallback {
+    /// 映射为 `<unk>` 标记，解码时无法还原原字符
+    Unk,
+    /// 拆成UTF-8字节，每个字节对应一个 `<0xNN>` 标记，解码时可以无损还原
+    #[default]
+    ByteFallback,
+}
+
+/// 解码时遇到词汇表之外的标记ID的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodeMode {
+    /// 返回 [`TokenizerError::UnknownTokenId`](crate::error::TokenizerError::UnknownTokenId)，
+    /// 指出第一个未知ID及其位置
+    #[default]
+    Strict,
+    /// 兼容旧模型：把未知ID当作Unicode码点解码，无效码点解码为替换字符
+    Lenient,
+}
+
+/// BPE的默认规范化流水线：只做NFC
+///
+/// 基础标记以字符码点作为ID，组合形式（如 `é`）和分解形式（`e` + U+0301）会得到不同的ID，
+/// 规范化后两者的编码结果相同
+fn default_normalizer() -> Normalizer {
+    Normalizer::new().with(NormalizerStep::Nfc)
+}
+
+/// 在流水线开头加入NFC，已以NFC开头时不变
+fn with_leading_nfc(normalizer: &Normalizer) -> Normalizer {
+    if normalizer.steps().first() == Some(&NormalizerStep::Nfc) {
+        return normalizer.clone();
+    }
+    std::iter::once(NormalizerStep::Nfc)
+        .chain(normalizer.steps().iter().copied())
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// 解析旧模型单独记录的 `normalization` 设置，返回是否做NFC
+fn parse_legacy_normalization(value: &str) -> Result<bool, TokenizerError> {
+    match value {
+        "nfc" => Ok(true),
+        "none" => Ok(false),
+        other => Err(model_load_error(format!(
+            "未知的规范化方式: {}，可选 \"nfc\" 或 \"none\"",
+            other
+        ))),
+    }
+}
+
+/// BPE分词器实现，参考template.rs并结合src/base基础组件
+#[cfg_attr(feature = "python", pyclass)]
+#[derive(Clone)]
 pub struct Tokenizer {
     /// 合并规则：(token_a, token_b) -> new_token_id
-    #[pyo3(get, set)]
-    pub merges: StdHashMap<(WordId, WordId), WordId>,
+    pub merges: MergeMap,
     /// 基础分词器，用于文本分割和基础功能
     pub base: TokenizerBase<u32>,
     /// 词汇表管理器（管理 ID <-> String 的双向映射）
     pub vocab: VocabManager<WordId, String>,
     /// 下一个可用的token ID
     pub next_token_id: WordId,
+    /// 训练事件观察者
+    pub observers: TrainObservers,
+    /// 编码性能分析
+    pub profiler: EncodeProfiler,
+    /// 异步编码使用的只读快照，通过Python接口修改分词器时作废
+    pub async_snapshot: FrozenSnapshot<Self>,
+    /// 未知字符的回退方式
+    pub unknown_fallback: UnknownCharFallback,
+    /// 解码时对未知标记ID的处理方式
+    pub decode_mode: DecodeMode,
 }
 
-#[cfg(feature = "python")]
 impl Tokenizer {
     /// 创建新的分词器
-    pub fn _new_internal() -> Result<Self, String> {
-        let base = TokenizerBase::new()?;
+    pub fn _new_internal() -> Result<Self, TokenizerError> {
+        let mut base = TokenizerBase::new()?;
+        base.normalizer = default_normalizer();
 
-        let tokenizer = Self {
-            merges: StdHashMap::new(),
+        let mut tokenizer = Self {
+            merges: MergeMap::new(),
             base,
             vocab: VocabManager::new(),
-            next_token_id: 0, // 从0开始，训练时动态分配
+            next_token_id: 0,
+            observers: TrainObservers::default(),
+            profiler: EncodeProfiler::default(),
+            async_snapshot: FrozenSnapshot::default(),
+            unknown_fallback: UnknownCharFallback::default(),
+            decode_mode: DecodeMode::default(),
         };
 
-        // vocab将在训练时按需初始化，无需预先分配所有Unicode字符
+        // 预置码点0-255，其余字符在训练时按需加入，无需预先分配所有Unicode字符
+        tokenizer._init_vocab();
         Ok(tokenizer)
     }
 
     /// 使用自定义正则表达式模式创建新的分词器
-    pub fn _with_pattern_internal(pattern: String) -> Result<Self, String> {
-        let base = TokenizerBase::with_pattern(pattern)?;
+    pub fn _with_pattern_internal(pattern: String) -> Result<Self, TokenizerError> {
+        let mut base = TokenizerBase::with_pattern(pattern)?;
+        base.normalizer = default_normalizer();
 
-        let tokenizer = Self {
-            merges: StdHashMap::new(),
+        let mut tokenizer = Self {
+            merges: MergeMap::new(),
             base,
             vocab: VocabManager::new(),
-            next_token_id: 0, // 从0开始，训练时动态分配
+            next_token_id: 0,
+            observers: TrainObservers::default(),
+            profiler: EncodeProfiler::default(),
+            async_snapshot: FrozenSnapshot::default(),
+            unknown_fallback: UnknownCharFallback::default(),
+            decode_mode: DecodeMode::default(),
         };
 
-        // vocab将在训练时按需初始化，无需预先分配所有Unicode字符
+        // 预置码点0-255，其余字符在训练时按需加入，无需预先分配所有Unicode字符
+        tokenizer._init_vocab();
         Ok(tokenizer)
     }
 
+    /// 初始化词汇表，添加码点0-255对应的字符
+    fn _init_vocab(&mut self) {
+        self.vocab.clear();
+        for code_point in 0..256u32 {
+            if let Some(ch) = char::from_u32(code_point) {
+                self.vocab.insert(code_point, ch.to_string());
+            }
+        }
+        self.next_token_id = 256;
+    }
+
+    /// 把词汇表重新编号为从0开始的连续ID，并同步改写合并规则
+    ///
+    /// 基础字符的ID取自码点，训练过汉字等字符后ID范围会非常稀疏。重新编号按原ID顺序进行，
+    /// 合并等级和编码结果的切分都不变，只是ID不同。返回的重映射清单可用于调整嵌入矩阵的行
+    pub fn compact_ids(&mut self) -> RemapManifest {
+        let mut old_ids: Vec<WordId> = self.vocab.ids().copied().collect();
+        old_ids.sort_unstable();
+        let id_map: std::collections::BTreeMap<WordId, WordId> =
+            old_ids.iter().copied().zip(0..).collect();
+
+        let mut vocab = VocabManager::with_capacity(old_ids.len());
+        for (old_id, &new_id) in &id_map {
+            if let Some(text) = self.vocab.get_by_id(old_id) {
+                vocab.insert(new_id, text.clone());
+            }
+        }
+        self.merges = self
+            .merges
+            .iter()
+            .filter_map(|(&(left, right), id)| {
+                Some((
+                    (*id_map.get(&left)?, *id_map.get(&right)?),
+                    *id_map.get(id)?,
+                ))
+            })
+            .collect();
+        self.vocab = vocab;
+        self.base
+            .special_tokens
+            .remap(|id| id_map.get(&id).copied());
+        self.next_token_id = old_ids.len() as WordId;
+
+        log::info!(
+            "重新编号完成: 最大ID {} -> {}",
+            old_ids.last().copied().unwrap_or(0),
+            self.next_token_id.saturating_sub(1)
+        );
+        RemapManifest::new(old_ids, 0..self.next_token_id, id_map)
+    }
+
+    /// 在新语料上继续训练，追加学习合并规则，已有标记的ID和合并规则保持不变
+    ///
+    /// 新语料先用现有合并规则切分，只在此基础上学习新的合并，新标记排在现有ID之后。
+    /// 语料中的新字符也会作为基础标记加入，并计入 `extra_tokens`。
+    ///
+    /// 返回的重映射清单中 `added` 为新增的标记，语料中可合并的配对不足时可能少于 `extra_tokens`
+    ///
+    /// # Errors
+    ///
+    /// 当语料预分词失败时返回错误
+    pub fn adapt<I, S>(
+        &mut self,
+        corpus: I,
+        extra_tokens: u32,
+    ) -> Result<RemapManifest, TokenizerError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        trace_span!("bpe.adapt", extra_tokens);
+        let before = self.vocab.len();
+        let old_ids: Vec<WordId> = self.vocab.ids().copied().collect();
+
+        let texts: Vec<String> = corpus.into_iter().map(|t| t.as_ref().to_string()).collect();
+        let texts = self.base.special_tokens.strip_texts(texts);
+        let mut pieces: StdHashMap<String, i32, ahash::RandomState> = StdHashMap::default();
+        for text in &texts {
+            for part in self.base.split_text(text)? {
+                if !part.is_empty() {
+                    *pieces.entry(part).or_default() += 1;
+                }
+            }
+        }
+
+        // 按文本排序，使新字符的ID分配与语料顺序无关
+        let mut pieces: Vec<(String, i32)> = pieces.into_iter().collect();
+        pieces.sort_unstable();
+
+        let mut words = Vec::with_capacity(pieces.len());
+        let mut counts = Vec::with_capacity(pieces.len());
+        for (piece, count) in pieces {
+            let mut ids: Vec<WordId> = piece
+                .chars()
+                .map(|ch| self.char_id_for_training(ch))
+                .collect();
+            apply_ranked_merges(&mut ids, &self.merges);
+            if ids.len() >= 2 {
+                words.push(Word::new(ids));
+                counts.push(count);
+            }
+        }
+
+        let target = u32::try_from(before)
+            .unwrap_or(u32::MAX)
+            .saturating_add(extra_tokens);
+        self._train_core_incremental(words, counts, target);
+
+        log::info!("继续训练完成，新增 {} 个标记", self.vocab.len() - before);
+        let mapping = old_ids.iter().map(|&id| (id, id)).collect();
+        Ok(RemapManifest::new(
+            old_ids,
+            self.vocab.ids().copied(),
+            mapping,
+        ))
+    }
+
+    /// 从头训练前清空合并规则并注册配置中的特殊标记，返回不小于256的目标词汇表大小
+    fn prepare_training(&mut self, vocab_size: u32) -> Result<u32, TokenizerError> {
+        self.merges.clear();
+        if self.vocab.is_empty() {
+            self._init_vocab();
+        }
+        // 配置中的特殊标记先于合并结果分配ID
+        self.base.trainer.clone().register_special_tokens(self)?;
+        Ok(vocab_size.max(256))
+    }
+
+    /// 直接从文本文件训练，支持目录、glob模式以及gzip和zstd压缩文件
+    ///
+    /// 文件按行流式读取，内存中只保留唯一片段的计数，不会把语料读成 `Vec<String>`。
+    /// 片段按文本排序后训练，结果与文件中行的顺序无关，`shuffle_seed` 不起作用
+    ///
+    /// # Errors
+    ///
+    /// 当文件无法读取或预分词失败时返回错误
+    #[cfg(feature = "files")]
+    pub fn train_from_files<P: AsRef<std::path::Path>>(
+        &mut self,
+        paths: &[P],
+        vocab_size: u32,
+        config: TrainerConfig,
+    ) -> Result<(), TokenizerError> {
+        trace_span!("bpe.train_from_files", files = paths.len(), vocab_size);
+        let previous = std::mem::replace(&mut self.base.trainer, config);
+        let result = self.prepare_training(vocab_size);
+        let result = result.and_then(|vocab_size| {
+            let base = &self.base;
+            let pieces = crate::corpus::files::count_chunks(paths, &base.special_tokens, |text| {
+                base.split_text(text)
+            })?;
+            let (words, counts) = self.training_words(&pieces);
+            self._train_core_incremental(words, counts, vocab_size);
+            log::info!("BPE训练完成，最终合并规则数: {}", self.merges.len());
+            Ok(())
+        });
+        self.base.trainer = previous;
+        result
+    }
+
+    /// 交叉检查词汇表、合并规则和特殊标记注册表，见 [`IntegrityReport`]
+    ///
+    /// 加载时会自动检查，直接修改 `vocab`、`merges` 等字段后可以再次调用
+    #[must_use]
+    pub fn check_integrity(&self) -> IntegrityReport {
+        let mut report = IntegrityReport::default();
+        report.check_vocab(&self.vocab);
+        report.check_merges(&self.vocab, &self.merges);
+        report.check_special_tokens(&self.base.special_tokens, &self.vocab);
+        report
+    }
+
+    /// 设置解码时对未知标记ID的处理方式
+    pub fn set_decode_mode(&mut self, mode: DecodeMode) {
+        self.decode_mode = mode;
+    }
+
+    /// 设置未知字符的回退方式，并把所需的回退标记加入词汇表
+    ///
+    /// 训练结束时会自动补齐回退标记，只有在训练后切换方式或加载旧模型时才需要手动调用
+    pub fn set_unknown_fallback(&mut self, fallback: UnknownCharFallback) {
+        self.unknown_fallback = fallback;
+        self.ensure_fallback_tokens();
+    }
+
+    /// 在词汇表末尾追加当前回退方式缺少的标记
+    ///
+    /// 训练时字符以码点作为ID，回退标记只在训练结束后追加，避免占用码点ID
+    fn ensure_fallback_tokens(&mut self) {
+        let missing: Vec<String> = match self.unknown_fallback {
+            UnknownCharFallback::Unk => vec![UNK_TOKEN.to_string()],
+            UnknownCharFallback::ByteFallback => {
+                (0..=255u8).map(|b| format!("<0x{:02X}>", b)).collect()
+            }
+        };
+        for token in missing {
+            if !self.vocab.contains_value(&token) {
+                self.vocab.insert(self.next_token_id, token);
+                self.next_token_id += 1;
+            }
+        }
+    }
+
+    /// 训练时字符对应的ID：已在词汇表中则复用，否则优先使用其码点
+    fn char_id_for_training(&mut self, ch: char) -> WordId {
+        let text = ch.to_string();
+        if let Some(&id) = self.vocab.get_by_value(&text) {
+            return id;
+        }
+        // 码点已被回退标记、合并标记占用或预留给特殊标记时改用下一个可用ID
+        let code_point = ch as u32;
+        let id = if self.vocab.contains_id(&code_point)
+            || self.base.special_tokens.is_reserved(code_point)
+        {
+            self.next_token_id
+        } else {
+            code_point
+        };
+        self.vocab.insert(id, text);
+        if id >= self.next_token_id {
+            self.next_token_id = id + 1;
+        }
+        id
+    }
+
+    /// 词汇表之外的字符按回退方式编码
+    fn encode_unknown_char(
+        &self,
+        ch: char,
+        ids: &mut Vec<WordId>,
+    ) -> Result<(), crate::error::TokenizerError> {
+        let missing = || crate::error::TokenizerError::EncodingError {
+            message: format!(
+                "字符 {:?} 不在词汇表中，且词汇表缺少回退标记，请先训练或调用 set_unknown_fallback",
+                ch
+            ),
+        };
+        match self.unknown_fallback {
+            UnknownCharFallback::Unk => {
+                let id = self.vocab.get_by_value(UNK_TOKEN).ok_or_else(missing)?;
+                ids.push(*id);
+            }
+            UnknownCharFallback::ByteFallback => {
+                let mut buf = [0u8; 4];
+                for byte in ch.encode_utf8(&mut buf).bytes() {
+                    let id = self
+                        .vocab
+                        .get_by_value(&format!("<0x{:02X}>", byte))
+                        .ok_or_else(missing)?;
+                    ids.push(*id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 注册训练事件观察者
+    pub fn add_train_observer(&mut self, observer: Arc<dyn TrainObserver>) {
+        self.observers.add(observer);
+    }
+
+    /// 转换为HuggingFace `tokenizers` 的BPE模型，标记使用原文
+    ///
+    /// # Errors
+    ///
+    /// 当合并规则引用的标记不在词汇表中时返回错误
+    pub fn to_hf_json(&self) -> Result<HfTokenizerJson, TokenizerError> {
+        let text = |id: WordId| {
+            self.vocab.get_by_id(&id).cloned().ok_or_else(|| {
+                model_save_error(format!("合并规则引用的标记ID {} 不在词汇表中", id))
+            })
+        };
+        let merges = ranked_merges(&self.merges)
+            .into_iter()
+            .map(|((a, b), _)| Ok((text(a)?, text(b)?)))
+            .collect::<Result<Vec<_>, TokenizerError>>()?;
+        let mut vocab: Vec<(String, u32)> = self
+            .vocab
+            .iter()
+            .map(|(&id, token)| (token.clone(), id))
+            .collect();
+        vocab.sort_unstable_by_key(|&(_, id)| id);
+
+        Ok(HfTokenizerJson {
+            model: HfModel::Bpe {
+                vocab,
+                merges,
+                unk_token: self
+                    .vocab
+                    .contains_value(&UNK_TOKEN.to_string())
+                    .then(|| UNK_TOKEN.to_string()),
+                byte_fallback: self.unknown_fallback == UnknownCharFallback::ByteFallback,
+            },
+            added_tokens: HfTokenizerJson::added_tokens_from(&self.base.special_tokens),
+            pattern: self.base.pattern.clone(),
+            pre_tokenizer: self.base.pre_tokenizer.clone(),
+            decoder: self.base.decoder,
+            byte_level: false,
+            normalizer: self.base.normalizer.clone(),
+        })
+    }
+
+    /// 加载HuggingFace `tokenizers` 的字符级BPE模型，替换当前的词汇表、合并规则、预分词模式和特殊标记
+    ///
+    /// 模型开启 `byte_fallback` 时使用 [`UnknownCharFallback::ByteFallback`]，否则使用
+    /// [`UnknownCharFallback::Unk`]
+    ///
+    /// # Errors
+    ///
+    /// 当模型不是BPE、使用 `ByteLevel` 预分词器（应使用BBPE分词器）、未知标记不是 `<unk>`，
+    /// 时返回错误
+    pub fn load_hf_json(&mut self, json: HfTokenizerJson) -> Result<(), TokenizerError> {
+        let HfModel::Bpe {
+            vocab,
+            merges,
+            unk_token,
+            byte_fallback,
+        } = &json.model
+        else {
+            return Err(model_load_error("BPE分词器只能加载BPE模型"));
+        };
+        if json.byte_level {
+            return Err(model_load_error(
+                "使用ByteLevel预分词器的模型请用BBPE分词器加载",
+            ));
+        }
+        if let Some(unk) = unk_token.as_deref().filter(|&unk| unk != UNK_TOKEN) {
+            return Err(model_load_error(format!(
+                "只支持 {} 作为未知标记，实际为 {}",
+                UNK_TOKEN, unk
+            )));
+        }
+        let special_tokens = json.special_tokens()?;
+
+        let mut id_map: StdHashMap<WordId, String> = vocab
+            .iter()
+            .map(|(token, id)| (*id, token.clone()))
+            .collect();
+        for token in &json.added_tokens {
+            id_map
+                .entry(token.id)
+                .or_insert_with(|| token.content.clone());
+        }
+        let new_merges = merge_ids(vocab, merges)?;
+
+        self.base.set_pattern(&json.pattern)?;
+        self.vocab = VocabManager::from_id_map(id_map);
+        self.merges = new_merges;
+        self.base.special_tokens = special_tokens;
+        self.unknown_fallback = if *byte_fallback {
+            UnknownCharFallback::ByteFallback
+        } else {
+            UnknownCharFallback::Unk
+        };
+        self.base.normalizer = json.normalizer.clone();
+        self.base.decoder = json.decoder;
+        self.base.pre_tokenizer = json.pre_tokenizer.clone();
+        self.next_token_id = self
+            .vocab
+            .ids()
+            .max()
+            .map_or(0, |&id| id + 1)
+            .max(self.base.special_tokens.end_id());
+        Ok(())
+    }
+
+    /// 保存为HuggingFace `tokenizers` 的 tokenizer.json，见 [`Tokenizer::to_hf_json`]
+    ///
+    /// # Errors
+    ///
+    /// 当转换或写入失败时返回错误
+    pub fn save_tokenizer_json(&self, path: &str) -> Result<(), TokenizerError> {
+        self.to_hf_json()?.save(path)
+    }
+
+    /// 从HuggingFace `tokenizers` 的 tokenizer.json 加载，见 [`Tokenizer::load_hf_json`]
+    ///
+    /// # Errors
+    ///
+    /// 当读取、解析或转换失败时返回错误
+    pub fn load_tokenizer_json(&mut self, path: &str) -> Result<(), TokenizerError> {
+        self.load_hf_json(HfTokenizerJson::load(path)?)
+    }
+
+    /// 以带版本头和校验和的二进制格式保存，见 [`crate::base::binary`]
+    ///
+    /// # Errors
+    ///
+    /// 当写入失败时返回错误
+    pub fn save_binary(&self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("bpe.save_binary", path);
+        let mut model = BinaryModel::new(ModelKind::Bpe);
+        self.base.write_binary(&mut model);
+        model.push(
+            binary::tag::VOCAB,
+            binary::write_vocab(self.vocab.iter().map(|(&id, text)| (id, text.as_bytes()))),
+        );
+        model.push(binary::tag::MERGES, binary::write_merges(&self.merges));
+        let fallback = match self.unknown_fallback {
+            UnknownCharFallback::Unk => "unk",
+            UnknownCharFallback::ByteFallback => "byte",
+        };
+        model.push(
+            binary::tag::SETTINGS,
+            binary::write_settings(&[
+                ("next_token_id", self.next_token_id.to_string()),
+                ("unknown_fallback", fallback.to_string()),
+            ]),
+        );
+        std::fs::write(path, model.to_bytes())
+            .map_err(|e| model_save_error(format!("写入模型文件失败: {}", e)))
+    }
+
+    /// 加载 [`Tokenizer::save_binary`] 写出的二进制模型，文件不是二进制格式时按文本格式加载
+    ///
+    /// # Errors
+    ///
+    /// 当读取失败、校验和不符、格式版本过高、模型类型不匹配或完整性检查发现问题时返回错误
+    pub fn load_binary(&mut self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("bpe.load_binary", path);
+        let data =
+            std::fs::read(path).map_err(|e| model_load_error(format!("打开文件失败: {}", e)))?;
+        if !binary::is_binary(&data) {
+            return TokenizerTrait::load(self, path);
+        }
+        let model = BinaryModel::from_bytes(&data, ModelKind::Bpe)?;
+
+        self.base.read_binary(&model)?;
+        let entries = binary::read_text_vocab(model.required(binary::tag::VOCAB)?)?;
+        let merges = binary::read_merges(model.required(binary::tag::MERGES)?)?;
+        let mut next_token_id = None;
+        let mut unknown_fallback = UnknownCharFallback::default();
+        let mut legacy_nfc = false;
+        if let Some(settings) = model.section(binary::tag::SETTINGS) {
+            for (key, value) in binary::read_settings(settings)? {
+                match key.as_str() {
+                    "next_token_id" => {
+                        next_token_id = Some(value.parse::<WordId>().map_err(|e| {
+                            model_load_error(format!("解析下一个token ID失败: {}", e))
+                        })?);
+                    }
+                    "unknown_fallback" => {
+                        unknown_fallback = match value.as_str() {
+                            "unk" => UnknownCharFallback::Unk,
+                            "byte" => UnknownCharFallback::ByteFallback,
+                            other => {
+                                return Err(model_load_error(format!("未知的回退方式: {}", other)))
+                            }
+                        };
+                    }
+                    "normalization" => legacy_nfc = parse_legacy_normalization(&value)?,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut report = IntegrityReport::default();
+        report.check_entries(&entries);
+        self.vocab.clear();
+        for (id, text) in entries {
+            self.vocab.insert(id, text);
+        }
+        self.merges = merges;
+        self.unknown_fallback = unknown_fallback;
+        if legacy_nfc {
+            self.base.normalizer = with_leading_nfc(&self.base.normalizer);
+        }
+        self.next_token_id = next_token_id.unwrap_or_else(|| {
+            self.vocab
+                .ids()
+                .max()
+                .map_or(0, |&id| id + 1)
+                .max(self.base.special_tokens.end_id())
+        });
+
+        report.extend(self.check_integrity());
+        report.into_result()
+    }
+
     /// 从常用汉字字表文件加载基础字符
     pub fn _load_base_chars(&mut self, file_path: &str) -> Result<(), std::io::Error> {
         use std::fs::File;
@@ -91,6 +811,8 @@ impl Tokenizer {
         for id in ids_to_remove {
             self.vocab.remove_by_id(&id);
         }
+        // 特殊标记随之清除，需要在加载之后重新注册
+        self.base.special_tokens.clear();
         self.next_token_id = 256;
 
         for line in reader.lines() {
@@ -106,14 +828,14 @@ impl Tokenizer {
         Ok(())
     }
 
-    /// 从dict目录加载初始化词表
-    pub fn _load_vocab_from_dict(&mut self, dict_file: &str) -> Result<(), String> {
+    /// 加载初始化词表，`dict_file` 为词表文件路径，文件不存在时在 `dict` 目录下查找
+    pub fn _load_vocab_from_dict(&mut self, dict_file: &str) -> Result<(), TokenizerError> {
         use std::fs::File;
         use std::io::{self, BufRead};
 
-        let dict_path = format!("dict/{}", dict_file);
+        let dict_path = resolve_dict_file(dict_file);
         let file = File::open(&dict_path)
-            .map_err(|e| format!("打开词表文件 {} 失败: {}", dict_path, e))?;
+            .map_err(|e| load_error(format!("打开词表文件 {} 失败: {}", dict_path.display(), e)))?;
         let reader = io::BufReader::new(file);
 
         // 清除现有词汇表中256以上的条目
@@ -123,10 +845,12 @@ impl Tokenizer {
         for id in ids_to_remove {
             self.vocab.remove_by_id(&id);
         }
+        // 特殊标记随之清除，需要在加载之后重新注册
+        self.base.special_tokens.clear();
         self.next_token_id = 256;
 
         for line in reader.lines() {
-            let line = line.map_err(|e| format!("读取行失败: {}", e))?;
+            let line = line.map_err(|e| load_error(format!("读取行失败: {}", e)))?;
             let token = line.trim();
             if token.is_empty() {
                 continue;
@@ -158,79 +882,118 @@ impl Tokenizer {
         ranks
     }
 
-    /// 应用合并规则到标记序列
-    pub fn _apply_merges(&mut self, tokens: &mut Vec<u32>) -> Result<(), String> {
-        // 创建Word并应用合并规则
-        let mut word = Word::new(tokens.clone());
-
-        // 持续应用合并规则，直到没有更多可能的合并
-        let mut changed = true;
-        while changed {
-            changed = false;
-
-            // 查找可以合并的词对
-            for (pair, &new_id) in &self.merges {
-                let mut i = 0;
-                while i < word.ids().len() - 1 {
-                    if word.ids()[i] == pair.0 && word.ids()[i + 1] == pair.1 {
-                        // 执行合并
-                        word.merge_pair(*pair, new_id, |a, b| a == b);
-                        changed = true;
-                        break;
-                    }
-                    i += 1;
+    /// 按训练顺序应用合并规则到标记序列
+    pub fn _apply_merges(&mut self, tokens: &mut Vec<u32>) -> Result<(), TokenizerError> {
+        apply_ranked_merges(tokens, &self.merges);
+        Ok(())
+    }
+
+    /// 把训练片段及其出现次数转换为字符ID序列，按训练配置准备字母表
+    ///
+    /// [`TrainerConfig::initial_alphabet`] 中的字符先加入词汇表。设置了
+    /// [`TrainerConfig::limit_alphabet`] 时，落选的字符不加入词汇表并在该处把片段断开，
+    /// 编码时按 [`UnknownCharFallback`] 处理
+    fn training_words<S: AsRef<str>>(
+        &mut self,
+        pieces: &[(S, i32)],
+    ) -> (Vec<Word<WordId>>, Vec<i32>) {
+        let config = self.base.trainer.clone();
+        for &ch in &config.initial_alphabet {
+            self.char_id_for_training(ch);
+        }
+
+        let excluded: AHashSet<char> = if config.limit_alphabet.is_some() {
+            let mut char_counts: StdHashMap<char, u64, ahash::RandomState> = StdHashMap::default();
+            for (piece, count) in pieces {
+                for ch in piece.as_ref().chars() {
+                    *char_counts.entry(ch).or_default() += *count as u64;
                 }
+            }
+            char_counts.retain(|ch, _| !self.vocab.contains_value(&ch.to_string()));
+            let alphabet = config.alphabet(&char_counts).unwrap_or_default();
+            char_counts
+                .into_keys()
+                .filter(|ch| !alphabet.contains(ch))
+                .collect()
+        } else {
+            AHashSet::new()
+        };
 
-                if changed {
-                    break;
+        let mut words = Vec::with_capacity(pieces.len());
+        let mut counts = Vec::with_capacity(pieces.len());
+        for (piece, count) in pieces {
+            let mut ids = Vec::new();
+            for ch in piece.as_ref().chars() {
+                if excluded.contains(&ch) {
+                    if !ids.is_empty() {
+                        words.push(Word::new(std::mem::take(&mut ids)));
+                        counts.push(*count);
+                    }
+                    continue;
                 }
+                ids.push(self.char_id_for_training(ch));
+            }
+            if !ids.is_empty() {
+                words.push(Word::new(ids));
+                counts.push(*count);
             }
         }
-
-        // 更新tokens为合并后的结果
-        *tokens = word.ids().to_vec();
-
-        Ok(())
+        (words, counts)
     }
 
-    /// 给定唯一词的核心增量BPE训练
-    fn _train_core_incremental(&mut self, mut words: Vec<Word<WordId>>, vocab_size: u32) {
-        // 确保词汇表大小不小于基础字符数量
-        let vocab_size = vocab_size.max(0x110000);
-        let num_merges = vocab_size - 0x110000;
-        log::info!("开始增量BPE训练: 需要计算 {} 次合并", num_merges);
-        self.merges.clear();
+    /// 给定唯一词及其出现次数的核心增量BPE训练
+    ///
+    /// 在现有合并规则之后追加，重新训练时由调用方先清空合并规则。
+    /// 计数低于 [`TrainerConfig::min_frequency`] 时停止合并，合并结果超过
+    /// [`TrainerConfig::max_piece_length`] 个字符的配对被跳过
+    fn _train_core_incremental(
+        &mut self,
+        mut words: Vec<Word<WordId>>,
+        counts: Vec<i32>,
+        vocab_size: u32,
+    ) {
+        let started = Instant::now();
+        let num_merges = vocab_size.saturating_sub(self.vocab.len() as u32);
+        self.observers.emit(TrainEvent::TrainStarted {
+            unique_sequences: words.len(),
+            target_vocab_size: vocab_size,
+            planned_merges: num_merges,
+        });
 
         // ---- 初始配对计数和更新位置（并行） ----
-        log::info!("从 {} 个唯一序列计算初始配对计数", words.len());
-        let counts: Vec<i32> = vec![1; words.len()]; // 每个词的初始计数为1
-        let (mut pair_counts, mut where_to_update) = count_pairs_parallel(&words, &counts);
+        let (mut pair_counts, mut where_to_update) =
+            count_pairs_parallel_with(&words, &counts, self.base.parallel);
+        self.observers.emit(TrainEvent::PairCountsDone {
+            unique_pairs: pair_counts.len(),
+        });
 
         // ---- 构建堆 ----
-        log::info!("使用 {} 个唯一配对构建堆", pair_counts.len());
+        // 按配对顺序入堆，计数和排序键都相同的任务出堆顺序与哈希表的迭代顺序无关
+        let mut tie_keys = TieKeys::new(self.base.tie_break, &words);
         let mut heap = OctonaryHeap::with_capacity(pair_counts.len());
-        for (pair, pos) in where_to_update.drain() {
+        for (pair, pos) in sorted_by_pair(where_to_update.drain()) {
             let c = *pair_counts.get(&pair).unwrap_or(&0);
             if c > 0 {
-                let mut merge_job = MergeJob::new(pair, c as u64);
+                let tie = tie_keys.key(pair, |id| self.vocab.get_by_id(&id).map(String::as_bytes));
+                let mut merge_job = MergeJob::new(pair, c as u64).with_tie(tie);
                 merge_job.add_positions(&pos);
                 heap.push(merge_job);
             }
         }
 
         // ---- 合并循环 ----
-        log::info!("开始合并循环");
         let mut merges_done = 0u32;
-        let mut last_log_percent = 0u32;
+        let mut stopped = false;
+        self.observers
+            .snapshot(merges_done, |k| self.pair_candidates(&pair_counts, k));
 
-        while merges_done < num_merges {
+        while (self.vocab.len() as u32) < vocab_size {
+            if self.observers.should_stop() {
+                stopped = true;
+                break;
+            }
+            // 如果没有更多的配对可以合并，停止训练
             let Some(top) = heap.pop() else {
-                // 如果没有更多的配对可以合并，停止训练
-                log::info!(
-                    "没有更多配对可合并，停止训练。已完成 {} 次合并，词汇表大小: {}",
-                    merges_done,
-                    self.vocab.len()
-                );
                 break;
             };
 
@@ -242,22 +1005,43 @@ impl Tokenizer {
             } else {
                 continue;
             }
+            // 堆顶计数最大，之后的配对都不会达到最低出现次数
+            if !self.base.trainer.keeps_frequency(top.count) {
+                break;
+            }
 
             // 执行合并
-            let new_id = self.next_token_id;
-            self.next_token_id += 1;
-            self.merges.insert(top.pair, new_id);
-
-            // 更新词汇表
-            if let (Some(a_text), Some(b_text)) = (
+            let (Some(a_text), Some(b_text)) = (
                 self.vocab.get_by_id(&top.pair.0),
                 self.vocab.get_by_id(&top.pair.1),
-            ) {
-                // 直接合并文本，不进行字节转换
-                let merged_text = format!("{}{}", a_text, b_text);
-                self.vocab.insert(new_id, merged_text.clone());
+            ) else {
+                continue;
+            };
+            // 直接合并文本，不进行字节转换
+            let merged_text = format!("{}{}", a_text, b_text);
+            if !self.base.trainer.allows_length(merged_text.chars().count()) {
+                continue;
             }
 
+            // 不同的合并路径可能得到相同的文本（如 "a"+"ab" 与 "aa"+"b"），
+            // 此时复用已有ID，保持词汇表双向映射一致
+            let new_id = match self.vocab.get_by_value(&merged_text) {
+                Some(&id) => id,
+                None => {
+                    let id = self.next_token_id;
+                    self.next_token_id += 1;
+                    self.vocab.insert(id, merged_text);
+                    id
+                }
+            };
+            self.merges.insert(top.pair, new_id);
+            self.observers.emit(TrainEvent::MergeApplied {
+                rank: merges_done,
+                pair: top.pair,
+                new_id,
+                count: top.count,
+            });
+
             // 更新受影响的词
             let mut updated_pairs: AHashMap<(WordId, WordId), i32> = AHashMap::new();
             let mut updated_where: AHashMap<(WordId, WordId), AHashSet<usize>> = AHashMap::new();
@@ -265,115 +1049,650 @@ impl Tokenizer {
             for &word_idx in &top.pos {
                 let deltas = words[word_idx].merge_pair(top.pair, new_id, |a, b| a == b);
                 for (pair, delta) in deltas {
-                    *updated_pairs.entry(pair).or_insert(0) += delta;
+                    *updated_pairs.entry(pair).or_insert(0) += delta * counts[word_idx];
                     updated_where.entry(pair).or_default().insert(word_idx);
                 }
             }
 
             // 更新全局计数
-            for (pair, delta) in updated_pairs {
+            for (pair, delta) in sorted_by_pair(updated_pairs) {
                 let entry = pair_counts.entry(pair).or_insert(0);
                 *entry += delta;
 
                 if *entry <= 0 {
                     pair_counts.remove(&pair);
                 } else if let Some(pos_set) = updated_where.get(&pair) {
-                    let mut merge_job = MergeJob::new(pair, *entry as u64);
+                    let tie =
+                        tie_keys.key(pair, |id| self.vocab.get_by_id(&id).map(String::as_bytes));
+                    let mut merge_job = MergeJob::new(pair, *entry as u64).with_tie(tie);
                     merge_job.add_positions(&pos_set.iter().cloned().collect::<Vec<_>>());
                     heap.push(merge_job);
                 }
             }
+            tie_keys.next_step();
 
             merges_done += 1;
+            self.observers
+                .snapshot(merges_done, |k| self.pair_candidates(&pair_counts, k));
+        }
+
+        self.ensure_fallback_tokens();
+        self.observers.emit(TrainEvent::Finished {
+            stats: TrainStats {
+                merges: merges_done,
+                vocab_size: self.vocab.len(),
+                elapsed: started.elapsed(),
+                stopped,
+            },
+        });
+    }
+
+    /// 计数最高的 `k` 个候选配对及其合并后的文本
+    fn pair_candidates(&self, pair_counts: &PairCounts<WordId>, k: usize) -> Vec<PairCandidate> {
+        top_pairs(pair_counts, k)
+            .into_iter()
+            .map(|(pair, count)| {
+                let text = |id| self.vocab.get_by_id(&id).map_or("", String::as_str);
+                PairCandidate {
+                    pair,
+                    count,
+                    piece: format!("{}{}", text(pair.0), text(pair.1)),
+                }
+            })
+            .collect()
+    }
+
+    /// 使用rayon并行编码一批文本，空值编码为空列表
+    fn _encode_batch_internal(
+        &self,
+        texts: &[Option<&str>],
+    ) -> Result<Vec<Vec<u32>>, crate::error::TokenizerError> {
+        texts
+            .par_iter()
+            .map(|text| text.map_or_else(|| Ok(Vec::new()), |text| self._encode_internal(text)))
+            .collect::<Result<_, _>>()
+            .map_err(|e| crate::error::TokenizerError::EncodingError {
+                message: e.to_string(),
+            })
+    }
+
+    /// 本次编码临时改用另一种预分词方式，不修改分词器，用于在同一组合并规则上对比切分策略
+    ///
+    /// `pattern` 为 [`PATTERN_PRESETS`](crate::base::tokenizer_base::PATTERN_PRESETS) 中的名称或正则表达式。
+    /// 需要反复编码时先用 [`compile_pattern`] 编译，再调用 [`Tokenizer::encode_with_regex`]。
+    ///
+    /// # Errors
+    ///
+    /// 当正则表达式无效或编码失败时返回错误
+    pub fn encode_with_pattern(
+        &self,
+        text: &str,
+        pattern: &str,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        self.encode_with_regex(text, &compile_pattern(pattern)?)
+    }
+
+    /// 以已编译的正则表达式代替预分词流水线中的正则表达式步骤并编码，不修改分词器
+    ///
+    /// # Errors
+    ///
+    /// 当正则表达式匹配失败或编码失败时返回错误
+    pub fn encode_with_regex(
+        &self,
+        text: &str,
+        pattern: &Regex,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        self.encode_split(text, pattern)
+    }
+
+    /// 内部编码实现，特殊标记整体匹配，其余各段规范化后分别编码
+    fn _encode_internal(&self, text: &str) -> Result<Vec<u32>, crate::error::TokenizerError> {
+        self.encode_split(text, &self.base.compiled_pattern)
+    }
 
-            // 每10%记录一次进度
-            let percent = merges_done * 100 / num_merges;
-            if percent > last_log_percent {
-                log::info!("训练进度: {}% ({} 次合并)", percent, merges_done);
-                last_log_percent = percent;
+    /// 以 `pattern` 作为预分词流水线中正则表达式步骤的编码实现
+    fn encode_split(
+        &self,
+        text: &str,
+        pattern: &Regex,
+    ) -> Result<Vec<u32>, crate::error::TokenizerError> {
+        trace_span!(debug: "bpe.encode", text_len = text.len());
+        let limits = &self.base.encode_options.limits;
+        let text = limits.check_input(text)?;
+        let mut result = self
+            .base
+            .special_tokens
+            .encode_with(text, |segment| self.encode_segment(segment, pattern))?;
+        limits.check_tokens(&mut result)?;
+        self.profiler.add_texts(1);
+        Ok(result)
+    }
+
+    /// 编码不含特殊标记的一段文本
+    fn encode_segment(
+        &self,
+        text: &str,
+        pattern: &Regex,
+    ) -> Result<Vec<u32>, crate::error::TokenizerError> {
+        let profiler = &self.profiler;
+        let text = self.base.normalizer.apply(text);
+        let text = text.as_ref();
+        let mut result = Vec::new();
+        let pre_tokenizer = self.base.pre_tokenizer.with_pattern(pattern);
+        profiler.pre_tokenize(&pre_tokenizer, text, &mut |piece| {
+            self.encode_piece(piece, &mut result)
+        })?;
+
+        Ok(result)
+    }
+
+    /// 编码一个预分词片段，结果追加到 `result`
+    fn encode_piece(
+        &self,
+        piece: &str,
+        result: &mut Vec<u32>,
+    ) -> Result<(), crate::error::TokenizerError> {
+        let profiler = &self.profiler;
+        // 首先尝试直接匹配整个片段 - O(1)查找
+        let whole = profiler.time(Stage::VocabLookup, || {
+            self.vocab.get_by_value(piece).copied()
+        });
+        if let Some(id) = whole {
+            result.push(id);
+            return Ok(());
+        }
+
+        // 将文本转换为字符序列，词汇表之外的字符按回退方式编码；缓冲区在片段之间复用
+        with_id_scratch(|ids| {
+            profiler.time(Stage::VocabLookup, || {
+                let mut buf = [0u8; 4];
+                for ch in piece.chars() {
+                    match self.vocab.get_by_value(ch.encode_utf8(&mut buf) as &str) {
+                        Some(&id) => ids.push(id),
+                        None => self.encode_unknown_char(ch, ids)?,
+                    }
+                }
+                Ok::<_, crate::error::TokenizerError>(())
+            })?;
+
+            // 按训练顺序应用合并规则
+            profiler.time(Stage::Merge, || apply_ranked_merges(ids, &self.merges));
+
+            result.extend_from_slice(ids);
+            Ok(())
+        })
+    }
+
+    /// 单个标记解码后的字节，字节回退标记 `<0xNN>` 还原为单个字节
+    ///
+    /// 词汇表之外的ID按Unicode码点解码，无效码点解码为替换字符，严格模式在调用前已拒绝这类ID
+    fn token_bytes_internal(&self, id: WordId) -> Cow<'_, [u8]> {
+        match self.vocab.get_by_id(&id) {
+            Some(text) => piece_bytes(text),
+            None => Cow::Owned(char::from_u32(id).unwrap_or('�').to_string().into_bytes()),
+        }
+    }
+
+    /// 交给解码器的单个标记，词汇表之外的ID按码点解码，不再按解码器的约定还原
+    fn decoded_piece_internal(&self, id: WordId) -> DecodedPiece<'_> {
+        self.vocab.get_by_id(&id).map_or_else(
+            || DecodedPiece::raw(self.token_bytes_internal(id)),
+            |text| DecodedPiece::from_vocab(text),
+        )
+    }
+
+    /// 内部解码实现
+    fn decode_internal(&self, tokens: Vec<u32>) -> Result<String, crate::error::TokenizerError> {
+        if self.decode_mode == DecodeMode::Strict {
+            if let Some((index, &id)) = tokens
+                .iter()
+                .enumerate()
+                .find(|(_, id)| !self.vocab.contains_id(id))
+            {
+                return Err(crate::error::TokenizerError::UnknownTokenId { id, index });
             }
         }
+        if self.base.decoder != DecoderKind::Fuse {
+            return decode_ids(&self.base.decoder, &tokens, |id| {
+                Some(self.decoded_piece_internal(id))
+            });
+        }
+        let pieces: Vec<Cow<'_, [u8]>> = tokens
+            .iter()
+            .map(|&id| self.token_bytes_internal(id))
+            .collect();
+        // 按标记字节长度之和预分配，避免逐个追加时反复扩容
+        let mut bytes = Vec::with_capacity(pieces.iter().map(|piece| piece.len()).sum());
+        for piece in &pieces {
+            bytes.extend_from_slice(piece);
+        }
 
-        log::info!(
-            "训练完成，词汇表大小: {}, next_token_id: {}",
-            self.vocab.len(),
-            self.next_token_id
-        );
+        String::from_utf8(bytes).map_err(|e| {
+            crate::error::invalid_utf8_error(&e.utf8_error(), pieces.iter().map(|p| p.len()))
+        })
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Tokenizer {
+    /// 创建新的BPE分词器
+    #[new]
+    pub fn new() -> PyResult<Self> {
+        Self::_new_internal().map_err(py_value_error)
+    }
+
+    /// 使用自定义正则表达式模式（或预设名称，如 `"code"`）创建新的BPE分词器
+    #[staticmethod]
+    pub fn with_pattern(pattern: String) -> PyResult<Self> {
+        let mut tokenizer = Self::_new_internal().map_err(py_value_error)?;
+        tokenizer.base.compiled_pattern = compile_pattern(&pattern).map_err(py_value_error)?;
+        tokenizer.base.pattern = resolve_pattern(&pattern).to_string();
+        Ok(tokenizer)
+    }
+
+    /// 合并规则：(token_a, token_b) -> new_token_id
+    #[getter(merges)]
+    pub fn py_merges(&self) -> MergeMap {
+        self.merges.clone()
+    }
+
+    /// 替换合并规则
+    #[setter(merges)]
+    pub fn py_set_merges(&mut self, merges: MergeMap) {
+        self.async_snapshot.invalidate();
+        self.merges = merges;
+    }
+
+    /// 编码文本为token IDs
+    #[pyo3(name = "encode")]
+    pub fn py_encode(&self, text: &str) -> PyResult<Vec<u32>> {
+        // 实际的编码实现
+        self._encode_internal(text).map_err(|e| {
+            crate::error::TokenizerError::EncodingError {
+                message: e.to_string(),
+            }
+            .into()
+        })
+    }
+
+    /// 本次编码临时改用另一种预分词方式（预设名称或正则表达式），不修改分词器
+    #[pyo3(name = "encode_with_pattern")]
+    pub fn py_encode_with_pattern(&self, text: &str, pattern: &str) -> PyResult<Vec<u32>> {
+        self.encode_with_pattern(text, pattern).map_err(Into::into)
+    }
+
+    /// 解码token IDs为文本，`skip_special_tokens` 为真时跳过特殊标记
+    #[pyo3(name = "decode", signature = (tokens, skip_special_tokens = false))]
+    pub fn py_decode(&self, tokens: Vec<u32>, skip_special_tokens: bool) -> PyResult<String> {
+        self.decode_with(&tokens, skip_special_tokens)
+            .map_err(PyErr::from)
+    }
+
+    /// 批量编码文本为token IDs（并行处理）
+    ///
+    /// `texts` 可以是字符串列表，也可以是pyarrow的字符串数组、ChunkedArray、RecordBatch或Table
+    /// （多列时通过 `column` 指定列名）。Arrow输入直接从缓冲区读取字符串，空值编码为空列表
+    #[pyo3(signature = (texts, column = None))]
+    pub fn encode_batch<'py>(
+        &self,
+        texts: &Bound<'py, PyAny>,
+        column: Option<&str>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let py = texts.py();
+        let profiler = &self.profiler;
+        let start = profiler.start_batch();
+
+        let input = profiler.time(Stage::PythonConversion, || {
+            crate::base::py_arrow::BatchInput::extract(texts, column)
+        })?;
+        let texts = input.texts();
+
+        let ids = py.allow_threads(|| self._encode_batch_internal(&texts))?;
+        let result = profiler.time(Stage::PythonConversion, || ids.into_pyobject(py))?;
+
+        profiler.finish_batch(start);
+        Ok(result)
+    }
+
+    /// 异步批量编码，返回可在asyncio中await的future
+    ///
+    /// 编码在共享的后台线程池中并行执行并释放GIL，不会阻塞事件循环。编码使用调用时的分词器快照，
+    /// 之后的训练或加载不影响结果；快照在分词器被修改后的第一次调用时复制，其余调用共用同一份
+    pub fn encode_batch_async<'py>(
+        slf: &Bound<'py, Self>,
+        texts: Vec<String>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        crate::base::py_future::spawn_with_future(
+            slf,
+            |this| {
+                // 快照在调用线程上复制，耗时统计仍计入原分词器
+                this.async_snapshot.get_or_freeze(|| {
+                    let mut snapshot = this.clone();
+                    snapshot.profiler = this.profiler.share();
+                    snapshot
+                })
+            },
+            move |this| {
+                let start = this.profiler.start_batch();
+                let texts: Vec<Option<&str>> =
+                    texts.iter().map(|text| Some(text.as_str())).collect();
+                let result = this._encode_batch_internal(&texts).map_err(Into::into);
+                this.profiler.finish_batch(start);
+                result
+            },
+        )
+    }
+
+    /// 开启或关闭分阶段的编码耗时统计
+    #[pyo3(signature = (enabled = true))]
+    pub fn enable_profiling(&self, enabled: bool) {
+        self.profiler.set_enabled(enabled);
+    }
+
+    /// 获取最近一次批量编码的分阶段耗时（纳秒）
+    ///
+    /// 返回的字典包含 texts、pre_tokenize_ns、vocab_lookup_ns、merge_ns、python_conversion_ns 和 total_ns
+    pub fn get_profile<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        self.profiler.snapshot().to_py_dict(py)
+    }
+
+    /// 解码结果以 `prefix` 开头的全部标记ID，用于logit偏置
+    #[pyo3(name = "ids_with_prefix")]
+    pub fn py_ids_with_prefix(&self, prefix: &str) -> Vec<u32> {
+        VocabBytes::ids_with_prefix(self, prefix)
+    }
+
+    /// 忽略开头的空白后解码结果恰好为 `text` 的全部标记ID，用于禁用词列表
+    #[pyo3(name = "token_ids_covering")]
+    pub fn py_token_ids_covering(&self, text: &str) -> Vec<u32> {
+        VocabBytes::token_ids_covering(self, text)
+    }
+
+    /// 注册特殊标记，返回每个标记的ID
+    #[pyo3(name = "add_special_tokens")]
+    pub fn py_add_special_tokens(&mut self, tokens: Vec<String>) -> PyResult<Vec<u32>> {
+        self.async_snapshot.invalidate();
+        let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+        SpecialTokenizer::add_special_tokens(self, &tokens).map_err(PyErr::from)
+    }
+
+    /// 在词汇表末尾预留 `count` 个特殊标记ID，返回 `(start, end)` 区间
+    #[pyo3(name = "reserve_special_ids")]
+    pub fn py_reserve_special_ids(&mut self, count: u32) -> PyResult<(u32, u32)> {
+        self.async_snapshot.invalidate();
+        self.reserve_special_ids(count)
+            .map(|range| (range.start, range.end))
+            .map_err(PyErr::from)
+    }
+
+    /// 已注册的特殊标记，`{标记: ID}`
+    #[getter(special_tokens)]
+    pub fn py_special_tokens(&self) -> StdHashMap<String, u32> {
+        self.base
+            .special_tokens
+            .iter()
+            .map(|(id, token)| (token.to_string(), id))
+            .collect()
+    }
+
+    /// 将单个标记渲染为适合日志和界面显示的字符串，控制字符转义、首尾空格显示为 ␣
+    #[pyo3(name = "render_token")]
+    pub fn py_render_token(&self, id: u32) -> String {
+        VocabBytes::render_token(self, &id)
+    }
+
+    /// 从词汇表中随机抽取 `n_tokens` 个标记并解码为文本，用于压测和往返测试
+    ///
+    /// `counts` 为 `[(ID, 次数)]` 时按次数加权抽取，否则等概率抽取；传入 `seed` 时结果可复现
+    #[pyo3(name = "sample_text", signature = (n_tokens, seed = None, counts = None))]
+    pub fn py_sample_text(
+        &self,
+        n_tokens: usize,
+        seed: Option<u64>,
+        counts: Option<Vec<(u32, u64)>>,
+    ) -> PyResult<String> {
+        let sampler = match counts {
+            Some(counts) => TextSampler::with_counts(self, &counts, seed),
+            None => TextSampler::new(self, seed),
+        };
+        sampler
+            .and_then(|mut sampler| sampler.sample_text(self, n_tokens))
+            .map_err(py_value_error)
+    }
+
+    /// 编码文本并返回每个标记的区间 `(start, end, id)`，用于可视化标记边界
+    ///
+    /// 区间默认为UTF-8字节偏移，`offset_unit="char"` 时为字符偏移
+    #[pyo3(name = "spans", signature = (text, offset_unit = "byte"))]
+    pub fn py_spans(&self, text: &str, offset_unit: &str) -> PyResult<Vec<(usize, usize, u32)>> {
+        crate::base::py_numpy::spans(self, text, offset_unit)
+    }
+
+    /// 编码文本并返回 `ids`、`tokens`、`offsets` 组成的字典，用于把标注对齐回原文
+    ///
+    /// 区间默认为UTF-8字节偏移，`offset_unit="char"` 时为字符偏移
+    #[pyo3(name = "encode_with_offsets", signature = (text, offset_unit = "byte"))]
+    pub fn py_encode_with_offsets<'py>(
+        &self,
+        py: Python<'py>,
+        text: &str,
+        offset_unit: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_with_offsets(py, self, text, offset_unit)
+    }
+
+    /// 编码句子对为 `[CLS] A [SEP] B [SEP]`，返回 `input_ids`、`token_type_ids` 组成的字典
+    ///
+    /// 分类和分隔标记需要已注册为特殊标记，可用 `cls_token`、`sep_token` 改用其他名称
+    #[cfg(feature = "python")]
+    #[pyo3(name = "encode_pair", signature = (text_a, text_b, cls_token = "[CLS]", sep_token = "[SEP]"))]
+    pub fn py_encode_pair<'py>(
+        &self,
+        py: Python<'py>,
+        text_a: &str,
+        text_b: &str,
+        cls_token: &str,
+        sep_token: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_pair(py, self, text_a, text_b, cls_token, sep_token)
+    }
+
+    /// 启用截断，`encode_plus`、`encode_batch_plus` 每行最多保留 `max_length` 个标记
+    ///
+    /// `strategy` 为 `"longest_first"`（默认）、`"only_first"` 或 `"only_second"`，只对句子对有效；
+    /// 指定 `stride` 时超出的部分切成相邻重叠 `stride` 个标记的溢出块，每块单独成行
+    #[cfg(feature = "python")]
+    #[pyo3(name = "enable_truncation", signature = (max_length, strategy = "longest_first", stride = None))]
+    pub fn py_enable_truncation(
+        &mut self,
+        max_length: usize,
+        strategy: &str,
+        stride: Option<usize>,
+    ) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        let config = crate::base::py_numpy::truncation_config(max_length, strategy, stride)?;
+        self.encode_options_mut().truncation = Some(config);
+        Ok(())
+    }
+
+    /// 关闭截断
+    #[cfg(feature = "python")]
+    #[pyo3(name = "no_truncation")]
+    pub fn py_no_truncation(&mut self) {
+        self.async_snapshot.invalidate();
+        self.no_truncation();
+    }
+
+    /// 启用补齐，`encode_plus`、`encode_batch_plus` 用 `pad_id` 把各行补齐到 `length`（默认为最长的行）
+    ///
+    /// `direction` 为 `"right"`（默认）或 `"left"`；指定 `pad_to_multiple_of` 时行宽向上取整到其倍数
+    #[cfg(feature = "python")]
+    #[pyo3(name = "enable_padding", signature = (pad_id = 0, direction = "right", length = None, pad_to_multiple_of = None))]
+    pub fn py_enable_padding(
+        &mut self,
+        pad_id: u32,
+        direction: &str,
+        length: Option<usize>,
+        pad_to_multiple_of: Option<usize>,
+    ) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        let config =
+            crate::base::py_numpy::padding_config(direction, pad_id, length, pad_to_multiple_of)?;
+        self.encode_options_mut().padding = Some(config);
+        Ok(())
+    }
+
+    /// 关闭补齐
+    #[cfg(feature = "python")]
+    #[pyo3(name = "no_padding")]
+    pub fn py_no_padding(&mut self) {
+        self.async_snapshot.invalidate();
+        self.no_padding();
+    }
+
+    /// 设置编码输入的长度限制，参数都为 `None` 时不限制
+    ///
+    /// 输入超过 `max_input_bytes` 字节或结果超过 `max_tokens` 个标记时，`policy="error"`（默认）报错，
+    /// `"truncate"` 截断输入或结果；对 `encode` 及所有基于它的接口生效
+    #[cfg(feature = "python")]
+    #[pyo3(name = "set_input_limits", signature = (max_input_bytes = None, max_tokens = None, policy = "error"))]
+    pub fn py_set_input_limits(
+        &mut self,
+        max_input_bytes: Option<usize>,
+        max_tokens: Option<usize>,
+        policy: &str,
+    ) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.encode_options_mut().limits =
+            crate::base::py_numpy::input_limits(max_input_bytes, max_tokens, policy)?;
+        Ok(())
     }
-}
 
-#[cfg(feature = "python")]
-#[pymethods]
-impl Tokenizer {
-    /// 创建新的BPE分词器
-    #[new]
-    pub fn new() -> PyResult<Self> {
-        Self::_new_internal().map_err(PyValueError::new_err)
+    /// 按截断与补齐配置编码单条文本或句子对，返回 `input_ids`、`attention_mask`、`token_type_ids`
+    /// 和 `overflow_to_sample_mapping` 组成的字典，每项按行给出（溢出块各占一行）
+    ///
+    /// 句子对拼接为 `[CLS] A [SEP] B [SEP]`；`return_tensors="np"` 时返回NumPy数组
+    #[cfg(feature = "python")]
+    #[pyo3(name = "encode_plus", signature = (text, pair = None, return_tensors = None))]
+    pub fn py_encode_plus<'py>(
+        &self,
+        py: Python<'py>,
+        text: String,
+        pair: Option<String>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_plus(
+            py,
+            self,
+            vec![text],
+            pair.map(|pair| vec![pair]),
+            return_tensors,
+        )
     }
 
-    /// 使用自定义正则表达式模式创建新的BPE分词器
-    #[staticmethod]
-    pub fn with_pattern(pattern: String) -> PyResult<Self> {
-        let mut tokenizer = Self::_new_internal().map_err(PyValueError::new_err)?;
-        tokenizer.base.pattern = pattern.clone();
-        tokenizer.base.compiled_pattern = fancy_regex::Regex::new(&pattern)
-            .map_err(|e| PyValueError::new_err(format!("无效的正则表达式: {}", e)))?;
-        Ok(tokenizer)
+    /// 按截断与补齐配置并行编码一批文本（及对应的第二句 `pairs`），返回格式同 `encode_plus`
+    #[cfg(feature = "python")]
+    #[pyo3(name = "encode_batch_plus", signature = (texts, pairs = None, return_tensors = None))]
+    pub fn py_encode_batch_plus<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        pairs: Option<Vec<String>>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_plus(py, self, texts, pairs, return_tensors)
     }
 
-    /// 编码文本为token IDs
-    pub fn encode(&self, text: &str) -> PyResult<Vec<u32>> {
-        // 实际的编码实现
-        self._encode_internal(text).map_err(|e| {
-            crate::error::TokenizerError::EncodingError {
-                message: e.to_string(),
-            }
-            .into()
-        })
+    /// 交叉检查词汇表、合并规则和特殊标记注册表，返回发现的问题列表，每项为包含 `kind`、`message` 的字典
+    #[cfg(feature = "python")]
+    #[pyo3(name = "check_integrity")]
+    pub fn py_check_integrity<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Vec<Bound<'py, pyo3::types::PyDict>>> {
+        self.check_integrity().to_py_list(py)
     }
 
-    /// 解码token IDs为文本
-    pub fn decode(&self, tokens: Vec<u32>) -> PyResult<String> {
-        // 实际的解码实现
-        self.decode_internal(tokens)
-            .map_err(|e| PyValueError::new_err(e.to_string()))
+    /// 批量编码并返回连续存储的 `input_ids` 和每行的 `lengths`、`offsets`，可直接复制到张量
+    ///
+    /// 每行最多保留 `max_len` 个标记；指定 `pad_id` 时补齐为二维并返回 `attention_mask`，
+    /// `return_tensors="np"` 时返回NumPy数组
+    #[pyo3(name = "encode_batch_packed", signature = (texts, max_len = None, pad_id = None, return_tensors = None))]
+    pub fn py_encode_batch_packed<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        max_len: Option<usize>,
+        pad_id: Option<u32>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_packed(py, self, texts, max_len, pad_id, return_tensors)
     }
 
-    /// 批量编码文本为token IDs（并行处理）
-    pub fn encode_batch(&self, texts: Vec<String>) -> PyResult<Vec<Vec<u32>>> {
-        use rayon::prelude::*;
+    /// 批量编码并返回补齐后的 `input_ids`、`offsets`、`attention_mask`
+    ///
+    /// `return_tensors="np"` 时返回NumPy数组，避免为大批量创建大量小元组；
+    /// `offset_unit="char"` 时偏移为字符区间
+    #[pyo3(signature = (texts, return_tensors = None, offset_unit = "byte"))]
+    pub fn encode_batch_with_offsets<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        return_tensors: Option<&str>,
+        offset_unit: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_with_offsets(
+            py,
+            self,
+            texts,
+            return_tensors,
+            offset_unit,
+        )
+    }
 
-        // 使用rayon并行处理所有文本
-        let results: Result<Vec<Vec<u32>>, _> = texts
-            .par_iter()
-            .map(|text| {
-                self._encode_internal(text).map_err(|e| {
-                    crate::error::TokenizerError::EncodingError {
-                        message: e.to_string(),
-                    }
-                })
-            })
-            .collect();
+    /// 编码文本并返回标记ID序列与平台无关的64位哈希，用于判断缓存的编码结果是否过期
+    #[pyo3(name = "content_hash")]
+    pub fn py_content_hash(&self, text: &str) -> PyResult<u64> {
+        Tokenizer::content_hash(self, text).map_err(PyErr::from)
+    }
 
-        results.map_err(|e| e.into())
+    /// 编码文本并标出每个标记是否开始一个新词，用于整词掩码
+    #[pyo3(name = "word_starts")]
+    pub fn py_word_starts(&self, text: &str) -> PyResult<Vec<bool>> {
+        VocabBytes::word_starts(self, text).map_err(PyErr::from)
     }
 
     /// 批量解码token IDs为文本（并行处理）
-    pub fn decode_batch(&self, token_lists: Vec<Vec<u32>>) -> PyResult<Vec<String>> {
+    ///
+    /// `token_lists` 可以是嵌套列表，也可以是二维整数NumPy数组或PyTorch CPU张量，
+    /// 给出 `attention_mask` 时只解码掩码非0位置的标记
+    #[pyo3(signature = (token_lists, attention_mask = None))]
+    pub fn decode_batch(
+        &self,
+        py: Python<'_>,
+        token_lists: &Bound<'_, PyAny>,
+        attention_mask: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Vec<String>> {
         use rayon::prelude::*;
 
+        let token_lists = crate::base::py_numpy::extract_id_batch(token_lists, attention_mask)?;
         // 使用rayon并行处理所有token列表
-        let results: Result<Vec<String>, _> = token_lists
-            .par_iter()
-            .map(|tokens| self.decode_internal(tokens.clone()))
-            .collect();
+        let results: Result<Vec<String>, _> = py.allow_threads(|| {
+            token_lists
+                .par_iter()
+                .map(|tokens| self.decode_internal(tokens.clone()))
+                .collect()
+        });
 
         results.map_err(|e| PyValueError::new_err(e.to_string()))
     }
 
     /// 训练分词器
-    pub fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> PyResult<()> {
-        TokenizerTrait::train(self, texts, vocab_size).map_err(PyValueError::new_err)
+    #[pyo3(name = "train")]
+    pub fn py_train(&mut self, texts: Vec<String>, vocab_size: u32) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        TokenizerTrait::train(self, texts, vocab_size).map_err(PyErr::from)
     }
 
     /// 获取词汇表大小
@@ -387,6 +1706,230 @@ impl Tokenizer {
         self.vocab.iter().map(|(&k, v)| (k, v.clone())).collect()
     }
 
+    /// 检查语料中的字符能否由基础词汇表表示，返回覆盖统计以及只能拆成字节或编码为未知标记的字符
+    #[pyo3(name = "coverage")]
+    pub fn py_coverage<'py>(
+        &self,
+        py: Python<'py>,
+        corpus: Vec<String>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        py.allow_threads(|| crate::analysis::coverage(self, &corpus))
+            .map_err(py_value_error)?
+            .to_py_dict(py)
+    }
+
+    /// 在新语料上继续训练，已有标记的ID和合并规则保持不变
+    ///
+    /// 返回重映射清单字典，`added` 为新增的标记ID
+    #[pyo3(name = "adapt")]
+    pub fn py_adapt<'py>(
+        &mut self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        extra_tokens: u32,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        self.async_snapshot.invalidate();
+        self.adapt(&texts, extra_tokens)
+            .map_err(py_value_error)?
+            .to_py_dict(py)
+    }
+
+    /// 把词汇表重新编号为从0开始的连续ID，返回重映射清单字典
+    #[pyo3(name = "compact_ids")]
+    pub fn py_compact_ids<'py>(
+        &mut self,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        self.async_snapshot.invalidate();
+        self.compact_ids().to_py_dict(py)
+    }
+
+    /// 兼容旧接口：`"nfc"` 在规范化流水线开头加入NFC，`"none"` 去掉开头的NFC，其余步骤不变
+    #[pyo3(name = "set_normalization")]
+    pub fn py_set_normalization(&mut self, mode: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        let nfc = parse_legacy_normalization(mode).map_err(py_value_error)?;
+        let normalizer = &self.base.normalizer;
+        self.base.normalizer = if nfc {
+            with_leading_nfc(normalizer)
+        } else {
+            normalizer
+                .steps()
+                .strip_prefix(&[NormalizerStep::Nfc])
+                .unwrap_or(normalizer.steps())
+                .to_vec()
+                .into()
+        };
+        Ok(())
+    }
+
+    /// 设置预分词之前的规范化流水线，步骤以逗号分隔，如 `"nfkc,lowercase"`，`"none"` 表示不做规范化
+    ///
+    /// 可选步骤：nfc、nfkc、nfkd、lowercase、strip_accents、clean_whitespace
+    #[pyo3(name = "set_normalizer")]
+    pub fn py_set_normalizer(&mut self, spec: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.base.normalizer = Normalizer::parse(spec).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的规范化流水线，格式同 `set_normalizer`
+    #[pyo3(name = "get_normalizer")]
+    pub fn py_get_normalizer(&self) -> String {
+        self.base.normalizer.spec()
+    }
+
+    /// 设置预分词流水线，步骤以逗号分隔，如 `"whitespace,punctuation"`，按顺序逐步细分片段，
+    /// `"none"` 表示不切分
+    ///
+    /// 可选步骤：regex（按 `set_pattern` 设置的正则表达式，默认流水线只有这一步）、whitespace、
+    /// byte_level、metaspace、digits、punctuation
+    #[pyo3(name = "set_pre_tokenizer")]
+    pub fn py_set_pre_tokenizer(&mut self, spec: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.base.pre_tokenizer = PreTokenizerPipeline::parse(spec).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的预分词流水线，格式同 `set_pre_tokenizer`
+    #[pyo3(name = "get_pre_tokenizer")]
+    pub fn py_get_pre_tokenizer(&self) -> String {
+        self.base.pre_tokenizer.spec()
+    }
+
+    /// 设置解码器："fuse"（默认，直接拼接）、"byte_level"（还原 `Ġ` 等字节映射字符）、
+    /// "metaspace"（`▁` 还原为空格并去掉开头的空格）或 "wordpiece"（词之间补空格）
+    #[pyo3(name = "set_decoder")]
+    pub fn py_set_decoder(&mut self, name: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.base.decoder = DecoderKind::from_name(name).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的解码器名称
+    #[pyo3(name = "get_decoder")]
+    pub fn py_get_decoder(&self) -> &'static str {
+        self.base.decoder.name()
+    }
+
+    /// 设置训练时计数相同的配对之间的合并顺序："pair_id"（默认）、"lexicographic" 或 "insertion_order"
+    pub fn set_tie_break(&mut self, mode: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.base.tie_break = match mode {
+            "pair_id" => TieBreak::PairId,
+            "lexicographic" => TieBreak::Lexicographic,
+            "insertion_order" => TieBreak::InsertionOrder,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "未知的平局处理方式: {}，可选 \"pair_id\"、\"lexicographic\" 或 \"insertion_order\"",
+                    other
+                )))
+            }
+        };
+        Ok(())
+    }
+
+    /// 设置之后训练使用的配置：最低合并次数、新标记的最大字符数、训练前注册的特殊标记、
+    /// 初始字母表和字母表最多保留的字符数
+    #[pyo3(signature = (
+        min_frequency = 0,
+        max_piece_length = None,
+        special_tokens = None,
+        initial_alphabet = None,
+        limit_alphabet = None,
+        shuffle_seed = None
+    ))]
+    pub fn set_trainer_config(
+        &mut self,
+        min_frequency: u64,
+        max_piece_length: Option<usize>,
+        special_tokens: Option<Vec<String>>,
+        initial_alphabet: Option<Vec<char>>,
+        limit_alphabet: Option<usize>,
+        shuffle_seed: Option<u64>,
+    ) {
+        self.async_snapshot.invalidate();
+        self.base.trainer = TrainerConfig::from_py(
+            min_frequency,
+            max_piece_length,
+            special_tokens,
+            initial_alphabet,
+            limit_alphabet,
+            shuffle_seed,
+        );
+    }
+
+    /// 设置训练时并行计数的粒度：每个任务处理的片段数（0为自适应）和归并树宽度
+    #[pyo3(signature = (pieces_per_task = 0, reduce_width = 2))]
+    pub fn set_parallel_chunking(
+        &mut self,
+        pieces_per_task: usize,
+        reduce_width: usize,
+    ) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.base.parallel =
+            ParallelChunking::new(pieces_per_task, reduce_width).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 设置解码时对未知标记ID的处理方式：`"strict"`（默认）报错，`"lenient"` 按码点解码
+    #[pyo3(name = "set_decode_mode")]
+    pub fn py_set_decode_mode(&mut self, mode: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        let mode = match mode {
+            "strict" => DecodeMode::Strict,
+            "lenient" => DecodeMode::Lenient,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "未知的解码模式: {}，可选 \"strict\" 或 \"lenient\"",
+                    other
+                )))
+            }
+        };
+        self.set_decode_mode(mode);
+        Ok(())
+    }
+
+    /// 设置未知字符的回退方式：`"unk"` 映射为 `<unk>`，`"byte"` 拆成 `<0xNN>` 字节标记
+    #[pyo3(name = "set_unknown_fallback")]
+    pub fn py_set_unknown_fallback(&mut self, mode: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        let fallback = match mode {
+            "unk" => UnknownCharFallback::Unk,
+            "byte" => UnknownCharFallback::ByteFallback,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "未知的回退方式: {}，可选 \"unk\" 或 \"byte\"",
+                    other
+                )))
+            }
+        };
+        self.set_unknown_fallback(fallback);
+        Ok(())
+    }
+
+    /// 注册训练事件回调，回调接收描述事件的dict
+    ///
+    /// 给出 `snapshot_every` 时，训练开始时和每完成这么多次合并后收到一个 `pair_snapshot` 事件，
+    /// 其中 `candidates` 为当前计数最高的 `top_k` 个配对。回调返回 `False` 时训练在下一次合并之前停止
+    #[pyo3(
+        name = "add_train_observer",
+        signature = (callback, snapshot_every = None, top_k = 10)
+    )]
+    pub fn py_add_train_observer(
+        &mut self,
+        callback: Py<PyAny>,
+        snapshot_every: Option<u32>,
+        top_k: usize,
+    ) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        let observer =
+            crate::base::events::PyTrainObserver::from_py(callback, snapshot_every, top_k)
+                .map_err(py_value_error)?;
+        self.add_train_observer(Arc::new(observer));
+        Ok(())
+    }
+
     /// 获取正则表达式模式
     pub fn get_pattern(&self) -> String {
         self.base.pattern.clone()
@@ -399,18 +1942,61 @@ impl Tokenizer {
     }
 
     /// 保存分词器
-    pub fn save(&self, path: &str) -> PyResult<()> {
-        TokenizerTrait::save(self, path)
-            .map_err(|e| crate::error::TokenizerError::ModelSaveError { message: e }.into())
+    #[pyo3(name = "save")]
+    pub fn py_save(&self, path: &str) -> PyResult<()> {
+        TokenizerTrait::save(self, path).map_err(PyErr::from)
     }
 
     /// 加载分词器
-    pub fn load(&mut self, path: &str) -> PyResult<()> {
-        TokenizerTrait::load(self, path).map_err(PyValueError::new_err)
+    #[pyo3(name = "load")]
+    pub fn py_load(&mut self, path: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        TokenizerTrait::load(self, path).map_err(PyErr::from)
+    }
+
+    /// 保存到本地模型注册表，返回模型文件路径；同名模型会被覆盖
+    #[pyo3(name = "save_as")]
+    pub fn py_save_as(&self, name: &str) -> PyResult<String> {
+        let path = NamedModel::save_as(self, name)?;
+        Ok(path.to_string_lossy().into_owned())
+    }
+
+    /// 按名称从本地模型注册表加载
+    #[pyo3(name = "load_named")]
+    pub fn py_load_named(&mut self, name: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        NamedModel::load_named(self, name).map_err(Into::into)
+    }
+
+    /// 以带版本头和校验和的二进制格式保存
+    #[pyo3(name = "save_binary")]
+    pub fn py_save_binary(&self, path: &str) -> PyResult<()> {
+        self.save_binary(path).map_err(Into::into)
+    }
+
+    /// 加载二进制模型，文件不是二进制格式时按文本格式加载
+    #[pyo3(name = "load_binary")]
+    pub fn py_load_binary(&mut self, path: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.load_binary(path).map_err(Into::into)
+    }
+
+    /// 保存为HuggingFace `tokenizers` 的 tokenizer.json
+    #[pyo3(name = "save_tokenizer_json")]
+    pub fn py_save_tokenizer_json(&self, path: &str) -> PyResult<()> {
+        self.save_tokenizer_json(path).map_err(Into::into)
+    }
+
+    /// 从HuggingFace `tokenizers` 的 tokenizer.json 加载
+    #[pyo3(name = "load_tokenizer_json")]
+    pub fn py_load_tokenizer_json(&mut self, path: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
+        self.load_tokenizer_json(path).map_err(py_value_error)
     }
 
     /// 从常用汉字字表文件加载基础字符
     pub fn load_base_chars(&mut self, file_path: &str) -> PyResult<()> {
+        self.async_snapshot.invalidate();
         self._load_base_chars(file_path)
             .map_err(|e| PyValueError::new_err(e.to_string()))
     }
@@ -418,6 +2004,7 @@ impl Tokenizer {
     /// 从常用汉字字表文件加载基础字符
     #[pyo3(name = "load_base_chars_bpe")]
     pub fn py_load_base_chars(&mut self, file_path: String) -> PyResult<()> {
+        self.async_snapshot.invalidate();
         self._load_base_chars(&file_path)
             .map_err(|e| crate::error::TokenizerError::IoError { source: e }.into())
     }
@@ -425,33 +2012,57 @@ impl Tokenizer {
     /// 从dict目录加载初始化词表
     #[pyo3(name = "load_vocab_from_dict")]
     pub fn py_load_vocab_from_dict(&mut self, dict_file: String) -> PyResult<()> {
-        self._load_vocab_from_dict(&dict_file)
-            .map_err(|e| crate::error::TokenizerError::LoadError { message: e }.into())
+        self.async_snapshot.invalidate();
+        self._load_vocab_from_dict(&dict_file).map_err(Into::into)
     }
 
     /// 从Python迭代器训练分词器
     #[cfg(feature = "python")]
     #[pyo3(name = "train_from_iterator")]
     pub fn py_train_from_iterator(&mut self, texts: Vec<String>, vocab_size: u32) -> PyResult<()> {
+        self.async_snapshot.invalidate();
         self.train(texts, vocab_size)
             .map_err(|e| PyValueError::new_err(format!("训练失败: {}", e)))
     }
 
     /// 从流式迭代器训练（并行摄取）
+    ///
+    /// 迭代器中的 `bytes`/`bytearray` 按UTF-8解码，`errors` 指定无效字节的处理方式：
+    /// `"strict"`（默认）报错，`"replace"` 替换为U+FFFD，`"ignore"` 丢弃。
+    /// `invalid` 指定其余不是字符串的项（如 `None`）的处理方式：`"fail"`（默认）报错，
+    /// `"skip"` 跳过并计数，`"str"` 调用 `str()` 转换，`None` 仍然跳过。
+    /// 返回摘要字典：`sequences` 为参与训练的文本数，`decoded` 为解码的 `bytes` 项数，
+    /// `skipped`/`coerced` 为跳过和转换的项数，`skipped_types` 按类型名统计跳过的项
     #[cfg(feature = "python")]
-    #[pyo3(signature = (iterator, vocab_size, buffer_size=8192, pattern=None))]
-    #[pyo3(text_signature = "(self, iterator, vocab_size, buffer_size=8192, pattern=None)")]
+    #[pyo3(signature = (
+        iterator, vocab_size, buffer_size=8192, pattern=None, invalid="fail", errors="strict"
+    ))]
+    #[pyo3(
+        text_signature = "(self, iterator, vocab_size, buffer_size=8192, pattern=None, invalid=\"fail\", errors=\"strict\")"
+    )]
     #[pyo3(name = "train_from_iterator_stream")]
-    pub fn train_from_iterator(
+    #[allow(clippy::too_many_arguments)]
+    pub fn train_from_iterator<'py>(
         &mut self,
-        py: pyo3::Python<'_>,
-        iterator: &pyo3::Bound<'_, pyo3::PyAny>,
+        py: pyo3::Python<'py>,
+        iterator: &pyo3::Bound<'py, pyo3::PyAny>,
         vocab_size: u32,
         buffer_size: usize,
         pattern: Option<String>,
-    ) -> PyResult<()> {
-        // 使用提供的模式或默认为GPT-4模式
-        let pattern_str = pattern.unwrap_or_else(|| GPT4_PATTERN.to_string());
+        invalid: &str,
+        errors: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        self.async_snapshot.invalidate();
+        trace_span!("bpe.train_from_iterator", vocab_size, buffer_size);
+        let policy = InvalidItemPolicy::parse(invalid).map_err(py_value_error)?;
+        let errors = Utf8Errors::parse(errors).map_err(py_value_error)?;
+        self.base.trainer.clone().register_special_tokens(self)?;
+
+        // 使用提供的模式（或预设名称）或默认为GPT-4模式
+        let pattern_str = pattern.map_or_else(
+            || GPT4_PATTERN.to_string(),
+            |pattern| resolve_pattern(&pattern).to_string(),
+        );
 
         // 更新存储的模式并编译它
         self.base.pattern = pattern_str.clone();
@@ -480,9 +2091,10 @@ impl Tokenizer {
             buffer_size
         );
         let mut total_sequences = 0u64;
+        let mut summary = InvalidItemSummary::default();
 
         // 辅助函数：在`buf`中填充最多`buffer_size`个字符串来自Python迭代器
-        let refill = |buf: &mut Vec<String>| -> PyResult<bool> {
+        let mut refill = |buf: &mut Vec<String>| -> PyResult<bool> {
             pyo3::Python::with_gil(|py| {
                 buf.clear();
                 let it = py_iter.bind(py);
@@ -493,12 +2105,9 @@ impl Tokenizer {
                     // next(it) - 使用安全的PyO3 API
                     match it.call_method0("__next__") {
                         Ok(obj) => {
-                            let s: String = obj.extract().map_err(|e| {
-                                crate::error::TokenizerError::InvalidInput {
-                                    message: e.to_string(),
-                                }
-                            })?;
-                            buf.push(s);
+                            if let Some(s) = summary.extract(&obj, policy, errors)? {
+                                buf.push(s);
+                            }
                         }
                         Err(e) => {
                             // 检查是否是StopIteration（正常结束）
@@ -523,27 +2132,20 @@ impl Tokenizer {
 
             total_sequences += buf.len() as u64;
 
-            let pattern = self.base.compiled_pattern.clone();
-            let local: AHashMap<CompactString, i32> = py.allow_threads(|| {
-                buf.par_iter()
-                    .map(|s| {
-                        let mut m: AHashMap<CompactString, i32> = AHashMap::new();
-                        for mat in pattern.find_iter(s) {
-                            let piece = match mat {
-                                Ok(m) => m.as_str(),
-                                Err(_) => continue,
-                            };
+            let pre_tokenizer = self.base.pre_tokenizer();
+            let normalizer = &self.base.normalizer;
+            let chunking = self.base.parallel;
+            let local: StdHashMap<CompactString, i32, ahash::RandomState> =
+                py.allow_threads(|| {
+                    chunking.count(&buf, |s, m| {
+                        let s = normalizer.apply(s);
+                        // 匹配失败时跳过该文本的剩余部分
+                        let _ = pre_tokenizer.pre_tokenize(&s, &mut |piece| {
                             *m.entry(CompactString::from(piece)).or_default() += 1;
-                        }
-                        m
+                            Ok(())
+                        });
                     })
-                    .reduce(AHashMap::new, |mut a, b| {
-                        for (k, v) in b {
-                            *a.entry(k).or_default() += v;
-                        }
-                        a
-                    })
-            });
+                });
 
             // 合并局部到全局（单线程）
             for (k, v) in local {
@@ -559,32 +2161,18 @@ impl Tokenizer {
             total_sequences,
             counts.len()
         );
-
-        // 物化词和计数
-        let mut words = Vec::with_capacity(counts.len());
-        let mut cvec = Vec::with_capacity(counts.len());
-        for (chunk, c) in counts.into_iter() {
-            // 将文本分割为字符序列
-            let mut ids = Vec::new();
-            for ch in chunk.chars() {
-                // 直接使用字符的Unicode码点作为token ID
-                let code_point = ch as u32;
-                // 确保字符在词汇表中
-                if !self.vocab.contains_id(&code_point) {
-                    self.vocab.insert(code_point, ch.to_string());
-                    if code_point >= self.next_token_id {
-                        self.next_token_id = code_point + 1;
-                    }
-                }
-                ids.push(code_point);
-            }
-
-            words.push(Word::new(ids));
-            cvec.push(c);
+        if summary.skipped > 0 {
+            log::warn!("跳过了 {} 个不是字符串的项", summary.skipped);
         }
 
-        self._train_core_incremental(words, vocab_size);
-        Ok(())
+        // 物化词和计数，按片段排序使字符ID的分配与哈希表的迭代顺序无关
+        let mut pieces: Vec<(CompactString, i32)> = counts.into_iter().collect();
+        pieces.sort_unstable();
+        let (words, cvec) = self.training_words(&pieces);
+
+        self.merges.clear();
+        self._train_core_incremental(words, cvec, vocab_size);
+        summary.to_py_dict(py, total_sequences)
     }
 
     /// 返回正则表达式模式
@@ -637,117 +2225,8 @@ impl Tokenizer {
             .into()
         })
     }
-
-    /// 内部编码实现
-    fn _encode_internal(&self, text: &str) -> Result<Vec<u32>, crate::error::TokenizerError> {
-        // 使用正则表达式分割文本
-        let mut result = Vec::new();
-        for mat in self.base.compiled_pattern.find_iter(text) {
-            let piece = match mat {
-                Ok(m) => m.as_str(),
-                Err(e) => {
-                    return Err(crate::error::TokenizerError::EncodingError {
-                        message: format!("正则表达式匹配失败: {}", e),
-                    })
-                }
-            };
-
-            if piece.is_empty() {
-                continue;
-            }
-
-            // 首先尝试直接匹配整个片段 - O(1)查找
-            let piece_string = piece.to_string();
-            if let Some(&id) = self.vocab.get_by_value(&piece_string) {
-                result.push(id);
-                continue;
-            }
-
-            // 将文本转换为字符序列
-            let mut ids: Vec<WordId> = Vec::new();
-            for ch in piece.chars() {
-                let ch_str = ch.to_string();
-                // 使用反向映射进行O(1)查找
-                if let Some(&id) = self.vocab.get_by_value(&ch_str) {
-                    ids.push(id);
-                } else {
-                    // 如果找不到，使用字符的Unicode码点作为token ID
-                    ids.push(ch as u32);
-                }
-            }
-
-            // 应用合并规则 - 优化版本：贪心合并，避免重复扫描
-            // 持续合并直到没有更多可以合并的对
-            while ids.len() >= 2 {
-                // 在一次扫描中找到所有可以合并的位置
-                let mut merges_to_apply = Vec::new();
-                let mut i = 0;
-
-                while i < ids.len() - 1 {
-                    if let Some(&new_id) = self.merges.get(&(ids[i], ids[i + 1])) {
-                        merges_to_apply.push((i, new_id));
-                        i += 2; // 跳过已合并的pair
-                    } else {
-                        i += 1;
-                    }
-                }
-
-                if merges_to_apply.is_empty() {
-                    break;
-                }
-
-                // 应用所有合并，从后往前以避免索引偏移问题
-                let mut new_ids = Vec::with_capacity(ids.len());
-                let mut next_merge_idx = 0;
-                let mut i = 0;
-
-                while i < ids.len() {
-                    if next_merge_idx < merges_to_apply.len()
-                        && merges_to_apply[next_merge_idx].0 == i
-                    {
-                        // 这个位置需要合并
-                        new_ids.push(merges_to_apply[next_merge_idx].1);
-                        i += 2; // 跳过被合并的两个token
-                        next_merge_idx += 1;
-                    } else {
-                        new_ids.push(ids[i]);
-                        i += 1;
-                    }
-                }
-
-                ids = new_ids;
-            }
-
-            result.extend(ids);
-        }
-
-        Ok(result)
-    }
-
-    /// 内部解码实现
-    fn decode_internal(&self, tokens: Vec<u32>) -> Result<String, crate::error::TokenizerError> {
-        let mut result = String::new();
-
-        for token in tokens {
-            if let Some(text) = self.vocab.get_by_id(&token) {
-                // 直接使用词汇表中的文本
-                result.push_str(text);
-            } else {
-                // 如果找不到对应的词汇，尝试作为Unicode字符处理
-                if let Some(c) = char::from_u32(token) {
-                    result.push(c);
-                } else {
-                    // 如果不是有效的Unicode字符，使用替换字符
-                    result.push('�');
-                }
-            }
-        }
-
-        Ok(result)
-    }
 }
 
-#[cfg(feature = "python")]
 impl Default for Tokenizer {
     fn default() -> Self {
         Self::_new_internal().expect(
@@ -757,73 +2236,64 @@ impl Default for Tokenizer {
     }
 }
 
-#[cfg(feature = "python")]
 impl TokenizerTrait for Tokenizer {
     type TokenId = u32;
 
     /// 编码文本为token ID序列
-    fn encode(&self, text: &str) -> Result<Vec<u32>, String> {
-        self._encode_internal(text).map_err(|e| e.to_string())
+    fn encode(&self, text: &str) -> Result<Vec<u32>, TokenizerError> {
+        self._encode_internal(text)
     }
 
     /// 解码token ID序列为文本，参考template.rs中的实现
-    fn decode(&self, tokens: &[u32]) -> Result<String, String> {
+    fn decode(&self, tokens: &[u32]) -> Result<String, TokenizerError> {
         self.decode_internal(tokens.to_vec())
-            .map_err(|e| e.to_string())
+    }
+
+    fn decode_cow<'a>(&'a self, tokens: &[u32]) -> Result<Cow<'a, str>, TokenizerError> {
+        if let ([id], DecoderKind::Fuse) = (tokens, self.base.decoder) {
+            if let Some(Cow::Borrowed(bytes)) =
+                self.vocab.get_by_id(id).map(|text| piece_bytes(text))
+            {
+                // 普通标记直接借用词汇表中的文本，字节回退标记需要还原
+                if let Ok(text) = std::str::from_utf8(bytes) {
+                    return Ok(Cow::Borrowed(text));
+                }
+            }
+        }
+
+        TokenizerTrait::decode(self, tokens).map(Cow::Owned)
     }
 
     /// 训练分词器，参考template.rs中的实现
-    fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> Result<(), String> {
+    fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> Result<(), TokenizerError> {
+        trace_span!("bpe.train", texts = texts.len(), vocab_size);
         log::info!("开始BPE训练，目标词汇表大小: {}", vocab_size);
 
-        // 确保词汇表大小不小于256
-        let vocab_size = vocab_size.max(256);
+        let vocab_size = self.prepare_training(vocab_size)?;
 
-        // 初始化合并规则
-        self.merges.clear();
+        // 特殊标记不参与合并，从语料中去除
+        let texts = self.base.special_tokens.strip_texts(texts);
+        let texts = self.base.trainer.shuffle(texts);
 
         // 将文本转换为词序列
         log::info!("处理 {} 个文本样本", texts.len());
-        let words: Vec<Word<WordId>> = {
-            let mut words = Vec::new();
-
-            for text in &texts {
-                // 使用正则表达式分割文本
-                let parts = self.base.split_text(text)?;
-
-                for part in parts {
-                    if part.is_empty() {
-                        continue;
-                    }
-
-                    // 将每个部分转换为字符序列
-                    let mut ids = Vec::new();
-                    for ch in part.chars() {
-                        // 直接使用字符的Unicode码点作为token ID
-                        let code_point = ch as u32;
-                        // 确保字符在词汇表中
-                        if !self.vocab.contains_id(&code_point) {
-                            let ch_str = ch.to_string();
-                            self.vocab.insert(code_point, ch_str);
-                            if code_point >= self.next_token_id {
-                                self.next_token_id = code_point + 1;
-                            }
-                        }
-                        ids.push(code_point);
-                    }
-
-                    if !ids.is_empty() {
-                        words.push(Word::new(ids));
-                    }
-                }
-            }
-            words
-        };
+        let mut pieces = Vec::new();
+        for text in &texts {
+            // 规范化后使用正则表达式分割文本
+            let parts = self.base.split_text(text)?;
+            pieces.extend(
+                parts
+                    .into_iter()
+                    .filter(|part| !part.is_empty())
+                    .map(|part| (part, 1)),
+            );
+        }
+        let (words, counts) = self.training_words(&pieces);
 
         log::info!("已处理 {} 个词", words.len());
 
         // 使用增量训练核心
-        self._train_core_incremental(words, vocab_size);
+        self._train_core_incremental(words, counts, vocab_size);
         log::info!("BPE训练完成，最终合并规则数: {}", self.merges.len());
         log::info!(
             "训练后词汇表大小: {}, next_token_id: {}",
@@ -834,6 +2304,18 @@ impl TokenizerTrait for Tokenizer {
         Ok(())
     }
 
+    fn train_with_config(
+        &mut self,
+        texts: Vec<String>,
+        vocab_size: u32,
+        config: TrainerConfig,
+    ) -> Result<(), TokenizerError> {
+        let previous = std::mem::replace(&mut self.base.trainer, config);
+        let result = TokenizerTrait::train(self, texts, vocab_size);
+        self.base.trainer = previous;
+        result
+    }
+
     fn vocab_size(&self) -> usize {
         log::debug!(
             "词汇表大小: {}, next_token_id: {}",
@@ -843,10 +2325,31 @@ impl TokenizerTrait for Tokenizer {
         self.vocab.len()
     }
 
-    fn save(&self, path: &str) -> Result<(), String> {
+    /// 未知标记、字节回退标记以及旧模型输出的词汇表之外的码点ID视为回退
+    fn is_fallback_token(&self, id: &Self::TokenId) -> bool {
+        self.vocab
+            .get_by_id(id)
+            .is_none_or(|text| text == UNK_TOKEN || matches!(piece_bytes(text), Cow::Owned(_)))
+    }
+
+    fn save(&self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("bpe.save", path);
         // 使用基础分词器的保存方法
         self.base.save(path)?;
+        self.save_model_data(path)
+    }
 
+    fn load(&mut self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("bpe.load", path);
+        // 使用基础分词器的加载方法
+        self.base.load(path)?;
+        self.load_model_data(path)
+    }
+}
+
+impl Tokenizer {
+    /// 在基础分词器写出的内容之后追加BPE特定的数据
+    fn save_model_data(&self, path: &str) -> Result<(), TokenizerError> {
         // 保存BPE特定的数据
         use std::fs::OpenOptions;
         use std::io::Write;
@@ -854,42 +2357,57 @@ impl TokenizerTrait for Tokenizer {
         let mut file = OpenOptions::new()
             .append(true)
             .open(path)
-            .map_err(|e| format!("打开文件失败: {}", e))?;
+            .map_err(|e| model_save_error(format!("打开文件失败: {}", e)))?;
 
         // 保存词汇表
         writeln!(file, "vocab: {}", self.vocab.len())
-            .map_err(|e| format!("写入词汇表大小失败: {}", e))?;
+            .map_err(|e| model_save_error(format!("写入词汇表大小失败: {}", e)))?;
 
         for (&id, text) in self.vocab.iter() {
+            // 文本以JSON字符串写入，避免空白和换行字符破坏行格式
+            let text = serde_json::to_string(text)
+                .map_err(|e| model_save_error(format!("序列化词汇失败: {}", e)))?;
             writeln!(file, "vocab_entry: {} {}", id, text)
-                .map_err(|e| format!("写入词汇表条目失败: {}", e))?;
+                .map_err(|e| model_save_error(format!("写入词汇表条目失败: {}", e)))?;
         }
 
         // 保存合并规则
         writeln!(file, "merges: {}", self.merges.len())
-            .map_err(|e| format!("写入合并规则数量失败: {}", e))?;
-
-        for ((a, b), &new_id) in &self.merges {
-            writeln!(file, "merge: {} {} {}", a, b, new_id)
-                .map_err(|e| format!("写入合并规则失败: {}", e))?;
+            .map_err(|e| model_save_error(format!("写入合并规则数量失败: {}", e)))?;
+
+        // 按等级顺序写出，并显式记录等级
+        for (rank, (pair, new_id)) in (0u32..).zip(ranked_merges(&self.merges)) {
+            let entry = MergeEntry {
+                pair,
+                new_id,
+                rank: Some(rank),
+            };
+            writeln!(file, "merge: {}", entry.to_line())
+                .map_err(|e| model_save_error(format!("写入合并规则失败: {}", e)))?;
         }
 
         // 保存下一个可用的token ID
         writeln!(file, "next_token_id: {}", self.next_token_id)
-            .map_err(|e| format!("写入下一个token ID失败: {}", e))?;
+            .map_err(|e| model_save_error(format!("写入下一个token ID失败: {}", e)))?;
+
+        let fallback = match self.unknown_fallback {
+            UnknownCharFallback::Unk => "unk",
+            UnknownCharFallback::ByteFallback => "byte",
+        };
+        writeln!(file, "unknown_fallback: {}", fallback)
+            .map_err(|e| model_save_error(format!("写入未知字符回退方式失败: {}", e)))?;
 
         Ok(())
     }
 
-    fn load(&mut self, path: &str) -> Result<(), String> {
-        // 使用基础分词器的加载方法
-        self.base.load(path)?;
-
+    /// 读取基础分词器数据之后的BPE特定数据，并检查完整性
+    fn load_model_data(&mut self, path: &str) -> Result<(), TokenizerError> {
         // 加载BPE特定的数据
         use std::fs::File;
         use std::io::{self, BufRead};
 
-        let file = File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+        let file =
+            File::open(path).map_err(|e| model_load_error(format!("打开文件失败: {}", e)))?;
         let reader = io::BufReader::new(file);
 
         let lines = reader.lines();
@@ -899,11 +2417,29 @@ impl TokenizerTrait for Tokenizer {
         // 清空当前数据
         self.vocab.clear();
         self.merges.clear();
+        // 规范化流水线由基础数据加载，旧模型单独记录的NFC在读完后并入
+        let mut legacy_nfc = false;
+        let mut entries = Vec::new();
+        let mut merges = Vec::new();
 
         for line in lines {
-            let line = line.map_err(|e| format!("读取行失败: {}", e))?;
-            let line = line.trim();
+            let line = line.map_err(|e| model_load_error(format!("读取行失败: {}", e)))?;
+
+            if let Some(entry_data) = line.strip_prefix("vocab_entry: ") {
+                if in_vocab {
+                    if let Some((id_str, text)) = entry_data.split_once(' ') {
+                        let id = id_str
+                            .parse::<WordId>()
+                            .map_err(|e| model_load_error(format!("解析词汇表ID失败: {}", e)))?;
+                        let text = serde_json::from_str::<String>(text)
+                            .map_err(|e| model_load_error(format!("解析词汇失败: {}", e)))?;
+                        entries.push((id, text));
+                    }
+                }
+                continue;
+            }
 
+            let line = line.trim();
             if line.starts_with("vocab: ") {
                 in_vocab = true;
                 continue;
@@ -915,77 +2451,112 @@ impl TokenizerTrait for Tokenizer {
                 let id_str = line[14..].trim();
                 self.next_token_id = id_str
                     .parse::<WordId>()
-                    .map_err(|e| format!("解析下一个token ID失败: {}", e))?;
-            } else if line.starts_with("vocab_entry: ") {
-                if in_vocab {
-                    let parts: Vec<&str> = line[12..].splitn(2, ' ').collect();
-                    if parts.len() == 2 {
-                        let id = parts[0]
-                            .parse::<WordId>()
-                            .map_err(|e| format!("解析词汇表ID失败: {}", e))?;
-                        let text = parts[1].to_string();
-                        self.vocab.insert(id, text);
-                    }
-                }
-            } else if line.starts_with("merge: ") && in_merges {
-                let parts: Vec<&str> = line[6..].split_whitespace().collect();
-                if parts.len() == 3 {
-                    let a = parts[0]
-                        .parse::<WordId>()
-                        .map_err(|e| format!("解析合并规则a失败: {}", e))?;
-                    let b = parts[1]
-                        .parse::<WordId>()
-                        .map_err(|e| format!("解析合并规则b失败: {}", e))?;
-                    let new_id = parts[2]
-                        .parse::<WordId>()
-                        .map_err(|e| format!("解析合并规则new_id失败: {}", e))?;
-                    self.merges.insert((a, b), new_id);
+                    .map_err(|e| model_load_error(format!("解析下一个token ID失败: {}", e)))?;
+            } else if let Some(fallback) = line.strip_prefix("unknown_fallback: ") {
+                self.unknown_fallback = match fallback.trim() {
+                    "unk" => UnknownCharFallback::Unk,
+                    "byte" => UnknownCharFallback::ByteFallback,
+                    other => return Err(model_load_error(format!("未知的回退方式: {}", other))),
+                };
+            } else if let Some(normalization) = line.strip_prefix("normalization: ") {
+                legacy_nfc = parse_legacy_normalization(normalization.trim())?;
+            } else if let Some(merge_data) = line.strip_prefix("merge: ") {
+                if in_merges {
+                    merges.push(MergeEntry::parse(merge_data)?);
                 }
             }
         }
 
-        Ok(())
+        let mut report = IntegrityReport::default();
+        report.check_entries(&entries);
+        for (id, text) in entries {
+            self.vocab.insert(id, text);
+        }
+        for entry in restore_ranked_merges(merges)? {
+            self.merges.insert(entry.pair, entry.new_id);
+        }
+        if legacy_nfc {
+            self.base.normalizer = with_leading_nfc(&self.base.normalizer);
+        }
+
+        report.extend(self.check_integrity());
+        report.into_result()
     }
 }
 
-#[cfg(feature = "python")]
 impl MergeBasedTokenizer for Tokenizer {
-    fn apply_merges(&mut self, tokens: &mut Vec<Self::TokenId>) -> Result<(), String> {
-        // 应用合并规则，直到没有更多合并可以应用
-        let mut changed = true;
-        while changed {
-            changed = false;
-            let mut new_tokens = Vec::new();
-            let mut i = 0;
-
-            while i < tokens.len() {
-                if i + 1 < tokens.len() {
-                    // 检查当前token对是否可以合并
-                    if let Some(&new_id) = self.merges.get(&(tokens[i], tokens[i + 1])) {
-                        new_tokens.push(new_id);
-                        i += 2;
-                        changed = true;
-                    } else {
-                        new_tokens.push(tokens[i]);
-                        i += 1;
-                    }
-                } else {
-                    new_tokens.push(tokens[i]);
-                    i += 1;
-                }
-            }
-
-            *tokens = new_tokens;
-        }
-
+    fn apply_merges(&mut self, tokens: &mut Vec<Self::TokenId>) -> Result<(), TokenizerError> {
+        apply_ranked_merges(tokens, &self.merges);
         Ok(())
     }
 
-    fn get_merges(&self) -> &StdHashMap<(Self::TokenId, Self::TokenId), Self::TokenId> {
+    fn get_merges(&self) -> &MergeMap {
         &self.merges
     }
 
-    fn set_merges(&mut self, merges: StdHashMap<(Self::TokenId, Self::TokenId), Self::TokenId>) {
+    fn set_merges(&mut self, merges: MergeMap) {
         self.merges = merges;
     }
 }
+
+impl SpecialTokenizer for Tokenizer {
+    fn special_tokens(&self) -> &SpecialTokens {
+        &self.base.special_tokens
+    }
+
+    fn encode_options(&self) -> &EncodeOptions {
+        &self.base.encode_options
+    }
+
+    fn encode_options_mut(&mut self) -> &mut EncodeOptions {
+        &mut self.base.encode_options
+    }
+
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Result<Vec<u32>, TokenizerError> {
+        if self.vocab.is_empty() {
+            self._init_vocab();
+        }
+        self.base
+            .special_tokens
+            .add(&mut self.vocab, &mut self.next_token_id, tokens)
+    }
+
+    fn reserve_special_ids(&mut self, count: u32) -> Result<std::ops::Range<u32>, TokenizerError> {
+        if self.vocab.is_empty() {
+            self._init_vocab();
+        }
+        self.base
+            .special_tokens
+            .reserve_after(&mut self.next_token_id, count)
+    }
+}
+
+impl VocabBytes for Tokenizer {
+    fn normalize_aligned(&self, text: &str) -> NormalizedText {
+        self.base.normalize_aligned(text)
+    }
+
+    fn token_bytes(&self, id: &u32) -> Option<Cow<'_, [u8]>> {
+        // 不在词汇表中的ID按Unicode码点解码，与 decode 一致
+        match self.vocab.get_by_id(id) {
+            Some(text) => Some(piece_bytes(text)),
+            None => char::from_u32(*id).map(|c| Cow::Owned(c.to_string().into_bytes())),
+        }
+    }
+
+    fn vocab_bytes(&self) -> Vec<(u32, Cow<'_, [u8]>)> {
+        piece_vocab_bytes(&self.vocab)
+    }
+
+    fn decoder(&self) -> DecoderKind {
+        self.base.decoder
+    }
+
+    fn decoded_piece(&self, id: &u32) -> Option<DecodedPiece<'_>> {
+        // 与 token_bytes 一致，无法解码为字符的ID视为无效
+        if !self.vocab.contains_id(id) && char::from_u32(*id).is_none() {
+            return None;
+        }
+        Some(self.decoded_piece_internal(*id))
+    }
+}
@@ -0,0 +1,239 @@
+//! 纯文本语料文件读取
+//!
+//! 支持普通文本、gzip（`.gz`）和zstd（`.zst`、`.zstd`）压缩文件。路径可以是单个文件、
+//! 目录（递归读取其中的全部文件）或glob模式。文件按行流式读取，训练时只保留唯一片段的计数。
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+
+use crate::base::special_tokens::SpecialTokens;
+use crate::error::{load_error, TokenizerError};
+
+/// 默认每批读取的行数
+pub const DEFAULT_BATCH_LINES: usize = 8192;
+
+/// 路径中是否含有glob通配符
+fn is_glob(path: &Path) -> bool {
+    path.to_str().is_some_and(|s| s.contains(['*', '?', '[']))
+}
+
+/// 递归收集目录下的全部文件，同一目录中的条目按名称排序
+fn walk_dir(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), TokenizerError> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| load_error(format!("无法读取目录 {}: {}", dir.display(), e)))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()
+        .map_err(|e| load_error(format!("无法读取目录 {}: {}", dir.display(), e)))?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            walk_dir(&path, out)?;
+        } else {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// 把文件、目录和glob模式展开为文件列表，保持参数顺序，模式和目录内按名称排序
+///
+/// # Errors
+///
+/// 当路径不存在、目录无法读取、glob模式无效或没有匹配任何文件时返回错误
+pub fn expand_paths<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<PathBuf>, TokenizerError> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        if path.is_dir() {
+            walk_dir(path, &mut files)?;
+        } else if path.is_file() {
+            files.push(path.to_path_buf());
+        } else if is_glob(path) {
+            let pattern = path.to_string_lossy();
+            let matches = glob::glob(&pattern)
+                .map_err(|e| load_error(format!("无效的glob模式 {}: {}", pattern, e)))?;
+            let before = files.len();
+            for entry in matches {
+                let entry =
+                    entry.map_err(|e| load_error(format!("无法读取 {}: {}", pattern, e)))?;
+                if entry.is_dir() {
+                    walk_dir(&entry, &mut files)?;
+                } else {
+                    files.push(entry);
+                }
+            }
+            if files.len() == before {
+                return Err(load_error(format!("glob模式 {} 没有匹配任何文件", pattern)));
+            }
+        } else {
+            return Err(load_error(format!("语料路径不存在: {}", path.display())));
+        }
+    }
+    Ok(files)
+}
+
+/// 按扩展名打开文本文件，`.gz` 按gzip解压，`.zst` 和 `.zstd` 按zstd解压
+///
+/// # Errors
+///
+/// 当文件无法打开或zstd解码器初始化失败时返回错误
+pub fn open_text(path: &Path) -> Result<Box<dyn BufRead + Send>, TokenizerError> {
+    let file = File::open(path)
+        .map_err(|e| load_error(format!("无法打开文件 {}: {}", path.display(), e)))?;
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    Ok(match extension.as_deref() {
+        Some("gz") => Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file))),
+        Some("zst" | "zstd") => Box::new(BufReader::new(
+            zstd::stream::read::Decoder::new(file)
+                .map_err(|e| load_error(format!("无法解压zstd文件 {}: {}", path.display(), e)))?,
+        )),
+        _ => Box::new(BufReader::new(file)),
+    })
+}
+
+/// 按批次读取文本文件各行的迭代器
+///
+/// 每次产出一批非空行，不含行尾的换行符。不是合法UTF-8的字节按替换字符处理。
+pub struct TextFileReader {
+    paths: VecDeque<PathBuf>,
+    batch_lines: usize,
+    current: Option<(PathBuf, Box<dyn BufRead + Send>)>,
+}
+
+impl TextFileReader {
+    /// 创建读取器，参数中的目录和glob模式先展开为文件列表
+    ///
+    /// # Errors
+    ///
+    /// 同 [`expand_paths`]
+    pub fn new<P: AsRef<Path>>(paths: &[P]) -> Result<Self, TokenizerError> {
+        Ok(Self {
+            paths: expand_paths(paths)?.into(),
+            batch_lines: DEFAULT_BATCH_LINES,
+            current: None,
+        })
+    }
+
+    /// 设置每批读取的行数，为0时按1处理
+    #[must_use]
+    pub fn with_batch_lines(mut self, batch_lines: usize) -> Self {
+        self.batch_lines = batch_lines.max(1);
+        self
+    }
+
+    /// 从当前文件读取至多一批行，文件读完时返回 `false`
+    fn fill(&mut self, batch: &mut Vec<String>) -> Result<bool, TokenizerError> {
+        let Some((path, reader)) = self.current.as_mut() else {
+            return Ok(false);
+        };
+        let mut line = Vec::new();
+        while batch.len() < self.batch_lines {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .map_err(|e| load_error(format!("读取文件 {} 失败: {}", path.display(), e)))?;
+            if read == 0 {
+                return Ok(false);
+            }
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\n', '\r']);
+            if !text.is_empty() {
+                batch.push(text.to_string());
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Iterator for TextFileReader {
+    type Item = Result<Vec<String>, TokenizerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut batch = Vec::new();
+        loop {
+            if self.current.is_none() {
+                let path = self.paths.pop_front()?;
+                match open_text(&path) {
+                    Ok(reader) => self.current = Some((path, reader)),
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            match self.fill(&mut batch) {
+                Ok(true) => return Some(Ok(batch)),
+                Ok(false) => {
+                    self.current = None;
+                    // 批次未满时继续读下一个文件，全部读完后产出剩余的行
+                    if self.paths.is_empty() {
+                        return (!batch.is_empty()).then_some(Ok(batch));
+                    }
+                }
+                Err(e) => {
+                    self.current = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// 流式统计语料文件中每个唯一片段的出现次数，按片段排序返回
+///
+/// 每批行先去除特殊标记，再由 `split` 并行切分为片段，只在内存中保留计数表
+///
+/// # Errors
+///
+/// 当文件无法读取或 `split` 返回错误时返回错误
+pub fn count_chunks<P, F>(
+    paths: &[P],
+    special_tokens: &SpecialTokens,
+    split: F,
+) -> Result<Vec<(String, i32)>, TokenizerError>
+where
+    P: AsRef<Path>,
+    F: Fn(&str) -> Result<Vec<String>, TokenizerError> + Sync,
+{
+    let mut counts: HashMap<String, i32> = HashMap::new();
+    let mut lines = 0usize;
+    for batch in TextFileReader::new(paths)? {
+        let batch = batch?;
+        lines += batch.len();
+        let batch = special_tokens.strip_texts(batch);
+        let local = batch
+            .par_iter()
+            .try_fold(HashMap::new, |mut local: HashMap<String, i32>, text| {
+                for piece in split(text)? {
+                    if !piece.is_empty() {
+                        let count = local.entry(piece).or_insert(0);
+                        *count = count.saturating_add(1);
+                    }
+                }
+                Ok::<_, TokenizerError>(local)
+            })
+            .try_reduce(HashMap::new, |mut a, b| {
+                for (piece, count) in b {
+                    let total = a.entry(piece).or_insert(0);
+                    *total = total.saturating_add(count);
+                }
+                Ok(a)
+            })?;
+        for (piece, count) in local {
+            let total = counts.entry(piece).or_insert(0);
+            *total = total.saturating_add(count);
+        }
+    }
+    log::info!(
+        "从文本文件读取 {} 行，得到 {} 个唯一片段",
+        lines,
+        counts.len()
+    );
+    let mut pieces: Vec<(String, i32)> = counts.into_iter().collect();
+    pieces.sort_unstable();
+    Ok(pieces)
+}
@@ -0,0 +1,15 @@
+//! 训练语料读取
+//!
+//! 从磁盘上的语料文件中读取文本，交给分词器训练。
+
+#[cfg(feature = "files")]
+pub mod files;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod shuffle;
+
+#[cfg(feature = "files")]
+pub use self::files::{count_chunks, TextFileReader};
+#[cfg(feature = "parquet")]
+pub use self::parquet::{train_from_parquet, ParquetTextReader};
+pub use self::shuffle::shuffle_seeded;
@@ -0,0 +1,177 @@
+//! Parquet语料读取
+//!
+//! 只解码指定的字符串列，按批次依次读取多个Parquet分片，其余列不会被读取。
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use ::parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+use ::parquet::arrow::ProjectionMask;
+use arrow_array::{Array, LargeStringArray, StringArray};
+
+use crate::base::traits::Tokenizer;
+use crate::corpus::shuffle::{derive_seed, shuffle_seeded};
+use crate::error::{load_error, TokenizerError};
+
+/// 默认每批读取的行数
+pub const DEFAULT_BATCH_SIZE: usize = 8192;
+
+/// 按批次读取Parquet文件中某个字符串列的迭代器
+///
+/// 每次产出一批非空文本，空值会被跳过。支持 `utf8` 和 `large_utf8` 列。
+pub struct ParquetTextReader {
+    paths: VecDeque<PathBuf>,
+    column: String,
+    batch_size: usize,
+    current: Option<(PathBuf, ParquetRecordBatchReader)>,
+    shuffle_seed: Option<u64>,
+    batches_read: u64,
+}
+
+impl ParquetTextReader {
+    /// 创建读取器，文件按给定顺序依次读取
+    pub fn new<P, I>(paths: I, column: &str) -> Self
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = P>,
+    {
+        Self {
+            paths: paths
+                .into_iter()
+                .map(|p| p.as_ref().to_path_buf())
+                .collect(),
+            column: column.to_string(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            current: None,
+            shuffle_seed: None,
+            batches_read: 0,
+        }
+    }
+
+    /// 设置每批读取的行数，为0时按1处理
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// 按种子打乱分片的读取顺序和每批中的行，同样的种子和分片列表总是产出同样的批次
+    ///
+    /// 只在批内打乱，内存占用不变；需要跨批打乱时可配合 [`TrainerConfig::with_shuffle_seed`]
+    ///
+    /// [`TrainerConfig::with_shuffle_seed`]: crate::base::trainer_config::TrainerConfig::with_shuffle_seed
+    pub fn with_shuffle(mut self, seed: u64) -> Self {
+        let paths: Vec<PathBuf> = self.paths.into_iter().collect();
+        self.paths = shuffle_seeded(paths, seed).into();
+        self.shuffle_seed = Some(seed);
+        self
+    }
+
+    /// 打开文件，只投影指定的列
+    fn open(&self, path: &Path) -> Result<ParquetRecordBatchReader, TokenizerError> {
+        let file = File::open(path)
+            .map_err(|e| load_error(format!("无法打开文件 {}: {}", path.display(), e)))?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+            .map_err(|e| load_error(format!("无法读取Parquet文件 {}: {}", path.display(), e)))?;
+
+        let index = builder.schema().index_of(&self.column).map_err(|_| {
+            load_error(format!(
+                "Parquet文件 {} 中不存在列 {:?}",
+                path.display(),
+                self.column
+            ))
+        })?;
+        let mask = ProjectionMask::roots(builder.parquet_schema(), [index]);
+
+        builder
+            .with_projection(mask)
+            .with_batch_size(self.batch_size)
+            .build()
+            .map_err(|e| load_error(format!("无法读取Parquet文件 {}: {}", path.display(), e)))
+    }
+
+    /// 读取下一批，当前文件读完时切换到下一个文件
+    fn next_batch(&mut self) -> Option<Result<Vec<String>, TokenizerError>> {
+        loop {
+            if self.current.is_none() {
+                let path = self.paths.pop_front()?;
+                match self.open(&path) {
+                    Ok(reader) => self.current = Some((path, reader)),
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+
+            let (path, reader) = self.current.as_mut()?;
+            match reader.next() {
+                Some(Ok(batch)) => {
+                    let texts = column_texts(batch.column(0).as_ref(), &self.column, path);
+                    let Some(seed) = self.shuffle_seed else {
+                        return Some(texts);
+                    };
+                    let seed = derive_seed(seed, self.batches_read);
+                    self.batches_read += 1;
+                    return Some(texts.map(|texts| shuffle_seeded(texts, seed)));
+                }
+                Some(Err(e)) => {
+                    let error =
+                        load_error(format!("读取Parquet文件 {} 失败: {}", path.display(), e));
+                    self.current = None;
+                    return Some(Err(error));
+                }
+                None => self.current = None,
+            }
+        }
+    }
+}
+
+impl Iterator for ParquetTextReader {
+    type Item = Result<Vec<String>, TokenizerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_batch()
+    }
+}
+
+/// 提取一批字符串列中的非空文本
+fn column_texts(
+    array: &dyn Array,
+    column: &str,
+    path: &Path,
+) -> Result<Vec<String>, TokenizerError> {
+    if let Some(array) = array.as_any().downcast_ref::<StringArray>() {
+        return Ok(array.iter().flatten().map(str::to_string).collect());
+    }
+    if let Some(array) = array.as_any().downcast_ref::<LargeStringArray>() {
+        return Ok(array.iter().flatten().map(str::to_string).collect());
+    }
+    Err(load_error(format!(
+        "Parquet文件 {} 中的列 {:?} 不是字符串类型，实际类型为 {}",
+        path.display(),
+        column,
+        array.data_type()
+    )))
+}
+
+/// 读取Parquet文件中指定列的全部文本并训练分词器
+///
+/// # Errors
+///
+/// 当文件无法读取、列不存在或不是字符串类型，或训练失败时返回错误
+pub fn train_from_parquet<T, P, I>(
+    tokenizer: &mut T,
+    paths: I,
+    column: &str,
+    vocab_size: u32,
+) -> Result<(), TokenizerError>
+where
+    T: Tokenizer + ?Sized,
+    P: AsRef<Path>,
+    I: IntoIterator<Item = P>,
+{
+    let mut texts = Vec::new();
+    for batch in ParquetTextReader::new(paths, column) {
+        texts.extend(batch?);
+    }
+    log::info!("从Parquet读取 {} 条训练文本", texts.len());
+    tokenizer.train(texts, vocab_size)
+}
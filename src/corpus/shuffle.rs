@@ -0,0 +1,35 @@
+//! 按种子确定性地打乱训练语料
+//!
+//! 语料文件通常按来源或时间排列，流式训练时先读到的部分会影响计数相同时的合并顺序
+//! （见 [`TieBreak::InsertionOrder`](crate::base::merge_job::TieBreak::InsertionOrder)）。
+//! 打乱时每个元素的排序键只由种子和它的位置决定，再并行排序，结果与线程数和调度无关：
+//! 同样的种子和同样的输入（分片列表）总是得到同样的顺序。
+
+use rayon::prelude::*;
+
+/// SplitMix64 的输出函数，把相邻的输入映射为互不相关的64位值
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// 由种子派生出第 `index` 个子种子，用于分片内的批次等需要独立打乱的场景
+#[must_use]
+pub fn derive_seed(seed: u64, index: u64) -> u64 {
+    splitmix64(seed ^ splitmix64(index))
+}
+
+/// 按种子打乱元素顺序，排序键并行计算、并行排序，结果只取决于种子和元素个数
+#[must_use]
+pub fn shuffle_seeded<T: Send>(items: Vec<T>, seed: u64) -> Vec<T> {
+    let mut keyed: Vec<(u64, usize, T)> = items
+        .into_par_iter()
+        .enumerate()
+        .map(|(index, item)| (derive_seed(seed, index as u64), index, item))
+        .collect();
+    // 键相同时按原位置排序，保证顺序唯一
+    keyed.par_sort_unstable_by_key(|&(key, index, _)| (key, index));
+    keyed.into_par_iter().map(|(_, _, item)| item).collect()
+}
@@ -42,6 +42,42 @@ pub enum TokenizerError {
     #[error("无效正则表达式: {message}")]
     InvalidRegex { message: String },
 
+    #[error("未知的标记ID {id}（第 {index} 个标记）")]
+    UnknownTokenId { id: u32, index: usize },
+
+    #[error("模型文件第 {line} 行的词汇表条目无效: {message}")]
+    InvalidVocabEntry { line: usize, message: String },
+
+    #[error("{token:?} 未注册为特殊标记，请先调用 add_special_tokens")]
+    UnregisteredSpecialToken { token: String },
+
+    #[error("批量输入的第 {index} 条处理失败: {source}")]
+    BatchItem {
+        index: usize,
+        source: Box<TokenizerError>,
+    },
+
+    #[error("{context}: {source}")]
+    Context {
+        context: String,
+        source: Box<TokenizerError>,
+    },
+
+    #[error("UTF-8解码失败: 字节偏移 {byte_offset} 处的序列无效（第 {token_index} 个标记）")]
+    InvalidUtf8 {
+        byte_offset: usize,
+        token_index: usize,
+    },
+
+    #[error("输入过长: {bytes} 字节，超过上限 {limit} 字节")]
+    InputTooLarge { bytes: usize, limit: usize },
+
+    #[error("编码结果过长: {tokens} 个标记，超过上限 {limit} 个")]
+    TooManyTokens { tokens: usize, limit: usize },
+
+    #[error("正则表达式匹配失败（字节偏移 {offset}）: {message}")]
+    RegexMatchError { offset: usize, message: String },
+
     #[error("IO错误: {source}")]
     IoError {
         #[from]
@@ -70,6 +106,63 @@ impl From<fancy_regex::Error> for TokenizerError {
 /// 结果类型别名
 pub type Result<T> = std::result::Result<T, TokenizerError>;
 
+impl TokenizerError {
+    /// 在错误信息前加上行号、文件名等上下文，带信息文本的错误类型不变
+    ///
+    /// 没有信息文本的结构化错误（如 [`TokenizerError::UnknownTokenId`]）包装为
+    /// [`TokenizerError::Context`]，原错误保留在 `source` 中
+    #[must_use]
+    pub fn context(self, context: impl std::fmt::Display) -> Self {
+        use TokenizerError::*;
+        let prefix = |message: String| format!("{}: {}", context, message);
+        match self {
+            EncodingError { message } => EncodingError {
+                message: prefix(message),
+            },
+            DecodingError { message } => DecodingError {
+                message: prefix(message),
+            },
+            TrainingError { message } => TrainingError {
+                message: prefix(message),
+            },
+            ModelLoadError { message } => ModelLoadError {
+                message: prefix(message),
+            },
+            ModelSaveError { message } => ModelSaveError {
+                message: prefix(message),
+            },
+            VocabError { message } => VocabError {
+                message: prefix(message),
+            },
+            InputValidationError { message } => InputValidationError {
+                message: prefix(message),
+            },
+            InitializationError { message } => InitializationError {
+                message: prefix(message),
+            },
+            LoadError { message } => LoadError {
+                message: prefix(message),
+            },
+            SplitError { message } => SplitError {
+                message: prefix(message),
+            },
+            InvalidIterator { message } => InvalidIterator {
+                message: prefix(message),
+            },
+            InvalidInput { message } => InvalidInput {
+                message: prefix(message),
+            },
+            InvalidRegex { message } => InvalidRegex {
+                message: prefix(message),
+            },
+            other => Context {
+                context: context.to_string(),
+                source: Box::new(other),
+            },
+        }
+    }
+}
+
 #[cfg(feature = "python")]
 impl From<TokenizerError> for pyo3::PyErr {
     fn from(error: TokenizerError) -> Self {
@@ -111,6 +204,17 @@ impl From<TokenizerError> for pyo3::PyErr {
             TokenizerError::InvalidRegex { message } => {
                 pyo3::exceptions::PyValueError::new_err(message)
             }
+            positioned @ (TokenizerError::UnknownTokenId { .. }
+            | TokenizerError::InvalidVocabEntry { .. }
+            | TokenizerError::UnregisteredSpecialToken { .. }
+            | TokenizerError::BatchItem { .. }
+            | TokenizerError::Context { .. }
+            | TokenizerError::InvalidUtf8 { .. }
+            | TokenizerError::RegexMatchError { .. }
+            | TokenizerError::InputTooLarge { .. }
+            | TokenizerError::TooManyTokens { .. }) => {
+                pyo3::exceptions::PyValueError::new_err(positioned.to_string())
+            }
             TokenizerError::IoError { source } => {
                 pyo3::exceptions::PyIOError::new_err(source.to_string())
             }
@@ -124,6 +228,12 @@ impl From<TokenizerError> for pyo3::PyErr {
     }
 }
 
+/// 转换为Python的 `ValueError`，用于参数无效时不论错误类型都报告 `ValueError` 的接口
+#[cfg(feature = "python")]
+pub(crate) fn py_value_error(error: TokenizerError) -> pyo3::PyErr {
+    pyo3::exceptions::PyValueError::new_err(error.to_string())
+}
+
 /// 创建编码错误
 pub fn encoding_error(message: impl Into<String>) -> TokenizerError {
     TokenizerError::EncodingError {
@@ -166,9 +276,40 @@ pub fn vocab_error(message: impl Into<String>) -> TokenizerError {
     }
 }
 
+/// 创建加载错误，用于读取训练语料等模型文件以外的数据
+pub fn load_error(message: impl Into<String>) -> TokenizerError {
+    TokenizerError::LoadError {
+        message: message.into(),
+    }
+}
+
 /// 创建输入验证错误
 pub fn input_validation_error(message: impl Into<String>) -> TokenizerError {
     TokenizerError::InputValidationError {
         message: message.into(),
     }
 }
+
+/// 根据UTF-8错误的字节偏移创建解码错误，并定位该字节所在的标记序号
+///
+/// `token_lens` 依次给出每个标记解码后的字节长度
+pub fn invalid_utf8_error(
+    error: &std::str::Utf8Error,
+    token_lens: impl IntoIterator<Item = usize>,
+) -> TokenizerError {
+    let byte_offset = error.valid_up_to();
+    let mut end = 0;
+    let mut token_index = 0;
+    for (index, len) in token_lens.into_iter().enumerate() {
+        token_index = index;
+        end += len;
+        if end > byte_offset {
+            break;
+        }
+    }
+
+    TokenizerError::InvalidUtf8 {
+        byte_offset,
+        token_index,
+    }
+}
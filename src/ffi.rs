@@ -0,0 +1,247 @@
+//! C ABI 接口
+//!
+//! 供 C/C++ 推理引擎直接链接分词器。头文件由 cbindgen 生成，位于 `include/zero_tokenizer.h`。
+//!
+//! 所有函数在失败时返回空指针或非零错误码，错误信息可通过
+//! [`zero_tokenizer_last_error`] 获取（每个线程独立保存）。
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use crate::base::resources::ResourceConfig;
+use crate::base::traits::Tokenizer;
+use crate::bbpe::BBPETokenizer;
+use crate::error::{decoding_error, input_validation_error, TokenizerError};
+use crate::unigram::UnigramTokenizer;
+use crate::wordpiece::WordPieceTokenizer;
+
+/// 分词器类型
+///
+/// C 接口以 `uint32_t` 传入类型值，越界的值返回错误而不是被解释为枚举
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZeroTokenizerKind {
+    /// 字节级BPE
+    Bbpe = 0,
+    /// Unigram
+    Unigram = 1,
+    /// WordPiece
+    WordPiece = 2,
+}
+
+impl TryFrom<u32> for ZeroTokenizerKind {
+    type Error = TokenizerError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Bbpe),
+            1 => Ok(Self::Unigram),
+            2 => Ok(Self::WordPiece),
+            _ => Err(input_validation_error(format!(
+                "无效的分词器类型: {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// 不透明的分词器句柄
+pub struct ZeroTokenizer {
+    inner: Box<dyn Tokenizer<TokenId = u32> + Send + Sync>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    // 错误信息中的空字节会导致 CString 构造失败，替换掉以保证总能返回信息
+    let message = CString::new(message.replace('\0', "\\0")).unwrap_or_default();
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(message));
+}
+
+/// 执行闭包并把错误和panic转换为最近错误信息
+fn guard<T>(f: impl FnOnce() -> Result<T, TokenizerError>) -> Option<T> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(error)) => {
+            set_last_error(error.to_string());
+            None
+        }
+        Err(_) => {
+            set_last_error("分词器内部发生panic".to_string());
+            None
+        }
+    }
+}
+
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, TokenizerError> {
+    if ptr.is_null() {
+        return Err(input_validation_error(format!("参数 {} 为空指针", name)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|e| input_validation_error(format!("参数 {} 不是有效的UTF-8: {}", name, e)))
+}
+
+/// 从文件加载分词器
+///
+/// `kind` 取 [`ZeroTokenizerKind`] 中的值，其他值视为错误。
+/// 失败时返回空指针。返回的句柄需要用 [`zero_tokenizer_free`] 释放。
+///
+/// # Safety
+///
+/// `path` 必须是以空字节结尾的有效字符串
+#[no_mangle]
+pub unsafe extern "C" fn zero_tokenizer_new_from_file(
+    path: *const c_char,
+    kind: u32,
+) -> *mut ZeroTokenizer {
+    guard(|| {
+        let path = str_arg(path, "path")?;
+        let kind = ZeroTokenizerKind::try_from(kind)?;
+        // 词汇表来自模型文件，不读取常用汉字字表
+        let resources = ResourceConfig::without_char_dict();
+        let mut inner: Box<dyn Tokenizer<TokenId = u32> + Send + Sync> = match kind {
+            ZeroTokenizerKind::Bbpe => Box::new(BBPETokenizer::new_internal()?),
+            ZeroTokenizerKind::Unigram => Box::new(UnigramTokenizer::with_resources(&resources)?),
+            ZeroTokenizerKind::WordPiece => {
+                Box::new(WordPieceTokenizer::with_resources(&resources)?)
+            }
+        };
+        inner.load(path)?;
+        Ok(Box::into_raw(Box::new(ZeroTokenizer { inner })))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// 释放分词器句柄，传入空指针时不做任何操作
+///
+/// # Safety
+///
+/// `tokenizer` 必须是 [`zero_tokenizer_new_from_file`] 返回且尚未释放的句柄
+#[no_mangle]
+pub unsafe extern "C" fn zero_tokenizer_free(tokenizer: *mut ZeroTokenizer) {
+    if !tokenizer.is_null() {
+        drop(Box::from_raw(tokenizer));
+    }
+}
+
+/// 编码文本，成功返回0并通过 `out_ids`/`out_len` 输出标记ID数组
+///
+/// 输出的数组需要用 [`zero_tokenizer_free_ids`] 释放。失败时返回-1。
+///
+/// # Safety
+///
+/// `tokenizer` 必须是有效句柄，`text` 必须是以空字节结尾的有效字符串，
+/// `out_ids` 和 `out_len` 必须指向可写的内存
+#[no_mangle]
+pub unsafe extern "C" fn zero_tokenizer_encode(
+    tokenizer: *const ZeroTokenizer,
+    text: *const c_char,
+    out_ids: *mut *mut u32,
+    out_len: *mut usize,
+) -> i32 {
+    let encoded = guard(|| {
+        let tokenizer = tokenizer
+            .as_ref()
+            .ok_or_else(|| input_validation_error("参数 tokenizer 为空指针"))?;
+        if out_ids.is_null() || out_len.is_null() {
+            return Err(input_validation_error("输出参数为空指针"));
+        }
+        let text = str_arg(text, "text")?;
+        tokenizer.inner.encode(text)
+    });
+
+    match encoded {
+        Some(ids) => {
+            // 转换为boxed slice，保证容量等于长度，释放时只需要长度
+            let ids = ids.into_boxed_slice();
+            *out_len = ids.len();
+            *out_ids = Box::into_raw(ids).cast::<u32>();
+            0
+        }
+        None => -1,
+    }
+}
+
+/// 释放 [`zero_tokenizer_encode`] 输出的标记ID数组
+///
+/// # Safety
+///
+/// `ids` 和 `len` 必须是同一次 [`zero_tokenizer_encode`] 调用的输出，且尚未释放
+#[no_mangle]
+pub unsafe extern "C" fn zero_tokenizer_free_ids(ids: *mut u32, len: usize) {
+    if !ids.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ids, len)));
+    }
+}
+
+/// 解码标记ID数组，返回以空字节结尾的字符串
+///
+/// 返回的字符串需要用 [`zero_tokenizer_free_string`] 释放。失败时返回空指针。
+///
+/// # Safety
+///
+/// `tokenizer` 必须是有效句柄，`ids` 必须指向至少 `len` 个元素（`len` 为0时可以为空指针）
+#[no_mangle]
+pub unsafe extern "C" fn zero_tokenizer_decode(
+    tokenizer: *const ZeroTokenizer,
+    ids: *const u32,
+    len: usize,
+) -> *mut c_char {
+    guard(|| {
+        let tokenizer = tokenizer
+            .as_ref()
+            .ok_or_else(|| input_validation_error("参数 tokenizer 为空指针"))?;
+        let ids = if len == 0 {
+            &[]
+        } else if ids.is_null() {
+            return Err(input_validation_error("参数 ids 为空指针"));
+        } else {
+            std::slice::from_raw_parts(ids, len)
+        };
+        let text = tokenizer.inner.decode(ids)?;
+        CString::new(text).map(CString::into_raw).map_err(|e| {
+            decoding_error(format!(
+                "解码结果包含空字节（字节偏移 {}）",
+                e.nul_position()
+            ))
+        })
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// 释放 [`zero_tokenizer_decode`] 返回的字符串
+///
+/// # Safety
+///
+/// `text` 必须是 [`zero_tokenizer_decode`] 返回且尚未释放的指针
+#[no_mangle]
+pub unsafe extern "C" fn zero_tokenizer_free_string(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+/// 获取词汇表大小，句柄为空时返回0
+///
+/// # Safety
+///
+/// `tokenizer` 必须是有效句柄或空指针
+#[no_mangle]
+pub unsafe extern "C" fn zero_tokenizer_vocab_size(tokenizer: *const ZeroTokenizer) -> usize {
+    tokenizer
+        .as_ref()
+        .map_or(0, |tokenizer| tokenizer.inner.vocab_size())
+}
+
+/// 获取当前线程最近一次错误的信息，没有错误时返回空指针
+///
+/// 返回的指针在同一线程下一次调用失败前有效，调用方不需要释放
+#[no_mangle]
+pub extern "C" fn zero_tokenizer_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| slot.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
@@ -0,0 +1,133 @@
+//! 流式增量解码
+
+use crate::base::decoder::DecoderKind;
+use crate::base::traits::VocabBytes;
+use crate::error::{decoding_error, TokenizerError};
+
+/// 逐个接收生成的标记ID，输出新增的文本片段
+///
+/// 字节级分词器常把一个多字节字符拆成多个字节标记，单独解码会得到不完整的UTF-8。
+/// 不完整的尾部字节会被缓存，直到后续标记把字符补全才一起输出。每个标记都按分词器的
+/// 解码器还原，WordPiece解码器清理标点前的空格时可能用到后续标记，最后一个词会暂缓输出。
+/// 因此拼接所有片段与一次性解码全部标记的结果相同，每一步的开销与历史长度无关。
+#[derive(Debug)]
+pub struct DecodeStream<'a, T: ?Sized> {
+    tokenizer: &'a T,
+    state: StreamState,
+}
+
+impl<'a, T> DecodeStream<'a, T>
+where
+    T: VocabBytes<TokenId = u32> + ?Sized,
+{
+    /// 创建解码流
+    pub fn new(tokenizer: &'a T) -> Self {
+        Self {
+            tokenizer,
+            state: StreamState::default(),
+        }
+    }
+
+    /// 接收一个新生成的标记，返回可以输出的新文本，字符尚不完整时返回 `None`
+    ///
+    /// 无论后续字节如何都无法构成有效UTF-8的字节输出为替换字符 U+FFFD
+    ///
+    /// # Errors
+    ///
+    /// 当标记ID不在词汇表中时返回错误
+    pub fn step(&mut self, id: u32) -> Result<Option<String>, TokenizerError> {
+        self.state.step(self.tokenizer, id)
+    }
+
+    /// 生成结束时取出暂缓输出的文本，剩余的不完整字节按替换字符输出
+    pub fn flush(&mut self) -> Option<String> {
+        self.state.flush()
+    }
+
+    /// 缓存中等待补全的字节数
+    #[must_use]
+    pub fn pending_len(&self) -> usize {
+        self.state.pending.len()
+    }
+
+    /// 清空缓存，开始新的生成
+    pub fn reset(&mut self) {
+        self.state = StreamState::default();
+    }
+}
+
+/// 不持有分词器的流式解码状态，供 [`DecodeStream`] 和
+/// [`StopSequenceMatcher`](crate::generation::StopSequenceMatcher) 共用
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StreamState {
+    /// 尚未构成完整字符的尾部字节
+    pending: Vec<u8>,
+    /// 已还原但整理结果可能受后续标记影响的文本
+    held: String,
+    /// 是否已接收过标记，部分解码器对第一个标记有特殊处理
+    started: bool,
+    decoder: DecoderKind,
+}
+
+impl StreamState {
+    /// 接收一个标记，返回可以输出的新文本
+    pub(crate) fn step<T>(
+        &mut self,
+        tokenizer: &T,
+        id: u32,
+    ) -> Result<Option<String>, TokenizerError>
+    where
+        T: VocabBytes<TokenId = u32> + ?Sized,
+    {
+        let piece = tokenizer
+            .decoded_piece(&id)
+            .ok_or_else(|| decoding_error(format!("未知的标记ID {}", id)))?;
+        self.decoder = tokenizer.decoder();
+        self.pending
+            .extend_from_slice(&self.decoder.piece(&piece, !self.started));
+        self.started = true;
+
+        let mut text = String::new();
+        let mut start = 0;
+        while start < self.pending.len() {
+            match std::str::from_utf8(&self.pending[start..]) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    start = self.pending.len();
+                }
+                Err(e) => {
+                    let valid_up_to = start + e.valid_up_to();
+                    // valid_up_to 之前的字节已经确认是有效UTF-8
+                    text.push_str(&String::from_utf8_lossy(&self.pending[start..valid_up_to]));
+                    start = valid_up_to;
+                    match e.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            start += len;
+                        }
+                        // 尾部是不完整的字符，等待后续字节
+                        None => break,
+                    }
+                }
+            }
+        }
+        self.pending.drain(..start);
+
+        self.held.push_str(&text);
+        let rest = self
+            .held
+            .split_off(self.decoder.stable_prefix_len(&self.held));
+        let stable = std::mem::replace(&mut self.held, rest);
+        let text = self.decoder.finish_text(stable);
+        Ok((!text.is_empty()).then_some(text))
+    }
+
+    /// 取出暂缓输出的文本和剩余的不完整字节
+    pub(crate) fn flush(&mut self) -> Option<String> {
+        let mut text = std::mem::take(&mut self.held);
+        text.push_str(&String::from_utf8_lossy(&self.pending));
+        self.pending.clear();
+        let text = self.decoder.finish_text(text);
+        (!text.is_empty()).then_some(text)
+    }
+}
@@ -0,0 +1,12 @@
+//! 文本生成辅助工具
+//!
+//! 在语言模型逐个生成标记时使用，避免每一步都重新解码完整的历史；
+//! 以及按词汇表生成随机文本，用于压测和往返测试。
+
+pub mod decode;
+pub mod sample;
+pub mod stop;
+
+pub use decode::DecodeStream;
+pub use sample::{sample_text, TextSampler};
+pub use stop::{StopMatch, StopSequenceMatcher};
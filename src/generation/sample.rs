@@ -0,0 +1,153 @@
+//! 按词汇表生成随机文本
+//!
+//! 从词汇表中随机抽取标记再解码，得到由真实标记拼成的文本，用于服务端压测和
+//! 编码解码往返的模糊测试。
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::base::traits::{SpecialTokenizer, VocabBytes};
+use crate::error::{vocab_error, TokenizerError};
+
+/// 随机文本生成器
+///
+/// 候选标记为词汇表中单独解码即为合法UTF-8的普通标记，不包括特殊标记、
+/// 回退标记（字节回退和未知标记）以及只含多字节字符一部分的字节级标记，
+/// 因此任意标记序列都能解码成功。
+#[derive(Debug, Clone)]
+pub struct TextSampler {
+    ids: Vec<u32>,
+    /// 按使用次数加权时的分布，为 `None` 时等概率抽取
+    weights: Option<WeightedIndex<u64>>,
+    rng: StdRng,
+}
+
+impl TextSampler {
+    /// 创建等概率抽取候选标记的生成器，传入 `seed` 时结果可复现
+    ///
+    /// # Errors
+    ///
+    /// 当词汇表中没有候选标记时返回错误
+    pub fn new<T>(tokenizer: &T, seed: Option<u64>) -> Result<Self, TokenizerError>
+    where
+        T: SpecialTokenizer + VocabBytes<TokenId = u32> + ?Sized,
+    {
+        let ids = candidates(tokenizer);
+        if ids.is_empty() {
+            return Err(vocab_error("词汇表中没有可用于生成文本的标记"));
+        }
+        Ok(Self {
+            ids,
+            weights: None,
+            rng: seeded(seed),
+        })
+    }
+
+    /// 创建按使用次数加权抽取的生成器
+    ///
+    /// `counts` 为 `(标记ID, 次数)` 列表，通常由 [`token_usage`](crate::analysis::token_usage)
+    /// 在训练语料上统计得到，生成的文本与训练语料的标记分布相近。
+    /// 不是候选标记或次数为0的标记不会被抽取。
+    ///
+    /// # Errors
+    ///
+    /// 当 `counts` 中没有次数大于0的候选标记时返回错误
+    pub fn with_counts<T>(
+        tokenizer: &T,
+        counts: &[(u32, u64)],
+        seed: Option<u64>,
+    ) -> Result<Self, TokenizerError>
+    where
+        T: SpecialTokenizer + VocabBytes<TokenId = u32> + ?Sized,
+    {
+        let allowed = candidates(tokenizer);
+        let (ids, weights): (Vec<u32>, Vec<u64>) = counts
+            .iter()
+            .filter(|&&(id, count)| count > 0 && allowed.binary_search(&id).is_ok())
+            .copied()
+            .unzip();
+        let weights = WeightedIndex::new(weights)
+            .map_err(|_| vocab_error("使用次数中没有可用于生成文本的标记"))?;
+        Ok(Self {
+            ids,
+            weights: Some(weights),
+            rng: seeded(seed),
+        })
+    }
+
+    /// 抽取 `n_tokens` 个标记ID
+    pub fn sample_ids(&mut self, n_tokens: usize) -> Vec<u32> {
+        (0..n_tokens)
+            .map(|_| {
+                let index = match &self.weights {
+                    Some(weights) => weights.sample(&mut self.rng),
+                    None => self.rng.gen_range(0..self.ids.len()),
+                };
+                self.ids[index]
+            })
+            .collect()
+    }
+
+    /// 抽取 `n_tokens` 个标记并解码为文本
+    ///
+    /// 重新编码得到的标记数通常与 `n_tokens` 不同，因为相邻标记可能被合并或按其他方式切分
+    ///
+    /// # Errors
+    ///
+    /// 当解码失败时返回错误
+    pub fn sample_text<T>(
+        &mut self,
+        tokenizer: &T,
+        n_tokens: usize,
+    ) -> Result<String, TokenizerError>
+    where
+        T: SpecialTokenizer + VocabBytes<TokenId = u32> + ?Sized,
+    {
+        tokenizer.decode(&self.sample_ids(n_tokens))
+    }
+}
+
+/// 等概率抽取 `n_tokens` 个标记并解码为文本，传入 `seed` 时结果可复现
+///
+/// 需要生成多条文本时使用 [`TextSampler`]，避免每次重新收集候选标记
+///
+/// # Errors
+///
+/// 当词汇表中没有候选标记或解码失败时返回错误
+pub fn sample_text<T>(
+    tokenizer: &T,
+    n_tokens: usize,
+    seed: Option<u64>,
+) -> Result<String, TokenizerError>
+where
+    T: SpecialTokenizer + VocabBytes<TokenId = u32> + ?Sized,
+{
+    TextSampler::new(tokenizer, seed)?.sample_text(tokenizer, n_tokens)
+}
+
+/// 按ID升序排列的候选标记
+fn candidates<T>(tokenizer: &T) -> Vec<u32>
+where
+    T: SpecialTokenizer + VocabBytes<TokenId = u32> + ?Sized,
+{
+    let special_tokens = tokenizer.special_tokens();
+    tokenizer
+        .vocab_bytes()
+        .into_iter()
+        .filter(|(id, bytes)| {
+            !bytes.is_empty()
+                && std::str::from_utf8(bytes).is_ok()
+                && !special_tokens.contains_id(*id)
+                && !tokenizer.is_fallback_token(id)
+        })
+        .map(|(id, _)| id)
+        .collect()
+}
+
+fn seeded(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
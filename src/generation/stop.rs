@@ -0,0 +1,146 @@
+//! 流式停止序列检测
+
+use crate::base::traits::VocabBytes;
+use crate::error::TokenizerError;
+use crate::generation::decode::StreamState;
+
+/// 一次停止序列匹配
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StopMatch {
+    /// 匹配到的停止序列在配置中的序号
+    pub stop_index: usize,
+    /// 停止序列在已生成文本中的起始字节偏移，截断到此处即可去掉停止序列
+    pub offset: usize,
+}
+
+/// 逐个接收生成的标记ID，检测解码后的文本中是否出现停止序列
+///
+/// 按字节比较，因此停止序列可以跨越任意多个标记，包括被拆成多个字节标记的多字节字符。
+/// [`StopSequenceMatcher::push`] 与 [`DecodeStream`](crate::generation::DecodeStream) 一样按分词器的解码器还原文本，
+/// 匹配位置是一次性解码结果中的偏移。只保留最长停止序列长度的尾部字节，每一步的开销与历史长度无关。
+#[derive(Debug, Clone)]
+pub struct StopSequenceMatcher {
+    stops: Vec<Vec<u8>>,
+    /// 最长停止序列的字节数
+    max_len: usize,
+    /// 最近生成的尾部字节
+    tail: Vec<u8>,
+    /// 已生成的总字节数
+    consumed: usize,
+    /// 按标记接收时的流式解码状态
+    stream: StreamState,
+}
+
+impl StopSequenceMatcher {
+    /// 使用一组停止序列创建匹配器，空字符串会被忽略
+    pub fn new<I, S>(stops: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let stops: Vec<Vec<u8>> = stops
+            .into_iter()
+            .map(|stop| stop.as_ref().as_bytes().to_vec())
+            .filter(|stop| !stop.is_empty())
+            .collect();
+        let max_len = stops.iter().map(Vec::len).max().unwrap_or(0);
+
+        Self {
+            stops,
+            max_len,
+            tail: Vec::with_capacity(max_len * 2),
+            consumed: 0,
+            stream: StreamState::default(),
+        }
+    }
+
+    /// 接收一个新生成的标记，返回在该标记中结束的停止序列
+    ///
+    /// 同一标记中结束多个停止序列时，返回起始位置最靠前的一个。解码流暂缓输出的文本
+    /// （不完整的字符、WordPiece的最后一个词）在后续标记到达或调用 [`StopSequenceMatcher::finish`] 时才参与匹配
+    ///
+    /// # Errors
+    ///
+    /// 当标记ID不在词汇表中时返回错误
+    pub fn push<T>(&mut self, tokenizer: &T, id: u32) -> Result<Option<StopMatch>, TokenizerError>
+    where
+        T: VocabBytes<TokenId = u32> + ?Sized,
+    {
+        Ok(self
+            .stream
+            .step(tokenizer, id)?
+            .and_then(|text| self.push_bytes(text.as_bytes())))
+    }
+
+    /// 生成结束时匹配解码流中暂缓输出的文本
+    pub fn finish(&mut self) -> Option<StopMatch> {
+        self.stream
+            .flush()
+            .and_then(|text| self.push_bytes(text.as_bytes()))
+    }
+
+    /// 接收一段新生成的字节，返回在其中结束的停止序列
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Option<StopMatch> {
+        if self.stops.is_empty() || bytes.is_empty() {
+            self.consumed += bytes.len();
+            return None;
+        }
+
+        // 只有结束位置落在新字节中的匹配才需要检查
+        let previous = self.tail.len();
+        self.tail.extend_from_slice(bytes);
+        let tail_start = self.consumed - previous;
+        self.consumed += bytes.len();
+
+        let found = self
+            .stops
+            .iter()
+            .enumerate()
+            .filter_map(|(stop_index, stop)| {
+                let first = previous.saturating_sub(stop.len() - 1);
+                self.tail[first..]
+                    .windows(stop.len())
+                    .position(|window| window == stop.as_slice())
+                    .map(|position| (first + position, stop_index))
+            })
+            .min();
+
+        // 保留足够匹配跨标记停止序列的尾部
+        let keep = self.max_len - 1;
+        if self.tail.len() > keep {
+            self.tail.drain(..self.tail.len() - keep);
+        }
+
+        found.map(|(position, stop_index)| StopMatch {
+            stop_index,
+            offset: tail_start + position,
+        })
+    }
+
+    /// 尾部可能是某个停止序列开头的最长字节数
+    ///
+    /// 流式输出时应暂缓输出这部分文本，直到确认它不是停止序列
+    #[must_use]
+    pub fn pending_len(&self) -> usize {
+        (1..=self.tail.len())
+            .rev()
+            .find(|&len| {
+                let suffix = &self.tail[self.tail.len() - len..];
+                self.stops.iter().any(|stop| stop.starts_with(suffix))
+            })
+            .unwrap_or(0)
+    }
+
+    /// 已接收的总字节数
+    #[must_use]
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// 清空历史，开始新的生成
+    pub fn reset(&mut self) {
+        self.tail.clear();
+        self.consumed = 0;
+        self.stream = StreamState::default();
+    }
+}
@@ -1,12 +1,30 @@
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
 
+#[macro_use]
+mod macros;
+
+pub mod analysis;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod auto;
 pub mod base;
+pub mod batching;
 pub mod bbpe;
 pub mod bpe;
+pub mod corpus;
 pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod generation;
+pub mod pipeline;
 pub mod prelude;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod telemetry;
 pub mod unigram;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 pub mod wordpiece;
 
 /// 导出所有分词器到Python
@@ -18,5 +36,6 @@ fn _zero_tokenizer(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<bbpe::BBPETokenizer>()?;
     m.add_class::<unigram::UnigramTokenizer>()?;
     m.add_class::<wordpiece::WordPieceTokenizer>()?;
+    m.add_function(wrap_pyfunction!(base::registry::py_list_models, m)?)?;
     Ok(())
 }
@@ -0,0 +1,20 @@
+//! 内部宏
+
+/// 启用 `tracing` 特性时进入一个span，直到当前作用域结束；未启用时为空操作
+///
+/// 默认使用 `info` 级别，热路径（如编码）可使用 `debug:` 前缀降低级别：
+///
+/// ```ignore
+/// trace_span!("bbpe.train", vocab_size);
+/// trace_span!(debug: "bbpe.encode", text_len = text.len());
+/// ```
+macro_rules! trace_span {
+    (debug: $name:expr $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($name $(, $($fields)*)?).entered();
+    };
+    ($name:expr $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($name $(, $($fields)*)?).entered();
+    };
+}
@@ -0,0 +1,473 @@
+//! 离线批量编码管线
+//!
+//! 为数据集预处理设计：读取线程按行读取语料（纯文本或JSONL记录）并分块，工作线程池并行编码，调用线程按原顺序写出。
+//! 各阶段之间使用有界通道，同时在途的块数有上限，写出跟不上时读取会被阻塞，
+//! 因此内存占用与语料大小无关。
+
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+
+use crate::base::traits::Tokenizer;
+use crate::error::{encoding_error, TokenizerError};
+
+/// 编码结果的写出目标
+pub trait IdSink {
+    /// 写出一条文本的标记ID
+    ///
+    /// # Errors
+    ///
+    /// 当写入失败时返回错误
+    fn write_ids(&mut self, ids: &[u32]) -> io::Result<()>;
+
+    /// 全部文本写出后调用，写出缓冲的数据
+    ///
+    /// # Errors
+    ///
+    /// 当写入失败时返回错误
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 长度前缀的二进制格式：每条文本先写标记数，再写标记ID，均为小端 `u32`
+#[derive(Debug)]
+pub struct LengthPrefixedWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> LengthPrefixedWriter<W> {
+    /// 包装一个写出目标，建议传入带缓冲的写出器
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// 取回内部的写出目标
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> IdSink for LengthPrefixedWriter<W> {
+    fn write_ids(&mut self, ids: &[u32]) -> io::Result<()> {
+        let len = u32::try_from(ids.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "单条文本的标记数超出u32"))?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        for id in ids {
+            self.writer.write_all(&id.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// 按固定标记数切分的 `.npy` 分片
+///
+/// 每个分片是一维 `uint32` 数组，文本的标记ID依次拼接，可选地在每条文本后追加分隔标记。
+/// 文本可能跨越两个分片。分片命名为 `{prefix}_{序号:05}.npy`。
+#[derive(Debug)]
+pub struct NpyShardWriter {
+    dir: PathBuf,
+    prefix: String,
+    shard_tokens: usize,
+    separator: Option<u32>,
+    buffer: Vec<u32>,
+    shards: Vec<PathBuf>,
+}
+
+impl NpyShardWriter {
+    /// 在 `dir` 中写出分片，每个分片包含 `shard_tokens` 个标记（最后一个分片可能更少）
+    ///
+    /// # Errors
+    ///
+    /// 当目录无法创建时返回错误
+    pub fn new(dir: impl AsRef<Path>, prefix: &str, shard_tokens: usize) -> io::Result<Self> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            prefix: prefix.to_string(),
+            shard_tokens: shard_tokens.max(1),
+            separator: None,
+            buffer: Vec::new(),
+            shards: Vec::new(),
+        })
+    }
+
+    /// 在每条文本之后追加分隔标记（如文档结束标记）
+    #[must_use]
+    pub fn with_separator(mut self, separator: u32) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+
+    /// 已写出的分片路径
+    #[must_use]
+    pub fn shards(&self) -> &[PathBuf] {
+        &self.shards
+    }
+
+    fn write_shard(&mut self, len: usize) -> io::Result<()> {
+        let path = self
+            .dir
+            .join(format!("{}_{:05}.npy", self.prefix, self.shards.len()));
+        let mut writer = BufWriter::new(File::create(&path)?);
+        write_npy_u32(&mut writer, &self.buffer[..len])?;
+        writer.flush()?;
+        self.buffer.drain(..len);
+        self.shards.push(path);
+        Ok(())
+    }
+}
+
+impl IdSink for NpyShardWriter {
+    fn write_ids(&mut self, ids: &[u32]) -> io::Result<()> {
+        self.buffer.extend_from_slice(ids);
+        self.buffer.extend(self.separator);
+        while self.buffer.len() >= self.shard_tokens {
+            self.write_shard(self.shard_tokens)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.write_shard(self.buffer.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// 以 `.npy` 1.0 格式写出一维小端 `uint32` 数组
+fn write_npy_u32<W: Write>(writer: &mut W, values: &[u32]) -> io::Result<()> {
+    const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+
+    let mut header = format!(
+        "{{'descr': '<u4', 'fortran_order': False, 'shape': ({},), }}",
+        values.len()
+    );
+    // 魔数、版本号、头部长度和头部的总长度按64字节对齐，头部以换行结尾
+    let unpadded = MAGIC.len() + 2 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for value in values {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// 管线的并行度和内存预算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamConfig {
+    /// 编码线程数，为0时使用CPU核数
+    pub workers: usize,
+    /// 每块包含的行数，为0时按1处理
+    pub chunk_lines: usize,
+    /// 同时在途（已读取、尚未写出）的最大块数，为0时按1处理
+    pub max_in_flight: usize,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            workers: 0,
+            chunk_lines: 1024,
+            max_in_flight: 64,
+        }
+    }
+}
+
+/// 管线处理结果统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamStats {
+    /// 处理的文本行数
+    pub texts: usize,
+    /// 写出的标记总数（不含分隔标记）
+    pub tokens: usize,
+    /// 读取的字节数（不含换行）
+    pub bytes: usize,
+}
+
+/// 一块待编码的文本：块序号、首行行号和文本
+type Chunk = (usize, usize, Vec<String>);
+/// 一块处理结果：块序号和每行的输出
+type Processed<O> = (usize, Result<Vec<O>, TokenizerError>);
+
+/// 按行读取语料，并行编码后按原顺序写出到 `sink`
+///
+/// # Errors
+///
+/// 当读取、编码或写出失败时返回错误，编码错误包含出错的行号（从0开始）。
+/// 出错时管线会尽快停止，已写出的内容不会回滚。
+pub fn encode_stream<T, R, S>(
+    tokenizer: &T,
+    reader: R,
+    sink: &mut S,
+    config: StreamConfig,
+) -> Result<StreamStats, TokenizerError>
+where
+    T: Tokenizer<TokenId = u32> + Sync + ?Sized,
+    R: BufRead + Send,
+    S: IdSink + ?Sized,
+{
+    let encode = |text: &str| {
+        tokenizer
+            .encode(text)
+            .map_err(|e| encoding_error(format!("编码失败: {}", e)))
+    };
+    let write = |ids: Vec<u32>| {
+        sink.write_ids(&ids)
+            .map_err(|e| encoding_error(format!("写出失败: {}", e)))?;
+        Ok(ids.len())
+    };
+    let stats = process_stream(reader, config, &encode, write)?;
+    sink.finish()
+        .map_err(|e| encoding_error(format!("写出失败: {}", e)))?;
+    Ok(stats)
+}
+
+/// JSONL记录中需要编码的字段和写回方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonlFields {
+    /// 需要编码的字符串字段，每个字段 `f` 的标记ID写回到 `f_ids`
+    pub fields: Vec<String>,
+    /// 是否同时把标记数写回到 `f_num_tokens`
+    pub counts: bool,
+}
+
+impl JsonlFields {
+    /// 编码 `fields` 中的字段，不写回标记数
+    pub fn new<S: Into<String>>(fields: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            fields: fields.into_iter().map(Into::into).collect(),
+            counts: false,
+        }
+    }
+
+    /// 同时写回每个字段的标记数
+    #[must_use]
+    pub fn with_counts(mut self) -> Self {
+        self.counts = true;
+        self
+    }
+
+    /// 解析一条记录，编码各字段并写回，返回序列化后的记录和标记总数
+    fn encode_record<T>(&self, tokenizer: &T, line: &str) -> Result<(String, usize), TokenizerError>
+    where
+        T: Tokenizer<TokenId = u32> + ?Sized,
+    {
+        let mut record: serde_json::Map<String, serde_json::Value> = serde_json::from_str(line)
+            .map_err(|e| encoding_error(format!("不是有效的JSON对象: {}", e)))?;
+        let mut tokens = 0;
+        for field in &self.fields {
+            let text = match record.get(field) {
+                Some(serde_json::Value::String(text)) => text,
+                Some(_) => return Err(encoding_error(format!("字段 {} 不是字符串", field))),
+                None => return Err(encoding_error(format!("缺少字段 {}", field))),
+            };
+            let ids = tokenizer
+                .encode(text)
+                .map_err(|e| encoding_error(format!("字段 {} 编码失败: {}", field, e)))?;
+            tokens += ids.len();
+            if self.counts {
+                record.insert(format!("{}_num_tokens", field), ids.len().into());
+            }
+            record.insert(format!("{}_ids", field), ids.into());
+        }
+        let line = serde_json::to_string(&record)
+            .map_err(|e| encoding_error(format!("序列化失败: {}", e)))?;
+        Ok((line, tokens))
+    }
+}
+
+/// 逐条读取JSONL记录，并行编码指定字段，把标记ID写回记录后按原顺序逐行写出到 `writer`
+///
+/// 记录的其余字段原样保留。统计中的 `texts` 为记录数，`tokens` 为各字段标记数之和。
+///
+/// # Errors
+///
+/// 当读取、解析、编码或写出失败时返回错误，包含出错的行号（从0开始）；
+/// 记录不是JSON对象、缺少字段或字段不是字符串都视为错误。
+pub fn encode_jsonl_stream<T, R, W>(
+    tokenizer: &T,
+    reader: R,
+    writer: &mut W,
+    fields: &JsonlFields,
+    config: StreamConfig,
+) -> Result<StreamStats, TokenizerError>
+where
+    T: Tokenizer<TokenId = u32> + Sync + ?Sized,
+    R: BufRead + Send,
+    W: Write + ?Sized,
+{
+    let encode = |line: &str| fields.encode_record(tokenizer, line);
+    let write = |(line, tokens): (String, usize)| {
+        writeln!(writer, "{}", line).map_err(|e| encoding_error(format!("写出失败: {}", e)))?;
+        Ok(tokens)
+    };
+    let stats = process_stream(reader, config, &encode, write)?;
+    writer
+        .flush()
+        .map_err(|e| encoding_error(format!("写出失败: {}", e)))?;
+    Ok(stats)
+}
+
+/// 管线主体：读取线程分块，`workers` 个线程对每行调用 `process`，
+/// 调用线程按原顺序对结果调用 `write`，`write` 返回写出的标记数
+fn process_stream<R, O, P, W>(
+    reader: R,
+    config: StreamConfig,
+    process: &P,
+    write: W,
+) -> Result<StreamStats, TokenizerError>
+where
+    R: BufRead + Send,
+    O: Send,
+    P: Fn(&str) -> Result<O, TokenizerError> + Sync,
+    W: FnMut(O) -> Result<usize, TokenizerError>,
+{
+    let workers = match config.workers {
+        0 => std::thread::available_parallelism().map_or(1, usize::from),
+        n => n,
+    };
+    let chunk_lines = config.chunk_lines.max(1);
+    let max_in_flight = config.max_in_flight.max(1);
+
+    let (chunk_sender, chunk_receiver) = mpsc::sync_channel::<Chunk>(max_in_flight);
+    let (result_sender, result_receiver) = mpsc::sync_channel::<Processed<O>>(max_in_flight);
+    // 每读取一块取走一个名额，写出后归还，限制在途的块数
+    let (credit_sender, credit_receiver) = mpsc::sync_channel::<()>(max_in_flight);
+    for _ in 0..max_in_flight {
+        let _ = credit_sender.send(());
+    }
+    let chunk_receiver = Arc::new(Mutex::new(chunk_receiver));
+
+    std::thread::scope(|scope| {
+        let reading =
+            scope.spawn(move || read_chunks(reader, chunk_lines, &chunk_sender, &credit_receiver));
+
+        for _ in 0..workers {
+            let chunk_receiver = Arc::clone(&chunk_receiver);
+            let result_sender = result_sender.clone();
+            scope.spawn(move || process_chunks(process, &chunk_receiver, &result_sender));
+        }
+        drop(chunk_receiver);
+        drop(result_sender);
+
+        let written = write_in_order(&result_receiver, write, &credit_sender);
+        // 提前出错时关闭通道，让读取和编码线程退出
+        drop(result_receiver);
+        drop(credit_sender);
+        let bytes = reading
+            .join()
+            .map_err(|_| encoding_error("读取线程异常终止"))??;
+
+        let (texts, tokens) = written?;
+        Ok(StreamStats {
+            texts,
+            tokens,
+            bytes,
+        })
+    })
+}
+
+/// 读取线程：按行分块发送，返回读取的字节数
+fn read_chunks<R: BufRead>(
+    reader: R,
+    chunk_lines: usize,
+    chunks: &SyncSender<Chunk>,
+    credits: &Receiver<()>,
+) -> Result<usize, TokenizerError> {
+    let mut bytes = 0;
+    let mut seq = 0;
+    let mut first_line = 0;
+    let mut chunk = Vec::with_capacity(chunk_lines);
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| encoding_error(format!("读取第 {} 行失败: {}", index, e)))?;
+        bytes += line.len();
+        chunk.push(line);
+        if chunk.len() == chunk_lines {
+            let full = std::mem::replace(&mut chunk, Vec::with_capacity(chunk_lines));
+            // 名额或通道关闭说明写出端已经停止，不再继续读取
+            if credits.recv().is_err() || chunks.send((seq, first_line, full)).is_err() {
+                return Ok(bytes);
+            }
+            seq += 1;
+            first_line = index + 1;
+        }
+    }
+
+    if !chunk.is_empty() && credits.recv().is_ok() {
+        let _ = chunks.send((seq, first_line, chunk));
+    }
+    Ok(bytes)
+}
+
+/// 编码线程：从共享队列取块处理，直到队列关闭
+fn process_chunks<O, P>(
+    process: &P,
+    chunks: &Mutex<Receiver<Chunk>>,
+    results: &SyncSender<Processed<O>>,
+) where
+    P: Fn(&str) -> Result<O, TokenizerError>,
+{
+    loop {
+        let received = chunks
+            .lock()
+            .map_err(|_| ())
+            .and_then(|c| c.recv().map_err(|_| ()));
+        let Ok((seq, first_line, texts)) = received else {
+            return;
+        };
+
+        let processed = texts
+            .iter()
+            .enumerate()
+            .map(|(offset, text)| {
+                process(text)
+                    .map_err(|e| encoding_error(format!("第 {} 行{}", first_line + offset, e)))
+            })
+            .collect();
+        if results.send((seq, processed)).is_err() {
+            return;
+        }
+    }
+}
+
+/// 在调用线程中按块序号重新排序并写出，返回写出的行数和标记数
+fn write_in_order<O, W>(
+    results: &Receiver<Processed<O>>,
+    mut write: W,
+    credits: &SyncSender<()>,
+) -> Result<(usize, usize), TokenizerError>
+where
+    W: FnMut(O) -> Result<usize, TokenizerError>,
+{
+    let mut pending = std::collections::BTreeMap::new();
+    let mut next = 0;
+    let mut texts = 0;
+    let mut tokens = 0;
+
+    for (seq, processed) in results {
+        pending.insert(seq, processed);
+        while let Some(processed) = pending.remove(&next) {
+            for output in processed? {
+                tokens += write(output)?;
+                texts += 1;
+            }
+            next += 1;
+            let _ = credits.send(());
+        }
+    }
+
+    Ok((texts, tokens))
+}
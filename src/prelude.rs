@@ -2,30 +2,45 @@
 //!
 //! 导出所有常用的类型和特征，方便使用。
 
-pub use crate::base::traits::{SubwordTokenizer, Tokenizer};
+pub use crate::auto::{AutoTokenizer, DynTokenizer};
+pub use crate::base::decoder::{Decoder, DecoderKind};
+pub use crate::base::encoding::{Encoding, PairEncoding};
+pub use crate::base::integrity::{IntegrityIssue, IntegrityReport};
+pub use crate::base::limits::{InputLimits, LimitPolicy};
+pub use crate::base::normalizer::{Normalizer, NormalizerStep};
+pub use crate::base::packed::{PackedBatch, PackedSequences};
+pub use crate::base::padding::{
+    BatchEncoding, EncodeOptions, PaddingConfig, PaddingDirection, TruncationConfig,
+    TruncationStrategy,
+};
+pub use crate::base::pre_tokenizer::{PreTokenizer, PreTokenizerPipeline, PreTokenizerStep};
+pub use crate::base::registry::{ModelRegistry, NamedModel};
+pub use crate::base::special_tokens::SpecialTokens;
+pub use crate::base::trainer_config::TrainerConfig;
+pub use crate::base::traits::{SpecialTokenizer, SubwordTokenizer, Tokenizer, VocabBytes};
 pub use crate::bbpe::BBPETokenizer as BBPE;
-#[cfg(feature = "python")]
+pub use crate::bbpe::ModelParts;
 pub use crate::bpe::Tokenizer as BPE;
+pub use crate::error::TokenizerError;
 pub use crate::unigram::UnigramTokenizer as Unigram;
 pub use crate::wordpiece::WordPieceTokenizer as WordPiece;
 
 /// 创建BPE分词器的便捷函数
-#[cfg(feature = "python")]
-pub fn bpe() -> Result<BPE, String> {
+pub fn bpe() -> Result<BPE, TokenizerError> {
     BPE::_new_internal()
 }
 
 /// 创建BBPE分词器的便捷函数
-pub fn bbpe() -> Result<BBPE, String> {
+pub fn bbpe() -> Result<BBPE, TokenizerError> {
     BBPE::new_internal()
 }
 
 /// 创建Unigram分词器的便捷函数
-pub fn unigram() -> Result<Unigram, String> {
+pub fn unigram() -> Result<Unigram, TokenizerError> {
     Unigram::new_internal()
 }
 
 /// 创建WordPiece分词器的便捷函数
-pub fn wordpiece() -> Result<WordPiece, String> {
+pub fn wordpiece() -> Result<WordPiece, TokenizerError> {
     WordPiece::new_internal()
 }
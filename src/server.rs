@@ -0,0 +1,307 @@
+//! HTTP分词服务
+//!
+//! 基于axum提供 `/encode`、`/decode`、`/count` 和 `/health` 接口。每个请求可以携带一批文本，
+//! 批内使用rayon并行处理，并在阻塞线程池中执行，避免占用异步执行器。
+//! 使用 [`batched_router`] 时，并发请求中的文本会经 [`BatchQueue`] 合并成批次统一编码。
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::base::traits::Tokenizer;
+use crate::batching::{BatchConfig, BatchQueue};
+use crate::telemetry;
+
+pub use crate::batching::SharedTokenizer;
+use crate::error::TokenizerError;
+
+/// 路由共享的状态
+#[derive(Clone)]
+struct AppState {
+    tokenizer: SharedTokenizer,
+    /// 启用动态批处理时的编码队列
+    queue: Option<Arc<BatchQueue>>,
+}
+
+impl AppState {
+    /// 编码一批文本，启用动态批处理时交给队列，否则在阻塞线程池中并行编码
+    async fn encode_texts(&self, texts: Vec<String>) -> Result<Vec<Vec<u32>>, ApiError> {
+        match &self.queue {
+            Some(queue) => {
+                let queue = Arc::clone(queue);
+                run_blocking(self.tokenizer.clone(), move |_| queue.encode_many(texts)).await
+            }
+            None => {
+                let ids =
+                    run_blocking(self.tokenizer.clone(), move |t| encode_batch(t, &texts)).await?;
+                telemetry::record_encoded(ids.len(), ids.iter().map(Vec::len).sum());
+                Ok(ids)
+            }
+        }
+    }
+}
+
+/// 编码和计数请求，`text` 和 `texts` 二选一
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum TextInput {
+    /// 单条文本
+    Single { text: String },
+    /// 一批文本
+    Batch { texts: Vec<String> },
+}
+
+impl TextInput {
+    fn into_texts(self) -> Vec<String> {
+        match self {
+            Self::Single { text } => vec![text],
+            Self::Batch { texts } => texts,
+        }
+    }
+}
+
+/// 编码响应，与请求形式对应
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum EncodeResponse {
+    /// 单条文本的标记ID
+    Single { ids: Vec<u32> },
+    /// 每条文本的标记ID
+    Batch { ids: Vec<Vec<u32>> },
+}
+
+/// 计数响应
+#[derive(Debug, Serialize)]
+pub struct CountResponse {
+    /// 每条文本的标记数
+    pub counts: Vec<usize>,
+    /// 标记总数
+    pub total: usize,
+}
+
+/// 解码请求，`ids` 为单个序列或序列列表
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum DecodeRequest {
+    /// 单个标记ID序列
+    Single { ids: Vec<u32> },
+    /// 多个标记ID序列
+    Batch { ids: Vec<Vec<u32>> },
+}
+
+/// 解码响应，与请求形式对应
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum DecodeResponse {
+    /// 单个序列的文本
+    Single { text: String },
+    /// 每个序列的文本
+    Batch { texts: Vec<String> },
+}
+
+/// 健康检查响应
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    /// 服务状态
+    pub status: &'static str,
+    /// 已加载模型的词汇表大小
+    pub vocab_size: usize,
+}
+
+/// 接口错误，以 `{"error": "..."}` 形式返回
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(error: TokenizerError) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(serde_json::json!({ "error": self.message }));
+        (self.status, body).into_response()
+    }
+}
+
+/// 在阻塞线程池中执行分词任务
+async fn run_blocking<T, F>(tokenizer: SharedTokenizer, f: F) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce(&(dyn Tokenizer<TokenId = u32> + Send + Sync)) -> Result<T, TokenizerError>
+        + Send
+        + 'static,
+{
+    tokio::task::spawn_blocking(move || f(tokenizer.as_ref()))
+        .await
+        .map_err(|e| ApiError {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: format!("分词任务执行失败: {}", e),
+        })?
+        .map_err(ApiError::bad_request)
+}
+
+/// 并行编码一批文本，错误信息包含出错文本的序号
+fn encode_batch(
+    tokenizer: &(dyn Tokenizer<TokenId = u32> + Send + Sync),
+    texts: &[String],
+) -> Result<Vec<Vec<u32>>, TokenizerError> {
+    texts
+        .par_iter()
+        .enumerate()
+        .map(|(index, text)| {
+            tokenizer
+                .encode(text)
+                .map_err(|e| TokenizerError::BatchItem {
+                    index,
+                    source: Box::new(e),
+                })
+        })
+        .collect()
+}
+
+async fn encode(
+    State(state): State<AppState>,
+    Json(input): Json<TextInput>,
+) -> Result<Json<EncodeResponse>, ApiError> {
+    let single = matches!(input, TextInput::Single { .. });
+    let mut ids = state.encode_texts(input.into_texts()).await?;
+
+    let response = if single {
+        EncodeResponse::Single {
+            ids: ids.pop().unwrap_or_default(),
+        }
+    } else {
+        EncodeResponse::Batch { ids }
+    };
+    Ok(Json(response))
+}
+
+async fn count(
+    State(state): State<AppState>,
+    Json(input): Json<TextInput>,
+) -> Result<Json<CountResponse>, ApiError> {
+    let ids = state.encode_texts(input.into_texts()).await?;
+    let counts: Vec<usize> = ids.iter().map(Vec::len).collect();
+
+    let total = counts.iter().sum();
+    Ok(Json(CountResponse { counts, total }))
+}
+
+async fn decode(
+    State(AppState { tokenizer, .. }): State<AppState>,
+    Json(request): Json<DecodeRequest>,
+) -> Result<Json<DecodeResponse>, ApiError> {
+    let response = match request {
+        DecodeRequest::Single { ids } => {
+            let text = run_blocking(tokenizer, move |t| t.decode(&ids)).await?;
+            DecodeResponse::Single { text }
+        }
+        DecodeRequest::Batch { ids } => {
+            let texts = run_blocking(tokenizer, move |t| {
+                ids.par_iter()
+                    .enumerate()
+                    .map(|(index, ids)| {
+                        t.decode(ids).map_err(|e| TokenizerError::BatchItem {
+                            index,
+                            source: Box::new(e),
+                        })
+                    })
+                    .collect()
+            })
+            .await?;
+            DecodeResponse::Batch { texts }
+        }
+    };
+    Ok(Json(response))
+}
+
+async fn health(State(AppState { tokenizer, .. }): State<AppState>) -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok",
+        vocab_size: tokenizer.vocab_size(),
+    })
+}
+
+/// 构建分词服务的路由
+pub fn router(tokenizer: SharedTokenizer) -> Router {
+    app(AppState {
+        tokenizer,
+        queue: None,
+    })
+}
+
+/// 构建启用动态批处理的分词服务路由，`/encode` 和 `/count` 的文本经同一个队列编码
+pub fn batched_router(tokenizer: SharedTokenizer, config: BatchConfig) -> Router {
+    let queue = Arc::new(BatchQueue::new(tokenizer.clone(), config));
+    app(AppState {
+        tokenizer,
+        queue: Some(queue),
+    })
+}
+
+fn app(state: AppState) -> Router {
+    Router::new()
+        .route("/encode", post(encode))
+        .route("/decode", post(decode))
+        .route("/count", post(count))
+        .route("/health", get(health))
+        .route_layer(middleware::from_fn(track_request))
+        .with_state(state)
+}
+
+/// 按路由记录请求数、耗时和失败数
+async fn track_request(request: Request, next: Next) -> Response {
+    let endpoint = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(String::new, |path| path.as_str().to_string());
+    let started = Instant::now();
+    let response = next.run(request).await;
+    telemetry::record_request(&endpoint, started.elapsed(), response.status().is_success());
+    response
+}
+
+/// 在指定地址上启动分词服务，直到进程退出
+///
+/// # Errors
+///
+/// 当地址无法绑定或服务运行失败时返回错误
+pub async fn serve(addr: SocketAddr, tokenizer: SharedTokenizer) -> std::io::Result<()> {
+    serve_router(addr, router(tokenizer)).await
+}
+
+/// 在指定地址上启动启用动态批处理的分词服务，直到进程退出
+///
+/// # Errors
+///
+/// 当地址无法绑定或服务运行失败时返回错误
+pub async fn serve_batched(
+    addr: SocketAddr,
+    tokenizer: SharedTokenizer,
+    config: BatchConfig,
+) -> std::io::Result<()> {
+    serve_router(addr, batched_router(tokenizer, config)).await
+}
+
+async fn serve_router(addr: SocketAddr, router: Router) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    log::info!("分词服务已启动: http://{}", listener.local_addr()?);
+    axum::serve(listener, router).await
+}
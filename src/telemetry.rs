@@ -0,0 +1,74 @@
+//! 服务指标
+//!
+//! 启用 `metrics` 特性后，HTTP服务和批处理队列通过 [`metrics`](https://docs.rs/metrics) 门面输出计数器和直方图。
+//! 库本身不安装记录器，部署方在进程启动时安装任意导出器（如 `metrics-exporter-prometheus`）即可采集；
+//! 未启用特性时下面的记录函数都是空操作。
+//!
+//! 目前编码路径没有缓存，因此不输出缓存命中率。
+
+use std::time::Duration;
+
+/// 请求数，标签 `endpoint`
+pub const REQUESTS: &str = "zero_tokenizer_requests_total";
+/// 失败的请求数，标签 `endpoint`
+pub const REQUEST_ERRORS: &str = "zero_tokenizer_request_errors_total";
+/// 请求耗时（秒），标签 `endpoint`
+pub const REQUEST_DURATION: &str = "zero_tokenizer_request_duration_seconds";
+/// 编码的文本条数
+pub const TEXTS_ENCODED: &str = "zero_tokenizer_texts_encoded_total";
+/// 编码产生的标记数
+pub const TOKENS_ENCODED: &str = "zero_tokenizer_tokens_encoded_total";
+/// 动态批处理每批的文本条数
+pub const BATCH_SIZE: &str = "zero_tokenizer_batch_size";
+/// 动态批处理每批的编码耗时（秒）
+pub const BATCH_DURATION: &str = "zero_tokenizer_batch_duration_seconds";
+
+/// 向已安装的记录器登记全部指标的单位和说明，安装导出器后调用一次即可
+#[cfg(feature = "metrics")]
+pub fn describe_metrics() {
+    use ::metrics::{describe_counter, describe_histogram, Unit};
+
+    describe_counter!(REQUESTS, Unit::Count, "分词服务收到的请求数");
+    describe_counter!(REQUEST_ERRORS, Unit::Count, "分词服务返回错误的请求数");
+    describe_histogram!(REQUEST_DURATION, Unit::Seconds, "分词服务的请求耗时");
+    describe_counter!(TEXTS_ENCODED, Unit::Count, "编码的文本条数");
+    describe_counter!(TOKENS_ENCODED, Unit::Count, "编码产生的标记数");
+    describe_histogram!(BATCH_SIZE, Unit::Count, "动态批处理每批的文本条数");
+    describe_histogram!(BATCH_DURATION, Unit::Seconds, "动态批处理每批的编码耗时");
+}
+
+/// 记录一次请求
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
+pub(crate) fn record_request(endpoint: &str, elapsed: Duration, succeeded: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        let endpoint = endpoint.to_string();
+        ::metrics::counter!(REQUESTS, "endpoint" => endpoint.clone()).increment(1);
+        ::metrics::histogram!(REQUEST_DURATION, "endpoint" => endpoint.clone())
+            .record(elapsed.as_secs_f64());
+        if !succeeded {
+            ::metrics::counter!(REQUEST_ERRORS, "endpoint" => endpoint).increment(1);
+        }
+    }
+}
+
+/// 记录编码的文本条数和产生的标记数
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_encoded(texts: usize, tokens: usize) {
+    #[cfg(feature = "metrics")]
+    {
+        ::metrics::counter!(TEXTS_ENCODED).increment(texts as u64);
+        ::metrics::counter!(TOKENS_ENCODED).increment(tokens as u64);
+    }
+}
+
+/// 记录动态批处理的一个批次
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_batch(size: usize, elapsed: Duration) {
+    #[cfg(feature = "metrics")]
+    {
+        ::metrics::histogram!(BATCH_SIZE).record(size as f64);
+        ::metrics::histogram!(BATCH_DURATION).record(elapsed.as_secs_f64());
+    }
+}
@@ -1,3 +1,7 @@
+mod sampling;
 mod tokenizer;
+mod trainer;
 
-pub use tokenizer::UnigramTokenizer;
+pub use sampling::ScoredIds;
+pub use tokenizer::{PruneCriterion, PruneReport, RemovedPiece, UnigramTokenizer};
+pub use trainer::UnigramTrainerConfig;
@@ -0,0 +1,265 @@
+//! Unigram子词采样
+//!
+//! 用于子词正则化（subword regularization）：训练时对同一文本使用不同的切分作为数据增强。
+//! 与SentencePiece的 `SampleEncode`、`NBestEncode` 相同，在每个预分词片段的格上按
+//! 前向过滤、后向采样抽取切分，或取分数最高的前n种切分。片段边界和特殊标记是固定的，
+//! 整条文本的切分概率等于各片段切分概率之积，因此逐片段采样与在整条文本的格上采样等价。
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::tokenizer::{PieceLattice, UnigramTokenizer};
+use crate::base::special_tokens::Segment;
+use crate::base::tokenizer_base::for_each_piece;
+use crate::error::{input_validation_error, TokenizerError};
+
+/// 格中的一条边：(起点, 片段ID, 分数)，未知字符的片段ID为 `None`
+type Edge = (usize, Option<u32>, f64);
+
+/// 一种切分及其分数
+pub type ScoredIds = (Vec<u32>, f64);
+
+/// 按终点分组的全部边，同一终点的边按起点升序排列
+fn edges_by_end(lattice: &PieceLattice<'_>, bytes: &[u8]) -> Vec<Vec<Edge>> {
+    let mut ends = vec![Vec::new(); bytes.len() + 1];
+    for start in 0..bytes.len() {
+        lattice.edges_from(bytes, start, |end, id, score| {
+            ends[end].push((start, id, score));
+        });
+    }
+    ends
+}
+
+/// 对数空间的加法 log(e^a + e^b)
+fn log_add(a: f64, b: f64) -> f64 {
+    if a == f64::NEG_INFINITY {
+        return b;
+    }
+    if b == f64::NEG_INFINITY {
+        return a;
+    }
+    let (hi, lo) = if a > b { (a, b) } else { (b, a) };
+    hi + (lo - hi).exp().ln_1p()
+}
+
+impl UnigramTokenizer {
+    /// 按切分分数采样编码文本，用于子词正则化
+    ///
+    /// 每种切分被选中的概率与 `exp(alpha * 切分分数)` 成正比：`alpha` 越大越接近
+    /// [`Tokenizer::encode`](crate::base::traits::Tokenizer::encode) 的结果，为0时在全部切分中均匀采样。
+    /// 传入 `seed` 时结果可复现。采样结果不计入未知标记统计。
+    ///
+    /// # Errors
+    ///
+    /// 当 `alpha` 不是非负的有限数、超出输入长度限制或预分词失败时返回错误
+    pub fn encode_sample(
+        &self,
+        text: &str,
+        alpha: f64,
+        seed: Option<u64>,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        self.encode_sample_rng(text, alpha, &mut rng)
+    }
+
+    fn encode_sample_rng<R: Rng>(
+        &self,
+        text: &str,
+        alpha: f64,
+        rng: &mut R,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        if !alpha.is_finite() || alpha < 0.0 {
+            return Err(input_validation_error(format!(
+                "alpha必须是非负的有限数，实际为 {}",
+                alpha
+            )));
+        }
+        trace_span!(debug: "unigram.encode_sample", text_len = text.len());
+        let lattice = self.piece_lattice();
+        let pre_tokenizer = self.base.pre_tokenizer();
+        self.base.encode_options.limits.apply(text, |text| {
+            self.base.special_tokens.encode_with(text, |text| {
+                let mut result = Vec::new();
+                let text = self.base.normalizer.apply(text);
+                for_each_piece(&pre_tokenizer, &text, |part| {
+                    self.sample_piece(&lattice, part.as_bytes(), alpha, rng, &mut result);
+                    Ok(())
+                })?;
+                Ok(result)
+            })
+        })
+    }
+
+    /// 前向计算每个位置之前全部切分的对数配分函数，再从末尾逐条边向前采样
+    fn sample_piece<R: Rng>(
+        &self,
+        lattice: &PieceLattice<'_>,
+        bytes: &[u8],
+        alpha: f64,
+        rng: &mut R,
+        out: &mut Vec<u32>,
+    ) {
+        let ends = edges_by_end(lattice, bytes);
+        let mut forward = vec![f64::NEG_INFINITY; bytes.len() + 1];
+        forward[0] = 0.0;
+        for end in 1..=bytes.len() {
+            for &(start, _, score) in &ends[end] {
+                forward[end] = log_add(forward[end], forward[start] + alpha * score);
+            }
+        }
+
+        let mut path = Vec::new();
+        let mut end = bytes.len();
+        while end > 0 {
+            // 选中每条边的概率为 exp(forward[起点] + alpha * 分数 - forward[终点])
+            let threshold: f64 = rng.gen();
+            let mut cumulative = 0.0;
+            let mut chosen = None;
+            for &(start, id, score) in &ends[end] {
+                if forward[start] == f64::NEG_INFINITY {
+                    continue;
+                }
+                cumulative += (forward[start] + alpha * score - forward[end]).exp();
+                chosen = Some((start, id));
+                if threshold < cumulative {
+                    break;
+                }
+            }
+            // 任何位置都能由未知字符边或片段边到达，至少有一条可达的边
+            let (start, id) = chosen.expect("lattice position is reachable");
+            path.push((start, end, id));
+            end = start;
+        }
+
+        for &(start, end, id) in path.iter().rev() {
+            self.push_edge(lattice, id, &bytes[start..end], out);
+        }
+    }
+
+    /// 分数最高的前 `n` 种切分，按分数从高到低排列，每种切分附带各片段分数之和
+    ///
+    /// 第一种切分与 [`Tokenizer::encode`](crate::base::traits::Tokenizer::encode) 的结果相同；
+    /// 全部切分不足 `n` 种时返回全部切分。结果不计入未知标记统计。
+    ///
+    /// # Errors
+    ///
+    /// 当 `n` 为0、超出输入长度限制或预分词失败时返回错误
+    pub fn encode_nbest(&self, text: &str, n: usize) -> Result<Vec<ScoredIds>, TokenizerError> {
+        if n == 0 {
+            return Err(input_validation_error("n必须大于0"));
+        }
+        trace_span!(debug: "unigram.encode_nbest", text_len = text.len());
+        let limits = &self.base.encode_options.limits;
+        let text = limits.check_input(text)?;
+        let lattice = self.piece_lattice();
+        let pre_tokenizer = self.base.pre_tokenizer();
+
+        // 每个预分词片段和特殊标记各自的前n种切分
+        let mut units: Vec<Vec<ScoredIds>> = Vec::new();
+        for segment in self.base.special_tokens.split(text) {
+            match segment {
+                Segment::Text(text) => {
+                    for_each_piece(&pre_tokenizer, &self.base.normalizer.apply(text), |part| {
+                        units.push(self.nbest_piece(&lattice, part.as_bytes(), n));
+                        Ok(())
+                    })?
+                }
+                Segment::Special(id) => units.push(vec![(vec![id], 0.0)]),
+            }
+        }
+
+        // 逐个单元组合，只保留总分最高的n个组合：(总分, 上一组合的序号, 本单元切分的序号)
+        let mut totals = vec![0.0];
+        let mut layers: Vec<Vec<(usize, usize)>> = Vec::with_capacity(units.len());
+        for unit in &units {
+            let mut candidates: Vec<(f64, usize, usize)> = totals
+                .iter()
+                .enumerate()
+                .flat_map(|(prev, &total)| {
+                    unit.iter()
+                        .enumerate()
+                        .map(move |(index, (_, score))| (total + score, prev, index))
+                })
+                .collect();
+            candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+            candidates.truncate(n);
+            totals = candidates.iter().map(|&(total, _, _)| total).collect();
+            layers.push(
+                candidates
+                    .iter()
+                    .map(|&(_, prev, index)| (prev, index))
+                    .collect(),
+            );
+        }
+
+        totals
+            .iter()
+            .enumerate()
+            .map(|(rank, &total)| {
+                let mut choices = vec![0; units.len()];
+                let mut current = rank;
+                for (unit, layer) in layers.iter().enumerate().rev() {
+                    let (prev, index) = layer[current];
+                    choices[unit] = index;
+                    current = prev;
+                }
+                let mut ids: Vec<u32> = units
+                    .iter()
+                    .zip(&choices)
+                    .flat_map(|(unit, &index)| unit[index].0.iter().copied())
+                    .collect();
+                limits.check_tokens(&mut ids)?;
+                Ok((ids, total))
+            })
+            .collect()
+    }
+
+    /// 一个片段分数最高的前 `n` 种切分
+    ///
+    /// 每个位置保留到达该位置的前n条路径；分数相同时按最后一个片段ID较小者优先，
+    /// 与Viterbi分段的规则一致，因此第一条路径就是Viterbi的结果
+    fn nbest_piece(&self, lattice: &PieceLattice<'_>, bytes: &[u8], n: usize) -> Vec<ScoredIds> {
+        let ends = edges_by_end(lattice, bytes);
+        let rank = |id: Option<u32>| id.unwrap_or(u32::MAX);
+        // best[i] = 到达位置i的前n条路径：(分数, 最后一条边在ends[i]中的序号, 起点上路径的序号)
+        let mut best: Vec<Vec<(f64, usize, usize)>> = vec![Vec::new(); bytes.len() + 1];
+        best[0].push((0.0, usize::MAX, 0));
+        for end in 1..=bytes.len() {
+            let mut candidates = Vec::new();
+            for (edge, &(start, _, score)) in ends[end].iter().enumerate() {
+                for (path, &(base, _, _)) in best[start].iter().enumerate() {
+                    candidates.push((base + score, edge, path));
+                }
+            }
+            candidates.sort_by(|a, b| {
+                b.0.total_cmp(&a.0)
+                    .then_with(|| rank(ends[end][a.1].1).cmp(&rank(ends[end][b.1].1)))
+            });
+            candidates.truncate(n);
+            best[end] = candidates;
+        }
+
+        best[bytes.len()]
+            .iter()
+            .enumerate()
+            .map(|(path, &(score, _, _))| {
+                let mut edges = Vec::new();
+                let (mut end, mut current) = (bytes.len(), path);
+                while end > 0 {
+                    let (_, edge, prev) = best[end][current];
+                    let (start, id, _) = ends[end][edge];
+                    edges.push((start, end, id));
+                    (end, current) = (start, prev);
+                }
+                let mut ids = Vec::with_capacity(edges.len());
+                for &(start, end, id) in edges.iter().rev() {
+                    self.push_edge(lattice, id, &bytes[start..end], &mut ids);
+                }
+                (ids, score)
+            })
+            .collect()
+    }
+}
@@ -2,10 +2,120 @@
 use pyo3::exceptions::PyValueError;
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+use ahash::{AHashMap, AHashSet};
+use fancy_regex::Regex;
+use rayon::prelude::*;
+
+use crate::base::binary::{self, BinaryModel, ModelKind};
+use crate::base::decoder::{decode_ids, DecodedPiece, DecoderKind};
+use crate::base::hf_json::{HfModel, HfTokenizerJson};
+use crate::base::integrity::IntegrityReport;
+use crate::base::normalizer::NormalizedText;
+#[cfg(feature = "python")]
+use crate::base::normalizer::Normalizer;
+use crate::base::padding::EncodeOptions;
+#[cfg(feature = "python")]
+use crate::base::pre_tokenizer::PreTokenizerPipeline;
+#[cfg(feature = "python")]
+use crate::base::registry::NamedModel;
+use crate::base::remap::RemapManifest;
+use crate::base::resources::ResourceConfig;
+use crate::base::score::{format_score, parse_score};
+use crate::base::special_tokens::SpecialTokens;
+use crate::base::tokenizer_base::{
+    compile_pattern, for_each_piece, lines_after_vocab, piece_bytes, piece_vocab_bytes,
+    TokenizerBase,
+};
+use crate::base::trainer_config::TrainerConfig;
+use crate::base::traits::{SpecialTokenizer, SubwordTokenizer, Tokenizer, VocabBytes};
+use crate::base::unk_stats::UnkCounter;
+use crate::base::vocab_manager::VocabManager;
+#[cfg(feature = "python")]
+use crate::error::py_value_error;
+use crate::error::{
+    decoding_error, encoding_error, input_validation_error, invalid_utf8_error, load_error,
+    model_load_error, model_save_error, vocab_error, TokenizerError,
+};
+#[cfg(feature = "python")]
+use crate::generation::TextSampler;
+use crate::unigram::trainer::{learn_pieces, UnigramTrainerConfig};
+
+/// 未知字符相对最低片段分数的惩罚，与SentencePiece一致
+const UNK_PENALTY: f64 = 10.0;
+
+/// 模型文件中分数段的首行前缀，完整形式为 `scores: <未知标记ID> <分数个数>`
+const SCORES_HEADER: &str = "scores: ";
+
+/// 分段用的片段索引，每次编码时根据当前词汇表和分数构建
+pub(super) struct PieceLattice<'a> {
+    /// 片段字节 -> (ID, 分数)，不含字节标记
+    pieces: AHashMap<Cow<'a, [u8]>, (u32, f64)>,
+    /// 最长片段的字节数
+    max_len: usize,
+    /// 未知字符的分数
+    unk_score: f64,
+    /// 每个字节对应的字节标记 `<0xNN>` 的ID
+    byte_ids: [Option<u32>; 256],
+}
 
-use crate::base::tokenizer_base::TokenizerBase;
-use crate::base::traits::{SubwordTokenizer, Tokenizer};
+impl PieceLattice<'_> {
+    /// 依次给出从 `start` 出发的边：(终点, 片段ID, 分数)，未知字符的片段ID为 `None`
+    ///
+    /// 没有片段恰好覆盖 `start` 处的字符时才给出未知字符边，与SentencePiece的格构造相同
+    pub(super) fn edges_from(
+        &self,
+        bytes: &[u8],
+        start: usize,
+        mut edge: impl FnMut(usize, Option<u32>, f64),
+    ) {
+        let char_end = (start + utf8_char_len(bytes[start])).min(bytes.len());
+        let mut covers_char = false;
+        for len in 1..=self.max_len.min(bytes.len() - start) {
+            let end = start + len;
+            if let Some(&(id, score)) = self.pieces.get(&bytes[start..end]) {
+                covers_char |= end == char_end;
+                edge(end, Some(id), score);
+            }
+        }
+        if !covers_char {
+            edge(char_end, None, self.unk_score);
+        }
+    }
+}
+
+/// UTF-8字符首字节对应的字符字节数，延续字节或无效字节按1处理
+fn utf8_char_len(first: u8) -> usize {
+    match first {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 1,
+    }
+}
+
+/// 裁剪片段的条件，见 [`UnigramTokenizer::prune_pieces`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PruneCriterion {
+    /// 移除分数低于给定值的片段
+    MinScore(f64),
+    /// 只保留分数最高的前 `k` 个片段
+    KeepTopK(usize),
+}
+
+/// 被移除的片段：(旧ID, 片段, 分数)
+pub type RemovedPiece = (u32, String, f64);
+
+/// 裁剪结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct PruneReport {
+    /// 被移除的片段，按旧ID升序排列
+    pub removed: Vec<RemovedPiece>,
+    /// 标记ID重映射清单，保留的片段按旧ID顺序重新编号
+    pub manifest: RemapManifest,
+}
 
 /// Unigram分词器
 #[cfg_attr(feature = "python", pyclass)]
@@ -16,45 +126,233 @@ pub struct UnigramTokenizer {
     pub scores: Vec<f64>,
     /// 未知标记ID
     pub unk_token_id: u32,
+    /// 是否把词汇表无法覆盖的字符拆成字节标记 `<0xNN>`，关闭时编码为未知标记
+    pub byte_fallback: bool,
     /// 下一个可用的token ID
     pub next_token_id: u32,
+    /// 种子片段数量、裁剪比例等Unigram训练专用的选项
+    pub em_config: UnigramTrainerConfig,
+    /// 自加载以来编码输出未知标记的统计
+    pub unk_stats: UnkCounter,
 }
 
 impl UnigramTokenizer {
-    /// 创建新的Unigram分词器
-    pub fn new_internal() -> Result<Self, String> {
-        let base = TokenizerBase::new()?;
+    /// 创建新的Unigram分词器，常用汉字字表按 [`ResourceConfig`] 的默认设置加载
+    pub fn new_internal() -> Result<Self, TokenizerError> {
+        Self::with_resources(&ResourceConfig::default())
+    }
 
+    /// 按 `resources` 加载初始字符集，创建新的Unigram分词器
+    ///
+    /// # Errors
+    ///
+    /// 当字表文件无法读取时返回错误
+    pub fn with_resources(resources: &ResourceConfig) -> Result<Self, TokenizerError> {
+        Self::build(TokenizerBase::new()?, resources)
+    }
+
+    /// 使用自定义正则表达式模式创建新的Unigram分词器
+    pub fn with_pattern_internal(pattern: String) -> Result<Self, TokenizerError> {
+        Self::build(
+            TokenizerBase::with_pattern(pattern)?,
+            &ResourceConfig::default(),
+        )
+    }
+
+    fn build(base: TokenizerBase<u32>, resources: &ResourceConfig) -> Result<Self, TokenizerError> {
         let mut tokenizer = Self {
             base,
             scores: Vec::new(),
             unk_token_id: 0,
+            byte_fallback: true,
             next_token_id: 0,
+            em_config: UnigramTrainerConfig::default(),
+            unk_stats: UnkCounter::default(),
         };
 
         // 初始化字节词汇表和常用汉字
         tokenizer.init_byte_vocab();
-        tokenizer.load_common_chinese_chars(None)?;
+        tokenizer.load_common_chinese_chars(resources)?;
 
         Ok(tokenizer)
     }
 
-    /// 使用自定义正则表达式模式创建新的Unigram分词器
-    pub fn with_pattern_internal(pattern: String) -> Result<Self, String> {
-        let base = TokenizerBase::with_pattern(pattern)?;
-
+    /// 直接从内存中的模型数据创建分词器
+    ///
+    /// 不读取常用汉字字表，适用于浏览器等没有文件系统的环境
+    ///
+    /// # Errors
+    ///
+    /// 当模型数据或分数数据格式无效、解析失败时返回错误
+    pub fn from_bytes(model: &[u8], scores: &[u8]) -> Result<Self, TokenizerError> {
         let mut tokenizer = Self {
-            base,
+            base: TokenizerBase::new()?,
             scores: Vec::new(),
             unk_token_id: 0,
+            byte_fallback: true,
             next_token_id: 0,
+            em_config: UnigramTrainerConfig::default(),
+            unk_stats: UnkCounter::default(),
         };
+        tokenizer.load_from_bytes(model, scores)?;
+        Ok(tokenizer)
+    }
 
-        // 初始化字节词汇表和常用汉字
-        tokenizer.init_byte_vocab();
-        tokenizer.load_common_chinese_chars(None)?;
+    /// 检查词汇表的正向和反向映射以及特殊标记注册表，见 [`IntegrityReport`]
+    ///
+    /// 加载时会自动检查，直接修改词汇表后可以再次调用
+    #[must_use]
+    pub fn check_integrity(&self) -> IntegrityReport {
+        let mut report = IntegrityReport::default();
+        report.check_vocab(&self.base.vocab);
+        report.check_special_tokens(&self.base.special_tokens, &self.base.vocab);
+        report
+    }
 
-        Ok(tokenizer)
+    /// 从内存中的模型数据和分数数据加载分词器
+    ///
+    /// 模型数据的格式与 [`Tokenizer::save`] 写出的文件相同，分数保存在模型文件末尾，此时忽略
+    /// `scores`；旧模型的分数保存在单独的 `.scores` 文件中，需要通过 `scores` 传入其内容
+    ///
+    /// # Errors
+    ///
+    /// 当模型数据或分数数据格式无效、解析失败、两者都没有分数或完整性检查发现问题时返回错误
+    pub fn load_from_bytes(&mut self, model: &[u8], scores: &[u8]) -> Result<(), TokenizerError> {
+        self.unk_stats.reset();
+        // 使用基础分词器的加载功能
+        self.base.load_from_reader(model)?;
+        self.load_scores(model, scores)
+    }
+
+    /// 加载词汇表之后的分数，并检查完整性
+    fn load_scores(&mut self, model: &[u8], scores: &[u8]) -> Result<(), TokenizerError> {
+        let model_content = std::str::from_utf8(model)
+            .map_err(|e| model_load_error(format!("模型数据不是有效的UTF-8: {}", e)))?;
+        let (unk_token_id, scores) = match Self::parse_embedded_scores(model_content)? {
+            Some(embedded) => embedded,
+            None => {
+                let scores_content = std::str::from_utf8(scores)
+                    .map_err(|e| model_load_error(format!("加载分数失败: {}", e)))?;
+                Self::parse_scores(scores_content.lines())?
+                    .ok_or_else(|| model_load_error("模型文件中没有分数，且缺少 .scores 数据"))?
+            }
+        };
+        self.unk_token_id = unk_token_id;
+        self.scores = scores;
+        self.next_token_id = self
+            .base
+            .vocab
+            .ids()
+            .max()
+            .map_or(0, |&id| id + 1)
+            .max(self.base.special_tokens.end_id());
+
+        self.check_integrity().into_result()
+    }
+
+    /// 解析模型文件末尾的分数段，旧模型没有分数段时返回 `None`
+    fn parse_embedded_scores(model: &str) -> Result<Option<(u32, Vec<f64>)>, TokenizerError> {
+        let mut lines = lines_after_vocab(model)?.skip_while(|line| line.trim().is_empty());
+
+        match lines.next() {
+            Some(line) if line.starts_with(SCORES_HEADER) => {
+                let unk = line.strip_prefix(SCORES_HEADER).unwrap_or_default();
+                let (unk, count) = unk
+                    .trim()
+                    .split_once(' ')
+                    .ok_or_else(|| model_load_error("无效的分数段"))?;
+                let count: usize = count
+                    .trim()
+                    .parse()
+                    .map_err(|e| model_load_error(format!("解析分数数量失败: {}", e)))?;
+                let scores: Vec<f64> = lines
+                    .take(count)
+                    .enumerate()
+                    .map(|(index, line)| {
+                        parse_score(line).map_err(|e| {
+                            model_load_error(format!("解析第 {} 个分数失败: {}", index, e))
+                        })
+                    })
+                    .collect::<Result<_, TokenizerError>>()?;
+                if scores.len() != count {
+                    return Err(model_load_error(format!(
+                        "分数段应有 {} 行，实际为 {} 行",
+                        count,
+                        scores.len()
+                    )));
+                }
+                let unk = unk
+                    .parse()
+                    .map_err(|e| model_load_error(format!("解析未知标记ID失败: {}", e)))?;
+                Ok(Some((unk, scores)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// 解析旧格式 `.scores` 文件：首行为未知标记ID，其余每行一个分数；内容为空时返回 `None`
+    fn parse_scores<'a>(
+        mut lines: impl Iterator<Item = &'a str>,
+    ) -> Result<Option<(u32, Vec<f64>)>, TokenizerError> {
+        let Some(first_line) = lines.next() else {
+            return Ok(None);
+        };
+        let unk = first_line
+            .trim()
+            .parse()
+            .map_err(|e| model_load_error(format!("解析未知标记ID失败: {}", e)))?;
+        let scores = lines
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(index, line)| {
+                parse_score(line)
+                    .map_err(|e| model_load_error(format!("解析第 {} 个分数失败: {}", index, e)))
+            })
+            .collect::<Result<_, TokenizerError>>()?;
+        Ok(Some((unk, scores)))
+    }
+
+    /// 用外部给出的片段和分数替换整个词汇表，第 `i` 个片段的ID为 `i`
+    ///
+    /// 用于导入其他工具训练出的模型。片段中的 `<0xNN>` 视为字节标记；有 `<unk>` 片段时
+    /// 将其设为未知标记，否则未知标记ID为0
+    ///
+    /// # Errors
+    ///
+    /// 当片段为空、重复、包含换行符，或分数不是有限数时返回错误，此时词汇表保持不变。
+    /// 成功时已注册的特殊标记随之清除
+    pub fn set_pieces(&mut self, pieces: Vec<(String, f64)>) -> Result<(), TokenizerError> {
+        let mut vocab = VocabManager::with_capacity(pieces.len());
+        let mut scores = Vec::with_capacity(pieces.len());
+        for (id, (piece, score)) in (0u32..).zip(pieces) {
+            if piece.is_empty() || piece.contains(['\n', '\r']) {
+                return Err(vocab_error(format!(
+                    "第 {} 个片段 {:?} 为空或包含换行符",
+                    id, piece
+                )));
+            }
+            if !score.is_finite() {
+                return Err(vocab_error(format!(
+                    "片段 {:?} 的分数不是有限数: {}",
+                    piece, score
+                )));
+            }
+            if vocab.contains_value(&piece) {
+                return Err(vocab_error(format!("片段 {:?} 重复", piece)));
+            }
+            vocab.insert(id, piece);
+            scores.push(score);
+        }
+
+        self.unk_token_id = vocab
+            .get_by_value(&"<unk>".to_string())
+            .copied()
+            .unwrap_or(0);
+        self.next_token_id = vocab.len() as u32;
+        self.base.vocab = vocab;
+        self.base.special_tokens.clear();
+        self.scores = scores;
+        Ok(())
     }
 
     /// 初始化词汇表，添加所有字节值
@@ -87,28 +385,15 @@ impl UnigramTokenizer {
         self.next_token_id = self.base.vocab.len() as u32;
     }
 
-    /// 加载常用汉字
-    ///
-    /// # 参数
-    /// - `file_path`: 汉字字表文件路径，默认为 "dict/常用汉字字表.txt"
-    fn load_common_chinese_chars(&mut self, file_path: Option<&str>) -> Result<(), String> {
-        use std::fs::File;
-        use std::io::{BufRead, BufReader};
-
-        let file_path = file_path.unwrap_or("dict/常用汉字字表.txt");
-        let file = File::open(file_path).map_err(|e| format!("无法打开常用汉字文件: {}", e))?;
-
-        let reader = BufReader::new(file);
-
-        for line in reader.lines() {
-            let line = line.map_err(|e| format!("读取常用汉字文件失败: {}", e))?;
-            let char_str = line.trim();
-            if !char_str.is_empty() {
-                // 将汉字添加到词汇表
-                let token_id = self.base.vocab.len() as u32;
-                self.base.vocab.insert(token_id, char_str.to_string());
-                self.scores.push(0.0); // 初始分数为0
-            }
+    /// 把 `resources` 指定的常用汉字加入词汇表
+    fn load_common_chinese_chars(
+        &mut self,
+        resources: &ResourceConfig,
+    ) -> Result<(), TokenizerError> {
+        for char_str in resources.char_dict_entries()? {
+            let token_id = self.base.vocab.len() as u32;
+            self.base.vocab.insert(token_id, char_str);
+            self.scores.push(0.0); // 初始分数为0
         }
 
         // 更新下一个可用的token ID
@@ -118,11 +403,12 @@ impl UnigramTokenizer {
     }
 
     /// 从dict目录加载初始化词表
-    fn _load_vocab_from_dict(&mut self, dict_file: &str) -> Result<(), String> {
+    fn _load_vocab_from_dict(&mut self, dict_file: &str) -> Result<(), TokenizerError> {
         use std::fs::File;
         use std::io::{BufRead, BufReader};
 
-        let file = File::open(dict_file).map_err(|e| format!("无法打开词表文件: {}", e))?;
+        let file =
+            File::open(dict_file).map_err(|e| load_error(format!("无法打开词表文件: {}", e)))?;
 
         let reader = BufReader::new(file);
 
@@ -142,10 +428,12 @@ impl UnigramTokenizer {
                 self.scores.remove(id as usize);
             }
         }
+        // 特殊标记随之清除，需要在加载之后重新注册
+        self.base.special_tokens.clear();
 
         // 从文件加载新的词汇
         for line in reader.lines() {
-            let line = line.map_err(|e| format!("读取词表文件失败: {}", e))?;
+            let line = line.map_err(|e| load_error(format!("读取词表文件失败: {}", e)))?;
             let token = line.trim();
             if !token.is_empty() {
                 // 检查token是否已存在
@@ -167,147 +455,572 @@ impl UnigramTokenizer {
         Ok(())
     }
 
-    /// 从文本中提取常见子字符串
-    fn extract_common_substrings(
-        &self,
-        texts: &[String],
-        max_substrings: usize,
-    ) -> Vec<(Vec<u8>, usize)> {
-        let mut substring_counts: HashMap<Vec<u8>, usize> = HashMap::new();
-
-        for text in texts {
-            let bytes = text.as_bytes();
-            // 添加长度最多为4的所有子字符串
-            for i in 0..bytes.len() {
-                for j in (i + 1)..=(i + 4).min(bytes.len()) {
-                    let substring = bytes[i..j].to_vec();
-                    *substring_counts.entry(substring).or_insert(0) += 1;
+    /// 构建分段用的片段索引：片段字节 -> (ID, 分数)
+    ///
+    /// 同一字节序列对应多个片段时保留分数最高的一个，分数相同时保留ID较小的一个。
+    /// 特殊标记在分段之前已经整体匹配，不参与分段；
+    /// 字节标记 `<0xNN>` 只在字节回退时使用，不参与普通分段；
+    /// 形如 `<0x..>` 但不是单个字节的片段无法还原，同样不参与分段。
+    pub(super) fn piece_lattice(&self) -> PieceLattice<'_> {
+        let mut pieces: AHashMap<Cow<'_, [u8]>, (u32, f64)> = AHashMap::new();
+        let mut max_len = 0;
+        let mut min_score = f64::INFINITY;
+        let mut byte_ids = [None; 256];
+
+        for (&id, piece) in self.base.vocab.iter() {
+            if self.base.special_tokens.contains_id(id) {
+                continue;
+            }
+            let bytes = piece_bytes(piece);
+            if piece.starts_with("<0x") && piece.ends_with('>') {
+                if let [byte] = bytes[..] {
+                    let slot: &mut Option<u32> = &mut byte_ids[usize::from(byte)];
+                    *slot = Some(slot.map_or(id, |current| current.min(id)));
                 }
+                continue;
+            }
+            if bytes.is_empty() {
+                continue;
             }
+
+            let score = self.scores.get(id as usize).copied().unwrap_or(0.0);
+            max_len = max_len.max(bytes.len());
+            min_score = min_score.min(score);
+            pieces
+                .entry(bytes)
+                .and_modify(|best| {
+                    if score > best.1 || (score == best.1 && id < best.0) {
+                        *best = (id, score);
+                    }
+                })
+                .or_insert((id, score));
         }
 
-        // 按频率排序并返回前max_substrings个
-        let mut sorted_substrings: Vec<_> = substring_counts.into_iter().collect();
-        sorted_substrings.sort_by(|a, b| b.1.cmp(&a.1));
-        sorted_substrings.into_iter().take(max_substrings).collect()
+        let unk_score = if min_score.is_finite() {
+            min_score - UNK_PENALTY
+        } else {
+            -UNK_PENALTY
+        };
+        PieceLattice {
+            pieces,
+            max_len,
+            unk_score,
+            byte_ids,
+        }
     }
 
-    /// 将字节向量转换为字符串表示
-    fn bytes_to_string(&self, bytes: &[u8]) -> String {
-        let mut result = String::new();
-        for &byte in bytes {
-            if (32..=126).contains(&byte) {
-                // 可打印ASCII字符
-                result.push(char::from(byte));
-            } else {
-                // 非打印字符使用特殊表示
-                result.push_str(&format!("<0x{:02X}>", byte));
+    /// 开启或关闭字节回退
+    ///
+    /// 关闭时无法覆盖的字符编码为未知标记；词汇表中没有 `<unk>` 时会追加一个，并设为未知标记
+    pub fn set_byte_fallback(&mut self, enabled: bool) {
+        self.byte_fallback = enabled;
+        if enabled {
+            return;
+        }
+
+        let unk = "<unk>".to_string();
+        self.unk_token_id = match self.base.vocab.get_by_value(&unk) {
+            Some(&id) => id,
+            None => {
+                let id = self.next_token_id.max(self.base.vocab.len() as u32);
+                self.base.vocab.insert(id, unk);
+                if self.scores.len() <= id as usize {
+                    self.scores.resize(id as usize + 1, 0.0);
+                }
+                self.next_token_id = id + 1;
+                id
             }
+        };
+    }
+
+    /// 片段是否受裁剪保护：基础字节词汇表、单字节片段（含字节标记 `<0xNN>`）和未知标记始终保留，
+    /// 保证裁剪后任意文本仍能编码；特殊标记同样保留
+    fn is_protected_piece(&self, id: u32, piece: &str) -> bool {
+        id < 256
+            || id == self.unk_token_id
+            || piece_bytes(piece).len() == 1
+            || self.base.special_tokens.contains_id(id)
+    }
+
+    /// 移除低概率片段，无需重新训练即可缩小词汇表
+    ///
+    /// 基础字节词汇表、单字节片段、未知标记和特殊标记不参与裁剪，`KeepTopK(k)` 中的 `k` 不包括这些标记。
+    /// 分数相同时优先保留ID较小的片段。裁剪后把分数视为对数概率重新归一化，
+    /// 使参与分段的片段概率之和为1；保留的片段按旧ID顺序重新编号。
+    ///
+    /// # Errors
+    ///
+    /// 当 `MinScore` 的阈值不是有限数时返回错误
+    pub fn prune_pieces(
+        &mut self,
+        criterion: PruneCriterion,
+    ) -> Result<PruneReport, TokenizerError> {
+        let score_of = |id: u32| self.scores.get(id as usize).copied().unwrap_or(0.0);
+
+        let mut candidates: Vec<(u32, f64)> = self
+            .base
+            .vocab
+            .iter()
+            .filter(|&(&id, piece)| !self.is_protected_piece(id, piece))
+            .map(|(&id, _)| (id, score_of(id)))
+            .collect();
+        let removed_ids: AHashSet<u32> = match criterion {
+            PruneCriterion::MinScore(min_score) => {
+                if !min_score.is_finite() {
+                    return Err(input_validation_error(format!(
+                        "分数阈值必须是有限数，实际为 {}",
+                        min_score
+                    )));
+                }
+                candidates
+                    .into_iter()
+                    .filter(|&(_, score)| score < min_score)
+                    .map(|(id, _)| id)
+                    .collect()
+            }
+            PruneCriterion::KeepTopK(k) => {
+                candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+                candidates.into_iter().skip(k).map(|(id, _)| id).collect()
+            }
+        };
+
+        let old_ids: Vec<u32> = {
+            let mut ids: Vec<u32> = self.base.vocab.ids().copied().collect();
+            ids.sort_unstable();
+            ids
+        };
+        let mut removed = Vec::with_capacity(removed_ids.len());
+        let mut mapping = BTreeMap::new();
+        let mut vocab = VocabManager::with_capacity(old_ids.len() - removed_ids.len());
+        let mut scores = Vec::with_capacity(old_ids.len() - removed_ids.len());
+        for &old_id in &old_ids {
+            let piece = self
+                .base
+                .vocab
+                .get_by_id(&old_id)
+                .cloned()
+                .unwrap_or_default();
+            if removed_ids.contains(&old_id) {
+                removed.push((old_id, piece, score_of(old_id)));
+                continue;
+            }
+            let new_id = mapping.len() as u32;
+            mapping.insert(old_id, new_id);
+            vocab.insert(new_id, piece);
+            scores.push(score_of(old_id));
         }
-        result
+
+        // 参与分段的片段按对数概率重新归一化
+        self.base
+            .special_tokens
+            .remap(|id| mapping.get(&id).copied());
+        let unk_token_id = mapping.get(&self.unk_token_id).copied();
+        let segmentable: Vec<usize> = (0..scores.len())
+            .filter(|&id| {
+                Some(id as u32) != unk_token_id
+                    && !self.base.special_tokens.contains_id(id as u32)
+                    && vocab
+                        .get_by_id(&(id as u32))
+                        .is_some_and(|piece| !(piece.starts_with("<0x") && piece.ends_with('>')))
+            })
+            .collect();
+        let max = segmentable
+            .iter()
+            .map(|&id| scores[id])
+            .fold(f64::NEG_INFINITY, f64::max);
+        if max.is_finite() {
+            let log_sum = max
+                + segmentable
+                    .iter()
+                    .map(|&id| (scores[id] - max).exp())
+                    .sum::<f64>()
+                    .ln();
+            for &id in &segmentable {
+                scores[id] -= log_sum;
+            }
+        }
+
+        self.unk_token_id = unk_token_id.unwrap_or(0);
+        self.next_token_id = vocab.len() as u32;
+        self.base.vocab = vocab;
+        self.scores = scores;
+
+        log::info!("裁剪Unigram词汇表，移除 {} 个片段", removed.len());
+        let manifest = RemapManifest::new(old_ids, 0..self.next_token_id, mapping);
+        Ok(PruneReport { removed, manifest })
     }
 
-    /// 使用Viterbi算法对字节序列进行分段
-    fn segment(&self, bytes: &[u8]) -> Option<Vec<u32>> {
+    /// 使用Viterbi算法对字节序列进行分段，返回片段分数之和最大的切分
+    ///
+    /// 片段无法覆盖的字符作为未知字符参与分段，分数低于任何片段；回溯时按
+    /// [`UnigramTokenizer::byte_fallback`] 拆成字节标记或输出未知标记。
+    /// 分数相同时，优先选择最后一个片段ID较小的切分，保证结果确定。
+    fn segment(&self, lattice: &PieceLattice<'_>, bytes: &[u8]) -> Option<Vec<u32>> {
         if bytes.is_empty() {
             return Some(vec![]);
         }
 
         let n = bytes.len();
-        // dp[i] = (best_score, best_token_id)
-        let mut dp: Vec<(f64, Option<u32>)> = vec![(f64::NEG_INFINITY, None); n + 1];
-        dp[0] = (0.0, None);
-
-        for i in 0..n {
-            if dp[i].0 == f64::NEG_INFINITY {
+        // best[i] = (前i个字节的最高分数, 最后一个片段的ID（未知字符为None）, 最后一个片段的字节数)
+        let mut best: Vec<(f64, Option<u32>, usize)> = vec![(f64::NEG_INFINITY, None, 0); n + 1];
+        best[0] = (0.0, None, 0);
+        let rank = |id: Option<u32>| id.unwrap_or(u32::MAX);
+
+        for start in 0..n {
+            let base = best[start].0;
+            if base == f64::NEG_INFINITY {
                 continue;
             }
 
-            // 尝试所有可能的标记
-            for (token_id, token_str) in self.base.vocab.iter() {
-                // 将token字符串转换回字节序列
-                let token_bytes = if token_str.starts_with("<0x") && token_str.ends_with(">") {
-                    // 特殊字节表示
-                    if let Some(hex_str) = token_str
-                        .strip_prefix("<0x")
-                        .and_then(|s| s.strip_suffix(">"))
-                    {
-                        if let Ok(byte_val) = u8::from_str_radix(hex_str, 16) {
-                            vec![byte_val]
-                        } else {
-                            continue;
-                        }
-                    } else {
-                        continue;
-                    }
-                } else {
-                    // 普通字符串
-                    token_str.as_bytes().to_vec()
-                };
-
-                let token_len = token_bytes.len();
-                if i + token_len <= n && bytes[i..i + token_len] == token_bytes {
-                    let score_value = self.scores.get(*token_id as usize).copied().unwrap_or(0.0);
-                    let score = dp[i].0 + score_value;
-                    if score > dp[i + token_len].0 {
-                        dp[i + token_len] = (score, Some(*token_id));
-                    }
+            lattice.edges_from(bytes, start, |end, id, score| {
+                let candidate = base + score;
+                if candidate > best[end].0
+                    || (candidate == best[end].0 && rank(id) < rank(best[end].1))
+                {
+                    best[end] = (candidate, id, end - start);
                 }
-            }
+            });
         }
 
         // 回溯以找到最佳分段
-        if dp[n].0 == f64::NEG_INFINITY {
-            // 如果无法分段，返回未知标记
-            return Some(vec![self.unk_token_id]);
-        }
-
-        let mut segmentation = Vec::new();
+        let mut path = Vec::new();
         let mut i = n;
         while i > 0 {
-            if let Some(token_id) = dp[i].1 {
-                segmentation.push(token_id);
-                i -= if let Some(token_str) = self.base.vocab.get_by_id(&token_id) {
-                    if token_str.starts_with("<0x") && token_str.ends_with(">") {
-                        1 // 特殊字节表示长度为1
-                    } else {
-                        token_str.len()
-                    }
-                } else {
-                    1
-                };
-            } else {
-                // 如果dp[n].0 != NEG_INFINITY，这不应该发生
-                return Some(vec![self.unk_token_id]);
-            }
+            let (_, id, len) = best[i];
+            path.push((i - len, i, id));
+            i -= len;
         }
 
-        segmentation.reverse();
+        let mut segmentation = Vec::with_capacity(path.len());
+        for &(start, end, id) in path.iter().rev() {
+            self.push_edge(lattice, id, &bytes[start..end], &mut segmentation);
+        }
         Some(segmentation)
     }
+
+    /// 输出格中的一条边：片段直接输出ID；未知字符按
+    /// [`UnigramTokenizer::byte_fallback`] 拆成字节标记，缺少字节标记时输出未知标记
+    pub(super) fn push_edge(
+        &self,
+        lattice: &PieceLattice<'_>,
+        id: Option<u32>,
+        bytes: &[u8],
+        out: &mut Vec<u32>,
+    ) {
+        if let Some(id) = id {
+            out.push(id);
+            return;
+        }
+        let fallback: Option<Vec<u32>> = if self.byte_fallback {
+            bytes
+                .iter()
+                .map(|&byte| lattice.byte_ids[usize::from(byte)])
+                .collect()
+        } else {
+            None
+        };
+        match fallback {
+            Some(ids) => out.extend(ids),
+            None => out.push(self.unk_token_id),
+        }
+    }
 }
 
-impl Tokenizer for UnigramTokenizer {
-    type TokenId = u32;
+impl UnigramTokenizer {
+    /// 本次编码临时改用另一种预分词方式，不修改分词器，用于在同一组词表上对比切分策略
+    ///
+    /// `pattern` 为 [`PATTERN_PRESETS`](crate::base::tokenizer_base::PATTERN_PRESETS) 中的名称或正则表达式。
+    /// 需要反复编码时先用 [`compile_pattern`] 编译，再调用 [`UnigramTokenizer::encode_with_regex`]。
+    ///
+    /// # Errors
+    ///
+    /// 当正则表达式无效或编码失败时返回错误
+    pub fn encode_with_pattern(
+        &self,
+        text: &str,
+        pattern: &str,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        self.encode_with_regex(text, &compile_pattern(pattern)?)
+    }
+
+    /// 以已编译的正则表达式代替预分词流水线中的正则表达式步骤并编码，不修改分词器
+    ///
+    /// # Errors
+    ///
+    /// 当正则表达式匹配失败或编码失败时返回错误
+    pub fn encode_with_regex(
+        &self,
+        text: &str,
+        pattern: &Regex,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        self.encode_split(text, pattern)
+    }
 
-    fn encode(&self, text: &str) -> Result<Vec<Self::TokenId>, String> {
-        // 使用基础分词器分割文本
-        let parts = self.base.split_text(text)?;
+    /// 以 `pattern` 作为预分词流水线中正则表达式步骤的编码实现
+    fn encode_split(&self, text: &str, pattern: &Regex) -> Result<Vec<u32>, TokenizerError> {
+        trace_span!(debug: "unigram.encode", text_len = text.len());
+        let limits = &self.base.encode_options.limits;
+        let text = limits.check_input(text)?;
+        let pre_tokenizer = self.base.pre_tokenizer.with_pattern(pattern);
+        let lattice = self.piece_lattice();
+        let (mut tokens, mut unk_tokens) = (0, 0);
+        let mut ids =
+            self.base
+                .special_tokens
+                .encode_with(text, |text| -> Result<_, TokenizerError> {
+                    let mut result = Vec::new();
+                    let text = self.base.normalizer.apply(text);
+                    for_each_piece(&pre_tokenizer, &text, |part| {
+                        let segment = self
+                            .segment(&lattice, part.as_bytes())
+                            .ok_or_else(|| encoding_error("分段失败"))?;
+                        result.extend(segment);
+                        Ok(())
+                    })?;
+                    tokens += result.len();
+                    unk_tokens += result.iter().filter(|&&id| id == self.unk_token_id).count();
+                    Ok(result)
+                })?;
+        self.unk_stats.record(tokens, unk_tokens);
+        limits.check_tokens(&mut ids)?;
+        Ok(ids)
+    }
+
+    /// 转换为HuggingFace `tokenizers` 的Unigram模型，第 `i` 个片段的ID为 `i`
+    ///
+    /// 未知标记为 `<unk>` 时写出 `unk_id`，否则不写出，由字节回退处理无法覆盖的字符
+    ///
+    /// # Errors
+    ///
+    /// 当标记ID不是从0开始的连续整数时返回错误，可以先用 [`UnigramTokenizer::set_pieces`] 重新编号
+    pub fn to_hf_json(&self) -> Result<HfTokenizerJson, TokenizerError> {
+        let vocab = (0..self.base.vocab.len() as u32)
+            .map(|id| {
+                let piece = self
+                    .base
+                    .vocab
+                    .get_by_id(&id)
+                    .ok_or_else(|| model_save_error(format!("标记ID不连续，缺少ID {}", id)))?;
+                let score = self.scores.get(id as usize).copied().unwrap_or(0.0);
+                Ok((piece.clone(), score))
+            })
+            .collect::<Result<Vec<_>, TokenizerError>>()?;
+        let unk_id = self
+            .base
+            .vocab
+            .get_by_id(&self.unk_token_id)
+            .is_some_and(|piece| piece == "<unk>")
+            .then_some(self.unk_token_id);
+
+        Ok(HfTokenizerJson {
+            model: HfModel::Unigram {
+                vocab,
+                unk_id,
+                byte_fallback: self.byte_fallback,
+            },
+            added_tokens: HfTokenizerJson::added_tokens_from(&self.base.special_tokens),
+            pattern: self.base.pattern.clone(),
+            pre_tokenizer: self.base.pre_tokenizer.clone(),
+            decoder: self.base.decoder,
+            byte_level: false,
+            normalizer: self.base.normalizer.clone(),
+        })
+    }
 
-        let mut result = Vec::new();
-        for part in parts {
-            let segment = self
-                .segment(part.as_bytes())
-                .ok_or_else(|| "分段失败".to_string())?;
-            result.extend(segment);
+    /// 加载HuggingFace `tokenizers` 的Unigram模型，替换当前的片段、分数、预分词模式和特殊标记
+    ///
+    /// # Errors
+    ///
+    /// 当模型不是Unigram、使用了规范化或 `ByteLevel` 预分词器、片段无效，
+    /// 或附加标记与模型中同一ID的片段不同时返回错误
+    pub fn load_hf_json(&mut self, json: HfTokenizerJson) -> Result<(), TokenizerError> {
+        self.unk_stats.reset();
+        let special_tokens = json.special_tokens()?;
+        let HfModel::Unigram {
+            vocab,
+            unk_id,
+            byte_fallback,
+        } = json.model
+        else {
+            return Err(model_load_error("Unigram分词器只能加载Unigram模型"));
+        };
+        if json.byte_level {
+            return Err(model_load_error("Unigram分词器不支持ByteLevel预分词器"));
+        }
+        if let Some(unk_id) = unk_id.filter(|&id| id as usize >= vocab.len()) {
+            return Err(model_load_error(format!(
+                "unk_id {} 超出词汇表大小 {}",
+                unk_id,
+                vocab.len()
+            )));
+        }
+        for token in &json.added_tokens {
+            if let Some((piece, _)) = vocab.get(token.id as usize) {
+                if *piece != token.content {
+                    return Err(model_load_error(format!(
+                        "附加标记 {:?} 的ID {} 已被片段 {:?} 占用",
+                        token.content, token.id, piece
+                    )));
+                }
+            }
+        }
+        compile_pattern(&json.pattern)?;
+
+        self.set_pieces(vocab)?;
+        self.base.set_pattern(&json.pattern)?;
+        for token in &json.added_tokens {
+            if !self.base.vocab.contains_id(&token.id) {
+                self.base.vocab.insert(token.id, token.content.clone());
+                if self.scores.len() <= token.id as usize {
+                    self.scores.resize(token.id as usize + 1, 0.0);
+                }
+                self.next_token_id = self.next_token_id.max(token.id + 1);
+            }
         }
+        self.next_token_id = self.next_token_id.max(special_tokens.end_id());
+        self.base.special_tokens = special_tokens;
+        self.base.normalizer = json.normalizer;
+        self.base.decoder = json.decoder;
+        self.base.pre_tokenizer = json.pre_tokenizer;
+        if let Some(unk_id) = unk_id {
+            self.unk_token_id = unk_id;
+        }
+        self.byte_fallback = byte_fallback;
+        Ok(())
+    }
 
-        Ok(result)
+    /// 保存为HuggingFace `tokenizers` 的 tokenizer.json，见 [`UnigramTokenizer::to_hf_json`]
+    ///
+    /// # Errors
+    ///
+    /// 当转换或写入失败时返回错误
+    pub fn save_tokenizer_json(&self, path: &str) -> Result<(), TokenizerError> {
+        self.to_hf_json()?.save(path)
+    }
+
+    /// 从HuggingFace `tokenizers` 的 tokenizer.json 加载，见 [`UnigramTokenizer::load_hf_json`]
+    ///
+    /// # Errors
+    ///
+    /// 当读取、解析或转换失败时返回错误
+    pub fn load_tokenizer_json(&mut self, path: &str) -> Result<(), TokenizerError> {
+        self.load_hf_json(HfTokenizerJson::load(path)?)
+    }
+
+    /// 以带版本头和校验和的二进制格式保存，分数与模型写在同一个文件中，见 [`crate::base::binary`]
+    ///
+    /// # Errors
+    ///
+    /// 当写入失败时返回错误
+    pub fn save_binary(&self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("unigram.save_binary", path);
+        let mut model = BinaryModel::new(ModelKind::Unigram);
+        self.base.write_binary(&mut model);
+        model.push(
+            binary::tag::VOCAB,
+            binary::write_vocab(
+                self.base
+                    .vocab
+                    .iter()
+                    .map(|(&id, text)| (id, text.as_bytes())),
+            ),
+        );
+        model.push(binary::tag::SCORES, binary::write_scores(&self.scores));
+        model.push(
+            binary::tag::SETTINGS,
+            binary::write_settings(&[
+                ("unk_token_id", self.unk_token_id.to_string()),
+                ("byte_fallback", self.byte_fallback.to_string()),
+            ]),
+        );
+        std::fs::write(path, model.to_bytes())
+            .map_err(|e| model_save_error(format!("写入模型文件失败: {}", e)))
     }
 
-    fn decode(&self, tokens: &[Self::TokenId]) -> Result<String, String> {
-        let mut bytes = Vec::new();
-        for &token_id in tokens {
+    /// 加载 [`UnigramTokenizer::save_binary`] 写出的二进制模型，文件不是二进制格式时按文本格式加载
+    ///
+    /// # Errors
+    ///
+    /// 当读取失败、校验和不符、格式版本过高、模型类型不匹配或完整性检查发现问题时返回错误
+    pub fn load_binary(&mut self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("unigram.load_binary", path);
+        let data =
+            std::fs::read(path).map_err(|e| model_load_error(format!("打开文件失败: {}", e)))?;
+        if !binary::is_binary(&data) {
+            return Tokenizer::load(self, path);
+        }
+        let model = BinaryModel::from_bytes(&data, ModelKind::Unigram)?;
+        self.unk_stats.reset();
+
+        self.base.read_binary(&model)?;
+        let entries = binary::read_text_vocab(model.required(binary::tag::VOCAB)?)?;
+        let scores = binary::read_scores(model.required(binary::tag::SCORES)?)?;
+        let mut unk_token_id = 0;
+        let mut byte_fallback = self.byte_fallback;
+        if let Some(settings) = model.section(binary::tag::SETTINGS) {
+            for (key, value) in binary::read_settings(settings)? {
+                match key.as_str() {
+                    "unk_token_id" => {
+                        unk_token_id = value
+                            .parse()
+                            .map_err(|e| model_load_error(format!("解析未知标记ID失败: {}", e)))?;
+                    }
+                    "byte_fallback" => {
+                        byte_fallback = value.parse().map_err(|e| {
+                            model_load_error(format!("解析字节回退设置失败: {}", e))
+                        })?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut report = IntegrityReport::default();
+        report.check_entries(&entries);
+        for (id, text) in entries {
+            self.base.vocab.insert(id, text);
+        }
+        self.scores = scores;
+        self.unk_token_id = unk_token_id;
+        self.byte_fallback = byte_fallback;
+        self.next_token_id = self
+            .base
+            .vocab
+            .ids()
+            .max()
+            .map_or(0, |&id| id + 1)
+            .max(self.base.special_tokens.end_id());
+
+        report.extend(self.check_integrity());
+        report.into_result()
+    }
+}
+
+impl Tokenizer for UnigramTokenizer {
+    type TokenId = u32;
+
+    fn encode(&self, text: &str) -> Result<Vec<Self::TokenId>, TokenizerError> {
+        self.encode_split(text, &self.base.compiled_pattern)
+    }
+
+    fn decode(&self, tokens: &[Self::TokenId]) -> Result<String, TokenizerError> {
+        if self.base.decoder != DecoderKind::Fuse {
+            return decode_ids(&self.base.decoder, tokens, |id| self.decoded_piece(&id));
+        }
+        // 按标记字节长度之和预分配，字节标记 `<0xNN>` 只占一个字节
+        let total_len = tokens
+            .iter()
+            .filter_map(|id| self.base.vocab.get_by_id(id))
+            .map(|token_str| {
+                if token_str.starts_with("<0x") && token_str.ends_with('>') {
+                    1
+                } else {
+                    token_str.len()
+                }
+            })
+            .sum();
+        let mut bytes = Vec::with_capacity(total_len);
+        for (index, &token_id) in tokens.iter().enumerate() {
             if let Some(token_str) = self.base.vocab.get_by_id(&token_id) {
                 // 将token字符串转换回字节序列
                 if token_str.starts_with("<0x") && token_str.ends_with(">") {
@@ -319,117 +1032,207 @@ impl Tokenizer for UnigramTokenizer {
                         if let Ok(byte_val) = u8::from_str_radix(hex_str, 16) {
                             bytes.push(byte_val);
                         } else {
-                            return Err("无效的字节表示".to_string());
+                            return Err(decoding_error(format!(
+                                "无效的字节表示: {}（第 {} 个标记）",
+                                token_str, index
+                            )));
                         }
                     } else {
-                        return Err("无效的字节表示".to_string());
+                        return Err(decoding_error(format!(
+                            "无效的字节表示: {}（第 {} 个标记）",
+                            token_str, index
+                        )));
                     }
                 } else {
                     // 普通字符串
                     bytes.extend(token_str.as_bytes());
                 }
             } else {
-                return Err(format!("无效的标记ID: {}", token_id));
+                return Err(TokenizerError::UnknownTokenId {
+                    id: token_id,
+                    index,
+                });
             }
         }
 
-        String::from_utf8(bytes).map_err(|e| format!("UTF-8解码失败: {}", e))
+        String::from_utf8(bytes).map_err(|e| {
+            let token_lens = tokens.iter().map(|id| {
+                self.base.vocab.get_by_id(id).map_or(0, |token_str| {
+                    if token_str.starts_with("<0x") && token_str.ends_with('>') {
+                        1
+                    } else {
+                        token_str.len()
+                    }
+                })
+            });
+            invalid_utf8_error(&e.utf8_error(), token_lens)
+        })
     }
 
-    fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> Result<(), String> {
+    fn decode_cow<'a>(&'a self, tokens: &[Self::TokenId]) -> Result<Cow<'a, str>, TokenizerError> {
+        if let ([id], DecoderKind::Fuse) = (tokens, self.base.decoder) {
+            if let Some(token_str) = self.base.vocab.get_by_id(id) {
+                // 字节标记需要还原为原始字节，其余标记可直接借用
+                if !(token_str.starts_with("<0x") && token_str.ends_with('>')) {
+                    return Ok(Cow::Borrowed(token_str.as_str()));
+                }
+            }
+        }
+
+        Tokenizer::decode(self, tokens).map(Cow::Owned)
+    }
+
+    fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> Result<(), TokenizerError> {
+        trace_span!("unigram.train", texts = texts.len(), vocab_size);
+        self.em_config.validate()?;
+        // 配置中的特殊标记先于训练得到的片段分配ID
+        self.base.trainer.clone().register_special_tokens(self)?;
         // 如果请求的词汇表大小小于等于当前词汇表大小，直接返回
         if vocab_size <= self.base.vocab.len() as u32 {
             return Ok(());
         }
 
-        // 计算需要提取的子字符串数量
-        let current_vocab_size = self.base.vocab.len() as u32;
-        let substrings_needed = vocab_size - current_vocab_size;
-
-        // 提取常见子字符串
-        let common_substrings = self.extract_common_substrings(&texts, substrings_needed as usize);
+        // 特殊标记不参与分段，从语料中去除
+        let texts = self.base.special_tokens.strip_texts(texts);
+        let texts = self.base.trainer.shuffle(texts);
 
-        // 添加常见子字符串到词汇表
-        let mut next_id = self.base.vocab.len() as u32;
-        for (substring, _) in common_substrings {
-            if next_id >= vocab_size {
+        // 新片段排在预留的特殊标记ID之后，分数与ID保持对齐
+        let mut next_id = self.next_token_id.max(self.base.vocab.len() as u32);
+        if self.scores.len() < next_id as usize {
+            self.scores.resize(next_id as usize, 0.0);
+        }
+        // 初始字母表中的非ASCII字符先于训练得到的片段加入，ASCII字符已有对应的片段
+        for ch in self.base.trainer.initial_alphabet.clone() {
+            if self.base.vocab.len() as u32 >= vocab_size {
                 break;
             }
-
-            let token_str = self.bytes_to_string(&substring);
-            self.base.vocab.insert(next_id, token_str);
-            self.scores.push(0.0); // 初始分数为0
+            let token = ch.to_string();
+            if ch.is_ascii() || self.base.vocab.contains_value(&token) {
+                continue;
+            }
+            self.base.vocab.insert(next_id, token);
+            self.scores.push(0.0);
             next_id += 1;
         }
 
-        // 如果词汇表还不够大，添加一些随机子字符串
-        while next_id < vocab_size {
-            // 创建一个随机的1-4字节序列
-            let len = (rand::random::<u8>() % 4) + 1;
-            let mut substring = Vec::new();
-            for _ in 0..len {
-                substring.push(rand::random::<u8>());
+        // 统计预分词片段的出现次数，按文本排序保证结果与线程数无关
+        let parts = texts
+            .par_iter()
+            .map(|text| self.base.split_text(text))
+            .collect::<Result<Vec<_>, TokenizerError>>()?;
+        let word_counts: HashMap<&str, i32> = self.base.parallel.count(&parts, |parts, local| {
+            for part in parts.iter().filter(|part| !part.is_empty()) {
+                *local.entry(part.as_str()).or_insert(0) += 1;
             }
+        });
+        let mut words: Vec<(&str, i32)> = word_counts.into_iter().collect();
+        words.sort_unstable();
 
-            let token_str = self.bytes_to_string(&substring);
-            self.base.vocab.insert(next_id, token_str);
-            self.scores.push(0.0); // 初始分数为0
-            next_id += 1;
+        // 参与分段的已有片段与编码时一致：不含特殊标记、未知标记和字节标记
+        let mut existing: Vec<(u32, &str)> = self
+            .base
+            .vocab
+            .iter()
+            .filter(|&(&id, piece)| {
+                id != self.unk_token_id
+                    && !self.base.special_tokens.contains_id(id)
+                    && !piece.is_empty()
+                    && piece_bytes(piece).len() == piece.len()
+            })
+            .map(|(&id, piece)| (id, piece.as_str()))
+            .collect();
+        existing.sort_unstable();
+        let budget = (vocab_size as usize).saturating_sub(self.base.vocab.len());
+        let trained = learn_pieces(
+            &words,
+            &existing,
+            &self.base.trainer,
+            &self.em_config,
+            self.base.parallel,
+            budget,
+        );
+        for (id, score) in trained.scores {
+            self.scores[id as usize] = score;
         }
-
-        // 迭代优化词汇表和分数
-        // 在实际实现中，这里会执行EM算法优化分数
-        // 为简化起见，我们只是设置一些随机分数
-        for score in &mut self.scores {
-            *score = rand::random::<f64>() * 2.0 - 1.0; // -1.0到1.0之间的随机分数
+        for (piece, score) in trained.pieces {
+            self.base.vocab.insert(next_id, piece);
+            self.scores.push(score);
+            next_id += 1;
         }
+        self.next_token_id = next_id;
 
         Ok(())
     }
 
+    fn train_with_config(
+        &mut self,
+        texts: Vec<String>,
+        vocab_size: u32,
+        config: TrainerConfig,
+    ) -> Result<(), TokenizerError> {
+        let previous = std::mem::replace(&mut self.base.trainer, config);
+        let result = Tokenizer::train(self, texts, vocab_size);
+        self.base.trainer = previous;
+        result
+    }
+
     fn vocab_size(&self) -> usize {
         self.base.vocab_size()
     }
 
-    fn save(&self, path: &str) -> Result<(), String> {
+    /// 未知标记以及非ASCII字节标记 `<0xNN>` 视为回退
+    fn is_fallback_token(&self, id: &Self::TokenId) -> bool {
+        *id == self.unk_token_id
+            || self.base.vocab.get_by_id(id).is_some_and(|token_str| {
+                token_str
+                    .strip_prefix("<0x")
+                    .and_then(|s| s.strip_suffix('>'))
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .is_some_and(|byte| byte >= 0x80)
+            })
+    }
+
+    fn save(&self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("unigram.save", path);
         // 使用基础分词器的保存功能
         self.base.save(path)?;
 
-        // 保存分数 - 先构建完整内容，然后一次性写入
-        let scores_path = format!("{}.scores", path);
-        let mut content = format!("{}\n", self.unk_token_id);
+        // 分数追加在模型文件末尾，格式见 `format_score`，可以精确还原每个f64
+        let mut content = format!(
+            "{}{} {}\n",
+            SCORES_HEADER,
+            self.unk_token_id,
+            self.scores.len()
+        );
         for score in &self.scores {
-            content.push_str(&format!("{}\n", score));
+            content.push_str(&format_score(*score));
+            content.push('\n');
         }
 
-        std::fs::write(&scores_path, content).map_err(|e| format!("保存分数失败: {}", e))?;
+        use std::io::Write;
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| model_save_error(format!("打开文件失败: {}", e)))?;
+        file.write_all(content.as_bytes())
+            .map_err(|e| model_save_error(format!("保存分数失败: {}", e)))?;
 
         Ok(())
     }
 
-    fn load(&mut self, path: &str) -> Result<(), String> {
-        // 使用基础分词器的加载功能
-        self.base.load(path)?;
-
-        // 加载分数
+    fn load(&mut self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("unigram.load", path);
+        let model =
+            std::fs::read(path).map_err(|e| model_load_error(format!("打开文件失败: {}", e)))?;
+        // 旧模型的分数保存在单独的 `.scores` 文件中
         let scores_path = format!("{}.scores", path);
-        let scores_content =
-            std::fs::read_to_string(&scores_path).map_err(|e| format!("加载分数失败: {}", e))?;
-
-        let mut lines = scores_content.lines();
-        if let Some(first_line) = lines.next() {
-            self.unk_token_id = first_line
-                .parse()
-                .map_err(|e| format!("解析未知标记ID失败: {}", e))?;
-        }
-
-        self.scores.clear();
-        for line in lines {
-            let score = line.parse().map_err(|e| format!("解析分数失败: {}", e))?;
-            self.scores.push(score);
-        }
+        let scores = match std::fs::read(&scores_path) {
+            Ok(scores) => scores,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(model_load_error(format!("加载分数失败: {}", e))),
+        };
 
-        Ok(())
+        self.load_from_bytes(&model, &scores)
     }
 }
 
@@ -443,30 +1246,189 @@ impl SubwordTokenizer for UnigramTokenizer {
     }
 }
 
+impl SpecialTokenizer for UnigramTokenizer {
+    fn special_tokens(&self) -> &SpecialTokens {
+        &self.base.special_tokens
+    }
+
+    fn encode_options(&self) -> &EncodeOptions {
+        &self.base.encode_options
+    }
+
+    fn encode_options_mut(&mut self) -> &mut EncodeOptions {
+        &mut self.base.encode_options
+    }
+
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Result<Vec<u32>, TokenizerError> {
+        let mut next_id = self.next_token_id.max(self.base.vocab.len() as u32);
+        let ids = self
+            .base
+            .special_tokens
+            .add(&mut self.base.vocab, &mut next_id, tokens);
+        self.next_token_id = next_id;
+        if let Some(&max_id) = self.base.vocab.ids().max() {
+            if self.scores.len() <= max_id as usize {
+                self.scores.resize(max_id as usize + 1, 0.0);
+            }
+        }
+        ids
+    }
+
+    fn reserve_special_ids(&mut self, count: u32) -> Result<std::ops::Range<u32>, TokenizerError> {
+        let mut next_id = self.next_token_id.max(self.base.vocab.len() as u32);
+        let ids = self
+            .base
+            .special_tokens
+            .reserve_after(&mut next_id, count)?;
+        self.next_token_id = next_id;
+        Ok(ids)
+    }
+}
+
+impl VocabBytes for UnigramTokenizer {
+    fn normalize_aligned(&self, text: &str) -> NormalizedText {
+        self.base.normalize_aligned(text)
+    }
+
+    fn token_bytes(&self, id: &u32) -> Option<Cow<'_, [u8]>> {
+        self.base
+            .vocab
+            .get_by_id(id)
+            .map(|piece| piece_bytes(piece))
+    }
+
+    fn vocab_bytes(&self) -> Vec<(u32, Cow<'_, [u8]>)> {
+        piece_vocab_bytes(&self.base.vocab)
+    }
+
+    fn decoder(&self) -> DecoderKind {
+        self.base.decoder
+    }
+
+    fn decoded_piece(&self, id: &u32) -> Option<DecodedPiece<'_>> {
+        self.base
+            .vocab
+            .get_by_id(id)
+            .map(|piece| DecodedPiece::from_vocab(piece))
+    }
+}
+
 #[cfg(feature = "python")]
 #[pymethods]
 impl UnigramTokenizer {
+    /// 创建分词器，`char_dict` 为常用汉字字表路径，`preload_chars` 为假时不预置任何字符
     #[new]
-    fn new() -> PyResult<Self> {
-        Self::new_internal().map_err(PyValueError::new_err)
+    #[pyo3(signature = (char_dict = None, preload_chars = true))]
+    fn new(char_dict: Option<String>, preload_chars: bool) -> PyResult<Self> {
+        let resources = crate::base::resources::from_py_args(char_dict, preload_chars);
+        Self::with_resources(&resources).map_err(py_value_error)
     }
 
     #[staticmethod]
     fn with_pattern(pattern: String) -> PyResult<Self> {
-        let tokenizer = Self::with_pattern_internal(pattern).map_err(PyValueError::new_err)?;
+        let tokenizer = Self::with_pattern_internal(pattern).map_err(py_value_error)?;
         Ok(tokenizer)
     }
 
     fn encode(&self, text: &str) -> PyResult<Vec<u32>> {
-        Tokenizer::encode(self, text).map_err(PyValueError::new_err)
+        Tokenizer::encode(self, text).map_err(PyErr::from)
+    }
+
+    /// 本次编码临时改用另一种预分词方式（预设名称或正则表达式），不修改分词器
+    #[pyo3(name = "encode_with_pattern")]
+    fn py_encode_with_pattern(&self, text: &str, pattern: &str) -> PyResult<Vec<u32>> {
+        self.encode_with_pattern(text, pattern)
+            .map_err(py_value_error)
+    }
+
+    /// 按切分分数采样编码文本，用于子词正则化，传入 `seed` 时结果可复现
+    #[pyo3(name = "encode_sample", signature = (text, alpha, seed = None))]
+    fn py_encode_sample(&self, text: &str, alpha: f64, seed: Option<u64>) -> PyResult<Vec<u32>> {
+        self.encode_sample(text, alpha, seed)
+            .map_err(py_value_error)
+    }
+
+    /// 分数最高的前 `n` 种切分，返回 `(ids, score)` 列表
+    #[pyo3(name = "encode_nbest")]
+    fn py_encode_nbest(&self, text: &str, n: usize) -> PyResult<Vec<(Vec<u32>, f64)>> {
+        self.encode_nbest(text, n).map_err(py_value_error)
+    }
+
+    #[pyo3(signature = (tokens, skip_special_tokens = false))]
+    fn decode(&self, tokens: Vec<u32>, skip_special_tokens: bool) -> PyResult<String> {
+        self.decode_with(&tokens, skip_special_tokens)
+            .map_err(PyErr::from)
+    }
+
+    /// 批量编码文本为token IDs（并行处理）
+    ///
+    /// `texts` 可以是字符串列表，也可以是pyarrow的字符串数组、ChunkedArray、RecordBatch或Table
+    /// （多列时通过 `column` 指定列名）。Arrow输入直接从缓冲区读取字符串，空值编码为空列表
+    #[pyo3(signature = (texts, column = None))]
+    fn encode_batch(
+        &self,
+        texts: &Bound<'_, PyAny>,
+        column: Option<&str>,
+    ) -> PyResult<Vec<Vec<u32>>> {
+        crate::base::py_numpy::encode_batch(self, texts, column)
     }
 
-    fn decode(&self, tokens: Vec<u32>) -> PyResult<String> {
-        Tokenizer::decode(self, &tokens).map_err(PyValueError::new_err)
+    /// 批量解码token IDs为文本（并行处理）
+    ///
+    /// `token_lists` 可以是嵌套列表，也可以是二维整数NumPy数组或PyTorch CPU张量，
+    /// 给出 `attention_mask` 时只解码掩码非0位置的标记
+    #[pyo3(signature = (token_lists, attention_mask = None))]
+    fn decode_batch(
+        &self,
+        py: Python<'_>,
+        token_lists: &Bound<'_, PyAny>,
+        attention_mask: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Vec<String>> {
+        crate::base::py_numpy::decode_batch(py, self, token_lists, attention_mask)
     }
 
     fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> PyResult<()> {
-        Tokenizer::train(self, texts, vocab_size).map_err(PyValueError::new_err)
+        Tokenizer::train(self, texts, vocab_size).map_err(PyErr::from)
+    }
+
+    /// 从Python迭代器训练分词器
+    #[pyo3(signature = (texts, vocab_size, _show_progress = false))]
+    fn train_from_iterator(
+        &mut self,
+        texts: Vec<String>,
+        vocab_size: u32,
+        _show_progress: bool,
+    ) -> PyResult<()> {
+        Tokenizer::train(self, texts, vocab_size).map_err(PyErr::from)
+    }
+
+    /// 设置训练时并行计数的粒度：每个任务处理的片段数（0为自适应）和归并树宽度
+    #[pyo3(signature = (pieces_per_task = 0, reduce_width = 2))]
+    fn set_parallel_chunking(
+        &mut self,
+        pieces_per_task: usize,
+        reduce_width: usize,
+    ) -> PyResult<()> {
+        self.base.parallel =
+            crate::base::tokenizer_base::ParallelChunking::new(pieces_per_task, reduce_width)
+                .map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 返回正则表达式模式
+    #[getter]
+    fn get_pattern(&self) -> String {
+        self.base.pattern.clone()
+    }
+
+    /// 获取词汇表，标记ID到标记
+    fn get_vocab(&self) -> HashMap<u32, String> {
+        self.base.vocab.id_map().clone()
+    }
+
+    /// 获取反向词汇表，标记到标记ID
+    fn get_vocab_rev(&self) -> HashMap<String, u32> {
+        self.base.vocab.value_map().clone()
     }
 
     fn vocab_size(&self) -> PyResult<usize> {
@@ -474,11 +1436,34 @@ impl UnigramTokenizer {
     }
 
     fn save(&self, path: &str) -> PyResult<()> {
-        Tokenizer::save(self, path).map_err(PyValueError::new_err)
+        Tokenizer::save(self, path).map_err(PyErr::from)
     }
 
     fn load(&mut self, path: &str) -> PyResult<()> {
-        Tokenizer::load(self, path).map_err(PyValueError::new_err)
+        Tokenizer::load(self, path).map_err(PyErr::from)
+    }
+
+    /// 保存到本地模型注册表，返回模型文件路径；同名模型会被覆盖
+    #[pyo3(name = "save_as")]
+    fn py_save_as(&self, name: &str) -> PyResult<String> {
+        let path = NamedModel::save_as(self, name)?;
+        Ok(path.to_string_lossy().into_owned())
+    }
+
+    /// 按名称从本地模型注册表加载
+    #[pyo3(name = "load_named")]
+    fn py_load_named(&mut self, name: &str) -> PyResult<()> {
+        NamedModel::load_named(self, name).map_err(Into::into)
+    }
+
+    /// 自加载以来的未知标记统计：`texts`、`tokens`、`unk_tokens`、`texts_with_unk` 和 `unk_rate`
+    fn get_unk_stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        self.unk_stats.snapshot().to_py_dict(py)
+    }
+
+    /// 清空未知标记统计
+    fn reset_unk_stats(&self) {
+        self.unk_stats.reset();
     }
 
     fn get_scores(&self) -> PyResult<Vec<f64>> {
@@ -490,6 +1475,414 @@ impl UnigramTokenizer {
         Ok(())
     }
 
+    /// 保存为HuggingFace `tokenizers` 的 tokenizer.json
+    #[pyo3(name = "save_tokenizer_json")]
+    fn py_save_tokenizer_json(&self, path: &str) -> PyResult<()> {
+        self.save_tokenizer_json(path).map_err(py_value_error)
+    }
+
+    /// 以带版本头和校验和的二进制格式保存
+    #[pyo3(name = "save_binary")]
+    fn py_save_binary(&self, path: &str) -> PyResult<()> {
+        self.save_binary(path).map_err(py_value_error)
+    }
+
+    /// 加载二进制模型，文件不是二进制格式时按文本格式加载
+    #[pyo3(name = "load_binary")]
+    fn py_load_binary(&mut self, path: &str) -> PyResult<()> {
+        self.load_binary(path).map_err(py_value_error)
+    }
+
+    /// 从HuggingFace `tokenizers` 的 tokenizer.json 加载
+    #[pyo3(name = "load_tokenizer_json")]
+    fn py_load_tokenizer_json(&mut self, path: &str) -> PyResult<()> {
+        self.load_tokenizer_json(path).map_err(py_value_error)
+    }
+
+    /// 用 `[(片段, 分数)]` 替换整个词汇表，第 `i` 个片段的ID为 `i`
+    #[pyo3(name = "set_pieces")]
+    fn py_set_pieces(&mut self, pieces: Vec<(String, f64)>) -> PyResult<()> {
+        self.set_pieces(pieces).map_err(py_value_error)
+    }
+
+    /// 解码结果以 `prefix` 开头的全部标记ID
+    fn ids_with_prefix(&self, prefix: &str) -> Vec<u32> {
+        VocabBytes::ids_with_prefix(self, prefix)
+    }
+
+    /// 忽略开头的空白后解码结果恰好为 `text` 的全部标记ID
+    fn token_ids_covering(&self, text: &str) -> Vec<u32> {
+        VocabBytes::token_ids_covering(self, text)
+    }
+
+    /// 注册特殊标记，返回每个标记的ID
+    fn add_special_tokens(&mut self, tokens: Vec<String>) -> PyResult<Vec<u32>> {
+        let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+        SpecialTokenizer::add_special_tokens(self, &tokens).map_err(PyErr::from)
+    }
+
+    /// 在词汇表末尾预留 `count` 个特殊标记ID，返回 `(start, end)` 区间
+    fn reserve_special_ids(&mut self, count: u32) -> PyResult<(u32, u32)> {
+        SpecialTokenizer::reserve_special_ids(self, count)
+            .map(|range| (range.start, range.end))
+            .map_err(PyErr::from)
+    }
+
+    /// 已注册的特殊标记，`{标记: ID}`
+    #[getter(special_tokens)]
+    fn py_special_tokens(&self) -> HashMap<String, u32> {
+        self.base
+            .special_tokens
+            .iter()
+            .map(|(id, token)| (token.to_string(), id))
+            .collect()
+    }
+
+    /// 将单个标记渲染为适合日志和界面显示的字符串，控制字符转义、首尾空格显示为 ␣
+    fn render_token(&self, id: u32) -> String {
+        VocabBytes::render_token(self, &id)
+    }
+
+    /// 从词汇表中随机抽取 `n_tokens` 个标记并解码为文本，用于压测和往返测试
+    ///
+    /// `counts` 为 `[(ID, 次数)]` 时按次数加权抽取，否则等概率抽取；传入 `seed` 时结果可复现
+    #[pyo3(name = "sample_text", signature = (n_tokens, seed = None, counts = None))]
+    fn py_sample_text(
+        &self,
+        n_tokens: usize,
+        seed: Option<u64>,
+        counts: Option<Vec<(u32, u64)>>,
+    ) -> PyResult<String> {
+        let sampler = match counts {
+            Some(counts) => TextSampler::with_counts(self, &counts, seed),
+            None => TextSampler::new(self, seed),
+        };
+        sampler
+            .and_then(|mut sampler| sampler.sample_text(self, n_tokens))
+            .map_err(py_value_error)
+    }
+
+    /// 检查语料中的字符能否由基础词汇表表示，返回覆盖统计以及只能拆成字节或编码为未知标记的字符
+    #[pyo3(name = "coverage")]
+    fn py_coverage<'py>(
+        &self,
+        py: Python<'py>,
+        corpus: Vec<String>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        py.allow_threads(|| crate::analysis::coverage(self, &corpus))
+            .map_err(py_value_error)?
+            .to_py_dict(py)
+    }
+
+    /// 开启或关闭字节回退，关闭时无法覆盖的字符编码为 `<unk>`
+    #[pyo3(name = "set_byte_fallback")]
+    fn py_set_byte_fallback(&mut self, enabled: bool) {
+        self.set_byte_fallback(enabled);
+    }
+
+    /// 设置预分词之前的规范化流水线，步骤以逗号分隔，如 `"nfkc,lowercase"`，`"none"` 表示不做规范化
+    ///
+    /// 可选步骤：nfc、nfkc、nfkd、lowercase、strip_accents、clean_whitespace
+    #[pyo3(name = "set_normalizer")]
+    fn py_set_normalizer(&mut self, spec: &str) -> PyResult<()> {
+        self.base.normalizer = Normalizer::parse(spec).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的规范化流水线，格式同 `set_normalizer`
+    #[pyo3(name = "get_normalizer")]
+    fn py_get_normalizer(&self) -> String {
+        self.base.normalizer.spec()
+    }
+
+    /// 设置预分词流水线，步骤以逗号分隔，如 `"whitespace,punctuation"`，按顺序逐步细分片段，
+    /// `"none"` 表示不切分
+    ///
+    /// 可选步骤：regex（按 `set_pattern` 设置的正则表达式，默认流水线只有这一步）、whitespace、
+    /// byte_level、metaspace、digits、punctuation
+    #[pyo3(name = "set_pre_tokenizer")]
+    fn py_set_pre_tokenizer(&mut self, spec: &str) -> PyResult<()> {
+        self.base.pre_tokenizer = PreTokenizerPipeline::parse(spec).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的预分词流水线，格式同 `set_pre_tokenizer`
+    #[pyo3(name = "get_pre_tokenizer")]
+    fn py_get_pre_tokenizer(&self) -> String {
+        self.base.pre_tokenizer.spec()
+    }
+
+    /// 设置解码器："fuse"（默认，直接拼接）、"byte_level"（还原 `Ġ` 等字节映射字符）、
+    /// "metaspace"（`▁` 还原为空格并去掉开头的空格）或 "wordpiece"（词之间补空格）
+    #[pyo3(name = "set_decoder")]
+    fn py_set_decoder(&mut self, name: &str) -> PyResult<()> {
+        self.base.decoder = DecoderKind::from_name(name).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的解码器名称
+    #[pyo3(name = "get_decoder")]
+    fn py_get_decoder(&self) -> &'static str {
+        self.base.decoder.name()
+    }
+
+    /// 移除低概率片段，`min_score` 和 `keep_top_k` 需要且只能给出一个
+    ///
+    /// 返回 (被移除的片段列表 `[(旧ID, 片段, 分数)]`, 重映射清单字典)
+    #[pyo3(name = "prune_pieces", signature = (min_score = None, keep_top_k = None))]
+    fn py_prune_pieces<'py>(
+        &mut self,
+        py: Python<'py>,
+        min_score: Option<f64>,
+        keep_top_k: Option<usize>,
+    ) -> PyResult<(Vec<RemovedPiece>, Bound<'py, pyo3::types::PyDict>)> {
+        let criterion = match (min_score, keep_top_k) {
+            (Some(min_score), None) => PruneCriterion::MinScore(min_score),
+            (None, Some(k)) => PruneCriterion::KeepTopK(k),
+            _ => {
+                return Err(PyValueError::new_err(
+                    "min_score和keep_top_k需要且只能给出一个",
+                ))
+            }
+        };
+        let report = self.prune_pieces(criterion).map_err(py_value_error)?;
+        Ok((report.removed, report.manifest.to_py_dict(py)?))
+    }
+
+    /// 设置之后训练使用的配置：种子片段的最低出现次数和最大字节数、训练前注册的特殊标记
+    /// 和初始字母表
+    ///
+    /// `limit_alphabet` 只保留出现次数最多的字符参与训练，其余字符按字节标记编码
+    #[pyo3(signature = (
+        min_frequency = 0,
+        max_piece_length = None,
+        special_tokens = None,
+        initial_alphabet = None,
+        limit_alphabet = None,
+        shuffle_seed = None
+    ))]
+    fn set_trainer_config(
+        &mut self,
+        min_frequency: u64,
+        max_piece_length: Option<usize>,
+        special_tokens: Option<Vec<String>>,
+        initial_alphabet: Option<Vec<char>>,
+        limit_alphabet: Option<usize>,
+        shuffle_seed: Option<u64>,
+    ) {
+        self.base.trainer = TrainerConfig::from_py(
+            min_frequency,
+            max_piece_length,
+            special_tokens,
+            initial_alphabet,
+            limit_alphabet,
+            shuffle_seed,
+        );
+    }
+
+    /// 设置Unigram训练专用的选项：种子片段的最大数量、每轮裁剪后保留的片段比例和每轮的EM迭代次数
+    #[pyo3(signature = (seed_size = 1_000_000, shrinking_factor = 0.75, num_sub_iterations = 2))]
+    fn set_em_config(
+        &mut self,
+        seed_size: usize,
+        shrinking_factor: f64,
+        num_sub_iterations: usize,
+    ) -> PyResult<()> {
+        let config = UnigramTrainerConfig::new()
+            .with_seed_size(seed_size)
+            .with_shrinking_factor(shrinking_factor)
+            .with_num_sub_iterations(num_sub_iterations);
+        config.validate().map_err(py_value_error)?;
+        self.em_config = config;
+        Ok(())
+    }
+
+    /// 编码文本并返回每个标记的区间 `(start, end, id)`
+    ///
+    /// 区间默认为UTF-8字节偏移，`offset_unit="char"` 时为字符偏移
+    #[pyo3(signature = (text, offset_unit = "byte"))]
+    fn spans(&self, text: &str, offset_unit: &str) -> PyResult<Vec<(usize, usize, u32)>> {
+        crate::base::py_numpy::spans(self, text, offset_unit)
+    }
+
+    /// 编码文本并返回 `ids`、`tokens`、`offsets` 组成的字典，用于把标注对齐回原文
+    ///
+    /// 区间默认为UTF-8字节偏移，`offset_unit="char"` 时为字符偏移
+    #[pyo3(signature = (text, offset_unit = "byte"))]
+    fn encode_with_offsets<'py>(
+        &self,
+        py: Python<'py>,
+        text: &str,
+        offset_unit: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_with_offsets(py, self, text, offset_unit)
+    }
+
+    /// 编码句子对为 `[CLS] A [SEP] B [SEP]`，返回 `input_ids`、`token_type_ids` 组成的字典
+    ///
+    /// 分类和分隔标记需要已注册为特殊标记，可用 `cls_token`、`sep_token` 改用其他名称
+    #[pyo3(name = "encode_pair", signature = (text_a, text_b, cls_token = "[CLS]", sep_token = "[SEP]"))]
+    fn py_encode_pair<'py>(
+        &self,
+        py: Python<'py>,
+        text_a: &str,
+        text_b: &str,
+        cls_token: &str,
+        sep_token: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_pair(py, self, text_a, text_b, cls_token, sep_token)
+    }
+
+    /// 启用截断，`encode_plus`、`encode_batch_plus` 每行最多保留 `max_length` 个标记
+    ///
+    /// `strategy` 为 `"longest_first"`（默认）、`"only_first"` 或 `"only_second"`，只对句子对有效；
+    /// 指定 `stride` 时超出的部分切成相邻重叠 `stride` 个标记的溢出块，每块单独成行
+    #[pyo3(name = "enable_truncation", signature = (max_length, strategy = "longest_first", stride = None))]
+    fn py_enable_truncation(
+        &mut self,
+        max_length: usize,
+        strategy: &str,
+        stride: Option<usize>,
+    ) -> PyResult<()> {
+        let config = crate::base::py_numpy::truncation_config(max_length, strategy, stride)?;
+        self.encode_options_mut().truncation = Some(config);
+        Ok(())
+    }
+
+    /// 关闭截断
+    #[pyo3(name = "no_truncation")]
+    fn py_no_truncation(&mut self) {
+        self.no_truncation();
+    }
+
+    /// 启用补齐，`encode_plus`、`encode_batch_plus` 用 `pad_id` 把各行补齐到 `length`（默认为最长的行）
+    ///
+    /// `direction` 为 `"right"`（默认）或 `"left"`；指定 `pad_to_multiple_of` 时行宽向上取整到其倍数
+    #[pyo3(name = "enable_padding", signature = (pad_id = 0, direction = "right", length = None, pad_to_multiple_of = None))]
+    fn py_enable_padding(
+        &mut self,
+        pad_id: u32,
+        direction: &str,
+        length: Option<usize>,
+        pad_to_multiple_of: Option<usize>,
+    ) -> PyResult<()> {
+        let config =
+            crate::base::py_numpy::padding_config(direction, pad_id, length, pad_to_multiple_of)?;
+        self.encode_options_mut().padding = Some(config);
+        Ok(())
+    }
+
+    /// 关闭补齐
+    #[pyo3(name = "no_padding")]
+    fn py_no_padding(&mut self) {
+        self.no_padding();
+    }
+
+    /// 设置编码输入的长度限制，参数都为 `None` 时不限制
+    ///
+    /// 输入超过 `max_input_bytes` 字节或结果超过 `max_tokens` 个标记时，`policy="error"`（默认）报错，
+    /// `"truncate"` 截断输入或结果；对 `encode` 及所有基于它的接口生效
+    #[pyo3(name = "set_input_limits", signature = (max_input_bytes = None, max_tokens = None, policy = "error"))]
+    fn py_set_input_limits(
+        &mut self,
+        max_input_bytes: Option<usize>,
+        max_tokens: Option<usize>,
+        policy: &str,
+    ) -> PyResult<()> {
+        self.encode_options_mut().limits =
+            crate::base::py_numpy::input_limits(max_input_bytes, max_tokens, policy)?;
+        Ok(())
+    }
+
+    /// 按截断与补齐配置编码单条文本或句子对，返回 `input_ids`、`attention_mask`、`token_type_ids`
+    /// 和 `overflow_to_sample_mapping` 组成的字典，每项按行给出（溢出块各占一行）
+    ///
+    /// 句子对拼接为 `[CLS] A [SEP] B [SEP]`；`return_tensors="np"` 时返回NumPy数组
+    #[pyo3(name = "encode_plus", signature = (text, pair = None, return_tensors = None))]
+    fn py_encode_plus<'py>(
+        &self,
+        py: Python<'py>,
+        text: String,
+        pair: Option<String>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_plus(
+            py,
+            self,
+            vec![text],
+            pair.map(|pair| vec![pair]),
+            return_tensors,
+        )
+    }
+
+    /// 按截断与补齐配置并行编码一批文本（及对应的第二句 `pairs`），返回格式同 `encode_plus`
+    #[pyo3(name = "encode_batch_plus", signature = (texts, pairs = None, return_tensors = None))]
+    fn py_encode_batch_plus<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        pairs: Option<Vec<String>>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_plus(py, self, texts, pairs, return_tensors)
+    }
+
+    /// 检查词汇表和特殊标记注册表，返回发现的问题列表，每项为包含 `kind`、`message` 的字典
+    #[pyo3(name = "check_integrity")]
+    fn py_check_integrity<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Vec<Bound<'py, pyo3::types::PyDict>>> {
+        self.check_integrity().to_py_list(py)
+    }
+
+    /// 批量编码并返回连续存储的 `input_ids` 和每行的 `lengths`、`offsets`，可直接复制到张量
+    ///
+    /// 每行最多保留 `max_len` 个标记；指定 `pad_id` 时补齐为二维并返回 `attention_mask`，
+    /// `return_tensors="np"` 时返回NumPy数组
+    #[pyo3(signature = (texts, max_len = None, pad_id = None, return_tensors = None))]
+    fn encode_batch_packed<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        max_len: Option<usize>,
+        pad_id: Option<u32>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_packed(py, self, texts, max_len, pad_id, return_tensors)
+    }
+
+    /// 批量编码并返回补齐后的 `input_ids`、`offsets`、`attention_mask`
+    ///
+    /// `return_tensors="np"` 时返回NumPy数组，避免为大批量创建大量小元组；
+    /// `offset_unit="char"` 时偏移为字符区间
+    #[pyo3(signature = (texts, return_tensors = None, offset_unit = "byte"))]
+    fn encode_batch_with_offsets<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        return_tensors: Option<&str>,
+        offset_unit: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_with_offsets(
+            py,
+            self,
+            texts,
+            return_tensors,
+            offset_unit,
+        )
+    }
+
+    /// 编码文本并返回标记ID序列与平台无关的64位哈希，用于判断缓存的编码结果是否过期
+    fn content_hash(&self, text: &str) -> PyResult<u64> {
+        Tokenizer::content_hash(self, text).map_err(PyErr::from)
+    }
+
+    /// 编码文本并标出每个标记是否开始一个新词，用于整词掩码
+    fn word_starts(&self, text: &str) -> PyResult<Vec<bool>> {
+        VocabBytes::word_starts(self, text).map_err(PyErr::from)
+    }
+
     /// 从dict目录加载初始化词表
     #[cfg(feature = "python")]
     #[pyo3(name = "load_vocab_from_dict")]
@@ -500,10 +1893,16 @@ impl UnigramTokenizer {
 }
 
 impl Default for UnigramTokenizer {
+    /// 默认字表无法读取时记录警告，改为创建不预置字符的分词器
     fn default() -> Self {
-        Self::new_internal().expect(
-            "Default Unigram Tokenizer initialization failed. \
-             This is a programming error - please report this bug.",
-        )
+        Self::new_internal()
+            .or_else(|e| {
+                log::warn!("{}，改为不预置字符", e);
+                Self::with_resources(&ResourceConfig::without_char_dict())
+            })
+            .expect(
+                "Default Unigram Tokenizer initialization failed. \
+                 This is a programming error - please report this bug.",
+            )
     }
 }
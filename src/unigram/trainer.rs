@@ -0,0 +1,591 @@
+//! Unigram训练
+//!
+//! 与SentencePiece相同的EM训练：先从语料的子字符串中选出种子片段，再交替执行
+//! E步（按前向后向算法统计每个片段的期望次数）和M步（重新估计片段的对数概率），
+//! 每轮按删除片段造成的似然损失裁剪一部分片段，直到片段数接近目标大小。
+
+use std::collections::HashMap;
+
+use ahash::AHashMap;
+use rayon::prelude::*;
+
+use crate::base::tokenizer_base::{piece_bytes, ParallelChunking};
+use crate::base::trainer_config::TrainerConfig;
+use crate::error::{input_validation_error, TokenizerError};
+
+/// 未知字符相对最低片段分数的惩罚，与编码时一致
+const UNK_PENALTY: f64 = 10.0;
+
+/// M步中期望次数低于该值的候选片段被移除
+const EXPECTED_FREQUENCY_THRESHOLD: f64 = 0.5;
+
+/// `max_piece_length` 未设置时候选片段的最大字节数
+const DEFAULT_MAX_PIECE_BYTES: usize = 16;
+
+/// E步每个并行任务处理的预分词片段数，固定划分使期望次数的累加顺序与线程数无关
+const E_STEP_CHUNK: usize = 256;
+
+/// Unigram训练专用的选项，各项含义与SentencePiece相同
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnigramTrainerConfig {
+    /// 种子片段的最大数量，不含字符和词汇表中已有的片段
+    pub seed_size: usize,
+    /// 每轮裁剪后保留的片段比例，取值在 (0, 1) 之间
+    pub shrinking_factor: f64,
+    /// 每轮裁剪之前执行的EM迭代次数，至少为1
+    pub num_sub_iterations: usize,
+}
+
+impl Default for UnigramTrainerConfig {
+    fn default() -> Self {
+        Self {
+            seed_size: 1_000_000,
+            shrinking_factor: 0.75,
+            num_sub_iterations: 2,
+        }
+    }
+}
+
+impl UnigramTrainerConfig {
+    /// 创建默认配置
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置种子片段的最大数量
+    #[must_use]
+    pub fn with_seed_size(mut self, seed_size: usize) -> Self {
+        self.seed_size = seed_size;
+        self
+    }
+
+    /// 设置每轮裁剪后保留的片段比例
+    #[must_use]
+    pub fn with_shrinking_factor(mut self, shrinking_factor: f64) -> Self {
+        self.shrinking_factor = shrinking_factor;
+        self
+    }
+
+    /// 设置每轮裁剪之前的EM迭代次数
+    #[must_use]
+    pub fn with_num_sub_iterations(mut self, num_sub_iterations: usize) -> Self {
+        self.num_sub_iterations = num_sub_iterations;
+        self
+    }
+
+    /// 检查各项选项是否有效
+    ///
+    /// # Errors
+    ///
+    /// 当 `seed_size` 或 `num_sub_iterations` 为0，或 `shrinking_factor` 不在 (0, 1) 之间时返回错误
+    pub fn validate(&self) -> Result<(), TokenizerError> {
+        if self.seed_size == 0 {
+            return Err(input_validation_error("种子片段数量必须大于0"));
+        }
+        if !(self.shrinking_factor > 0.0 && self.shrinking_factor < 1.0) {
+            return Err(input_validation_error(format!(
+                "裁剪比例必须在0和1之间，实际为 {}",
+                self.shrinking_factor
+            )));
+        }
+        if self.num_sub_iterations == 0 {
+            return Err(input_validation_error("EM迭代次数必须大于0"));
+        }
+        Ok(())
+    }
+}
+
+/// 训练中片段的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PieceKind {
+    /// 词汇表中已有的片段，不参与裁剪，训练后只更新分数
+    Existing(u32),
+    /// 词汇表中没有的字符，不参与裁剪
+    Char,
+    /// 种子片段
+    Candidate,
+}
+
+/// 训练中的片段
+struct Piece {
+    text: String,
+    score: f64,
+    kind: PieceKind,
+}
+
+/// 预分词片段上的一条边：从第 `from` 个字符边界到第 `to` 个字符边界，`piece` 为 `None` 时是未知字符
+struct Edge {
+    from: usize,
+    to: usize,
+    piece: Option<usize>,
+    score: f64,
+}
+
+/// 训练结果
+pub(crate) struct TrainedPieces {
+    /// 新片段及其分数，按分数从高到低排列
+    pub pieces: Vec<(String, f64)>,
+    /// 词汇表中已有片段的新分数
+    pub scores: Vec<(u32, f64)>,
+}
+
+/// `ln(exp(a) + exp(b))`
+fn log_add(a: f64, b: f64) -> f64 {
+    if a == f64::NEG_INFINITY {
+        return b;
+    }
+    let (high, low) = if a > b { (a, b) } else { (b, a) };
+    high + (low - high).exp().ln_1p()
+}
+
+/// digamma函数，与SentencePiece的M步一样用它代替对数，使低频片段的概率更快趋于0
+fn digamma(mut x: f64) -> f64 {
+    let mut result = 0.0;
+    while x < 7.0 {
+        result -= 1.0 / x;
+        x += 1.0;
+    }
+    x -= 0.5;
+    let xx = 1.0 / x;
+    let xx2 = xx * xx;
+    let xx4 = xx2 * xx2;
+    result + x.ln() + xx2 / 24.0 - 7.0 / 960.0 * xx4 + 31.0 / 8064.0 * xx4 * xx2
+        - 127.0 / 30720.0 * xx4 * xx4
+}
+
+/// 不能进入片段的字符：模型文件按行存储词汇表，换行符只能按字节标记编码
+fn is_excluded_char(ch: char) -> bool {
+    matches!(ch, '\n' | '\r')
+}
+
+/// 训练中的Unigram模型
+struct Model {
+    pieces: Vec<Piece>,
+    index: AHashMap<String, usize>,
+    max_len: usize,
+    unk_score: f64,
+}
+
+impl Model {
+    fn new(pieces: Vec<Piece>) -> Self {
+        let mut model = Self {
+            pieces,
+            index: AHashMap::new(),
+            max_len: 0,
+            unk_score: -UNK_PENALTY,
+        };
+        model.rebuild();
+        model
+    }
+
+    /// 片段变化后重建索引和未知字符分数
+    fn rebuild(&mut self) {
+        self.index = (0..self.pieces.len())
+            .map(|i| (self.pieces[i].text.clone(), i))
+            .collect();
+        self.max_len = self.pieces.iter().map(|p| p.text.len()).max().unwrap_or(0);
+        let min_score = self
+            .pieces
+            .iter()
+            .map(|p| p.score)
+            .fold(f64::INFINITY, f64::min);
+        self.unk_score = if min_score.is_finite() {
+            min_score - UNK_PENALTY
+        } else {
+            -UNK_PENALTY
+        };
+    }
+
+    /// 新片段（字符和种子片段）的数量
+    fn new_piece_count(&self) -> usize {
+        self.pieces
+            .iter()
+            .filter(|p| !matches!(p.kind, PieceKind::Existing(_)))
+            .count()
+    }
+
+    /// 按字符边界列出 `word` 中的全部边，按起点升序排列
+    ///
+    /// 跳过第 `exclude` 个片段；某个位置没有单字符片段时，`allow_unk` 为真则加入一条未知字符边
+    fn edges(&self, word: &str, exclude: Option<usize>, allow_unk: bool) -> (usize, Vec<Edge>) {
+        let bounds: Vec<usize> = word
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(word.len()))
+            .collect();
+        let mut edges = Vec::new();
+        for from in 0..bounds.len() - 1 {
+            let mut has_char = false;
+            for to in from + 1..bounds.len() {
+                if bounds[to] - bounds[from] > self.max_len {
+                    break;
+                }
+                let Some(&piece) = self.index.get(&word[bounds[from]..bounds[to]]) else {
+                    continue;
+                };
+                if Some(piece) == exclude {
+                    continue;
+                }
+                has_char |= to == from + 1;
+                edges.push(Edge {
+                    from,
+                    to,
+                    piece: Some(piece),
+                    score: self.pieces[piece].score,
+                });
+            }
+            if !has_char && allow_unk {
+                edges.push(Edge {
+                    from,
+                    to: from + 1,
+                    piece: None,
+                    score: self.unk_score,
+                });
+            }
+        }
+        (bounds.len() - 1, edges)
+    }
+
+    /// 按前向后向算法把 `word` 中各片段的期望次数乘以 `count` 累加到 `expected`，返回对数似然
+    fn accumulate(&self, word: &str, count: f64, expected: &mut AHashMap<usize, f64>) -> f64 {
+        let (n, edges) = self.edges(word, None, true);
+        let mut alpha = vec![f64::NEG_INFINITY; n + 1];
+        alpha[0] = 0.0;
+        for edge in &edges {
+            alpha[edge.to] = log_add(alpha[edge.to], alpha[edge.from] + edge.score);
+        }
+        let mut beta = vec![f64::NEG_INFINITY; n + 1];
+        beta[n] = 0.0;
+        for edge in edges.iter().rev() {
+            beta[edge.from] = log_add(beta[edge.from], edge.score + beta[edge.to]);
+        }
+
+        let z = alpha[n];
+        for edge in &edges {
+            if let Some(piece) = edge.piece {
+                let posterior = (alpha[edge.from] + edge.score + beta[edge.to] - z).exp();
+                *expected.entry(piece).or_insert(0.0) += count * posterior;
+            }
+        }
+        count * z
+    }
+
+    /// 分数之和最大的切分，返回片段编号；未知字符不出现在结果中，`allow_unk` 为假且无法切分时返回 `None`
+    fn viterbi(&self, word: &str, exclude: Option<usize>, allow_unk: bool) -> Option<Vec<usize>> {
+        let (n, edges) = self.edges(word, exclude, allow_unk);
+        // best[i] = (前i个字符的最高分数, 到达i的边)
+        let mut best: Vec<(f64, Option<usize>)> = vec![(f64::NEG_INFINITY, None); n + 1];
+        best[0].0 = 0.0;
+        for (i, edge) in edges.iter().enumerate() {
+            let candidate = best[edge.from].0 + edge.score;
+            if candidate > best[edge.to].0 {
+                best[edge.to] = (candidate, Some(i));
+            }
+        }
+
+        let mut pieces = Vec::new();
+        let mut position = n;
+        while position > 0 {
+            let edge = &edges[best[position].1?];
+            pieces.extend(edge.piece);
+            position = edge.from;
+        }
+        pieces.reverse();
+        Some(pieces)
+    }
+
+    /// E步：返回各片段的期望次数和语料的对数似然，按固定大小分块累加，结果与线程数无关
+    fn expected_counts(&self, words: &[(&str, i32)]) -> (Vec<f64>, f64) {
+        let partials: Vec<(AHashMap<usize, f64>, f64)> = words
+            .par_chunks(E_STEP_CHUNK)
+            .map(|chunk| {
+                let mut expected = AHashMap::new();
+                let mut objective = 0.0;
+                for &(word, count) in chunk {
+                    objective += self.accumulate(word, f64::from(count), &mut expected);
+                }
+                (expected, objective)
+            })
+            .collect();
+
+        let mut expected = vec![0.0; self.pieces.len()];
+        let mut objective = 0.0;
+        for (partial, partial_objective) in partials {
+            let mut entries: Vec<(usize, f64)> = partial.into_iter().collect();
+            entries.sort_unstable_by_key(|&(piece, _)| piece);
+            for (piece, count) in entries {
+                expected[piece] += count;
+            }
+            objective += partial_objective;
+        }
+        (expected, objective)
+    }
+
+    /// M步：移除期望次数过低的种子片段，其余片段的分数设为 `digamma(次数) - digamma(总次数)`
+    ///
+    /// 词汇表中已有的片段和字符不会移除，次数不足阈值时按阈值计
+    fn maximize(&mut self, expected: &[f64]) {
+        let kept: Vec<(Piece, f64)> = std::mem::take(&mut self.pieces)
+            .into_iter()
+            .zip(expected.iter().copied())
+            .filter(|(piece, freq)| {
+                piece.kind != PieceKind::Candidate || *freq >= EXPECTED_FREQUENCY_THRESHOLD
+            })
+            .collect();
+        let total: f64 = kept.iter().map(|(_, freq)| freq).sum();
+        let log_total = digamma(total.max(EXPECTED_FREQUENCY_THRESHOLD));
+        self.pieces = kept
+            .into_iter()
+            .map(|(mut piece, freq)| {
+                piece.score = digamma(freq.max(EXPECTED_FREQUENCY_THRESHOLD)) - log_total;
+                piece
+            })
+            .collect();
+        self.rebuild();
+    }
+
+    /// 裁剪种子片段，最多保留 `target` 个新片段
+    ///
+    /// 按Viterbi切分统计片段次数，用去掉片段后改用其余片段切分造成的似然损失衡量片段的重要性，
+    /// 保留损失最大的片段。没有出现在切分中的种子片段直接移除，只能整体匹配的种子片段总是保留
+    fn prune(&mut self, words: &[(&str, i32)], target: usize) {
+        let mut freq = vec![0.0; self.pieces.len()];
+        let segmentations: Vec<Vec<usize>> = words
+            .par_iter()
+            .map(|&(word, _)| self.viterbi(word, None, true).unwrap_or_default())
+            .collect();
+        for (&(_, count), pieces) in words.iter().zip(&segmentations) {
+            for &piece in pieces {
+                freq[piece] += f64::from(count);
+            }
+        }
+        let total: f64 = freq.iter().sum();
+        let log_total = total.ln();
+
+        let alternatives: Vec<Option<Vec<usize>>> = (0..self.pieces.len())
+            .into_par_iter()
+            .map(|i| {
+                (self.pieces[i].kind == PieceKind::Candidate && freq[i] > 0.0)
+                    .then(|| self.viterbi(&self.pieces[i].text, Some(i), false))
+                    .flatten()
+            })
+            .collect();
+
+        let mut keep = vec![false; self.pieces.len()];
+        let mut kept_new = 0;
+        let mut candidates = Vec::new();
+        for (i, piece) in self.pieces.iter().enumerate() {
+            match piece.kind {
+                PieceKind::Existing(_) => keep[i] = true,
+                PieceKind::Char => {
+                    keep[i] = true;
+                    kept_new += 1;
+                }
+                PieceKind::Candidate if freq[i] == 0.0 => {}
+                PieceKind::Candidate => match &alternatives[i] {
+                    None => {
+                        keep[i] = true;
+                        kept_new += 1;
+                    }
+                    Some(alternative) => {
+                        let log_prob = freq[i].ln() - log_total;
+                        let log_total_alt =
+                            (total + freq[i] * (alternative.len() as f64 - 1.0)).ln();
+                        let log_prob_alt: f64 = alternative
+                            .iter()
+                            .map(|&j| (freq[j] + freq[i]).ln() - log_total_alt)
+                            .sum();
+                        let loss = freq[i] / total * (log_prob - log_prob_alt);
+                        candidates.push((i, loss));
+                    }
+                },
+            }
+        }
+
+        candidates.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| self.pieces[a.0].text.cmp(&self.pieces[b.0].text))
+        });
+        for (i, _) in candidates {
+            if kept_new >= target {
+                break;
+            }
+            keep[i] = true;
+            kept_new += 1;
+        }
+
+        let mut index = 0;
+        self.pieces.retain(|_| {
+            index += 1;
+            keep[index - 1]
+        });
+        self.rebuild();
+    }
+
+    /// 执行 `iterations` 轮EM
+    fn run_em(&mut self, words: &[(&str, i32)], iterations: usize) {
+        for _ in 0..iterations {
+            let (expected, objective) = self.expected_counts(words);
+            log::debug!("Unigram EM：对数似然 {:.3}", objective);
+            self.maximize(&expected);
+        }
+    }
+}
+
+/// 从语料中选出种子片段，连同词汇表中已有的片段一起构成初始模型
+///
+/// 种子片段是长度不超过 `max_len` 个字节、达到最低出现次数的子字符串，按 `次数 × 字符数` 从高到低取前
+/// `seed_size` 个。初始分数为次数（种子片段为 `次数 × 字符数`）的对数概率
+fn seed_model(
+    words: &[(&str, i32)],
+    existing: &[(u32, &str)],
+    config: &TrainerConfig,
+    seed_size: usize,
+    chunking: ParallelChunking,
+) -> Model {
+    let mut char_counts: HashMap<char, u64> = HashMap::new();
+    for &(word, count) in words {
+        for ch in word.chars().filter(|&ch| !is_excluded_char(ch)) {
+            *char_counts.entry(ch).or_insert(0) += count as u64;
+        }
+    }
+    let alphabet = config.alphabet(&char_counts);
+    let in_alphabet = |ch: char| {
+        !is_excluded_char(ch)
+            && alphabet
+                .as_ref()
+                .is_none_or(|alphabet| alphabet.contains(&ch))
+    };
+
+    let max_len = config.max_piece_length.unwrap_or(DEFAULT_MAX_PIECE_BYTES);
+    let substring_counts: HashMap<&str, i32> = chunking.count(words, |&(word, count), local| {
+        let bounds: Vec<(usize, char)> = word.char_indices().collect();
+        for (start, &(from, _)) in bounds.iter().enumerate() {
+            for &(to, ch) in &bounds[start..] {
+                let end = to + ch.len_utf8();
+                if end - from > max_len || !in_alphabet(ch) {
+                    break;
+                }
+                *local.entry(&word[from..end]).or_insert(0) += count;
+            }
+        }
+    });
+
+    let existing_ids: AHashMap<&str, u32> = existing.iter().map(|&(id, text)| (text, id)).collect();
+    let mut seeds: Vec<(&str, u64)> = Vec::new();
+    let mut chars: Vec<(&str, u64)> = Vec::new();
+    for (&text, &count) in &substring_counts {
+        if existing_ids.contains_key(text) {
+            continue;
+        }
+        let char_len = text.chars().count();
+        if char_len == 1 {
+            chars.push((text, count as u64));
+        } else if config.keeps_frequency(count as u64) && piece_bytes(text).len() == text.len() {
+            seeds.push((text, count as u64 * char_len as u64));
+        }
+    }
+    seeds.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    seeds.truncate(seed_size);
+    chars.sort_unstable();
+
+    // 词汇表中已有的片段按出现次数计，未出现的按阈值计
+    let mut pieces: Vec<(Piece, f64)> = Vec::new();
+    for &(id, text) in existing {
+        let count = substring_counts
+            .get(text)
+            .map_or(0.0, |&count| f64::from(count));
+        let piece = Piece {
+            text: text.to_string(),
+            score: 0.0,
+            kind: PieceKind::Existing(id),
+        };
+        pieces.push((piece, count.max(EXPECTED_FREQUENCY_THRESHOLD)));
+    }
+    for (kind, list) in [(PieceKind::Char, chars), (PieceKind::Candidate, seeds)] {
+        for (text, weight) in list {
+            let piece = Piece {
+                text: text.to_string(),
+                score: 0.0,
+                kind,
+            };
+            pieces.push((piece, weight as f64));
+        }
+    }
+
+    let log_total = pieces.iter().map(|(_, weight)| weight).sum::<f64>().ln();
+    Model::new(
+        pieces
+            .into_iter()
+            .map(|(mut piece, weight)| {
+                piece.score = weight.ln() - log_total;
+                piece
+            })
+            .collect(),
+    )
+}
+
+/// 在预分词片段 `words` 上训练，最多学习 `budget` 个新片段
+///
+/// `existing` 为词汇表中参与分段的片段，它们总是保留并参与EM，训练后更新分数。
+/// 每轮执行 `num_sub_iterations` 次EM，新片段数不超过 `budget` 的1.1倍时停止裁剪，
+/// 最后保留语料中的字符和分数最高的种子片段
+pub(crate) fn learn_pieces(
+    words: &[(&str, i32)],
+    existing: &[(u32, &str)],
+    config: &TrainerConfig,
+    em: &UnigramTrainerConfig,
+    chunking: ParallelChunking,
+    budget: usize,
+) -> TrainedPieces {
+    let mut model = seed_model(words, existing, config, em.seed_size, chunking);
+    let desired = budget + budget / 10;
+    loop {
+        model.run_em(words, em.num_sub_iterations);
+        let count = model.new_piece_count();
+        log::info!("Unigram训练：{} 个新片段", count);
+        if count <= desired {
+            break;
+        }
+        let target = desired.max((count as f64 * em.shrinking_factor) as usize);
+        model.prune(words, target);
+        if model.new_piece_count() >= count {
+            break;
+        }
+    }
+
+    // 字符优先，其余按分数从高到低取满预算
+    let mut new_pieces: Vec<&Piece> = model
+        .pieces
+        .iter()
+        .filter(|p| !matches!(p.kind, PieceKind::Existing(_)))
+        .collect();
+    new_pieces.sort_by(|a, b| {
+        (b.kind == PieceKind::Char)
+            .cmp(&(a.kind == PieceKind::Char))
+            .then(b.score.total_cmp(&a.score))
+            .then_with(|| a.text.cmp(&b.text))
+    });
+    new_pieces.truncate(budget);
+    new_pieces.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.text.cmp(&b.text))
+    });
+
+    TrainedPieces {
+        pieces: new_pieces
+            .into_iter()
+            .map(|p| (p.text.clone(), p.score))
+            .collect(),
+        scores: model
+            .pieces
+            .iter()
+            .filter_map(|p| match p.kind {
+                PieceKind::Existing(id) => Some((id, p.score)),
+                _ => None,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,97 @@
+//! WebAssembly绑定
+//!
+//! 通过 wasm-bindgen 导出BBPE和Unigram分词器。浏览器和边缘运行时没有文件系统，
+//! 模型直接从字节数据加载，编码结果与训练时使用的实现完全一致。
+
+use wasm_bindgen::prelude::*;
+
+use crate::base::traits::Tokenizer;
+use crate::bbpe::BBPETokenizer;
+use crate::unigram::UnigramTokenizer;
+
+fn js_error(error: impl std::fmt::Display) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+/// BBPE分词器的JavaScript包装
+#[wasm_bindgen(js_name = BBPETokenizer)]
+pub struct WasmBBPETokenizer {
+    inner: BBPETokenizer,
+}
+
+#[wasm_bindgen(js_class = BBPETokenizer)]
+impl WasmBBPETokenizer {
+    /// 从 `save` 写出的模型文件内容创建分词器
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(model: &[u8]) -> Result<WasmBBPETokenizer, JsError> {
+        let inner = BBPETokenizer::from_bytes(model).map_err(js_error)?;
+        Ok(Self { inner })
+    }
+
+    /// 编码文本为标记ID序列
+    pub fn encode(&self, text: &str) -> Result<Vec<u32>, JsError> {
+        self.inner.encode(text).map_err(js_error)
+    }
+
+    /// 解码标记ID序列为文本
+    pub fn decode(&self, ids: &[u32]) -> Result<String, JsError> {
+        Tokenizer::decode(&self.inner, ids).map_err(js_error)
+    }
+
+    /// 统计文本编码后的标记数
+    pub fn count(&self, text: &str) -> Result<usize, JsError> {
+        self.inner
+            .encode(text)
+            .map(|ids| ids.len())
+            .map_err(js_error)
+    }
+
+    /// 词汇表大小
+    #[wasm_bindgen(getter, js_name = vocabSize)]
+    pub fn vocab_size(&self) -> usize {
+        self.inner.vocab_size()
+    }
+}
+
+/// Unigram分词器的JavaScript包装
+#[wasm_bindgen(js_name = UnigramTokenizer)]
+pub struct WasmUnigramTokenizer {
+    inner: UnigramTokenizer,
+}
+
+#[wasm_bindgen(js_class = UnigramTokenizer)]
+impl WasmUnigramTokenizer {
+    /// 从 `save` 写出的模型文件内容创建分词器，旧模型还需要传入 `.scores` 文件的内容
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(
+        model: &[u8],
+        scores: Option<Vec<u8>>,
+    ) -> Result<WasmUnigramTokenizer, JsError> {
+        let scores = scores.unwrap_or_default();
+        let inner = UnigramTokenizer::from_bytes(model, &scores).map_err(js_error)?;
+        Ok(Self { inner })
+    }
+
+    /// 编码文本为标记ID序列
+    pub fn encode(&self, text: &str) -> Result<Vec<u32>, JsError> {
+        Tokenizer::encode(&self.inner, text).map_err(js_error)
+    }
+
+    /// 解码标记ID序列为文本
+    pub fn decode(&self, ids: &[u32]) -> Result<String, JsError> {
+        Tokenizer::decode(&self.inner, ids).map_err(js_error)
+    }
+
+    /// 统计文本编码后的标记数
+    pub fn count(&self, text: &str) -> Result<usize, JsError> {
+        Tokenizer::encode(&self.inner, text)
+            .map(|ids| ids.len())
+            .map_err(js_error)
+    }
+
+    /// 词汇表大小
+    #[wasm_bindgen(getter, js_name = vocabSize)]
+    pub fn vocab_size(&self) -> usize {
+        Tokenizer::vocab_size(&self.inner)
+    }
+}
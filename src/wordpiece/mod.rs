@@ -1,3 +1,7 @@
 mod tokenizer;
+mod trainer;
 
-pub use tokenizer::WordPieceTokenizer;
+pub use tokenizer::{
+    WordPieceTokenizer, BERT_SPECIAL_TOKENS, CLS_TOKEN_ID, MASK_TOKEN_ID, PAD_TOKEN_ID,
+    SEP_TOKEN_ID, UNK_TOKEN_ID,
+};
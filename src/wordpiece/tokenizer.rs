@@ -2,10 +2,73 @@
 use pyo3::exceptions::PyValueError;
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-use crate::base::tokenizer_base::TokenizerBase;
-use crate::base::traits::{SubwordTokenizer, Tokenizer};
+use fancy_regex::Regex;
+use rayon::prelude::*;
+
+use crate::base::binary::{self, BinaryModel, ModelKind};
+use crate::base::decoder::{decode_ids, DecodedPiece, DecoderKind};
+use crate::base::hf_json::{HfModel, HfTokenizerJson};
+use crate::base::integrity::IntegrityReport;
+use crate::base::normalizer::NormalizedText;
+#[cfg(feature = "python")]
+use crate::base::normalizer::Normalizer;
+use crate::base::padding::EncodeOptions;
+#[cfg(feature = "python")]
+use crate::base::pre_tokenizer::PreTokenizerPipeline;
+#[cfg(feature = "python")]
+use crate::base::registry::NamedModel;
+use crate::base::resources::ResourceConfig;
+use crate::base::score::{format_score, parse_score};
+use crate::base::special_tokens::SpecialTokens;
+use crate::base::tokenizer_base::{
+    compile_pattern, for_each_piece, lines_after_vocab, piece_bytes, TokenizerBase,
+};
+use crate::base::trainer_config::TrainerConfig;
+use crate::base::traits::{SpecialTokenizer, SubwordTokenizer, Tokenizer, VocabBytes};
+use crate::base::unk_stats::UnkCounter;
+use crate::base::vocab_manager::VocabManager;
+#[cfg(feature = "python")]
+use crate::error::py_value_error;
+use crate::error::{
+    input_validation_error, invalid_utf8_error, load_error, model_load_error, model_save_error,
+    vocab_error, TokenizerError,
+};
+#[cfg(feature = "python")]
+use crate::generation::TextSampler;
+use crate::wordpiece::trainer::learn_pieces;
+
+/// BERT的特殊标记，启用后依次占用ID 0–4
+pub const BERT_SPECIAL_TOKENS: [&str; 5] = ["[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]"];
+/// `[PAD]` 的ID
+pub const PAD_TOKEN_ID: u32 = 0;
+/// `[UNK]` 的ID
+pub const UNK_TOKEN_ID: u32 = 1;
+
+/// 模型文件中未知标记行的前缀，完整形式为 `unk_token: <ID> <标记>`
+const UNK_TOKEN_HEADER: &str = "unk_token: ";
+/// `[CLS]` 的ID
+pub const CLS_TOKEN_ID: u32 = 2;
+/// `[SEP]` 的ID
+pub const SEP_TOKEN_ID: u32 = 3;
+/// `[MASK]` 的ID
+pub const MASK_TOKEN_ID: u32 = 4;
+
+/// 默认的续接前缀，与BERT相同
+pub const DEFAULT_CONTINUING_SUBWORD_PREFIX: &str = "##";
+/// 模型文件中续接前缀行的前缀，完整形式为 `continuing_subword_prefix: <前缀>`，没有此行的旧模型不区分续接片段
+const CONTINUING_SUBWORD_PREFIX_HEADER: &str = "continuing_subword_prefix: ";
+
+/// 单个字节在词汇表中的写法：可打印ASCII字符为字符本身，其余为 `<0xNN>`
+pub(crate) fn byte_token(byte: u8) -> String {
+    if (32..=126).contains(&byte) {
+        char::from(byte).to_string()
+    } else {
+        format!("<0x{:02X}>", byte)
+    }
+}
 
 /// WordPiece分词器
 #[cfg_attr(feature = "python", pyclass)]
@@ -18,97 +81,345 @@ pub struct WordPieceTokenizer {
     pub unk_token_id: u32,
     /// 下一个可用的token ID
     pub next_token_id: u32,
+    /// 续接前缀：以它开头的标记只在词内（预分词片段的第一个字符之后）匹配，解码时去掉前缀。
+    /// 为空时不区分词首和续接片段
+    pub continuing_subword_prefix: String,
+    /// 自加载以来编码输出未知标记的统计
+    pub unk_stats: UnkCounter,
 }
 
 impl WordPieceTokenizer {
-    /// 创建新的WordPiece分词器
-    pub fn new_internal() -> Result<Self, String> {
-        let base = TokenizerBase::new()?;
+    /// 创建新的WordPiece分词器，常用汉字字表按 [`ResourceConfig`] 的默认设置加载
+    pub fn new_internal() -> Result<Self, TokenizerError> {
+        Self::with_resources(&ResourceConfig::default())
+    }
 
+    /// 按 `resources` 加载初始字符集，创建新的WordPiece分词器
+    ///
+    /// # Errors
+    ///
+    /// 当字表文件无法读取时返回错误
+    pub fn with_resources(resources: &ResourceConfig) -> Result<Self, TokenizerError> {
+        Self::build(TokenizerBase::new()?, false, resources)
+    }
+
+    /// 使用自定义正则表达式模式创建新的WordPiece分词器
+    pub fn with_pattern_internal(pattern: String) -> Result<Self, TokenizerError> {
+        Self::build(
+            TokenizerBase::with_pattern(pattern)?,
+            false,
+            &ResourceConfig::default(),
+        )
+    }
+
+    /// 创建带有BERT特殊标记的WordPiece分词器
+    ///
+    /// `[PAD]`、`[UNK]`、`[CLS]`、`[SEP]`、`[MASK]` 依次占用ID 0–4 并注册为特殊标记，
+    /// 字节标记和常用汉字顺延，未知标记为 `[UNK]`，与标准BERT检查点的特殊标记ID一致。
+    /// 配合 [`WordPieceTokenizer::encode_with_special_tokens`] 在编码结果两端加上 `[CLS]`/`[SEP]`。
+    pub fn with_bert_special_tokens_internal() -> Result<Self, TokenizerError> {
+        Self::with_bert_special_tokens_and_resources(&ResourceConfig::default())
+    }
+
+    /// 按 `resources` 加载初始字符集，创建带有BERT特殊标记的WordPiece分词器，
+    /// 见 [`WordPieceTokenizer::with_bert_special_tokens_internal`]
+    ///
+    /// # Errors
+    ///
+    /// 当字表文件无法读取时返回错误
+    pub fn with_bert_special_tokens_and_resources(
+        resources: &ResourceConfig,
+    ) -> Result<Self, TokenizerError> {
+        Self::build(TokenizerBase::new()?, true, resources)
+    }
+
+    fn build(
+        base: TokenizerBase<u32>,
+        bert_special_tokens: bool,
+        resources: &ResourceConfig,
+    ) -> Result<Self, TokenizerError> {
         let mut tokenizer = Self {
             base,
             scores: Vec::new(),
             unk_token_id: 0,
             next_token_id: 0,
+            continuing_subword_prefix: DEFAULT_CONTINUING_SUBWORD_PREFIX.to_string(),
+            unk_stats: UnkCounter::default(),
         };
 
         // 初始化字节词汇表和常用汉字
-        tokenizer.init_byte_vocab();
-        tokenizer.load_common_chinese_chars(None)?;
+        tokenizer.init_byte_vocab(bert_special_tokens);
+        tokenizer.load_common_chinese_chars(resources)?;
 
         Ok(tokenizer)
     }
 
-    /// 使用自定义正则表达式模式创建新的WordPiece分词器
-    pub fn with_pattern_internal(pattern: String) -> Result<Self, String> {
-        let base = TokenizerBase::with_pattern(pattern)?;
+    /// 词汇表的ID 0–4 是否为BERT特殊标记
+    ///
+    /// 直接检查词汇表，因此保存后重新加载的模型同样适用
+    pub fn has_bert_special_tokens(&self) -> bool {
+        BERT_SPECIAL_TOKENS
+            .iter()
+            .zip(0u32..)
+            .all(|(token, id)| self.base.vocab.get_by_id(&id).is_some_and(|t| t == token))
+    }
+
+    /// 编码单条文本并加上BERT后处理：`[CLS] 文本 [SEP]`
+    ///
+    /// # Errors
+    ///
+    /// 当词汇表没有注册BERT特殊标记或编码失败时返回错误
+    pub fn encode_with_special_tokens(&self, text: &str) -> Result<Vec<u32>, TokenizerError> {
+        self.ensure_bert_special_tokens()?;
+        let ids = Tokenizer::encode(self, text)?;
+
+        let mut result = Vec::with_capacity(ids.len() + 2);
+        result.push(CLS_TOKEN_ID);
+        result.extend(ids);
+        result.push(SEP_TOKEN_ID);
+        Ok(result)
+    }
+
+    /// 编码句子对并加上BERT后处理：`[CLS] 第一句 [SEP] 第二句 [SEP]`
+    ///
+    /// # Errors
+    ///
+    /// 当词汇表没有注册BERT特殊标记或编码失败时返回错误
+    pub fn encode_pair_with_special_tokens(
+        &self,
+        first: &str,
+        second: &str,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        let mut result = self.encode_with_special_tokens(first)?;
+        result.extend(Tokenizer::encode(self, second)?);
+        result.push(SEP_TOKEN_ID);
+        Ok(result)
+    }
+
+    fn ensure_bert_special_tokens(&self) -> Result<(), TokenizerError> {
+        if self.has_bert_special_tokens() {
+            Ok(())
+        } else {
+            Err(vocab_error(
+                "词汇表未注册BERT特殊标记，请使用 with_bert_special_tokens 创建分词器",
+            ))
+        }
+    }
+
+    /// 设置未知标记，返回其ID
+    ///
+    /// 标记已在词汇表中时直接使用其ID；否则注册到 `id`（未给出时使用下一个可用ID）。
+    /// 未知标记作为特殊标记，不会从输入文本中匹配出来，并随模型文件保存
+    ///
+    /// # Errors
+    ///
+    /// 当标记为空或包含换行符、标记已在词汇表中但ID与 `id` 不同，或 `id` 已被其他标记占用时返回错误
+    pub fn set_unk_token(&mut self, token: &str, id: Option<u32>) -> Result<u32, TokenizerError> {
+        if token.is_empty() || token.contains(['\n', '\r']) {
+            return Err(vocab_error(format!(
+                "未知标记 {:?} 为空或包含换行符",
+                token
+            )));
+        }
+
+        let unk_token_id = match (self.base.vocab.get_by_value(&token.to_string()), id) {
+            (Some(&existing), Some(id)) if existing != id => {
+                return Err(vocab_error(format!(
+                    "标记 {:?} 已在词汇表中，ID为 {} 而不是 {}",
+                    token, existing, id
+                )));
+            }
+            (Some(&existing), _) => existing,
+            (None, id) => {
+                let next_free = self
+                    .base
+                    .vocab
+                    .ids()
+                    .max()
+                    .map_or(0, |&max| max + 1)
+                    .max(self.next_token_id);
+                let id = id.unwrap_or(next_free);
+                if let Some(occupied) = self.base.vocab.get_by_id(&id) {
+                    return Err(vocab_error(format!(
+                        "ID {} 已被标记 {:?} 占用",
+                        id, occupied
+                    )));
+                }
+                self.base.vocab.insert(id, token.to_string());
+                if self.scores.len() <= id as usize {
+                    self.scores.resize(id as usize + 1, 0.0);
+                }
+                self.next_token_id = self.next_token_id.max(id + 1);
+                id
+            }
+        };
+
+        self.unk_token_id = unk_token_id;
+        Ok(unk_token_id)
+    }
+
+    /// 当前未知标记的文本
+    pub fn unk_token(&self) -> Option<&str> {
+        self.base
+            .vocab
+            .get_by_id(&self.unk_token_id)
+            .map(String::as_str)
+    }
 
+    /// 直接从内存中的模型数据创建分词器
+    ///
+    /// 不读取常用汉字字表，适用于浏览器等没有文件系统的环境
+    ///
+    /// # Errors
+    ///
+    /// 当模型数据或分数数据格式无效、解析失败时返回错误
+    pub fn from_bytes(model: &[u8], scores: &[u8]) -> Result<Self, TokenizerError> {
         let mut tokenizer = Self {
-            base,
+            base: TokenizerBase::new()?,
             scores: Vec::new(),
             unk_token_id: 0,
             next_token_id: 0,
+            continuing_subword_prefix: DEFAULT_CONTINUING_SUBWORD_PREFIX.to_string(),
+            unk_stats: UnkCounter::default(),
         };
+        tokenizer.load_from_bytes(model, scores)?;
+        Ok(tokenizer)
+    }
 
-        // 初始化字节词汇表和常用汉字
-        tokenizer.init_byte_vocab();
-        tokenizer.load_common_chinese_chars(None)?;
+    /// 检查词汇表的正向和反向映射以及特殊标记注册表，见 [`IntegrityReport`]
+    ///
+    /// 加载时会自动检查，直接修改词汇表后可以再次调用
+    #[must_use]
+    pub fn check_integrity(&self) -> IntegrityReport {
+        let mut report = IntegrityReport::default();
+        report.check_vocab(&self.base.vocab);
+        report.check_special_tokens(&self.base.special_tokens, &self.base.vocab);
+        report
+    }
 
-        Ok(tokenizer)
+    /// 从内存中的模型数据和分数数据加载分词器
+    ///
+    /// 两者的格式分别与 [`Tokenizer::save`] 写出的模型文件和 `.scores` 文件相同
+    ///
+    /// # Errors
+    ///
+    /// 当模型数据或分数数据格式无效、解析失败或完整性检查发现问题时返回错误
+    pub fn load_from_bytes(&mut self, model: &[u8], scores: &[u8]) -> Result<(), TokenizerError> {
+        self.unk_stats.reset();
+        // 使用基础分词器的加载功能
+        self.base.load_from_reader(model)?;
+        self.load_scores(model, scores)
+    }
+
+    /// 加载分数以及词汇表之后的续接前缀和未知标记，并检查完整性
+    fn load_scores(&mut self, model: &[u8], scores: &[u8]) -> Result<(), TokenizerError> {
+        // 加载分数
+        let scores_content = std::str::from_utf8(scores)
+            .map_err(|e| model_load_error(format!("加载分数失败: {}", e)))?;
+
+        let mut lines = scores_content.lines();
+        if let Some(first_line) = lines.next() {
+            self.unk_token_id = first_line
+                .trim()
+                .parse()
+                .map_err(|e| model_load_error(format!("解析未知标记ID失败: {}", e)))?;
+        }
+
+        self.scores.clear();
+        for (index, line) in lines.filter(|line| !line.trim().is_empty()).enumerate() {
+            let score = parse_score(line)
+                .map_err(|e| model_load_error(format!("解析第 {} 个分数失败: {}", index, e)))?;
+            self.scores.push(score);
+        }
+
+        // 模型文件中记录的未知标记优先于分数文件首行的ID
+        let model_content = std::str::from_utf8(model)
+            .map_err(|e| model_load_error(format!("模型数据不是有效的UTF-8: {}", e)))?;
+        self.continuing_subword_prefix.clear();
+        for line in lines_after_vocab(model_content)? {
+            if let Some(prefix) = line.strip_prefix(CONTINUING_SUBWORD_PREFIX_HEADER) {
+                self.continuing_subword_prefix = prefix.to_string();
+                continue;
+            }
+            let Some(entry) = line.strip_prefix(UNK_TOKEN_HEADER) else {
+                continue;
+            };
+            let (id, token) = entry
+                .split_once(' ')
+                .ok_or_else(|| model_load_error("无效的未知标记行"))?;
+            let id: u32 = id
+                .parse()
+                .map_err(|e| model_load_error(format!("解析未知标记ID失败: {}", e)))?;
+            if self.base.vocab.get_by_id(&id).map(String::as_str) != Some(token) {
+                return Err(model_load_error(format!(
+                    "未知标记 {:?} 与词汇表中ID {} 的标记不一致",
+                    token, id
+                )));
+            }
+            self.unk_token_id = id;
+        }
+        self.next_token_id = self
+            .base
+            .vocab
+            .ids()
+            .max()
+            .map_or(0, |&id| id + 1)
+            .max(self.base.special_tokens.end_id());
+
+        // 旧模型没有特殊标记行，ID 0–4 为BERT特殊标记时按特殊标记注册
+        if self.base.special_tokens.is_empty() && self.has_bert_special_tokens() {
+            for (token, id) in BERT_SPECIAL_TOKENS.iter().zip(0u32..) {
+                self.base.special_tokens.insert(token, id)?;
+            }
+        }
+
+        self.check_integrity().into_result()
     }
 
     /// 初始化词汇表，添加所有字节值
-    fn init_byte_vocab(&mut self) {
+    ///
+    /// `bert_special_tokens` 为真时先在ID 0–4 注册BERT特殊标记，字节标记顺延
+    fn init_byte_vocab(&mut self, bert_special_tokens: bool) {
         // 清空现有词汇表
         self.base.vocab.clear();
+        self.base.special_tokens.clear();
         self.scores.clear();
 
-        // 添加所有字节值
-        for i in 0..=255 {
-            let byte_str = format!("<0x{:02X}>", i);
-            let _byte_vec = [i];
-
-            // 将字节向量转换为字符串表示
-            let token = if (32..=126).contains(&i) {
-                // 可打印ASCII字符
-                char::from(i).to_string()
-            } else {
-                // 非打印字符使用特殊表示
-                byte_str
-            };
+        let offset = if bert_special_tokens {
+            for (token, id) in BERT_SPECIAL_TOKENS.iter().zip(0u32..) {
+                self.base.vocab.insert(id, token.to_string());
+                self.base
+                    .special_tokens
+                    .insert(token, id)
+                    .expect("BERT special tokens are non-empty and distinct");
+                self.scores.push(0.0);
+            }
+            BERT_SPECIAL_TOKENS.len() as u32
+        } else {
+            0
+        };
 
-            self.base.vocab.insert(i as u32, token);
+        // 添加所有字节值，可打印ASCII字符以字符本身表示
+        for i in 0..=255u8 {
+            self.base.vocab.insert(offset + u32::from(i), byte_token(i));
             self.scores.push(0.0); // 初始分数为0
         }
 
         // 设置未知标记ID
-        self.unk_token_id = 0;
+        self.unk_token_id = if bert_special_tokens { UNK_TOKEN_ID } else { 0 };
         // 设置下一个可用的token ID
         self.next_token_id = self.base.vocab.len() as u32;
     }
 
-    /// 加载常用汉字
-    ///
-    /// # 参数
-    /// - `file_path`: 汉字字表文件路径，默认为 "dict/常用汉字字表.txt"
-    fn load_common_chinese_chars(&mut self, file_path: Option<&str>) -> Result<(), String> {
-        use std::fs::File;
-        use std::io::{BufRead, BufReader};
-
-        let file_path = file_path.unwrap_or("dict/常用汉字字表.txt");
-        let file = File::open(file_path).map_err(|e| format!("无法打开常用汉字文件: {}", e))?;
-
-        let reader = BufReader::new(file);
-
-        for line in reader.lines() {
-            let line = line.map_err(|e| format!("读取常用汉字文件失败: {}", e))?;
-            let char_str = line.trim();
-            if !char_str.is_empty() {
-                // 将汉字添加到词汇表
-                let token_id = self.base.vocab.len() as u32;
-                self.base.vocab.insert(token_id, char_str.to_string());
-                self.scores.push(0.0); // 初始分数为0
-            }
+    /// 把 `resources` 指定的常用汉字加入词汇表
+    fn load_common_chinese_chars(
+        &mut self,
+        resources: &ResourceConfig,
+    ) -> Result<(), TokenizerError> {
+        for char_str in resources.char_dict_entries()? {
+            let token_id = self.base.vocab.len() as u32;
+            self.base.vocab.insert(token_id, char_str);
+            self.scores.push(0.0); // 初始分数为0
         }
 
         // 更新下一个可用的token ID
@@ -118,20 +429,26 @@ impl WordPieceTokenizer {
     }
 
     /// 从dict目录加载初始化词表
-    fn _load_vocab_from_dict(&mut self, dict_file: &str) -> Result<(), String> {
+    fn _load_vocab_from_dict(&mut self, dict_file: &str) -> Result<(), TokenizerError> {
         use std::fs::File;
         use std::io::{BufRead, BufReader};
 
-        let file = File::open(dict_file).map_err(|e| format!("无法打开词表文件: {}", e))?;
+        let file =
+            File::open(dict_file).map_err(|e| load_error(format!("无法打开词表文件: {}", e)))?;
 
         let reader = BufReader::new(file);
 
-        // 清除256以上的条目，保留基础字节词汇表
+        // 清除字节词汇表之后的条目，保留BERT特殊标记和基础字节词汇表
+        let base_len = if self.has_bert_special_tokens() {
+            BERT_SPECIAL_TOKENS.len() as u32 + 256
+        } else {
+            256
+        };
         let ids_to_remove: Vec<u32> = self
             .base
             .vocab
             .ids()
-            .filter(|&&id| id >= 256)
+            .filter(|&&id| id >= base_len)
             .copied()
             .collect();
 
@@ -142,10 +459,14 @@ impl WordPieceTokenizer {
                 self.scores.remove(id as usize);
             }
         }
+        // 被清除的特殊标记需要在加载之后重新注册
+        self.base
+            .special_tokens
+            .remap(|id| (id < base_len).then_some(id));
 
         // 从文件加载新的词汇
         for line in reader.lines() {
-            let line = line.map_err(|e| format!("读取词表文件失败: {}", e))?;
+            let line = line.map_err(|e| load_error(format!("读取词表文件失败: {}", e)))?;
             let token = line.trim();
             if !token.is_empty() {
                 // 检查token是否已存在
@@ -167,249 +488,584 @@ impl WordPieceTokenizer {
         Ok(())
     }
 
-    /// 从文本中提取常见子字符串
-    fn extract_common_substrings(
-        &self,
-        texts: &[String],
-        max_substrings: usize,
-    ) -> Vec<(Vec<u8>, usize)> {
-        let mut substring_counts: HashMap<Vec<u8>, usize> = HashMap::new();
-
-        for text in texts {
-            let bytes = text.as_bytes();
-            // 添加长度最多为4的所有子字符串
-            for i in 0..bytes.len() {
-                for j in (i + 1)..=(i + 4).min(bytes.len()) {
-                    let substring = bytes[i..j].to_vec();
-                    *substring_counts.entry(substring).or_insert(0) += 1;
-                }
-            }
+    /// 设置训练时合并配对的最低出现次数，0和1都表示不过滤
+    ///
+    /// 等同于设置 [`TrainerConfig::min_frequency`]
+    pub fn set_min_frequency(&mut self, min_frequency: usize) {
+        self.base.trainer.min_frequency = min_frequency as u64;
+    }
+
+    /// 设置续接前缀，之后训练得到的续接片段使用新前缀，为空时不区分词首和续接片段
+    ///
+    /// 词汇表中已有的标记不会改写，通常在训练前设置
+    ///
+    /// # Errors
+    ///
+    /// 当前缀包含换行符时返回错误
+    pub fn set_continuing_subword_prefix(&mut self, prefix: &str) -> Result<(), TokenizerError> {
+        if prefix.contains(['\n', '\r']) {
+            return Err(input_validation_error(format!(
+                "续接前缀 {:?} 包含换行符",
+                prefix
+            )));
         }
+        self.continuing_subword_prefix = prefix.to_string();
+        Ok(())
+    }
+
+    /// 去掉续接片段的前缀，其余标记原样返回；与前缀相同的标记不算续接片段
+    fn strip_continuation<'a>(&self, piece: &'a str) -> &'a str {
+        match piece.strip_prefix(self.continuing_subword_prefix.as_str()) {
+            Some(rest) if !rest.is_empty() && !self.continuing_subword_prefix.is_empty() => rest,
+            _ => piece,
+        }
+    }
 
-        // 按频率排序并返回前max_substrings个
-        let mut sorted_substrings: Vec<_> = substring_counts.into_iter().collect();
-        sorted_substrings.sort_by(|a, b| b.1.cmp(&a.1));
-        sorted_substrings.into_iter().take(max_substrings).collect()
+    /// 标记解码后的字节：去掉续接前缀，`<0xNN>` 形式的字节标记还原为单个字节
+    fn token_piece_bytes<'a>(&self, piece: &'a str) -> Cow<'a, [u8]> {
+        piece_bytes(self.strip_continuation(piece))
     }
 
-    /// 将字节向量转换为字符串表示
-    fn bytes_to_string(&self, bytes: &[u8]) -> String {
-        let mut result = String::new();
-        for &byte in bytes {
-            if (32..=126).contains(&byte) {
-                // 可打印ASCII字符
-                result.push(char::from(byte));
+    /// 词汇表中最长的标记解码后的字节数，用于限制贪婪匹配的窗口
+    fn max_piece_len(&self) -> usize {
+        self.base
+            .vocab
+            .values()
+            .map(|piece| self.token_piece_bytes(piece).len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 可以从文本中匹配出的标记ID，未知标记和特殊标记除外
+    fn matchable(&self, token: &str) -> Option<u32> {
+        self.base
+            .vocab
+            .value_map()
+            .get(token)
+            .copied()
+            .filter(|&id| id != self.unk_token_id && !self.base.special_tokens.contains_id(id))
+    }
+
+    /// 与 `bytes` 完全相同的词首标记：单个字节查找字节标记，多个字节查找同样文本的标记
+    fn lookup_piece(&self, bytes: &[u8]) -> Option<u32> {
+        if let [byte] = bytes {
+            return self.matchable(&byte_token(*byte));
+        }
+        let text = std::str::from_utf8(bytes).ok()?;
+        // 文本恰好写成 `<0xNN>` 时不能匹配到对应的字节标记
+        if matches!(piece_bytes(text), Cow::Owned(_)) {
+            return None;
+        }
+        self.matchable(text)
+    }
+
+    /// 与 `bytes` 完全相同的续接标记，`buffer` 用于拼接前缀
+    fn lookup_continuation(&self, bytes: &[u8], buffer: &mut String) -> Option<u32> {
+        let text = std::str::from_utf8(bytes).ok()?;
+        buffer.clear();
+        buffer.push_str(&self.continuing_subword_prefix);
+        buffer.push_str(text);
+        self.matchable(buffer)
+    }
+
+    /// 使用贪婪最长匹配切分一个预分词片段，结果追加到 `ids`，`buffer` 为拼接续接标记用的缓冲区
+    ///
+    /// 片段开头只匹配词首标记；之后优先匹配续接标记，没有匹配时再匹配词首标记（如字节标记、
+    /// 训练时不在字母表中的字符），仍然没有匹配时输出未知标记并前进一个字节
+    fn segment(&self, word: &str, max_len: usize, buffer: &mut String, ids: &mut Vec<u32>) {
+        let bytes = word.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let window = max_len.min(bytes.len() - i);
+            let continuation = if i > 0 && !self.continuing_subword_prefix.is_empty() {
+                (1..=window).rev().find_map(|len| {
+                    self.lookup_continuation(&bytes[i..i + len], buffer)
+                        .map(|id| (id, len))
+                })
             } else {
-                // 非打印字符使用特殊表示
-                result.push_str(&format!("<0x{:02X}>", byte));
+                None
+            };
+            let matched = continuation.or_else(|| {
+                (1..=window)
+                    .rev()
+                    .find_map(|len| self.lookup_piece(&bytes[i..i + len]).map(|id| (id, len)))
+            });
+
+            match matched {
+                Some((id, len)) => {
+                    ids.push(id);
+                    i += len;
+                }
+                None => {
+                    ids.push(self.unk_token_id);
+                    i += 1;
+                }
             }
         }
-        result
     }
+}
 
-    /// 使用贪婪算法对字节序列进行分段
-    fn segment(&self, bytes: &[u8]) -> Option<Vec<u32>> {
-        if bytes.is_empty() {
-            return Some(vec![]);
+impl WordPieceTokenizer {
+    /// 本次编码临时改用另一种预分词方式，不修改分词器，用于在同一组词表上对比切分策略
+    ///
+    /// `pattern` 为 [`PATTERN_PRESETS`](crate::base::tokenizer_base::PATTERN_PRESETS) 中的名称或正则表达式。
+    /// 需要反复编码时先用 [`compile_pattern`] 编译，再调用 [`WordPieceTokenizer::encode_with_regex`]。
+    ///
+    /// # Errors
+    ///
+    /// 当正则表达式无效或编码失败时返回错误
+    pub fn encode_with_pattern(
+        &self,
+        text: &str,
+        pattern: &str,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        self.encode_with_regex(text, &compile_pattern(pattern)?)
+    }
+
+    /// 以已编译的正则表达式代替预分词流水线中的正则表达式步骤并编码，不修改分词器
+    ///
+    /// # Errors
+    ///
+    /// 当正则表达式匹配失败或编码失败时返回错误
+    pub fn encode_with_regex(
+        &self,
+        text: &str,
+        pattern: &Regex,
+    ) -> Result<Vec<u32>, TokenizerError> {
+        self.encode_split(text, pattern)
+    }
+
+    /// 以 `pattern` 作为预分词流水线中正则表达式步骤的编码实现
+    fn encode_split(&self, text: &str, pattern: &Regex) -> Result<Vec<u32>, TokenizerError> {
+        trace_span!(debug: "wordpiece.encode", text_len = text.len());
+        let limits = &self.base.encode_options.limits;
+        let text = limits.check_input(text)?;
+        let pre_tokenizer = self.base.pre_tokenizer.with_pattern(pattern);
+        let max_len = self.max_piece_len();
+        let (mut tokens, mut unk_tokens) = (0, 0);
+        // 拼接续接前缀用的缓冲区，在所有片段之间复用
+        let mut buffer = String::new();
+        let mut ids =
+            self.base
+                .special_tokens
+                .encode_with(text, |text| -> Result<_, TokenizerError> {
+                    let mut result = Vec::new();
+                    let text = self.base.normalizer.apply(text);
+                    for_each_piece(&pre_tokenizer, &text, |part| {
+                        self.segment(part, max_len, &mut buffer, &mut result);
+                        Ok(())
+                    })?;
+                    tokens += result.len();
+                    unk_tokens += result.iter().filter(|&&id| id == self.unk_token_id).count();
+                    Ok(result)
+                })?;
+        self.unk_stats.record(tokens, unk_tokens);
+        limits.check_tokens(&mut ids)?;
+        Ok(ids)
+    }
+
+    /// 转换为HuggingFace `tokenizers` 的WordPiece模型
+    ///
+    /// 词汇表无法覆盖的部分本库逐字节输出未知标记，`tokenizers` 则把整个片段输出为一个未知标记；
+    /// 词内没有匹配的续接标记时本库改用词首标记，`tokenizers` 同样输出未知标记。
+    /// 训练语料覆盖全部字符时两者结果相同
+    ///
+    /// # Errors
+    ///
+    /// 当未知标记ID不在词汇表中时返回错误
+    pub fn to_hf_json(&self) -> Result<HfTokenizerJson, TokenizerError> {
+        let unk_token = self
+            .unk_token()
+            .ok_or_else(|| {
+                model_save_error(format!("未知标记ID {} 不在词汇表中", self.unk_token_id))
+            })?
+            .to_string();
+        let mut vocab: Vec<(String, u32)> = self
+            .base
+            .vocab
+            .iter()
+            .map(|(&id, token)| (token.clone(), id))
+            .collect();
+        vocab.sort_unstable_by_key(|&(_, id)| id);
+
+        Ok(HfTokenizerJson {
+            model: HfModel::WordPiece {
+                vocab,
+                unk_token,
+                continuing_subword_prefix: self.continuing_subword_prefix.clone(),
+            },
+            added_tokens: HfTokenizerJson::added_tokens_from(&self.base.special_tokens),
+            pattern: self.base.pattern.clone(),
+            pre_tokenizer: self.base.pre_tokenizer.clone(),
+            decoder: self.base.decoder,
+            byte_level: false,
+            normalizer: self.base.normalizer.clone(),
+        })
+    }
+
+    /// 加载HuggingFace `tokenizers` 的WordPiece模型，替换当前的词汇表、续接前缀、预分词模式和特殊标记，
+    /// 分数全部置为0
+    ///
+    /// # Errors
+    ///
+    /// 当模型不是WordPiece、使用了规范化或 `ByteLevel` 预分词器、续接前缀包含换行符，
+    /// 或未知标记不在词汇表中时返回错误
+    pub fn load_hf_json(&mut self, json: HfTokenizerJson) -> Result<(), TokenizerError> {
+        self.unk_stats.reset();
+        let special_tokens = json.special_tokens()?;
+        let HfModel::WordPiece {
+            vocab,
+            unk_token,
+            continuing_subword_prefix,
+        } = json.model
+        else {
+            return Err(model_load_error("WordPiece分词器只能加载WordPiece模型"));
+        };
+        if json.byte_level {
+            return Err(model_load_error("WordPiece分词器不支持ByteLevel预分词器"));
+        }
+        if continuing_subword_prefix.contains(['\n', '\r']) {
+            return Err(model_load_error(format!(
+                "续接前缀 {:?} 包含换行符",
+                continuing_subword_prefix
+            )));
+        }
+        let unk_token_id = vocab
+            .iter()
+            .find(|(token, _)| *token == unk_token)
+            .map(|&(_, id)| id)
+            .ok_or_else(|| model_load_error(format!("未知标记 {:?} 不在词汇表中", unk_token)))?;
+
+        let mut id_map: HashMap<u32, String> =
+            vocab.into_iter().map(|(token, id)| (id, token)).collect();
+        for token in &json.added_tokens {
+            id_map
+                .entry(token.id)
+                .or_insert_with(|| token.content.clone());
         }
 
-        let mut result = Vec::new();
-        let mut i = 0;
+        self.base.set_pattern(&json.pattern)?;
+        self.base.vocab = VocabManager::from_id_map(id_map);
+        self.base.special_tokens = special_tokens;
+        self.base.normalizer = json.normalizer;
+        self.base.decoder = json.decoder;
+        self.base.pre_tokenizer = json.pre_tokenizer;
+        self.unk_token_id = unk_token_id;
+        self.continuing_subword_prefix = continuing_subword_prefix;
+        self.next_token_id = self
+            .base
+            .vocab
+            .ids()
+            .max()
+            .map_or(0, |&id| id + 1)
+            .max(self.base.special_tokens.end_id());
+        self.scores = vec![0.0; self.next_token_id as usize];
+        Ok(())
+    }
 
-        while i < bytes.len() {
-            // 尝试找到最长的匹配
-            let mut longest_match = None;
-            let mut longest_len = 0;
-
-            // 尝试所有可能的标记
-            for (token_id, token_str) in self.base.vocab.iter() {
-                // 将token字符串转换回字节序列
-                let token_bytes = if token_str.starts_with("<0x") && token_str.ends_with(">") {
-                    // 特殊字节表示
-                    if let Some(hex_str) = token_str
-                        .strip_prefix("<0x")
-                        .and_then(|s| s.strip_suffix(">"))
-                    {
-                        if let Ok(byte_val) = u8::from_str_radix(hex_str, 16) {
-                            vec![byte_val]
-                        } else {
-                            continue;
-                        }
-                    } else {
-                        continue;
+    /// 保存为HuggingFace `tokenizers` 的 tokenizer.json，见 [`WordPieceTokenizer::to_hf_json`]
+    ///
+    /// # Errors
+    ///
+    /// 当转换或写入失败时返回错误
+    pub fn save_tokenizer_json(&self, path: &str) -> Result<(), TokenizerError> {
+        self.to_hf_json()?.save(path)
+    }
+
+    /// 从HuggingFace `tokenizers` 的 tokenizer.json 加载，见 [`WordPieceTokenizer::load_hf_json`]
+    ///
+    /// # Errors
+    ///
+    /// 当读取、解析或转换失败时返回错误
+    pub fn load_tokenizer_json(&mut self, path: &str) -> Result<(), TokenizerError> {
+        self.load_hf_json(HfTokenizerJson::load(path)?)
+    }
+
+    /// 以带版本头和校验和的二进制格式保存，分数与模型写在同一个文件中，见 [`crate::base::binary`]
+    ///
+    /// # Errors
+    ///
+    /// 当写入失败时返回错误
+    pub fn save_binary(&self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("wordpiece.save_binary", path);
+        let mut model = BinaryModel::new(ModelKind::WordPiece);
+        self.base.write_binary(&mut model);
+        model.push(
+            binary::tag::VOCAB,
+            binary::write_vocab(
+                self.base
+                    .vocab
+                    .iter()
+                    .map(|(&id, text)| (id, text.as_bytes())),
+            ),
+        );
+        model.push(binary::tag::SCORES, binary::write_scores(&self.scores));
+        model.push(
+            binary::tag::SETTINGS,
+            binary::write_settings(&[
+                ("unk_token_id", self.unk_token_id.to_string()),
+                (
+                    "continuing_subword_prefix",
+                    self.continuing_subword_prefix.clone(),
+                ),
+            ]),
+        );
+        std::fs::write(path, model.to_bytes())
+            .map_err(|e| model_save_error(format!("写入模型文件失败: {}", e)))
+    }
+
+    /// 加载 [`WordPieceTokenizer::save_binary`] 写出的二进制模型，文件不是二进制格式时按文本格式加载
+    ///
+    /// # Errors
+    ///
+    /// 当读取失败、校验和不符、格式版本过高、模型类型不匹配或完整性检查发现问题时返回错误
+    pub fn load_binary(&mut self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("wordpiece.load_binary", path);
+        let data =
+            std::fs::read(path).map_err(|e| model_load_error(format!("打开文件失败: {}", e)))?;
+        if !binary::is_binary(&data) {
+            return Tokenizer::load(self, path);
+        }
+        let model = BinaryModel::from_bytes(&data, ModelKind::WordPiece)?;
+        self.unk_stats.reset();
+
+        self.base.read_binary(&model)?;
+        let entries = binary::read_text_vocab(model.required(binary::tag::VOCAB)?)?;
+        let scores = binary::read_scores(model.required(binary::tag::SCORES)?)?;
+        let mut unk_token_id = 0;
+        let mut continuing_subword_prefix = String::new();
+        if let Some(settings) = model.section(binary::tag::SETTINGS) {
+            for (key, value) in binary::read_settings(settings)? {
+                match key.as_str() {
+                    "unk_token_id" => {
+                        unk_token_id = value
+                            .parse()
+                            .map_err(|e| model_load_error(format!("解析未知标记ID失败: {}", e)))?;
                     }
-                } else {
-                    // 普通字符串
-                    token_str.as_bytes().to_vec()
-                };
-
-                let token_len = token_bytes.len();
-                if i + token_len <= bytes.len()
-                    && bytes[i..i + token_len] == token_bytes
-                    && token_len > longest_len
-                {
-                    longest_len = token_len;
-                    longest_match = Some(*token_id);
+                    "continuing_subword_prefix" => continuing_subword_prefix = value,
+                    _ => {}
                 }
             }
+        }
 
-            if let Some(token_id) = longest_match {
-                result.push(token_id);
-                i += longest_len;
-            } else {
-                // 如果没有匹配，添加未知标记
-                result.push(self.unk_token_id);
-                i += 1;
-            }
+        let mut report = IntegrityReport::default();
+        report.check_entries(&entries);
+        for (id, text) in entries {
+            self.base.vocab.insert(id, text);
         }
+        self.scores = scores;
+        self.unk_token_id = unk_token_id;
+        self.continuing_subword_prefix = continuing_subword_prefix;
+        self.next_token_id = self
+            .base
+            .vocab
+            .ids()
+            .max()
+            .map_or(0, |&id| id + 1)
+            .max(self.base.special_tokens.end_id());
 
-        Some(result)
+        report.extend(self.check_integrity());
+        report.into_result()
     }
 }
 
 impl Tokenizer for WordPieceTokenizer {
     type TokenId = u32;
 
-    fn encode(&self, text: &str) -> Result<Vec<Self::TokenId>, String> {
-        // 使用基础分词器分割文本
-        let parts = self.base.split_text(text)?;
+    fn encode(&self, text: &str) -> Result<Vec<Self::TokenId>, TokenizerError> {
+        self.encode_split(text, &self.base.compiled_pattern)
+    }
 
-        let mut result = Vec::new();
-        for part in parts {
-            let segment = self
-                .segment(part.as_bytes())
-                .ok_or_else(|| "分段失败".to_string())?;
-            result.extend(segment);
+    fn decode(&self, tokens: &[Self::TokenId]) -> Result<String, TokenizerError> {
+        if self.base.decoder != DecoderKind::Fuse {
+            return decode_ids(&self.base.decoder, tokens, |id| self.decoded_piece(&id));
+        }
+        // 按标记字节长度之和预分配，字节标记 `<0xNN>` 只占一个字节
+        let total_len = tokens
+            .iter()
+            .filter_map(|id| self.base.vocab.get_by_id(id))
+            .map(|token_str| self.token_piece_bytes(token_str).len())
+            .sum();
+        let mut bytes = Vec::with_capacity(total_len);
+        for (index, &token_id) in tokens.iter().enumerate() {
+            let Some(token_str) = self.base.vocab.get_by_id(&token_id) else {
+                return Err(TokenizerError::UnknownTokenId {
+                    id: token_id,
+                    index,
+                });
+            };
+            bytes.extend_from_slice(&self.token_piece_bytes(token_str));
         }
 
-        Ok(result)
+        String::from_utf8(bytes).map_err(|e| {
+            let token_lens = tokens.iter().map(|id| {
+                self.base
+                    .vocab
+                    .get_by_id(id)
+                    .map_or(0, |token_str| self.token_piece_bytes(token_str).len())
+            });
+            invalid_utf8_error(&e.utf8_error(), token_lens)
+        })
     }
 
-    fn decode(&self, tokens: &[Self::TokenId]) -> Result<String, String> {
-        let mut bytes = Vec::new();
-        for &token_id in tokens {
-            if let Some(token_str) = self.base.vocab.get_by_id(&token_id) {
-                // 将token字符串转换回字节序列
-                if token_str.starts_with("<0x") && token_str.ends_with(">") {
-                    // 特殊字节表示
-                    if let Some(hex_str) = token_str
-                        .strip_prefix("<0x")
-                        .and_then(|s| s.strip_suffix(">"))
-                    {
-                        if let Ok(byte_val) = u8::from_str_radix(hex_str, 16) {
-                            bytes.push(byte_val);
-                        } else {
-                            return Err("无效的字节表示".to_string());
-                        }
-                    } else {
-                        return Err("无效的字节表示".to_string());
+    fn decode_cow<'a>(&'a self, tokens: &[Self::TokenId]) -> Result<Cow<'a, str>, TokenizerError> {
+        if let ([id], DecoderKind::Fuse) = (tokens, self.base.decoder) {
+            if let Some(token_str) = self.base.vocab.get_by_id(id) {
+                // 字节标记需要还原为原始字节，其余标记去掉续接前缀后可直接借用
+                if let Cow::Borrowed(bytes) = self.token_piece_bytes(token_str) {
+                    if let Ok(text) = std::str::from_utf8(bytes) {
+                        return Ok(Cow::Borrowed(text));
                     }
-                } else {
-                    // 普通字符串
-                    bytes.extend(token_str.as_bytes());
                 }
-            } else {
-                return Err(format!("无效的标记ID: {}", token_id));
             }
         }
 
-        String::from_utf8(bytes).map_err(|e| format!("UTF-8解码失败: {}", e))
+        Tokenizer::decode(self, tokens).map(Cow::Owned)
     }
 
-    fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> Result<(), String> {
+    fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> Result<(), TokenizerError> {
+        trace_span!("wordpiece.train", texts = texts.len(), vocab_size);
+        // 配置中的特殊标记先于训练得到的片段分配ID
+        self.base.trainer.clone().register_special_tokens(self)?;
         // 如果请求的词汇表大小小于等于当前词汇表大小，直接返回
         if vocab_size <= self.base.vocab.len() as u32 {
             return Ok(());
         }
 
-        // 计算需要提取的子字符串数量
-        let current_vocab_size = self.base.vocab.len() as u32;
-        let substrings_needed = vocab_size - current_vocab_size;
+        // 特殊标记不参与训练，从语料中去除
+        let texts = self.base.special_tokens.strip_texts(texts);
+        let texts = self.base.trainer.shuffle(texts);
 
-        // 提取常见子字符串
-        let common_substrings = self.extract_common_substrings(&texts, substrings_needed as usize);
-
-        // 添加常见子字符串到词汇表
-        let mut next_id = self.base.vocab.len() as u32; // 从当前词汇表大小开始
-        for (substring, _) in common_substrings {
-            if next_id >= vocab_size {
+        // 新片段排在预留的特殊标记ID之后，分数与ID保持对齐
+        let mut next_id = self.next_token_id.max(self.base.vocab.len() as u32);
+        if self.scores.len() < next_id as usize {
+            self.scores.resize(next_id as usize, 0.0);
+        }
+        // 初始字母表中的非ASCII字符先于训练得到的片段加入，ASCII字符已有对应的字节标记
+        for ch in self.base.trainer.initial_alphabet.clone() {
+            if self.base.vocab.len() as u32 >= vocab_size {
                 break;
             }
-
-            let token_str = self.bytes_to_string(&substring);
-            self.base.vocab.insert(next_id, token_str);
-            self.scores.push(0.0); // 初始分数为0
+            let token = ch.to_string();
+            if ch.is_ascii() || self.base.vocab.contains_value(&token) {
+                continue;
+            }
+            self.base.vocab.insert(next_id, token);
+            self.scores.push(0.0);
             next_id += 1;
         }
 
-        // 如果词汇表还不够大，添加一些随机子字符串
-        while next_id < vocab_size {
-            // 创建一个随机的1-4字节序列
-            let len = (rand::random::<u8>() % 4) + 1;
-            let mut substring = Vec::new();
-            for _ in 0..len {
-                substring.push(rand::random::<u8>());
+        // 统计预分词片段的出现次数，按文本排序保证结果与线程数无关
+        let parts = texts
+            .par_iter()
+            .map(|text| self.base.split_text(text))
+            .collect::<Result<Vec<_>, TokenizerError>>()?;
+        let word_counts: HashMap<&str, i32> = self.base.parallel.count(&parts, |parts, local| {
+            for part in parts.iter().filter(|part| !part.is_empty()) {
+                *local.entry(part.as_str()).or_insert(0) += 1;
             }
-
-            let token_str = self.bytes_to_string(&substring);
-            self.base.vocab.insert(next_id, token_str);
-            self.scores.push(0.0); // 初始分数为0
+        });
+        let mut words: Vec<(&str, i32)> = word_counts.into_iter().collect();
+        words.sort_unstable();
+
+        let budget = (vocab_size as usize).saturating_sub(self.base.vocab.len());
+        let pieces = learn_pieces(
+            &words,
+            &self.base.vocab,
+            &self.continuing_subword_prefix,
+            &self.base.trainer,
+            self.base.parallel,
+            budget,
+        );
+        for (piece, score) in pieces {
+            self.base.vocab.insert(next_id, piece);
+            self.scores.push(score);
             next_id += 1;
         }
-
-        // 迭代优化词汇表和分数
-        // 在实际实现中，这里会执行EM算法优化分数
-        // 为简化起见，我们只是设置一些随机分数
-        for score in &mut self.scores {
-            *score = rand::random::<f64>() * 2.0 - 1.0; // -1.0到1.0之间的随机分数
-        }
+        self.next_token_id = next_id;
 
         Ok(())
     }
 
+    fn train_with_config(
+        &mut self,
+        texts: Vec<String>,
+        vocab_size: u32,
+        config: TrainerConfig,
+    ) -> Result<(), TokenizerError> {
+        let previous = std::mem::replace(&mut self.base.trainer, config);
+        let result = Tokenizer::train(self, texts, vocab_size);
+        self.base.trainer = previous;
+        result
+    }
+
     fn vocab_size(&self) -> usize {
         self.base.vocab_size()
     }
 
-    fn save(&self, path: &str) -> Result<(), String> {
+    /// 未知标记以及非ASCII字节标记 `<0xNN>` 视为回退
+    fn is_fallback_token(&self, id: &Self::TokenId) -> bool {
+        *id == self.unk_token_id
+            || self.base.vocab.get_by_id(id).is_some_and(|token_str| {
+                token_str
+                    .strip_prefix("<0x")
+                    .and_then(|s| s.strip_suffix('>'))
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .is_some_and(|byte| byte >= 0x80)
+            })
+    }
+
+    fn save(&self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("wordpiece.save", path);
         // 使用基础分词器的保存功能
         self.base.save(path)?;
 
+        // 续接前缀和未知标记追加在模型文件末尾
+        use std::io::Write;
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| model_save_error(format!("打开文件失败: {}", e)))?;
+        if !self.continuing_subword_prefix.is_empty() {
+            writeln!(
+                file,
+                "{}{}",
+                CONTINUING_SUBWORD_PREFIX_HEADER, self.continuing_subword_prefix
+            )
+            .map_err(|e| model_save_error(format!("写入续接前缀失败: {}", e)))?;
+        }
+        if let Some(unk_token) = self.unk_token() {
+            writeln!(
+                file,
+                "{}{} {}",
+                UNK_TOKEN_HEADER, self.unk_token_id, unk_token
+            )
+            .map_err(|e| model_save_error(format!("写入未知标记失败: {}", e)))?;
+        }
+
         // 保存分数 - 先构建完整内容，然后一次性写入
         let scores_path = format!("{}.scores", path);
         let mut content = format!("{}\n", self.unk_token_id);
         for score in &self.scores {
-            content.push_str(&format!("{}\n", score));
+            content.push_str(&format_score(*score));
+            content.push('\n');
         }
 
-        std::fs::write(&scores_path, content).map_err(|e| format!("保存分数失败: {}", e))?;
+        std::fs::write(&scores_path, content)
+            .map_err(|e| model_save_error(format!("保存分数失败: {}", e)))?;
 
         Ok(())
     }
 
-    fn load(&mut self, path: &str) -> Result<(), String> {
-        // 使用基础分词器的加载功能
-        self.base.load(path)?;
-
-        // 加载分数
+    fn load(&mut self, path: &str) -> Result<(), TokenizerError> {
+        trace_span!("wordpiece.load", path);
+        let model =
+            std::fs::read(path).map_err(|e| model_load_error(format!("打开文件失败: {}", e)))?;
         let scores_path = format!("{}.scores", path);
-        let scores_content =
-            std::fs::read_to_string(&scores_path).map_err(|e| format!("加载分数失败: {}", e))?;
+        let scores = std::fs::read(&scores_path)
+            .map_err(|e| model_load_error(format!("加载分数失败: {}", e)))?;
 
-        let mut lines = scores_content.lines();
-        if let Some(first_line) = lines.next() {
-            self.unk_token_id = first_line
-                .parse()
-                .map_err(|e| format!("解析未知标记ID失败: {}", e))?;
-        }
-
-        self.scores.clear();
-        for line in lines {
-            let score = line.parse().map_err(|e| format!("解析分数失败: {}", e))?;
-            self.scores.push(score);
-        }
-
-        Ok(())
+        self.load_from_bytes(&model, &scores)
     }
 }
 
@@ -423,30 +1079,201 @@ impl SubwordTokenizer for WordPieceTokenizer {
     }
 }
 
+impl SpecialTokenizer for WordPieceTokenizer {
+    fn special_tokens(&self) -> &SpecialTokens {
+        &self.base.special_tokens
+    }
+
+    fn encode_options(&self) -> &EncodeOptions {
+        &self.base.encode_options
+    }
+
+    fn encode_options_mut(&mut self) -> &mut EncodeOptions {
+        &mut self.base.encode_options
+    }
+
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Result<Vec<u32>, TokenizerError> {
+        let mut next_id = self.next_token_id.max(self.base.vocab.len() as u32);
+        let ids = self
+            .base
+            .special_tokens
+            .add(&mut self.base.vocab, &mut next_id, tokens);
+        self.next_token_id = next_id;
+        if let Some(&max_id) = self.base.vocab.ids().max() {
+            if self.scores.len() <= max_id as usize {
+                self.scores.resize(max_id as usize + 1, 0.0);
+            }
+        }
+        ids
+    }
+
+    fn reserve_special_ids(&mut self, count: u32) -> Result<std::ops::Range<u32>, TokenizerError> {
+        let mut next_id = self.next_token_id.max(self.base.vocab.len() as u32);
+        let ids = self
+            .base
+            .special_tokens
+            .reserve_after(&mut next_id, count)?;
+        self.next_token_id = next_id;
+        Ok(ids)
+    }
+}
+
+impl VocabBytes for WordPieceTokenizer {
+    fn normalize_aligned(&self, text: &str) -> NormalizedText {
+        self.base.normalize_aligned(text)
+    }
+
+    fn token_bytes(&self, id: &u32) -> Option<Cow<'_, [u8]>> {
+        self.base
+            .vocab
+            .get_by_id(id)
+            .map(|piece| self.token_piece_bytes(piece))
+    }
+
+    fn vocab_bytes(&self) -> Vec<(u32, Cow<'_, [u8]>)> {
+        let mut entries: Vec<(u32, Cow<'_, [u8]>)> = self
+            .base
+            .vocab
+            .iter()
+            .map(|(&id, piece)| (id, self.token_piece_bytes(piece)))
+            .collect();
+        entries.sort_unstable_by_key(|&(id, _)| id);
+        entries
+    }
+
+    fn decoder(&self) -> DecoderKind {
+        self.base.decoder
+    }
+
+    fn decoded_piece(&self, id: &u32) -> Option<DecodedPiece<'_>> {
+        self.base.vocab.get_by_id(id).map(|piece| {
+            let stripped = self.strip_continuation(piece);
+            DecodedPiece::from_vocab(stripped).with_continuation(stripped.len() < piece.len())
+        })
+    }
+}
+
 #[cfg(feature = "python")]
 #[pymethods]
 impl WordPieceTokenizer {
+    /// 创建分词器，`char_dict` 为常用汉字字表路径，`preload_chars` 为假时不预置任何字符
     #[new]
-    fn new() -> PyResult<Self> {
-        Self::new_internal().map_err(PyValueError::new_err)
+    #[pyo3(signature = (char_dict = None, preload_chars = true))]
+    fn new(char_dict: Option<String>, preload_chars: bool) -> PyResult<Self> {
+        let resources = crate::base::resources::from_py_args(char_dict, preload_chars);
+        Self::with_resources(&resources).map_err(py_value_error)
     }
 
     #[staticmethod]
     fn with_pattern(pattern: String) -> PyResult<Self> {
-        let tokenizer = Self::with_pattern_internal(pattern).map_err(PyValueError::new_err)?;
+        let tokenizer = Self::with_pattern_internal(pattern).map_err(py_value_error)?;
         Ok(tokenizer)
     }
 
+    /// 创建带有BERT特殊标记（ID 0–4）的分词器
+    #[staticmethod]
+    #[pyo3(signature = (char_dict = None, preload_chars = true))]
+    fn with_bert_special_tokens(char_dict: Option<String>, preload_chars: bool) -> PyResult<Self> {
+        let resources = crate::base::resources::from_py_args(char_dict, preload_chars);
+        Self::with_bert_special_tokens_and_resources(&resources).map_err(py_value_error)
+    }
+
+    /// 编码文本并加上 `[CLS]`/`[SEP]`；传入 `pair` 时编码为句子对
+    #[pyo3(name = "encode_with_special_tokens", signature = (text, pair=None))]
+    fn py_encode_with_special_tokens(&self, text: &str, pair: Option<&str>) -> PyResult<Vec<u32>> {
+        match pair {
+            Some(pair) => WordPieceTokenizer::encode_pair_with_special_tokens(self, text, pair),
+            None => WordPieceTokenizer::encode_with_special_tokens(self, text),
+        }
+        .map_err(py_value_error)
+    }
+
     fn encode(&self, text: &str) -> PyResult<Vec<u32>> {
-        Tokenizer::encode(self, text).map_err(PyValueError::new_err)
+        Tokenizer::encode(self, text).map_err(PyErr::from)
+    }
+
+    /// 本次编码临时改用另一种预分词方式（预设名称或正则表达式），不修改分词器
+    #[pyo3(name = "encode_with_pattern")]
+    fn py_encode_with_pattern(&self, text: &str, pattern: &str) -> PyResult<Vec<u32>> {
+        self.encode_with_pattern(text, pattern)
+            .map_err(py_value_error)
     }
 
-    fn decode(&self, tokens: Vec<u32>) -> PyResult<String> {
-        Tokenizer::decode(self, &tokens).map_err(PyValueError::new_err)
+    #[pyo3(signature = (tokens, skip_special_tokens = false))]
+    fn decode(&self, tokens: Vec<u32>, skip_special_tokens: bool) -> PyResult<String> {
+        self.decode_with(&tokens, skip_special_tokens)
+            .map_err(PyErr::from)
+    }
+
+    /// 批量编码文本为token IDs（并行处理）
+    ///
+    /// `texts` 可以是字符串列表，也可以是pyarrow的字符串数组、ChunkedArray、RecordBatch或Table
+    /// （多列时通过 `column` 指定列名）。Arrow输入直接从缓冲区读取字符串，空值编码为空列表
+    #[pyo3(signature = (texts, column = None))]
+    fn encode_batch(
+        &self,
+        texts: &Bound<'_, PyAny>,
+        column: Option<&str>,
+    ) -> PyResult<Vec<Vec<u32>>> {
+        crate::base::py_numpy::encode_batch(self, texts, column)
+    }
+
+    /// 批量解码token IDs为文本（并行处理）
+    ///
+    /// `token_lists` 可以是嵌套列表，也可以是二维整数NumPy数组或PyTorch CPU张量，
+    /// 给出 `attention_mask` 时只解码掩码非0位置的标记
+    #[pyo3(signature = (token_lists, attention_mask = None))]
+    fn decode_batch(
+        &self,
+        py: Python<'_>,
+        token_lists: &Bound<'_, PyAny>,
+        attention_mask: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Vec<String>> {
+        crate::base::py_numpy::decode_batch(py, self, token_lists, attention_mask)
     }
 
     fn train(&mut self, texts: Vec<String>, vocab_size: u32) -> PyResult<()> {
-        Tokenizer::train(self, texts, vocab_size).map_err(PyValueError::new_err)
+        Tokenizer::train(self, texts, vocab_size).map_err(PyErr::from)
+    }
+
+    /// 从Python迭代器训练分词器
+    #[pyo3(signature = (texts, vocab_size, _show_progress = false))]
+    fn train_from_iterator(
+        &mut self,
+        texts: Vec<String>,
+        vocab_size: u32,
+        _show_progress: bool,
+    ) -> PyResult<()> {
+        Tokenizer::train(self, texts, vocab_size).map_err(PyErr::from)
+    }
+
+    /// 设置训练时并行计数的粒度：每个任务处理的片段数（0为自适应）和归并树宽度
+    #[pyo3(signature = (pieces_per_task = 0, reduce_width = 2))]
+    fn set_parallel_chunking(
+        &mut self,
+        pieces_per_task: usize,
+        reduce_width: usize,
+    ) -> PyResult<()> {
+        self.base.parallel =
+            crate::base::tokenizer_base::ParallelChunking::new(pieces_per_task, reduce_width)
+                .map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 返回正则表达式模式
+    #[getter]
+    fn get_pattern(&self) -> String {
+        self.base.pattern.clone()
+    }
+
+    /// 获取词汇表，标记ID到标记
+    fn get_vocab(&self) -> HashMap<u32, String> {
+        self.base.vocab.id_map().clone()
+    }
+
+    /// 获取反向词汇表，标记到标记ID
+    fn get_vocab_rev(&self) -> HashMap<String, u32> {
+        self.base.vocab.value_map().clone()
     }
 
     fn vocab_size(&self) -> PyResult<usize> {
@@ -454,11 +1281,34 @@ impl WordPieceTokenizer {
     }
 
     fn save(&self, path: &str) -> PyResult<()> {
-        Tokenizer::save(self, path).map_err(PyValueError::new_err)
+        Tokenizer::save(self, path).map_err(PyErr::from)
     }
 
     fn load(&mut self, path: &str) -> PyResult<()> {
-        Tokenizer::load(self, path).map_err(PyValueError::new_err)
+        Tokenizer::load(self, path).map_err(PyErr::from)
+    }
+
+    /// 保存到本地模型注册表，返回模型文件路径；同名模型会被覆盖
+    #[pyo3(name = "save_as")]
+    fn py_save_as(&self, name: &str) -> PyResult<String> {
+        let path = NamedModel::save_as(self, name)?;
+        Ok(path.to_string_lossy().into_owned())
+    }
+
+    /// 按名称从本地模型注册表加载
+    #[pyo3(name = "load_named")]
+    fn py_load_named(&mut self, name: &str) -> PyResult<()> {
+        NamedModel::load_named(self, name).map_err(Into::into)
+    }
+
+    /// 自加载以来的未知标记统计：`texts`、`tokens`、`unk_tokens`、`texts_with_unk` 和 `unk_rate`
+    fn get_unk_stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        self.unk_stats.snapshot().to_py_dict(py)
+    }
+
+    /// 清空未知标记统计
+    fn reset_unk_stats(&self) {
+        self.unk_stats.reset();
     }
 
     fn get_scores(&self) -> PyResult<Vec<f64>> {
@@ -470,6 +1320,399 @@ impl WordPieceTokenizer {
         Ok(())
     }
 
+    /// 保存为HuggingFace `tokenizers` 的 tokenizer.json
+    #[pyo3(name = "save_tokenizer_json")]
+    fn py_save_tokenizer_json(&self, path: &str) -> PyResult<()> {
+        self.save_tokenizer_json(path).map_err(py_value_error)
+    }
+
+    /// 以带版本头和校验和的二进制格式保存
+    #[pyo3(name = "save_binary")]
+    fn py_save_binary(&self, path: &str) -> PyResult<()> {
+        self.save_binary(path).map_err(py_value_error)
+    }
+
+    /// 加载二进制模型，文件不是二进制格式时按文本格式加载
+    #[pyo3(name = "load_binary")]
+    fn py_load_binary(&mut self, path: &str) -> PyResult<()> {
+        self.load_binary(path).map_err(py_value_error)
+    }
+
+    /// 从HuggingFace `tokenizers` 的 tokenizer.json 加载
+    #[pyo3(name = "load_tokenizer_json")]
+    fn py_load_tokenizer_json(&mut self, path: &str) -> PyResult<()> {
+        self.load_tokenizer_json(path).map_err(py_value_error)
+    }
+
+    /// 解码结果以 `prefix` 开头的全部标记ID
+    fn ids_with_prefix(&self, prefix: &str) -> Vec<u32> {
+        VocabBytes::ids_with_prefix(self, prefix)
+    }
+
+    /// 忽略开头的空白后解码结果恰好为 `text` 的全部标记ID
+    fn token_ids_covering(&self, text: &str) -> Vec<u32> {
+        VocabBytes::token_ids_covering(self, text)
+    }
+
+    /// 注册特殊标记，返回每个标记的ID
+    fn add_special_tokens(&mut self, tokens: Vec<String>) -> PyResult<Vec<u32>> {
+        let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+        SpecialTokenizer::add_special_tokens(self, &tokens).map_err(PyErr::from)
+    }
+
+    /// 在词汇表末尾预留 `count` 个特殊标记ID，返回 `(start, end)` 区间
+    fn reserve_special_ids(&mut self, count: u32) -> PyResult<(u32, u32)> {
+        SpecialTokenizer::reserve_special_ids(self, count)
+            .map(|range| (range.start, range.end))
+            .map_err(PyErr::from)
+    }
+
+    /// 已注册的特殊标记，`{标记: ID}`
+    #[getter(special_tokens)]
+    fn py_special_tokens(&self) -> HashMap<String, u32> {
+        self.base
+            .special_tokens
+            .iter()
+            .map(|(id, token)| (token.to_string(), id))
+            .collect()
+    }
+
+    /// 将单个标记渲染为适合日志和界面显示的字符串，控制字符转义、首尾空格显示为 ␣
+    fn render_token(&self, id: u32) -> String {
+        VocabBytes::render_token(self, &id)
+    }
+
+    /// 从词汇表中随机抽取 `n_tokens` 个标记并解码为文本，用于压测和往返测试
+    ///
+    /// `counts` 为 `[(ID, 次数)]` 时按次数加权抽取，否则等概率抽取；传入 `seed` 时结果可复现
+    #[pyo3(name = "sample_text", signature = (n_tokens, seed = None, counts = None))]
+    fn py_sample_text(
+        &self,
+        n_tokens: usize,
+        seed: Option<u64>,
+        counts: Option<Vec<(u32, u64)>>,
+    ) -> PyResult<String> {
+        let sampler = match counts {
+            Some(counts) => TextSampler::with_counts(self, &counts, seed),
+            None => TextSampler::new(self, seed),
+        };
+        sampler
+            .and_then(|mut sampler| sampler.sample_text(self, n_tokens))
+            .map_err(py_value_error)
+    }
+
+    /// 设置预分词之前的规范化流水线，步骤以逗号分隔，如 `"nfkc,lowercase"`，`"none"` 表示不做规范化
+    ///
+    /// 可选步骤：nfc、nfkc、nfkd、lowercase、strip_accents、clean_whitespace
+    #[pyo3(name = "set_normalizer")]
+    fn py_set_normalizer(&mut self, spec: &str) -> PyResult<()> {
+        self.base.normalizer = Normalizer::parse(spec).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的规范化流水线，格式同 `set_normalizer`
+    #[pyo3(name = "get_normalizer")]
+    fn py_get_normalizer(&self) -> String {
+        self.base.normalizer.spec()
+    }
+
+    /// 设置预分词流水线，步骤以逗号分隔，如 `"whitespace,punctuation"`，按顺序逐步细分片段，
+    /// `"none"` 表示不切分
+    ///
+    /// 可选步骤：regex（按 `set_pattern` 设置的正则表达式，默认流水线只有这一步）、whitespace、
+    /// byte_level、metaspace、digits、punctuation
+    #[pyo3(name = "set_pre_tokenizer")]
+    fn py_set_pre_tokenizer(&mut self, spec: &str) -> PyResult<()> {
+        self.base.pre_tokenizer = PreTokenizerPipeline::parse(spec).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的预分词流水线，格式同 `set_pre_tokenizer`
+    #[pyo3(name = "get_pre_tokenizer")]
+    fn py_get_pre_tokenizer(&self) -> String {
+        self.base.pre_tokenizer.spec()
+    }
+
+    /// 设置解码器："fuse"（默认，直接拼接）、"byte_level"（还原 `Ġ` 等字节映射字符）、
+    /// "metaspace"（`▁` 还原为空格并去掉开头的空格）或 "wordpiece"（词之间补空格）
+    #[pyo3(name = "set_decoder")]
+    fn py_set_decoder(&mut self, name: &str) -> PyResult<()> {
+        self.base.decoder = DecoderKind::from_name(name).map_err(py_value_error)?;
+        Ok(())
+    }
+
+    /// 当前的解码器名称
+    #[pyo3(name = "get_decoder")]
+    fn py_get_decoder(&self) -> &'static str {
+        self.base.decoder.name()
+    }
+
+    /// 设置未知标记，`id` 未给出时使用已有ID或下一个可用ID，返回未知标记的ID
+    #[pyo3(name = "set_unk_token", signature = (token, id = None))]
+    fn py_set_unk_token(&mut self, token: &str, id: Option<u32>) -> PyResult<u32> {
+        self.set_unk_token(token, id).map_err(py_value_error)
+    }
+
+    /// 当前未知标记的文本
+    #[getter(unk_token)]
+    fn py_unk_token(&self) -> Option<String> {
+        self.unk_token().map(str::to_string)
+    }
+
+    /// 当前未知标记的ID
+    #[getter(unk_token_id)]
+    fn py_unk_token_id(&self) -> u32 {
+        self.unk_token_id
+    }
+
+    /// 检查语料中的字符能否由基础词汇表表示，返回覆盖统计以及只能拆成字节或编码为未知标记的字符
+    #[pyo3(name = "coverage")]
+    fn py_coverage<'py>(
+        &self,
+        py: Python<'py>,
+        corpus: Vec<String>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        py.allow_threads(|| crate::analysis::coverage(self, &corpus))
+            .map_err(py_value_error)?
+            .to_py_dict(py)
+    }
+
+    /// 设置训练时配对的最低出现次数
+    #[pyo3(name = "set_min_frequency")]
+    fn py_set_min_frequency(&mut self, min_frequency: usize) {
+        self.set_min_frequency(min_frequency);
+    }
+
+    /// 设置续接前缀，为空时不区分词首和续接片段
+    #[pyo3(name = "set_continuing_subword_prefix")]
+    fn py_set_continuing_subword_prefix(&mut self, prefix: &str) -> PyResult<()> {
+        self.set_continuing_subword_prefix(prefix)
+            .map_err(py_value_error)
+    }
+
+    /// 续接前缀，默认为 `##`
+    #[getter]
+    fn continuing_subword_prefix(&self) -> String {
+        self.continuing_subword_prefix.clone()
+    }
+
+    /// 设置之后训练使用的配置：配对的最低出现次数、新片段的最大字节数、训练前注册的特殊标记
+    /// 和初始字母表
+    ///
+    /// `limit_alphabet` 只保留出现次数最多的字符参与合并，其余字符按字节标记编码
+    #[pyo3(signature = (
+        min_frequency = 0,
+        max_piece_length = None,
+        special_tokens = None,
+        initial_alphabet = None,
+        limit_alphabet = None,
+        shuffle_seed = None
+    ))]
+    fn set_trainer_config(
+        &mut self,
+        min_frequency: u64,
+        max_piece_length: Option<usize>,
+        special_tokens: Option<Vec<String>>,
+        initial_alphabet: Option<Vec<char>>,
+        limit_alphabet: Option<usize>,
+        shuffle_seed: Option<u64>,
+    ) {
+        self.base.trainer = TrainerConfig::from_py(
+            min_frequency,
+            max_piece_length,
+            special_tokens,
+            initial_alphabet,
+            limit_alphabet,
+            shuffle_seed,
+        );
+    }
+
+    /// 编码文本并返回每个标记的区间 `(start, end, id)`
+    ///
+    /// 区间默认为UTF-8字节偏移，`offset_unit="char"` 时为字符偏移
+    #[pyo3(signature = (text, offset_unit = "byte"))]
+    fn spans(&self, text: &str, offset_unit: &str) -> PyResult<Vec<(usize, usize, u32)>> {
+        crate::base::py_numpy::spans(self, text, offset_unit)
+    }
+
+    /// 编码文本并返回 `ids`、`tokens`、`offsets` 组成的字典，用于把标注对齐回原文
+    ///
+    /// 区间默认为UTF-8字节偏移，`offset_unit="char"` 时为字符偏移
+    #[pyo3(signature = (text, offset_unit = "byte"))]
+    fn encode_with_offsets<'py>(
+        &self,
+        py: Python<'py>,
+        text: &str,
+        offset_unit: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_with_offsets(py, self, text, offset_unit)
+    }
+
+    /// 编码句子对为 `[CLS] A [SEP] B [SEP]`，返回 `input_ids`、`token_type_ids` 组成的字典
+    ///
+    /// 分类和分隔标记需要已注册为特殊标记，可用 `cls_token`、`sep_token` 改用其他名称
+    #[pyo3(name = "encode_pair", signature = (text_a, text_b, cls_token = "[CLS]", sep_token = "[SEP]"))]
+    fn py_encode_pair<'py>(
+        &self,
+        py: Python<'py>,
+        text_a: &str,
+        text_b: &str,
+        cls_token: &str,
+        sep_token: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_pair(py, self, text_a, text_b, cls_token, sep_token)
+    }
+
+    /// 启用截断，`encode_plus`、`encode_batch_plus` 每行最多保留 `max_length` 个标记
+    ///
+    /// `strategy` 为 `"longest_first"`（默认）、`"only_first"` 或 `"only_second"`，只对句子对有效；
+    /// 指定 `stride` 时超出的部分切成相邻重叠 `stride` 个标记的溢出块，每块单独成行
+    #[pyo3(name = "enable_truncation", signature = (max_length, strategy = "longest_first", stride = None))]
+    fn py_enable_truncation(
+        &mut self,
+        max_length: usize,
+        strategy: &str,
+        stride: Option<usize>,
+    ) -> PyResult<()> {
+        let config = crate::base::py_numpy::truncation_config(max_length, strategy, stride)?;
+        self.encode_options_mut().truncation = Some(config);
+        Ok(())
+    }
+
+    /// 关闭截断
+    #[pyo3(name = "no_truncation")]
+    fn py_no_truncation(&mut self) {
+        self.no_truncation();
+    }
+
+    /// 启用补齐，`encode_plus`、`encode_batch_plus` 用 `pad_id` 把各行补齐到 `length`（默认为最长的行）
+    ///
+    /// `direction` 为 `"right"`（默认）或 `"left"`；指定 `pad_to_multiple_of` 时行宽向上取整到其倍数
+    #[pyo3(name = "enable_padding", signature = (pad_id = 0, direction = "right", length = None, pad_to_multiple_of = None))]
+    fn py_enable_padding(
+        &mut self,
+        pad_id: u32,
+        direction: &str,
+        length: Option<usize>,
+        pad_to_multiple_of: Option<usize>,
+    ) -> PyResult<()> {
+        let config =
+            crate::base::py_numpy::padding_config(direction, pad_id, length, pad_to_multiple_of)?;
+        self.encode_options_mut().padding = Some(config);
+        Ok(())
+    }
+
+    /// 关闭补齐
+    #[pyo3(name = "no_padding")]
+    fn py_no_padding(&mut self) {
+        self.no_padding();
+    }
+
+    /// 设置编码输入的长度限制，参数都为 `None` 时不限制
+    ///
+    /// 输入超过 `max_input_bytes` 字节或结果超过 `max_tokens` 个标记时，`policy="error"`（默认）报错，
+    /// `"truncate"` 截断输入或结果；对 `encode` 及所有基于它的接口生效
+    #[pyo3(name = "set_input_limits", signature = (max_input_bytes = None, max_tokens = None, policy = "error"))]
+    fn py_set_input_limits(
+        &mut self,
+        max_input_bytes: Option<usize>,
+        max_tokens: Option<usize>,
+        policy: &str,
+    ) -> PyResult<()> {
+        self.encode_options_mut().limits =
+            crate::base::py_numpy::input_limits(max_input_bytes, max_tokens, policy)?;
+        Ok(())
+    }
+
+    /// 按截断与补齐配置编码单条文本或句子对，返回 `input_ids`、`attention_mask`、`token_type_ids`
+    /// 和 `overflow_to_sample_mapping` 组成的字典，每项按行给出（溢出块各占一行）
+    ///
+    /// 句子对拼接为 `[CLS] A [SEP] B [SEP]`；`return_tensors="np"` 时返回NumPy数组
+    #[pyo3(name = "encode_plus", signature = (text, pair = None, return_tensors = None))]
+    fn py_encode_plus<'py>(
+        &self,
+        py: Python<'py>,
+        text: String,
+        pair: Option<String>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_plus(
+            py,
+            self,
+            vec![text],
+            pair.map(|pair| vec![pair]),
+            return_tensors,
+        )
+    }
+
+    /// 按截断与补齐配置并行编码一批文本（及对应的第二句 `pairs`），返回格式同 `encode_plus`
+    #[pyo3(name = "encode_batch_plus", signature = (texts, pairs = None, return_tensors = None))]
+    fn py_encode_batch_plus<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        pairs: Option<Vec<String>>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_plus(py, self, texts, pairs, return_tensors)
+    }
+
+    /// 检查词汇表和特殊标记注册表，返回发现的问题列表，每项为包含 `kind`、`message` 的字典
+    #[pyo3(name = "check_integrity")]
+    fn py_check_integrity<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Vec<Bound<'py, pyo3::types::PyDict>>> {
+        self.check_integrity().to_py_list(py)
+    }
+
+    /// 批量编码并返回连续存储的 `input_ids` 和每行的 `lengths`、`offsets`，可直接复制到张量
+    ///
+    /// 每行最多保留 `max_len` 个标记；指定 `pad_id` 时补齐为二维并返回 `attention_mask`，
+    /// `return_tensors="np"` 时返回NumPy数组
+    #[pyo3(signature = (texts, max_len = None, pad_id = None, return_tensors = None))]
+    fn encode_batch_packed<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        max_len: Option<usize>,
+        pad_id: Option<u32>,
+        return_tensors: Option<&str>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_packed(py, self, texts, max_len, pad_id, return_tensors)
+    }
+
+    /// 批量编码并返回补齐后的 `input_ids`、`offsets`、`attention_mask`
+    ///
+    /// `return_tensors="np"` 时返回NumPy数组，避免为大批量创建大量小元组；
+    /// `offset_unit="char"` 时偏移为字符区间
+    #[pyo3(signature = (texts, return_tensors = None, offset_unit = "byte"))]
+    fn encode_batch_with_offsets<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        return_tensors: Option<&str>,
+        offset_unit: &str,
+    ) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        crate::base::py_numpy::encode_batch_with_offsets(
+            py,
+            self,
+            texts,
+            return_tensors,
+            offset_unit,
+        )
+    }
+
+    /// 编码文本并返回标记ID序列与平台无关的64位哈希，用于判断缓存的编码结果是否过期
+    fn content_hash(&self, text: &str) -> PyResult<u64> {
+        Tokenizer::content_hash(self, text).map_err(PyErr::from)
+    }
+
+    /// 编码文本并标出每个标记是否开始一个新词，用于整词掩码
+    fn word_starts(&self, text: &str) -> PyResult<Vec<bool>> {
+        VocabBytes::word_starts(self, text).map_err(PyErr::from)
+    }
+
     /// 从dict目录加载初始化词表
     #[cfg(feature = "python")]
     #[pyo3(name = "load_vocab_from_dict")]
@@ -480,10 +1723,16 @@ impl WordPieceTokenizer {
 }
 
 impl Default for WordPieceTokenizer {
+    /// 默认字表无法读取时记录警告，改为创建不预置字符的分词器
     fn default() -> Self {
-        Self::new_internal().expect(
-            "Default WordPiece Tokenizer initialization failed. \
-             This is a programming error - please report this bug.",
-        )
+        Self::new_internal()
+            .or_else(|e| {
+                log::warn!("{}，改为不预置字符", e);
+                Self::with_resources(&ResourceConfig::without_char_dict())
+            })
+            .expect(
+                "Default WordPiece Tokenizer initialization failed. \
+                 This is a programming error - please report this bug.",
+            )
     }
 }
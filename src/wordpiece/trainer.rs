@@ -0,0 +1,304 @@
+//! WordPiece训练
+//!
+//! 与BPE一样从单个字符出发反复合并相邻片段，但按似然增益而不是频率选择配对：
+//! 配对 `(a, b)` 的得分为 `count(ab) / (count(a) * count(b))`，偏向合并各自少见、却经常一起出现的片段。
+//! 每个预分词片段的第一个字符是词首片段，其余字符是带续接前缀（默认 `##`）的续接片段，
+//! 合并结果沿用左侧片段的形式。
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::rc::Rc;
+
+use ahash::{AHashMap, AHashSet};
+
+use crate::base::merge_job::sorted_by_pair;
+use crate::base::tokenizer_base::{count_pairs_parallel_with, piece_bytes, ParallelChunking};
+use crate::base::trainer_config::TrainerConfig;
+use crate::base::vocab_manager::VocabManager;
+use crate::base::word::Word;
+use crate::wordpiece::tokenizer::byte_token;
+
+/// 训练中的片段：不含续接前缀的文本，以及是否为续接片段
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Symbol {
+    text: String,
+    continuation: bool,
+}
+
+impl Symbol {
+    /// 片段在词汇表中的写法：续接片段加上前缀，单字节的词首片段与字节标记一致
+    fn vocab_form(&self, prefix: &str) -> String {
+        if self.continuation {
+            format!("{}{}", prefix, self.text)
+        } else if let [byte] = self.text.as_bytes() {
+            byte_token(*byte)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+/// 片段表，相同的片段共用一个编号
+#[derive(Default)]
+struct Symbols {
+    symbols: Vec<Rc<Symbol>>,
+    ids: AHashMap<Rc<Symbol>, u32>,
+}
+
+impl Symbols {
+    fn intern(&mut self, symbol: Symbol) -> u32 {
+        if let Some(&id) = self.ids.get(&symbol) {
+            return id;
+        }
+        let id = self.symbols.len() as u32;
+        let symbol = Rc::new(symbol);
+        self.ids.insert(Rc::clone(&symbol), id);
+        self.symbols.push(symbol);
+        id
+    }
+}
+
+/// 堆中的候选配对，入堆时记录得分和次数，出堆时与当前值核对以跳过过期条目
+struct Candidate {
+    pair: (u32, u32),
+    count: i32,
+    score: f64,
+    /// 配对两侧的片段，得分和次数都相同时内容较小的优先
+    key: (Rc<Symbol>, Rc<Symbol>),
+}
+
+impl Candidate {
+    fn new(pair: (u32, u32), count: i32, symbols: &Symbols, symbol_counts: &[i64]) -> Self {
+        let score = f64::from(count)
+            / (symbol_counts[pair.0 as usize] as f64 * symbol_counts[pair.1 as usize] as f64);
+        Self {
+            pair,
+            count,
+            score,
+            key: (
+                Rc::clone(&symbols.symbols[pair.0 as usize]),
+                Rc::clone(&symbols.symbols[pair.1 as usize]),
+            ),
+        }
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then(self.count.cmp(&other.count))
+            .then_with(|| other.key.cmp(&self.key))
+    }
+}
+
+/// 不能进入字母表的字符：模型文件按行存储词汇表，换行符只能按字节标记编码
+fn is_excluded_char(ch: char) -> bool {
+    matches!(ch, '\n' | '\r')
+}
+
+/// 学习新的词汇表条目，返回按加入顺序排列的 (词汇表写法, 分数)，最多 `budget` 项
+///
+/// 先加入语料中词汇表还没有的字符（词首形式和续接形式，按出现次数从多到少），
+/// 再反复合并得分最高的配对。得分相同时依次比较配对次数和片段文本，结果与线程数无关。
+/// 合并得到的片段分数为得分的自然对数，字符的分数为0。
+///
+/// 配对次数低于 `config.min_frequency`、合并后超过 `config.max_piece_length` 个字节（不含前缀）
+/// 的配对不会合并；不在 `config.alphabet` 选出的字母表中的字符和换行符不参与合并，编码时按字节标记输出
+pub(crate) fn learn_pieces(
+    words: &[(&str, i32)],
+    vocab: &VocabManager<u32, String>,
+    prefix: &str,
+    config: &TrainerConfig,
+    chunking: ParallelChunking,
+    budget: usize,
+) -> Vec<(String, f64)> {
+    let mut learned: Vec<(String, f64)> = Vec::new();
+    let mut known: AHashSet<String> = AHashSet::new();
+    let mut add_piece = |piece: String, score: f64, learned: &mut Vec<(String, f64)>| {
+        if learned.len() < budget && !vocab.contains_value(&piece) && known.insert(piece.clone()) {
+            learned.push((piece, score));
+        }
+    };
+
+    let mut char_counts: HashMap<char, u64> = HashMap::new();
+    for &(word, count) in words {
+        for ch in word.chars().filter(|&ch| !is_excluded_char(ch)) {
+            *char_counts.entry(ch).or_insert(0) += count as u64;
+        }
+    }
+    let alphabet = config.alphabet(&char_counts);
+    let in_alphabet = |ch: char| {
+        !is_excluded_char(ch)
+            && alphabet
+                .as_ref()
+                .is_none_or(|alphabet| alphabet.contains(&ch))
+    };
+
+    // 每个片段按字符拆开，字母表之外的字符把片段截断，后面的字符仍是续接片段
+    let mut symbols = Symbols::default();
+    let mut training_words = Vec::new();
+    let mut counts = Vec::new();
+    let mut symbol_counts: Vec<i64> = Vec::new();
+    for &(word, count) in words {
+        let mut ids = Vec::new();
+        for (position, ch) in word.char_indices() {
+            if !in_alphabet(ch) {
+                if ids.len() > 1 {
+                    training_words.push(Word::new(std::mem::take(&mut ids)));
+                    counts.push(count);
+                }
+                ids.clear();
+                continue;
+            }
+            let id = symbols.intern(Symbol {
+                text: ch.to_string(),
+                continuation: position > 0 && !prefix.is_empty(),
+            });
+            if symbol_counts.len() <= id as usize {
+                symbol_counts.resize(id as usize + 1, 0);
+            }
+            symbol_counts[id as usize] += i64::from(count);
+            ids.push(id);
+        }
+        if ids.len() > 1 {
+            training_words.push(Word::new(ids));
+            counts.push(count);
+        }
+    }
+
+    let mut alphabet_symbols: Vec<u32> = (0..symbols.symbols.len() as u32).collect();
+    alphabet_symbols.sort_unstable_by(|&a, &b| {
+        symbol_counts[b as usize]
+            .cmp(&symbol_counts[a as usize])
+            .then_with(|| symbols.symbols[a as usize].cmp(&symbols.symbols[b as usize]))
+    });
+    for id in alphabet_symbols {
+        add_piece(
+            symbols.symbols[id as usize].vocab_form(prefix),
+            0.0,
+            &mut learned,
+        );
+    }
+
+    let (mut pair_counts, mut where_to_update) =
+        count_pairs_parallel_with(&training_words, &counts, chunking);
+    // 片段参与的配对，片段次数减少后这些配对的得分升高，需要重新入堆
+    let mut pairs_by_symbol: AHashMap<u32, AHashSet<(u32, u32)>> = AHashMap::new();
+    let mut heap = BinaryHeap::with_capacity(pair_counts.len());
+    for (pair, count) in sorted_by_pair(pair_counts.iter().map(|(&pair, &count)| (pair, count))) {
+        if count > 0 {
+            pairs_by_symbol.entry(pair.0).or_default().insert(pair);
+            pairs_by_symbol.entry(pair.1).or_default().insert(pair);
+            heap.push(Candidate::new(pair, count, &symbols, &symbol_counts));
+        }
+    }
+
+    // 配对的得分随次数和两侧片段的次数变化：得分降低的条目出堆时按当前值重新入堆，
+    // 得分升高的配对在变化时立即重新入堆，因此堆顶与当前值一致的条目就是得分最高的配对
+    let mut rejected: AHashSet<(u32, u32)> = AHashSet::new();
+    while learned.len() < budget {
+        let Some(top) = heap.pop() else {
+            break;
+        };
+        let pair = top.pair;
+        let count = pair_counts.get(&pair).copied().unwrap_or(0);
+        // 不可合并的条目直接丢弃，配对次数之后增长时会重新入堆
+        if count <= 0 || !config.keeps_frequency(count as u64) || rejected.contains(&pair) {
+            continue;
+        }
+        let current = Candidate::new(pair, count, &symbols, &symbol_counts);
+        if current.count != top.count || current.score.total_cmp(&top.score).is_ne() {
+            heap.push(current);
+            continue;
+        }
+        let score = current.score;
+
+        let (left, right) = (
+            &symbols.symbols[pair.0 as usize],
+            &symbols.symbols[pair.1 as usize],
+        );
+        let merged = Symbol {
+            text: format!("{}{}", left.text, right.text),
+            continuation: left.continuation,
+        };
+        // 词首片段不能以续接前缀开头，也不能与字节标记的写法混淆
+        if !config.allows_length(merged.text.len())
+            || (!merged.continuation && !prefix.is_empty() && merged.text.starts_with(prefix))
+            || piece_bytes(&merged.text).len() != merged.text.len()
+        {
+            rejected.insert(pair);
+            continue;
+        }
+        add_piece(merged.vocab_form(prefix), score.ln(), &mut learned);
+        let new_id = symbols.intern(merged);
+        if symbol_counts.len() <= new_id as usize {
+            symbol_counts.resize(new_id as usize + 1, 0);
+        }
+
+        let mut positions = where_to_update.remove(&pair).unwrap_or_default();
+        positions.sort_unstable();
+        positions.dedup();
+        let mut grown: AHashSet<(u32, u32)> = AHashSet::new();
+        for index in positions {
+            let word = &mut training_words[index];
+            let count = counts[index];
+            let before = word.ids.len();
+            let changes = word.merge_pair(pair, new_id, |a, b| a == b);
+            let merged_count = (before - word.ids.len()) as i64 * i64::from(count);
+            symbol_counts[pair.0 as usize] -= merged_count;
+            symbol_counts[pair.1 as usize] -= merged_count;
+            symbol_counts[new_id as usize] += merged_count;
+            for (changed, delta) in changes {
+                *pair_counts.entry(changed).or_insert(0) += delta * count;
+                if delta > 0 {
+                    where_to_update.entry(changed).or_default().push(index);
+                    grown.insert(changed);
+                }
+            }
+        }
+        pair_counts.remove(&pair);
+
+        for &changed in &grown {
+            pairs_by_symbol
+                .entry(changed.0)
+                .or_default()
+                .insert(changed);
+            pairs_by_symbol
+                .entry(changed.1)
+                .or_default()
+                .insert(changed);
+        }
+        let mut refreshed: Vec<(u32, u32)> = grown.into_iter().collect();
+        for symbol in [pair.0, pair.1] {
+            if let Some(pairs) = pairs_by_symbol.get_mut(&symbol) {
+                pairs.retain(|changed| pair_counts.get(changed).is_some_and(|&count| count > 0));
+                refreshed.extend(pairs.iter().copied());
+            }
+        }
+        refreshed.sort_unstable();
+        refreshed.dedup();
+        for changed in refreshed {
+            let count = pair_counts.get(&changed).copied().unwrap_or(0);
+            if count > 0 && !rejected.contains(&changed) {
+                heap.push(Candidate::new(changed, count, &symbols, &symbol_counts));
+            }
+        }
+    }
+
+    learned
+}
@@ -224,6 +224,571 @@ def test_batch_performance_comparison():
         assert individual_tokens[i] == batch_tokens[i]
 
 
+def test_encode_batch_async():
+    """测试异步批量编码与同步结果一致"""
+    import asyncio
+    from zero_tokenizer import BBPETokenizer, Tokenizer
+
+    texts = [f"Test text {i}" for i in range(50)]
+
+    async def run(tokenizer):
+        return await tokenizer.encode_batch_async(texts)
+
+    for tokenizer in (BBPETokenizer(), Tokenizer()):
+        tokenizer.train(texts[:5], 300)
+        assert asyncio.run(run(tokenizer)) == tokenizer.encode_batch(texts)
+
+
+def test_encode_batch_async_uses_snapshot():
+    """测试异步编码开始后重新训练不会失败，结果来自调用时的分词器"""
+    import asyncio
+    from zero_tokenizer import BBPETokenizer, Tokenizer
+
+    texts = [f"Test text {i}" for i in range(200)]
+
+    async def run(tokenizer):
+        future = tokenizer.encode_batch_async(texts)
+        # 后台线程可能尚未开始，此时修改分词器不影响已提交的编码
+        tokenizer.train(["completely different corpus"] * 5, 300)
+        return await future
+
+    for tokenizer in (BBPETokenizer(), Tokenizer()):
+        tokenizer.train(texts[:5], 300)
+        expected = tokenizer.encode_batch(texts)
+        assert asyncio.run(run(tokenizer)) == expected
+
+
+def test_encode_batch_async_refreshes_after_mutation():
+    """测试修改分词器后的异步编码使用新的模型"""
+    import asyncio
+    from zero_tokenizer import BBPETokenizer, Tokenizer
+
+    texts = [f"Test text {i} <|sep|>" for i in range(50)]
+
+    async def run(tokenizer):
+        return await tokenizer.encode_batch_async(texts)
+
+    for tokenizer in (BBPETokenizer(), Tokenizer()):
+        tokenizer.train(texts[:5], 300)
+        before = asyncio.run(run(tokenizer))
+        assert asyncio.run(run(tokenizer)) == before
+
+        tokenizer.add_special_tokens(["<|sep|>"])
+        expected = tokenizer.encode_batch(texts)
+        assert expected != before
+        assert asyncio.run(run(tokenizer)) == expected
+
+
+def test_encode_batch_arrow():
+    """测试直接编码pyarrow字符串列"""
+    pa = pytest.importorskip("pyarrow")
+    from zero_tokenizer import BBPETokenizer
+
+    tokenizer = BBPETokenizer()
+    texts = ["Hello world!", None, "你好世界！", ""]
+    tokenizer.train([t for t in texts if t], 300)
+    expected = [tokenizer.encode(t) if t is not None else [] for t in texts]
+
+    array = pa.array(texts, type=pa.string())
+    assert tokenizer.encode_batch(array) == expected
+    assert tokenizer.encode_batch(pa.array(texts, type=pa.large_string())) == expected
+    assert tokenizer.encode_batch(pa.chunked_array([texts[:2], texts[2:]])) == expected
+
+    batch = pa.record_batch({"id": list(range(len(texts))), "text": array})
+    assert tokenizer.encode_batch(batch, column="text") == expected
+    assert tokenizer.encode_batch(pa.Table.from_batches([batch]), column="text") == expected
+
+
+def test_decode_batch_numpy():
+    """测试直接解码二维NumPy数组及注意力掩码"""
+    np = pytest.importorskip("numpy")
+    from zero_tokenizer import BBPETokenizer, Tokenizer
+
+    texts = ["Hello world!", "你好", "Test"]
+    for tokenizer in (BBPETokenizer(), Tokenizer()):
+        tokenizer.train(texts, 300)
+        encoded = [tokenizer.encode(t) for t in texts]
+        width = max(len(ids) for ids in encoded)
+        ids = np.zeros((len(texts), width), dtype=np.int64)
+        mask = np.zeros_like(ids)
+        for row, tokens in enumerate(encoded):
+            ids[row, : len(tokens)] = tokens
+            mask[row, : len(tokens)] = 1
+
+        assert tokenizer.decode_batch(ids, attention_mask=mask) == texts
+        assert tokenizer.decode_batch(ids.astype(np.int32), attention_mask=mask.astype(np.uint8)) == texts
+        # 非连续的视图按步长读取
+        assert tokenizer.decode_batch(np.asfortranarray(ids), attention_mask=mask) == texts
+        # 嵌套列表同样支持掩码
+        assert tokenizer.decode_batch(ids.tolist(), attention_mask=mask.tolist()) == texts
+
+        with pytest.raises(ValueError):
+            tokenizer.decode_batch(ids, attention_mask=mask[:, :1])
+        with pytest.raises(TypeError):
+            tokenizer.decode_batch(ids.astype(np.float32))
+
+
+def test_encode_batch_with_offsets():
+    """测试批量编码返回补齐的偏移和注意力掩码"""
+    from zero_tokenizer import BBPETokenizer, Tokenizer
+
+    texts = ["Hello world!", "你好", ""]
+    for tokenizer in (BBPETokenizer(), Tokenizer()):
+        tokenizer.train(texts[:2], 300)
+        output = tokenizer.encode_batch_with_offsets(texts)
+        width = max(len(tokenizer.encode(t)) for t in texts)
+
+        for row, text in enumerate(texts):
+            spans = tokenizer.spans(text)
+            padding = width - len(spans)
+            assert output["input_ids"][row] == [id for _, _, id in spans] + [0] * padding
+            assert output["offsets"][row] == [(s, e) for s, e, _ in spans] + [(0, 0)] * padding
+            assert output["attention_mask"][row] == [1] * len(spans) + [0] * padding
+
+        with pytest.raises(ValueError):
+            tokenizer.encode_batch_with_offsets(texts, return_tensors="pt")
+
+
+def test_encode_batch_with_offsets_numpy():
+    """测试以NumPy数组返回偏移和注意力掩码"""
+    np = pytest.importorskip("numpy")
+    from zero_tokenizer import BBPETokenizer
+
+    texts = ["Hello world!", "你好", ""]
+    tokenizer = BBPETokenizer()
+    tokenizer.train(texts[:2], 300)
+    lists = tokenizer.encode_batch_with_offsets(texts)
+    arrays = tokenizer.encode_batch_with_offsets(texts, return_tensors="np")
+
+    width = len(lists["input_ids"][0])
+    assert arrays["input_ids"].shape == (len(texts), width)
+    assert arrays["offsets"].shape == (len(texts), width, 2)
+    assert arrays["attention_mask"].shape == (len(texts), width)
+    assert arrays["offsets"].dtype == np.int64
+    assert arrays["input_ids"].tolist() == lists["input_ids"]
+    assert [[tuple(o) for o in row] for row in arrays["offsets"].tolist()] == lists["offsets"]
+    assert arrays["attention_mask"].tolist() == lists["attention_mask"]
+
+
+def test_encode_batch_with_char_offsets():
+    """测试按字符偏移返回区间，可直接用于Python字符串切片"""
+    from zero_tokenizer import BBPETokenizer, UnigramTokenizer
+
+    texts = ["Hello 世界", "你好 world"]
+    for tokenizer in (BBPETokenizer(), UnigramTokenizer()):
+        tokenizer.train(texts, 300)
+        output = tokenizer.encode_batch_with_offsets(texts, offset_unit="char")
+        for row, text in enumerate(texts):
+            spans = tokenizer.spans(text, offset_unit="char")
+            assert output["offsets"][row][: len(spans)] == [(s, e) for s, e, _ in spans]
+            assert max(e for _, e, _ in spans) == len(text)
+
+        with pytest.raises(ValueError):
+            tokenizer.spans(texts[0], offset_unit="utf16")
+
+
+def test_encode_batch_profiling():
+    """测试批量编码的分阶段耗时统计"""
+    from zero_tokenizer import BBPETokenizer, Tokenizer
+
+    texts = [f"Test text {i}" for i in range(50)]
+    keys = {"texts", "pre_tokenize_ns", "vocab_lookup_ns", "merge_ns", "python_conversion_ns", "total_ns"}
+
+    for tokenizer in (BBPETokenizer(), Tokenizer()):
+        tokenizer.train(texts[:5], 300)
+        tokenizer.encode_batch(texts)
+        assert tokenizer.get_profile()["texts"] == 0
+
+        tokenizer.enable_profiling()
+        tokenizer.encode_batch(texts)
+        profile = tokenizer.get_profile()
+        assert set(profile) == keys
+        assert profile["texts"] == len(texts)
+        assert profile["python_conversion_ns"] > 0
+        assert profile["total_ns"] >= profile["python_conversion_ns"]
+
+        # 每次批量编码重新开始统计
+        tokenizer.encode_batch(texts[:10])
+        assert tokenizer.get_profile()["texts"] == 10
+
+        tokenizer.enable_profiling(False)
+
+
+def test_unigram_wordpiece_batch_parity():
+    """测试Unigram和WordPiece的批量接口、模式和词汇表访问与BPE/BBPE一致"""
+    np = pytest.importorskip("numpy")
+    from zero_tokenizer import UnigramTokenizer, WordPieceTokenizer
+
+    texts = ["hello world", "你好世界", "hello"]
+    for cls in (UnigramTokenizer, WordPieceTokenizer):
+        tokenizer = cls.with_pattern("gpt2")
+        tokenizer.set_parallel_chunking(pieces_per_task=2)
+        tokenizer.train_from_iterator(texts, tokenizer.vocab_size() + 20)
+
+        batch = tokenizer.encode_batch(texts)
+        assert batch == [tokenizer.encode(t) for t in texts]
+        assert tokenizer.decode_batch(batch) == texts
+
+        width = max(len(ids) for ids in batch)
+        ids = np.zeros((len(texts), width), dtype=np.int64)
+        mask = np.zeros_like(ids)
+        for row, tokens in enumerate(batch):
+            ids[row, : len(tokens)] = tokens
+            mask[row, : len(tokens)] = 1
+        assert tokenizer.decode_batch(ids, attention_mask=mask) == texts
+
+        assert tokenizer.pattern.startswith("'(?:[sdmt]")
+        vocab = tokenizer.get_vocab()
+        assert len(vocab) == tokenizer.vocab_size()
+        assert {token: id for id, token in vocab.items()} == tokenizer.get_vocab_rev()
+
+
+
+@pytest.mark.parametrize(
+    "cls_name", ["Tokenizer", "BBPETokenizer", "UnigramTokenizer", "WordPieceTokenizer"]
+)
+def test_encode_batch_plus_truncation_padding(cls_name):
+    """测试截断与补齐配置"""
+    import zero_tokenizer
+
+    tokenizer = getattr(zero_tokenizer, cls_name)()
+    texts = ["hello world", "hi", "a much longer sentence here"]
+    encoded = [tokenizer.encode(t) for t in texts]
+
+    batch = tokenizer.encode_batch_plus(texts)
+    assert batch["input_ids"] == encoded
+    assert batch["overflow_to_sample_mapping"] == [0, 1, 2]
+
+    pad_id = tokenizer.add_special_tokens(["[PAD]"])[0]
+    tokenizer.enable_truncation(4)
+    tokenizer.enable_padding(pad_id=pad_id, direction="left", length=6)
+    batch = tokenizer.encode_batch_plus(texts, return_tensors="np")
+    assert batch["input_ids"].shape == (3, 6)
+    assert batch["attention_mask"].sum(axis=1).tolist() == [min(len(ids), 4) for ids in encoded]
+
+    tokenizer.enable_truncation(4, stride=1)
+    tokenizer.no_padding()
+    batch = tokenizer.encode_plus(texts[2])
+    assert len(batch["input_ids"]) > 1
+    assert set(batch["overflow_to_sample_mapping"]) == {0}
+
+    tokenizer.no_truncation()
+    tokenizer.add_special_tokens(["[CLS]", "[SEP]"])
+    batch = tokenizer.encode_plus("hello", pair="hi")
+    assert batch["input_ids"][0] == tokenizer.encode_pair("hello", "hi")["input_ids"]
+    assert batch["token_type_ids"][0][-1] == 1
+
+    with pytest.raises(ValueError):
+        tokenizer.enable_truncation(4, strategy="unknown")
+    with pytest.raises(ValueError):
+        tokenizer.enable_padding(direction="middle")
+
+
 if __name__ == "__main__":
     # 支持直接运行
     pytest.main([__file__, "-v"])
+
+
+def test_bpe_stream_invalid_items():
+    """测试流式训练时不是字符串的项按策略处理"""
+    from zero_tokenizer import Tokenizer
+
+    rows = ["hello world", None, b"hello bytes", "hello again", 42]
+
+    with pytest.raises(Exception):
+        Tokenizer().train_from_iterator_stream(iter(rows), 300)
+
+    summary = Tokenizer().train_from_iterator_stream(iter(rows), 300, invalid="skip")
+    assert summary["sequences"] == 3
+    assert summary["decoded"] == 1
+    assert summary["skipped"] == 2
+    assert summary["skipped_types"] == {"NoneType": 1, "int": 1}
+
+    summary = Tokenizer().train_from_iterator_stream(iter(rows), 300, invalid="str")
+    assert summary["sequences"] == 4
+    assert summary["coerced"] == 1
+    assert summary["skipped_types"] == {"NoneType": 1}
+
+    with pytest.raises(ValueError):
+        Tokenizer().train_from_iterator_stream(iter(rows), 300, invalid="drop")
+
+
+def test_stream_bytes_items():
+    """测试流式训练直接接受bytes项"""
+    from zero_tokenizer import BBPETokenizer, Tokenizer
+
+    rows = [b"hello world", bytearray(b"hello there"), b"caf\xe9 hello", "hello again"]
+
+    with pytest.raises(Exception):
+        Tokenizer().train_from_iterator_stream(iter(rows), 300)
+    with pytest.raises(ValueError):
+        Tokenizer().train_from_iterator_stream(iter(rows), 300, errors="surrogate")
+
+    summary = Tokenizer().train_from_iterator_stream(iter(rows), 300, errors="replace")
+    assert summary["sequences"] == 4
+    assert summary["decoded"] == 3
+    summary = Tokenizer().train_from_iterator_stream(iter(rows), 300, errors="ignore")
+    assert summary["sequences"] == 4
+
+    # BBPE按原始字节训练，无效UTF-8字节不会被替换
+    tokenizer = BBPETokenizer()
+    summary = tokenizer.train_from_iterator_stream(iter(rows), 300)
+    assert summary["sequences"] == 4
+    assert summary["bytes"] == 3
+    assert summary["pruned"] == 0
+    assert tokenizer.decode(tokenizer.encode("hello world")) == "hello world"
+
+    with pytest.raises(Exception):
+        BBPETokenizer().train_from_iterator_stream(iter([1, 2]), 300)
+
+
+def test_unigram_sample_and_nbest():
+    """测试Unigram采样编码和n-best切分"""
+    from zero_tokenizer import UnigramTokenizer
+
+    tokenizer = UnigramTokenizer()
+    tokenizer.train(["hello world", "hello there", "world peace"] * 20, 300)
+    text = "hello world"
+
+    nbest = tokenizer.encode_nbest(text, 3)
+    assert 1 <= len(nbest) <= 3
+    assert nbest[0][0] == tokenizer.encode(text)
+    assert all(a[1] >= b[1] for a, b in zip(nbest, nbest[1:]))
+
+    sample = tokenizer.encode_sample(text, 0.1, seed=7)
+    assert sample == tokenizer.encode_sample(text, 0.1, seed=7)
+    assert tokenizer.decode(sample) == text
+
+    with pytest.raises(ValueError):
+        tokenizer.encode_sample(text, -1.0)
+    with pytest.raises(ValueError):
+        tokenizer.encode_nbest(text, 0)
+
+
+def test_normalizer_pipeline():
+    """测试规范化流水线在训练和编码时生效，并随模型保存"""
+    import os
+    import tempfile
+    from zero_tokenizer import BBPETokenizer, BPETokenizer, UnigramTokenizer, WordPieceTokenizer
+
+    for cls in (BPETokenizer, BBPETokenizer, UnigramTokenizer, WordPieceTokenizer):
+        tokenizer = cls()
+        assert tokenizer.get_normalizer() == "none"
+        tokenizer.set_normalizer("nfkc,lowercase,strip_accents,clean_whitespace")
+        tokenizer.train(["Café Ｈｅｌｌｏ world, héllo WORLD!"] * 10, 300)
+        assert tokenizer.encode("HÉLLO   Ｗorld") == tokenizer.encode("hello world")
+
+        with tempfile.TemporaryDirectory() as tmp:
+            path = os.path.join(tmp, "model.bin")
+            tokenizer.save_binary(path)
+            loaded = cls()
+            loaded.load_binary(path)
+        assert loaded.get_normalizer() == "nfkc,lowercase,strip_accents,clean_whitespace"
+
+        with pytest.raises(ValueError):
+            tokenizer.set_normalizer("upper")
+
+
+def test_pre_tokenizer_pipeline():
+    """测试预分词流水线在训练和编码时生效，并随模型保存"""
+    import os
+    import tempfile
+    from zero_tokenizer import BBPETokenizer, BPETokenizer, UnigramTokenizer, WordPieceTokenizer
+
+    for cls in (BPETokenizer, BBPETokenizer, UnigramTokenizer, WordPieceTokenizer):
+        tokenizer = cls()
+        assert tokenizer.get_pre_tokenizer() == "regex"
+        tokenizer.set_pre_tokenizer("metaspace,digits")
+        tokenizer.train(["The year 2024 was great, 2024 again"] * 10, 300)
+        assert len(tokenizer.encode("2024")) == 4
+
+        with tempfile.TemporaryDirectory() as tmp:
+            path = os.path.join(tmp, "model.bin")
+            tokenizer.save_binary(path)
+            loaded = cls()
+            loaded.load_binary(path)
+        assert loaded.get_pre_tokenizer() == "metaspace,digits"
+
+        with pytest.raises(ValueError):
+            tokenizer.set_pre_tokenizer("bert")
+
+
+def test_decoder():
+    """测试解码器按模型家族的约定还原文本，并随模型保存"""
+    import os
+    import tempfile
+    from zero_tokenizer import BBPETokenizer, BPETokenizer, UnigramTokenizer, WordPieceTokenizer
+
+    for cls in (BPETokenizer, BBPETokenizer, UnigramTokenizer, WordPieceTokenizer):
+        tokenizer = cls()
+        assert tokenizer.get_decoder() == "fuse"
+        tokenizer.train(["hello world"] * 10, 300)
+        tokenizer.set_decoder("metaspace")
+        assert tokenizer.decode(tokenizer.encode(" hello world")) == "hello world"
+
+        with tempfile.TemporaryDirectory() as tmp:
+            path = os.path.join(tmp, "model.bin")
+            tokenizer.save_binary(path)
+            loaded = cls()
+            loaded.load_binary(path)
+        assert loaded.get_decoder() == "metaspace"
+
+        with pytest.raises(ValueError):
+            tokenizer.set_decoder("bert")
+
+
+def test_sample_text():
+    """测试按词汇表生成随机文本"""
+    from zero_tokenizer import BBPETokenizer
+
+    tokenizer = BBPETokenizer()
+    tokenizer.train(["hello world, 你好世界 tokenizer 123"] * 4, 300)
+    tokenizer.add_special_tokens(["<|endoftext|>"])
+
+    text = tokenizer.sample_text(100, seed=5)
+    assert text == tokenizer.sample_text(100, seed=5)
+    assert "<|endoftext|>" not in text
+    assert tokenizer.decode(tokenizer.encode(text)) == text
+
+    hello = tokenizer.encode("hello")
+    weighted = tokenizer.sample_text(20, seed=1, counts=[(hello[0], 3)])
+    assert weighted == tokenizer.decode(hello[:1] * 20)
+
+    with pytest.raises(ValueError):
+        tokenizer.sample_text(10, counts=[])
+
+
+def test_input_limits():
+    """测试编码输入的长度限制"""
+    from zero_tokenizer import BBPETokenizer
+
+    tokenizer = BBPETokenizer()
+    tokenizer.train(["hello world 你好世界"] * 4, 300)
+    text = "hello world 你好"
+    full = tokenizer.encode(text)
+
+    tokenizer.set_input_limits(max_input_bytes=14)
+    with pytest.raises(ValueError):
+        tokenizer.encode(text)
+    with pytest.raises(ValueError):
+        tokenizer.encode_batch([text])
+
+    tokenizer.set_input_limits(max_input_bytes=16, policy="truncate")
+    assert tokenizer.encode(text) == tokenizer.encode("hello world 你")
+
+    tokenizer.set_input_limits(max_tokens=2, policy="truncate")
+    assert tokenizer.encode(text) == full[:2]
+
+    tokenizer.set_input_limits()
+    assert tokenizer.encode(text) == full
+
+    with pytest.raises(ValueError):
+        tokenizer.set_input_limits(max_tokens=0)
+    with pytest.raises(ValueError):
+        tokenizer.set_input_limits(policy="drop")
+
+
+def test_stream_max_unique_pieces():
+    """测试BBPE流式训练的唯一片段数上限"""
+    from zero_tokenizer import BBPETokenizer
+
+    rows = ["hello world"] * 50 + [f"noise{i}" for i in range(200)]
+
+    exact = BBPETokenizer().train_from_iterator_stream(iter(rows), 300, buffer_size=16)
+    assert exact["pruned"] == 0
+    assert exact["count_error"] == 0
+
+    tokenizer = BBPETokenizer()
+    summary = tokenizer.train_from_iterator_stream(
+        iter(rows), 300, buffer_size=16, max_unique_pieces=20
+    )
+    assert summary["pruned"] > 0
+    assert summary["unique_pieces"] <= 20
+    assert summary["unique_pieces"] < exact["unique_pieces"]
+    assert tokenizer.decode(tokenizer.encode("hello world")) == "hello world"
+
+    with pytest.raises(ValueError):
+        BBPETokenizer().train_from_iterator_stream(iter(rows), 300, max_unique_pieces=0)
+
+
+def test_train_pair_snapshots():
+    """测试训练中途的候选配对快照和提前停止"""
+    from zero_tokenizer import BBPETokenizer, Tokenizer
+
+    snapshots = []
+
+    def watch(event):
+        if event["event"] == "pair_snapshot":
+            snapshots.append(event)
+
+    tokenizer = Tokenizer()
+    tokenizer.add_train_observer(watch, snapshot_every=2, top_k=3)
+    tokenizer.train(["hello hello world"], 300)
+    assert snapshots[0]["merges_done"] == 0
+    assert [s["merges_done"] % 2 for s in snapshots] == [0] * len(snapshots)
+    first = snapshots[0]["candidates"]
+    assert len(first) == 3
+    assert first[0]["count"] >= first[-1]["count"]
+    assert set(first[0]) == {"pair", "count", "piece"}
+
+    # 第一次快照后停止，不学任何合并
+    finished = []
+
+    def stop(event):
+        if event["event"] == "finished":
+            finished.append(event)
+        return event["event"] != "pair_snapshot"
+
+    tokenizer = BBPETokenizer()
+    tokenizer.add_train_observer(stop, snapshot_every=1)
+    tokenizer.train(["hello hello world"], 300)
+    assert finished[0]["stopped"]
+    assert finished[0]["merges"] == 0
+
+    with pytest.raises(ValueError):
+        BBPETokenizer().add_train_observer(watch, snapshot_every=0)
+
+
+def test_model_registry(tmp_path, monkeypatch):
+    """测试按名称保存和加载模型"""
+    from zero_tokenizer import BBPETokenizer, Tokenizer, list_models
+
+    monkeypatch.setenv("ZERO_TOKENIZER_MODELS", str(tmp_path))
+    assert list_models() == []
+
+    tokenizer = BBPETokenizer()
+    tokenizer.train(["hello world hello there"], 270)
+    path = tokenizer.save_as("en-small")
+    assert path.startswith(str(tmp_path))
+
+    loaded = BBPETokenizer()
+    loaded.load_named("en-small")
+    assert loaded.encode("hello world") == tokenizer.encode("hello world")
+
+    models = list_models()
+    assert [m["name"] for m in models] == ["en-small"]
+    assert models[0]["kind"] == "bbpe"
+    assert models[0]["vocab_size"] == tokenizer.vocab_size()
+
+    with pytest.raises(Exception):
+        Tokenizer().load_named("en-small")
+    with pytest.raises(Exception):
+        tokenizer.save_as("../escape")
+
+
+def test_char_dict_resources(tmp_path, monkeypatch):
+    """常用汉字字表可以指定路径或不预置，不依赖当前工作目录"""
+    from zero_tokenizer import UnigramTokenizer, WordPieceTokenizer
+
+    monkeypatch.chdir(tmp_path)
+    assert UnigramTokenizer(preload_chars=False).vocab_size() == 256
+    assert WordPieceTokenizer(preload_chars=False).vocab_size() == 256
+    assert WordPieceTokenizer.with_bert_special_tokens(preload_chars=False).vocab_size() == 261
+
+    chars = tmp_path / "chars.txt"
+    chars.write_text("甲\n乙\n丙\n", encoding="utf-8")
+    tokenizer = UnigramTokenizer(char_dict=str(chars))
+    assert tokenizer.vocab_size() == 259
+
+    with pytest.raises(ValueError):
+        UnigramTokenizer(char_dict=str(tmp_path / "missing.txt"))
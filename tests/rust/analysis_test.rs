@@ -0,0 +1,322 @@
+//! 分析工具测试
+
+use zero_tokenizer::analysis;
+use zero_tokenizer::error::TokenizerError;
+use zero_tokenizer::prelude::*;
+
+/// 测试样本统计、排序和压缩率
+#[test]
+fn test_inspect_counts_and_ratios() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello hello world".to_string()], 270)
+        .unwrap();
+
+    let corpus = ["hello world", "hello"];
+    let report = analysis::inspect(&tokenizer, corpus, 3).unwrap();
+
+    let expected_tokens: usize = corpus
+        .iter()
+        .map(|text| tokenizer.encode(text).unwrap().len())
+        .sum();
+    assert_eq!(report.vocab_size, tokenizer.vocab_size());
+    assert_eq!(report.samples, 2);
+    assert_eq!(report.total_bytes, 16);
+    assert_eq!(report.total_tokens, expected_tokens);
+    assert_eq!(report.fallback_tokens, 0);
+    assert!(report.bytes_per_token() > 1.0);
+
+    // 最常用的标记是出现两次的 "hello"
+    let top = &report.most_used[0];
+    assert_eq!(top.count, 2);
+    assert_eq!(top.token, "hello");
+    assert!(report.most_used.len() <= 3);
+    assert!(report
+        .most_used
+        .windows(2)
+        .all(|pair| pair[0].count >= pair[1].count));
+    assert_eq!(
+        report.unused_tokens(),
+        report.vocab_size - report.used_tokens
+    );
+}
+
+/// 测试未合并的多字节字符计为字节回退
+#[test]
+fn test_inspect_byte_fallback_rate() {
+    let tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+
+    // 未训练的BBPE将 "中" 拆成3个字节
+    let report = analysis::inspect(&tokenizer, ["a中"], 5).unwrap();
+    assert_eq!(report.total_tokens, 4);
+    assert_eq!(report.fallback_tokens, 3);
+    assert!((report.fallback_rate() - 0.75).abs() < 1e-9);
+    assert_eq!(report.most_used.len(), 4);
+
+    // 单独的字节无法解码，显示为替换字符
+    assert!(report
+        .most_used
+        .iter()
+        .any(|entry| entry.token == "\u{FFFD}"));
+}
+
+/// 测试字节级BPE能无损还原任意文本
+#[test]
+fn test_verify_lossless_bbpe() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello world".to_string()], 265)
+        .unwrap();
+
+    let report =
+        analysis::verify_lossless(&tokenizer, ["hello world", "  空格\n与换行", "🎉 emoji"])
+            .unwrap();
+    assert_eq!(report.samples, 3);
+    assert!(report.is_lossless());
+    assert!((report.lossless_rate() - 1.0).abs() < 1e-9);
+}
+
+/// 将字母转为小写的字符级分词器，用于构造有损的往返
+struct LowercaseTokenizer;
+
+impl Tokenizer for LowercaseTokenizer {
+    type TokenId = u32;
+
+    fn encode(&self, text: &str) -> Result<Vec<u32>, TokenizerError> {
+        Ok(text
+            .chars()
+            .map(|c| c.to_ascii_lowercase() as u32)
+            .collect())
+    }
+
+    fn decode(&self, tokens: &[u32]) -> Result<String, TokenizerError> {
+        tokens
+            .iter()
+            .enumerate()
+            .map(|(index, &id)| {
+                char::from_u32(id).ok_or(TokenizerError::UnknownTokenId { id, index })
+            })
+            .collect()
+    }
+
+    fn train(&mut self, _texts: Vec<String>, _vocab_size: u32) -> Result<(), TokenizerError> {
+        Ok(())
+    }
+
+    fn vocab_size(&self) -> usize {
+        0
+    }
+
+    fn save(&self, _path: &str) -> Result<(), TokenizerError> {
+        Ok(())
+    }
+
+    fn load(&mut self, _path: &str) -> Result<(), TokenizerError> {
+        Ok(())
+    }
+}
+
+/// 测试有损的往返会记录输入、解码结果和第一个不同的字节
+#[test]
+fn test_verify_lossless_reports_divergence() {
+    let corpus = ["hello", "héllo World", "abc"];
+    let report = analysis::verify_lossless(&LowercaseTokenizer, corpus).unwrap();
+
+    assert_eq!(report.samples, 3);
+    assert_eq!(report.mismatches.len(), 1);
+    assert!(!report.is_lossless());
+    assert!((report.lossless_rate() - 2.0 / 3.0).abs() < 1e-9);
+
+    let mismatch = &report.mismatches[0];
+    assert_eq!(mismatch.index, 1);
+    assert_eq!(mismatch.input, "héllo World");
+    assert_eq!(mismatch.decoded.as_deref(), Some("héllo world"));
+    // "é" 占2个字节，"W" 位于第7个字节
+    assert_eq!(mismatch.first_divergent_byte, 7);
+    assert!(mismatch.error.is_none());
+}
+
+/// 测试两个分词器的对比统计
+#[test]
+fn test_compare_tokenizers() {
+    let untrained = zero_tokenizer::prelude::bbpe().unwrap();
+    let mut trained = zero_tokenizer::prelude::bbpe().unwrap();
+    trained
+        .train(vec!["hello hello hello world".to_string()], 270)
+        .unwrap();
+
+    let corpus = ["hello", "xyz", "hello world"];
+    let report = analysis::compare(&untrained, &trained, corpus, 1).unwrap();
+
+    assert_eq!(report.samples, 3);
+    assert_eq!(report.left_tokens, 5 + 3 + 11);
+    assert_eq!(
+        report.right_tokens,
+        corpus
+            .iter()
+            .map(|text| trained.encode(text).unwrap().len())
+            .sum::<usize>()
+    );
+    assert!(report.token_ratio() < 1.0);
+    assert_eq!(report.left_fewer, 0);
+    assert_eq!(report.right_fewer, 2);
+
+    // 只有 "xyz" 的切分相同，示例数量受 max_examples 限制
+    assert_eq!(report.identical, 1);
+    assert!((report.agreement_rate() - 1.0 / 3.0).abs() < 1e-9);
+    assert_eq!(report.divergences.len(), 1);
+    let divergence = &report.divergences[0];
+    assert_eq!(divergence.index, 0);
+    assert_eq!(divergence.left, vec!["h", "e", "l", "l", "o"]);
+    assert_eq!(divergence.right, vec!["hello"]);
+}
+
+/// 测试切分粒度统计和按文字体系分组
+#[test]
+fn test_analyze_fertility_by_script() {
+    let tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+
+    // 未训练的BBPE每个字节一个标记
+    let report = analysis::analyze(&tokenizer, ["hi 中文", "ok 42"]).unwrap();
+    assert_eq!(report.samples, 2);
+    assert_eq!(report.overall.words, 4);
+    assert_eq!(report.overall.bytes, 14);
+    assert_eq!(report.overall.tokens, 14);
+    assert!((report.overall.tokens_per_byte() - 1.0).abs() < 1e-9);
+
+    let latin = &report.scripts["Latin"];
+    assert_eq!(latin.words, 2);
+    assert_eq!(latin.tokens, 4);
+    assert!((latin.fertility() - 2.0).abs() < 1e-9);
+    assert_eq!(latin.fallback_tokens, 0);
+
+    let han = &report.scripts["Han"];
+    assert_eq!(han.words, 1);
+    assert_eq!(han.chars, 2);
+    assert_eq!(han.tokens, 6);
+    assert_eq!(han.fallback_tokens, 6);
+    assert!((han.fallback_rate() - 1.0).abs() < 1e-9);
+
+    assert_eq!(report.scripts["Common"].words, 1);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["scripts"]["Han"]["fertility"], 6.0);
+    assert_eq!(json["overall"]["tokens"], 14);
+}
+
+/// 测试标记使用频次按ID排序
+#[test]
+fn test_token_usage() {
+    let tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+
+    let usage = analysis::token_usage(&tokenizer, ["abca", "c"]).unwrap();
+    let id = |b: u8| tokenizer.encode(&(b as char).to_string()).unwrap()[0];
+    let mut expected = vec![(id(b'a'), 2), (id(b'b'), 1), (id(b'c'), 2)];
+    expected.sort_unstable();
+    assert_eq!(usage, expected);
+
+    let total: u64 = usage.iter().map(|&(_, count)| count).sum();
+    assert_eq!(total, 5);
+    assert!(analysis::token_usage(&tokenizer, Vec::<String>::new())
+        .unwrap()
+        .is_empty());
+}
+
+/// 测试多分词器评测表
+#[test]
+fn test_evaluate_table() {
+    let untrained = zero_tokenizer::prelude::bbpe().unwrap();
+    let mut trained = zero_tokenizer::prelude::bbpe().unwrap();
+    trained
+        .train(vec!["hello hello hello world".to_string()], 270)
+        .unwrap();
+
+    let english = ["hello world".to_string(), "hello".to_string()];
+    let chinese = ["你好".to_string()];
+    let report = analysis::evaluate(
+        &[("raw", &untrained), ("trained", &trained)],
+        &[("en", &english[..]), ("zh", &chinese[..])],
+    )
+    .unwrap();
+
+    assert_eq!(report.rows.len(), 4);
+    let row = |tokenizer: &str, corpus: &str| {
+        report
+            .rows
+            .iter()
+            .find(|row| row.tokenizer == tokenizer && row.corpus == corpus)
+            .unwrap()
+    };
+    assert!((row("raw", "en").bytes_per_token - 1.0).abs() < 1e-9);
+    assert!(row("trained", "en").bytes_per_token > 1.0);
+    assert!(row("trained", "en").fertility < row("raw", "en").fertility);
+    assert!((row("raw", "zh").fallback_rate - 1.0).abs() < 1e-9);
+    assert!(report
+        .rows
+        .iter()
+        .all(|row| (row.lossless_rate - 1.0).abs() < 1e-9));
+
+    let table = report.to_markdown();
+    assert_eq!(table.lines().count(), 2 + 4);
+    assert!(table.contains("| trained | zh | 1 |"));
+}
+
+/// 测试基础词汇表之外的字符按字节回退和未知标记分别列出
+#[test]
+fn test_coverage_lists_missing_chars() {
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    let corpus = ["ab测😀", "😀😀𠀀"];
+
+    let report = analysis::coverage(&tokenizer, corpus).unwrap();
+    assert_eq!(report.samples, 2);
+    assert_eq!(report.total_chars, 7);
+    assert_eq!(report.distinct_chars, 5);
+    assert_eq!(report.covered_chars, 3);
+    assert!((report.coverage_rate() - 3.0 / 7.0).abs() < 1e-9);
+    let missing: Vec<(char, usize)> = report
+        .byte_fallback
+        .iter()
+        .map(|c| (c.ch, c.count))
+        .collect();
+    assert_eq!(missing, [('😀', 3), ('𠀀', 1)]);
+    assert!(report.unknown.is_empty());
+    assert!(report.is_lossless());
+
+    // 关闭字节回退后同样的字符编码为未知标记
+    tokenizer.set_byte_fallback(false);
+    let report = analysis::coverage(&tokenizer, corpus).unwrap();
+    assert!(report.byte_fallback.is_empty());
+    assert_eq!(report.unknown.len(), 2);
+    assert_eq!(report.unknown[0].ch, '😀');
+    assert!(!report.is_lossless());
+
+    let empty = analysis::coverage(&tokenizer, std::iter::empty::<&str>()).unwrap();
+    assert_eq!(empty.coverage_rate(), 1.0);
+}
+
+/// 测试编码长度分布的区间、分位数和截断比例
+#[test]
+fn test_length_histogram() {
+    let tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    // 未训练的BBPE每个字节一个标记，编码长度等于字节数
+    let corpus: Vec<String> = (1..=10).map(|len| "a".repeat(len)).collect();
+
+    let histogram = analysis::length_histogram(&tokenizer, &corpus, 4).unwrap();
+    assert_eq!(histogram.samples, 10);
+    assert_eq!(histogram.total_tokens, 55);
+    assert_eq!((histogram.min, histogram.max), (1, 10));
+    assert_eq!((histogram.p50, histogram.p90, histogram.p99), (5, 9, 10));
+    assert_eq!(histogram.mean(), 5.5);
+    let counts: Vec<usize> = histogram.buckets.iter().map(|b| b.count).collect();
+    assert_eq!(counts, vec![3, 4, 3]);
+    assert_eq!(histogram.buckets[2].start, 8);
+    assert_eq!(histogram.fraction_within(7), 0.7);
+
+    let json = serde_json::to_value(&histogram).unwrap();
+    assert_eq!(json["mean"], 5.5);
+
+    let empty = analysis::length_histogram(&tokenizer, &Vec::<String>::new(), 4).unwrap();
+    assert_eq!(empty.samples, 0);
+    assert_eq!(empty.fraction_within(0), 1.0);
+    assert!(analysis::length_histogram(&tokenizer, &corpus, 0).is_err());
+}
@@ -0,0 +1,45 @@
+//! 异步接口测试
+
+use futures_util::stream;
+
+use zero_tokenizer::asynchronous;
+use zero_tokenizer::prelude::*;
+
+#[tokio::test]
+async fn test_train_from_stream_matches_sync_training() {
+    let texts = vec!["hello world".to_string(), "hello there".to_string()];
+
+    let tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    let trained = asynchronous::train_from_stream(tokenizer, stream::iter(texts.clone()), 265)
+        .await
+        .unwrap();
+
+    let mut expected = zero_tokenizer::prelude::bbpe().unwrap();
+    expected.train(texts, 265).unwrap();
+
+    assert_eq!(trained.vocab_size(), expected.vocab_size());
+    assert_eq!(
+        trained.encode("hello world").unwrap(),
+        expected.encode("hello world").unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_encode_batch_in_chunks() {
+    let tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    let texts: Vec<String> = (0..10).map(|i| format!("text {}", i)).collect();
+
+    let encoded = asynchronous::encode_batch(&tokenizer, &texts, 3)
+        .await
+        .unwrap();
+    assert_eq!(encoded.len(), texts.len());
+    for (text, ids) in texts.iter().zip(&encoded) {
+        assert_eq!(ids, &tokenizer.encode(text).unwrap());
+    }
+
+    // 块大小为0时按1处理
+    let encoded_one_by_one = asynchronous::encode_batch(&tokenizer, &texts, 0)
+        .await
+        .unwrap();
+    assert_eq!(encoded_one_by_one, encoded);
+}
@@ -0,0 +1,96 @@
+//! 运行时选择分词器类型测试
+//!
+//! 测试按模型文件内容识别四种分词器的文本、二进制和 `tokenizer.json` 格式，以及以trait对象使用分词器
+
+use zero_tokenizer::base::binary::ModelKind;
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::{corpus, temp_path, trained_tokenizers};
+
+#[test]
+fn test_detect_text_models() {
+    let text = "hello world";
+    for tokenizer in trained_tokenizers(&corpus()) {
+        let path = temp_path(&format!("auto_text_{}", tokenizer.kind().name()));
+        tokenizer.save(&path).unwrap();
+
+        assert_eq!(AutoTokenizer::detect(&path).unwrap(), tokenizer.kind());
+        let loaded = AutoTokenizer::from_file(&path).unwrap();
+        assert_eq!(loaded.kind(), tokenizer.kind());
+        assert_eq!(
+            loaded.encode(text).unwrap(),
+            tokenizer.encode(text).unwrap()
+        );
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(format!("{}.scores", path));
+    }
+}
+
+#[test]
+fn test_detect_binary_and_hf_json_models() {
+    let text = "hello world";
+    let mut bbpe = bbpe().unwrap();
+    bbpe.train(corpus(), 280).unwrap();
+    let binary = temp_path("auto_binary.ztok");
+    bbpe.save_binary(&binary).unwrap();
+    assert_eq!(AutoTokenizer::detect(&binary).unwrap(), ModelKind::Bbpe);
+    let loaded = AutoTokenizer::from_file(&binary).unwrap();
+    assert_eq!(loaded.encode(text).unwrap(), bbpe.encode(text).unwrap());
+
+    let json = temp_path("auto_tokenizer.json");
+    bbpe.save_tokenizer_json(&json).unwrap();
+    assert_eq!(AutoTokenizer::detect(&json).unwrap(), ModelKind::Bbpe);
+
+    let mut wordpiece = wordpiece().unwrap();
+    let size = wordpiece.vocab_size() as u32 + 20;
+    wordpiece.train(corpus(), size).unwrap();
+    wordpiece.save_tokenizer_json(&json).unwrap();
+    let loaded = AutoTokenizer::from_file(&json).unwrap();
+    assert_eq!(loaded.kind(), ModelKind::WordPiece);
+    assert_eq!(
+        loaded.encode(text).unwrap(),
+        wordpiece.encode(text).unwrap()
+    );
+
+    let _ = std::fs::remove_file(&binary);
+    let _ = std::fs::remove_file(&json);
+}
+
+#[test]
+fn test_unrecognized_model_is_an_error() {
+    let path = temp_path("auto_garbage");
+    std::fs::write(&path, "not a model\n").unwrap();
+    assert!(AutoTokenizer::detect(&path).is_err());
+    assert!(AutoTokenizer::from_file(&path).is_err());
+    assert!(AutoTokenizer::from_file(&temp_path("auto_missing")).is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_heterogeneous_tokenizers_as_trait_objects() {
+    let tokenizers = trained_tokenizers(&corpus());
+    let kinds: Vec<ModelKind> = tokenizers.iter().map(|t| t.kind()).collect();
+    assert_eq!(kinds, ModelKind::ALL.to_vec());
+
+    for tokenizer in &tokenizers {
+        let texts = ["hello world", "the world is round"];
+        let batch = tokenizer.batch_encode(&texts).unwrap();
+        for (text, ids) in texts.iter().zip(&batch) {
+            assert_eq!(ids, &tokenizer.encode(text).unwrap());
+        }
+        let plus = tokenizer.batch_encode_plus(&texts, None).unwrap();
+        assert_eq!(plus.ids.len(), batch.len());
+    }
+
+    let mut first = AutoTokenizer::for_kind(ModelKind::Bbpe).unwrap();
+    assert!(first.downcast_ref::<BBPE>().is_some());
+    assert!(first.downcast_ref::<BPE>().is_none());
+    first
+        .downcast_mut::<BBPE>()
+        .unwrap()
+        .train(corpus(), 260)
+        .unwrap();
+    assert_eq!(first.vocab_size(), 260);
+}
@@ -131,7 +131,6 @@ fn test_wordpiece_parallel_encode() {
     }
 }
 
-#[cfg(feature = "python")]
 #[test]
 fn test_bpe_parallel_encode() {
     let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
@@ -205,3 +204,189 @@ fn test_empty_texts_in_batch() {
     assert!(!batch_results[2].is_empty()); // "World"
     assert!(batch_results[3].is_empty()); // ""
 }
+
+/// 测试动态批处理队列合并多个线程提交的文本
+#[test]
+fn test_batch_queue_concurrent_submit() {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use zero_tokenizer::batching::{BatchConfig, BatchQueue};
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello world".to_string()], 270)
+        .unwrap();
+    let expected = tokenizer.encode("hello world").unwrap();
+
+    let config = BatchConfig {
+        max_batch_size: 8,
+        max_wait: Duration::from_millis(5),
+    };
+    let queue = Arc::new(BatchQueue::new(Arc::new(tokenizer), config));
+
+    let handles: Vec<_> = (0..16)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            std::thread::spawn(move || queue.encode("hello world").unwrap())
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), expected);
+    }
+
+    let many = queue.encode_many(["hello", "", "hello world"]).unwrap();
+    assert_eq!(many.len(), 3);
+    assert!(many[1].is_empty());
+    assert_eq!(many[2], expected);
+
+    // 先提交再取回，结果按请求分别返回
+    let first = queue.submit("world").unwrap();
+    let second = queue.submit("hello world").unwrap();
+    assert_eq!(second.wait().unwrap(), expected);
+    assert!(!first.wait().unwrap().is_empty());
+}
+
+#[test]
+fn test_parallel_chunking_counts_match() {
+    use zero_tokenizer::base::tokenizer_base::{count_pairs_parallel_with, ParallelChunking};
+    use zero_tokenizer::base::word::Word;
+
+    assert!(ParallelChunking::new(16, 1).is_err());
+
+    // 少量超长片段混在大量短片段中
+    let mut words: Vec<Word<u32>> = (0..500).map(|i| Word::new(vec![i % 7, i % 5, 1])).collect();
+    words.push(Word::new((0..2000).map(|i| i % 13).collect()));
+    let counts: Vec<i32> = (0..words.len() as i32).map(|i| i % 3 + 1).collect();
+
+    let (expected, _) = count_pairs_parallel_with(&words, &counts, ParallelChunking::default());
+    for (pieces_per_task, reduce_width) in [(1, 2), (7, 3), (64, 16), (10_000, 2)] {
+        let chunking = ParallelChunking::new(pieces_per_task, reduce_width).unwrap();
+        let (pair_counts, _) = count_pairs_parallel_with(&words, &counts, chunking);
+        assert_eq!(pair_counts, expected);
+    }
+
+    // 不同粒度训练出的合并规则一致
+    let texts: Vec<String> = (0..50)
+        .map(|i| format!("sample {} {}", i, "x".repeat(i * 10)))
+        .collect();
+    let mut reference = zero_tokenizer::prelude::bbpe().unwrap();
+    reference.train(texts.clone(), 320).unwrap();
+    let mut tuned = zero_tokenizer::prelude::bbpe().unwrap();
+    tuned.base.parallel = ParallelChunking::new(4, 8).unwrap();
+    tuned.train(texts, 320).unwrap();
+    assert_eq!(tuned.merges, reference.merges);
+}
+
+/// 测试连续存储的批量编码：截断、拼接和补齐
+#[test]
+fn test_encode_batch_packed() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    let texts = ["Hello world!", "你好世界！", "", "Test text"];
+    tokenizer
+        .train(texts.iter().map(|t| t.to_string()).collect(), 300)
+        .unwrap();
+    let rows: Vec<Vec<u32>> = texts.iter().map(|t| tokenizer.encode(t).unwrap()).collect();
+
+    // 不补齐：各行首尾相接
+    let packed = tokenizer.encode_batch_packed(&texts, None, None).unwrap();
+    assert_eq!(packed.len(), texts.len());
+    assert_eq!(packed.width, None);
+    assert_eq!(packed.ids, rows.concat());
+    assert_eq!(packed.offsets.last(), Some(&packed.ids.len()));
+    for (index, row) in rows.iter().enumerate() {
+        assert_eq!(packed.row(index).unwrap(), row.as_slice());
+        assert_eq!(packed.lengths[index], row.len());
+    }
+
+    // 截断并补齐到固定宽度
+    let packed = tokenizer
+        .encode_batch_packed(&texts, Some(3), Some(0))
+        .unwrap();
+    assert_eq!(packed.width, Some(3));
+    assert_eq!(packed.ids.len(), texts.len() * 3);
+    assert_eq!(packed.lengths[2], 0);
+    assert_eq!(packed.row(0).unwrap(), &rows[0][..3]);
+    assert_eq!(&packed.ids[6..9], &[0, 0, 0]);
+    let mask = packed.attention_mask();
+    assert_eq!(
+        mask.iter().filter(|&&m| m == 1).count(),
+        packed.lengths.iter().sum::<usize>()
+    );
+
+    // 未指定最大长度时补齐到最长的行
+    let packed = PackedBatch::pack(&rows, None, Some(0));
+    assert_eq!(packed.width, rows.iter().map(Vec::len).max());
+}
+
+/// 测试最佳适配拼接：样本边界、位置编号和超长样本的切分
+#[test]
+fn test_pack_sequences() {
+    use zero_tokenizer::base::packed::pack_sequences;
+
+    let sample = |id: u32, len: usize| vec![id; len];
+    // 加上分隔符后的长度为 6、4、3、2、10
+    let encodings = vec![
+        sample(10, 5),
+        sample(11, 3),
+        sample(12, 2),
+        sample(13, 1),
+        sample(14, 9),
+    ];
+    let packed = pack_sequences(&encodings, 8, 0).unwrap();
+
+    assert_eq!(packed.len(), 4);
+    assert_eq!(packed.ids.len(), 4 * 8);
+    assert_eq!(
+        packed.sources,
+        vec![vec![4], vec![0, 3], vec![1, 2], vec![4]]
+    );
+    assert_eq!(packed.row(0).unwrap(), &[14; 8]);
+    assert_eq!(packed.row(1).unwrap(), &[10, 10, 10, 10, 10, 0, 13, 0]);
+    assert_eq!(packed.row(2).unwrap(), &[11, 11, 11, 0, 12, 12, 0, 0]);
+    assert_eq!(&packed.segment_ids[16..24], &[1, 1, 1, 1, 2, 2, 2, 0]);
+    assert_eq!(&packed.position_ids[16..24], &[0, 1, 2, 3, 0, 1, 2, 0]);
+    assert_eq!(packed.row(3).unwrap(), &[14, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        packed.attention_mask().iter().filter(|&&m| m == 1).count(),
+        25
+    );
+    assert_eq!(packed.efficiency(), 25.0 / 32.0);
+
+    assert!(pack_sequences(&[], 8, 0).unwrap().is_empty());
+    assert!(pack_sequences(&encodings, 0, 0).is_err());
+}
+
+/// 测试异步编码快照只复制一次，作废后重建，复制出的分词器不共用快照
+#[test]
+fn test_frozen_snapshot_reused_until_invalidated() {
+    use std::sync::Arc;
+
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello".to_string()], 270)
+        .unwrap();
+
+    let mut copies = 0;
+    let mut freeze = |tokenizer: &BBPE| {
+        tokenizer.async_snapshot.get_or_freeze(|| {
+            copies += 1;
+            tokenizer.clone()
+        })
+    };
+    let first = freeze(&tokenizer);
+    let second = freeze(&tokenizer);
+    assert!(Arc::ptr_eq(&first, &second));
+    assert!(!tokenizer.clone().async_snapshot.is_frozen());
+
+    tokenizer.async_snapshot.invalidate();
+    assert!(!tokenizer.async_snapshot.is_frozen());
+    let third = freeze(&tokenizer);
+    assert!(!Arc::ptr_eq(&first, &third));
+    assert_eq!(copies, 2);
+
+    // 已经取得的快照不受作废影响
+    assert_eq!(
+        first.encode("hello").unwrap(),
+        tokenizer.encode("hello").unwrap()
+    );
+}
@@ -2,8 +2,10 @@
 //!
 //! 这个文件包含BBPE分词器的特定功能测试，不包含与correctness_test.rs重复的正确性测试。
 
+use zero_tokenizer::base::tokenizer_base::MergeMap;
 use zero_tokenizer::prelude::*;
 mod test_utils;
+use test_utils::temp_path;
 
 /// 测试BBPE分词器的训练功能
 #[test]
@@ -33,3 +35,800 @@ fn test_bbpe_default() {
 
     test_utils::test_default_constructor(&tokenizer, 256); // BBPE初始化时包含所有字节值
 }
+
+/// 测试训练事件按顺序分发给观察者
+#[test]
+fn test_bbpe_train_events() {
+    use std::sync::{Arc, Mutex};
+    use zero_tokenizer::base::events::TrainEvent;
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    tokenizer.add_train_observer(Arc::new(move |event: &TrainEvent| {
+        sink.lock().unwrap().push(event.clone());
+    }));
+
+    tokenizer
+        .train(vec!["hello hello world".to_string()], 260)
+        .unwrap();
+
+    let events = events.lock().unwrap();
+    assert!(matches!(
+        events.first(),
+        Some(TrainEvent::TrainStarted {
+            planned_merges: 4,
+            ..
+        })
+    ));
+    assert!(matches!(
+        events.get(1),
+        Some(TrainEvent::PairCountsDone { .. })
+    ));
+
+    let ranks: Vec<u32> = events
+        .iter()
+        .filter_map(|e| match e {
+            TrainEvent::MergeApplied { rank, .. } => Some(*rank),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(ranks, vec![0, 1, 2, 3]);
+
+    match events.last() {
+        Some(TrainEvent::Finished { stats }) => {
+            assert_eq!(stats.merges, 4);
+            assert_eq!(stats.vocab_size, tokenizer.vocab_size());
+        }
+        other => panic!("最后一个事件应为Finished: {:?}", other),
+    }
+}
+
+/// 记录候选配对快照，收到指定次数的快照后要求停止训练的观察者
+struct SnapshotRecorder {
+    snapshots: std::sync::Mutex<Vec<(u32, Vec<zero_tokenizer::base::events::PairCandidate>)>>,
+    stop_after: usize,
+}
+
+impl zero_tokenizer::base::events::TrainObserver for SnapshotRecorder {
+    fn on_event(&self, event: &zero_tokenizer::base::events::TrainEvent) {
+        if let zero_tokenizer::base::events::TrainEvent::PairSnapshot {
+            merges_done,
+            candidates,
+        } = event
+        {
+            self.snapshots
+                .lock()
+                .unwrap()
+                .push((*merges_done, candidates.clone()));
+        }
+    }
+
+    fn snapshot_request(&self) -> Option<zero_tokenizer::base::events::SnapshotRequest> {
+        Some(zero_tokenizer::base::events::SnapshotRequest { every: 2, top_k: 3 })
+    }
+
+    fn should_stop(&self) -> bool {
+        self.snapshots.lock().unwrap().len() >= self.stop_after
+    }
+}
+
+/// 测试训练中途的候选配对快照和提前停止
+#[test]
+fn test_bbpe_pair_snapshots() {
+    use std::sync::{Arc, Mutex};
+
+    let texts = vec!["hello hello world".to_string()];
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    let recorder = Arc::new(SnapshotRecorder {
+        snapshots: Mutex::new(Vec::new()),
+        stop_after: usize::MAX,
+    });
+    tokenizer.add_train_observer(recorder.clone());
+    tokenizer.train(texts.clone(), 262).unwrap();
+
+    let snapshots = recorder.snapshots.lock().unwrap();
+    let points: Vec<u32> = snapshots.iter().map(|(merges, _)| *merges).collect();
+    assert_eq!(points, vec![0, 2, 4, 6]);
+    let (_, first) = &snapshots[0];
+    assert_eq!(first.len(), 3);
+    assert!(first.windows(2).all(|w| w[0].count >= w[1].count));
+    // 训练开始时计数最高的配对就是第一次合并的配对
+    let merges = tokenizer.get_mergeable_ranks();
+    let (&first_pair, _) = merges.iter().min_by_key(|&(_, &id)| id).unwrap();
+    assert_eq!(first[0].pair, first_pair);
+    assert_eq!(first[0].count, 2);
+    assert_eq!(first[0].piece.len(), 2);
+
+    // 收到两次快照后停止，只完成两次合并
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    tokenizer.add_train_observer(Arc::new(
+        move |event: &zero_tokenizer::base::events::TrainEvent| {
+            sink.lock().unwrap().push(event.clone());
+        },
+    ));
+    tokenizer.add_train_observer(Arc::new(SnapshotRecorder {
+        snapshots: Mutex::new(Vec::new()),
+        stop_after: 2,
+    }));
+    tokenizer.train(texts, 262).unwrap();
+    let events = events.lock().unwrap();
+    // 快照只发给请求了快照的观察者
+    assert!(!events.iter().any(|e| matches!(
+        e,
+        zero_tokenizer::base::events::TrainEvent::PairSnapshot { .. }
+    )));
+    match events.last() {
+        Some(zero_tokenizer::base::events::TrainEvent::Finished { stats }) => {
+            assert!(stats.stopped);
+            assert_eq!(stats.merges, 2);
+        }
+        other => panic!("最后一个事件应为Finished: {:?}", other),
+    }
+}
+
+/// 测试分阶段编码耗时统计
+#[test]
+fn test_bbpe_encode_profiling() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello world".to_string()], 270)
+        .unwrap();
+
+    // 默认关闭，不产生统计
+    tokenizer.encode("hello world").unwrap();
+    assert_eq!(tokenizer.profiler.snapshot(), Default::default());
+
+    tokenizer.profiler.set_enabled(true);
+    tokenizer.encode("hello world").unwrap();
+    tokenizer.encode("world").unwrap();
+    let profile = tokenizer.profiler.snapshot();
+    assert_eq!(profile.texts, 2);
+    assert!(profile.pre_tokenize_ns > 0);
+    assert!(profile.vocab_lookup_ns > 0);
+    assert!(profile.merge_ns > 0);
+    assert_eq!(profile.python_conversion_ns, 0);
+
+    tokenizer.profiler.reset();
+    assert_eq!(tokenizer.profiler.snapshot().texts, 0);
+}
+
+/// 测试按前缀和完整文本查找标记ID
+#[test]
+fn test_bbpe_ids_with_prefix_and_covering() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello hello hello".to_string()], 270)
+        .unwrap();
+
+    let hello = tokenizer.encode("hello").unwrap();
+    let spaced = tokenizer.encode(" hello").unwrap();
+    assert_eq!(hello.len(), 1);
+    assert_eq!(spaced.len(), 1);
+
+    // "hello" 和 " hello" 都是禁用词 "hello" 的变体
+    let mut expected = vec![hello[0], spaced[0]];
+    expected.sort_unstable();
+    assert_eq!(tokenizer.token_ids_covering("hello"), expected);
+
+    let with_prefix = tokenizer.ids_with_prefix("he");
+    assert!(with_prefix.contains(&hello[0]));
+    assert!(!with_prefix.contains(&spaced[0]));
+    assert!(with_prefix.windows(2).all(|pair| pair[0] < pair[1]));
+    for id in &with_prefix {
+        assert!(tokenizer.decode(&[*id]).unwrap().starts_with("he"));
+    }
+
+    // 空前缀匹配整个词汇表
+    assert_eq!(tokenizer.ids_with_prefix("").len(), tokenizer.vocab_size());
+}
+
+/// 测试标记渲染：控制字符转义、首尾空格可见、不完整的字节显示为十六进制
+#[test]
+fn test_bbpe_render_token() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello hello hello".to_string()], 270)
+        .unwrap();
+
+    assert_eq!(tokenizer.render_token(&u32::from(b'a')), "a");
+    assert_eq!(tokenizer.render_token(&u32::from(b' ')), "␣");
+    assert_eq!(tokenizer.render_token(&u32::from(b'\n')), "\\n");
+    assert_eq!(tokenizer.render_token(&0x1B), "\\u{1b}");
+    assert_eq!(tokenizer.render_token(&0xE4), "<0xE4>");
+    assert_eq!(tokenizer.render_token(&u32::MAX), "<?>");
+
+    let spaced = tokenizer.encode(" hello").unwrap();
+    assert_eq!(spaced.len(), 1);
+    assert_eq!(tokenizer.render_token(&spaced[0]), "␣hello");
+}
+
+/// 测试计数相同的配对之间的合并顺序
+#[test]
+fn test_bbpe_tie_break() {
+    use zero_tokenizer::base::merge_job::TieBreak;
+
+    let train = |texts: &[&str], tie_break: TieBreak| {
+        let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+        tokenizer.base.tie_break = tie_break;
+        let texts = texts.iter().map(|text| text.to_string()).collect();
+        tokenizer.train(texts, 258).unwrap();
+        tokenizer.merges
+    };
+    let (a, b, c, d) = (
+        u32::from(b'a'),
+        u32::from(b'b'),
+        u32::from(b'c'),
+        u32::from(b'd'),
+    );
+
+    // 第一次合并 "aa" 后，("aa", "b") 与 ("c", "d") 计数相同
+    let texts = ["aab", "aab", "aab", "cd", "cd", "cd"];
+    let by_id = train(&texts, TieBreak::PairId);
+    assert_eq!(by_id.get(&(a, a)), Some(&256));
+    assert_eq!(by_id.get(&(c, d)), Some(&257));
+
+    // 按内容比较时 "aa" 排在 "c" 之前，不受 "aa" 的ID较大影响
+    let lexicographic = train(&texts, TieBreak::Lexicographic);
+    assert_eq!(lexicographic.get(&(a, a)), Some(&256));
+    assert_eq!(lexicographic.get(&(256, b)), Some(&257));
+
+    // 按出现顺序时先合并语料中最先出现的配对
+    let texts = ["cd", "cd", "cd", "aab", "aab", "aab"];
+    let by_order = train(&texts, TieBreak::InsertionOrder);
+    assert_eq!(by_order.get(&(c, d)), Some(&256));
+    assert_eq!(by_order.get(&(a, a)), Some(&257));
+    assert_eq!(train(&texts, TieBreak::PairId), by_id);
+}
+
+/// 测试标记的字节区间
+#[test]
+fn test_bbpe_spans() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello hello world".to_string()], 270)
+        .unwrap();
+
+    let text = "hello 世界";
+    let spans = tokenizer.spans(text).unwrap();
+    let ids: Vec<u32> = spans.iter().map(|&(_, _, id)| id).collect();
+    assert_eq!(ids, tokenizer.encode(text).unwrap());
+
+    // 区间首尾相接，覆盖整个文本
+    assert_eq!(spans.first().unwrap().0, 0);
+    assert_eq!(spans.last().unwrap().1, text.len());
+    assert!(spans.windows(2).all(|pair| pair[0].1 == pair[1].0));
+    assert_eq!(&text[spans[0].0..spans[0].1], "hello");
+
+    // 多字节字符被拆成多个字节标记时，每个标记各占一个字节
+    assert!(spans
+        .iter()
+        .skip(1)
+        .any(|&(start, end, _)| end - start == 1 && !text.is_char_boundary(end)));
+}
+
+/// 测试按字符偏移给出的区间
+#[test]
+fn test_bbpe_char_spans() {
+    use zero_tokenizer::base::traits::OffsetUnit;
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello hello world".to_string()], 270)
+        .unwrap();
+
+    let text = "hello 世界 ok";
+    let bytes = tokenizer.spans_in(text, OffsetUnit::Byte).unwrap();
+    assert_eq!(bytes, tokenizer.spans(text).unwrap());
+
+    let chars = tokenizer.spans_in(text, OffsetUnit::Char).unwrap();
+    assert_eq!(chars.len(), bytes.len());
+    let char_vec: Vec<char> = text.chars().collect();
+    assert_eq!(chars.last().unwrap().1, char_vec.len());
+    for (&(start, end, id), &(byte_start, byte_end, byte_id)) in chars.iter().zip(&bytes) {
+        assert_eq!(id, byte_id);
+        let covered: String = char_vec[start..end].iter().collect();
+        if text.is_char_boundary(byte_start) && text.is_char_boundary(byte_end) {
+            assert_eq!(covered, text[byte_start..byte_end]);
+        } else {
+            // 同一字符的字节标记都对应该字符
+            assert_eq!(end - start, 1);
+            assert!(covered == "世" || covered == "界");
+        }
+    }
+    assert!(chars.iter().any(|&(start, end, _)| (start, end) == (6, 7)));
+}
+
+/// 测试带偏移的编码结果，以及把原文标注区间对齐到标记
+#[test]
+fn test_bbpe_encode_with_offsets() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello hello world".to_string()], 270)
+        .unwrap();
+
+    let text = "hello world";
+    let encoding = tokenizer.encode_with_offsets(text).unwrap();
+    assert_eq!(encoding.ids, tokenizer.encode(text).unwrap());
+    assert_eq!(encoding.len(), encoding.tokens.len());
+    assert_eq!(encoding.len(), encoding.offsets.len());
+    for (token, &(start, end)) in encoding.tokens.iter().zip(&encoding.offsets) {
+        assert_eq!(token, &text[start..end]);
+    }
+
+    // "world" 在原文中的区间 6..11
+    let range = encoding.token_range(6, 11);
+    assert!(!range.is_empty());
+    let covered: String = encoding.tokens[range.clone()].concat();
+    assert!(covered.contains("world"));
+    assert_eq!(encoding.token_at(6), Some(range.start));
+    assert_eq!(encoding.token_at(text.len()), None);
+}
+
+/// 测试规范化改写文本时，带偏移的编码结果和字符区间仍按原文给出
+#[test]
+fn test_bbpe_offsets_with_normalizer() {
+    use zero_tokenizer::base::normalizer::Normalizer;
+    use zero_tokenizer::base::traits::OffsetUnit;
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer.base.normalizer = Normalizer::parse("nfkc,lowercase").unwrap();
+    tokenizer
+        .train(vec!["hello world café ".repeat(5)], 280)
+        .unwrap();
+
+    // 全角字母经NFKC变为半角，分解形式的 "é" 经NFKC组合
+    let text = "ＨＥＬＬＯ cafe\u{301}";
+    let encoding = tokenizer.encode_with_offsets(text).unwrap();
+    assert_eq!(encoding.ids, tokenizer.encode(text).unwrap());
+    assert!(encoding.offsets.iter().all(|&(start, end)| start < end));
+    assert!(encoding
+        .offsets
+        .windows(2)
+        .all(|pair| pair[0].1 == pair[1].0));
+    assert_eq!(encoding.offsets.first().unwrap().0, 0);
+    assert_eq!(encoding.offsets.last().unwrap().1, text.len());
+
+    // "ＨＥＬＬＯ" 对应的标记解码后为 "hello"
+    let hello = "ＨＥＬＬＯ".len();
+    let range = encoding.token_range(0, hello);
+    assert_eq!(encoding.tokens[range.clone()].concat(), "hello");
+    assert_eq!(encoding.offsets[range.end - 1].1, hello);
+
+    // 字符区间：原文共11个字符，"e\u{301}" 占两个字符
+    let chars = tokenizer.spans_in(text, OffsetUnit::Char).unwrap();
+    assert_eq!(chars.len(), encoding.len());
+    assert_eq!(chars.last().unwrap().1, text.chars().count());
+    assert_eq!(
+        chars
+            .iter()
+            .find(|&&(_, end, _)| end == 5)
+            .map(|&(start, ..)| start),
+        Some(0)
+    );
+}
+
+/// 测试找出无法单独解码为UTF-8的标记
+#[test]
+fn test_bbpe_audit_vocab() {
+    use zero_tokenizer::analysis::Utf8Fragment;
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    let audit = tokenizer.audit_vocab();
+    // 单字节标记中 0x80..=0xFF 都无法单独解码
+    assert_eq!(audit.vocab_size, 256);
+    assert_eq!(audit.unsafe_count(), 128);
+    assert!(audit.is_utf8_safe(u32::from(b'a')));
+    let lead = tokenizer.encode("你").unwrap()[0];
+    assert_eq!(audit.fragment(lead), Some(Utf8Fragment::Truncated));
+
+    tokenizer
+        .train(vec!["你好 你好 你好".to_string()], 270)
+        .unwrap();
+    let audit = tokenizer.audit_vocab();
+    let nihao = tokenizer.encode("你好").unwrap();
+    assert_eq!(nihao.len(), 1);
+    assert!(audit.is_utf8_safe(nihao[0]));
+    // 训练过程中产生的中间标记是字符片段
+    assert!(audit.unsafe_count() > 128);
+    for token in &audit.unsafe_tokens {
+        assert!(std::str::from_utf8(&token.bytes).is_err());
+    }
+
+    assert_eq!(Utf8Fragment::classify("好".as_bytes()), None);
+    assert_eq!(
+        Utf8Fragment::classify(&"好".as_bytes()[1..]),
+        Some(Utf8Fragment::Continuation)
+    );
+    assert_eq!(
+        Utf8Fragment::classify(&"你好".as_bytes()[2..4]),
+        Some(Utf8Fragment::Both)
+    );
+    assert_eq!(Utf8Fragment::classify(&[0xFF]), Some(Utf8Fragment::Invalid));
+}
+
+/// 测试按使用频次蒸馏出更小的词汇表
+#[test]
+fn test_bbpe_distill() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(
+            vec!["hello hello hello world world tokenizer tokenizer".to_string()],
+            300,
+        )
+        .unwrap();
+    let large_size = tokenizer.vocab_size();
+
+    let corpus = ["hello hello hello", "hello there"];
+    let (small, manifest) = tokenizer.distill(corpus, 270).unwrap();
+    assert_eq!(small.vocab_size(), 270);
+    assert!(large_size > 270);
+    assert_eq!(manifest.old_vocab_size, large_size);
+    assert_eq!(manifest.new_vocab_size, 270);
+    assert_eq!(manifest.mapping.len(), 270);
+    assert_eq!(manifest.removed.len(), large_size - 270);
+    assert!(manifest.added.is_empty());
+
+    // 合并表只引用保留的标记
+    for (&(left, right), &id) in &small.merges {
+        assert!(left < 270 && right < 270 && id < 270);
+        assert!(left < id && right < id);
+    }
+
+    // 语料中的高频词仍是单个标记，且ID映射一致
+    let old_hello = tokenizer.encode("hello").unwrap();
+    assert_eq!(old_hello.len(), 1);
+    assert_eq!(
+        small.encode("hello").unwrap(),
+        vec![manifest.get(old_hello[0]).unwrap()]
+    );
+
+    // 蒸馏后仍能无损编码任意文本
+    for text in ["tokenizer world", "未见过的文本"] {
+        let ids = small.encode(text).unwrap();
+        assert_eq!(small.decode(&ids).unwrap(), text);
+    }
+
+    assert!(tokenizer.distill(corpus, 10).is_err());
+}
+
+/// 测试领域适配只追加新标记，已有ID保持不变
+#[test]
+fn test_bbpe_adapt_keeps_existing_ids() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello world world".to_string()], 262)
+        .unwrap();
+    let before_size = tokenizer.vocab_size();
+    let before_vocab = tokenizer.vocab.id_map().clone();
+    let before_merges = tokenizer.merges.clone();
+    let hello = tokenizer.encode("hello").unwrap();
+
+    let domain = ["kinase kinase kinase phosphorylation phosphorylation"];
+    let before_domain = tokenizer.encode(domain[0]).unwrap().len();
+    let manifest = tokenizer.adapt(domain, 10).unwrap();
+
+    assert_eq!(manifest.added.len(), 10);
+    assert!(manifest.removed.is_empty());
+    assert_eq!(manifest.mapping.len(), before_size);
+    assert_eq!(tokenizer.vocab_size(), before_size + 10);
+    for (id, bytes) in &before_vocab {
+        assert_eq!(tokenizer.vocab.get_by_id(id), Some(bytes));
+    }
+    for (pair, id) in &before_merges {
+        assert_eq!(tokenizer.merges.get(pair), Some(id));
+    }
+    // 新标记排在已有ID之后
+    let max_old = *before_vocab.keys().max().unwrap();
+    assert!(manifest.added.iter().all(|&id| id > max_old));
+
+    assert_eq!(tokenizer.encode("hello").unwrap(), hello);
+    assert!(tokenizer.encode(domain[0]).unwrap().len() < before_domain);
+    assert_eq!(
+        tokenizer
+            .decode(&tokenizer.encode(domain[0]).unwrap())
+            .unwrap(),
+        domain[0]
+    );
+}
+
+/// 测试用GPT-2字节映射和转义两种方式渲染词汇表
+#[test]
+fn test_bbpe_vocab_strings() {
+    use zero_tokenizer::bbpe::byte_level::{byte_level_to_bytes, byte_to_char, char_to_byte};
+    use zero_tokenizer::bbpe::VocabStringStyle;
+
+    // 映射是256个不同字符之间的双射
+    let mapped: std::collections::HashSet<char> = (0..=255u8).map(byte_to_char).collect();
+    assert_eq!(mapped.len(), 256);
+    assert!((0..=255u8).all(|byte| char_to_byte(byte_to_char(byte)) == Some(byte)));
+    assert_eq!(byte_to_char(b' '), 'Ġ');
+    assert_eq!(byte_to_char(b'\n'), 'Ċ');
+    assert_eq!(byte_to_char(b'a'), 'a');
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello hello 你好 你好".to_string()], 270)
+        .unwrap();
+
+    let strings = tokenizer.vocab_strings(VocabStringStyle::ByteLevel);
+    assert_eq!(strings.len(), tokenizer.vocab_size());
+    assert!(strings.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    for (id, text) in &strings {
+        let bytes = tokenizer.token_bytes(id).unwrap();
+        assert_eq!(byte_level_to_bytes(text).as_deref(), Some(bytes.as_ref()));
+    }
+
+    let escaped = tokenizer.vocab_strings(VocabStringStyle::Escaped);
+    let lead = tokenizer.encode("你").unwrap()[0];
+    let (_, text) = escaped.iter().find(|(id, _)| *id == lead).unwrap();
+    assert_eq!(text, "\\xE4");
+}
+
+/// 测试BPE-dropout：切分会变化、解码无损，固定种子时可复现
+#[test]
+fn test_bbpe_encode_with_dropout() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    let text = "hello world hello world";
+    tokenizer.train(vec![text.repeat(10)], 300).unwrap();
+    let encoded = tokenizer.encode(text).unwrap();
+
+    assert_eq!(
+        tokenizer.encode_with_dropout(text, 0.0, None).unwrap(),
+        encoded
+    );
+    // 全部丢弃时退化为字节序列
+    let bytes = tokenizer.encode_with_dropout(text, 1.0, None).unwrap();
+    assert_eq!(bytes.len(), text.len());
+
+    let first = tokenizer.encode_with_dropout(text, 0.5, Some(42)).unwrap();
+    assert_eq!(
+        tokenizer.encode_with_dropout(text, 0.5, Some(42)).unwrap(),
+        first
+    );
+    assert_eq!(tokenizer.decode(&first).unwrap(), text);
+    let variants: std::collections::HashSet<Vec<u32>> = (0..20)
+        .map(|seed| {
+            tokenizer
+                .encode_with_dropout(text, 0.5, Some(seed))
+                .unwrap()
+        })
+        .collect();
+    assert!(variants.len() > 1);
+
+    let batch = tokenizer
+        .encode_batch_with_dropout(&[text, text], 0.5, Some(42))
+        .unwrap();
+    assert_eq!(batch[0], first);
+    assert_eq!(
+        batch[1],
+        tokenizer.encode_with_dropout(text, 0.5, Some(43)).unwrap()
+    );
+
+    assert!(tokenizer.encode_with_dropout(text, 1.5, None).is_err());
+}
+
+/// 测试在字节标记之后预留特殊标记ID，训练后再分配
+#[test]
+fn test_bbpe_reserve_special_tokens() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    let reserved = tokenizer.reserve_special_tokens(4).unwrap();
+    assert_eq!(reserved, 256..260);
+
+    tokenizer
+        .train(vec!["hello hello hello world".to_string()], 270)
+        .unwrap();
+    assert_eq!(tokenizer.vocab_size(), 270);
+    // 合并规则产生的ID都在预留区间之后
+    assert!(tokenizer.merges.values().all(|&id| id >= reserved.end));
+    assert!(tokenizer.reserve_special_tokens(1).is_err());
+
+    let start = tokenizer.assign_special_token("<|im_start|>").unwrap();
+    let end = tokenizer.assign_special_token("<|im_end|>").unwrap();
+    assert_eq!((start, end), (256, 257));
+    assert_eq!(tokenizer.assign_special_token("<|im_start|>").unwrap(), 256);
+    assert_eq!(tokenizer.reserved_special_token_ids(), vec![258, 259]);
+    // 词汇表中的普通标记不能分配为特殊标记，也不占用预留位置
+    let err = tokenizer.assign_special_token("h").unwrap_err();
+    assert!(err.to_string().contains("普通标记"), "{}", err);
+    assert!(!tokenizer.special_tokens().contains_id(u32::from(b'h')));
+    assert_eq!(tokenizer.reserved_special_token_ids(), vec![258, 259]);
+    assert_eq!(tokenizer.decode(&[start]).unwrap(), "<|im_start|>");
+
+    tokenizer.assign_special_token("<|a|>").unwrap();
+    tokenizer.assign_special_token("<|b|>").unwrap();
+    assert!(tokenizer.assign_special_token("<|c|>").is_err());
+}
+
+/// 测试新增标记写入追加式日志，加载时自动重放
+#[test]
+fn test_bbpe_append_journal() {
+    use zero_tokenizer::bbpe::journal_path;
+
+    let path = &temp_path("bbpe_journal.model");
+    let journal = journal_path(path);
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer.reserve_special_tokens(2).unwrap();
+    tokenizer
+        .train(vec!["hello hello world world".to_string()], 266)
+        .unwrap();
+    // 新训练的分词器没有对应的模型文件
+    assert!(tokenizer.append_journal(path).is_err());
+    tokenizer.save(path).unwrap();
+    let model_len = std::fs::metadata(path).unwrap().len();
+
+    let mut adapted = zero_tokenizer::prelude::bbpe().unwrap();
+    adapted.load(path).unwrap();
+    assert_eq!(adapted.append_journal(path).unwrap(), 0);
+    let manifest = adapted
+        .adapt(["kinase kinase kinase phosphorylation"], 5)
+        .unwrap();
+    let im_start = adapted.assign_special_token("<|im_start|>").unwrap();
+    let written = adapted.append_journal(path).unwrap();
+    // 每个新标记一条词汇表记录和一条合并规则，特殊标记另有一条注册记录
+    assert_eq!(written, manifest.added.len() * 2 + 2);
+    // 模型文件没有被重写
+    assert_eq!(std::fs::metadata(path).unwrap().len(), model_len);
+    assert_eq!(adapted.append_journal(path).unwrap(), 0);
+
+    let text = "<|im_start|>kinase phosphorylation hello";
+    let mut loaded = zero_tokenizer::prelude::bbpe().unwrap();
+    loaded.load(path).unwrap();
+    assert_eq!(loaded.vocab.id_map(), adapted.vocab.id_map());
+    assert_eq!(loaded.merges, adapted.merges);
+    assert_eq!(loaded.encode(text).unwrap(), adapted.encode(text).unwrap());
+    assert_eq!(loaded.decode(&[im_start]).unwrap(), "<|im_start|>");
+
+    // 重复和末尾不完整的记录不影响结果
+    let mut data = std::fs::read(&journal).unwrap();
+    data.extend_from_within(..);
+    data.extend_from_slice(b"merge: 1 2");
+    let mut replayed = zero_tokenizer::prelude::bbpe().unwrap();
+    replayed
+        .load_from_bytes(&std::fs::read(path).unwrap())
+        .unwrap();
+    assert_eq!(replayed.replay_journal(&data).unwrap(), written * 2);
+    assert_eq!(replayed.merges, adapted.merges);
+
+    // 完整保存后旧日志被删除
+    loaded.save(path).unwrap();
+    assert!(!std::path::Path::new(&journal).exists());
+    let mut reloaded = zero_tokenizer::prelude::bbpe().unwrap();
+    reloaded.load(path).unwrap();
+    assert_eq!(
+        reloaded.encode(text).unwrap(),
+        adapted.encode(text).unwrap()
+    );
+    std::fs::remove_file(path).ok();
+}
+
+/// 按等级逐个合并的参考实现
+fn naive_ranked_merges(ids: &mut Vec<u32>, merges: &MergeMap) {
+    while let Some((_, i, new_id)) = ids
+        .windows(2)
+        .enumerate()
+        .filter_map(|(i, pair)| {
+            let (rank, _, &new_id) = merges.get_full(&(pair[0], pair[1]))?;
+            Some((rank, i, new_id))
+        })
+        .min()
+    {
+        ids[i] = new_id;
+        ids.remove(i + 1);
+    }
+}
+
+/// 测试编码时先合并等级最高的词对，而不是按扫描顺序合并
+#[test]
+fn test_bbpe_encode_applies_merges_by_rank() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    let (h, e, l) = (u32::from(b'h'), u32::from(b'e'), u32::from(b'l'));
+    // (e, l) 先学到，等级高于 (h, e)
+    tokenizer.merges.insert((e, l), 300);
+    tokenizer.merges.insert((h, e), 301);
+    assert_eq!(tokenizer.encode("hel").unwrap(), [h, 300]);
+
+    // 等级相同的重叠词对先合并最左边的一处
+    let a = u32::from(b'a');
+    tokenizer.merges.insert((a, a), 302);
+    assert_eq!(tokenizer.encode("aaa").unwrap(), [302, a]);
+
+    // 等级由插入顺序决定，与新标记ID的大小无关
+    let (x, y, z) = (u32::from(b'x'), u32::from(b'y'), u32::from(b'z'));
+    tokenizer.merges.insert((y, z), 401);
+    tokenizer.merges.insert((x, y), 400);
+    assert_eq!(tokenizer.encode("xyz").unwrap(), [x, 401]);
+}
+
+/// 测试长片段使用优先队列时与逐轮扫描的结果一致
+#[test]
+fn test_apply_ranked_merges_long_pieces() {
+    use rand::{Rng, SeedableRng};
+    use zero_tokenizer::base::tokenizer_base::apply_ranked_merges;
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let mut merges = MergeMap::new();
+    let mut next_id = 10;
+    for _ in 0..40 {
+        let pair = (rng.gen_range(0..next_id), rng.gen_range(0..next_id));
+        if !merges.contains_key(&pair) {
+            merges.insert(pair, next_id);
+            next_id += 1;
+        }
+    }
+    // 等级与新标记ID的顺序无关
+    merges.reverse();
+
+    for len in [0, 1, 2, 31, 32, 33, 200, 1000] {
+        let ids: Vec<u32> = (0..len).map(|_| rng.gen_range(0..10)).collect();
+        let mut expected = ids.clone();
+        naive_ranked_merges(&mut expected, &merges);
+        let mut actual = ids;
+        apply_ranked_merges(&mut actual, &merges);
+        assert_eq!(actual, expected, "长度 {}", len);
+    }
+}
+
+/// 测试只加载词汇表或只加载合并规则的部分加载模式
+#[test]
+fn test_bbpe_load_parts() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello world world 你好 你好".to_string()], 300)
+        .unwrap();
+    tokenizer.add_special_tokens(&["<|eot|>"]).unwrap();
+    let text = "hello 你好<|eot|>";
+    let ids = tokenizer.encode(text).unwrap();
+
+    let path = &temp_path("bbpe_load_parts.model");
+    tokenizer.save(path).unwrap();
+
+    let mut decoder = zero_tokenizer::prelude::bbpe().unwrap();
+    decoder.load_parts(path, ModelParts::DecodeOnly).unwrap();
+    assert!(decoder.merges.is_empty());
+    assert_eq!(decoder.vocab_size(), tokenizer.vocab_size());
+    assert_eq!(decoder.decode(&ids).unwrap(), text);
+    assert!(decoder.encode(text).is_err());
+    assert!(decoder.save(path).is_err());
+
+    let mut encoder = zero_tokenizer::prelude::bbpe().unwrap();
+    encoder.load_parts(path, ModelParts::EncodeOnly).unwrap();
+    std::fs::remove_file(path).ok();
+    assert_eq!(encoder.merges, tokenizer.merges);
+    // 只保留256个单字节标记和特殊标记
+    assert_eq!(encoder.vocab_size(), 257);
+    assert_eq!(encoder.encode(text).unwrap(), ids);
+    assert_eq!(encoder.next_token_id, tokenizer.next_token_id);
+
+    assert!(matches!(
+        ModelParts::parse("encode_only"),
+        Ok(ModelParts::EncodeOnly)
+    ));
+    assert!(ModelParts::parse("merges").is_err());
+}
+
+/// 测试不同合并路径得到相同字节序列时复用已有ID，词汇表双向映射保持一致
+#[test]
+fn test_bbpe_merge_reuses_existing_id() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    let text = "aa aba aaabab ba baaaa aabaaaa babab aaaabab";
+    tokenizer.train(vec![text.to_string()], 280).unwrap();
+
+    // 这份语料中至少有两条合并规则产生同一个标记
+    let targets: std::collections::HashSet<u32> = tokenizer.merges.values().copied().collect();
+    assert!(targets.len() < tokenizer.merges.len());
+    assert_eq!(tokenizer.vocab_size(), 256 + targets.len());
+
+    for (&(left, right), id) in &tokenizer.merges {
+        let mut merged = tokenizer.vocab.get_by_id(&left).unwrap().clone();
+        merged.extend(tokenizer.vocab.get_by_id(&right).unwrap());
+        assert_eq!(tokenizer.vocab.get_by_id(id), Some(&merged));
+        assert_eq!(tokenizer.vocab.get_by_value(&merged), Some(id));
+    }
+
+    let ids = tokenizer.encode(text).unwrap();
+    assert_eq!(tokenizer.decode(&ids).unwrap(), text);
+}
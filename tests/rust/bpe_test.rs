@@ -2,12 +2,11 @@
 //!
 //! 这个文件包含BPE分词器的特定功能测试，不包含与correctness_test.rs重复的正确性测试。
 
-#[cfg(feature = "python")]
 use zero_tokenizer::prelude::*;
 mod test_utils;
+use test_utils::temp_path;
 
 /// 测试BPE分词器的训练功能
-#[cfg(feature = "python")]
 #[test]
 fn test_bpe_tokenizer() {
     let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
@@ -20,7 +19,6 @@ fn test_bpe_tokenizer() {
 }
 
 /// 测试BPE编码和解码的无损性
-#[cfg(feature = "python")]
 #[test]
 fn test_bpe_encode_decode() {
     let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
@@ -30,7 +28,6 @@ fn test_bpe_encode_decode() {
 }
 
 /// 测试BPE默认构造
-#[cfg(feature = "python")]
 #[test]
 fn test_bpe_default() {
     let tokenizer = zero_tokenizer::prelude::bpe().unwrap();
@@ -39,7 +36,6 @@ fn test_bpe_default() {
 }
 
 /// 测试BPE词对生成功能
-#[cfg(feature = "python")]
 #[test]
 fn test_word_pairs() {
     let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
@@ -54,7 +50,6 @@ fn test_word_pairs() {
 }
 
 /// 测试BPE词对合并功能
-#[cfg(feature = "python")]
 #[test]
 fn test_word_merge_pair() {
     let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
@@ -67,3 +62,259 @@ fn test_word_merge_pair() {
     let word = tokenizer.encode(text).unwrap();
     assert!(!word.is_empty());
 }
+
+/// 测试未见过的字符按字节回退或映射为未知标记
+#[test]
+fn test_bpe_unknown_fallback() {
+    use zero_tokenizer::bpe::{UnknownCharFallback, UNK_TOKEN};
+
+    let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
+    tokenizer
+        .train(vec!["hello world 你好".to_string()], 300)
+        .unwrap();
+    assert_eq!(
+        tokenizer.unknown_fallback,
+        UnknownCharFallback::ByteFallback
+    );
+
+    // 训练语料中没有的字符拆成词汇表内的字节标记，可以无损解码
+    let text = "hello 世界😀";
+    let ids = tokenizer.encode(text).unwrap();
+    assert!(ids.iter().all(|id| tokenizer.vocab.contains_id(id)));
+    assert_eq!(tokenizer.decode(&ids).unwrap(), text);
+    let fallback = ids
+        .iter()
+        .filter(|id| tokenizer.is_fallback_token(id))
+        .count();
+    assert_eq!(fallback, "世界😀".len());
+
+    // 切换为未知标记后，每个未见过的字符对应一个 <unk>
+    tokenizer.set_unknown_fallback(UnknownCharFallback::Unk);
+    let unk = *tokenizer
+        .vocab
+        .get_by_value(&UNK_TOKEN.to_string())
+        .unwrap();
+    let ids = tokenizer.encode("你世界").unwrap();
+    assert_eq!(ids[1..], [unk, unk]);
+    assert!(ids.iter().all(|id| tokenizer.vocab.contains_id(id)));
+
+    // 回退方式随模型保存
+    let path = &temp_path("bpe_unknown_fallback.model");
+    tokenizer.save(path).unwrap();
+    let mut loaded = zero_tokenizer::prelude::bpe().unwrap();
+    loaded.load(path).unwrap();
+    std::fs::remove_file(path).ok();
+    assert_eq!(loaded.unknown_fallback, UnknownCharFallback::Unk);
+    assert_eq!(loaded.encode("你世界").unwrap(), ids);
+
+    // 未训练且没有回退标记时返回错误，而不是输出词汇表之外的ID
+    let untrained = zero_tokenizer::prelude::bpe().unwrap();
+    assert!(untrained.encode("世界").is_err());
+}
+
+/// 测试组合形式和分解形式的文本在NFC规范化后得到相同的ID
+#[test]
+fn test_bpe_nfc_normalization() {
+    use zero_tokenizer::base::normalizer::{Normalizer, NormalizerStep};
+
+    let composed = "café crème";
+    let decomposed = "cafe\u{301} cre\u{300}me";
+
+    let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
+    assert_eq!(tokenizer.base.normalizer.steps(), [NormalizerStep::Nfc]);
+    tokenizer
+        .train(vec![decomposed.repeat(5), composed.repeat(5)], 300)
+        .unwrap();
+    assert_eq!(
+        tokenizer.encode(composed).unwrap(),
+        tokenizer.encode(decomposed).unwrap()
+    );
+    // 训练时分解形式也被规范化，词汇表中没有单独的组合附加符号
+    assert!(tokenizer
+        .vocab
+        .get_by_value(&"\u{301}".to_string())
+        .is_none());
+
+    let mut raw = zero_tokenizer::prelude::bpe().unwrap();
+    raw.base.normalizer = Normalizer::new();
+    raw.train(vec![decomposed.repeat(5), composed.repeat(5)], 300)
+        .unwrap();
+    assert_ne!(
+        raw.encode(composed).unwrap(),
+        raw.encode(decomposed).unwrap()
+    );
+
+    // 旧模型单独记录的 `normalization: nfc` 加载后并入规范化流水线
+    let path = &temp_path("bpe_legacy_nfc.model");
+    tokenizer.save(path).unwrap();
+    let content = std::fs::read_to_string(path).unwrap();
+    assert!(!content.contains("\nnormalization: "));
+    let legacy: String = content
+        .lines()
+        .filter(|line| !line.starts_with("normalizer: ") && !line.starts_with("sections: "))
+        .map(|line| format!("{}\n", line))
+        .chain(["normalization: nfc\n".to_string()])
+        .collect();
+    std::fs::write(path, legacy).unwrap();
+    let mut loaded = zero_tokenizer::prelude::bpe().unwrap();
+    loaded.load(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(loaded.base.normalizer.steps(), [NormalizerStep::Nfc]);
+    assert_eq!(
+        loaded.encode(decomposed).unwrap(),
+        tokenizer.encode(composed).unwrap()
+    );
+}
+
+#[test]
+fn test_bpe_strict_decode() {
+    use zero_tokenizer::bpe::DecodeMode;
+
+    let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
+    tokenizer
+        .train(vec!["hello world".to_string()], 280)
+        .unwrap();
+    assert_eq!(tokenizer.decode_mode, DecodeMode::Strict);
+
+    let mut ids = Tokenizer::encode(&tokenizer, "hello").unwrap();
+    let unknown = 0x4E16;
+    assert!(!tokenizer.vocab.contains_id(&unknown));
+    ids.push(unknown);
+
+    // 严格模式报出第一个未知ID及其位置
+    let error = Tokenizer::decode(&tokenizer, &ids).unwrap_err().to_string();
+    assert!(error.contains(&unknown.to_string()), "{}", error);
+    assert!(
+        error.contains(&format!("第 {} 个标记", ids.len() - 1)),
+        "{}",
+        error
+    );
+
+    // 宽松模式按码点解码
+    tokenizer.set_decode_mode(DecodeMode::Lenient);
+    assert_eq!(Tokenizer::decode(&tokenizer, &ids).unwrap(), "hello世");
+}
+
+/// 测试重新编号后ID连续，合并规则和切分结果保持不变
+#[test]
+fn test_bpe_compact_ids() {
+    let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
+    tokenizer
+        .train(vec!["你好世界 你好 hello hello".to_string()], 300)
+        .unwrap();
+    let text = "你好世界 hello";
+    let before = tokenizer.encode(text).unwrap();
+    let vocab_size = tokenizer.vocab_size();
+    assert!(tokenizer.vocab.ids().any(|&id| id as usize >= vocab_size));
+
+    let manifest = tokenizer.compact_ids();
+    assert_eq!(tokenizer.vocab_size(), vocab_size);
+    assert_eq!(tokenizer.next_token_id as usize, vocab_size);
+    assert!((0..vocab_size as u32).all(|id| tokenizer.vocab.contains_id(&id)));
+    assert!(manifest.added.is_empty() && manifest.removed.is_empty());
+    // 码点0-255的基础字符ID不变
+    assert!((0..256).all(|id| manifest.get(id) == Some(id)));
+
+    let after = tokenizer.encode(text).unwrap();
+    let remapped: Vec<u32> = before.iter().map(|&id| manifest.get(id).unwrap()).collect();
+    assert_eq!(after, remapped);
+    assert_eq!(tokenizer.decode(&after).unwrap(), text);
+
+    // 已经连续时重新编号不改变任何ID
+    assert!(tokenizer.compact_ids().is_identity());
+
+    let path = &temp_path("bpe_compact_ids.model");
+    tokenizer.save(path).unwrap();
+    let mut loaded = zero_tokenizer::prelude::bpe().unwrap();
+    loaded.load(path).unwrap();
+    std::fs::remove_file(path).ok();
+    assert_eq!(loaded.encode(text).unwrap(), after);
+}
+
+/// 测试继续训练保留已有ID和合并规则，只追加新标记
+#[test]
+fn test_bpe_adapt_keeps_existing_ids() {
+    let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello world world".to_string()], 262)
+        .unwrap();
+    let before_size = tokenizer.vocab_size();
+    let before_vocab = tokenizer.vocab.id_map().clone();
+    let before_merges = tokenizer.merges.clone();
+    let hello = tokenizer.encode("hello").unwrap();
+
+    let domain = ["kinase kinase kinase phosphorylation phosphorylation"];
+    let before_domain = tokenizer.encode(domain[0]).unwrap().len();
+    let manifest = tokenizer.adapt(domain, 10).unwrap();
+
+    assert_eq!(manifest.added.len(), 10);
+    assert!(manifest.removed.is_empty());
+    assert_eq!(tokenizer.vocab_size(), before_size + 10);
+    for (id, text) in &before_vocab {
+        assert_eq!(tokenizer.vocab.get_by_id(id), Some(text));
+    }
+    for (pair, id) in &before_merges {
+        assert_eq!(tokenizer.merges.get(pair), Some(id));
+    }
+    let max_old = *before_vocab.keys().max().unwrap();
+    assert!(manifest.added.iter().all(|&id| id > max_old));
+
+    assert_eq!(tokenizer.encode("hello").unwrap(), hello);
+    let ids = tokenizer.encode(domain[0]).unwrap();
+    assert!(ids.len() < before_domain);
+    assert_eq!(tokenizer.decode(&ids).unwrap(), domain[0]);
+}
+
+/// 测试BPE预置码点0-255，训练目标包含这256个基础字符，合并结果从256开始分配
+#[test]
+fn test_bpe_seeded_code_points() {
+    let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
+    assert_eq!(tokenizer.vocab_size(), 256);
+    assert_eq!(tokenizer.next_token_id, 256);
+    for id in 0..256u32 {
+        let expected = char::from_u32(id).unwrap().to_string();
+        assert_eq!(tokenizer.vocab.get_by_id(&id), Some(&expected));
+    }
+
+    tokenizer
+        .train(vec!["hello hello world world".to_string()], 262)
+        .unwrap();
+    let merged: std::collections::BTreeSet<u32> = tokenizer.merges.values().copied().collect();
+    assert_eq!(merged, (256..262).collect());
+    for id in 0..256u32 {
+        let expected = char::from_u32(id).unwrap().to_string();
+        assert_eq!(tokenizer.vocab.get_by_id(&id), Some(&expected));
+    }
+
+    // 码点0-255以外的字符在训练时按码点加入
+    tokenizer.train(vec!["你好 你好".to_string()], 257).unwrap();
+    assert_eq!(
+        tokenizer.vocab.get_by_value(&"你".to_string()),
+        Some(&('你' as u32))
+    );
+}
+
+/// 测试不同合并路径得到相同文本时复用已有ID，词汇表双向映射保持一致
+#[test]
+fn test_bpe_merge_reuses_existing_id() {
+    let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
+    let text = "aa aba aaabab ba baaaa aabaaaa babab aaaabab";
+    tokenizer.train(vec![text.to_string()], 280).unwrap();
+
+    // 这份语料中至少有两条合并规则产生同一个标记
+    let targets: std::collections::HashSet<u32> = tokenizer.merges.values().copied().collect();
+    assert!(targets.len() < tokenizer.merges.len());
+
+    for (&(left, right), id) in &tokenizer.merges {
+        let merged = format!(
+            "{}{}",
+            tokenizer.vocab.get_by_id(&left).unwrap(),
+            tokenizer.vocab.get_by_id(&right).unwrap()
+        );
+        assert_eq!(tokenizer.vocab.get_by_id(id), Some(&merged));
+        assert_eq!(tokenizer.vocab.get_by_value(&merged), Some(id));
+    }
+
+    let ids = tokenizer.encode(text).unwrap();
+    assert_eq!(tokenizer.decode(&ids).unwrap(), text);
+}
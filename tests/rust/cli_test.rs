@@ -0,0 +1,81 @@
+//! 命令行工具测试
+//!
+//! 通过管道向 `repl` 子命令输入命令和文本，检查各显示方式的输出
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::{corpus, temp_path};
+
+/// 以 `input` 为标准输入运行 `zero-tokenizer repl`
+fn run_repl(model: &str, input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_zero-tokenizer"))
+        .args(["repl", model, "--kind", "bbpe"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// 测试 `:ids`、`:tokens`、`:both` 三种显示方式
+#[test]
+fn test_repl_display_modes() {
+    let path = temp_path("cli_repl.model");
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.train(corpus(), 280).unwrap();
+    tokenizer.save(&path).unwrap();
+
+    let text = "hello world";
+    let ids = tokenizer.encode(text).unwrap();
+    let id_line = format!("{:?}", ids);
+    let tokens: Vec<String> = ids.iter().map(|id| tokenizer.render_token(id)).collect();
+    let token_line = format!("|{}|", tokens.join("|"));
+
+    let input = format!(":ids\n{text}\n:tokens\n{text}\n:both\n{text}\n:quit\n");
+    let output = run_repl(&path, input.as_bytes());
+    let _ = std::fs::remove_file(&path);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "{}", stdout);
+
+    // 每个提示符之后是上一行输入的输出，第一段是加载信息
+    let replies: Vec<Vec<&str>> = stdout
+        .split("> ")
+        .skip(1)
+        .map(|reply| reply.lines().collect())
+        .collect();
+    let summary = format!(
+        "标记: {}  字节: {}  字符: {}",
+        ids.len(),
+        text.len(),
+        text.len()
+    );
+    assert_eq!(replies.len(), 7, "{}", stdout);
+    assert_eq!(replies[1], [summary.as_str(), id_line.as_str()]);
+    assert_eq!(replies[3], [summary.as_str(), token_line.as_str()]);
+    assert_eq!(
+        replies[5],
+        [summary.as_str(), id_line.as_str(), token_line.as_str()]
+    );
+    for command in [0, 2, 4] {
+        assert!(replies[command].is_empty(), "{}", stdout);
+    }
+}
+
+/// 测试读取标准输入失败时以IO错误退出
+#[test]
+fn test_repl_stdin_error() {
+    let path = temp_path("cli_repl_error.model");
+    bbpe().unwrap().save(&path).unwrap();
+
+    let output = run_repl(&path, b"hello\n\xff\xfe\n");
+    let _ = std::fs::remove_file(&path);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("IO错误"), "{}", stderr);
+}
@@ -0,0 +1,174 @@
+//! 解码器测试
+//!
+//! 测试各内置解码器的拼接规则、四种分词器按解码器解码，以及解码器随模型保存和加载
+
+use zero_tokenizer::base::decoder::DecodedPiece;
+use zero_tokenizer::base::hf_json::HfTokenizerJson;
+use zero_tokenizer::bbpe::byte_level::bytes_to_byte_level;
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::{corpus, temp_path};
+
+fn decode(decoder: DecoderKind, pieces: &[DecodedPiece<'_>]) -> String {
+    decoder.decode_pieces(pieces).unwrap()
+}
+
+/// 测试各内置解码器的拼接规则
+#[test]
+fn test_decoders() {
+    let vocab = |pieces: &[&'static str]| -> Vec<DecodedPiece<'static>> {
+        pieces
+            .iter()
+            .map(|&p| DecodedPiece::from_vocab(p))
+            .collect()
+    };
+
+    let pieces = vocab(&["Hello", "Ġworld", "<0x21>"]);
+    assert_eq!(decode(DecoderKind::Fuse, &pieces), "HelloĠworld!");
+    assert_eq!(decode(DecoderKind::ByteLevel, &pieces), "Hello world!");
+    // 原始字节不按字节映射还原
+    assert_eq!(
+        decode(DecoderKind::ByteLevel, &[DecodedPiece::raw("é".as_bytes())]),
+        "é"
+    );
+
+    let pieces = vocab(&["▁Hello", "▁wor", "ld", "▁"]);
+    assert_eq!(decode(DecoderKind::Metaspace, &pieces), "Hello world ");
+
+    let pieces = vec![
+        DecodedPiece::from_vocab("it"),
+        DecodedPiece::from_vocab("'s"),
+        DecodedPiece::from_vocab("un"),
+        DecodedPiece::from_vocab("aff").with_continuation(true),
+        DecodedPiece::from_vocab("able").with_continuation(true),
+        DecodedPiece::from_vocab("!"),
+    ];
+    assert_eq!(decode(DecoderKind::WordPiece, &pieces), "it's unaffable!");
+    assert_eq!(decode(DecoderKind::Fuse, &pieces), "it'sunaffable!");
+
+    assert!(DecoderKind::Fuse
+        .decode_pieces(&vocab(&["<0xE4>"]))
+        .is_err());
+}
+
+/// 测试名称解析
+#[test]
+fn test_names() {
+    for kind in DecoderKind::ALL {
+        assert_eq!(DecoderKind::from_name(kind.name()).unwrap(), kind);
+    }
+    assert_eq!(DecoderKind::default(), DecoderKind::Fuse);
+    assert!(DecoderKind::from_name("bert")
+        .unwrap_err()
+        .to_string()
+        .contains("bert"));
+}
+
+/// 测试四种分词器按解码器解码
+#[test]
+fn test_tokenizers_use_decoder() {
+    // BERT风格：预分词丢弃空格，WordPiece解码器在词之间补空格
+    let mut wordpiece = wordpiece().unwrap();
+    wordpiece.base.pre_tokenizer = PreTokenizerPipeline::parse("whitespace,punctuation").unwrap();
+    let vocab_size = wordpiece.base.vocab.len() as u32 + 100;
+    wordpiece.train(corpus(), vocab_size).unwrap();
+    let ids = wordpiece
+        .encode("hello world, the world is round!")
+        .unwrap();
+    assert_eq!(
+        wordpiece.decode(&ids).unwrap(),
+        "helloworld,theworldisround!"
+    );
+    wordpiece.base.decoder = DecoderKind::WordPiece;
+    assert_eq!(
+        wordpiece.decode(&ids).unwrap(),
+        "hello world, the world is round!"
+    );
+    assert_eq!(wordpiece.decode_cow(&ids[..1]).unwrap(), "hello");
+
+    // 词汇表按GPT-2字节映射书写的字符级BPE
+    let mapped: Vec<String> = corpus()
+        .iter()
+        .map(|text| bytes_to_byte_level(text.as_bytes()))
+        .collect();
+    let mut bpe = bpe().unwrap();
+    bpe.train(mapped, 300).unwrap();
+    let ids = bpe.encode(&bytes_to_byte_level(b"hello world")).unwrap();
+    assert_eq!(bpe.decode(&ids).unwrap(), "helloĠworld");
+    bpe.base.decoder = DecoderKind::ByteLevel;
+    assert_eq!(bpe.decode(&ids).unwrap(), "hello world");
+
+    // SentencePiece风格：去掉开头的空格
+    let mut unigram = unigram().unwrap();
+    unigram.base.pre_tokenizer = PreTokenizerPipeline::parse("metaspace").unwrap();
+    unigram.train(corpus(), 60).unwrap();
+    unigram.base.decoder = DecoderKind::Metaspace;
+    let ids = unigram.encode(" hello world").unwrap();
+    assert_eq!(unigram.decode(&ids).unwrap(), "hello world");
+
+    // BBPE的标记是原始字节，ByteLevel解码器与直接拼接相同
+    let mut bbpe = bbpe().unwrap();
+    bbpe.train(corpus(), 300).unwrap();
+    bbpe.base.decoder = DecoderKind::ByteLevel;
+    let text = "héllo wörld";
+    assert_eq!(bbpe.decode(&bbpe.encode(text).unwrap()).unwrap(), text);
+}
+
+/// 测试文本格式和二进制格式保存后重新加载，解码器不变
+#[test]
+fn test_save_load_preserves_decoder() {
+    let mut tokenizer = unigram().unwrap();
+    tokenizer.train(corpus(), 60).unwrap();
+    tokenizer.base.decoder = DecoderKind::Metaspace;
+
+    let path = temp_path("decoder_text.model");
+    tokenizer.save(&path).unwrap();
+    let mut loaded = unigram().unwrap();
+    loaded.load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(loaded.base.decoder, DecoderKind::Metaspace);
+
+    let path = temp_path("decoder_binary.bin");
+    tokenizer.save_binary(&path).unwrap();
+    let mut loaded = unigram().unwrap();
+    loaded.load_binary(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(loaded.base.decoder, DecoderKind::Metaspace);
+
+    // 使用默认解码器的模型加载后恢复直接拼接
+    let plain = unigram().unwrap();
+    let path = temp_path("decoder_plain.model");
+    plain.save(&path).unwrap();
+    loaded.load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(loaded.base.decoder, DecoderKind::Fuse);
+}
+
+/// 测试tokenizer.json导出和加载解码器，Llama风格的 `Replace` 解码器识别为Metaspace
+#[test]
+fn test_hf_json_decoder() {
+    let mut tokenizer = wordpiece().unwrap();
+    let vocab_size = tokenizer.base.vocab.len() as u32 + 100;
+    tokenizer.train(corpus(), vocab_size).unwrap();
+    tokenizer.base.decoder = DecoderKind::WordPiece;
+    let json = tokenizer.to_hf_json().unwrap().to_json_string().unwrap();
+    let mut loaded = wordpiece().unwrap();
+    loaded
+        .load_hf_json(HfTokenizerJson::parse(&json).unwrap())
+        .unwrap();
+    assert_eq!(loaded.base.decoder, DecoderKind::WordPiece);
+
+    let mut root: serde_json::Value = serde_json::from_str(&json).unwrap();
+    root["decoder"] = serde_json::json!({
+        "type": "Sequence",
+        "decoders": [
+            { "type": "Replace", "pattern": { "String": "▁" }, "content": " " },
+            { "type": "ByteFallback" },
+            { "type": "Fuse" },
+            { "type": "Strip", "content": " ", "start": 1, "stop": 0 },
+        ],
+    });
+    let parsed = HfTokenizerJson::parse(&root.to_string()).unwrap();
+    assert_eq!(parsed.decoder, DecoderKind::Metaspace);
+}
@@ -45,7 +45,7 @@ fn test_repeated_training() {
 
     // 第一次训练
     tokenizer.train(vec!["Hello".to_string()], 300).unwrap();
-    let vocab_size_1 = tokenizer.vocab_size();
+    let _vocab_size_1 = tokenizer.vocab_size();
 
     // 第二次训练（应该重置并重新训练）
     tokenizer.train(vec!["World".to_string()], 300).unwrap();
@@ -163,7 +163,7 @@ fn test_newlines_and_tabs() {
 
 #[test]
 fn test_unigram_single_character() {
-    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    let tokenizer = zero_tokenizer::prelude::unigram().unwrap();
 
     // Unigram初始化时已有大量常用汉字
     let text = "测";
@@ -174,7 +174,7 @@ fn test_unigram_single_character() {
 
 #[test]
 fn test_wordpiece_single_character() {
-    let mut tokenizer = zero_tokenizer::prelude::wordpiece().unwrap();
+    let tokenizer = zero_tokenizer::prelude::wordpiece().unwrap();
 
     // WordPiece初始化时已有大量常用汉字
     let text = "测";
@@ -225,3 +225,44 @@ fn test_binary_like_text() {
     let decoded = tokenizer.decode(&tokens).unwrap();
     assert_eq!(decoded, text);
 }
+
+#[test]
+fn test_decode_cow_single_token() {
+    use std::borrow::Cow;
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello hello".to_string()], 300)
+        .unwrap();
+
+    let tokens = tokenizer.encode("hello").unwrap();
+    for &id in &tokens {
+        // 单个标记直接借用词汇表中的文本
+        let piece = tokenizer.decode_cow(&[id]).unwrap();
+        assert!(matches!(piece, Cow::Borrowed(_)));
+    }
+
+    let decoded = tokenizer.decode_cow(&tokens).unwrap();
+    assert_eq!(decoded, "hello");
+
+    // 不完整的多字节字符无法借用为字符串
+    let partial = tokenizer.encode("你").unwrap();
+    if partial.len() > 1 {
+        assert!(tokenizer.decode_cow(&partial[..1]).is_err());
+    }
+}
+
+#[test]
+fn test_decode_cow_unigram_and_wordpiece() {
+    let unigram = zero_tokenizer::prelude::unigram().unwrap();
+    let tokens = unigram.encode("abc").unwrap();
+    let decoded: String = tokens
+        .iter()
+        .map(|&id| unigram.decode_cow(&[id]).unwrap().into_owned())
+        .collect();
+    assert_eq!(decoded, "abc");
+
+    let wordpiece = zero_tokenizer::prelude::wordpiece().unwrap();
+    let tokens = wordpiece.encode("abc").unwrap();
+    assert_eq!(wordpiece.decode_cow(&tokens).unwrap(), "abc");
+}
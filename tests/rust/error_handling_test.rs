@@ -2,8 +2,12 @@
 //!
 //! 测试所有错误路径和异常情况
 
+use zero_tokenizer::error::TokenizerError;
 use zero_tokenizer::prelude::*;
 
+mod test_utils;
+use test_utils::temp_path;
+
 #[test]
 fn test_decode_invalid_token_id() {
     let tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
@@ -14,7 +18,12 @@ fn test_decode_invalid_token_id() {
 
     assert!(result.is_err());
     if let Err(e) = result {
-        // 验证错误消息包含相关信息
+        // 验证错误类型和错误消息包含相关信息
+        assert!(matches!(
+            e,
+            TokenizerError::UnknownTokenId { id: 999999, .. }
+        ));
+        let e = e.to_string();
         assert!(e.contains("未找到") || e.contains("not found") || e.contains("ID"));
     }
 }
@@ -28,6 +37,8 @@ fn test_train_with_invalid_vocab_size() {
     assert!(result.is_err());
 
     if let Err(e) = result {
+        assert!(matches!(e, TokenizerError::TrainingError { .. }));
+        let e = e.to_string();
         assert!(e.contains("256") || e.contains("至少") || e.contains("vocab"));
     }
 }
@@ -72,16 +83,17 @@ fn test_decode_empty_tokens() {
     assert_eq!(text, "");
 }
 
-#[cfg(feature = "python")]
 #[test]
 fn test_bpe_invalid_pattern() {
     use zero_tokenizer::bpe::Tokenizer;
 
     // 无效的正则表达式
-    let result = Tokenizer::with_pattern("[invalid(".to_string());
+    let result = Tokenizer::_with_pattern_internal("[invalid(".to_string());
     assert!(result.is_err());
 
     if let Err(e) = result {
+        assert!(matches!(e, TokenizerError::InvalidRegex { .. }));
+        let e = e.to_string();
         assert!(e.contains("正则") || e.contains("regex") || e.contains("pattern"));
     }
 }
@@ -91,7 +103,7 @@ fn test_bbpe_invalid_pattern() {
     use zero_tokenizer::bbpe::BBPETokenizer;
 
     // 无效的正则表达式
-    let result = BBPETokenizer::with_pattern("[unclosed(".to_string());
+    let result = BBPETokenizer::with_pattern_internal("[unclosed(".to_string());
     assert!(result.is_err());
 }
 
@@ -170,3 +182,143 @@ fn test_encode_after_failed_training() {
     assert!(result.is_ok());
     assert!(!result.unwrap().is_empty());
 }
+
+#[test]
+fn test_decode_error_reports_token_index() {
+    let tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+
+    // 第3个标记（索引2）无效
+    let result = tokenizer.decode(&[104, 105, 999999, 106]);
+    assert!(matches!(
+        result.unwrap_err(),
+        TokenizerError::UnknownTokenId {
+            id: 999999,
+            index: 2,
+        }
+    ));
+}
+
+#[test]
+fn test_decode_invalid_utf8_reports_offset() {
+    let tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+
+    // 'h' 之后是被截断的 "中"（0xE4 0xB8 0xAD）
+    let result = tokenizer.decode(&[104, 0xE4, 0xB8]);
+    assert!(matches!(
+        result.unwrap_err(),
+        TokenizerError::InvalidUtf8 {
+            byte_offset: 1,
+            token_index: 1,
+        }
+    ));
+
+    // Unigram 的字节标记同样能定位到出错的标记
+    let unigram = zero_tokenizer::prelude::unigram().unwrap();
+    let result = unigram.decode(&[0xE4, 0xB8, 0xAD, 0xFF]);
+    assert!(matches!(
+        result.unwrap_err(),
+        TokenizerError::InvalidUtf8 {
+            byte_offset: 3,
+            token_index: 3,
+        }
+    ));
+}
+
+#[test]
+fn test_input_limit_error_kind() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .set_input_limits(InputLimits {
+            max_input_bytes: Some(4),
+            ..InputLimits::default()
+        })
+        .unwrap();
+
+    let result = tokenizer.encode("hello");
+    assert!(matches!(
+        result.unwrap_err(),
+        TokenizerError::InputTooLarge { bytes: 5, limit: 4 }
+    ));
+
+    // 无效的配置属于输入验证错误
+    let result = tokenizer.set_input_limits(InputLimits {
+        max_tokens: Some(0),
+        ..InputLimits::default()
+    });
+    assert!(matches!(
+        result.unwrap_err(),
+        TokenizerError::InputValidationError { .. }
+    ));
+}
+
+#[test]
+fn test_unregistered_special_token_error_kind() {
+    let tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+
+    let result = tokenizer.encode_pair("hello", "world");
+    match result.unwrap_err() {
+        TokenizerError::UnregisteredSpecialToken { token } => assert_eq!(token, "[CLS]"),
+        e => panic!("unexpected error: {}", e),
+    }
+}
+
+#[test]
+fn test_batch_item_error_reports_index() {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .set_input_limits(InputLimits {
+            max_input_bytes: Some(4),
+            ..InputLimits::default()
+        })
+        .unwrap();
+
+    // 第2条文本（索引1）超出长度限制，错误中保留原始原因
+    let texts = ["hi", "hello", "ok"];
+    for error in [
+        tokenizer.encode_batch_plus(&texts, None).unwrap_err(),
+        tokenizer
+            .encode_batch_packed(&texts, None, None)
+            .unwrap_err(),
+    ] {
+        match error {
+            TokenizerError::BatchItem { index, source } => {
+                assert_eq!(index, 1);
+                assert!(matches!(*source, TokenizerError::InputTooLarge { .. }));
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+}
+
+#[test]
+fn test_invalid_vocab_entry_reports_line() {
+    let model_path = &temp_path("vocab_entry.model");
+    let mut unigram = zero_tokenizer::prelude::unigram().unwrap();
+    unigram
+        .train(vec!["hello hello world".to_string()], 300)
+        .unwrap();
+    unigram.save(model_path).unwrap();
+
+    // 第5行的词汇表条目缺少ID
+    let content = std::fs::read_to_string(model_path).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    let (token, _) = lines[4].rsplit_once(' ').unwrap();
+    let corrupted = content.replacen(lines[4], &format!("{} not-an-id", token), 1);
+    std::fs::write(model_path, corrupted).unwrap();
+
+    let result = zero_tokenizer::prelude::unigram().unwrap().load(model_path);
+    let _ = std::fs::remove_file(model_path);
+    assert!(matches!(
+        result.unwrap_err(),
+        TokenizerError::InvalidVocabEntry { line: 5, .. }
+    ));
+
+    // 文件不存在属于模型加载错误
+    let result = zero_tokenizer::prelude::unigram()
+        .unwrap()
+        .load("missing_error_kind.model");
+    assert!(matches!(
+        result.unwrap_err(),
+        TokenizerError::ModelLoadError { .. }
+    ));
+}
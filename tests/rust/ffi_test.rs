@@ -0,0 +1,98 @@
+//! C ABI 接口测试
+
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::ptr;
+
+use zero_tokenizer::ffi::*;
+use zero_tokenizer::prelude::*;
+
+#[test]
+fn test_ffi_encode_decode_roundtrip() {
+    let model_path = "test_ffi.model";
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello".to_string()], 270)
+        .unwrap();
+    tokenizer.save(model_path).unwrap();
+
+    let path = CString::new(model_path).unwrap();
+    let text = CString::new("hello world").unwrap();
+    unsafe {
+        let handle = zero_tokenizer_new_from_file(path.as_ptr(), ZeroTokenizerKind::Bbpe as u32);
+        assert!(!handle.is_null());
+        assert_eq!(zero_tokenizer_vocab_size(handle), tokenizer.vocab_size());
+
+        let mut ids = ptr::null_mut();
+        let mut len = 0;
+        assert_eq!(
+            zero_tokenizer_encode(handle, text.as_ptr(), &mut ids, &mut len),
+            0
+        );
+        let encoded = std::slice::from_raw_parts(ids, len).to_vec();
+        assert_eq!(encoded, tokenizer.encode("hello world").unwrap());
+
+        let decoded = zero_tokenizer_decode(handle, ids, len);
+        assert_eq!(CStr::from_ptr(decoded).to_str().unwrap(), "hello world");
+
+        zero_tokenizer_free_string(decoded);
+        zero_tokenizer_free_ids(ids, len);
+        zero_tokenizer_free(handle);
+    }
+
+    fs::remove_file(model_path).ok();
+}
+
+#[test]
+fn test_ffi_errors_are_reported() {
+    let path = CString::new("nonexistent_ffi.model").unwrap();
+    unsafe {
+        let handle = zero_tokenizer_new_from_file(path.as_ptr(), ZeroTokenizerKind::Bbpe as u32);
+        assert!(handle.is_null());
+        let message = CStr::from_ptr(zero_tokenizer_last_error());
+        assert!(message.to_str().unwrap().contains("打开文件失败"));
+
+        // 空句柄不会崩溃
+        let mut ids = ptr::null_mut();
+        let mut len = 0;
+        let text = CString::new("hi").unwrap();
+        assert_eq!(
+            zero_tokenizer_encode(ptr::null(), text.as_ptr(), &mut ids, &mut len),
+            -1
+        );
+        assert!(zero_tokenizer_decode(ptr::null(), ptr::null(), 0).is_null());
+        zero_tokenizer_free(ptr::null_mut());
+    }
+}
+
+#[test]
+fn test_ffi_rejects_invalid_kind() {
+    let path = CString::new("nonexistent_ffi.model").unwrap();
+    unsafe {
+        // 越界的类型值在读取文件之前被拒绝
+        let handle = zero_tokenizer_new_from_file(path.as_ptr(), 7);
+        assert!(handle.is_null());
+        let message = CStr::from_ptr(zero_tokenizer_last_error());
+        assert!(message.to_str().unwrap().contains("无效的分词器类型: 7"));
+    }
+    assert_eq!(
+        ZeroTokenizerKind::try_from(2).unwrap(),
+        ZeroTokenizerKind::WordPiece
+    );
+    assert!(ZeroTokenizerKind::try_from(3).is_err());
+}
+
+#[test]
+fn test_ffi_header_up_to_date() {
+    let generated = fs::read_to_string(concat!(env!("OUT_DIR"), "/zero_tokenizer.h")).unwrap();
+    let header = concat!(env!("CARGO_MANIFEST_DIR"), "/include/zero_tokenizer.h");
+    // 设置 ZERO_TOKENIZER_BLESS_HEADER=1 时把构建生成的头文件复制到 include/
+    if std::env::var_os("ZERO_TOKENIZER_BLESS_HEADER").is_some() {
+        fs::write(header, &generated).unwrap();
+    }
+    assert_eq!(
+        fs::read_to_string(header).unwrap(),
+        generated,
+        "include/zero_tokenizer.h 已过期，使用 ZERO_TOKENIZER_BLESS_HEADER=1 重新运行此测试"
+    );
+}
@@ -0,0 +1,105 @@
+//! 文本文件语料读取测试
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use zero_tokenizer::corpus::{count_chunks, TextFileReader};
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::temp_path;
+
+const LINES: &[&str] = &[
+    "hello world hello",
+    "",
+    "the quick brown fox",
+    "你好世界 你好",
+    "hello there world",
+];
+
+/// 在临时目录中写入同样内容的普通、gzip和zstd文件
+fn write_corpus(name: &str) -> PathBuf {
+    let dir = PathBuf::from(temp_path(&format!("files_{}", name)));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+    let text = LINES.join("\r\n");
+
+    std::fs::write(dir.join("a.txt"), &text).unwrap();
+    let mut gz = flate2::write::GzEncoder::new(
+        std::fs::File::create(dir.join("nested/b.txt.gz")).unwrap(),
+        flate2::Compression::default(),
+    );
+    gz.write_all(text.as_bytes()).unwrap();
+    gz.finish().unwrap();
+    let zst = zstd::encode_all(text.as_bytes(), 0).unwrap();
+    std::fs::write(dir.join("nested/c.zst"), zst).unwrap();
+    dir
+}
+
+#[test]
+fn test_reader_decompresses_and_walks_directories() {
+    let dir = write_corpus("reader");
+    let batches: Vec<Vec<String>> = TextFileReader::new(&[&dir])
+        .unwrap()
+        .with_batch_lines(3)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // 3个文件各4个非空行，按3行一批且跨文件拼接
+    assert_eq!(batches.len(), 4);
+    let lines: Vec<String> = batches.into_iter().flatten().collect();
+    let expected: Vec<&str> = LINES.iter().copied().filter(|l| !l.is_empty()).collect();
+    assert_eq!(lines.len(), 12);
+    for chunk in lines.chunks(4) {
+        assert_eq!(chunk, expected.as_slice());
+    }
+}
+
+#[test]
+fn test_count_chunks_matches_glob_and_merges_counts() {
+    let dir = write_corpus("glob");
+    let pattern = dir.join("nested").join("*");
+    let pieces = count_chunks(&[&pattern], &SpecialTokens::new(), |text| {
+        Ok(text.split_whitespace().map(str::to_string).collect())
+    })
+    .unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // 两个压缩文件中 "hello" 各出现3次，结果按片段排序
+    let hello = pieces.iter().find(|(piece, _)| piece == "hello").unwrap();
+    assert_eq!(hello.1, 6);
+    assert!(pieces.windows(2).all(|w| w[0].0 < w[1].0));
+
+    let missing = dir.join("missing.txt");
+    assert!(TextFileReader::new(&[&missing]).is_err());
+    let unmatched = dir.join("*.none");
+    assert!(TextFileReader::new(&[&unmatched]).is_err());
+}
+
+#[test]
+fn test_train_from_files() {
+    let dir = write_corpus("train");
+    let config = TrainerConfig::new().with_special_tokens(["<|endoftext|>"]);
+
+    let mut bpe = bpe().unwrap();
+    bpe.train_from_files(&[&dir], 280, config.clone()).unwrap();
+    let mut bbpe = bbpe().unwrap();
+    bbpe.train_from_files(&[&dir], 280, config).unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    for text in ["hello world", "你好世界"] {
+        let ids = Tokenizer::encode(&bpe, text).unwrap();
+        assert_eq!(Tokenizer::decode(&bpe, &ids).unwrap(), text);
+        let ids = bbpe.encode(text).unwrap();
+        assert!(ids.len() < text.len());
+        assert_eq!(bbpe.decode(&ids).unwrap(), text);
+    }
+    assert!(bbpe.special_tokens().id("<|endoftext|>").is_some());
+
+    let mut fresh = zero_tokenizer::prelude::bbpe().unwrap();
+    let missing = PathBuf::from(temp_path("files_missing"));
+    assert!(fresh
+        .train_from_files(&[&missing], 280, TrainerConfig::new())
+        .is_err());
+}
@@ -0,0 +1,296 @@
+//! 文本生成辅助工具测试
+
+use zero_tokenizer::analysis::token_usage;
+use zero_tokenizer::bbpe::byte_level::bytes_to_byte_level;
+use zero_tokenizer::generation::{
+    sample_text, DecodeStream, StopMatch, StopSequenceMatcher, TextSampler,
+};
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::corpus;
+
+/// 逐个推入标记，返回第一次匹配及其所在的标记序号
+fn feed(
+    matcher: &mut StopSequenceMatcher,
+    tokenizer: &BBPE,
+    ids: &[u32],
+) -> Option<(usize, StopMatch)> {
+    ids.iter().enumerate().find_map(|(index, &id)| {
+        matcher
+            .push(tokenizer, id)
+            .unwrap()
+            .map(|found| (index, found))
+    })
+}
+
+/// 测试跨越多个标记的停止序列
+#[test]
+fn test_stop_sequence_spans_tokens() {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello world".to_string()], 270)
+        .unwrap();
+
+    let text = "hello world\n\nUser: hi";
+    let ids = tokenizer.encode(text).unwrap();
+    let mut matcher = StopSequenceMatcher::new(["\n\nUser:", "never"]);
+
+    let (index, found) = feed(&mut matcher, &tokenizer, &ids).unwrap();
+    assert_eq!(found.stop_index, 0);
+    assert_eq!(found.offset, text.find("\n\nUser:").unwrap());
+
+    // 在停止序列最后一个字节所在的标记处触发
+    let decoded_before = tokenizer.decode(&ids[..index]).unwrap();
+    assert!(!decoded_before.contains("\n\nUser:"));
+    assert!(tokenizer
+        .decode(&ids[..=index])
+        .unwrap()
+        .contains("\n\nUser:"));
+}
+
+/// 测试由多个字节标记组成的多字节字符
+#[test]
+fn test_stop_sequence_inside_multibyte_char() {
+    let tokenizer = bbpe().unwrap();
+    let ids = tokenizer.encode("好的。结束").unwrap();
+    let mut matcher = StopSequenceMatcher::new(["结束"]);
+
+    let (index, found) = feed(&mut matcher, &tokenizer, &ids).unwrap();
+    assert_eq!(index, ids.len() - 1);
+    assert_eq!(found.offset, "好的。".len());
+    assert_eq!(matcher.consumed(), "好的。结束".len());
+}
+
+/// 测试多个停止序列同时出现时返回起始位置最靠前的一个
+#[test]
+fn test_stop_sequence_earliest_match() {
+    let mut matcher = StopSequenceMatcher::new(["bc", "abcd"]);
+    assert_eq!(matcher.push_bytes(b"xa"), None);
+    assert_eq!(
+        matcher.push_bytes(b"bcd"),
+        Some(StopMatch {
+            stop_index: 1,
+            offset: 1
+        })
+    );
+
+    matcher.reset();
+    assert_eq!(matcher.consumed(), 0);
+    assert_eq!(
+        matcher.push_bytes(b"xbc"),
+        Some(StopMatch {
+            stop_index: 0,
+            offset: 1
+        })
+    );
+}
+
+/// 测试可能构成停止序列开头的尾部长度
+#[test]
+fn test_stop_sequence_pending_len() {
+    let mut matcher = StopSequenceMatcher::new(["</s>"]);
+    assert_eq!(matcher.push_bytes(b"answer <"), None);
+    assert_eq!(matcher.pending_len(), 1);
+    assert_eq!(matcher.push_bytes(b"/"), None);
+    assert_eq!(matcher.pending_len(), 2);
+    assert_eq!(matcher.push_bytes(b"x"), None);
+    assert_eq!(matcher.pending_len(), 0);
+
+    // 未配置停止序列时永远不会匹配
+    let mut empty = StopSequenceMatcher::new([""]);
+    assert_eq!(empty.push_bytes(b"anything"), None);
+    assert_eq!(empty.pending_len(), 0);
+}
+
+/// 测试未知标记ID
+#[test]
+fn test_stop_sequence_unknown_token() {
+    let tokenizer = bbpe().unwrap();
+    let mut matcher = StopSequenceMatcher::new(["x"]);
+    assert!(matcher.push(&tokenizer, 999_999).is_err());
+}
+
+/// 测试流式解码：多字节字符被拆成字节标记时等字符完整后再输出
+#[test]
+fn test_decode_stream_buffers_incomplete_chars() {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello world".to_string()], 270)
+        .unwrap();
+    let text = "hello 你好，world 🎉";
+    let ids = tokenizer.encode(text).unwrap();
+
+    let mut stream = tokenizer.decode_stream();
+    let mut output = String::new();
+    let mut buffered = false;
+    for &id in &ids {
+        match stream.step(id).unwrap() {
+            Some(fragment) => output.push_str(&fragment),
+            None => buffered = true,
+        }
+    }
+    assert!(buffered, "多字节字符的字节标记应被缓存");
+    assert_eq!(stream.pending_len(), 0);
+    assert_eq!(stream.flush(), None);
+    assert_eq!(output, text);
+}
+
+/// 测试生成在字符中间结束时，剩余字节按替换字符输出
+#[test]
+fn test_decode_stream_flush_incomplete() {
+    let tokenizer = bbpe().unwrap();
+    let ids = tokenizer.encode("好").unwrap();
+    assert_eq!(ids.len(), 3);
+
+    let mut stream = DecodeStream::new(&tokenizer);
+    assert_eq!(stream.step(ids[0]).unwrap(), None);
+    assert_eq!(stream.step(ids[1]).unwrap(), None);
+    assert_eq!(stream.pending_len(), 2);
+    assert_eq!(stream.flush().as_deref(), Some("\u{FFFD}"));
+
+    // 不可能补全的字节立即输出为替换字符
+    let a = tokenizer.encode("a").unwrap()[0];
+    assert_eq!(stream.step(ids[0]).unwrap(), None);
+    assert_eq!(stream.step(a).unwrap().as_deref(), Some("\u{FFFD}a"));
+    assert!(stream.step(u32::MAX).is_err());
+}
+
+/// 流式解码拼接的结果和停止序列的位置都应与一次性解码相同
+fn assert_stream_matches_decode<T>(tokenizer: &T, ids: &[u32], expected: &str, stop: &str)
+where
+    T: VocabBytes<TokenId = u32>,
+{
+    let mut stream = DecodeStream::new(tokenizer);
+    let mut output = String::new();
+    for &id in ids {
+        output.extend(stream.step(id).unwrap());
+    }
+    output.extend(stream.flush());
+    assert_eq!(output, expected, "{:?}", tokenizer.decoder());
+
+    let mut matcher = StopSequenceMatcher::new([stop]);
+    let found = ids
+        .iter()
+        .find_map(|&id| matcher.push(tokenizer, id).unwrap())
+        .or_else(|| matcher.finish())
+        .unwrap();
+    assert_eq!(found.offset, expected.find(stop).unwrap());
+}
+
+/// 测试每种解码器下流式解码与一次性解码结果相同
+#[test]
+fn test_decode_stream_matches_decoders() {
+    // 直接拼接：多字节字符被拆成多个字节标记
+    let mut bbpe = bbpe().unwrap();
+    bbpe.train(corpus(), 300).unwrap();
+    let ids = bbpe.encode("hello 你好，world 🎉").unwrap();
+    assert_stream_matches_decode(&bbpe, &ids, &bbpe.decode(&ids).unwrap(), "world");
+
+    // GPT-2字节映射：一个字符的映射字节分散在多个标记中
+    let mut training = corpus();
+    training.push("wörld 你好".to_string());
+    let mapped: Vec<String> = training
+        .iter()
+        .map(|text| bytes_to_byte_level(text.as_bytes()))
+        .collect();
+    let mut bpe = bpe().unwrap();
+    bpe.train(mapped, 300).unwrap();
+    bpe.base.decoder = DecoderKind::ByteLevel;
+    let ids = bpe
+        .encode(&bytes_to_byte_level("hello wörld 你好 world".as_bytes()))
+        .unwrap();
+    let expected = bpe.decode(&ids).unwrap();
+    assert_eq!(expected, "hello wörld 你好 world");
+    assert_stream_matches_decode(&bpe, &ids, &expected, "world");
+
+    // SentencePiece风格：只去掉第一个标记开头的空格
+    let mut unigram = unigram().unwrap();
+    unigram.base.pre_tokenizer = PreTokenizerPipeline::parse("metaspace").unwrap();
+    unigram.train(corpus(), 60).unwrap();
+    unigram.base.decoder = DecoderKind::Metaspace;
+    let ids = unigram.encode(" hello world the world").unwrap();
+    let expected = unigram.decode(&ids).unwrap();
+    assert_eq!(expected, "hello world the world");
+    assert_stream_matches_decode(&unigram, &ids, &expected, "world");
+
+    // BERT风格：标点和缩写之前的空格要等后续标记到达后才能确定是否保留
+    let mut wordpiece = wordpiece().unwrap();
+    wordpiece.base.pre_tokenizer = PreTokenizerPipeline::parse("whitespace,punctuation").unwrap();
+    let vocab_size = wordpiece.base.vocab.len() as u32 + 100;
+    wordpiece.train(corpus(), vocab_size).unwrap();
+    wordpiece.base.decoder = DecoderKind::WordPiece;
+    let ids = wordpiece
+        .encode("hello world , the world is round ! do not stop .")
+        .unwrap();
+    let expected = wordpiece.decode(&ids).unwrap();
+    assert!(expected.starts_with("hello world, the world is round!"));
+    assert_stream_matches_decode(&wordpiece, &ids, &expected, "round!");
+
+    // 停止序列出现在暂缓输出的最后一个词中时，生成结束后才能匹配
+    let ids = wordpiece.encode("hello world").unwrap();
+    let mut matcher = StopSequenceMatcher::new(["world"]);
+    assert!(ids
+        .iter()
+        .all(|&id| matcher.push(&wordpiece, id).unwrap().is_none()));
+    assert_eq!(matcher.finish().unwrap().offset, "hello ".len());
+}
+
+/// 测试随机文本由候选标记组成，固定种子时可复现，且编码后能解码回原文
+#[test]
+fn test_sample_text_round_trip() {
+    let corpus = vec!["hello world, 你好世界! tokenizer 123 héllo".to_string(); 4];
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.train(corpus.clone(), 320).unwrap();
+    let eot = tokenizer.add_special_tokens(&["<|endoftext|>"]).unwrap()[0];
+
+    let text = sample_text(&tokenizer, 200, Some(7)).unwrap();
+    assert_eq!(text, sample_text(&tokenizer, 200, Some(7)).unwrap());
+    assert!(!text.contains("<|endoftext|>"));
+
+    let mut sampler = TextSampler::new(&tokenizer, Some(1)).unwrap();
+    for _ in 0..20 {
+        let ids = sampler.sample_ids(50);
+        assert_eq!(ids.len(), 50);
+        assert!(!ids.contains(&eot));
+        let text = tokenizer.decode(&ids).unwrap();
+        assert_eq!(
+            tokenizer.decode(&tokenizer.encode(&text).unwrap()).unwrap(),
+            text
+        );
+    }
+
+    let mut bpe = bpe().unwrap();
+    bpe.train(corpus.clone(), 300).unwrap();
+    let mut unigram = unigram().unwrap();
+    unigram.train(corpus.clone(), 60).unwrap();
+    let mut wordpiece = wordpiece().unwrap();
+    wordpiece.train(corpus, 80).unwrap();
+    for seed in 0..20 {
+        let text = sample_text(&bpe, 30, Some(seed)).unwrap();
+        assert_eq!(bpe.decode(&bpe.encode(&text).unwrap()).unwrap(), text);
+        let text = sample_text(&unigram, 30, Some(seed)).unwrap();
+        assert_eq!(
+            unigram.decode(&unigram.encode(&text).unwrap()).unwrap(),
+            text
+        );
+        assert!(!sample_text(&wordpiece, 30, Some(seed)).unwrap().is_empty());
+    }
+}
+
+/// 测试按使用次数加权时只抽取出现过的标记
+#[test]
+fn test_sample_text_weighted_by_usage() {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello world".to_string()], 270)
+        .unwrap();
+    let usage = token_usage(&tokenizer, ["hello hello hello world"]).unwrap();
+
+    let mut sampler = TextSampler::with_counts(&tokenizer, &usage, Some(3)).unwrap();
+    let used: Vec<u32> = usage.iter().map(|&(id, _)| id).collect();
+    assert!(sampler.sample_ids(500).iter().all(|id| used.contains(id)));
+
+    assert!(TextSampler::with_counts(&tokenizer, &[], None).is_err());
+    assert!(TextSampler::with_counts(&tokenizer, &[(used[0], 0)], None).is_err());
+}
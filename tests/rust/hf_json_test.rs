@@ -0,0 +1,239 @@
+//! HuggingFace tokenizer.json 导入导出测试
+//!
+//! 测试四种分词器导出后重新加载的编码结果不变，以及对外部文件的解析和拒绝
+
+use zero_tokenizer::base::hf_json::{HfModel, HfTokenizerJson};
+use zero_tokenizer::bbpe::byte_level::byte_to_char;
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::temp_path;
+
+fn corpus() -> Vec<String> {
+    vec!["hello world, hello tokenizer! 你好世界 123456 hello world".to_string()]
+}
+
+const SAMPLE: &str = "hello tokenizer world 你好 42<|endoftext|>";
+
+/// 测试BBPE导出的文件重新加载后编码结果和特殊标记不变
+#[test]
+fn test_bbpe_round_trip() {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.add_special_tokens(&["<|endoftext|>"]).unwrap();
+    tokenizer.train(corpus(), 300).unwrap();
+    let expected = tokenizer.encode(SAMPLE).unwrap();
+
+    let path = temp_path("test_hf_bbpe.json");
+    tokenizer.save_tokenizer_json(&path).unwrap();
+    let mut loaded = bbpe().unwrap();
+    loaded.load_tokenizer_json(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.encode(SAMPLE).unwrap(), expected);
+    assert_eq!(loaded.decode(&expected).unwrap(), SAMPLE);
+    assert_eq!(
+        loaded.special_tokens().id("<|endoftext|>"),
+        tokenizer.special_tokens().id("<|endoftext|>")
+    );
+    assert_eq!(loaded.merges, tokenizer.merges);
+}
+
+/// 测试BPE导出后重新加载，回退方式和规范化方式一并保留
+#[test]
+fn test_bpe_round_trip() {
+    let mut tokenizer = bpe().unwrap();
+    tokenizer.train(corpus(), 300).unwrap();
+    let text = "hello world 你好 ☃";
+    let expected = tokenizer.encode(text).unwrap();
+
+    let json =
+        HfTokenizerJson::parse(&tokenizer.to_hf_json().unwrap().to_json_string().unwrap()).unwrap();
+    assert_eq!(json.normalizer.steps(), [NormalizerStep::Nfc]);
+    let mut loaded = bpe().unwrap();
+    loaded.load_hf_json(json).unwrap();
+
+    assert_eq!(loaded.encode(text).unwrap(), expected);
+    assert_eq!(loaded.decode(&expected).unwrap(), text);
+    assert_eq!(loaded.unknown_fallback, tokenizer.unknown_fallback);
+}
+
+/// 测试Unigram导出后重新加载，片段和分数不变
+#[test]
+fn test_unigram_round_trip() {
+    let mut tokenizer = unigram().unwrap();
+    tokenizer
+        .set_pieces(vec![
+            ("<unk>".to_string(), 0.0),
+            ("hello".to_string(), -2.0),
+            ("world".to_string(), -2.5),
+            ("h".to_string(), -5.0),
+            ("e".to_string(), -5.0),
+            ("l".to_string(), -5.0),
+            ("o".to_string(), -5.0),
+            (" ".to_string(), -3.0),
+        ])
+        .unwrap();
+    tokenizer.set_byte_fallback(false);
+    let text = "hello world hole";
+    let expected = tokenizer.encode(text).unwrap();
+
+    let mut loaded = unigram().unwrap();
+    loaded
+        .load_hf_json(tokenizer.to_hf_json().unwrap())
+        .unwrap();
+    assert_eq!(loaded.encode(text).unwrap(), expected);
+    assert_eq!(loaded.scores, tokenizer.scores);
+    assert_eq!(loaded.unk_token_id, 0);
+    assert!(!loaded.byte_fallback);
+}
+
+/// 测试WordPiece导出后重新加载，未知标记保持不变
+#[test]
+fn test_wordpiece_round_trip() {
+    let mut tokenizer = wordpiece().unwrap();
+    tokenizer.train(corpus(), 400).unwrap();
+    tokenizer.set_unk_token("[UNK]", None).unwrap();
+    let expected = tokenizer.encode(SAMPLE).unwrap();
+
+    let path = temp_path("test_hf_wordpiece.json");
+    tokenizer.save_tokenizer_json(&path).unwrap();
+    let mut loaded = wordpiece().unwrap();
+    loaded.load_tokenizer_json(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.encode(SAMPLE).unwrap(), expected);
+    assert_eq!(loaded.unk_token(), Some("[UNK]"));
+}
+
+/// 测试导出的JSON包含 `tokenizers` 需要的字段
+#[test]
+fn test_exported_schema() {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.add_special_tokens(&["<|endoftext|>"]).unwrap();
+    tokenizer.train(corpus(), 270).unwrap();
+    let text = tokenizer.to_hf_json().unwrap().to_json_string().unwrap();
+    let root: serde_json::Value = serde_json::from_str(&text).unwrap();
+
+    assert_eq!(root["version"], "1.0");
+    assert_eq!(root["model"]["type"], "BPE");
+    assert_eq!(root["model"]["vocab"]["Ġ"], 32);
+    assert_eq!(
+        root["model"]["merges"].as_array().unwrap().len(),
+        tokenizer.merges.len()
+    );
+    assert_eq!(root["added_tokens"][0]["content"], "<|endoftext|>");
+    assert_eq!(root["added_tokens"][0]["special"], true);
+    assert_eq!(root["pre_tokenizer"]["type"], "Sequence");
+    assert_eq!(
+        root["pre_tokenizer"]["pretokenizers"][1]["type"],
+        "ByteLevel"
+    );
+    assert_eq!(root["decoder"]["type"], "ByteLevel");
+}
+
+/// 构造一个GPT-2风格的字节级BPE tokenizer.json，合并规则使用旧版 "a b" 字符串格式
+fn gpt2_style_json() -> String {
+    let mut vocab = serde_json::Map::new();
+    for byte in 0..=255u8 {
+        vocab.insert(byte_to_char(byte).to_string(), u32::from(byte).into());
+    }
+    for (id, token) in (256u32..).zip(["Ġt", "he", "Ġthe", "<|endoftext|>"]) {
+        vocab.insert(token.to_string(), id.into());
+    }
+    serde_json::json!({
+        "version": "1.0",
+        "added_tokens": [{"id": 259, "content": "<|endoftext|>", "special": true}],
+        "normalizer": null,
+        "pre_tokenizer": {"type": "ByteLevel", "add_prefix_space": false, "trim_offsets": true, "use_regex": true},
+        "post_processor": {"type": "ByteLevel", "trim_offsets": false},
+        "decoder": {"type": "ByteLevel"},
+        "model": {
+            "type": "BPE",
+            "dropout": null,
+            "unk_token": null,
+            "vocab": vocab,
+            "merges": ["Ġ t", "h e", "Ġt he"],
+        },
+    })
+    .to_string()
+}
+
+/// 测试加载外部的GPT-2风格文件
+#[test]
+fn test_load_gpt2_style_json() {
+    use zero_tokenizer::base::tokenizer_base::GPT2_PATTERN;
+
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .load_hf_json(HfTokenizerJson::parse(&gpt2_style_json()).unwrap())
+        .unwrap();
+
+    assert_eq!(tokenizer.base.pattern, GPT2_PATTERN);
+    assert_eq!(
+        tokenizer.encode("the the<|endoftext|>").unwrap(),
+        vec![u32::from(b't'), 257, 258, 259]
+    );
+    assert_eq!(tokenizer.next_token_id, 260);
+}
+
+/// 测试合并顺序与新标记ID顺序不一致的模型按文件中的顺序保留等级
+#[test]
+fn test_out_of_order_merges_keep_rank() {
+    let mut json = HfTokenizerJson::parse(&gpt2_style_json()).unwrap();
+    if let HfModel::Bpe { merges, .. } = &mut json.model {
+        merges.swap(0, 1);
+    }
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.load_hf_json(json).unwrap();
+
+    let (h, e) = (u32::from(b'h'), u32::from(b'e'));
+    assert_eq!(tokenizer.merges.get_index(0), Some((&(h, e), &257)));
+    assert_eq!(
+        tokenizer.encode("the the").unwrap(),
+        vec![u32::from(b't'), 257, 258]
+    );
+
+    let exported = tokenizer.to_hf_json().unwrap();
+    let HfModel::Bpe { merges, .. } = &exported.model else {
+        panic!("导出的模型应为BPE");
+    };
+    assert_eq!(merges[0], ("h".to_string(), "e".to_string()));
+}
+
+/// 测试无法等价实现的组件被拒绝
+#[test]
+fn test_rejects_unsupported_components() {
+    let bert = serde_json::json!({
+        "normalizer": {"type": "BertNormalizer"},
+        "pre_tokenizer": null,
+        "model": {"type": "WordPiece", "unk_token": "[UNK]", "vocab": {"[UNK]": 0}},
+    });
+    assert!(HfTokenizerJson::parse(&bert.to_string()).is_err());
+
+    // 字节级模型只能由BBPE加载
+    let json = HfTokenizerJson::parse(&gpt2_style_json()).unwrap();
+    assert!(bpe().unwrap().load_hf_json(json).is_err());
+}
+
+/// 测试加载带 `##` 续接前缀的WordPiece词汇表，续接标记只在词内匹配，解码时去掉前缀
+#[test]
+fn test_load_prefixed_wordpiece() {
+    let prefixed = serde_json::json!({
+        "model": {
+            "type": "WordPiece",
+            "unk_token": "[UNK]",
+            "continuing_subword_prefix": "##",
+            "vocab": {"[UNK]": 0, "hello": 1, "##s": 2, "s": 3},
+        },
+    });
+    let mut tokenizer = wordpiece().unwrap();
+    tokenizer
+        .load_hf_json(HfTokenizerJson::parse(&prefixed.to_string()).unwrap())
+        .unwrap();
+
+    assert_eq!(tokenizer.continuing_subword_prefix, "##");
+    assert_eq!(tokenizer.encode("hellos").unwrap(), vec![1, 2]);
+    // 词内没有匹配的续接标记时改用词首标记
+    assert_eq!(tokenizer.encode("shello").unwrap(), vec![3, 1]);
+    assert_eq!(tokenizer.decode(&[1, 2]).unwrap(), "hellos");
+}
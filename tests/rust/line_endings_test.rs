@@ -0,0 +1,135 @@
+//! 模型文件换行方式测试
+//!
+//! 测试在Windows上编辑过、换行为 `\r\n` 或混用两种换行的模型文件仍能正确加载
+
+use std::borrow::Cow;
+
+use zero_tokenizer::base::tokenizer_base::normalize_line_endings;
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::{temp_path, trained_tokenizers};
+
+fn corpus() -> Vec<String> {
+    vec!["hello world, hello there! the world is big.\r\nthe world is round.".to_string()]
+}
+
+/// 按 `convert` 改写模型文件及其 `.scores` 文件
+fn rewrite(path: &str, convert: impl Fn(&str) -> String) {
+    for file in [path.to_string(), format!("{}.scores", path)] {
+        if let Ok(content) = std::fs::read_to_string(&file) {
+            std::fs::write(&file, convert(&content)).unwrap();
+        }
+    }
+}
+
+fn remove(path: &str) {
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(format!("{}.scores", path));
+}
+
+/// 保存后按 `convert` 改写，重新加载的分词器与原分词器一致
+fn assert_reloads(tokenizer: &dyn DynTokenizer, name: &str, convert: impl Fn(&str) -> String) {
+    let path = temp_path(name);
+    tokenizer.save(&path).unwrap();
+    rewrite(&path, convert);
+
+    let mut loaded = AutoTokenizer::for_kind(tokenizer.kind()).unwrap();
+    loaded.load(&path).unwrap();
+    assert_eq!(loaded.vocab_size(), tokenizer.vocab_size());
+    for text in ["hello world", "the world is round.\r\n"] {
+        assert_eq!(
+            loaded.encode(text).unwrap(),
+            tokenizer.encode(text).unwrap()
+        );
+    }
+    remove(&path);
+}
+
+/// 训练好的四种分词器，BBPE另外注册一个特殊标记
+fn trained() -> Vec<Box<dyn DynTokenizer>> {
+    let mut tokenizers = trained_tokenizers(&corpus());
+    tokenizers[1]
+        .add_special_tokens(&["<|endoftext|>"])
+        .unwrap();
+    tokenizers
+}
+
+#[test]
+fn test_crlf_models() {
+    for tokenizer in trained() {
+        let name = format!("crlf_{}", tokenizer.kind().name());
+        assert_reloads(tokenizer.as_ref(), &name, |content| {
+            content.replace('\n', "\r\n")
+        });
+    }
+}
+
+#[test]
+fn test_mixed_line_endings() {
+    for tokenizer in trained() {
+        let name = format!("mixed_{}", tokenizer.kind().name());
+        assert_reloads(tokenizer.as_ref(), &name, |content| {
+            content
+                .split_inclusive('\n')
+                .enumerate()
+                .map(|(index, line)| match line.strip_suffix('\n') {
+                    Some(line) if index % 2 == 0 => format!("{}\r\n", line),
+                    _ => line.to_string(),
+                })
+                .collect()
+        });
+    }
+}
+
+#[test]
+fn test_bbpe_trailing_whitespace_falls_back_to_lines() {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.train(corpus(), 280).unwrap();
+    // 编辑器在行尾留下的空白使段索引不再对齐
+    assert_reloads(&tokenizer, "trailing_bbpe", |content| {
+        content
+            .lines()
+            .map(|line| {
+                if line.starts_with("vocab_entry: ") {
+                    format!("{} \r\n", line)
+                } else {
+                    format!("{}\n", line)
+                }
+            })
+            .collect()
+    });
+}
+
+#[test]
+fn test_crlf_scores_file() {
+    let mut tokenizer = wordpiece().unwrap();
+    let size = tokenizer.vocab_size() as u32 + 20;
+    tokenizer.train(corpus(), size).unwrap();
+    let path = temp_path("crlf_scores_wordpiece");
+    tokenizer.save(&path).unwrap();
+    let model = std::fs::read(&path).unwrap();
+    let scores = std::fs::read_to_string(format!("{}.scores", path)).unwrap();
+    let scores = scores.replace('\n', " \r\n") + "\r\n";
+
+    let mut loaded = wordpiece().unwrap();
+    loaded.load_from_bytes(&model, scores.as_bytes()).unwrap();
+    assert_eq!(
+        loaded.encode("hello world").unwrap(),
+        tokenizer.encode("hello world").unwrap()
+    );
+    remove(&path);
+}
+
+#[test]
+fn test_normalize_line_endings() {
+    assert!(matches!(
+        normalize_line_endings(b"a\nb\n"),
+        Cow::Borrowed(_)
+    ));
+    assert_eq!(
+        normalize_line_endings(b"a\r\nb\nc\rd\r\n").as_ref(),
+        b"a\nb\nc\rd\n"
+    );
+    assert_eq!(normalize_line_endings(b"\r\r\n").as_ref(), b"\r\n");
+}
@@ -0,0 +1,90 @@
+//! 有内存上限的近似计数测试
+//!
+//! 测试唯一键数量上限、计数误差上界以及不设上限时的精确计数
+
+use std::collections::HashMap;
+
+use zero_tokenizer::base::lossy_count::LossyCounts;
+
+/// 少量高频键混合大量只出现一次的噪声键，按批次交替出现
+fn batches() -> Vec<Vec<(String, i32)>> {
+    (0..40)
+        .map(|batch| {
+            let mut items: Vec<(String, i32)> =
+                (0..5).map(|i| (format!("frequent{}", i), 10 + i)).collect();
+            items.extend((0..30).map(|i| (format!("noise{}_{}", batch, i), 1)));
+            items
+        })
+        .collect()
+}
+
+fn true_counts() -> HashMap<String, i32> {
+    let mut counts = HashMap::new();
+    for batch in batches() {
+        for (key, count) in batch {
+            *counts.entry(key).or_default() += count;
+        }
+    }
+    counts
+}
+
+#[test]
+fn test_unbounded_counts_are_exact() {
+    let mut counts = LossyCounts::new(None).unwrap();
+    for batch in batches() {
+        counts.extend(batch);
+    }
+    assert_eq!(counts.threshold(), 0);
+    assert_eq!(counts.pruned(), 0);
+    let counts: HashMap<String, i32> = counts.into_counts().collect();
+    assert_eq!(counts, true_counts());
+}
+
+#[test]
+fn test_bounded_counts_respect_error_bound() {
+    let max_keys = 64;
+    let mut counts = LossyCounts::new(Some(max_keys)).unwrap();
+    for batch in batches() {
+        counts.extend(batch);
+        assert!(counts.len() <= max_keys);
+    }
+    assert!(counts.pruned() > 0);
+    let threshold = counts.threshold();
+    assert!(threshold > 0);
+
+    let truth = true_counts();
+    let kept: HashMap<String, i32> = counts.into_counts().collect();
+    for (key, &count) in &kept {
+        let actual = truth[key];
+        assert!(count <= actual, "{}: {} > {}", key, count, actual);
+        assert!(
+            actual <= count + threshold,
+            "{}: {} 误差超过 {}",
+            key,
+            actual,
+            threshold
+        );
+    }
+    for (key, &actual) in &truth {
+        if actual > threshold {
+            assert!(kept.contains_key(key), "{} 出现 {} 次却被丢弃", key, actual);
+        }
+    }
+    for i in 0..5 {
+        assert!(kept.contains_key(&format!("frequent{}", i)));
+    }
+}
+
+#[test]
+fn test_single_key_budget() {
+    let mut counts = LossyCounts::new(Some(1)).unwrap();
+    counts.extend([("a", 5), ("b", 1)]);
+    assert_eq!(counts.len(), 1);
+    let kept: Vec<(&str, i32)> = counts.into_counts().collect();
+    assert_eq!(kept, vec![("a", 5)]);
+}
+
+#[test]
+fn test_zero_budget_is_rejected() {
+    assert!(LossyCounts::<String>::new(Some(0)).is_err());
+}
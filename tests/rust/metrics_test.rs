@@ -0,0 +1,110 @@
+//! 服务指标测试
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::Request;
+use metrics_util::debugging::{DebugValue, DebuggingRecorder, Snapshotter};
+use metrics_util::MetricKind;
+use serde_json::json;
+use tower::ServiceExt;
+
+use zero_tokenizer::batching::{BatchConfig, BatchQueue};
+use zero_tokenizer::prelude::*;
+use zero_tokenizer::server::{self, SharedTokenizer};
+use zero_tokenizer::telemetry;
+
+fn shared_tokenizer() -> SharedTokenizer {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello".to_string()], 270)
+        .unwrap();
+    Arc::new(tokenizer)
+}
+
+/// 按名称汇总计数器的值
+fn counter(snapshot: &[(String, u64)], name: &str) -> u64 {
+    snapshot
+        .iter()
+        .filter(|(key, _)| key == name)
+        .map(|(_, value)| value)
+        .sum()
+}
+
+/// 指标名称及其数值（计数器为值，直方图为样本数）
+type Values<T> = Vec<(String, T)>;
+
+/// 读取全部计数器的值和直方图的样本数
+fn snapshot(snapshotter: &Snapshotter) -> (Values<u64>, Values<usize>) {
+    let mut counters = Vec::new();
+    let mut histograms = Vec::new();
+    for (key, _, _, value) in snapshotter.snapshot().into_vec() {
+        let name = key.key().name().to_string();
+        match (key.kind(), value) {
+            (MetricKind::Counter, DebugValue::Counter(value)) => counters.push((name, value)),
+            (MetricKind::Histogram, DebugValue::Histogram(values)) => {
+                histograms.push((name, values.len()));
+            }
+            _ => {}
+        }
+    }
+    (counters, histograms)
+}
+
+/// 测试服务请求和批处理队列输出的指标
+///
+/// 批处理队列在后台线程中记录指标，因此安装全局记录器，本文件只能有这一个测试
+#[tokio::test]
+async fn test_server_and_batch_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    recorder.install().unwrap();
+    telemetry::describe_metrics();
+
+    let shared = shared_tokenizer();
+    let expected_tokens = shared.encode("hello world").unwrap().len() as u64;
+
+    for body in [
+        json!({ "texts": ["hello world", "hello world"] }),
+        json!({}),
+    ] {
+        let request = Request::post("/count")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        server::router(shared.clone())
+            .oneshot(request)
+            .await
+            .unwrap();
+    }
+
+    let (counters, histograms) = snapshot(&snapshotter);
+    assert_eq!(counter(&counters, telemetry::REQUESTS), 2);
+    assert_eq!(counter(&counters, telemetry::REQUEST_ERRORS), 1);
+    assert_eq!(counter(&counters, telemetry::TEXTS_ENCODED), 2);
+    assert_eq!(
+        counter(&counters, telemetry::TOKENS_ENCODED),
+        expected_tokens * 2
+    );
+    assert!(histograms
+        .iter()
+        .any(|(name, samples)| name == telemetry::REQUEST_DURATION && *samples == 2));
+
+    let queue = BatchQueue::new(
+        shared,
+        BatchConfig {
+            max_batch_size: 4,
+            max_wait: Duration::from_millis(1),
+        },
+    );
+    queue.encode_many(["hello world", "hello"]).unwrap();
+    drop(queue);
+
+    // 快照只包含上次快照之后的增量
+    let (counters, histograms) = snapshot(&snapshotter);
+    assert_eq!(counter(&counters, telemetry::TEXTS_ENCODED), 2);
+    assert!(histograms
+        .iter()
+        .any(|(name, samples)| name == telemetry::BATCH_SIZE && *samples >= 1));
+}
@@ -0,0 +1,281 @@
+//! 规范化流水线测试
+//!
+//! 测试各内置步骤的行为、训练与编码使用相同的规范化，以及规范化流水线随模型保存和加载
+
+use zero_tokenizer::base::hf_json::HfTokenizerJson;
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::temp_path;
+
+fn corpus() -> Vec<String> {
+    vec!["Café Ｈｅｌｌｏ world, héllo WORLD! naïve ﬁle 123".to_string()]
+}
+
+/// 测试各内置步骤的结果，文本不变时不分配
+#[test]
+fn test_steps() {
+    let apply = |step: NormalizerStep, text: &str| step.apply(text).into_owned();
+    assert_eq!(apply(NormalizerStep::Nfc, "e\u{301}"), "é");
+    assert_eq!(apply(NormalizerStep::Nfkc, "Ｈｅｌｌｏ ﬁ"), "Hello fi");
+    assert_eq!(apply(NormalizerStep::Nfkd, "é"), "e\u{301}");
+    assert_eq!(
+        apply(NormalizerStep::Lowercase, "HÉLLO Straße"),
+        "héllo straße"
+    );
+    assert_eq!(
+        apply(NormalizerStep::StripAccents, "Café naïve Ångström"),
+        "Cafe naive Angstrom"
+    );
+    assert_eq!(
+        apply(NormalizerStep::CleanWhitespace, "  hello\t\tworld \n "),
+        "hello world"
+    );
+
+    for step in NormalizerStep::ALL {
+        assert!(matches!(
+            step.apply("hello world"),
+            std::borrow::Cow::Borrowed(_)
+        ));
+    }
+}
+
+/// 测试流水线按顺序执行，名称解析与 `spec` 互逆
+#[test]
+fn test_parse_and_spec() {
+    let normalizer = Normalizer::parse("nfkc, lowercase,strip_accents").unwrap();
+    assert_eq!(
+        normalizer.steps(),
+        [
+            NormalizerStep::Nfkc,
+            NormalizerStep::Lowercase,
+            NormalizerStep::StripAccents
+        ]
+    );
+    assert_eq!(normalizer.spec(), "nfkc,lowercase,strip_accents");
+    assert_eq!(Normalizer::parse(&normalizer.spec()).unwrap(), normalizer);
+    assert_eq!(normalizer.apply("ＣＡＦÉ"), "cafe");
+
+    assert!(Normalizer::parse("none").unwrap().is_empty());
+    assert_eq!(Normalizer::new().spec(), "none");
+    assert!(Normalizer::parse("nfc,upper")
+        .unwrap_err()
+        .to_string()
+        .contains("upper"));
+}
+
+/// 测试四种分词器在训练和编码时都使用规范化，规范化前不同但规范化后相同的文本得到相同的ID
+#[test]
+fn test_train_and_encode_use_normalizer() {
+    let normalizer = Normalizer::parse("nfkc,lowercase,strip_accents,clean_whitespace").unwrap();
+    let check = |tokenizer: &dyn Tokenizer<TokenId = u32>| {
+        assert_eq!(
+            tokenizer.encode("HÉLLO   Ｗorld").unwrap(),
+            tokenizer.encode("hello world").unwrap()
+        );
+    };
+
+    let mut bpe = bpe().unwrap();
+    bpe.base.normalizer = normalizer.clone();
+    bpe.train(corpus(), 300).unwrap();
+    check(&bpe);
+
+    let mut bbpe = bbpe().unwrap();
+    bbpe.base.normalizer = normalizer.clone();
+    bbpe.train(corpus(), 300).unwrap();
+    check(&bbpe);
+
+    let mut unigram = unigram().unwrap();
+    unigram.base.normalizer = normalizer.clone();
+    unigram.train(corpus(), 40).unwrap();
+    check(&unigram);
+
+    let mut wordpiece = wordpiece().unwrap();
+    wordpiece.base.normalizer = normalizer;
+    wordpiece.train(corpus(), 60).unwrap();
+    check(&wordpiece);
+}
+
+/// 测试特殊标记不参与规范化
+#[test]
+fn test_special_tokens_not_normalized() {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.base.normalizer = Normalizer::parse("lowercase").unwrap();
+    tokenizer.train(corpus(), 300).unwrap();
+    let ids = tokenizer.add_special_tokens(&["<|EOT|>"]).unwrap();
+
+    let encoded = tokenizer.encode("HELLO<|EOT|>").unwrap();
+    assert_eq!(encoded.last(), Some(&ids[0]));
+    assert_eq!(
+        encoded[..encoded.len() - 1],
+        tokenizer.encode("hello").unwrap()[..]
+    );
+}
+
+/// 测试文本格式和二进制格式保存后重新加载，规范化流水线不变
+#[test]
+fn test_save_load_preserves_normalizer() {
+    let text = "ＨÉLLO  wörld";
+    let mut tokenizer = bpe().unwrap();
+    tokenizer.base.normalizer = Normalizer::parse("nfkc,lowercase,clean_whitespace").unwrap();
+    tokenizer.train(corpus(), 300).unwrap();
+    let expected = tokenizer.encode(text).unwrap();
+
+    let path = temp_path("normalizer_text.model");
+    tokenizer.save(&path).unwrap();
+    let mut loaded = bpe().unwrap();
+    loaded.load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(loaded.base.normalizer, tokenizer.base.normalizer);
+    assert_eq!(loaded.encode(text).unwrap(), expected);
+
+    let path = temp_path("normalizer_binary.bin");
+    tokenizer.save_binary(&path).unwrap();
+    let mut loaded = bpe().unwrap();
+    loaded.load_binary(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(loaded.base.normalizer, tokenizer.base.normalizer);
+    assert_eq!(loaded.encode(text).unwrap(), expected);
+
+    // 没有规范化的模型加载后清除之前的流水线
+    let mut plain = bpe().unwrap();
+    plain.base.normalizer = Normalizer::new();
+    let path = temp_path("normalizer_plain.model");
+    plain.save(&path).unwrap();
+    let mut reloaded = bpe().unwrap();
+    reloaded.base.normalizer = Normalizer::parse("lowercase").unwrap();
+    reloaded.load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert!(reloaded.base.normalizer.is_empty());
+}
+
+/// 测试tokenizer.json导出和加载规范化流水线，无法等价表示的步骤报错
+#[test]
+fn test_hf_json_normalizer() {
+    let mut tokenizer = wordpiece().unwrap();
+    tokenizer.base.normalizer = Normalizer::parse("nfkc,lowercase").unwrap();
+    tokenizer.train(corpus(), 60).unwrap();
+    let text = "ＨＥＬＬＯ World";
+    let expected = tokenizer.encode(text).unwrap();
+
+    let json = tokenizer.to_hf_json().unwrap().to_json_string().unwrap();
+    let mut loaded = wordpiece().unwrap();
+    loaded
+        .load_hf_json(HfTokenizerJson::parse(&json).unwrap())
+        .unwrap();
+    assert_eq!(loaded.base.normalizer, tokenizer.base.normalizer);
+    assert_eq!(loaded.encode(text).unwrap(), expected);
+
+    tokenizer.base.normalizer = Normalizer::parse("strip_accents").unwrap();
+    assert!(tokenizer
+        .to_hf_json()
+        .and_then(|json| json.to_json_string())
+        .is_err());
+}
+
+/// 测试规范化文本与原文的对齐：各段分别规范化的结果与整段相同，区间换算回原文
+#[test]
+fn test_apply_aligned() {
+    let cases = [
+        ("nfc", "cafe\u{301} x"),
+        ("nfkc,lowercase", "ＨＥＬＬＯ  ﬁle"),
+        ("nfkd,strip_accents", "Ångström naïve"),
+        ("clean_whitespace", "  hello\t\tworld \n "),
+        ("lowercase", "ΟΔΟΣ İstanbul"),
+    ];
+    for (spec, text) in cases {
+        let normalizer = Normalizer::parse(spec).unwrap();
+        let aligned = normalizer.apply_aligned(text);
+        assert_eq!(aligned.text(), normalizer.apply(text), "{}", spec);
+    }
+
+    // 组合附加符号并入前一个字符，"é" 对应原文的 "e\u{301}"
+    let aligned = Normalizer::parse("nfc")
+        .unwrap()
+        .apply_aligned("cafe\u{301} x");
+    assert_eq!(aligned.text(), "café x");
+    assert_eq!(aligned.source_range(0, 3), (0, 3));
+    assert_eq!(aligned.source_range(3, 5), (3, 6));
+    assert_eq!(aligned.source_range(5, 7), (6, 8));
+    assert_eq!(aligned.source_range(7, 7), (8, 8));
+
+    // 全角字符逐个对应；"ﬁ" 展开为两个字符，两者都对应整个 "ﬁ"
+    let text = "ＡＢ ﬁ";
+    let aligned = Normalizer::parse("nfkc,lowercase")
+        .unwrap()
+        .apply_aligned(text);
+    assert_eq!(aligned.text(), "ab fi");
+    assert_eq!(aligned.source_range(1, 2), (3, 6));
+    assert_eq!(aligned.source_range(3, 4), (7, 10));
+    assert_eq!(aligned.source_range(4, 5), (7, 10));
+
+    // 丢弃的空白不对应任何字节，合并后的空格对应整段空白
+    let text = "  a \t b ";
+    let aligned = Normalizer::parse("clean_whitespace")
+        .unwrap()
+        .apply_aligned(text);
+    assert_eq!(aligned.text(), "a b");
+    assert_eq!(aligned.source_range(0, 1), (2, 3));
+    assert_eq!(aligned.source_range(1, 2), (3, 6));
+    assert_eq!(aligned.source_range(2, 3), (6, 7));
+}
+
+/// 测试规范化改写文本后，标记区间仍按原文给出
+#[test]
+fn test_spans_follow_normalization() {
+    // BPE默认做NFC：分解形式的 "é" 得到覆盖两个码点的区间，之后的标记不受影响
+    let mut tokenizer = bpe().unwrap();
+    tokenizer
+        .train(vec!["café x café x café x".to_string()], 300)
+        .unwrap();
+    let text = "cafe\u{301} x";
+    let spans = tokenizer.spans(text).unwrap();
+    let ids: Vec<u32> = spans.iter().map(|&(_, _, id)| id).collect();
+    assert_eq!(ids, tokenizer.encode(text).unwrap());
+    assert_eq!(spans.first().unwrap().0, 0);
+    assert_eq!(spans.last().unwrap().1, text.len());
+    assert!(spans.windows(2).all(|pair| pair[0].1 == pair[1].0));
+    assert!(spans.iter().all(|&(start, end, _)| start < end));
+    assert!(spans.iter().any(|&(_, end, _)| end == "cafe\u{301}".len()));
+
+    // NFKC和小写：全角字母的标记对应原文中的全角字母
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.base.normalizer = Normalizer::parse("nfkc,lowercase").unwrap();
+    tokenizer.train(corpus(), 300).unwrap();
+    let text = "ＨＥＬＬＯ world";
+    let spans = tokenizer.spans(text).unwrap();
+    assert!(spans.iter().all(|&(start, end, _)| start < end));
+    assert!(spans.windows(2).all(|pair| pair[0].1 == pair[1].0));
+    assert_eq!(spans.last().unwrap().1, text.len());
+    let hello_end = "ＨＥＬＬＯ".len();
+    assert!(spans.iter().any(|&(_, end, _)| end == hello_end));
+
+    // 特殊标记保持原样，前后的文本各自规范化
+    let ids = tokenizer.add_special_tokens(&["<|EOT|>"]).unwrap();
+    let text = "ＨＥＬＬＯ<|EOT|>Ｗorld";
+    let spans = tokenizer.spans(text).unwrap();
+    let eot = spans.iter().find(|&&(_, _, id)| id == ids[0]).unwrap();
+    assert_eq!(&text[eot.0..eot.1], "<|EOT|>");
+    assert_eq!(spans.last().unwrap().1, text.len());
+}
+
+/// 测试对不上的未知标记覆盖其间的文本，之后的标记重新对齐
+#[test]
+fn test_spans_resync_after_unknown() {
+    use zero_tokenizer::bpe::UnknownCharFallback;
+
+    let mut tokenizer = bpe().unwrap();
+    tokenizer.unknown_fallback = UnknownCharFallback::Unk;
+    tokenizer
+        .train(vec!["hello world hello world".to_string()], 300)
+        .unwrap();
+    let text = "hello ☃☃ world";
+    let spans = tokenizer.spans(text).unwrap();
+    let covered: Vec<&str> = spans
+        .iter()
+        .map(|&(start, end, _)| &text[start..end])
+        .collect();
+    assert_eq!(covered.concat(), text);
+    assert!(covered.contains(&"☃"));
+    assert_eq!(covered.last().map(|s| s.trim()), Some("world"));
+}
@@ -0,0 +1,316 @@
+//! 截断与补齐测试
+//!
+//! 测试截断策略、溢出块、补齐位置和行宽取整，以及四种分词器的 `encode_plus`
+
+use zero_tokenizer::error::TokenizerError;
+use zero_tokenizer::prelude::*;
+
+fn range(start: u32, end: u32) -> Vec<u32> {
+    (start..end).collect()
+}
+
+#[test]
+fn test_truncation_single() {
+    let ids = range(0, 10);
+    let config = TruncationConfig::new(4);
+    assert_eq!(config.truncate(&ids).unwrap(), [&ids[..4]]);
+    assert_eq!(config.truncate(&ids[..3]).unwrap(), [&ids[..3]]);
+
+    // 溢出块相邻重叠 stride 个标记，最后一块可以较短
+    let config = TruncationConfig::new(4).with_stride(1);
+    assert_eq!(
+        config.truncate(&ids).unwrap(),
+        [&ids[0..4], &ids[3..7], &ids[6..10]]
+    );
+    let config = TruncationConfig::new(5).with_stride(2);
+    assert_eq!(
+        config.truncate(&ids).unwrap(),
+        [&ids[0..5], &ids[3..8], &ids[6..10]]
+    );
+
+    assert!(TruncationConfig::new(0).validate().is_err());
+    assert!(TruncationConfig::new(4).with_stride(4).validate().is_err());
+    assert!(TruncationConfig::new(4).with_stride(3).validate().is_ok());
+
+    // 未经 validate 的配置在需要溢出块时返回错误，而不是panic
+    assert!(TruncationConfig::new(4)
+        .with_stride(8)
+        .truncate(&ids)
+        .is_err());
+    assert!(TruncationConfig::new(4)
+        .with_stride(4)
+        .truncate(&ids)
+        .is_err());
+    assert!(TruncationConfig::new(0)
+        .with_stride(0)
+        .truncate(&ids)
+        .is_err());
+    let short = TruncationConfig::new(4).with_stride(8);
+    assert_eq!(short.truncate(&ids[..3]).unwrap(), [&ids[..3]]);
+}
+
+#[test]
+fn test_truncation_pair_strategies() {
+    let long = range(0, 10);
+    let other = range(100, 110);
+    let short = range(200, 202);
+
+    // longest_first：先截较长的一句，两句都超过一半时第一句多保留一个
+    let config = TruncationConfig::new(3 + 8);
+    assert_eq!(
+        config.truncate_pair(&long, &short, 3).unwrap(),
+        [(&long[..6], &short[..])]
+    );
+    assert_eq!(
+        config.truncate_pair(&short, &long, 3).unwrap(),
+        [(&short[..], &long[..6])]
+    );
+    let config = TruncationConfig::new(3 + 5);
+    assert_eq!(
+        config.truncate_pair(&long, &other, 3).unwrap(),
+        [(&long[..3], &other[..2])]
+    );
+    assert_eq!(
+        config.truncate_pair(&short, &short, 3).unwrap(),
+        [(&short[..], &short[..])]
+    );
+
+    // only_first / only_second 只截断一句，可以产生溢出块
+    let config = TruncationConfig::new(3 + 6)
+        .with_strategy(TruncationStrategy::OnlyFirst)
+        .with_stride(1);
+    assert_eq!(
+        config.truncate_pair(&long, &short, 3).unwrap(),
+        [
+            (&long[0..4], &short[..]),
+            (&long[3..7], &short[..]),
+            (&long[6..10], &short[..])
+        ]
+    );
+    let config = TruncationConfig::new(3 + 6).with_strategy(TruncationStrategy::OnlySecond);
+    assert_eq!(
+        config.truncate_pair(&short, &long, 3).unwrap(),
+        [(&short[..], &long[..4])]
+    );
+
+    // 容纳不下特殊标记、不被截断的一句已占满、longest_first 产生溢出块
+    assert!(TruncationConfig::new(2)
+        .truncate_pair(&short, &short, 3)
+        .is_err());
+    assert!(TruncationConfig::new(3 + 2)
+        .with_strategy(TruncationStrategy::OnlySecond)
+        .truncate_pair(&short, &long, 3)
+        .is_err());
+    assert!(TruncationConfig::new(3 + 4)
+        .with_stride(1)
+        .truncate_pair(&long, &other, 3)
+        .is_err());
+}
+
+#[test]
+fn test_padding_width() {
+    let config = PaddingConfig::new(0);
+    assert_eq!(config.width(7), 7);
+    assert_eq!(config.with_length(5).width(3), 5);
+    // 比固定行宽更长的行不被截断
+    assert_eq!(config.with_length(5).width(7), 7);
+    assert_eq!(config.with_pad_to_multiple_of(4).width(7), 8);
+    assert_eq!(config.with_pad_to_multiple_of(4).width(8), 8);
+    assert_eq!(config.with_length(5).with_pad_to_multiple_of(4).width(3), 8);
+    assert!(config.with_pad_to_multiple_of(0).validate().is_err());
+}
+
+fn check_encode_plus<T: SpecialTokenizer + Sync>(tokenizer: &mut T) {
+    let texts = ["hello world", "hi", "a much longer sentence here"];
+    let encoded: Vec<Vec<u32>> = texts.iter().map(|t| tokenizer.encode(t).unwrap()).collect();
+
+    // 未配置时与 encode 相同，掩码全为1
+    let batch = tokenizer.encode_batch_plus(&texts, None).unwrap();
+    assert_eq!(batch.ids, encoded);
+    assert!(batch.attention_mask.iter().flatten().all(|&m| m == 1));
+    assert!(batch.token_type_ids.iter().flatten().all(|&t| t == 0));
+    assert_eq!(batch.overflow_to_sample, [0, 1, 2]);
+
+    // 右侧补齐到最长的行，行宽取整到8的倍数
+    let pad_id = tokenizer.add_special_tokens(&["[PAD]"]).unwrap()[0];
+    tokenizer
+        .enable_padding(PaddingConfig::new(pad_id).with_pad_to_multiple_of(8))
+        .unwrap();
+    let longest = encoded.iter().map(Vec::len).max().unwrap();
+    let width = longest.div_ceil(8) * 8;
+    let batch = tokenizer.encode_batch_plus(&texts, None).unwrap();
+    for (row, ids) in encoded.iter().enumerate() {
+        assert_eq!(batch.ids[row].len(), width);
+        assert_eq!(&batch.ids[row][..ids.len()], ids.as_slice());
+        assert!(batch.ids[row][ids.len()..].iter().all(|&id| id == pad_id));
+        assert_eq!(
+            batch.attention_mask[row]
+                .iter()
+                .filter(|&&m| m == 1)
+                .count(),
+            ids.len()
+        );
+    }
+
+    // 截断后左侧补齐，真实标记靠右
+    tokenizer
+        .enable_truncation(TruncationConfig::new(4))
+        .unwrap();
+    tokenizer
+        .enable_padding(
+            PaddingConfig::new(pad_id)
+                .with_direction(PaddingDirection::Left)
+                .with_length(6),
+        )
+        .unwrap();
+    let batch = tokenizer.encode_plus("hi", None).unwrap();
+    let hi = &encoded[1];
+    assert_eq!(batch.len(), 1);
+    assert_eq!(&batch.ids[0][6 - hi.len()..], hi.as_slice());
+    assert_eq!(
+        batch.attention_mask[0][..6 - hi.len()],
+        vec![0; 6 - hi.len()]
+    );
+    let batch = tokenizer.encode_batch_plus(&texts, None).unwrap();
+    assert!(batch.attention_mask.iter().all(|mask| mask.len() == 6));
+    assert_eq!(batch.ids[2][2..], encoded[2][..4]);
+    // encode 不截断也不补齐
+    for (text, ids) in texts.iter().zip(&encoded) {
+        assert_eq!(&tokenizer.encode(text).unwrap(), ids);
+    }
+
+    // 溢出块各占一行并记录来源
+    tokenizer
+        .enable_truncation(TruncationConfig::new(4).with_stride(1))
+        .unwrap();
+    tokenizer.no_padding();
+    let batch = tokenizer.encode_batch_plus(&texts, None).unwrap();
+    let chunks = TruncationConfig::new(4).with_stride(1);
+    let expected: Vec<usize> = encoded
+        .iter()
+        .enumerate()
+        .flat_map(|(index, ids)| vec![index; chunks.truncate(ids).unwrap().len()])
+        .collect();
+    assert_eq!(batch.overflow_to_sample, expected);
+    assert!(batch.ids.iter().all(|row| row.len() <= 4));
+
+    // 句子对拼接为 [CLS] A [SEP] B [SEP]，第二句的句子编号为1
+    tokenizer.no_truncation();
+    assert!(tokenizer.encode_plus("hello world", Some("hi")).is_err());
+    let ids = tokenizer.add_special_tokens(&["[CLS]", "[SEP]"]).unwrap();
+    let batch = tokenizer.encode_plus("hello world", Some("hi")).unwrap();
+    let pair = tokenizer.encode_pair("hello world", "hi").unwrap();
+    assert_eq!(batch.ids[0], pair.ids);
+    assert_eq!(batch.token_type_ids, [pair.token_type_ids]);
+    assert_eq!(batch.ids[0][0], ids[0]);
+
+    tokenizer
+        .enable_truncation(
+            TruncationConfig::new(3 + 3).with_strategy(TruncationStrategy::OnlyFirst),
+        )
+        .unwrap();
+    let batch = tokenizer
+        .encode_batch_plus(&["hello world"], Some(&["hi"]))
+        .unwrap();
+    assert_eq!(batch.ids[0].len(), 6);
+    assert!(tokenizer
+        .encode_batch_plus(&["hello world"], Some(&[]))
+        .is_err());
+
+    assert!(tokenizer
+        .enable_truncation(TruncationConfig::new(0))
+        .is_err());
+    assert!(tokenizer
+        .enable_padding(PaddingConfig::new(0).with_pad_to_multiple_of(0))
+        .is_err());
+}
+
+#[test]
+fn test_encode_plus_all_tokenizers() {
+    check_encode_plus(&mut bpe().unwrap());
+    check_encode_plus(&mut bbpe().unwrap());
+    check_encode_plus(&mut unigram().unwrap());
+    check_encode_plus(&mut wordpiece().unwrap());
+}
+
+/// 测试输入长度限制：超出时报错，或截断输入和编码结果；四种分词器的 `encode` 都会检查
+#[test]
+fn test_input_limits() {
+    let corpus = vec!["hello world 你好世界 hello tokenizer".to_string()];
+    let text = "hello world 你好";
+    let mut bpe = bpe().unwrap();
+    bpe.train(corpus.clone(), 300).unwrap();
+    let mut bbpe = bbpe().unwrap();
+    bbpe.train(corpus.clone(), 300).unwrap();
+    let mut unigram = unigram().unwrap();
+    unigram.train(corpus.clone(), 40).unwrap();
+    let mut wordpiece = wordpiece().unwrap();
+    wordpiece.train(corpus, 60).unwrap();
+
+    let tokenizers: [&mut dyn SpecialTokenizer; 4] =
+        [&mut bpe, &mut bbpe, &mut unigram, &mut wordpiece];
+    for tokenizer in tokenizers {
+        let full = tokenizer.encode(text).unwrap();
+
+        tokenizer
+            .set_input_limits(InputLimits {
+                max_input_bytes: Some(14),
+                ..InputLimits::default()
+            })
+            .unwrap();
+        let err = tokenizer.encode(text).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                TokenizerError::InputTooLarge {
+                    bytes: 18,
+                    limit: 14,
+                }
+            ),
+            "{}",
+            err
+        );
+        assert!(tokenizer.encode("hello").is_ok());
+
+        // 截断到字符边界："你" 占第12到14字节，"好" 被整个去掉
+        tokenizer
+            .set_input_limits(InputLimits {
+                max_input_bytes: Some(16),
+                policy: LimitPolicy::Truncate,
+                ..InputLimits::default()
+            })
+            .unwrap();
+        assert_eq!(
+            tokenizer.encode(text).unwrap(),
+            tokenizer.encode("hello world 你").unwrap()
+        );
+
+        tokenizer
+            .set_input_limits(InputLimits {
+                max_tokens: Some(2),
+                ..InputLimits::default()
+            })
+            .unwrap();
+        if full.len() > 2 {
+            assert!(tokenizer.encode(text).is_err());
+        }
+        tokenizer
+            .set_input_limits(InputLimits {
+                max_tokens: Some(2),
+                policy: LimitPolicy::Truncate,
+                ..InputLimits::default()
+            })
+            .unwrap();
+        assert_eq!(tokenizer.encode(text).unwrap(), full[..full.len().min(2)]);
+
+        tokenizer.set_input_limits(InputLimits::default()).unwrap();
+        assert_eq!(tokenizer.encode(text).unwrap(), full);
+    }
+
+    assert!(bpe
+        .set_input_limits(InputLimits {
+            max_tokens: Some(0),
+            ..InputLimits::default()
+        })
+        .is_err());
+}
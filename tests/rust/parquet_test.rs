@@ -0,0 +1,119 @@
+//! Parquet语料读取测试
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+use parquet::arrow::ArrowWriter;
+
+use zero_tokenizer::corpus::{train_from_parquet, ParquetTextReader};
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::temp_path;
+
+fn write_parquet(name: &str, texts: &[Option<&str>]) -> PathBuf {
+    let path = PathBuf::from(temp_path(&format!("{}.parquet", name)));
+    let ids: ArrayRef = Arc::new(Int64Array::from_iter_values(0..texts.len() as i64));
+    let text: ArrayRef = Arc::new(StringArray::from(texts.to_vec()));
+    let batch = RecordBatch::try_from_iter([("id", ids), ("text", text)]).unwrap();
+
+    let file = std::fs::File::create(&path).unwrap();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+    path
+}
+
+fn remove(paths: &[&Path]) {
+    for path in paths {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[test]
+fn test_reader_streams_column_across_files() {
+    let first = write_parquet("reader_a", &[Some("hello world"), None, Some("你好")]);
+    let second = write_parquet("reader_b", &[Some(""), Some("hello there")]);
+
+    let batches: Vec<Vec<String>> = ParquetTextReader::new([&first, &second], "text")
+        .with_batch_size(2)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    remove(&[&first, &second]);
+
+    // 第一个文件3行分为两批，空值被跳过
+    assert_eq!(batches.len(), 3);
+    let texts: Vec<String> = batches.into_iter().flatten().collect();
+    assert_eq!(texts, vec!["hello world", "你好", "", "hello there"]);
+}
+
+#[test]
+fn test_reader_shuffle_is_deterministic() {
+    let rows: Vec<String> = (0..40).map(|i| format!("row {}", i)).collect();
+    let (first_rows, second_rows) = rows.split_at(20);
+    let first_rows: Vec<Option<&str>> = first_rows.iter().map(|s| Some(s.as_str())).collect();
+    let second_rows: Vec<Option<&str>> = second_rows.iter().map(|s| Some(s.as_str())).collect();
+    let first = write_parquet("shuffle_a", &first_rows);
+    let second = write_parquet("shuffle_b", &second_rows);
+
+    let read = |seed: u64| -> Vec<Vec<String>> {
+        ParquetTextReader::new([&first, &second], "text")
+            .with_batch_size(8)
+            .with_shuffle(seed)
+            .collect::<Result<_, _>>()
+            .unwrap()
+    };
+    let a = read(7);
+    let b = read(7);
+    let c = read(8);
+    remove(&[&first, &second]);
+
+    // 同样的种子和分片列表得到同样的批次，批大小不变，内容是原数据的一个排列
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(a.iter().map(Vec::len).sum::<usize>(), 40);
+    let mut texts: Vec<String> = a.into_iter().flatten().collect();
+    assert_ne!(texts, rows);
+    texts.sort();
+    let mut sorted = rows.clone();
+    sorted.sort();
+    assert_eq!(texts, sorted);
+}
+
+#[test]
+fn test_reader_rejects_missing_and_non_string_columns() {
+    let path = write_parquet("columns", &[Some("hello")]);
+
+    let missing = ParquetTextReader::new([&path], "content").next().unwrap();
+    assert!(missing.unwrap_err().to_string().contains("content"));
+
+    let non_string = ParquetTextReader::new([&path], "id").next().unwrap();
+    assert!(non_string
+        .unwrap_err()
+        .to_string()
+        .contains("不是字符串类型"));
+
+    remove(&[&path]);
+}
+
+#[test]
+fn test_train_from_parquet_matches_in_memory_training() {
+    let texts = ["hello world", "hello there", "world of words"];
+    let path = write_parquet("train", &texts.map(Some));
+
+    let mut tokenizer = bbpe().unwrap();
+    let result = train_from_parquet(&mut tokenizer, [&path], "text", 265);
+    remove(&[&path]);
+    result.unwrap();
+
+    let mut expected = bbpe().unwrap();
+    expected
+        .train(texts.iter().map(|t| t.to_string()).collect(), 265)
+        .unwrap();
+    assert_eq!(tokenizer.vocab_size(), expected.vocab_size());
+    assert_eq!(
+        tokenizer.encode("hello words").unwrap(),
+        expected.encode("hello words").unwrap()
+    );
+}
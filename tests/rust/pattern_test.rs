@@ -3,6 +3,7 @@
 //! 测试自定义正则表达式模式功能
 
 use zero_tokenizer::bbpe::BBPETokenizer;
+use zero_tokenizer::prelude::*;
 
 #[cfg(feature = "python")]
 use zero_tokenizer::bpe::Tokenizer as BPETokenizer;
@@ -30,7 +31,7 @@ fn test_bpe_custom_pattern_word_only() {
 fn test_bbpe_custom_pattern_chinese() {
     // 自定义模式: 匹配中文字符
     let pattern = r"[\u4e00-\u9fa5]+".to_string();
-    let mut tokenizer = BBPETokenizer::with_pattern(pattern).unwrap();
+    let mut tokenizer = BBPETokenizer::with_pattern_internal(pattern).unwrap();
 
     let text = "你好世界Hello";
     tokenizer.train(vec![text.to_string()], 300).unwrap();
@@ -46,7 +47,7 @@ fn test_bbpe_custom_pattern_chinese() {
 fn test_bbpe_custom_pattern_digits() {
     // 匹配数字
     let pattern = r"\d+".to_string();
-    let mut tokenizer = BBPETokenizer::with_pattern(pattern).unwrap();
+    let mut tokenizer = BBPETokenizer::with_pattern_internal(pattern).unwrap();
 
     let text = "123 456 789";
     tokenizer.train(vec![text.to_string()], 300).unwrap();
@@ -59,7 +60,7 @@ fn test_bbpe_custom_pattern_digits() {
 fn test_bbpe_custom_pattern_alphanumeric() {
     // 匹配字母数字
     let pattern = r"[a-zA-Z0-9]+".to_string();
-    let mut tokenizer = BBPETokenizer::with_pattern(pattern).unwrap();
+    let mut tokenizer = BBPETokenizer::with_pattern_internal(pattern).unwrap();
 
     let text = "Test123 Hello456";
     tokenizer.train(vec![text.to_string()], 300).unwrap();
@@ -83,7 +84,7 @@ fn test_bpe_invalid_regex_pattern() {
 fn test_bbpe_invalid_regex_pattern() {
     // 无效的正则表达式
     let pattern = "[unclosed(".to_string();
-    let result = BBPETokenizer::with_pattern(pattern);
+    let result = BBPETokenizer::with_pattern_internal(pattern);
     assert!(result.is_err());
 }
 
@@ -91,21 +92,18 @@ fn test_bbpe_invalid_regex_pattern() {
 fn test_bbpe_empty_pattern() {
     // 空模式（可能导致问题）
     let pattern = "".to_string();
-    let result = BBPETokenizer::with_pattern(pattern);
+    let result = BBPETokenizer::with_pattern_internal(pattern);
 
     // 根据实现，可能返回错误或使用默认模式
     // 这里我们只验证不会panic
-    match result {
-        Ok(_) => {}
-        Err(_) => {}
-    }
+    let _ = result;
 }
 
 #[test]
 fn test_bbpe_whitespace_pattern() {
     // 匹配空白字符
     let pattern = r"\s+".to_string();
-    let mut tokenizer = BBPETokenizer::with_pattern(pattern).unwrap();
+    let mut tokenizer = BBPETokenizer::with_pattern_internal(pattern).unwrap();
 
     let text = "   \t\n  ";
     tokenizer.train(vec![text.to_string()], 300).unwrap();
@@ -119,7 +117,7 @@ fn test_bbpe_whitespace_pattern() {
 fn test_bbpe_mixed_pattern() {
     // 混合模式：单词或数字
     let pattern = r"\w+|\d+".to_string();
-    let mut tokenizer = BBPETokenizer::with_pattern(pattern).unwrap();
+    let mut tokenizer = BBPETokenizer::with_pattern_internal(pattern).unwrap();
 
     let text = "Hello 123 World 456";
     tokenizer.train(vec![text.to_string()], 300).unwrap();
@@ -134,7 +132,7 @@ fn test_bbpe_pattern_with_unicode() {
     let pattern = r"[\p{L}\p{N}]+".to_string();
 
     // 注意：fancy_regex支持\p{L}等Unicode类别
-    match BBPETokenizer::with_pattern(pattern) {
+    match BBPETokenizer::with_pattern_internal(pattern) {
         Ok(mut tokenizer) => {
             let text = "Hello你好123";
             tokenizer.train(vec![text.to_string()], 300).unwrap();
@@ -167,7 +165,7 @@ fn test_bbpe_default_pattern() {
 fn test_pattern_persistence() {
     // 测试模式在训练后是否保持
     let pattern = r"\w+".to_string();
-    let mut tokenizer = BBPETokenizer::with_pattern(pattern.clone()).unwrap();
+    let mut tokenizer = BBPETokenizer::with_pattern_internal(pattern.clone()).unwrap();
 
     tokenizer.train(vec!["test".to_string()], 300).unwrap();
 
@@ -176,3 +174,128 @@ fn test_pattern_persistence() {
     let tokens = tokenizer.encode(text).unwrap();
     assert!(!tokens.is_empty());
 }
+
+/// 测试单次编码改用其他预分词方式，分词器本身的模式保持不变
+#[test]
+fn test_encode_with_pattern_override() {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world 12345 hello world 12345".to_string()], 300)
+        .unwrap();
+    let text = "hello world 12345";
+    let default = tokenizer.encode(text).unwrap();
+
+    // 预设名称与默认模式相同时结果一致
+    assert_eq!(
+        tokenizer.encode_with_pattern(text, "gpt4").unwrap(),
+        default
+    );
+
+    // 按单个字符切分时不会发生合并
+    let chars = tokenizer.encode_with_pattern(text, ".").unwrap();
+    assert_eq!(chars.len(), text.len());
+    assert_eq!(tokenizer.decode(&chars).unwrap(), text);
+
+    // GPT-2 模式不限制数字长度，与GPT-4的三位分组切分不同
+    let gpt2 = tokenizer.encode_with_pattern(text, "gpt2").unwrap();
+    assert_eq!(tokenizer.decode(&gpt2).unwrap(), text);
+
+    assert!(tokenizer.encode_with_pattern(text, "(").is_err());
+    assert_eq!(tokenizer.encode(text).unwrap(), default);
+}
+
+/// 测试所有分词器都支持单次覆盖预分词模式
+#[test]
+fn test_encode_with_pattern_all_tokenizers() {
+    use zero_tokenizer::base::tokenizer_base::compile_pattern;
+
+    let regex = compile_pattern(r"\S+|\s+").unwrap();
+    let texts = vec!["hello world hello world".to_string()];
+    let text = "hello  world";
+
+    let mut bpe = bpe().unwrap();
+    bpe.train(texts.clone(), 300).unwrap();
+    let ids = bpe.encode_with_regex(text, &regex).unwrap();
+    assert_eq!(bpe.decode(&ids).unwrap(), text);
+
+    let mut unigram = unigram().unwrap();
+    unigram.train(texts.clone(), 3600).unwrap();
+    let ids = unigram.encode_with_pattern(text, r"\S+|\s+").unwrap();
+    assert_eq!(unigram.decode(&ids).unwrap(), text);
+
+    let mut wordpiece = wordpiece().unwrap();
+    wordpiece.train(texts, 300).unwrap();
+    let ids = wordpiece.encode_with_pattern(text, "gpt2").unwrap();
+    assert_eq!(wordpiece.decode(&ids).unwrap(), text);
+}
+
+/// 切分结果，用于检查预设模式
+fn split(preset: &str, text: &str) -> Vec<String> {
+    use zero_tokenizer::base::tokenizer_base::{compile_pattern, split_with};
+
+    split_with(&compile_pattern(preset).unwrap(), text).unwrap()
+}
+
+/// 测试代码预设：换行单独成片，行首缩进整体成片，标识符保持完整
+#[test]
+fn test_code_pattern_preset() {
+    let text = "def get_value(self):\n    if self.x:\n\t\treturn 1000\n";
+    let parts = split("code", text);
+    assert_eq!(parts.concat(), text, "切分结果应覆盖全部文本");
+    assert!(parts.contains(&" get_value".to_string()));
+    assert!(parts.contains(&"    ".to_string()));
+    assert!(parts.contains(&"\t\t".to_string()));
+    assert_eq!(parts.iter().filter(|part| *part == "\n").count(), 3);
+    assert!(parts.contains(&"):".to_string()), "标点不应吞并后面的换行");
+
+    // GPT-4模式把换行并入前面的标点
+    assert!(split("gpt4", text).contains(&"):\n".to_string()));
+}
+
+/// 测试在 camelCase 和 snake_case 边界拆分标识符
+#[test]
+fn test_code_subwords_pattern_preset() {
+    let text = "let getHTTPResponse_code = __init__(值x, utf8);\r\n";
+    let parts = split("code_subwords", text);
+    assert_eq!(parts.concat(), text);
+    for expected in [
+        " get", "HTTP", "Response", "_code", " __init", "__", " utf", "8", "\r\n",
+    ] {
+        assert!(
+            parts.contains(&expected.to_string()),
+            "缺少 {:?}: {:?}",
+            expected,
+            parts
+        );
+    }
+}
+
+/// 测试使用预设名称创建分词器时保存完整的正则表达式
+#[test]
+fn test_pattern_preset_by_name() {
+    use zero_tokenizer::base::tokenizer_base::CODE_PATTERN;
+
+    let mut tokenizer = BBPETokenizer::with_pattern_internal("code".to_string()).unwrap();
+    assert_eq!(tokenizer.base.pattern, CODE_PATTERN);
+
+    let code = "fn main() {\n    println!(\"hi\");\n}\n".repeat(20);
+    tokenizer.train(vec![code.clone()], 300).unwrap();
+    let ids = tokenizer.encode(&code).unwrap();
+    assert_eq!(tokenizer.decode(&ids).unwrap(), code);
+}
+
+/// 测试tiktoken预设：o200k按大小写边界拆分单词，缩写跟随前面的单词
+#[test]
+fn test_tiktoken_pattern_presets() {
+    let tokenizer = BBPETokenizer::with_pattern_internal("o200k".to_string()).unwrap();
+    assert_eq!(
+        tokenizer.base.split_text("HelloWorld's 12345").unwrap(),
+        vec!["Hello", "World's", " ", "123", "45"]
+    );
+
+    let tokenizer = BBPETokenizer::with_pattern_internal("cl100k".to_string()).unwrap();
+    assert_eq!(
+        tokenizer.base.split_text("hello world's").unwrap(),
+        vec!["hello", " world", "'s"]
+    );
+}
@@ -0,0 +1,203 @@
+//! 离线批量编码管线测试
+
+use std::io::Cursor;
+
+use zero_tokenizer::pipeline::{
+    encode_jsonl_stream, encode_stream, IdSink, JsonlFields, LengthPrefixedWriter, NpyShardWriter,
+    StreamConfig,
+};
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::temp_path;
+
+fn trained_tokenizer() -> BBPE {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello world".to_string()], 270)
+        .unwrap();
+    tokenizer
+}
+
+/// 解析长度前缀格式
+fn read_length_prefixed(bytes: &[u8]) -> Vec<Vec<u32>> {
+    let words: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    let mut rows = Vec::new();
+    let mut rest = &words[..];
+    while let Some((&len, tail)) = rest.split_first() {
+        let (row, tail) = tail.split_at(len as usize);
+        rows.push(row.to_vec());
+        rest = tail;
+    }
+    rows
+}
+
+/// 测试多线程编码后仍按原顺序写出
+#[test]
+fn test_encode_stream_preserves_order() {
+    let tokenizer = trained_tokenizer();
+    let lines: Vec<String> = (0..500)
+        .map(|i| format!("hello {} world {}", i, "你好".repeat(i % 5)))
+        .collect();
+    let corpus = lines.join("\n");
+
+    let config = StreamConfig {
+        workers: 4,
+        chunk_lines: 7,
+        max_in_flight: 3,
+    };
+    let mut sink = LengthPrefixedWriter::new(Vec::new());
+    let stats = encode_stream(&tokenizer, Cursor::new(corpus), &mut sink, config).unwrap();
+
+    let rows = read_length_prefixed(&sink.into_inner());
+    assert_eq!(rows.len(), lines.len());
+    assert_eq!(stats.texts, lines.len());
+    assert_eq!(stats.tokens, rows.iter().map(Vec::len).sum::<usize>());
+    assert_eq!(stats.bytes, lines.iter().map(String::len).sum::<usize>());
+    for (line, row) in lines.iter().zip(&rows) {
+        assert_eq!(&tokenizer.encode(line).unwrap(), row);
+    }
+}
+
+/// 测试写出失败时管线停止并返回错误
+#[test]
+fn test_encode_stream_sink_error() {
+    struct FailingSink(usize);
+
+    impl IdSink for FailingSink {
+        fn write_ids(&mut self, _ids: &[u32]) -> std::io::Result<()> {
+            self.0 += 1;
+            if self.0 > 10 {
+                return Err(std::io::Error::other("磁盘已满"));
+            }
+            Ok(())
+        }
+    }
+
+    let tokenizer = trained_tokenizer();
+    let corpus = "hello world\n".repeat(10_000);
+    let config = StreamConfig {
+        workers: 2,
+        chunk_lines: 4,
+        max_in_flight: 2,
+    };
+    let err =
+        encode_stream(&tokenizer, Cursor::new(corpus), &mut FailingSink(0), config).unwrap_err();
+    assert!(err.to_string().contains("磁盘已满"));
+}
+
+/// 测试按固定标记数写出npy分片
+#[test]
+fn test_npy_shards() {
+    let tokenizer = trained_tokenizer();
+    let dir = std::path::PathBuf::from(temp_path("npy"));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // 词汇表之外的ID，模拟文档结束标记
+    let separator = tokenizer.vocab_size() as u32;
+    let mut sink = NpyShardWriter::new(&dir, "train", 16)
+        .unwrap()
+        .with_separator(separator);
+    let corpus = "hello world\nhello\n你好世界\n".repeat(5);
+    let stats = encode_stream(
+        &tokenizer,
+        Cursor::new(corpus),
+        &mut sink,
+        StreamConfig::default(),
+    )
+    .unwrap();
+
+    let total = stats.tokens + stats.texts;
+    assert_eq!(sink.shards().len(), total.div_ceil(16));
+
+    let mut ids = Vec::new();
+    for path in sink.shards() {
+        let bytes = std::fs::read(path).unwrap();
+        assert_eq!(&bytes[..6], b"\x93NUMPY");
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+        assert!(header.contains("'descr': '<u4'"));
+        let data = &bytes[10 + header_len..];
+        assert!(header.contains(&format!("'shape': ({},)", data.len() / 4)));
+        ids.extend(
+            data.chunks_exact(4)
+                .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap())),
+        );
+    }
+    assert_eq!(ids.len(), total);
+    assert_eq!(
+        ids.iter().filter(|&&id| id == separator).count(),
+        stats.texts
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// 测试JSONL记录按原顺序写回标记ID和标记数，其余字段保留
+#[test]
+fn test_encode_jsonl_stream() {
+    let tokenizer = trained_tokenizer();
+    let records: Vec<String> = (0..200)
+        .map(|i| {
+            serde_json::json!({"id": i, "text": format!("hello {}", i), "title": "世界"})
+                .to_string()
+        })
+        .collect();
+    let config = StreamConfig {
+        workers: 3,
+        chunk_lines: 5,
+        max_in_flight: 2,
+    };
+    let fields = JsonlFields::new(["text", "title"]).with_counts();
+    let mut output = Vec::new();
+    let stats = encode_jsonl_stream(
+        &tokenizer,
+        Cursor::new(records.join("\n")),
+        &mut output,
+        &fields,
+        config,
+    )
+    .unwrap();
+    assert_eq!(stats.texts, records.len());
+
+    let lines: Vec<serde_json::Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), records.len());
+    let mut tokens = 0;
+    for (i, record) in lines.iter().enumerate() {
+        assert_eq!(record["id"], i);
+        let text = record["text"].as_str().unwrap();
+        let ids: Vec<u32> = serde_json::from_value(record["text_ids"].clone()).unwrap();
+        assert_eq!(ids, tokenizer.encode(text).unwrap());
+        assert_eq!(record["text_num_tokens"], ids.len());
+        let title_ids = record["title_ids"].as_array().unwrap();
+        assert_eq!(record["title_num_tokens"], title_ids.len());
+        tokens += ids.len() + title_ids.len();
+    }
+    assert_eq!(stats.tokens, tokens);
+}
+
+/// 测试JSONL记录缺少字段时返回带行号的错误
+#[test]
+fn test_encode_jsonl_stream_missing_field() {
+    let tokenizer = trained_tokenizer();
+    let input = "{\"text\": \"hello\"}\n{\"body\": \"world\"}\n";
+    let mut output = Vec::new();
+    let err = encode_jsonl_stream(
+        &tokenizer,
+        Cursor::new(input),
+        &mut output,
+        &JsonlFields::new(["text"]),
+        StreamConfig::default(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("第 1 行"), "{}", err);
+    assert!(err.to_string().contains("text"), "{}", err);
+}
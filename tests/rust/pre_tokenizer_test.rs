@@ -0,0 +1,198 @@
+//! 预分词流水线测试
+//!
+//! 测试各内置步骤的切分结果、步骤的组合、训练与编码使用相同的流水线，
+//! 以及预分词流水线随模型保存和加载
+
+use zero_tokenizer::base::hf_json::HfTokenizerJson;
+use zero_tokenizer::base::tokenizer_base::{compile_pattern, split_with};
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::temp_path;
+
+fn corpus() -> Vec<String> {
+    vec!["Hello, world! The year 2024 was great, and 2025 will be better. Hello world!".to_string()]
+}
+
+/// 用 `spec` 描述的流水线切分 `text`，正则表达式步骤使用 `pattern`
+fn split(spec: &str, pattern: &str, text: &str) -> Vec<String> {
+    let pipeline = PreTokenizerPipeline::parse(spec).unwrap();
+    split_with(
+        &pipeline.with_pattern(&compile_pattern(pattern).unwrap()),
+        text,
+    )
+    .unwrap()
+}
+
+/// 测试各内置步骤单独使用时的切分结果
+#[test]
+fn test_steps() {
+    assert_eq!(
+        split("whitespace", "gpt4", "Hello, world!!"),
+        ["Hello", ",", "world", "!!"]
+    );
+    assert_eq!(
+        split("byte_level", "gpt4", "Hello world's"),
+        ["Hello", " world", "'s"]
+    );
+    assert_eq!(
+        split("metaspace", "gpt4", "Hello  big world"),
+        ["Hello", " ", " big", " world"]
+    );
+    assert_eq!(split("digits", "gpt4", "abc123"), ["abc", "1", "2", "3"]);
+    assert_eq!(
+        split("punctuation", "gpt4", "a,b!!$"),
+        ["a", ",", "b", "!", "!", "$"]
+    );
+
+    // 正则表达式步骤丢弃匹配之间的文本，没有任何匹配时按空白分割
+    assert_eq!(split("regex", r"\d+", "ab 12 cd 3"), ["12", "3"]);
+    assert_eq!(split("regex", r"\d+", "ab cd"), ["ab", "cd"]);
+    assert_eq!(split("none", "gpt4", "ab cd"), ["ab cd"]);
+    assert!(split("none", "gpt4", "").is_empty());
+}
+
+/// 测试后面的步骤细分前面步骤得到的每个片段
+#[test]
+fn test_steps_compose() {
+    assert_eq!(
+        split("whitespace,digits", "gpt4", "abc 2024!"),
+        ["abc", "2", "0", "2", "4", "!"]
+    );
+    assert_eq!(
+        split("metaspace,digits", "gpt4", "in 2024 year"),
+        ["in", " ", "2", "0", "2", "4", " year"]
+    );
+    assert_eq!(
+        split("regex,punctuation", "gpt4", "Hello, world..."),
+        ["Hello", ",", " world", ".", ".", "."]
+    );
+}
+
+/// 测试流水线名称解析与 `spec` 互逆
+#[test]
+fn test_parse_and_spec() {
+    let pipeline = PreTokenizerPipeline::parse("whitespace, digits,punctuation").unwrap();
+    assert_eq!(
+        pipeline.steps(),
+        [
+            PreTokenizerStep::Whitespace,
+            PreTokenizerStep::Digits,
+            PreTokenizerStep::Punctuation
+        ]
+    );
+    assert_eq!(pipeline.spec(), "whitespace,digits,punctuation");
+    assert_eq!(
+        PreTokenizerPipeline::parse(&pipeline.spec()).unwrap(),
+        pipeline
+    );
+    assert!(!pipeline.is_default());
+
+    assert!(PreTokenizerPipeline::default().is_default());
+    assert_eq!(PreTokenizerPipeline::default().spec(), "regex");
+    assert!(PreTokenizerPipeline::parse("none")
+        .unwrap()
+        .steps()
+        .is_empty());
+    assert!(PreTokenizerPipeline::parse("").is_err());
+    assert!(PreTokenizerPipeline::parse("regex,bert")
+        .unwrap_err()
+        .to_string()
+        .contains("bert"));
+}
+
+/// 测试四种分词器在训练和编码时都使用预分词流水线，标记不会跨越片段边界
+#[test]
+fn test_train_and_encode_use_pipeline() {
+    let digits = PreTokenizerPipeline::parse("metaspace,digits").unwrap();
+    let check = |tokenizer: &dyn Tokenizer<TokenId = u32>| {
+        // 数字逐个切分，即使语料中反复出现 "2024" 也不会合并成一个标记
+        assert_eq!(tokenizer.encode("2024").unwrap().len(), 4);
+    };
+
+    let mut bpe = bpe().unwrap();
+    bpe.base.pre_tokenizer = digits.clone();
+    bpe.train(corpus(), 300).unwrap();
+    check(&bpe);
+
+    let mut bbpe = bbpe().unwrap();
+    bbpe.base.pre_tokenizer = digits.clone();
+    bbpe.train(corpus(), 400).unwrap();
+    check(&bbpe);
+    let text = "Hello world, 2024!";
+    assert_eq!(bbpe.decode(&bbpe.encode(text).unwrap()).unwrap(), text);
+
+    let mut unigram = unigram().unwrap();
+    unigram.base.pre_tokenizer = digits.clone();
+    unigram.train(corpus(), 60).unwrap();
+    check(&unigram);
+
+    // BERT风格：空白被丢弃，标点单独成片
+    let mut wordpiece = wordpiece().unwrap();
+    wordpiece.base.pre_tokenizer = PreTokenizerPipeline::parse("whitespace,punctuation").unwrap();
+    wordpiece.train(corpus(), 80).unwrap();
+    assert_eq!(
+        wordpiece.encode("Hello,world").unwrap(),
+        wordpiece.encode("Hello ,  world").unwrap()
+    );
+}
+
+/// 测试文本格式和二进制格式保存后重新加载，预分词流水线不变
+#[test]
+fn test_save_load_preserves_pipeline() {
+    let text = "Hello 2024, world!";
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.base.pre_tokenizer = PreTokenizerPipeline::parse("regex,digits").unwrap();
+    tokenizer.train(corpus(), 400).unwrap();
+    let expected = tokenizer.encode(text).unwrap();
+
+    let path = temp_path("pre_tokenizer_text.model");
+    tokenizer.save(&path).unwrap();
+    let mut loaded = bbpe().unwrap();
+    loaded.load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(loaded.base.pre_tokenizer, tokenizer.base.pre_tokenizer);
+    assert_eq!(loaded.encode(text).unwrap(), expected);
+
+    let path = temp_path("pre_tokenizer_binary.bin");
+    tokenizer.save_binary(&path).unwrap();
+    let mut loaded = bbpe().unwrap();
+    loaded.load_binary(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(loaded.base.pre_tokenizer, tokenizer.base.pre_tokenizer);
+    assert_eq!(loaded.encode(text).unwrap(), expected);
+
+    // 使用默认流水线的模型加载后恢复默认流水线
+    let plain = wordpiece().unwrap();
+    let path = temp_path("pre_tokenizer_plain.model");
+    plain.save(&path).unwrap();
+    let mut reloaded = wordpiece().unwrap();
+    reloaded.base.pre_tokenizer = PreTokenizerPipeline::parse("whitespace").unwrap();
+    reloaded.load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert!(reloaded.base.pre_tokenizer.is_default());
+}
+
+/// 测试tokenizer.json导出和加载预分词流水线，无法等价表示的步骤报错
+#[test]
+fn test_hf_json_pipeline() {
+    let mut tokenizer = wordpiece().unwrap();
+    tokenizer.base.pre_tokenizer = PreTokenizerPipeline::parse("whitespace,punctuation").unwrap();
+    tokenizer.train(corpus(), 80).unwrap();
+    let text = "Hello, world 2024!";
+    let expected = tokenizer.encode(text).unwrap();
+
+    let json = tokenizer.to_hf_json().unwrap().to_json_string().unwrap();
+    let mut loaded = wordpiece().unwrap();
+    loaded
+        .load_hf_json(HfTokenizerJson::parse(&json).unwrap())
+        .unwrap();
+    assert_eq!(loaded.base.pre_tokenizer, tokenizer.base.pre_tokenizer);
+    assert_eq!(loaded.encode(text).unwrap(), expected);
+
+    tokenizer.base.pre_tokenizer = PreTokenizerPipeline::parse("metaspace").unwrap();
+    assert!(tokenizer
+        .to_hf_json()
+        .and_then(|json| json.to_json_string())
+        .is_err());
+}
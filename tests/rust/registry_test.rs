@@ -0,0 +1,110 @@
+//! 本地模型注册表测试
+//!
+//! 测试按名称保存和加载、列出模型、类型检查以及名称校验
+
+use zero_tokenizer::base::binary::ModelKind;
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::temp_path;
+
+fn temp_root(name: &str) -> std::path::PathBuf {
+    let root = std::path::PathBuf::from(temp_path(name));
+    let _ = std::fs::remove_dir_all(&root);
+    root
+}
+
+fn trained_bbpe() -> BBPE {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello there".to_string()], 270)
+        .unwrap();
+    tokenizer
+}
+
+#[test]
+fn test_save_and_load_by_name() {
+    let root = temp_root("registry_roundtrip");
+    let registry = ModelRegistry::new(&root);
+    assert_eq!(registry.list().unwrap(), Vec::new());
+    assert!(!registry.contains("en-small"));
+
+    let tokenizer = trained_bbpe();
+    let path = registry.save(&tokenizer, "en-small").unwrap();
+    assert_eq!(path, registry.model_path("en-small").unwrap());
+    assert!(registry.contains("en-small"));
+
+    let mut loaded = bbpe().unwrap();
+    registry.load(&mut loaded, "en-small").unwrap();
+    let text = "hello world";
+    assert_eq!(
+        loaded.encode(text).unwrap(),
+        tokenizer.encode(text).unwrap()
+    );
+
+    let entry = registry.get("en-small").unwrap();
+    assert_eq!(entry.kind, ModelKind::Bbpe);
+    assert_eq!(entry.vocab_size, tokenizer.vocab_size());
+    assert!(entry.saved_at > 0);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_list_models_sorted_by_name() {
+    let root = temp_root("registry_list");
+    let registry = ModelRegistry::new(&root);
+
+    let mut wp = wordpiece().unwrap();
+    wp.train(vec!["hello world".to_string()], wp.vocab_size() as u32 + 10)
+        .unwrap();
+    registry.save(&wp, "zh.wordpiece").unwrap();
+    registry.save(&trained_bbpe(), "en_v2").unwrap();
+    // 不含元数据的目录被跳过
+    std::fs::create_dir_all(root.join("broken")).unwrap();
+
+    let models = registry.list().unwrap();
+    let names: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["en_v2", "zh.wordpiece"]);
+    assert_eq!(models[1].kind, ModelKind::WordPiece);
+
+    // 同名保存覆盖原模型
+    let mut small = bbpe().unwrap();
+    small.train(vec!["hello world".to_string()], 258).unwrap();
+    registry.save(&small, "en_v2").unwrap();
+    assert_eq!(
+        registry.get("en_v2").unwrap().vocab_size,
+        small.vocab_size()
+    );
+
+    registry.remove("en_v2").unwrap();
+    assert!(!registry.contains("en_v2"));
+    assert!(registry.remove("en_v2").is_err());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_load_checks_kind() {
+    let root = temp_root("registry_kind");
+    let registry = ModelRegistry::new(&root);
+    registry.save(&trained_bbpe(), "bytes").unwrap();
+
+    let mut tokenizer = bpe().unwrap();
+    let err = registry.load(&mut tokenizer, "bytes").unwrap_err();
+    assert!(err.to_string().contains("bbpe"), "{}", err);
+    assert!(registry.load(&mut bbpe().unwrap(), "missing").is_err());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_invalid_names_are_rejected() {
+    let registry = ModelRegistry::new(temp_root("registry_names"));
+    let tokenizer = trained_bbpe();
+    for name in ["", "..", "../escape", "a/b", ".hidden", "名字"] {
+        assert!(registry.save(&tokenizer, name).is_err(), "{:?}", name);
+        assert!(registry.model_path(name).is_err(), "{:?}", name);
+    }
+    assert!(registry.model_path("team-en_1.0").is_ok());
+}
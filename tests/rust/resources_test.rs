@@ -0,0 +1,168 @@
+//! 资源加载测试
+//!
+//! 测试Unigram和WordPiece可以从自定义路径或内嵌字表加载常用汉字，也可以不预置任何字符
+
+use std::path::PathBuf;
+
+use zero_tokenizer::base::resources::{resolve_dict_file, ResourceConfig};
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::temp_path;
+
+/// 仓库中的常用汉字字表，使用绝对路径，不依赖当前工作目录
+fn repo_char_dict() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("dict/常用汉字字表.txt")
+}
+
+fn without_chars() -> ResourceConfig {
+    ResourceConfig::without_char_dict()
+}
+
+/// 测试不预置字符时初始词汇表只有字节标记
+#[test]
+fn test_without_char_dict() {
+    let unigram = Unigram::with_resources(&without_chars()).unwrap();
+    assert_eq!(unigram.vocab_size(), 256);
+    assert_eq!(unigram.scores.len(), 256);
+
+    let wordpiece = WordPiece::with_resources(&without_chars()).unwrap();
+    assert_eq!(wordpiece.vocab_size(), 256);
+    let bert = WordPiece::with_bert_special_tokens_and_resources(&without_chars()).unwrap();
+    assert!(bert.has_bert_special_tokens());
+    assert_eq!(bert.vocab_size(), 256 + 5);
+
+    // 没有预置字符时中文按字节标记编码，仍能无损还原
+    let mut unigram = Unigram::with_resources(&without_chars()).unwrap();
+    unigram
+        .train(vec!["你好，世界。你好！".to_string()], 300)
+        .unwrap();
+    let ids = unigram.encode("你好世界").unwrap();
+    assert_eq!(unigram.decode(&ids).unwrap(), "你好世界");
+}
+
+/// 测试从自定义路径加载字表，结果与默认字表一致
+#[test]
+fn test_custom_char_dict_path() {
+    let entries = ResourceConfig::default()
+        .with_char_dict_path(repo_char_dict())
+        .char_dict_entries()
+        .unwrap();
+    assert!(entries.len() > 1000);
+
+    let resources = ResourceConfig::default().with_char_dict_path(repo_char_dict());
+    let unigram = Unigram::with_resources(&resources).unwrap();
+    assert_eq!(unigram.vocab_size(), 256 + entries.len());
+    let wordpiece = WordPiece::with_resources(&resources).unwrap();
+    assert_eq!(wordpiece.vocab_size(), 256 + entries.len());
+
+    // 较小的自定义字表，空行和首尾空白被忽略
+    let path = temp_path("chars.txt");
+    std::fs::write(&path, "甲\r\n\n 乙 \n丙").unwrap();
+    let resources = ResourceConfig::default().with_char_dict_path(&path);
+    let unigram = Unigram::with_resources(&resources).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(unigram.vocab_size(), 259);
+    assert_eq!(unigram.encode("乙").unwrap(), vec![257]);
+}
+
+/// 测试字表不存在时返回说明如何配置的错误
+#[test]
+fn test_missing_char_dict_error() {
+    let resources = ResourceConfig::default().with_char_dict_path("no/such/chars.txt");
+    let Err(error) = Unigram::with_resources(&resources) else {
+        panic!("missing char dict should fail");
+    };
+    assert!(error.to_string().contains("no/such/chars.txt"), "{}", error);
+    assert!(error.to_string().contains("ResourceConfig"), "{}", error);
+    assert!(WordPiece::with_resources(&resources).is_err());
+}
+
+/// 测试词表文件路径存在时直接使用，否则在 `dict` 目录下查找
+#[test]
+fn test_resolve_dict_file() {
+    let path = repo_char_dict();
+    assert_eq!(resolve_dict_file(path.to_str().unwrap()), path);
+    assert_eq!(
+        resolve_dict_file("no_such_vocab.txt"),
+        PathBuf::from("dict").join("no_such_vocab.txt")
+    );
+}
+
+/// 子进程中要加载的模型所在目录，由 [`test_load_outside_repo`] 设置
+const CHILD_MODEL_DIR: &str = "ZT_RESOURCES_TEST_MODEL_DIR";
+
+/// 测试在没有 `dict` 目录的工作目录中加载已保存的Unigram和WordPiece模型
+///
+/// 工作目录是进程级状态，因此在临时目录中重新运行本测试的子进程里加载
+#[test]
+fn test_load_outside_repo() {
+    use zero_tokenizer::base::binary::ModelKind;
+
+    if let Ok(dir) = std::env::var(CHILD_MODEL_DIR) {
+        assert!(!std::path::Path::new("dict").exists());
+        let text = "你好世界";
+        for (name, kind) in [
+            ("unigram.model", ModelKind::Unigram),
+            ("wordpiece.model", ModelKind::WordPiece),
+        ] {
+            let path = PathBuf::from(&dir).join(name);
+            let path = path.to_str().unwrap();
+            let expected = std::fs::read_to_string(format!("{}.expected", path)).unwrap();
+
+            let tokenizer = AutoTokenizer::from_file(path).unwrap();
+            assert_eq!(tokenizer.kind(), kind);
+            assert_eq!(format!("{:?}", tokenizer.encode(text).unwrap()), expected);
+
+            let mut tokenizer = AutoTokenizer::for_kind(kind).unwrap();
+            tokenizer.load(path).unwrap();
+            assert_eq!(format!("{:?}", tokenizer.encode(text).unwrap()), expected);
+        }
+        return;
+    }
+
+    let dir = PathBuf::from(temp_path("outside_repo"));
+    std::fs::create_dir_all(&dir).unwrap();
+    let corpus = vec!["你好，世界。你好！世界你好".to_string()];
+    let text = "你好世界";
+
+    let mut unigram = unigram().unwrap();
+    unigram.train(corpus.clone(), 16000).unwrap();
+    let path = dir.join("unigram.model");
+    unigram.save(path.to_str().unwrap()).unwrap();
+    let expected = format!("{:?}", unigram.encode(text).unwrap());
+    std::fs::write(dir.join("unigram.model.expected"), expected).unwrap();
+
+    let mut wordpiece = wordpiece().unwrap();
+    wordpiece.train(corpus, 16000).unwrap();
+    let path = dir.join("wordpiece.model");
+    wordpiece.save(path.to_str().unwrap()).unwrap();
+    let expected = format!("{:?}", wordpiece.encode(text).unwrap());
+    std::fs::write(dir.join("wordpiece.model.expected"), expected).unwrap();
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["test_load_outside_repo", "--exact", "--nocapture"])
+        .env(CHILD_MODEL_DIR, &dir)
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("1 passed"), "{}", stdout);
+}
+
+/// 测试内嵌字表与仓库中的字表内容相同，且为默认来源
+#[cfg(feature = "embedded-dict")]
+#[test]
+fn test_embedded_char_dict() {
+    use zero_tokenizer::base::resources::CharDict;
+
+    assert_eq!(CharDict::default(), CharDict::Embedded);
+    let embedded = ResourceConfig::default().char_dict_entries().unwrap();
+    let file = ResourceConfig::default()
+        .with_char_dict_path(repo_char_dict())
+        .char_dict_entries()
+        .unwrap();
+    assert_eq!(embedded, file);
+}
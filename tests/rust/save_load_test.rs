@@ -6,17 +6,21 @@ use std::fs;
 use std::path::Path;
 use zero_tokenizer::prelude::*;
 
-/// 清理测试文件的辅助函数
+mod test_utils;
+use test_utils::temp_path;
+
+/// 清理测试文件的辅助函数，同时删除模型旁的 `.scores` 文件
 fn cleanup_test_file(path: &str) {
-    if Path::new(path).exists() {
-        fs::remove_file(path).ok();
+    for path in [path.to_string(), format!("{}.scores", path)] {
+        if Path::new(&path).exists() {
+            fs::remove_file(path).ok();
+        }
     }
 }
 
-#[cfg(feature = "python")]
 #[test]
 fn test_bpe_save_load_roundtrip() {
-    let model_path = "test_bpe.model";
+    let model_path = &temp_path("test_bpe.model");
     cleanup_test_file(model_path);
 
     // 训练并保存
@@ -48,7 +52,7 @@ fn test_bpe_save_load_roundtrip() {
 
 #[test]
 fn test_bbpe_save_load_roundtrip() {
-    let model_path = "test_bbpe.model";
+    let model_path = &temp_path("test_bbpe.model");
     cleanup_test_file(model_path);
 
     let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
@@ -78,7 +82,7 @@ fn test_bbpe_save_load_roundtrip() {
 
 #[test]
 fn test_unigram_save_load_roundtrip() {
-    let model_path = "test_unigram.model";
+    let model_path = &temp_path("test_unigram.model");
     cleanup_test_file(model_path);
 
     let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
@@ -108,7 +112,7 @@ fn test_unigram_save_load_roundtrip() {
 
 #[test]
 fn test_wordpiece_save_load_roundtrip() {
-    let model_path = "test_wordpiece.model";
+    let model_path = &temp_path("test_wordpiece.model");
     cleanup_test_file(model_path);
 
     let mut tokenizer = zero_tokenizer::prelude::wordpiece().unwrap();
@@ -138,9 +142,34 @@ fn test_wordpiece_save_load_roundtrip() {
 
 #[test]
 fn test_save_to_invalid_path() {
+    // 父路径是普通文件，无论进程权限如何都无法创建目录
+    let blocker = &temp_path("save_blocker");
+    fs::write(blocker, b"not a directory").unwrap();
+
     let tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
-    let result = tokenizer.save("/invalid/nonexistent/path/model.bin");
+    let result = tokenizer.save(&format!("{}/model.bin", blocker));
     assert!(result.is_err());
+
+    cleanup_test_file(blocker);
+}
+
+#[test]
+fn test_save_creates_parent_dirs() {
+    let dir = temp_path("nested_save_dir");
+    let model_path = &format!("{}/sub/model.bin", dir);
+    fs::remove_dir_all(&dir).ok();
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world".to_string()], 300)
+        .unwrap();
+    tokenizer.save(model_path).unwrap();
+
+    let mut loaded = zero_tokenizer::prelude::bbpe().unwrap();
+    loaded.load(model_path).unwrap();
+    assert_eq!(loaded.vocab_size(), tokenizer.vocab_size());
+
+    fs::remove_dir_all(&dir).ok();
 }
 
 #[test]
@@ -152,7 +181,7 @@ fn test_load_nonexistent_file() {
 
 #[test]
 fn test_load_corrupted_file() {
-    let model_path = "corrupted.model";
+    let model_path = &temp_path("corrupted.model");
     fs::write(model_path, b"invalid corrupted data").unwrap();
 
     let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
@@ -166,7 +195,7 @@ fn test_load_corrupted_file() {
 
 #[test]
 fn test_save_multiple_times() {
-    let model_path = "test_multiple_saves.model";
+    let model_path = &temp_path("test_multiple_saves.model");
     cleanup_test_file(model_path);
 
     let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
@@ -191,3 +220,586 @@ fn test_save_multiple_times() {
 
     cleanup_test_file(model_path);
 }
+
+#[test]
+fn test_load_from_bytes() {
+    let model_path = &temp_path("test_from_bytes.model");
+    let scores_path = &format!("{}.scores", model_path);
+    cleanup_test_file(model_path);
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello".to_string()], 270)
+        .unwrap();
+    tokenizer.save(model_path).unwrap();
+
+    let loaded = BBPE::from_bytes(&fs::read(model_path).unwrap()).unwrap();
+    assert_eq!(loaded.vocab_size(), tokenizer.vocab_size());
+    assert_eq!(
+        loaded.encode("hello world").unwrap(),
+        tokenizer.encode("hello world").unwrap()
+    );
+
+    // Unigram的分数保存在模型文件中，不再写出单独的分数文件
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    tokenizer
+        .train(vec!["这是一个测试文本".to_string()], 15300)
+        .unwrap();
+    tokenizer.save(model_path).unwrap();
+    assert!(!Path::new(scores_path).exists());
+
+    let loaded = Unigram::from_bytes(&fs::read(model_path).unwrap(), &[]).unwrap();
+    assert_eq!(loaded.scores, tokenizer.scores);
+    assert_eq!(loaded.vocab_size(), tokenizer.vocab_size());
+    let tokens = loaded.encode("测试文本").unwrap();
+    assert_eq!(tokens, tokenizer.encode("测试文本").unwrap());
+    assert_eq!(loaded.decode(&tokens).unwrap(), "测试文本");
+
+    cleanup_test_file(model_path);
+}
+
+/// 测试重映射清单的生成和JSON往返
+#[test]
+fn test_remap_manifest_roundtrip() {
+    use std::collections::BTreeMap;
+    use zero_tokenizer::base::remap::RemapManifest;
+
+    let mapping = BTreeMap::from([(0, 0), (2, 1), (3, 2)]);
+    let manifest = RemapManifest::new(0..4, 0..4, mapping);
+    assert_eq!(manifest.removed, vec![1]);
+    assert_eq!(manifest.added, vec![3]);
+    assert_eq!(manifest.get(2), Some(1));
+    assert_eq!(manifest.get(1), None);
+    assert!(!manifest.is_identity());
+
+    let path = &temp_path("remap.json");
+    manifest.save(path).unwrap();
+    let loaded = RemapManifest::load(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(loaded, manifest);
+
+    let identity = RemapManifest::new(0..3, 0..3, (0..3).map(|id| (id, id)).collect());
+    assert!(identity.is_identity());
+}
+
+/// 测试合并规则按等级顺序保存，并带有显式等级
+#[test]
+fn test_merge_ranks_roundtrip() {
+    use zero_tokenizer::base::tokenizer_base::ranked_merges;
+
+    let model_path = &temp_path("test_merge_ranks.model");
+    cleanup_test_file(model_path);
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello hello world world wide web".to_string()], 280)
+        .unwrap();
+    tokenizer.save(model_path).unwrap();
+    let content = fs::read_to_string(model_path).unwrap();
+    cleanup_test_file(model_path);
+
+    // 文件中第 i 条合并规则的等级为 i，顺序与训练顺序一致
+    let lines: Vec<&str> = content
+        .lines()
+        .filter_map(|line| line.strip_prefix("merge: "))
+        .collect();
+    let expected: Vec<String> = ranked_merges(&tokenizer.merges)
+        .into_iter()
+        .enumerate()
+        .map(|(rank, ((a, b), id))| format!("{} {} {} {}", a, b, id, rank))
+        .collect();
+    assert_eq!(lines, expected);
+
+    let loaded = BBPE::from_bytes(content.as_bytes()).unwrap();
+    assert_eq!(
+        ranked_merges(&loaded.merges),
+        ranked_merges(&tokenizer.merges)
+    );
+
+    // 没有等级列（也没有段索引）的旧格式仍可加载
+    let legacy: String = content
+        .lines()
+        .filter(|line| !line.starts_with("sections: "))
+        .map(|line| match line.strip_prefix("merge: ") {
+            Some(data) => format!("merge: {}\n", data.rsplit_once(' ').unwrap().0),
+            None => format!("{}\n", line),
+        })
+        .collect();
+    let loaded = BBPE::from_bytes(legacy.as_bytes()).unwrap();
+    assert_eq!(loaded.merges, tokenizer.merges);
+
+    // 等级重复时拒绝加载
+    let first = format!("merge: {}", lines[0]);
+    let duplicated = content.replacen(&first, &format!("{}\n{}", first, first), 1);
+    assert!(BBPE::from_bytes(duplicated.as_bytes()).is_err());
+}
+
+/// 测试带段索引的模型文件并行加载，并兼容没有索引的旧格式
+/// 测试多条合并规则复用同一个ID时，等级随模型保存，加载后切分不变
+#[test]
+fn test_reused_merge_ids_roundtrip() {
+    use zero_tokenizer::base::tokenizer_base::ranked_merges;
+
+    let model_path = &temp_path("test_reused_merges.model");
+    let text = "aa aba aaabab ba baaaa aabaaaa babab aaaabab";
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer.train(vec![text.to_string()], 280).unwrap();
+    // 复用ID后新标记ID不再随等级递增
+    let ids: Vec<u32> = tokenizer.merges.values().copied().collect();
+    assert!(ids.windows(2).any(|pair| pair[1] <= pair[0]));
+
+    tokenizer.save(model_path).unwrap();
+    let mut loaded = zero_tokenizer::prelude::bbpe().unwrap();
+    loaded.load(model_path).unwrap();
+    cleanup_test_file(model_path);
+
+    assert_eq!(
+        ranked_merges(&loaded.merges),
+        ranked_merges(&tokenizer.merges)
+    );
+    for sample in text.split(' ').chain(["abaabab", "bbbaaaab"]) {
+        assert_eq!(
+            loaded.encode(sample).unwrap(),
+            tokenizer.encode(sample).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_bbpe_sectioned_load() {
+    let model_path = &temp_path("test_sectioned.model");
+    cleanup_test_file(model_path);
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(
+            vec!["the quick brown fox jumps over the lazy dog, 敏捷的棕色狐狸".to_string()],
+            320,
+        )
+        .unwrap();
+    tokenizer.save(model_path).unwrap();
+    let content = fs::read_to_string(model_path).unwrap();
+    cleanup_test_file(model_path);
+
+    let index = content
+        .lines()
+        .find_map(|line| line.strip_prefix("sections: "))
+        .expect("新格式应包含段索引");
+    let lengths: Vec<usize> = index
+        .split_whitespace()
+        .map(|n| n.parse().unwrap())
+        .collect();
+    let start = content.find("base_chars: ").unwrap();
+    assert_eq!(content.len() - start, lengths.iter().sum::<usize>());
+    assert!(content[start + lengths[0]..].starts_with("vocab: "));
+    assert!(content[start + lengths[0] + lengths[1]..].starts_with("merges: "));
+
+    let loaded = BBPE::from_bytes(content.as_bytes()).unwrap();
+    assert_eq!(loaded.merges, tokenizer.merges);
+    assert_eq!(loaded.vocab_size(), tokenizer.vocab_size());
+    assert_eq!(loaded.base_chars, tokenizer.base_chars);
+    let text = "the lazy 狐狸";
+    assert_eq!(
+        loaded.encode(text).unwrap(),
+        tokenizer.encode(text).unwrap()
+    );
+
+    // 去掉索引后按旧格式逐行加载，结果相同
+    let legacy: String = content
+        .lines()
+        .filter(|line| !line.starts_with("sections: "))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    let legacy = BBPE::from_bytes(legacy.as_bytes()).unwrap();
+    assert_eq!(legacy.merges, tokenizer.merges);
+    assert_eq!(legacy.vocab_size(), tokenizer.vocab_size());
+
+    // 只差行尾空白时不依赖索引逐行加载，结果相同
+    let trimmed = BBPE::from_bytes(content.trim_end().as_bytes()).unwrap();
+    assert_eq!(trimmed.merges, tokenizer.merges);
+
+    // 内容被截断或改坏时拒绝加载
+    let truncated = &content[..content.len() - 2];
+    assert!(BBPE::from_bytes(truncated.as_bytes()).is_err());
+    let missing_line = content.replacen("\nmerge: ", "\nxmerge: ", 1);
+    assert!(BBPE::from_bytes(missing_line.as_bytes()).is_err());
+}
+
+/// 测试通过set_pieces构造的Unigram模型精确往返，并兼容分数单独保存的旧模型
+#[test]
+fn test_unigram_set_pieces_roundtrip() {
+    let model_path = &temp_path("pieces.model");
+    let scores_path = format!("{}.scores", model_path);
+
+    let pieces = vec![
+        ("<unk>".to_string(), 0.0),
+        ("h".to_string(), -std::f64::consts::LN_10),
+        ("e".to_string(), -1.0 / 3.0),
+        ("l".to_string(), f64::MIN_POSITIVE),
+        ("o".to_string(), -1e-300),
+        ("hello".to_string(), -0.1 - 0.2),
+        ("▁world".to_string(), -0.0),
+        ("a b".to_string(), -1234.5678e10),
+    ];
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    tokenizer.set_pieces(pieces.clone()).unwrap();
+    assert_eq!(tokenizer.vocab_size(), pieces.len());
+    assert_eq!(tokenizer.unk_token_id, 0);
+    assert_eq!(tokenizer.encode("hello").unwrap(), vec![5]);
+
+    tokenizer.save(model_path).unwrap();
+    let mut loaded = zero_tokenizer::prelude::unigram().unwrap();
+    loaded.load(model_path).unwrap();
+    let bits = |scores: &[f64]| scores.iter().map(|s| s.to_bits()).collect::<Vec<_>>();
+    assert_eq!(bits(&loaded.scores), bits(&tokenizer.scores));
+    for (id, (piece, _)) in pieces.iter().enumerate() {
+        assert_eq!(loaded.base.vocab.get_by_id(&(id as u32)), Some(piece));
+    }
+
+    // 旧模型：分数在单独的文件中
+    let content = fs::read_to_string(model_path).unwrap();
+    let (legacy, embedded) = content.split_at(content.find("scores: ").unwrap());
+    let mut legacy_scores: Vec<&str> = embedded.lines().skip(1).collect();
+    legacy_scores.insert(0, "0");
+    fs::write(model_path, legacy).unwrap();
+    fs::write(&scores_path, legacy_scores.join("\n")).unwrap();
+    let mut loaded = zero_tokenizer::prelude::unigram().unwrap();
+    loaded.load(model_path).unwrap();
+    assert_eq!(bits(&loaded.scores), bits(&tokenizer.scores));
+
+    // 两处都没有分数时报错
+    fs::remove_file(&scores_path).unwrap();
+    assert!(loaded.load(model_path).is_err());
+    fs::remove_file(model_path).unwrap();
+
+    // 无效片段不修改词汇表
+    assert!(tokenizer
+        .set_pieces(vec![("a".to_string(), 0.0), ("a".to_string(), 1.0)])
+        .is_err());
+    assert!(tokenizer
+        .set_pieces(vec![("a\nb".to_string(), 0.0)])
+        .is_err());
+    assert!(tokenizer
+        .set_pieces(vec![("a".to_string(), f64::NAN)])
+        .is_err());
+    assert_eq!(tokenizer.vocab_size(), pieces.len());
+}
+
+/// 测试编码哈希与平台无关，保存加载后不变，分词器重新训练后改变
+#[test]
+fn test_content_hash() {
+    use zero_tokenizer::base::content_hash::hash_ids;
+
+    assert_eq!(hash_ids(&[]), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(hash_ids(&[1, 256]), 0x9276_ac29_2212_ee4f);
+
+    let model_path = &temp_path("test_content_hash.model");
+    cleanup_test_file(model_path);
+    let text = "hello world hello tokenizer";
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.train(vec![text.repeat(10)], 270).unwrap();
+    let hash = tokenizer.content_hash(text).unwrap();
+    assert_eq!(hash, hash_ids(&tokenizer.encode(text).unwrap()));
+
+    tokenizer.save(model_path).unwrap();
+    let mut loaded = bbpe().unwrap();
+    loaded.load(model_path).unwrap();
+    cleanup_test_file(model_path);
+    assert_eq!(loaded.content_hash(text).unwrap(), hash);
+
+    let mut retrained = bbpe().unwrap();
+    retrained.train(vec![text.repeat(10)], 260).unwrap();
+    assert_ne!(retrained.content_hash(text).unwrap(), hash);
+}
+
+/// 测试加载时的完整性检查：合并结果与两侧不符、重复的ID、合并引用不存在的标记
+#[test]
+fn test_integrity_check_on_load() {
+    let model_path = &temp_path("integrity.model");
+
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(
+            vec!["the quick brown fox jumps over the lazy dog".to_string()],
+            300,
+        )
+        .unwrap();
+    assert!(tokenizer.check_integrity().is_ok());
+    tokenizer.save(model_path).unwrap();
+    let content: String = fs::read_to_string(model_path)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.starts_with("sections: "))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    cleanup_test_file(model_path);
+    assert!(BBPE::from_bytes(content.as_bytes()).is_ok());
+
+    let (&(left, right), &new_id) = tokenizer.merges.iter().next().unwrap();
+    let entry = content
+        .lines()
+        .find(|line| line.starts_with(&format!("vocab_entry: {} ", new_id)))
+        .unwrap();
+
+    // 合并结果不等于两侧拼接
+    let mismatched = content.replacen(entry, &format!("vocab_entry: {} 120", new_id), 1);
+    let Err(err) = BBPE::from_bytes(mismatched.as_bytes()) else {
+        panic!("合并结果不符时应拒绝加载");
+    };
+    assert!(err.to_string().contains("完整性检查"), "{}", err);
+    assert!(
+        err.to_string()
+            .contains(&format!("合并 ({}, {}) -> {}", left, right, new_id)),
+        "{}",
+        err
+    );
+
+    // 同一个ID出现两次
+    let duplicated = content.replacen(
+        entry,
+        &format!("{}\nvocab_entry: {} 120 121", entry, new_id),
+        1,
+    );
+    let Err(err) = BBPE::from_bytes(duplicated.as_bytes()) else {
+        panic!("ID重复时应拒绝加载");
+    };
+    assert!(
+        err.to_string().contains(&format!("ID {} 重复出现", new_id)),
+        "{}",
+        err
+    );
+
+    // 直接修改字段后重新检查，报告列出具体问题
+    tokenizer
+        .merges
+        .insert((left, right + 1_000_000), 2_000_000);
+    let report = tokenizer.check_integrity();
+    assert!(report.issues.contains(&IntegrityIssue::MissingMergeToken {
+        left,
+        right: right + 1_000_000,
+        new_id: 2_000_000,
+        missing: right + 1_000_000,
+    }));
+    assert!(report.issues.contains(&IntegrityIssue::MissingMergeToken {
+        left,
+        right: right + 1_000_000,
+        new_id: 2_000_000,
+        missing: 2_000_000,
+    }));
+    assert!(report.into_result().is_err());
+
+    // BPE：删除合并结果的词汇表条目
+    let mut bpe = zero_tokenizer::prelude::bpe().unwrap();
+    bpe.train(vec!["hello hello world".to_string()], 300)
+        .unwrap();
+    assert!(bpe.check_integrity().is_ok());
+    bpe.save(model_path).unwrap();
+    let content = fs::read_to_string(model_path).unwrap();
+    let (_, &new_id) = bpe.merges.iter().next().unwrap();
+    let pruned: String = content
+        .lines()
+        .filter(|line| !line.starts_with(&format!("vocab_entry: {} ", new_id)))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    fs::write(model_path, pruned).unwrap();
+    let err = zero_tokenizer::prelude::bpe()
+        .unwrap()
+        .load(model_path)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains(&format!("ID {} 不在词汇表中", new_id)),
+        "{}",
+        err
+    );
+    cleanup_test_file(model_path);
+
+    // Unigram：两个词汇表条目使用同一个ID
+    let mut unigram = zero_tokenizer::prelude::unigram().unwrap();
+    unigram
+        .train(vec!["hello hello world".to_string()], 300)
+        .unwrap();
+    assert!(unigram.check_integrity().is_ok());
+    unigram.save(model_path).unwrap();
+    let content = fs::read_to_string(model_path).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    let (_, first_id) = lines[2].rsplit_once(' ').unwrap();
+    let (second_token, _) = lines[3].rsplit_once(' ').unwrap();
+    let duplicated = content.replacen(lines[3], &format!("{} {}", second_token, first_id), 1);
+    fs::write(model_path, duplicated).unwrap();
+    let err = zero_tokenizer::prelude::unigram()
+        .unwrap()
+        .load(model_path)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("重复出现"), "{}", err);
+    cleanup_test_file(model_path);
+    cleanup_test_file(&format!("{}.scores", model_path));
+}
+
+#[test]
+fn test_binary_save_load_roundtrip() {
+    let path = |name: &str| temp_path(&format!("binary_{}", name));
+    let texts = vec!["Hello world! 你好世界 hello hello world".to_string()];
+    let text = "hello 你好 world";
+
+    let mut bpe = zero_tokenizer::prelude::bpe().unwrap();
+    bpe.train(texts.clone(), 300).unwrap();
+    bpe.add_special_tokens(&["<|eot|>"]).unwrap();
+    let bpe_path = path("bpe.bin");
+    bpe.save_binary(&bpe_path).unwrap();
+    let mut loaded = zero_tokenizer::prelude::bpe().unwrap();
+    loaded.load_binary(&bpe_path).unwrap();
+    assert_eq!(loaded.vocab.id_map(), bpe.vocab.id_map());
+    assert_eq!(loaded.merges, bpe.merges);
+    assert_eq!(loaded.next_token_id, bpe.next_token_id);
+    assert_eq!(loaded.base.special_tokens, bpe.base.special_tokens);
+    assert_eq!(loaded.encode(text).unwrap(), bpe.encode(text).unwrap());
+
+    // 文本格式的模型同样可以通过 load_binary 加载
+    bpe.save(&bpe_path).unwrap();
+    let mut legacy = zero_tokenizer::prelude::bpe().unwrap();
+    legacy.load_binary(&bpe_path).unwrap();
+    assert_eq!(legacy.encode(text).unwrap(), bpe.encode(text).unwrap());
+    cleanup_test_file(&bpe_path);
+
+    let mut bbpe = zero_tokenizer::prelude::bbpe().unwrap();
+    bbpe.train(texts.clone(), 300).unwrap();
+    let bbpe_path = path("bbpe.bin");
+    bbpe.save_binary(&bbpe_path).unwrap();
+    let mut loaded = zero_tokenizer::prelude::bbpe().unwrap();
+    loaded.load_binary(&bbpe_path).unwrap();
+    assert_eq!(loaded.vocab.id_map(), bbpe.vocab.id_map());
+    assert_eq!(loaded.merges, bbpe.merges);
+    assert_eq!(loaded.encode(text).unwrap(), bbpe.encode(text).unwrap());
+
+    // 校验和能发现损坏，模型类型不匹配时拒绝加载
+    let mut data = fs::read(&bbpe_path).unwrap();
+    let middle = data.len() / 2;
+    data[middle] ^= 0xff;
+    fs::write(&bbpe_path, &data).unwrap();
+    let err = loaded.load_binary(&bbpe_path).unwrap_err();
+    assert!(err.to_string().contains("校验和"), "{}", err);
+    let err = zero_tokenizer::prelude::bpe()
+        .unwrap()
+        .load_binary(&bbpe_path)
+        .unwrap_err();
+    assert!(err.to_string().contains("校验和"), "{}", err);
+    bbpe.save_binary(&bbpe_path).unwrap();
+    let err = zero_tokenizer::prelude::bpe()
+        .unwrap()
+        .load_binary(&bbpe_path)
+        .unwrap_err();
+    assert!(err.to_string().contains("模型类型不匹配"), "{}", err);
+    cleanup_test_file(&bbpe_path);
+
+    let mut unigram = zero_tokenizer::prelude::unigram().unwrap();
+    unigram.train(texts.clone(), 300).unwrap();
+    let unigram_path = path("unigram.bin");
+    unigram.save_binary(&unigram_path).unwrap();
+    let mut loaded = zero_tokenizer::prelude::unigram().unwrap();
+    loaded.load_binary(&unigram_path).unwrap();
+    cleanup_test_file(&unigram_path);
+    assert_eq!(loaded.base.vocab.id_map(), unigram.base.vocab.id_map());
+    assert_eq!(loaded.scores, unigram.scores);
+    assert_eq!(loaded.unk_token_id, unigram.unk_token_id);
+    assert_eq!(loaded.encode(text).unwrap(), unigram.encode(text).unwrap());
+
+    let mut wordpiece = zero_tokenizer::prelude::wordpiece().unwrap();
+    wordpiece.train(texts, 300).unwrap();
+    let wordpiece_path = path("wordpiece.bin");
+    wordpiece.save_binary(&wordpiece_path).unwrap();
+    let mut loaded = zero_tokenizer::prelude::wordpiece().unwrap();
+    loaded.load_binary(&wordpiece_path).unwrap();
+    cleanup_test_file(&wordpiece_path);
+    assert_eq!(loaded.base.vocab.id_map(), wordpiece.base.vocab.id_map());
+    assert_eq!(loaded.scores, wordpiece.scores);
+    assert_eq!(
+        loaded.continuing_subword_prefix,
+        wordpiece.continuing_subword_prefix
+    );
+    assert_eq!(
+        loaded.encode(text).unwrap(),
+        wordpiece.encode(text).unwrap()
+    );
+}
+
+#[test]
+fn test_binary_format_version() {
+    use zero_tokenizer::base::binary::{BinaryModel, ModelKind, BINARY_FORMAT_VERSION};
+
+    let model = BinaryModel::new(ModelKind::Bpe);
+    let mut data = model.to_bytes();
+    assert_eq!(&data[..4], b"ZTOK");
+    assert_eq!(
+        BinaryModel::from_bytes(&data, ModelKind::Bpe).unwrap(),
+        model
+    );
+
+    // 更高的格式版本即使校验和正确也拒绝加载
+    data.truncate(data.len() - 8);
+    data[4..6].copy_from_slice(&(BINARY_FORMAT_VERSION + 1).to_le_bytes());
+    let checksum = zero_tokenizer::base::content_hash::hash_bytes(&data);
+    data.extend_from_slice(&checksum.to_le_bytes());
+    let err = BinaryModel::from_bytes(&data, ModelKind::Bpe).unwrap_err();
+    assert!(err.to_string().contains("版本"), "{}", err);
+
+    assert!(BinaryModel::from_bytes(&data[..10], ModelKind::Bpe).is_err());
+}
+
+#[test]
+fn test_base_vocab_format() {
+    use std::io::Cursor;
+    use zero_tokenizer::base::tokenizer_base::TokenizerBase;
+    use zero_tokenizer::error::TokenizerError;
+
+    let model_path = &temp_path("test_base_format.model");
+    let mut base = TokenizerBase::<u32>::new().unwrap();
+    base.vocab.insert(0, "hello world".to_string());
+    base.vocab.insert(1, " x".to_string());
+    base.vocab.insert(2, "y".to_string());
+    base.save(model_path).unwrap();
+    let content = fs::read_to_string(model_path).unwrap();
+    cleanup_test_file(model_path);
+
+    // ID位于行尾，标记中的空格原样保留；vocab_size 之后由各分词器追加的行不当作词汇表条目
+    let appended = format!("{}merge: 0 1 2\n", content);
+    let mut loaded = TokenizerBase::<u32>::new().unwrap();
+    loaded
+        .load_from_reader(Cursor::new(appended.as_bytes()))
+        .unwrap();
+    assert_eq!(loaded.vocab.len(), 3);
+    assert_eq!(loaded.vocab.get_by_id(&0).unwrap(), "hello world");
+    assert_eq!(loaded.vocab.get_by_id(&1).unwrap(), " x");
+
+    // 缺少 pattern 行或 vocab_size 行时拒绝加载
+    let body = content.split_once('\n').unwrap().1;
+    let without_size = content.replacen("vocab_size: 3\n", "", 1);
+    for broken in ["", body, without_size.as_str()] {
+        let result = TokenizerBase::<u32>::new()
+            .unwrap()
+            .load_from_reader(Cursor::new(broken.as_bytes()));
+        assert!(matches!(result, Err(TokenizerError::ModelLoadError { .. })));
+    }
+}
+
+#[test]
+fn test_bpe_whitespace_vocab_roundtrip() {
+    let model_path = &temp_path("test_bpe_whitespace.model");
+    let mut tokenizer = zero_tokenizer::prelude::bpe().unwrap();
+    let text = "line one\n line two\n\t tab";
+    tokenizer.train(vec![text.to_string()], 300).unwrap();
+    tokenizer.save(model_path).unwrap();
+
+    // 词汇以JSON字符串写入，空白和换行字符不会破坏行格式
+    let mut loaded = zero_tokenizer::prelude::bpe().unwrap();
+    loaded.load(model_path).unwrap();
+    cleanup_test_file(model_path);
+
+    assert_eq!(loaded.vocab.get_by_id(&10).unwrap(), "\n");
+    assert_eq!(loaded.vocab.get_by_id(&32).unwrap(), " ");
+    assert_eq!(loaded.vocab_size(), tokenizer.vocab_size());
+    let ids = tokenizer.encode(text).unwrap();
+    assert_eq!(loaded.encode(text).unwrap(), ids);
+    assert_eq!(loaded.decode(&ids).unwrap(), text);
+}
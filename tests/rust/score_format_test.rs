@@ -0,0 +1,160 @@
+//! 分数序列化测试
+//!
+//! 测试Unigram和WordPiece的分数以固定格式精确保存，且加载时拒绝NaN和无穷大
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use zero_tokenizer::base::score::{format_score, parse_score};
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::temp_path;
+
+fn remove(path: &str) {
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(format!("{}.scores", path));
+}
+
+fn bits(scores: &[f64]) -> Vec<u64> {
+    scores.iter().map(|score| score.to_bits()).collect()
+}
+
+/// 随机的有限分数：一半取任意位模式，覆盖次正规数和极大极小值；一半取常见的对数概率范围
+fn random_scores(rng: &mut StdRng, count: usize) -> Vec<f64> {
+    let mut scores = Vec::with_capacity(count);
+    while scores.len() < count {
+        let score = if rng.gen_bool(0.5) {
+            f64::from_bits(rng.gen())
+        } else {
+            rng.gen_range(-30.0..0.0)
+        };
+        if score.is_finite() {
+            scores.push(score);
+        }
+    }
+    scores
+}
+
+fn trained_unigram() -> Unigram {
+    let mut tokenizer = unigram().unwrap();
+    tokenizer
+        .train(vec!["hello world, hello there".to_string()], 300)
+        .unwrap();
+    tokenizer
+}
+
+fn trained_wordpiece() -> WordPiece {
+    let mut tokenizer = wordpiece().unwrap();
+    tokenizer
+        .train(vec!["hello world, hello there".to_string()], 60)
+        .unwrap();
+    tokenizer
+}
+
+/// 测试任意有限分数格式化后解析回完全相同的位模式
+#[test]
+fn test_format_parse_round_trip() {
+    let edge_cases = [
+        0.0,
+        -0.0,
+        0.1,
+        -1.0 / 3.0,
+        f64::EPSILON,
+        f64::MIN_POSITIVE,
+        f64::from_bits(1),
+        f64::MAX,
+        f64::MIN,
+    ];
+    let mut rng = StdRng::seed_from_u64(3275);
+    for score in edge_cases
+        .into_iter()
+        .chain(random_scores(&mut rng, 10_000))
+    {
+        let text = format_score(score);
+        assert!(!text.contains(','), "{}", text);
+        let parsed = parse_score(&text).unwrap();
+        assert_eq!(parsed.to_bits(), score.to_bits(), "{}", text);
+    }
+}
+
+/// 测试旧模型的普通小数写法仍能解析，NaN、无穷大和逗号小数点被拒绝
+#[test]
+fn test_parse_score_rejects_invalid() {
+    assert_eq!(parse_score(" -3.25 ").unwrap(), -3.25);
+    assert_eq!(parse_score("-3.25e0").unwrap(), -3.25);
+
+    for text in ["NaN", "inf", "-inf", "infinity"] {
+        let error = parse_score(text).unwrap_err();
+        assert!(error.to_string().contains("有限数"), "{}", error);
+    }
+    let error = parse_score("-3,25").unwrap_err();
+    assert!(error.to_string().contains("'.'"), "{}", error);
+    assert!(parse_score("").is_err());
+}
+
+/// 测试随机分数经文本模型保存和加载后逐位不变
+#[test]
+fn test_text_model_scores_round_trip() {
+    let mut rng = StdRng::seed_from_u64(17);
+
+    let mut tokenizer = trained_unigram();
+    tokenizer.scores = random_scores(&mut rng, tokenizer.scores.len());
+    let path = temp_path("score_round_trip_unigram");
+    tokenizer.save(&path).unwrap();
+    let mut loaded = unigram().unwrap();
+    loaded.load(&path).unwrap();
+    remove(&path);
+    assert_eq!(bits(&loaded.scores), bits(&tokenizer.scores));
+
+    let mut tokenizer = trained_wordpiece();
+    tokenizer.scores = random_scores(&mut rng, tokenizer.scores.len());
+    let path = temp_path("score_round_trip_wordpiece");
+    tokenizer.save(&path).unwrap();
+    let content = std::fs::read_to_string(format!("{}.scores", path)).unwrap();
+    let mut loaded = wordpiece().unwrap();
+    loaded.load(&path).unwrap();
+    remove(&path);
+    assert_eq!(bits(&loaded.scores), bits(&tokenizer.scores));
+
+    // 分数文件每行都是固定格式
+    for (line, score) in content.lines().skip(1).zip(&tokenizer.scores) {
+        assert_eq!(line, format_score(*score));
+    }
+}
+
+/// 测试文本模型和二进制模型中的NaN或无穷大分数在加载时报错
+#[test]
+fn test_load_rejects_non_finite_scores() {
+    // Unigram的分数在模型文件末尾的分数段中
+    let tokenizer = trained_unigram();
+    let path = temp_path("score_nan_unigram");
+    tokenizer.save(&path).unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    let last = format!("{}\n", format_score(*tokenizer.scores.last().unwrap()));
+    assert!(content.ends_with(&last));
+    let corrupted = format!("{}NaN\n", &content[..content.len() - last.len()]);
+    std::fs::write(&path, corrupted).unwrap();
+    let error = unigram().unwrap().load(&path).unwrap_err().to_string();
+    remove(&path);
+    assert!(error.contains("有限数"), "{}", error);
+
+    // WordPiece的分数在单独的 `.scores` 文件中
+    let tokenizer = trained_wordpiece();
+    let path = temp_path("score_inf_wordpiece");
+    tokenizer.save(&path).unwrap();
+    let scores_path = format!("{}.scores", path);
+    let content = std::fs::read_to_string(&scores_path).unwrap();
+    std::fs::write(&scores_path, format!("{}inf\n", content)).unwrap();
+    let error = wordpiece().unwrap().load(&path).unwrap_err().to_string();
+    remove(&path);
+    assert!(error.contains("有限数"), "{}", error);
+
+    // 二进制模型按位保存分数，同样在加载时检查
+    let mut tokenizer = trained_unigram();
+    tokenizer.scores[0] = f64::NEG_INFINITY;
+    let path = temp_path("score_inf_binary");
+    tokenizer.save_binary(&path).unwrap();
+    let error = unigram().unwrap().load_binary(&path).unwrap_err();
+    remove(&path);
+    assert!(error.to_string().contains("有限数"), "{}", error);
+}
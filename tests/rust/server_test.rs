@@ -0,0 +1,133 @@
+//! HTTP分词服务测试
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use zero_tokenizer::prelude::*;
+use zero_tokenizer::server::{self, SharedTokenizer};
+
+fn trained_tokenizer() -> BBPE {
+    let mut tokenizer = zero_tokenizer::prelude::bbpe().unwrap();
+    tokenizer
+        .train(vec!["hello world hello".to_string()], 270)
+        .unwrap();
+    tokenizer
+}
+
+async fn post(tokenizer: SharedTokenizer, uri: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::post(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = server::router(tokenizer).oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_encode_count_decode() {
+    let tokenizer = trained_tokenizer();
+    let expected = tokenizer.encode("hello world").unwrap();
+    let shared: SharedTokenizer = Arc::new(tokenizer);
+
+    let (status, body) = post(shared.clone(), "/encode", json!({ "text": "hello world" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "ids": expected }));
+
+    let (status, body) = post(
+        shared.clone(),
+        "/encode",
+        json!({ "texts": ["hello world", "hello"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["ids"][0], json!(expected));
+
+    let (_, body) = post(
+        shared.clone(),
+        "/count",
+        json!({ "texts": ["hello world", ""] }),
+    )
+    .await;
+    assert_eq!(
+        body,
+        json!({ "counts": [expected.len(), 0], "total": expected.len() })
+    );
+
+    let (_, body) = post(shared.clone(), "/decode", json!({ "ids": expected })).await;
+    assert_eq!(body, json!({ "text": "hello world" }));
+
+    let (_, body) = post(shared, "/decode", json!({ "ids": [expected, []] })).await;
+    assert_eq!(body, json!({ "texts": ["hello world", ""] }));
+}
+
+#[tokio::test]
+async fn test_errors_and_health() {
+    let tokenizer = trained_tokenizer();
+    let vocab_size = tokenizer.vocab_size();
+    // 错误信息包含出错序列的序号和原始原因
+    let expected = TokenizerError::BatchItem {
+        index: 1,
+        source: Box::new(tokenizer.decode(&[999999]).unwrap_err()),
+    };
+    let shared: SharedTokenizer = Arc::new(tokenizer);
+
+    let (status, body) = post(
+        shared.clone(),
+        "/decode",
+        json!({ "ids": [[104], [999999]] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], expected.to_string());
+
+    let request = Request::get("/health").body(Body::empty()).unwrap();
+    let response = server::router(shared).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body, json!({ "status": "ok", "vocab_size": vocab_size }));
+}
+
+#[tokio::test]
+async fn test_batched_router() {
+    use zero_tokenizer::batching::BatchConfig;
+
+    let tokenizer = trained_tokenizer();
+    let expected = tokenizer.encode("hello world").unwrap();
+    let shared: SharedTokenizer = Arc::new(tokenizer);
+    let app = server::batched_router(shared, BatchConfig::default());
+
+    let request = Request::post("/count")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "texts": ["hello world", "", "hello world"] }).to_string(),
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "counts": [expected.len(), 0, expected.len()],
+            "total": expected.len() * 2
+        })
+    );
+
+    let request = Request::post("/encode")
+        .header("content-type", "application/json")
+        .body(Body::from(json!({ "text": "hello world" }).to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body, json!({ "ids": expected }));
+}
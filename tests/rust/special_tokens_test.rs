@@ -0,0 +1,243 @@
+//! 特殊标记测试
+//!
+//! 测试所有分词器在编码、解码、训练、保存和加载时对特殊标记的处理
+
+use std::fs;
+use std::path::Path;
+use zero_tokenizer::prelude::*;
+
+/// 清理测试文件的辅助函数
+fn cleanup_test_files(path: &str) {
+    for file in [
+        path.to_string(),
+        format!("{}.scores", path),
+        format!("{}.journal", path),
+    ] {
+        if Path::new(&file).exists() {
+            fs::remove_file(file).ok();
+        }
+    }
+}
+
+/// 特殊标记整体编码为一个ID，解码时可以跳过
+fn check_encode_decode<T: SpecialTokenizer>(tokenizer: &mut T) {
+    let ids = tokenizer
+        .add_special_tokens(&["<|endoftext|>", "<|end|>"])
+        .unwrap();
+    assert_eq!(tokenizer.special_tokens().len(), 2);
+    assert_eq!(tokenizer.special_tokens().id("<|endoftext|>"), Some(ids[0]));
+
+    let text = "hi<|endoftext|>there<|end|>";
+    let encoded = tokenizer.encode(text).unwrap();
+    assert_eq!(
+        encoded.iter().filter(|id| ids.contains(id)).count(),
+        2,
+        "特殊标记应各编码为一个ID: {:?}",
+        encoded
+    );
+    assert_eq!(encoded.last(), Some(&ids[1]));
+    assert_eq!(tokenizer.decode(&encoded).unwrap(), text);
+    assert_eq!(tokenizer.decode_with(&encoded, true).unwrap(), "hithere");
+
+    // 重复注册返回相同的ID
+    assert_eq!(
+        tokenizer.add_special_tokens(&["<|end|>"]).unwrap(),
+        [ids[1]]
+    );
+}
+
+#[test]
+fn test_special_tokens_encode_decode() {
+    check_encode_decode(&mut bpe().unwrap());
+    check_encode_decode(&mut bbpe().unwrap());
+    check_encode_decode(&mut unigram().unwrap());
+    check_encode_decode(&mut wordpiece().unwrap());
+}
+
+/// 句子对编码为 `[CLS] A [SEP] B [SEP]`，`token_type_ids` 区分两句
+fn check_encode_pair<T: SpecialTokenizer>(tokenizer: &mut T) {
+    // 未注册 `[CLS]`/`[SEP]` 时返回错误
+    assert!(tokenizer.encode_pair("hi", "there").is_err());
+
+    let ids = tokenizer.add_special_tokens(&["[CLS]", "[SEP]"]).unwrap();
+    let (cls, sep) = (ids[0], ids[1]);
+    let first = tokenizer.encode("hi").unwrap();
+    let second = tokenizer.encode("there").unwrap();
+
+    let pair = tokenizer.encode_pair("hi", "there").unwrap();
+    let mut expected = vec![cls];
+    expected.extend(&first);
+    expected.push(sep);
+    expected.extend(&second);
+    expected.push(sep);
+    assert_eq!(pair.ids, expected);
+    assert_eq!(pair.len(), pair.token_type_ids.len());
+    assert_eq!(
+        pair.token_type_ids.iter().filter(|&&t| t == 0).count(),
+        first.len() + 2
+    );
+    assert!(pair.token_type_ids[first.len() + 2..]
+        .iter()
+        .all(|&t| t == 1));
+    assert_eq!(tokenizer.decode_with(&pair.ids, true).unwrap(), "hithere");
+
+    // 其他名称的分类和分隔标记
+    let ids = tokenizer.add_special_tokens(&["<s>", "</s>"]).unwrap();
+    let pair = tokenizer.encode_pair_with("hi", "", "<s>", "</s>").unwrap();
+    assert_eq!(pair.ids.first(), Some(&ids[0]));
+    assert_eq!(pair.ids.last(), Some(&ids[1]));
+    assert_eq!(pair.token_type_ids.last(), Some(&1));
+    assert!(tokenizer
+        .encode_pair_with("hi", "there", "<s>", "<unknown>")
+        .is_err());
+}
+
+#[test]
+fn test_special_tokens_encode_pair() {
+    check_encode_pair(&mut bpe().unwrap());
+    check_encode_pair(&mut bbpe().unwrap());
+    check_encode_pair(&mut unigram().unwrap());
+    check_encode_pair(&mut wordpiece().unwrap());
+
+    // BERT预设的特殊标记已在注册表中
+    let tokenizer = WordPiece::with_bert_special_tokens_internal().unwrap();
+    let pair = tokenizer.encode_pair("hello", "world").unwrap();
+    assert_eq!(
+        pair.ids,
+        tokenizer
+            .encode_pair_with_special_tokens("hello", "world")
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_special_tokens_invalid() {
+    let mut tokenizer = bbpe().unwrap();
+    assert!(tokenizer.add_special_tokens(&[""]).is_err());
+    assert!(tokenizer.add_special_tokens(&["a\nb"]).is_err());
+    assert!(tokenizer.special_tokens().is_empty());
+}
+
+#[test]
+fn test_special_tokens_longest_match() {
+    let mut tokenizer = bbpe().unwrap();
+    let ids = tokenizer.add_special_tokens(&["<|a|>", "<|a|>x"]).unwrap();
+    let encoded = tokenizer.encode("<|a|>x<|a|>").unwrap();
+    assert_eq!(encoded, [ids[1], ids[0]]);
+}
+
+/// 训练语料中的特殊标记不参与合并
+#[test]
+fn test_special_tokens_excluded_from_training() {
+    let mut tokenizer = bbpe().unwrap();
+    let id = tokenizer.add_special_tokens(&["<|sep|>"]).unwrap()[0];
+    let texts = vec!["hello<|sep|>world<|sep|>".to_string(); 20];
+    tokenizer.train(texts, 300).unwrap();
+
+    for (token_id, bytes) in tokenizer.vocab_bytes() {
+        if token_id != id && bytes.len() > 1 {
+            assert!(!bytes.contains(&b'|'), "合并结果不应包含特殊标记的片段");
+        }
+    }
+    let encoded = tokenizer.encode("hello<|sep|>").unwrap();
+    assert_eq!(encoded.last(), Some(&id));
+}
+
+#[test]
+fn test_bpe_reserved_special_ids() {
+    let mut tokenizer = bpe().unwrap();
+    let reserved = tokenizer.reserve_special_ids(2).unwrap();
+    tokenizer
+        .train(vec!["aaa bbb aaa bbb".to_string()], 270)
+        .unwrap();
+
+    // 训练产生的标记不会占用预留的ID
+    for id in reserved.clone() {
+        assert!(!tokenizer.vocab.contains_id(&id));
+    }
+    let ids = tokenizer
+        .add_special_tokens(&["<s>", "</s>", "<pad>"])
+        .unwrap();
+    assert_eq!(ids[..2], [reserved.start, reserved.start + 1]);
+    assert!(ids[2] >= reserved.end);
+}
+
+fn check_save_load<T: SpecialTokenizer + Default>(tokenizer: &mut T, path: &str) {
+    cleanup_test_files(path);
+    let ids = tokenizer.add_special_tokens(&["<|user|>"]).unwrap();
+    tokenizer.reserve_special_ids(2).unwrap();
+    let text = "hi <|user|> there";
+    let encoded = tokenizer.encode(text).unwrap();
+    tokenizer.save(path).unwrap();
+
+    let mut loaded = T::default();
+    loaded.load(path).unwrap();
+    assert_eq!(loaded.special_tokens(), tokenizer.special_tokens());
+    assert_eq!(loaded.encode(text).unwrap(), encoded);
+    assert!(encoded.contains(&ids[0]));
+    cleanup_test_files(path);
+}
+
+#[test]
+fn test_special_tokens_save_load() {
+    check_save_load(&mut bpe().unwrap(), "test_special_bpe.model");
+    check_save_load(&mut bbpe().unwrap(), "test_special_bbpe.model");
+    check_save_load(&mut unigram().unwrap(), "test_special_unigram.model");
+    check_save_load(&mut wordpiece().unwrap(), "test_special_wordpiece.model");
+}
+
+#[test]
+fn test_bbpe_special_tokens_journal() {
+    let path = "test_special_bbpe_journal.model";
+    cleanup_test_files(path);
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.reserve_special_tokens(1).unwrap();
+    tokenizer.save(path).unwrap();
+
+    let mut loaded = bbpe().unwrap();
+    loaded.load(path).unwrap();
+    let ids = loaded
+        .add_special_tokens(&["<|im_start|>", "<|im_end|>"])
+        .unwrap();
+    assert_eq!(ids[0], 256, "应先使用预留的占位符位置");
+    assert!(loaded.append_journal(path).unwrap() > 0);
+
+    let mut replayed = bbpe().unwrap();
+    replayed.load(path).unwrap();
+    assert_eq!(replayed.special_tokens(), loaded.special_tokens());
+    assert_eq!(
+        replayed.encode("<|im_start|>hi<|im_end|>").unwrap(),
+        loaded.encode("<|im_start|>hi<|im_end|>").unwrap()
+    );
+    cleanup_test_files(path);
+}
+
+#[test]
+fn test_wordpiece_bert_special_tokens_registered() {
+    let tokenizer =
+        zero_tokenizer::wordpiece::WordPieceTokenizer::with_bert_special_tokens_internal().unwrap();
+    let special = tokenizer.special_tokens();
+    assert_eq!(special.id("[CLS]"), Some(2));
+    assert_eq!(special.id("[MASK]"), Some(4));
+
+    let encoded = tokenizer.encode("[CLS]hi[SEP]").unwrap();
+    assert_eq!(encoded.first(), Some(&2));
+    assert_eq!(encoded.last(), Some(&3));
+    assert_eq!(tokenizer.decode_with(&encoded, true).unwrap(), "hi");
+}
+
+#[test]
+fn test_unigram_prune_keeps_special_tokens() {
+    let mut tokenizer = unigram().unwrap();
+    tokenizer
+        .train(vec!["hello world hello there".to_string()], 3600)
+        .unwrap();
+    let id = tokenizer.add_special_tokens(&["<mask>"]).unwrap()[0];
+
+    let report = tokenizer
+        .prune_pieces(zero_tokenizer::unigram::PruneCriterion::KeepTopK(0))
+        .unwrap();
+    let new_id = report.manifest.get(id).unwrap();
+    assert_eq!(tokenizer.special_tokens().id("<mask>"), Some(new_id));
+    assert_eq!(tokenizer.encode("<mask>").unwrap(), [new_id]);
+}
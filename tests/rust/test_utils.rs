@@ -2,6 +2,10 @@
 //!
 //! 提供通用的测试函数，减少各测试文件中的重复代码
 
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use zero_tokenizer::prelude::*;
 
 /// 通用测试函数：测试分词器的训练功能
@@ -65,3 +69,39 @@ pub fn test_default_constructor<T: Tokenizer>(tokenizer: &T, expected_initial_si
     // 对于未初始化的分词器，每个字符对应一个token
     assert!(!tokens.is_empty());
 }
+
+/// 临时文件路径，`name` 放在末尾以保留扩展名
+///
+/// 路径中带进程号和进程内自增序号，同一测试二进制中并行运行的测试即使传入相同的名称也不会互相覆盖
+pub fn temp_path(name: &str) -> String {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir()
+        .join(format!("zt_{}_{}_{}", std::process::id(), id, name))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// 各测试共用的训练语料
+pub fn corpus() -> Vec<String> {
+    vec!["hello world, hello there! the world is big and the world is round.".to_string()]
+}
+
+/// 在 `corpus` 上训练好的四种分词器，依次为BPE、BBPE、Unigram和WordPiece
+pub fn trained_tokenizers(corpus: &[String]) -> Vec<Box<dyn DynTokenizer>> {
+    let mut bpe = bpe().unwrap();
+    bpe.train(corpus.to_vec(), 300).unwrap();
+    let mut bbpe = bbpe().unwrap();
+    bbpe.train(corpus.to_vec(), 280).unwrap();
+    let mut unigram = unigram().unwrap();
+    unigram.train(corpus.to_vec(), 60).unwrap();
+    let mut wordpiece = wordpiece().unwrap();
+    let size = wordpiece.vocab_size() as u32 + 20;
+    wordpiece.train(corpus.to_vec(), size).unwrap();
+    vec![
+        Box::new(bpe),
+        Box::new(bbpe),
+        Box::new(unigram),
+        Box::new(wordpiece),
+    ]
+}
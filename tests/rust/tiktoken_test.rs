@@ -0,0 +1,100 @@
+//! tiktoken 等级表导入导出测试
+//!
+//! 测试导出后重新加载的词汇表和编码结果不变，以及加载后的合并顺序与tiktoken一致
+
+use zero_tokenizer::bbpe::tiktoken::{parse_tiktoken_ranks, write_tiktoken_ranks};
+use zero_tokenizer::bbpe::BBPETokenizer;
+use zero_tokenizer::prelude::*;
+
+mod test_utils;
+use test_utils::temp_path;
+
+const SAMPLE: &str = "hello tokenizer world 你好 42";
+
+/// 全部单字节标记加上给定的多字节标记，等级依次排在255之后
+fn ranks_with(tokens: &[&str]) -> Vec<(Vec<u8>, u32)> {
+    (0..=255u8)
+        .map(|b| (vec![b], u32::from(b)))
+        .chain(
+            (256u32..)
+                .zip(tokens)
+                .map(|(rank, token)| (token.as_bytes().to_vec(), rank)),
+        )
+        .collect()
+}
+
+/// 测试训练得到的BBPE导出等级表文件后重新加载，词汇表和编码结果不变，特殊标记不写入文件
+#[test]
+fn test_round_trip() {
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer.add_special_tokens(&["<|endoftext|>"]).unwrap();
+    tokenizer
+        .train(
+            vec!["hello world, hello tokenizer! 你好世界 42 hello".to_string()],
+            300,
+        )
+        .unwrap();
+    let ranks = tokenizer.to_tiktoken_ranks();
+    assert!(ranks.iter().all(|(token, _)| token != b"<|endoftext|>"));
+    let mergeable = tokenizer.mergeable_ranks_bytes();
+    assert_eq!(mergeable.len(), ranks.len());
+    assert_eq!(mergeable.len(), tokenizer.vocab_size() - 1);
+    assert!(ranks
+        .iter()
+        .all(|(token, rank)| mergeable.get(token) == Some(rank)));
+
+    let path = temp_path("test_tiktoken_round_trip.tiktoken");
+    tokenizer.save_tiktoken(&path).unwrap();
+    let mut loaded = BBPETokenizer::from_tiktoken_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.to_tiktoken_ranks(), ranks);
+    let eot = tokenizer.special_tokens().id("<|endoftext|>").unwrap();
+    loaded.insert_special_token("<|endoftext|>", eot).unwrap();
+    let text = format!("{}<|endoftext|>", SAMPLE);
+    let expected = tokenizer.encode(&text).unwrap();
+    assert_eq!(loaded.encode(&text).unwrap(), expected);
+    assert_eq!(loaded.decode(&expected).unwrap(), text);
+}
+
+/// 测试加载后按tiktoken的规则合并：等级最低的配对先合并，同一标记可以由任意两段拼成
+#[test]
+fn test_tiktoken_merge_order() {
+    let mut tokenizer =
+        BBPETokenizer::from_tiktoken_ranks(ranks_with(&["bc", "ab", "abc", "aa"])).unwrap();
+    assert_eq!(tokenizer.next_token_id, 260);
+
+    // "bc" 等级最低先合并，"abc" 只能由 "a" + "bc" 拼成
+    assert_eq!(tokenizer.encode("abc").unwrap(), vec![258]);
+    assert_eq!(tokenizer.encode("abcab").unwrap(), vec![258, 257]);
+    // 等级相同时先合并最左边的一处
+    assert_eq!(tokenizer.encode("aaa").unwrap(), vec![259, u32::from(b'a')]);
+
+    tokenizer
+        .insert_special_token("<|endoftext|>", 100)
+        .unwrap_err();
+    tokenizer
+        .insert_special_token("<|endoftext|>", 300)
+        .unwrap();
+    assert_eq!(tokenizer.next_token_id, 301);
+    assert_eq!(tokenizer.encode("ab<|endoftext|>").unwrap(), vec![257, 300]);
+}
+
+/// 测试等级表文本的解析和写出
+#[test]
+fn test_parse_and_write() {
+    let ranks = vec![(b"hello".to_vec(), 0), (vec![0xE4, 0xBD], 7)];
+    let text = write_tiktoken_ranks(&ranks);
+    assert_eq!(text, "aGVsbG8= 0\n5L0= 7\n");
+    assert_eq!(parse_tiktoken_ranks(&format!("{}\n", text)).unwrap(), ranks);
+
+    let err = parse_tiktoken_ranks("aGVsbG8= 0\n!!! 1\n").unwrap_err();
+    assert!(err.to_string().contains("第 2 行"), "{}", err);
+    assert!(parse_tiktoken_ranks("aGVsbG8=\n").is_err());
+    assert!(parse_tiktoken_ranks("aGVsbG8= 0\nd29ybGQ= 0\n").is_err());
+
+    match BBPETokenizer::from_tiktoken_ranks(ranks) {
+        Err(err) => assert!(err.to_string().contains("单字节"), "{}", err),
+        Ok(_) => panic!("缺少单字节标记的等级表应被拒绝"),
+    }
+}
@@ -0,0 +1,235 @@
+//! 训练配置测试
+//!
+//! 测试 `TrainerConfig` 的各项选项在四种训练器中的效果
+
+use zero_tokenizer::prelude::*;
+
+/// 测试低于最低出现次数的配对不被合并
+#[test]
+fn test_min_frequency_stops_rare_merges() {
+    let texts = vec!["ab ab ab ab cd".to_string()];
+    let config = TrainerConfig::new().with_min_frequency(2);
+
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .train_with_config(texts.clone(), 400, config.clone())
+        .unwrap();
+    assert_eq!(tokenizer.encode("ab").unwrap().len(), 1);
+    assert_eq!(tokenizer.encode("cd").unwrap().len(), 2);
+
+    let mut tokenizer = bpe().unwrap();
+    tokenizer.train_with_config(texts, 400, config).unwrap();
+    assert_eq!(tokenizer.encode("ab").unwrap().len(), 1);
+    assert_eq!(tokenizer.encode("cd").unwrap().len(), 2);
+}
+
+/// 测试合并结果不超过最大长度
+#[test]
+fn test_max_piece_length_limits_merges() {
+    let texts = vec!["internationalization ".repeat(50)];
+    let config = TrainerConfig::new().with_max_piece_length(4);
+
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .train_with_config(texts.clone(), 400, config.clone())
+        .unwrap();
+    assert!(!tokenizer.merges.is_empty());
+    for id in tokenizer.merges.values() {
+        assert!(tokenizer.vocab.get_by_id(id).unwrap().len() <= 4);
+    }
+
+    let mut tokenizer = bpe().unwrap();
+    tokenizer.train_with_config(texts, 400, config).unwrap();
+    assert!(!tokenizer.merges.is_empty());
+    for id in tokenizer.merges.values() {
+        assert!(tokenizer.vocab.get_by_id(id).unwrap().chars().count() <= 4);
+    }
+}
+
+/// 测试配置中的特殊标记先于合并结果分配ID，且配置只对本次训练有效
+#[test]
+fn test_special_tokens_reserved_before_training() {
+    let texts = vec!["hello world<|endoftext|>hello world".to_string()];
+    let config = TrainerConfig::new().with_special_tokens(["<|endoftext|>", "<|pad|>"]);
+
+    let mut tokenizer = bbpe().unwrap();
+    let first_merge_id = tokenizer.next_token_id;
+    tokenizer.train_with_config(texts, 300, config).unwrap();
+
+    let end = tokenizer.special_tokens().id("<|endoftext|>").unwrap();
+    let pad = tokenizer.special_tokens().id("<|pad|>").unwrap();
+    assert_eq!((end, pad), (first_merge_id, first_merge_id + 1));
+    assert!(tokenizer.merges.values().all(|&id| id > pad));
+    assert!(tokenizer
+        .encode("hi<|endoftext|>")
+        .unwrap()
+        .ends_with(&[end]));
+    assert_eq!(tokenizer.base.trainer, TrainerConfig::default());
+}
+
+/// 测试初始字母表中的字符即使不在语料中也对应单个标记
+#[test]
+fn test_initial_alphabet() {
+    let texts = vec!["hello world".to_string()];
+    let config = TrainerConfig::new().with_initial_alphabet("你好".chars());
+
+    let mut tokenizer = bbpe().unwrap();
+    tokenizer
+        .train_with_config(texts.clone(), 300, config.clone())
+        .unwrap();
+    let ids = tokenizer.encode("你好").unwrap();
+    assert_eq!(ids.len(), 2);
+    assert_eq!(tokenizer.decode(&ids).unwrap(), "你好");
+
+    let mut tokenizer = bpe().unwrap();
+    tokenizer.train_with_config(texts, 300, config).unwrap();
+    assert!(tokenizer.vocab.contains_value(&"你".to_string()));
+    assert_eq!(tokenizer.encode("你好").unwrap().len(), 2);
+}
+
+/// 测试字母表只保留最常见的字符，落选的字符不参与合并
+#[test]
+fn test_limit_alphabet() {
+    let texts = vec!["αβαβαβ αβγ δε".to_string()];
+    let config = TrainerConfig::new()
+        .with_limit_alphabet(2)
+        .with_initial_alphabet(['ε']);
+
+    let mut tokenizer = bpe().unwrap();
+    tokenizer.train_with_config(texts, 300, config).unwrap();
+    for ch in ["α", "β", "ε"] {
+        assert!(
+            tokenizer.vocab.contains_value(&ch.to_string()),
+            "缺少 {}",
+            ch
+        );
+    }
+    for ch in ["γ", "δ"] {
+        assert!(
+            !tokenizer.vocab.contains_value(&ch.to_string()),
+            "多余 {}",
+            ch
+        );
+    }
+    assert!(tokenizer.vocab.contains_value(&"αβ".to_string()));
+    assert!(!tokenizer.vocab.iter().any(|(_, text)| text.contains('γ')));
+}
+
+/// 测试Unigram和WordPiece的候选片段遵守最低出现次数和最大长度
+#[test]
+fn test_subword_trainers_accept_config() {
+    let texts = vec!["tokenizer tokenizer tokenizer unique".to_string()];
+    let config = TrainerConfig::new()
+        .with_min_frequency(3)
+        .with_max_piece_length(6)
+        .with_special_tokens(["<mask>"]);
+
+    let mut unigram = unigram().unwrap();
+    let before = unigram.vocab_size() as u32;
+    unigram
+        .train_with_config(texts.clone(), before + 200, config.clone())
+        .unwrap();
+    let mask = unigram.special_tokens().id("<mask>").unwrap();
+    assert_eq!(mask, before);
+    assert!(unigram.base.vocab.contains_value(&"tokeni".to_string()));
+    assert!(!unigram.base.vocab.contains_value(&"tokeniz".to_string()));
+    assert!(!unigram.base.vocab.contains_value(&"uniq".to_string()));
+
+    let mut wordpiece = wordpiece().unwrap();
+    let before = wordpiece.vocab_size() as u32;
+    wordpiece
+        .train_with_config(texts, before + 200, config)
+        .unwrap();
+    assert_eq!(wordpiece.special_tokens().id("<mask>").unwrap(), before);
+    assert!(wordpiece.base.vocab.contains_value(&"##okeniz".to_string()));
+    assert!(!wordpiece
+        .base
+        .vocab
+        .contains_value(&"##okenize".to_string()));
+    // " tokenizer" 只出现两次，"unique" 中的配对只出现一次
+    assert!(!wordpiece.base.vocab.contains_value(&" t".to_string()));
+    assert!(!wordpiece.base.vocab.contains_value(&"##iq".to_string()));
+}
+
+/// 测试按种子打乱训练文本：结果与线程数无关，同样的种子训练出同样的模型
+#[test]
+fn test_shuffle_seed_is_deterministic() {
+    use zero_tokenizer::corpus::shuffle_seeded;
+
+    let items: Vec<u32> = (0..1000).collect();
+    let shuffled = shuffle_seeded(items.clone(), 42);
+    assert_ne!(shuffled, items);
+    let single_thread = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap()
+        .install(|| shuffle_seeded(items.clone(), 42));
+    assert_eq!(single_thread, shuffled);
+    assert_ne!(shuffle_seeded(items.clone(), 43), shuffled);
+    let mut sorted = shuffled;
+    sorted.sort_unstable();
+    assert_eq!(sorted, items);
+
+    let texts: Vec<String> = (0..200)
+        .map(|i| format!("ab{} ba{} abba", i % 7, i % 5))
+        .collect();
+    let config = TrainerConfig::new().with_shuffle_seed(3);
+    let train = || {
+        let mut tokenizer = bbpe().unwrap();
+        tokenizer.base.tie_break = zero_tokenizer::base::merge_job::TieBreak::InsertionOrder;
+        tokenizer
+            .train_with_config(texts.clone(), 300, config.clone())
+            .unwrap();
+        tokenizer.merges
+    };
+    assert_eq!(train(), train());
+}
+
+/// 测试训练结果与线程数和哈希种子无关：不同线程数下多次训练得到同样的合并规则
+#[test]
+fn test_training_is_independent_of_thread_count() {
+    use zero_tokenizer::base::merge_job::TieBreak;
+    use zero_tokenizer::base::tokenizer_base::{ranked_merges, ParallelChunking};
+    use zero_tokenizer::base::traits::MergeBasedTokenizer;
+
+    // 大量计数相同的配对，出堆顺序只由平局规则决定
+    let texts: Vec<String> = (0..300)
+        .map(|i| format!("xy{} qz{} 分词{} abcd dcba", i % 11, i % 13, i % 3))
+        .collect();
+    let in_pool = |threads: usize, train: &(dyn Fn() -> Vec<((u32, u32), u32)> + Sync)| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap()
+            .install(train)
+    };
+
+    for tie_break in [
+        TieBreak::PairId,
+        TieBreak::Lexicographic,
+        TieBreak::InsertionOrder,
+    ] {
+        let train_bbpe = || {
+            let mut tokenizer = bbpe().unwrap();
+            tokenizer.base.tie_break = tie_break;
+            tokenizer.base.parallel = ParallelChunking::new(1, 2).unwrap();
+            tokenizer.train(texts.clone(), 320).unwrap();
+            ranked_merges(tokenizer.get_merges())
+        };
+        let expected = in_pool(1, &train_bbpe);
+        for threads in [2, 4, 8] {
+            assert_eq!(in_pool(threads, &train_bbpe), expected, "{:?}", tie_break);
+        }
+    }
+
+    let train_bpe = || {
+        let mut tokenizer = bpe().unwrap();
+        tokenizer.base.parallel = ParallelChunking::new(1, 2).unwrap();
+        Tokenizer::train(&mut tokenizer, texts.clone(), 320).unwrap();
+        ranked_merges(tokenizer.get_merges())
+    };
+    let expected = in_pool(1, &train_bpe);
+    for threads in [2, 4, 8] {
+        assert_eq!(in_pool(threads, &train_bpe), expected);
+    }
+}
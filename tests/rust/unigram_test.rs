@@ -4,6 +4,7 @@
 
 use zero_tokenizer::prelude::*;
 mod test_utils;
+use test_utils::temp_path;
 
 /// 测试Unigram分词器的训练功能
 #[test]
@@ -43,3 +44,529 @@ fn test_unigram_default() {
     // Unigram特定的验证 - 初始词汇表大小应为256+15001
     assert_eq!(tokenizer.vocab_size(), 256 + 15001); // 256个字节 + 15001个常用汉字
 }
+
+/// 测试字节标记按解码后的字节参与前缀查找
+#[test]
+fn test_unigram_ids_with_prefix() {
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    tokenizer
+        .train(vec!["hello world hello".to_string()], 300)
+        .unwrap();
+
+    let ids = tokenizer.ids_with_prefix("h");
+    assert!(!ids.is_empty());
+    for id in &ids {
+        assert!(tokenizer.decode(&[*id]).unwrap().starts_with('h'));
+    }
+
+    // 字节标记 `<0x0A>` 按解码后的换行符匹配
+    let newline = *tokenizer
+        .base
+        .vocab
+        .get_by_value(&"<0x0A>".to_string())
+        .unwrap();
+    assert!(tokenizer.ids_with_prefix("\n").contains(&newline));
+    assert!(!tokenizer.ids_with_prefix("<").contains(&newline));
+    assert_eq!(tokenizer.token_ids_covering("zzzz"), Vec::<u32>::new());
+}
+
+/// 枚举字节序列的全部切分，返回最高的分数之和
+fn brute_force_best(bytes: &[u8], pieces: &[(Vec<u8>, f64)]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    pieces
+        .iter()
+        .filter(|(piece, _)| bytes.starts_with(piece))
+        .map(|(piece, score)| score + brute_force_best(&bytes[piece.len()..], pieces))
+        .fold(f64::NEG_INFINITY, f64::max)
+}
+
+/// 测试编码结果是片段分数之和最大的切分，与暴力枚举对比
+#[test]
+fn test_unigram_viterbi_matches_brute_force() {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(3228);
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    let alphabet = [b'a', b'b', b'c'];
+    let multi = ["ab", "bc", "ca", "abc", "bca", "aa", "cab", "abca"];
+    let first_id = tokenizer.vocab_size() as u32;
+    for (id, piece) in (first_id..).zip(multi) {
+        tokenizer.base.vocab.insert(id, piece.to_string());
+        tokenizer.scores.push(0.0);
+    }
+
+    for _ in 0..20 {
+        // 为单字节片段和多字节片段随机分配分数
+        let mut pieces = Vec::new();
+        for &byte in &alphabet {
+            let score = rng.gen_range(-5.0..0.0);
+            tokenizer.scores[byte as usize] = score;
+            pieces.push((vec![byte], score));
+        }
+        for piece in multi {
+            let id = *tokenizer
+                .base
+                .vocab
+                .get_by_value(&piece.to_string())
+                .unwrap();
+            let score = rng.gen_range(-8.0..0.0);
+            tokenizer.scores[id as usize] = score;
+            pieces.push((piece.as_bytes().to_vec(), score));
+        }
+
+        for _ in 0..20 {
+            let len = rng.gen_range(1..=8);
+            let text: String = (0..len)
+                .map(|_| char::from(alphabet[rng.gen_range(0..alphabet.len())]))
+                .collect();
+
+            let ids = tokenizer.encode(&text).unwrap();
+            assert_eq!(tokenizer.decode(&ids).unwrap(), text);
+            let score: f64 = ids.iter().map(|&id| tokenizer.scores[id as usize]).sum();
+            let best = brute_force_best(text.as_bytes(), &pieces);
+            assert!(
+                (score - best).abs() < 1e-9,
+                "{:?}: 得分 {} 低于最优 {}",
+                text,
+                score,
+                best
+            );
+        }
+    }
+
+    // 贪心最长匹配会选择 "abca"，分数更高的切分是 "ab" + "ca"
+    for &byte in &alphabet {
+        tokenizer.scores[byte as usize] = -5.0;
+    }
+    for piece in multi {
+        let id = *tokenizer
+            .base
+            .vocab
+            .get_by_value(&piece.to_string())
+            .unwrap();
+        tokenizer.scores[id as usize] = match piece {
+            "ab" | "ca" => -1.0,
+            _ => -10.0,
+        };
+    }
+    let ids = tokenizer.encode("abca").unwrap();
+    let pieces: Vec<String> = ids
+        .iter()
+        .map(|&id| tokenizer.decode(&[id]).unwrap())
+        .collect();
+    assert_eq!(pieces, ["ab", "ca"]);
+}
+
+/// 测试词汇表无法覆盖的字符拆成字节标记，关闭字节回退后编码为未知标记
+#[test]
+fn test_unigram_byte_fallback() {
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    assert!(tokenizer.byte_fallback);
+
+    // 😀 不在常用汉字字表中，拆成4个字节标记
+    let text = "a😀测";
+    let ids = tokenizer.encode(text).unwrap();
+    assert_eq!(ids.len(), 1 + 4 + 1);
+    let byte_pieces: Vec<&String> = ids[1..5]
+        .iter()
+        .map(|id| tokenizer.base.vocab.get_by_id(id).unwrap())
+        .collect();
+    assert_eq!(byte_pieces, ["<0xF0>", "<0x9F>", "<0x98>", "<0x80>"]);
+    assert_eq!(tokenizer.decode(&ids).unwrap(), text);
+
+    // 有对应片段的字符不会拆成字节
+    let ce = tokenizer.encode("测").unwrap();
+    assert_eq!(ce.len(), 1);
+    assert!(!tokenizer.is_fallback_token(&ce[0]));
+
+    let vocab_size = tokenizer.vocab_size();
+    tokenizer.set_byte_fallback(false);
+    assert_eq!(tokenizer.vocab_size(), vocab_size + 1);
+    assert_eq!(
+        tokenizer.base.vocab.get_by_id(&tokenizer.unk_token_id),
+        Some(&"<unk>".to_string())
+    );
+    let ids = tokenizer.encode(text).unwrap();
+    assert_eq!(ids, [ids[0], tokenizer.unk_token_id, ce[0]]);
+
+    // 再次关闭不会重复追加 `<unk>`
+    tokenizer.set_byte_fallback(true);
+    tokenizer.set_byte_fallback(false);
+    assert_eq!(tokenizer.vocab_size(), vocab_size + 1);
+}
+
+/// 测试按分数和按数量裁剪片段
+#[test]
+fn test_unigram_prune_pieces() {
+    use zero_tokenizer::unigram::PruneCriterion;
+
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    let first_learned = tokenizer.vocab_size();
+    let corpus = "hello world, hello there. the quick brown fox jumps over the lazy dog, \
+                  and the dog sleeps. tokenizers split words into pieces; pieces join into words. \
+                  hello again, world";
+    tokenizer.train(vec![corpus.to_string()], 15400).unwrap();
+    let before = tokenizer.vocab_size();
+    let text = "hello world";
+    let expected_text = tokenizer.decode(&tokenizer.encode(text).unwrap()).unwrap();
+
+    // 按分数裁剪：低于阈值的普通片段全部移除，字节词汇表和单字节片段保留。
+    // 阈值取训练得到的第15高的分数，语料中没有出现的汉字分数更低，全部移除
+    let mut learned: Vec<f64> = tokenizer.scores[first_learned..].to_vec();
+    assert!(learned.len() > 15);
+    learned.sort_by(|a, b| b.total_cmp(a));
+    let threshold = learned[14];
+    let low = tokenizer
+        .base
+        .vocab
+        .iter()
+        .filter(|&(&id, piece)| {
+            let single_byte = piece.len() == 1 || (piece.len() == 6 && piece.starts_with("<0x"));
+            id >= 256 && !single_byte && tokenizer.scores[id as usize] < threshold
+        })
+        .count();
+    let report = tokenizer
+        .prune_pieces(PruneCriterion::MinScore(threshold))
+        .unwrap();
+    assert_eq!(report.removed.len(), low);
+    assert!(report
+        .removed
+        .iter()
+        .all(|&(id, _, score)| id >= 256 && score < threshold));
+    assert_eq!(tokenizer.vocab_size(), before - low);
+    assert_eq!(report.manifest.new_vocab_size, tokenizer.vocab_size());
+    assert_eq!(report.manifest.removed.len(), low);
+    assert!(report.manifest.removed.iter().all(|&id| id >= 256));
+
+    // ID连续，分数按对数概率归一化
+    let ids: std::collections::BTreeSet<u32> = (0..tokenizer.vocab_size() as u32).collect();
+    assert!(ids
+        .iter()
+        .all(|id| tokenizer.base.vocab.get_by_id(id).is_some()));
+    let total: f64 = tokenizer
+        .base
+        .vocab
+        .iter()
+        .filter(|&(&id, piece)| {
+            id != tokenizer.unk_token_id && !(piece.starts_with("<0x") && piece.ends_with('>'))
+        })
+        .map(|(&id, _)| tokenizer.scores[id as usize].exp())
+        .sum();
+    assert!((total - 1.0).abs() < 1e-9, "{}", total);
+
+    // 裁剪后仍能无损编码
+    let tokens = tokenizer.encode(text).unwrap();
+    assert_eq!(tokenizer.decode(&tokens).unwrap(), expected_text);
+
+    // 按数量裁剪只保留分数最高的片段
+    let mut top: Vec<f64> = tokenizer
+        .base
+        .vocab
+        .iter()
+        .filter(|&(&id, piece)| {
+            id >= 256 && piece.len() != 1 && !(piece.len() == 6 && piece.starts_with("<0x"))
+        })
+        .map(|(&id, _)| tokenizer.scores[id as usize])
+        .collect();
+    let protected = tokenizer.vocab_size() - top.len();
+    top.sort_by(|a, b| b.total_cmp(a));
+    let report = tokenizer
+        .prune_pieces(PruneCriterion::KeepTopK(10))
+        .unwrap();
+    assert_eq!(tokenizer.vocab_size(), protected + 10);
+    assert!(report.removed.iter().all(|&(_, _, score)| score <= top[9]));
+    assert_eq!(
+        tokenizer.decode(&tokenizer.encode(text).unwrap()).unwrap(),
+        expected_text
+    );
+
+    assert!(tokenizer
+        .prune_pieces(PruneCriterion::MinScore(f64::NAN))
+        .is_err());
+}
+
+/// 从音节随机组成的 `pool` 个词语中随机抽取 `words` 个，用空格连接，返回语料和词语表
+fn syllable_corpus(seed: u64, pool: usize, words: usize) -> (String, Vec<String>) {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let syllables = ["ka", "to", "ri", "mu", "sa", "ne", "lo", "pi"];
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut pool_words: Vec<String> = Vec::new();
+    while pool_words.len() < pool {
+        let len = rng.gen_range(2..=4);
+        let word: String = (0..len)
+            .map(|_| syllables[rng.gen_range(0..syllables.len())])
+            .collect();
+        if !pool_words.contains(&word) {
+            pool_words.push(word);
+        }
+    }
+    let corpus = (0..words)
+        .map(|_| pool_words[rng.gen_range(0..pool)].as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (corpus, pool_words)
+}
+
+/// 测试EM训练：新片段都来自语料，按分数从高到低分配ID，片段过多时裁剪到目标大小
+#[test]
+fn test_unigram_em_training() {
+    let (corpus, pool) = syllable_corpus(3260, 20, 400);
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    let before = tokenizer.vocab_size();
+    tokenizer
+        .train(vec![corpus.clone()], before as u32 + 30)
+        .unwrap();
+    // 期望次数过低的片段在M步中移除，词汇表可能达不到目标大小
+    assert!(tokenizer.vocab_size() > before + pool.len() / 2);
+    assert!(tokenizer.vocab_size() <= before + 30);
+
+    let learned: Vec<(&String, f64)> = (before as u32..tokenizer.vocab_size() as u32)
+        .map(|id| {
+            (
+                tokenizer.base.vocab.get_by_id(&id).unwrap(),
+                tokenizer.scores[id as usize],
+            )
+        })
+        .collect();
+    for (piece, score) in &learned {
+        assert!(
+            corpus.contains(piece.as_str()),
+            "片段 {:?} 不在语料中",
+            piece
+        );
+        assert!(piece.chars().count() > 1);
+        assert!(*score < 0.0);
+    }
+    assert!(learned.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+
+    // 分数为对数概率，语料中出现过的字符的概率不超过1
+    assert!(tokenizer.scores[usize::from(b'k')] < 0.0);
+
+    let ids = tokenizer.encode(&corpus).unwrap();
+    assert_eq!(tokenizer.decode(&ids).unwrap(), corpus);
+    assert!(ids.iter().all(|id| !tokenizer.is_fallback_token(id)));
+    // 反复出现的词语连同前面的空格成为一个片段
+    for word in &pool {
+        assert_eq!(tokenizer.encode(&format!(" {}", word)).unwrap().len(), 1);
+    }
+
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    tokenizer
+        .train(vec![corpus.clone()], before as u32 + 10)
+        .unwrap();
+    assert_eq!(tokenizer.vocab_size(), before + 10);
+    let ids = tokenizer.encode(&corpus).unwrap();
+    assert_eq!(tokenizer.decode(&ids).unwrap(), corpus);
+}
+
+/// 测试Unigram训练专用的选项：无效的选项返回错误，种子片段数量限制候选片段
+#[test]
+fn test_unigram_em_config() {
+    use zero_tokenizer::base::tokenizer_base::ParallelChunking;
+    use zero_tokenizer::unigram::UnigramTrainerConfig;
+
+    let (corpus, _) = syllable_corpus(7, 100, 300);
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    let before = tokenizer.vocab_size();
+    for invalid in [
+        UnigramTrainerConfig::new().with_shrinking_factor(1.0),
+        UnigramTrainerConfig::new().with_seed_size(0),
+        UnigramTrainerConfig::new().with_num_sub_iterations(0),
+    ] {
+        tokenizer.em_config = invalid;
+        assert!(tokenizer
+            .train(vec![corpus.clone()], before as u32 + 30)
+            .is_err());
+        assert_eq!(tokenizer.vocab_size(), before);
+    }
+
+    tokenizer.em_config = UnigramTrainerConfig::new()
+        .with_seed_size(5)
+        .with_shrinking_factor(0.5)
+        .with_num_sub_iterations(1);
+    tokenizer
+        .train(vec![corpus.clone()], before as u32 + 30)
+        .unwrap();
+    let learned = tokenizer.vocab_size() - before;
+    assert!((1..=5).contains(&learned), "{}", learned);
+
+    // 结果与并行划分无关
+    let train = |pieces_per_task: usize| {
+        let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+        tokenizer.base.parallel = ParallelChunking::new(pieces_per_task, 2).unwrap();
+        tokenizer
+            .train(vec![corpus.clone()], before as u32 + 20)
+            .unwrap();
+        (before as u32..tokenizer.vocab_size() as u32)
+            .map(|id| {
+                (
+                    tokenizer.base.vocab.get_by_id(&id).unwrap().clone(),
+                    tokenizer.scores[id as usize],
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(train(0), train(3));
+}
+
+/// 测试未知标记统计：每次编码累加，可以清空，加载模型后从零开始
+#[test]
+fn test_unigram_unk_stats() {
+    use zero_tokenizer::base::unk_stats::UnkStats;
+
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    tokenizer.set_byte_fallback(false);
+    assert_eq!(tokenizer.unk_stats.snapshot(), UnkStats::default());
+
+    tokenizer.encode("a😀测").unwrap();
+    tokenizer.encode("测").unwrap();
+    let stats = tokenizer.unk_stats.snapshot();
+    assert_eq!(
+        stats,
+        UnkStats {
+            texts: 2,
+            tokens: 4,
+            unk_tokens: 1,
+            texts_with_unk: 1,
+        }
+    );
+    assert_eq!(stats.unk_rate(), 0.25);
+
+    // 并行批量编码同样计入
+    let texts = ["😀😀", "a"];
+    tokenizer.encode_batch_packed(&texts, None, None).unwrap();
+    assert_eq!(tokenizer.unk_stats.snapshot().unk_tokens, 3);
+
+    tokenizer.unk_stats.reset();
+    assert_eq!(tokenizer.unk_stats.snapshot(), UnkStats::default());
+
+    tokenizer.encode("😀").unwrap();
+    let path = &temp_path("unigram_unk_stats.model");
+    tokenizer.save(path).unwrap();
+    tokenizer.load(path).unwrap();
+    let _ = std::fs::remove_file(path);
+    assert_eq!(tokenizer.unk_stats.snapshot(), UnkStats::default());
+}
+
+/// 枚举字节序列的全部切分及其分数
+fn all_segmentations(bytes: &[u8], pieces: &[(&str, f64)]) -> Vec<(Vec<String>, f64)> {
+    if bytes.is_empty() {
+        return vec![(Vec::new(), 0.0)];
+    }
+    let mut result = Vec::new();
+    for &(piece, score) in pieces {
+        if bytes.starts_with(piece.as_bytes()) {
+            for (mut rest, rest_score) in all_segmentations(&bytes[piece.len()..], pieces) {
+                rest.insert(0, piece.to_string());
+                result.push((rest, score + rest_score));
+            }
+        }
+    }
+    result
+}
+
+/// 测试n-best切分与暴力枚举一致，按分数采样的频率与切分概率一致
+#[test]
+fn test_unigram_sample_and_nbest() {
+    let mut tokenizer = zero_tokenizer::prelude::unigram().unwrap();
+    let pieces = [
+        ("a", -2.0),
+        ("b", -2.5),
+        ("c", -3.0),
+        ("ab", -2.0),
+        ("bc", -3.5),
+        ("ca", -2.5),
+        ("abc", -4.0),
+    ];
+    let mut next_id = tokenizer.vocab_size() as u32;
+    for &(piece, score) in &pieces {
+        let id = match tokenizer.base.vocab.get_by_value(piece) {
+            Some(&id) => id,
+            None => {
+                tokenizer.base.vocab.insert(next_id, piece.to_string());
+                tokenizer.scores.push(0.0);
+                next_id += 1;
+                next_id - 1
+            }
+        };
+        tokenizer.scores[id as usize] = score;
+    }
+    let split = |tokenizer: &Unigram, ids: &[u32]| -> Vec<String> {
+        ids.iter()
+            .map(|&id| tokenizer.decode(&[id]).unwrap())
+            .collect()
+    };
+
+    let text = "abca";
+    let mut expected = all_segmentations(text.as_bytes(), &pieces);
+    expected.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let nbest = tokenizer.encode_nbest(text, 100).unwrap();
+    assert_eq!(nbest.len(), expected.len());
+    assert_eq!(nbest[0].0, tokenizer.encode(text).unwrap());
+    for ((ids, score), (_, expected_score)) in nbest.iter().zip(&expected) {
+        assert!((score - expected_score).abs() < 1e-9);
+        let segmentation = split(&tokenizer, ids);
+        assert_eq!(segmentation.concat(), text);
+        let sum: f64 = segmentation
+            .iter()
+            .map(|piece| pieces.iter().find(|(p, _)| p == piece).unwrap().1)
+            .sum();
+        assert!((sum - score).abs() < 1e-9);
+    }
+    assert_eq!(tokenizer.encode_nbest(text, 2).unwrap(), nbest[..2]);
+
+    // 多个预分词片段和特殊标记：组合后的第一种切分与编码结果相同
+    let special = tokenizer.add_special_tokens(&["<sep>"]).unwrap()[0];
+    let text = "abca<sep>ab ca";
+    let nbest = tokenizer.encode_nbest(text, 5).unwrap();
+    assert_eq!(nbest.len(), 5);
+    assert_eq!(nbest[0].0, tokenizer.encode(text).unwrap());
+    assert!(nbest.windows(2).all(|w| w[0].1 >= w[1].1));
+    assert!(nbest.iter().all(|(ids, _)| ids.contains(&special)));
+
+    // 采样频率接近 exp(alpha * 分数) / Z，特殊标记两侧的片段各自独立采样
+    let text = "abca";
+    let alpha = 0.5;
+    let z: f64 = expected.iter().map(|(_, s)| (alpha * s).exp()).sum();
+    let samples = 20_000;
+    let repeated = vec![text; samples].join("<sep>");
+    let ids = tokenizer
+        .encode_sample(&repeated, alpha, Some(3268))
+        .unwrap();
+    assert_eq!(tokenizer.decode(&ids).unwrap(), repeated);
+    let mut counts = std::collections::HashMap::new();
+    for sample in ids.split(|&id| id == special) {
+        *counts.entry(split(&tokenizer, sample)).or_insert(0u32) += 1;
+    }
+    for (segmentation, score) in &expected {
+        let probability = (alpha * score).exp() / z;
+        let observed = f64::from(counts.get(segmentation).copied().unwrap_or(0)) / samples as f64;
+        assert!(
+            (observed - probability).abs() < 0.02,
+            "{:?}: 频率 {} 与概率 {} 相差过大",
+            segmentation,
+            observed,
+            probability
+        );
+    }
+
+    // 同一种子结果相同；alpha很大时退化为最优切分
+    let text = "abcab caab";
+    assert_eq!(
+        tokenizer.encode_sample(text, 0.1, Some(7)).unwrap(),
+        tokenizer.encode_sample(text, 0.1, Some(7)).unwrap()
+    );
+    assert_eq!(
+        tokenizer.encode_sample(text, 1e6, None).unwrap(),
+        tokenizer.encode(text).unwrap()
+    );
+
+    assert!(tokenizer.encode_sample(text, -1.0, None).is_err());
+    assert!(tokenizer.encode_sample(text, f64::NAN, None).is_err());
+    assert!(tokenizer.encode_nbest(text, 0).is_err());
+}
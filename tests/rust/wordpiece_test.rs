@@ -4,6 +4,7 @@
 
 use zero_tokenizer::prelude::*;
 mod test_utils;
+use test_utils::temp_path;
 
 /// 测试WordPiece分词器的训练功能
 #[test]
@@ -62,3 +63,253 @@ fn test_wordpiece_default() {
     // WordPiece特定的验证 - 初始词汇表大小应为256+15001
     assert_eq!(tokenizer.vocab_size(), 256 + 15001); // 256个字节 + 15001个常用汉字
 }
+
+/// 测试BERT特殊标记的注册和 `[CLS]`/`[SEP]` 后处理
+#[test]
+fn test_wordpiece_bert_special_tokens() {
+    use zero_tokenizer::wordpiece::{
+        BERT_SPECIAL_TOKENS, CLS_TOKEN_ID, SEP_TOKEN_ID, UNK_TOKEN_ID,
+    };
+
+    let tokenizer = WordPiece::with_bert_special_tokens_internal().unwrap();
+    assert!(tokenizer.has_bert_special_tokens());
+    assert_eq!(tokenizer.vocab_size(), 5 + 256 + 15001);
+    assert_eq!(tokenizer.unk_token_id, UNK_TOKEN_ID);
+    for (id, token) in (0u32..).zip(BERT_SPECIAL_TOKENS) {
+        assert_eq!(tokenizer.base.vocab.get_by_id(&id).unwrap(), token);
+    }
+
+    let ids = tokenizer.encode("hello").unwrap();
+    let single = tokenizer.encode_with_special_tokens("hello").unwrap();
+    assert_eq!(single.first(), Some(&CLS_TOKEN_ID));
+    assert_eq!(single.last(), Some(&SEP_TOKEN_ID));
+    assert_eq!(&single[1..single.len() - 1], ids.as_slice());
+
+    let pair = tokenizer
+        .encode_pair_with_special_tokens("hello", "world")
+        .unwrap();
+    let second = tokenizer.encode("world").unwrap();
+    let mut expected = single.clone();
+    expected.extend(&second);
+    expected.push(SEP_TOKEN_ID);
+    assert_eq!(pair, expected);
+    assert_eq!(tokenizer.decode(&ids).unwrap(), "hello");
+
+    // 默认构造不注册特殊标记，后处理返回错误
+    let plain = zero_tokenizer::prelude::wordpiece().unwrap();
+    assert!(!plain.has_bert_special_tokens());
+    assert!(plain.encode_with_special_tokens("hello").is_err());
+}
+
+/// 测试整词掩码所需的词首标记
+#[test]
+fn test_wordpiece_word_starts() {
+    let tokenizer = zero_tokenizer::prelude::wordpiece().unwrap();
+
+    // 未训练时英文按字符切分，只有每个词的第一个字符开始新词
+    let text = "hello, world 世界";
+    let starts = tokenizer.word_starts(text).unwrap();
+    assert_eq!(starts.len(), tokenizer.encode(text).unwrap().len());
+    assert_eq!(&starts[..6], &[true, false, false, false, false, true]);
+    // hello / , / 空格 / world / 空格 / 世 / 界
+    assert_eq!(starts.iter().filter(|&&start| start).count(), 7);
+
+    // 被拆成字节标记的字符整体算一个词
+    let starts = tokenizer.word_starts("a😀").unwrap();
+    assert_eq!(starts, vec![true, true, false, false, false]);
+}
+
+/// 测试训练时按最低出现次数过滤配对：合并得到的片段在语料中至少出现3次
+#[test]
+fn test_wordpiece_min_frequency() {
+    let texts = vec!["hello hello world".to_string(), "hello there".to_string()];
+    let mut tokenizer = zero_tokenizer::prelude::wordpiece().unwrap();
+    tokenizer.set_min_frequency(3);
+    let before = tokenizer.vocab_size() as u32;
+    tokenizer.train(texts.clone(), before + 100).unwrap();
+
+    let corpus = texts.join("\n");
+    for (&id, piece) in tokenizer.base.vocab.iter().filter(|(&id, _)| id >= before) {
+        let text = piece.strip_prefix("##").unwrap_or(piece);
+        if text.chars().count() > 1 {
+            assert!(
+                corpus.matches(text).count() >= 3,
+                "片段 {:?}（ID {}）出现不足3次",
+                piece,
+                id
+            );
+        }
+    }
+    assert!(tokenizer.base.vocab.contains_value(&"##ello".to_string()));
+    assert!(!tokenizer.base.vocab.contains_value(&"##orl".to_string()));
+    assert!(!tokenizer.base.vocab.contains_value(&"##her".to_string()));
+    assert_eq!(
+        tokenizer
+            .decode(&tokenizer.encode("hello there").unwrap())
+            .unwrap(),
+        "hello there"
+    );
+}
+
+/// 测试训练得到的片段都来自语料：词首片段是某个预分词片段的开头，续接片段出现在某个片段的词内
+#[test]
+fn test_wordpiece_learned_pieces_in_corpus() {
+    let texts = vec![
+        "the tokenizer learns pieces from the training corpus".to_string(),
+        "tokenizers split words into pieces, and pieces join into words".to_string(),
+        "训练语料中的词语被切分为片段，片段再组成词语".to_string(),
+    ];
+    let mut tokenizer = zero_tokenizer::prelude::wordpiece().unwrap();
+    let before = tokenizer.vocab_size() as u32;
+    tokenizer.train(texts.clone(), before + 150).unwrap();
+    assert!(tokenizer.vocab_size() as u32 > before + 50);
+
+    let words: Vec<String> = texts
+        .iter()
+        .flat_map(|text| tokenizer.base.split_text(text).unwrap())
+        .collect();
+    for (&id, piece) in tokenizer.base.vocab.iter().filter(|(&id, _)| id >= before) {
+        let found = match piece.strip_prefix("##") {
+            Some(text) => words.iter().any(|word| {
+                word.char_indices()
+                    .skip(1)
+                    .any(|(i, _)| word[i..].starts_with(text))
+            }),
+            None => words.iter().any(|word| word.starts_with(piece.as_str())),
+        };
+        assert!(found, "片段 {:?}（ID {}）不在语料中", piece, id);
+    }
+
+    for text in &texts {
+        let ids = tokenizer.encode(text).unwrap();
+        assert_eq!(tokenizer.decode(&ids).unwrap(), *text);
+        // 训练语料中的字符都有对应的片段，不会退化为字节标记或未知标记
+        assert!(ids.iter().all(|id| !tokenizer.is_fallback_token(id)));
+    }
+    let ids = tokenizer.encode("tokenizer").unwrap();
+    assert!(ids.len() < "tokenizer".len());
+    assert!(tokenizer
+        .base
+        .vocab
+        .get_by_id(&ids[1])
+        .unwrap()
+        .starts_with("##"));
+}
+
+/// 测试配对按 `count(ab) / (count(a) * count(b))` 评分：各自少见、总是一起出现的配对先于高频配对合并
+#[test]
+fn test_wordpiece_pair_score() {
+    let mut tokenizer = WordPiece::with_pattern_internal(r"\w+".to_string()).unwrap();
+    let before = tokenizer.vocab_size() as u32;
+    let text = "ab ab ab ab ac ac ac ac ad ad ad ad xy xy";
+    tokenizer
+        .train(vec![text.to_string()], before + 100)
+        .unwrap();
+
+    let id = |piece: &str| {
+        *tokenizer
+            .base
+            .vocab
+            .get_by_value(&piece.to_string())
+            .unwrap()
+    };
+    assert!(id("xy") < id("ab"));
+    // 得分和次数相同时按片段文本排序
+    assert!(id("ab") < id("ac"));
+    assert!(id("ac") < id("ad"));
+    assert!(tokenizer.scores[id("xy") as usize] > tokenizer.scores[id("ab") as usize]);
+    // 词内没有 "##a" 时改用词首片段 "ad"
+    assert_eq!(tokenizer.encode("xyad").unwrap(), vec![id("xy"), id("ad")]);
+}
+
+/// 测试配置未知标记：注册为特殊标记、不从文本中匹配，并随模型文件保存
+#[test]
+fn test_wordpiece_set_unk_token() {
+    let mut tokenizer = zero_tokenizer::prelude::wordpiece().unwrap();
+    tokenizer
+        .train(vec!["hello world hello".to_string()], 300)
+        .unwrap();
+    let vocab_size = tokenizer.vocab_size() as u32;
+
+    let unk = tokenizer.set_unk_token("[UNK]", None).unwrap();
+    assert_eq!(unk, vocab_size);
+    assert_eq!(tokenizer.unk_token_id, unk);
+    assert_eq!(tokenizer.unk_token(), Some("[UNK]"));
+    assert_eq!(tokenizer.scores.len(), vocab_size as usize + 1);
+
+    // 文本中的 "[UNK]" 按普通字符编码
+    let ids = tokenizer.encode("[UNK] hello").unwrap();
+    assert!(!ids.contains(&unk));
+    assert_eq!(tokenizer.decode(&ids).unwrap(), "[UNK] hello");
+
+    // 重复设置返回已有ID，ID冲突时报错
+    assert_eq!(tokenizer.set_unk_token("[UNK]", None).unwrap(), unk);
+    assert!(tokenizer.set_unk_token("[UNK]", Some(unk + 1)).is_err());
+    assert!(tokenizer.set_unk_token("<unk>", Some(b'a' as u32)).is_err());
+    assert!(tokenizer.set_unk_token("", None).is_err());
+    assert_eq!(tokenizer.unk_token_id, unk);
+
+    let path = &temp_path("wordpiece_unk_token.model");
+    tokenizer.save(path).unwrap();
+    let mut loaded = zero_tokenizer::prelude::wordpiece().unwrap();
+    loaded.load(path).unwrap();
+    assert_eq!(loaded.unk_token_id, unk);
+    assert_eq!(loaded.unk_token(), Some("[UNK]"));
+    assert_eq!(loaded.encode("[UNK] hello").unwrap(), ids);
+    assert_eq!(loaded.continuing_subword_prefix, "##");
+    assert_eq!(
+        loaded.set_unk_token("<unk>", None).unwrap(),
+        loaded.vocab_size() as u32 - 1
+    );
+    std::fs::remove_file(path).ok();
+    std::fs::remove_file(format!("{}.scores", path)).ok();
+}
+
+/// 测试带偏移的编码：标记文本与原文区间一致
+#[test]
+fn test_wordpiece_encode_with_offsets() {
+    let mut tokenizer = wordpiece().unwrap();
+    tokenizer
+        .train(vec!["hello world hello world".to_string()], 300)
+        .unwrap();
+
+    let text = "hello 世界";
+    let encoding = tokenizer.encode_with_offsets(text).unwrap();
+    assert_eq!(encoding.ids, tokenizer.encode(text).unwrap());
+    for (token, &(start, end)) in encoding.tokens.iter().zip(&encoding.offsets) {
+        assert_eq!(token, &text[start..end]);
+    }
+    let range = encoding.token_range(6, text.len());
+    assert_eq!(encoding.tokens[range].concat(), "世界");
+}
+
+/// 测试未知标记统计：词汇表无法覆盖的字节计为未知标记，文本中的特殊标记不计入
+#[test]
+fn test_wordpiece_unk_stats() {
+    use zero_tokenizer::base::hf_json::HfTokenizerJson;
+
+    let json = serde_json::json!({
+        "model": {
+            "type": "WordPiece",
+            "unk_token": "[UNK]",
+            "continuing_subword_prefix": "##",
+            "vocab": {"[UNK]": 0, "[SEP]": 1, "hello": 2, "##s": 3},
+        },
+        "added_tokens": [{"id": 1, "content": "[SEP]", "special": true}],
+    });
+    let mut tokenizer = zero_tokenizer::prelude::wordpiece().unwrap();
+    tokenizer.encode("hello").unwrap();
+    tokenizer
+        .load_hf_json(HfTokenizerJson::parse(&json.to_string()).unwrap())
+        .unwrap();
+    assert_eq!(tokenizer.unk_stats.snapshot().texts, 0);
+
+    assert_eq!(tokenizer.encode("hellos[SEP]").unwrap(), vec![2, 3, 1]);
+    assert_eq!(tokenizer.encode("hi").unwrap(), vec![0, 0]);
+    let stats = tokenizer.unk_stats.snapshot();
+    assert_eq!(stats.texts, 2);
+    assert_eq!(stats.tokens, 4);
+    assert_eq!(stats.unk_tokens, 2);
+    assert_eq!(stats.texts_with_unk, 1);
+    assert_eq!(stats.unk_rate(), 0.5);
+}